@@ -30,13 +30,9 @@ async fn create_new_association_pair(
 
     // Setup client
     tokio::spawn(async move {
-        let client = Association::client(sctp::association::Config {
-            net_conn: ca,
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
-            name: "client".to_owned(),
-        })
-        .await;
+        let mut config = sctp::association::Config::new(ca);
+        config.name = "client".to_owned();
+        let client = Association::client(config).await;
 
         let _ = handshake0ch_tx.send(client).await;
         let _ = closed_rx0.recv().await;
@@ -46,13 +42,9 @@ async fn create_new_association_pair(
 
     // Setup server
     tokio::spawn(async move {
-        let server = Association::server(sctp::association::Config {
-            net_conn: cb,
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
-            name: "server".to_owned(),
-        })
-        .await;
+        let mut config = sctp::association::Config::new(cb);
+        config.name = "server".to_owned();
+        let server = Association::server(config).await;
 
         let _ = handshake1ch_tx.send(server).await;
         let _ = closed_rx1.recv().await;
@@ -51,6 +51,8 @@ pub struct DataChannel {
 
 impl DataChannel {
     pub fn new(stream: Arc<Stream>, config: Config) -> Self {
+        stream.set_priority(config.priority);
+
         Self {
             config,
             stream,
@@ -84,10 +86,11 @@ impl DataChannel {
     where
         T: Borrow<Self>,
     {
-        let stream = association
-            .accept_stream()
-            .await
-            .ok_or(Error::ErrStreamClosed)?;
+        let stream = association.accept_stream().await.ok_or_else(|| {
+            association
+                .abort_error()
+                .map_or(Error::ErrStreamClosed, Error::Sctp)
+        })?;
 
         for channel in existing_channels.iter().map(|ch| ch.borrow()) {
             if channel.stream_identifier() == stream.stream_identifier() {
@@ -175,7 +178,9 @@ impl DataChannel {
                 Ok((n, ppi)) => (n, ppi),
                 Err(err) => {
                     // Shutdown the stream and send the reset request to the remote.
-                    self.close().await?;
+                    if let Err(close_err) = self.close().await {
+                        log::debug!("Failed to close the stream: {}", close_err);
+                    }
                     return Err(err.into());
                 }
             };
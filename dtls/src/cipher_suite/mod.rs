@@ -25,7 +25,7 @@ use super::record_layer::record_layer_header::*;
 // CipherSuiteID is an ID for our supported CipherSuites
 // Supported Cipher Suites
 #[allow(non_camel_case_types)]
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Default, Copy, Clone, Debug, PartialEq, Eq)]
 pub enum CipherSuiteId {
     // AES-128-CCM
     Tls_Ecdhe_Ecdsa_With_Aes_128_Ccm = 0xc0ac,
@@ -43,6 +43,7 @@ pub enum CipherSuiteId {
     Tls_Psk_With_Aes_128_Ccm_8 = 0xc0a8,
     Tls_Psk_With_Aes_128_Gcm_Sha256 = 0x00a8,
 
+    #[default]
     Unsupported,
 }
 
@@ -6,7 +6,8 @@ use crate::cipher_suite::*;
 use crate::crypto::*;
 use crate::error::*;
 use crate::extension::extension_use_srtp::SrtpProtectionProfile;
-use crate::handshaker::VerifyPeerCertificateFn;
+use crate::handshaker::{HandshakeObserverFn, VerifyPeerCertificateFn};
+use crate::session::{SessionStore, SessionTicketKey};
 use crate::signature_hash_algorithm::SignatureScheme;
 
 /// Config is used to configure a DTLS client or server.
@@ -100,6 +101,28 @@ pub struct Config {
     /// Packet with sequence number older than this value compared to the latest
     /// accepted packet will be discarded. (default is 64)
     pub replay_protection_window: usize,
+
+    /// session_store enables session resumption. A client offers the session
+    /// stored for server_name and a server resumes the session a client offers
+    /// with an abbreviated handshake, skipping the key exchange and certificate
+    /// messages. Resumed connections reuse the master secret of the original
+    /// handshake and therefore do not have forward secrecy, see Session.
+    /// If session_store is None, sessions are neither saved nor resumed.
+    pub session_store: Option<Arc<dyn SessionStore + Send + Sync>>,
+
+    /// session_ticket_key lets a server issue session tickets, RFC 5077, to
+    /// clients that ask for them and resume the sessions of the tickets they
+    /// present, without storing the sessions itself. A client asks for tickets
+    /// and keeps them in its session_store. Like stored sessions, tickets give
+    /// resumed connections no forward secrecy, and a leaked key exposes every
+    /// ticket it sealed, see SessionTicketKey.
+    /// If session_ticket_key is None, a server neither issues nor accepts tickets.
+    pub session_ticket_key: Option<SessionTicketKey>,
+
+    /// handshake_observer, if not None, is called with every handshake message
+    /// sent or received, e.g. to keep a record of the handshake. It only
+    /// observes the messages and can't alter the handshake.
+    pub handshake_observer: Option<HandshakeObserverFn>,
 }
 
 impl Default for Config {
@@ -123,6 +146,9 @@ impl Default for Config {
             server_name: String::default(),
             mtu: 0,
             replay_protection_window: 0,
+            session_store: None,
+            session_ticket_key: None,
+            handshake_observer: None,
         }
     }
 }
@@ -172,14 +198,6 @@ pub(crate) fn validate_config(is_client: bool, config: &Config) -> Result<()> {
         return Err(Error::ErrIdentityNoPsk);
     }
 
-    for cert in &config.certificates {
-        match cert.private_key.kind {
-            CryptoPrivateKeyKind::Ed25519(_) => {}
-            CryptoPrivateKeyKind::Ecdsa256(_) => {}
-            _ => return Err(Error::ErrInvalidPrivateKey),
-        }
-    }
-
     parse_cipher_suites(
         &config.cipher_suites,
         config.psk.is_none(),
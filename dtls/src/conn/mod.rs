@@ -68,6 +68,7 @@ struct ConnReaderContext {
     handshake_tx: mpsc::Sender<(oneshot::Sender<()>, mpsc::Sender<()>)>,
     handshake_done_rx: mpsc::Receiver<()>,
     packet_tx: Arc<mpsc::Sender<PacketSendRequest>>,
+    handshake_observer: Option<HandshakeObserverFn>,
 }
 
 // Conn represents a DTLS connection
@@ -207,6 +208,9 @@ impl DTLSConn {
             }
         }
 
+        let handshake_observer = config.handshake_observer.take();
+        let handshake_observer2 = handshake_observer.clone();
+
         let cfg = HandshakeConfig {
             local_psk_callback: config.psk.take(),
             local_psk_identity_hint: config.psk_identity_hint.take(),
@@ -253,6 +257,8 @@ impl DTLSConn {
             retransmit_interval,
             //log: logger,
             initial_epoch: 0,
+            session_store: config.session_store.take(),
+            session_ticket_key: config.session_ticket_key.take(),
             ..Default::default()
         };
 
@@ -335,6 +341,7 @@ impl DTLSConn {
                         &sequence_number,
                         &cipher_suite1,
                         maximum_transmission_unit,
+                        &handshake_observer,
                     )
                     .await;
 
@@ -367,6 +374,7 @@ impl DTLSConn {
                 handshake_tx,
                 handshake_done_rx,
                 packet_tx: packet_tx2,
+                handshake_observer: handshake_observer2,
             };
 
             //trace!("before enter read_and_buffer: {}] ", srv_cli_str(is_client));
@@ -552,6 +560,7 @@ impl DTLSConn {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     async fn handle_outgoing_packets(
         next_conn: &Arc<dyn util::Conn + Send + Sync>,
         mut pkts: Vec<Packet>,
@@ -560,6 +569,7 @@ impl DTLSConn {
         local_sequence_number: &Arc<Mutex<Vec<u64>>>,
         cipher_suite: &Arc<Mutex<Option<Box<dyn CipherSuite + Send + Sync>>>>,
         maximum_transmission_unit: usize,
+        handshake_observer: &Option<HandshakeObserverFn>,
     ) -> Result<()> {
         let mut raw_packets = vec![];
         for p in &mut pkts {
@@ -576,7 +586,7 @@ impl DTLSConn {
                     p.record.record_layer_header.epoch,
                     h.handshake_header.message_sequence
                 );
-                cache
+                let is_new = cache
                     .push(
                         handshake_raw[RECORD_LAYER_HEADER_SIZE..].to_vec(),
                         p.record.record_layer_header.epoch,
@@ -585,6 +595,13 @@ impl DTLSConn {
                         is_client,
                     )
                     .await;
+                if let (true, Some(observer)) = (is_new, handshake_observer) {
+                    observer(
+                        HandshakeDirection::Sent,
+                        h.handshake_header.handshake_type,
+                        &handshake_raw[RECORD_LAYER_HEADER_SIZE..],
+                    );
+                }
 
                 let raw_handshake_packets = DTLSConn::process_handshake_packet(
                     local_sequence_number,
@@ -1058,7 +1075,12 @@ impl DTLSConn {
                     }
                 };
 
-                ctx.cache
+                let observed = ctx
+                    .handshake_observer
+                    .as_ref()
+                    .map(|observer| (Arc::clone(observer), out.clone()));
+                let is_new = ctx
+                    .cache
                     .push(
                         out,
                         epoch,
@@ -1067,6 +1089,13 @@ impl DTLSConn {
                         !ctx.is_client,
                     )
                     .await;
+                if let (true, Some((observer, raw))) = (is_new, observed) {
+                    observer(
+                        HandshakeDirection::Received,
+                        raw_handshake.handshake_header.handshake_type,
+                        &raw,
+                    );
+                }
             }
 
             return (true, None, None);
@@ -20,6 +20,7 @@ use rcgen::{generate_simple_self_signed, CertifiedKey, KeyPair};
 use ring::rand::SystemRandom;
 use ring::signature::{EcdsaKeyPair, Ed25519KeyPair};
 
+use crate::client_certificate_type::ClientCertificateType;
 use crate::curve::named_curve::*;
 use crate::error::*;
 use crate::record_layer::record_layer_header::*;
@@ -250,6 +251,21 @@ impl CryptoPrivateKey {
             Err(Error::Other("Unsupported key_pair".to_owned()))
         }
     }
+
+    /// is_compatible reports whether this key can authenticate cipher suites requiring
+    /// the given certificate type.
+    pub(crate) fn is_compatible(&self, certificate_type: ClientCertificateType) -> bool {
+        match certificate_type {
+            ClientCertificateType::EcdsaSign => matches!(
+                self.kind,
+                CryptoPrivateKeyKind::Ecdsa256(_) | CryptoPrivateKeyKind::Ed25519(_)
+            ),
+            ClientCertificateType::RsaSign => {
+                matches!(self.kind, CryptoPrivateKeyKind::Rsa256(_))
+            }
+            ClientCertificateType::Unsupported => false,
+        }
+    }
 }
 
 // If the client provided a "signature_algorithms" extension, then all
@@ -50,6 +50,12 @@ pub enum Error {
     ErrCookieMismatch,
     #[error("cookie must not be longer then 255 bytes")]
     ErrCookieTooLong,
+    #[error("session id must not be longer then 32 bytes")]
+    ErrSessionIdTooLong,
+    #[error("server resumed the session with a different cipher suite")]
+    ErrSessionCipherSuiteMismatch,
+    #[error("server resumed the session with a different extended master secret setting")]
+    ErrSessionExtendedMasterSecretMismatch,
     #[error("PSK Identity Hint provided but PSK is nil")]
     ErrIdentityNoPsk,
     #[error("no certificate provided")]
@@ -0,0 +1,43 @@
+#[cfg(test)]
+mod extension_session_ticket_test;
+
+use super::*;
+
+/// SessionTicket is empty in a ClientHello that asks for a new ticket and in
+/// the ServerHello of a server that will send one, otherwise a ClientHello
+/// carries the ticket it presents.
+///
+/// ## Specifications
+///
+/// * [RFC 5077 §3.2]
+///
+/// [RFC 5077 §3.2]: https://tools.ietf.org/html/rfc5077#section-3.2
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtensionSessionTicket {
+    pub(crate) ticket: Vec<u8>,
+}
+
+impl ExtensionSessionTicket {
+    pub fn extension_value(&self) -> ExtensionValue {
+        ExtensionValue::SessionTicket
+    }
+
+    pub fn size(&self) -> usize {
+        2 + self.ticket.len()
+    }
+
+    pub fn marshal<W: Write>(&self, writer: &mut W) -> Result<()> {
+        writer.write_u16::<BigEndian>(self.ticket.len() as u16)?;
+        writer.write_all(&self.ticket)?;
+
+        Ok(writer.flush()?)
+    }
+
+    pub fn unmarshal<R: Read>(reader: &mut R) -> Result<Self> {
+        let ticket_len = reader.read_u16::<BigEndian>()? as usize;
+        let mut ticket = vec![0; ticket_len];
+        reader.read_exact(&mut ticket)?;
+
+        Ok(ExtensionSessionTicket { ticket })
+    }
+}
@@ -0,0 +1,38 @@
+use std::io::{BufReader, BufWriter};
+
+use super::*;
+
+#[test]
+fn test_extension_session_ticket() -> Result<()> {
+    for (raw_session_ticket, parsed_session_ticket) in [
+        (vec![0x00, 0x00], ExtensionSessionTicket { ticket: vec![] }),
+        (
+            vec![0x00, 0x03, 0x01, 0x02, 0x03],
+            ExtensionSessionTicket {
+                ticket: vec![0x01, 0x02, 0x03],
+            },
+        ),
+    ] {
+        let mut raw = vec![];
+        {
+            let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+            parsed_session_ticket.marshal(&mut writer)?;
+        }
+
+        assert_eq!(
+            raw, raw_session_ticket,
+            "extensionSessionTicket marshal: got {raw:?}, want {raw_session_ticket:?}"
+        );
+        assert_eq!(raw.len(), parsed_session_ticket.size());
+
+        let mut reader = BufReader::new(raw.as_slice());
+        let new_session_ticket = ExtensionSessionTicket::unmarshal(&mut reader)?;
+
+        assert_eq!(
+            new_session_ticket, parsed_session_ticket,
+            "extensionSessionTicket unmarshal: got {new_session_ticket:?}, want {parsed_session_ticket:?}"
+        );
+    }
+
+    Ok(())
+}
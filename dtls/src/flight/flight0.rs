@@ -5,13 +5,16 @@ use async_trait::async_trait;
 use rand::Rng;
 
 use super::flight2::*;
+use super::flight4b::*;
 use super::*;
 use crate::config::*;
 use crate::conn::*;
 use crate::error::Error;
 use crate::extension::*;
+use crate::handshake::handshake_message_client_hello::*;
 use crate::handshake::*;
 use crate::record_layer::record_layer_header::*;
+use crate::session::*;
 use crate::*;
 
 #[derive(Debug, PartialEq)]
@@ -77,8 +80,33 @@ impl Flight for Flight0 {
 
             state.remote_random = client_hello.random.clone();
 
+            for extension in &client_hello.extensions {
+                if let Extension::SupportedSignatureAlgorithms(e) = extension {
+                    state
+                        .remote_signature_schemes
+                        .clone_from(&e.signature_hash_algorithms);
+                }
+            }
+
+            // With several certificates, only offer cipher suites one of them can authenticate
+            let local_cipher_suites: Vec<CipherSuiteId> = if cfg.local_certificates.len() > 1 {
+                cfg.local_cipher_suites
+                    .iter()
+                    .filter(|id| {
+                        cipher_suite_for_id(**id)
+                            .map(|c| {
+                                cfg.can_authenticate(c.as_ref(), &state.remote_signature_schemes)
+                            })
+                            .unwrap_or(false)
+                    })
+                    .copied()
+                    .collect()
+            } else {
+                cfg.local_cipher_suites.clone()
+            };
+
             if let Ok(id) =
-                find_matching_cipher_suite(&client_hello.cipher_suites, &cfg.local_cipher_suites)
+                find_matching_cipher_suite(&client_hello.cipher_suites, &local_cipher_suites)
             {
                 if let Ok(cipher_suite) = cipher_suite_for_id(id) {
                     log::debug!(
@@ -153,6 +181,69 @@ impl Flight for Flight0 {
                 ));
             }
 
+            // A client asks for a ticket with an empty SessionTicket extension and
+            // presents one in it to resume its session
+            let offered_ticket = if cfg.session_ticket_key.is_some() {
+                client_hello
+                    .extensions
+                    .iter()
+                    .find_map(|extension| match extension {
+                        Extension::SessionTicket(e) => Some(e.ticket.clone()),
+                        _ => None,
+                    })
+            } else {
+                None
+            };
+            state.new_session_ticket = offered_ticket.is_some();
+
+            if !client_hello.session_id.is_empty() {
+                let mut session = match (&cfg.session_ticket_key, &offered_ticket) {
+                    (Some(key), Some(ticket)) if !ticket.is_empty() => key.open(ticket),
+                    _ => None,
+                };
+                if session.is_none() {
+                    if let Some(session_store) = &cfg.session_store {
+                        session = match session_store.get(&client_hello.session_id).await {
+                            Ok(session) => session,
+                            Err(err) => {
+                                return Err((
+                                    Some(Alert {
+                                        alert_level: AlertLevel::Fatal,
+                                        alert_description: AlertDescription::InternalError,
+                                    }),
+                                    Some(err),
+                                ))
+                            }
+                        };
+                    }
+                }
+
+                if let Some(session) = session {
+                    if let Some(cipher_suite) =
+                        resumable_cipher_suite(state, cfg, client_hello, &session)
+                    {
+                        log::trace!("[handshake] resume session: {:?}", client_hello.session_id);
+                        {
+                            let mut cs = state.cipher_suite.lock().await;
+                            *cs = Some(cipher_suite);
+                        }
+                        state.session_id.clone_from(&client_hello.session_id);
+                        state.master_secret = session.secret;
+                        state.peer_certificates = session.peer_certificates;
+                        // The presented ticket stays valid, no new one is issued
+                        state.new_session_ticket = false;
+                        initialize_resumed_cipher_suite(state).await?;
+
+                        return Ok(Box::new(Flight4b {}));
+                    }
+
+                    log::trace!(
+                        "[handshake] session {:?} doesn't match the ClientHello, do a full handshake",
+                        client_hello.session_id
+                    );
+                }
+            }
+
             if state.local_keypair.is_none() {
                 state.local_keypair = match state.named_curve.generate_keypair() {
                     Ok(local_keypar) => Some(local_keypar),
@@ -201,3 +292,26 @@ impl Flight for Flight0 {
         Ok(vec![])
     }
 }
+
+// resumable_cipher_suite returns the cipher suite to resume session with, or None
+// if the ClientHello can't resume it and a full handshake is needed. A session is
+// only resumed with its own cipher suite, which the client has to offer again,
+// RFC 5246 Section 7.4.1.2, and by a handshake that negotiates the extended master
+// secret like the original one did, RFC 7627 Section 5.3.
+fn resumable_cipher_suite(
+    state: &State,
+    cfg: &HandshakeConfig,
+    client_hello: &HandshakeMessageClientHello,
+    session: &Session,
+) -> Option<Box<dyn CipherSuite + Send + Sync>> {
+    if session.extended_master_secret != state.extended_master_secret {
+        return None;
+    }
+    if !client_hello.cipher_suites.contains(&session.cipher_suite)
+        || !cfg.local_cipher_suites.contains(&session.cipher_suite)
+    {
+        return None;
+    }
+
+    cipher_suite_for_id(session.cipher_suite).ok()
+}
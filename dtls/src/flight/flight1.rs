@@ -2,6 +2,7 @@ use std::fmt;
 use std::sync::atomic::Ordering;
 
 use async_trait::async_trait;
+use rand::Rng;
 
 use super::flight3::*;
 use super::*;
@@ -12,6 +13,7 @@ use crate::content::*;
 use crate::curve::named_curve::*;
 use crate::error::Error;
 use crate::extension::extension_server_name::*;
+use crate::extension::extension_session_ticket::*;
 use crate::extension::extension_supported_elliptic_curves::*;
 use crate::extension::extension_supported_point_formats::*;
 use crate::extension::extension_supported_signature_algorithms::*;
@@ -130,6 +132,35 @@ impl Flight for Flight1 {
         state.cookie = vec![];
         state.local_random.populate();
 
+        state.session_id = vec![];
+        state.offered_session = None;
+        if let Some(session_store) = &cfg.session_store {
+            match session_store.get(cfg.server_name.as_bytes()).await {
+                Ok(Some(session)) => {
+                    log::trace!("[handshake] get saved session: {:?}", session.id);
+                    // A client presenting a ticket picks a session id, which the server
+                    // echoes if it accepts the ticket, RFC 5077 Section 3.4
+                    if session.ticket.is_empty() {
+                        state.session_id.clone_from(&session.id);
+                    } else {
+                        state.session_id = vec![0; SESSION_ID_MAX_LENGTH];
+                        rand::thread_rng().fill(state.session_id.as_mut_slice());
+                    }
+                    state.offered_session = Some(session);
+                }
+                Ok(None) => {}
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InternalError,
+                        }),
+                        Some(err),
+                    ))
+                }
+            }
+        }
+
         let mut extensions = vec![
             Extension::SupportedSignatureAlgorithms(ExtensionSupportedSignatureAlgorithms {
                 signature_hash_algorithms: cfg.local_signature_schemes.clone(),
@@ -170,6 +201,16 @@ impl Flight for Flight1 {
             }));
         }
 
+        if cfg.session_store.is_some() {
+            extensions.push(Extension::SessionTicket(ExtensionSessionTicket {
+                ticket: state
+                    .offered_session
+                    .as_ref()
+                    .map(|session| session.ticket.clone())
+                    .unwrap_or_default(),
+            }));
+        }
+
         Ok(vec![Packet {
             record: RecordLayer::new(
                 PROTOCOL_VERSION1_2,
@@ -178,6 +219,7 @@ impl Flight for Flight1 {
                     HandshakeMessageClientHello {
                         version: PROTOCOL_VERSION1_2,
                         random: state.local_random.clone(),
+                        session_id: state.session_id.clone(),
                         cookie: state.cookie.clone(),
 
                         cipher_suites: cfg.local_cipher_suites.clone(),
@@ -3,7 +3,9 @@ use std::fmt;
 use async_trait::async_trait;
 use log::*;
 
+use super::flight4b::*;
 use super::flight5::*;
+use super::flight5b::*;
 use super::*;
 use crate::cipher_suite::cipher_suite_for_id;
 use crate::compression_methods::*;
@@ -12,6 +14,7 @@ use crate::content::*;
 use crate::curve::named_curve::*;
 use crate::error::Error;
 use crate::extension::extension_server_name::*;
+use crate::extension::extension_session_ticket::*;
 use crate::extension::extension_supported_elliptic_curves::*;
 use crate::extension::extension_supported_point_formats::*;
 use crate::extension::extension_supported_signature_algorithms::*;
@@ -20,11 +23,13 @@ use crate::extension::extension_use_srtp::*;
 use crate::extension::renegotiation_info::ExtensionRenegotiationInfo;
 use crate::extension::*;
 use crate::handshake::handshake_message_client_hello::*;
+use crate::handshake::handshake_message_server_hello::*;
 use crate::handshake::handshake_message_server_key_exchange::*;
 use crate::handshake::*;
-use crate::prf::{prf_pre_master_secret, prf_psk_pre_master_secret};
+use crate::prf::{prf_pre_master_secret, prf_psk_pre_master_secret, prf_verify_data_server};
 use crate::record_layer::record_layer_header::*;
 use crate::record_layer::*;
+use crate::session::*;
 use crate::{find_matching_cipher_suite, find_matching_srtp_profile};
 
 #[derive(Debug, PartialEq)]
@@ -40,7 +45,7 @@ impl fmt::Display for Flight3 {
 impl Flight for Flight3 {
     async fn parse(
         &self,
-        _tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+        tx: &mut mpsc::Sender<mpsc::Sender<()>>,
         state: &mut State,
         cache: &HandshakeCache,
         cfg: &HandshakeConfig,
@@ -94,6 +99,35 @@ impl Flight for Flight3 {
             }
         }
 
+        // A ServerHello echoing the session id we offered resumes the session,
+        // the server's Finished follows it instead of the key exchange
+        if let Some(session) = state.offered_session.clone() {
+            if let Ok((_, msgs)) = cache
+                .full_pull_map(
+                    state.handshake_recv_sequence,
+                    &[HandshakeCachePullRule {
+                        typ: HandshakeType::ServerHello,
+                        epoch: cfg.initial_epoch,
+                        is_client: false,
+                        optional: false,
+                    }],
+                )
+                .await
+            {
+                if let Some(HandshakeMessage::ServerHello(h)) =
+                    msgs.get(&HandshakeType::ServerHello)
+                {
+                    if !h.session_id.is_empty() && h.session_id == state.session_id {
+                        handle_server_hello(state, cfg, h).await?;
+                        check_resumed_session(state, h, &session)?;
+                        state.master_secret = session.secret;
+                        state.peer_certificates = session.peer_certificates;
+                        return handle_resumption(tx, state, cache, cfg).await;
+                    }
+                }
+            }
+        }
+
         let result = if cfg.local_psk_callback.is_some() {
             cache
                 .full_pull_map(
@@ -181,112 +215,24 @@ impl Flight for Flight3 {
                 }
             };
 
-            if h.version != PROTOCOL_VERSION1_2 {
-                return Err((
-                    Some(Alert {
-                        alert_level: AlertLevel::Fatal,
-                        alert_description: AlertDescription::ProtocolVersion,
-                    }),
-                    Some(Error::ErrUnsupportedProtocolVersion),
-                ));
-            }
+            handle_server_hello(state, cfg, h).await?;
 
-            for extension in &h.extensions {
-                match extension {
-                    Extension::UseSrtp(e) => {
-                        let profile = match find_matching_srtp_profile(
-                            &e.protection_profiles,
-                            &cfg.local_srtp_protection_profiles,
-                        ) {
-                            Ok(profile) => profile,
-                            Err(_) => {
-                                return Err((
-                                    Some(Alert {
-                                        alert_level: AlertLevel::Fatal,
-                                        alert_description: AlertDescription::IllegalParameter,
-                                    }),
-                                    Some(Error::ErrClientNoMatchingSrtpProfile),
-                                ))
-                            }
-                        };
-                        state.srtp_protection_profile = profile;
-                    }
-                    Extension::UseExtendedMasterSecret(_) => {
-                        if cfg.extended_master_secret != ExtendedMasterSecretType::Disable {
-                            state.extended_master_secret = true;
-                        }
+            // The server didn't resume the offered session, so it is gone or expired
+            if let Some(session_store) = &cfg.session_store {
+                if state.offered_session.take().is_some() {
+                    trace!("[handshake] clean old session: {:?}", state.session_id);
+                    if let Err(err) = session_store.del(cfg.server_name.as_bytes()).await {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::InternalError,
+                            }),
+                            Some(err),
+                        ));
                     }
-                    _ => {}
-                };
-            }
-
-            if cfg.extended_master_secret == ExtendedMasterSecretType::Require
-                && !state.extended_master_secret
-            {
-                return Err((
-                    Some(Alert {
-                        alert_level: AlertLevel::Fatal,
-                        alert_description: AlertDescription::InsufficientSecurity,
-                    }),
-                    Some(Error::ErrClientRequiredButNoServerEms),
-                ));
-            }
-            if !cfg.local_srtp_protection_profiles.is_empty()
-                && state.srtp_protection_profile == SrtpProtectionProfile::Unsupported
-            {
-                return Err((
-                    Some(Alert {
-                        alert_level: AlertLevel::Fatal,
-                        alert_description: AlertDescription::InsufficientSecurity,
-                    }),
-                    Some(Error::ErrRequestedButNoSrtpExtension),
-                ));
-            }
-            if find_matching_cipher_suite(&[h.cipher_suite], &cfg.local_cipher_suites).is_err() {
-                debug!(
-                    "[handshake:{}] use cipher suite: {}",
-                    srv_cli_str(state.is_client),
-                    h.cipher_suite
-                );
-
-                return Err((
-                    Some(Alert {
-                        alert_level: AlertLevel::Fatal,
-                        alert_description: AlertDescription::InsufficientSecurity,
-                    }),
-                    Some(Error::ErrCipherSuiteNoIntersection),
-                ));
-            }
-
-            let cipher_suite = match cipher_suite_for_id(h.cipher_suite) {
-                Ok(cipher_suite) => cipher_suite,
-                Err(_) => {
-                    debug!(
-                        "[handshake:{}] use cipher suite: {}",
-                        srv_cli_str(state.is_client),
-                        h.cipher_suite
-                    );
-
-                    return Err((
-                        Some(Alert {
-                            alert_level: AlertLevel::Fatal,
-                            alert_description: AlertDescription::InsufficientSecurity,
-                        }),
-                        Some(Error::ErrInvalidCipherSuite),
-                    ));
                 }
-            };
-
-            trace!(
-                "[handshake:{}] use cipher suite: {}",
-                srv_cli_str(state.is_client),
-                cipher_suite.to_string()
-            );
-            {
-                let mut cs = state.cipher_suite.lock().await;
-                *cs = Some(cipher_suite);
+                state.session_id.clone_from(&h.session_id);
             }
-            state.remote_random = h.random.clone();
         }
 
         if let Some(message) = msgs.get(&HandshakeType::Certificate) {
@@ -389,6 +335,16 @@ impl Flight for Flight3 {
             }));
         }
 
+        if cfg.session_store.is_some() {
+            extensions.push(Extension::SessionTicket(ExtensionSessionTicket {
+                ticket: state
+                    .offered_session
+                    .as_ref()
+                    .map(|session| session.ticket.clone())
+                    .unwrap_or_default(),
+            }));
+        }
+
         Ok(vec![Packet {
             record: RecordLayer::new(
                 PROTOCOL_VERSION1_2,
@@ -397,6 +353,7 @@ impl Flight for Flight3 {
                     HandshakeMessageClientHello {
                         version: PROTOCOL_VERSION1_2,
                         random: state.local_random.clone(),
+                        session_id: state.session_id.clone(),
                         cookie: state.cookie.clone(),
 
                         cipher_suites: cfg.local_cipher_suites.clone(),
@@ -411,6 +368,288 @@ impl Flight for Flight3 {
     }
 }
 
+// handle_resumption finishes an abbreviated handshake, the server has sent its
+// Finished right after the ServerHello.
+async fn handle_resumption(
+    tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+    state: &mut State,
+    cache: &HandshakeCache,
+    cfg: &HandshakeConfig,
+) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+    initialize_resumed_cipher_suite(state).await?;
+
+    // Now, encrypted packets can be handled
+    let (done_tx, mut done_rx) = mpsc::channel(1);
+    if let Err(err) = tx.send(done_tx).await {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InternalError,
+            }),
+            Some(Error::Other(err.to_string())),
+        ));
+    }
+    done_rx.recv().await;
+
+    let (seq, msgs) = match cache
+        .full_pull_map(
+            state.handshake_recv_sequence,
+            &[
+                HandshakeCachePullRule {
+                    typ: HandshakeType::ServerHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::NewSessionTicket,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: !state.new_session_ticket,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::Finished,
+                    epoch: cfg.initial_epoch + 1,
+                    is_client: false,
+                    optional: false,
+                },
+            ],
+        )
+        .await
+    {
+        Ok((seq, msgs)) => (seq, msgs),
+        // No valid message received. Keep reading
+        Err(_) => return Err((None, None)),
+    };
+
+    let finished = if let Some(HandshakeMessage::Finished(h)) = msgs.get(&HandshakeType::Finished) {
+        h
+    } else {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InternalError,
+            }),
+            None,
+        ));
+    };
+
+    let plain_text = cache
+        .pull_and_merge(&[
+            HandshakeCachePullRule {
+                typ: HandshakeType::ClientHello,
+                epoch: cfg.initial_epoch,
+                is_client: true,
+                optional: false,
+            },
+            HandshakeCachePullRule {
+                typ: HandshakeType::ServerHello,
+                epoch: cfg.initial_epoch,
+                is_client: false,
+                optional: false,
+            },
+            HandshakeCachePullRule {
+                typ: HandshakeType::NewSessionTicket,
+                epoch: cfg.initial_epoch,
+                is_client: false,
+                optional: true,
+            },
+        ])
+        .await;
+
+    {
+        let cipher_suite = state.cipher_suite.lock().await;
+        if let Some(cipher_suite) = &*cipher_suite {
+            let expected_verify_data = match prf_verify_data_server(
+                &state.master_secret,
+                &plain_text,
+                cipher_suite.hash_func(),
+            ) {
+                Ok(d) => d,
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InsufficientSecurity,
+                        }),
+                        Some(err),
+                    ))
+                }
+            };
+
+            if expected_verify_data != finished.verify_data {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::HandshakeFailure,
+                    }),
+                    Some(Error::ErrVerifyDataMismatch),
+                ));
+            }
+        }
+    }
+
+    state.handshake_recv_sequence = seq;
+
+    // The server renewed the ticket of the session
+    if let Some(HandshakeMessage::NewSessionTicket(h)) = msgs.get(&HandshakeType::NewSessionTicket)
+    {
+        state.session_ticket.clone_from(&h.ticket);
+        save_session(state, cfg, cfg.server_name.as_bytes()).await?;
+    }
+
+    Ok(Box::new(Flight5b {}) as Box<dyn Flight + Send + Sync>)
+}
+
+// check_resumed_session makes sure the server resumes the session with the
+// cipher suite it was created with, RFC 5246 Section 7.4.1.3, and negotiates
+// the extended master secret like the original handshake did, RFC 7627
+// Section 5.3. Otherwise the handshake is aborted.
+fn check_resumed_session(
+    state: &State,
+    h: &HandshakeMessageServerHello,
+    session: &Session,
+) -> Result<(), (Option<Alert>, Option<Error>)> {
+    if h.cipher_suite != session.cipher_suite {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::IllegalParameter,
+            }),
+            Some(Error::ErrSessionCipherSuiteMismatch),
+        ));
+    }
+    if state.extended_master_secret != session.extended_master_secret {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::HandshakeFailure,
+            }),
+            Some(Error::ErrSessionExtendedMasterSecretMismatch),
+        ));
+    }
+
+    Ok(())
+}
+
+async fn handle_server_hello(
+    state: &mut State,
+    cfg: &HandshakeConfig,
+    h: &HandshakeMessageServerHello,
+) -> Result<(), (Option<Alert>, Option<Error>)> {
+    if h.version != PROTOCOL_VERSION1_2 {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::ProtocolVersion,
+            }),
+            Some(Error::ErrUnsupportedProtocolVersion),
+        ));
+    }
+
+    for extension in &h.extensions {
+        match extension {
+            Extension::UseSrtp(e) => {
+                let profile = match find_matching_srtp_profile(
+                    &e.protection_profiles,
+                    &cfg.local_srtp_protection_profiles,
+                ) {
+                    Ok(profile) => profile,
+                    Err(_) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::IllegalParameter,
+                            }),
+                            Some(Error::ErrClientNoMatchingSrtpProfile),
+                        ))
+                    }
+                };
+                state.srtp_protection_profile = profile;
+            }
+            Extension::UseExtendedMasterSecret(_) => {
+                if cfg.extended_master_secret != ExtendedMasterSecretType::Disable {
+                    state.extended_master_secret = true;
+                }
+            }
+            Extension::SessionTicket(_) => {
+                state.new_session_ticket = cfg.session_store.is_some();
+            }
+            _ => {}
+        };
+    }
+
+    if cfg.extended_master_secret == ExtendedMasterSecretType::Require
+        && !state.extended_master_secret
+    {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InsufficientSecurity,
+            }),
+            Some(Error::ErrClientRequiredButNoServerEms),
+        ));
+    }
+    if !cfg.local_srtp_protection_profiles.is_empty()
+        && state.srtp_protection_profile == SrtpProtectionProfile::Unsupported
+    {
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InsufficientSecurity,
+            }),
+            Some(Error::ErrRequestedButNoSrtpExtension),
+        ));
+    }
+    if find_matching_cipher_suite(&[h.cipher_suite], &cfg.local_cipher_suites).is_err() {
+        debug!(
+            "[handshake:{}] use cipher suite: {}",
+            srv_cli_str(state.is_client),
+            h.cipher_suite
+        );
+
+        return Err((
+            Some(Alert {
+                alert_level: AlertLevel::Fatal,
+                alert_description: AlertDescription::InsufficientSecurity,
+            }),
+            Some(Error::ErrCipherSuiteNoIntersection),
+        ));
+    }
+
+    let cipher_suite = match cipher_suite_for_id(h.cipher_suite) {
+        Ok(cipher_suite) => cipher_suite,
+        Err(_) => {
+            debug!(
+                "[handshake:{}] use cipher suite: {}",
+                srv_cli_str(state.is_client),
+                h.cipher_suite
+            );
+
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InsufficientSecurity,
+                }),
+                Some(Error::ErrInvalidCipherSuite),
+            ));
+        }
+    };
+
+    trace!(
+        "[handshake:{}] use cipher suite: {}",
+        srv_cli_str(state.is_client),
+        cipher_suite.to_string()
+    );
+    {
+        let mut cs = state.cipher_suite.lock().await;
+        *cs = Some(cipher_suite);
+    }
+    state.remote_random = h.random.clone();
+
+    Ok(())
+}
+
 pub(crate) fn handle_server_key_exchange(
     state: &mut State,
     cfg: &HandshakeConfig,
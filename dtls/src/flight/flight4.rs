@@ -3,6 +3,7 @@ use std::io::BufWriter;
 
 use async_trait::async_trait;
 use log::*;
+use rand::Rng;
 
 use super::flight6::*;
 use super::*;
@@ -16,6 +17,7 @@ use crate::curve::named_curve::*;
 use crate::curve::*;
 use crate::error::Error;
 use crate::extension::extension_supported_elliptic_curves::*;
+use crate::extension::extension_session_ticket::*;
 use crate::extension::extension_supported_point_formats::*;
 use crate::extension::extension_use_extended_master_secret::*;
 use crate::extension::extension_use_srtp::*;
@@ -489,9 +491,11 @@ impl Flight for Flight4 {
                     ));
                 }
             }
-            ClientAuthType::NoClientCert | ClientAuthType::RequestClientCert => {
-                return Ok(Box::new(Flight6 {}) as Box<dyn Flight + Send + Sync>);
-            }
+            ClientAuthType::NoClientCert | ClientAuthType::RequestClientCert => {}
+        }
+
+        if !state.session_id.is_empty() {
+            save_session(state, cfg, &state.session_id).await?;
         }
 
         Ok(Box::new(Flight6 {}) as Box<dyn Flight + Send + Sync>)
@@ -503,33 +507,11 @@ impl Flight for Flight4 {
         _cache: &HandshakeCache,
         cfg: &HandshakeConfig,
     ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
-        let mut extensions = vec![Extension::RenegotiationInfo(ExtensionRenegotiationInfo {
-            renegotiated_connection: 0,
-        })];
-        if (cfg.extended_master_secret == ExtendedMasterSecretType::Request
-            || cfg.extended_master_secret == ExtendedMasterSecretType::Require)
-            && state.extended_master_secret
-        {
-            extensions.push(Extension::UseExtendedMasterSecret(
-                ExtensionUseExtendedMasterSecret { supported: true },
-            ));
-        }
-
-        if state.srtp_protection_profile != SrtpProtectionProfile::Unsupported {
-            extensions.push(Extension::UseSrtp(ExtensionUseSrtp {
-                protection_profiles: vec![state.srtp_protection_profile],
-            }));
-        }
-
-        if cfg.local_psk_callback.is_none() {
-            extensions.extend_from_slice(&[
-                Extension::SupportedEllipticCurves(ExtensionSupportedEllipticCurves {
-                    elliptic_curves: vec![NamedCurve::P256, NamedCurve::X25519, NamedCurve::P384],
-                }),
-                Extension::SupportedPointFormats(ExtensionSupportedPointFormats {
-                    point_formats: vec![ELLIPTIC_CURVE_POINT_FORMAT_UNCOMPRESSED],
-                }),
-            ]);
+        // A server with a session store assigns every full handshake a session id,
+        // the session is saved under it once the client's Finished arrives
+        if cfg.session_store.is_some() && state.session_id.is_empty() {
+            state.session_id = vec![0; SESSION_ID_MAX_LENGTH];
+            rand::thread_rng().fill(state.session_id.as_mut_slice());
         }
 
         let mut pkts = vec![Packet {
@@ -537,20 +519,7 @@ impl Flight for Flight4 {
                 PROTOCOL_VERSION1_2,
                 0,
                 Content::Handshake(Handshake::new(HandshakeMessage::ServerHello(
-                    HandshakeMessageServerHello {
-                        version: PROTOCOL_VERSION1_2,
-                        random: state.local_random.clone(),
-                        cipher_suite: {
-                            let cipher_suite = state.cipher_suite.lock().await;
-                            if let Some(cipher_suite) = &*cipher_suite {
-                                cipher_suite.id()
-                            } else {
-                                CipherSuiteId::Unsupported
-                            }
-                        },
-                        compression_method: default_compression_methods().ids[0],
-                        extensions,
-                    },
+                    generate_server_hello(state, cfg).await,
                 ))),
             ),
             should_encrypt: false,
@@ -558,7 +527,15 @@ impl Flight for Flight4 {
         }];
 
         if cfg.local_psk_callback.is_none() {
-            let certificate = match cfg.get_certificate(&cfg.server_name) {
+            let selected = {
+                let cipher_suite = state.cipher_suite.lock().await;
+                cfg.select_certificate(
+                    &cfg.server_name,
+                    cipher_suite.as_deref(),
+                    &state.remote_signature_schemes,
+                )
+            };
+            let certificate = match selected {
                 Ok(cert) => cert,
                 Err(err) => {
                     return Err((
@@ -602,7 +579,7 @@ impl Flight for Flight4 {
 
             // Find compatible signature scheme
             let signature_hash_algo = match select_signature_scheme(
-                &cfg.local_signature_schemes,
+                &cfg.signature_schemes(&state.remote_signature_schemes),
                 &certificate.private_key,
             ) {
                 Ok(s) => s,
@@ -726,6 +703,62 @@ impl Flight for Flight4 {
     }
 }
 
+pub(crate) async fn generate_server_hello(
+    state: &State,
+    cfg: &HandshakeConfig,
+) -> HandshakeMessageServerHello {
+    let mut extensions = vec![Extension::RenegotiationInfo(ExtensionRenegotiationInfo {
+        renegotiated_connection: 0,
+    })];
+    if (cfg.extended_master_secret == ExtendedMasterSecretType::Request
+        || cfg.extended_master_secret == ExtendedMasterSecretType::Require)
+        && state.extended_master_secret
+    {
+        extensions.push(Extension::UseExtendedMasterSecret(
+            ExtensionUseExtendedMasterSecret { supported: true },
+        ));
+    }
+
+    if state.srtp_protection_profile != SrtpProtectionProfile::Unsupported {
+        extensions.push(Extension::UseSrtp(ExtensionUseSrtp {
+            protection_profiles: vec![state.srtp_protection_profile],
+        }));
+    }
+
+    if state.new_session_ticket {
+        extensions.push(Extension::SessionTicket(ExtensionSessionTicket {
+            ticket: vec![],
+        }));
+    }
+
+    if cfg.local_psk_callback.is_none() {
+        extensions.extend_from_slice(&[
+            Extension::SupportedEllipticCurves(ExtensionSupportedEllipticCurves {
+                elliptic_curves: vec![NamedCurve::P256, NamedCurve::X25519, NamedCurve::P384],
+            }),
+            Extension::SupportedPointFormats(ExtensionSupportedPointFormats {
+                point_formats: vec![ELLIPTIC_CURVE_POINT_FORMAT_UNCOMPRESSED],
+            }),
+        ]);
+    }
+
+    HandshakeMessageServerHello {
+        version: PROTOCOL_VERSION1_2,
+        random: state.local_random.clone(),
+        session_id: state.session_id.clone(),
+        cipher_suite: {
+            let cipher_suite = state.cipher_suite.lock().await;
+            if let Some(cipher_suite) = &*cipher_suite {
+                cipher_suite.id()
+            } else {
+                CipherSuiteId::Unsupported
+            }
+        },
+        compression_method: default_compression_methods().ids[0],
+        extensions,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -0,0 +1,284 @@
+use std::fmt;
+use std::io::BufWriter;
+
+use async_trait::async_trait;
+
+use super::flight4::*;
+use super::*;
+use crate::change_cipher_spec::*;
+use crate::content::*;
+use crate::handshake::handshake_message_finished::*;
+use crate::handshake::*;
+use crate::prf::*;
+use crate::record_layer::record_layer_header::*;
+
+/*
+  An abbreviated handshake resumes a session the server has stored, RFC 5246 Section 7.3.
+  The server answers the ClientHello directly, without HelloVerifyRequest, key exchange
+  or certificates, and sends its Finished first.
+
+  Client                                          Server
+  ------                                          ------
+  ClientHello             -------->                           Flight 1
+
+                                             ServerHello    \
+                                      [ChangeCipherSpec]     Flight 4b
+                          <--------             Finished    /
+
+  [ChangeCipherSpec]                                        \ Flight 5b
+  Finished                -------->                         /
+*/
+#[derive(Debug, PartialEq)]
+pub(crate) struct Flight4b;
+
+impl fmt::Display for Flight4b {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Flight 4b")
+    }
+}
+
+#[async_trait]
+impl Flight for Flight4b {
+    fn is_last_recv_flight(&self) -> bool {
+        true
+    }
+
+    async fn parse(
+        &self,
+        tx: &mut mpsc::Sender<mpsc::Sender<()>>,
+        state: &mut State,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
+    ) -> Result<Box<dyn Flight + Send + Sync>, (Option<Alert>, Option<Error>)> {
+        // The cipher suite was initialized when the session was resumed,
+        // encrypted packets can be handled
+        let (done_tx, mut done_rx) = mpsc::channel(1);
+        if let Err(err) = tx.send(done_tx).await {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(Error::Other(err.to_string())),
+            ));
+        }
+        done_rx.recv().await;
+
+        let (_, msgs) = match cache
+            .full_pull_map(
+                state.handshake_recv_sequence,
+                &[HandshakeCachePullRule {
+                    typ: HandshakeType::Finished,
+                    epoch: cfg.initial_epoch + 1,
+                    is_client: true,
+                    optional: false,
+                }],
+            )
+            .await
+        {
+            Ok((seq, msgs)) => (seq, msgs),
+            // No valid message received. Keep reading
+            Err(_) => return Err((None, None)),
+        };
+
+        let finished =
+            if let Some(HandshakeMessage::Finished(h)) = msgs.get(&HandshakeType::Finished) {
+                h
+            } else {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::InternalError,
+                    }),
+                    None,
+                ));
+            };
+
+        let plain_text = cache
+            .pull_and_merge(&[
+                HandshakeCachePullRule {
+                    typ: HandshakeType::ClientHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: true,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::ServerHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: false,
+                },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::Finished,
+                    epoch: cfg.initial_epoch + 1,
+                    is_client: false,
+                    optional: false,
+                },
+            ])
+            .await;
+
+        let cipher_suite = state.cipher_suite.lock().await;
+        if let Some(cipher_suite) = &*cipher_suite {
+            let expected_verify_data = match prf_verify_data_client(
+                &state.master_secret,
+                &plain_text,
+                cipher_suite.hash_func(),
+            ) {
+                Ok(d) => d,
+                Err(err) => {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InsufficientSecurity,
+                        }),
+                        Some(err),
+                    ))
+                }
+            };
+
+            if expected_verify_data != finished.verify_data {
+                return Err((
+                    Some(Alert {
+                        alert_level: AlertLevel::Fatal,
+                        alert_description: AlertDescription::HandshakeFailure,
+                    }),
+                    Some(Error::ErrVerifyDataMismatch),
+                ));
+            }
+        }
+
+        Ok(Box::new(Flight4b {}))
+    }
+
+    async fn generate(
+        &self,
+        state: &mut State,
+        cache: &HandshakeCache,
+        cfg: &HandshakeConfig,
+    ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
+        let mut server_hello = Handshake::new(HandshakeMessage::ServerHello(
+            generate_server_hello(state, cfg).await,
+        ));
+        server_hello.handshake_header.message_sequence = state.handshake_send_sequence as u16;
+
+        // The ServerHello hasn't been sent yet, so it is appended to the cached ClientHello
+        if state.local_verify_data.is_empty() {
+            let mut plain_text = cache
+                .pull_and_merge(&[HandshakeCachePullRule {
+                    typ: HandshakeType::ClientHello,
+                    epoch: cfg.initial_epoch,
+                    is_client: true,
+                    optional: false,
+                }])
+                .await;
+
+            {
+                let mut writer = BufWriter::<&mut Vec<u8>>::new(plain_text.as_mut());
+                if let Err(err) = server_hello.marshal(&mut writer) {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InternalError,
+                        }),
+                        Some(err),
+                    ));
+                }
+            }
+
+            let cipher_suite = state.cipher_suite.lock().await;
+            if let Some(cipher_suite) = &*cipher_suite {
+                state.local_verify_data = match prf_verify_data_server(
+                    &state.master_secret,
+                    &plain_text,
+                    cipher_suite.hash_func(),
+                ) {
+                    Ok(data) => data,
+                    Err(err) => {
+                        return Err((
+                            Some(Alert {
+                                alert_level: AlertLevel::Fatal,
+                                alert_description: AlertDescription::InternalError,
+                            }),
+                            Some(err),
+                        ))
+                    }
+                };
+            }
+        }
+
+        Ok(vec![
+            Packet {
+                record: RecordLayer::new(PROTOCOL_VERSION1_2, 0, Content::Handshake(server_hello)),
+                should_encrypt: false,
+                reset_local_sequence_number: false,
+            },
+            Packet {
+                record: RecordLayer::new(
+                    PROTOCOL_VERSION1_2,
+                    0,
+                    Content::ChangeCipherSpec(ChangeCipherSpec {}),
+                ),
+                should_encrypt: false,
+                reset_local_sequence_number: false,
+            },
+            Packet {
+                record: RecordLayer::new(
+                    PROTOCOL_VERSION1_2,
+                    1,
+                    Content::Handshake(Handshake::new(HandshakeMessage::Finished(
+                        HandshakeMessageFinished {
+                            verify_data: state.local_verify_data.clone(),
+                        },
+                    ))),
+                ),
+                should_encrypt: true,
+                reset_local_sequence_number: true,
+            },
+        ])
+    }
+}
+
+// initialize_resumed_cipher_suite keys the negotiated cipher suite with the
+// master secret of a resumed session and the randoms of this handshake.
+pub(crate) async fn initialize_resumed_cipher_suite(
+    state: &State,
+) -> Result<(), (Option<Alert>, Option<Error>)> {
+    let mut local_random = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(local_random.as_mut());
+        let _ = state.local_random.marshal(&mut writer);
+    }
+    let mut remote_random = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(remote_random.as_mut());
+        let _ = state.remote_random.marshal(&mut writer);
+    }
+    let (client_random, server_random) = if state.is_client {
+        (local_random, remote_random)
+    } else {
+        (remote_random, local_random)
+    };
+
+    let mut cipher_suite = state.cipher_suite.lock().await;
+    if let Some(cipher_suite) = &mut *cipher_suite {
+        if cipher_suite.is_initialized() {
+            return Ok(());
+        }
+        if let Err(err) = cipher_suite.init(
+            &state.master_secret,
+            &client_random,
+            &server_random,
+            state.is_client,
+        ) {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -47,12 +47,20 @@ impl Flight for Flight5 {
         let (_seq, msgs) = match cache
             .full_pull_map(
                 state.handshake_recv_sequence,
-                &[HandshakeCachePullRule {
-                    typ: HandshakeType::Finished,
-                    epoch: cfg.initial_epoch + 1,
-                    is_client: false,
-                    optional: false,
-                }],
+                &[
+                    HandshakeCachePullRule {
+                        typ: HandshakeType::NewSessionTicket,
+                        epoch: cfg.initial_epoch,
+                        is_client: false,
+                        optional: !state.new_session_ticket,
+                    },
+                    HandshakeCachePullRule {
+                        typ: HandshakeType::Finished,
+                        epoch: cfg.initial_epoch + 1,
+                        is_client: false,
+                        optional: false,
+                    },
+                ],
             )
             .await
         {
@@ -135,6 +143,12 @@ impl Flight for Flight5 {
                     is_client: true,
                     optional: false,
                 },
+                HandshakeCachePullRule {
+                    typ: HandshakeType::NewSessionTicket,
+                    epoch: cfg.initial_epoch,
+                    is_client: false,
+                    optional: true,
+                },
             ])
             .await;
 
@@ -170,6 +184,15 @@ impl Flight for Flight5 {
             }
         }
 
+        if let Some(HandshakeMessage::NewSessionTicket(h)) =
+            msgs.get(&HandshakeType::NewSessionTicket)
+        {
+            state.session_ticket.clone_from(&h.ticket);
+        }
+        if !state.session_id.is_empty() || !state.session_ticket.is_empty() {
+            save_session(state, cfg, cfg.server_name.as_bytes()).await?;
+        }
+
         Ok(Box::new(Flight5 {}))
     }
 
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::io::BufWriter;
 
 use async_trait::async_trait;
 
@@ -6,9 +7,11 @@ use super::*;
 use crate::change_cipher_spec::*;
 use crate::content::*;
 use crate::handshake::handshake_message_finished::*;
+use crate::handshake::handshake_message_new_session_ticket::*;
 use crate::handshake::*;
 use crate::prf::*;
 use crate::record_layer::record_layer_header::*;
+use crate::session::*;
 
 #[derive(Debug, PartialEq)]
 pub(crate) struct Flight6;
@@ -74,7 +77,48 @@ impl Flight for Flight6 {
         cache: &HandshakeCache,
         cfg: &HandshakeConfig,
     ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)> {
-        let mut pkts = vec![Packet {
+        let mut pkts = vec![];
+
+        // The ticket is sealed once, a retransmitted flight carries the same one
+        // as the Finished covers it
+        let mut new_session_ticket = None;
+        if state.new_session_ticket {
+            if let Some(key) = &cfg.session_ticket_key {
+                if state.session_ticket.is_empty() {
+                    state.session_ticket = match key.seal(&current_session(state).await) {
+                        Ok(ticket) => ticket,
+                        Err(err) => {
+                            return Err((
+                                Some(Alert {
+                                    alert_level: AlertLevel::Fatal,
+                                    alert_description: AlertDescription::InternalError,
+                                }),
+                                Some(err),
+                            ))
+                        }
+                    };
+                }
+
+                let mut h = Handshake::new(HandshakeMessage::NewSessionTicket(
+                    HandshakeMessageNewSessionTicket {
+                        ticket_lifetime_hint: SESSION_LIFETIME.as_secs() as u32,
+                        ticket: state.session_ticket.clone(),
+                    },
+                ));
+                h.handshake_header.message_sequence = state.handshake_send_sequence as u16;
+                new_session_ticket = Some(h);
+            }
+        }
+
+        if let Some(h) = &new_session_ticket {
+            pkts.push(Packet {
+                record: RecordLayer::new(PROTOCOL_VERSION1_2, 0, Content::Handshake(h.clone())),
+                should_encrypt: false,
+                reset_local_sequence_number: false,
+            });
+        }
+
+        pkts.push(Packet {
             record: RecordLayer::new(
                 PROTOCOL_VERSION1_2,
                 0,
@@ -82,10 +126,10 @@ impl Flight for Flight6 {
             ),
             should_encrypt: false,
             reset_local_sequence_number: false,
-        }];
+        });
 
         if state.local_verify_data.is_empty() {
-            let plain_text = cache
+            let mut plain_text = cache
                 .pull_and_merge(&[
                     HandshakeCachePullRule {
                         typ: HandshakeType::ClientHello,
@@ -150,6 +194,20 @@ impl Flight for Flight6 {
                 ])
                 .await;
 
+            // The NewSessionTicket hasn't been sent yet, so it is appended to the cached messages
+            if let Some(h) = &new_session_ticket {
+                let mut writer = BufWriter::<&mut Vec<u8>>::new(plain_text.as_mut());
+                if let Err(err) = h.marshal(&mut writer) {
+                    return Err((
+                        Some(Alert {
+                            alert_level: AlertLevel::Fatal,
+                            alert_description: AlertDescription::InternalError,
+                        }),
+                        Some(err),
+                    ));
+                }
+            }
+
             let cipher_suite = state.cipher_suite.lock().await;
             if let Some(cipher_suite) = &*cipher_suite {
                 state.local_verify_data = match prf_verify_data_server(
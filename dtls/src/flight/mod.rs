@@ -3,7 +3,9 @@ pub(crate) mod flight1;
 pub(crate) mod flight2;
 pub(crate) mod flight3;
 pub(crate) mod flight4;
+pub(crate) mod flight4b;
 pub(crate) mod flight5;
+pub(crate) mod flight5b;
 pub(crate) mod flight6;
 
 use std::fmt;
@@ -12,10 +14,12 @@ use async_trait::async_trait;
 use tokio::sync::mpsc;
 
 use crate::alert::*;
+use crate::cipher_suite::*;
 use crate::error::Error;
 use crate::handshake::handshake_cache::*;
 use crate::handshaker::*;
 use crate::record_layer::*;
+use crate::session::*;
 use crate::state::*;
 
 /*
@@ -85,3 +89,47 @@ pub(crate) trait Flight: fmt::Display + fmt::Debug {
         cfg: &HandshakeConfig,
     ) -> Result<Vec<Packet>, (Option<Alert>, Option<Error>)>;
 }
+
+// current_session returns the resumable state of the handshake.
+pub(crate) async fn current_session(state: &State) -> Session {
+    let cipher_suite = {
+        let cipher_suite = state.cipher_suite.lock().await;
+        cipher_suite
+            .as_ref()
+            .map(|cipher_suite| cipher_suite.id())
+            .unwrap_or(CipherSuiteId::Unsupported)
+    };
+
+    Session {
+        id: state.session_id.clone(),
+        secret: state.master_secret.clone(),
+        peer_certificates: state.peer_certificates.clone(),
+        cipher_suite,
+        extended_master_secret: state.extended_master_secret,
+        ticket: state.session_ticket.clone(),
+    }
+}
+
+// save_session stores the session of the handshake under key, so that a later
+// handshake can resume it.
+pub(crate) async fn save_session(
+    state: &State,
+    cfg: &HandshakeConfig,
+    key: &[u8],
+) -> Result<(), (Option<Alert>, Option<Error>)> {
+    if let Some(session_store) = &cfg.session_store {
+        let session = current_session(state).await;
+        log::trace!("[handshake] save new session: {:?}", session.id);
+        if let Err(err) = session_store.set(key, session).await {
+            return Err((
+                Some(Alert {
+                    alert_level: AlertLevel::Fatal,
+                    alert_description: AlertDescription::InternalError,
+                }),
+                Some(err),
+            ));
+        }
+    }
+
+    Ok(())
+}
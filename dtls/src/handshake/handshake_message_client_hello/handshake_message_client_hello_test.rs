@@ -34,6 +34,7 @@ fn test_handshake_message_client_hello() -> Result<()> {
                 0x15, 0x8d, 0x95, 0x71, 0x8a, 0xbb, 0x22, 0xd7, 0x47, 0xec, 0xd8, 0x3d, 0xdc, 0x4b,
             ],
         },
+        session_id: vec![],
         cookie: vec![
             0xe6, 0x14, 0x3a, 0x1b, 0x04, 0xea, 0x9e, 0x7a, 0x14, 0xd6, 0x6c, 0x57, 0xd0, 0x0e,
             0x32, 0x85, 0x76, 0x18, 0xde, 0xd8,
@@ -73,3 +74,43 @@ fn test_handshake_message_client_hello() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_handshake_message_client_hello_session_id() -> Result<()> {
+    let client_hello = HandshakeMessageClientHello {
+        version: ProtocolVersion {
+            major: 0xFE,
+            minor: 0xFD,
+        },
+        random: HandshakeRandom::default(),
+        session_id: vec![0xAB; SESSION_ID_MAX_LENGTH],
+        cookie: vec![0xCD; 20],
+        cipher_suites: vec![CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_128_Gcm_Sha256],
+        compression_methods: CompressionMethods {
+            ids: vec![CompressionMethodId::Null],
+        },
+        extensions: vec![],
+    };
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        client_hello.marshal(&mut writer)?;
+    }
+    assert_eq!(raw.len(), client_hello.size());
+
+    let mut reader = BufReader::new(raw.as_slice());
+    let c = HandshakeMessageClientHello::unmarshal(&mut reader)?;
+    assert_eq!(c, client_hello);
+
+    let mut too_long = client_hello;
+    too_long.session_id = vec![0xAB; SESSION_ID_MAX_LENGTH + 1];
+    let mut raw = vec![];
+    let result = {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        too_long.marshal(&mut writer)
+    };
+    assert_eq!(result, Err(Error::ErrSessionIdTooLong));
+
+    Ok(())
+}
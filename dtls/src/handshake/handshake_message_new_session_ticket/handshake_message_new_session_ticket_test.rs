@@ -0,0 +1,34 @@
+use std::io::{BufReader, BufWriter};
+
+use super::*;
+
+#[test]
+fn test_handshake_message_new_session_ticket() -> Result<()> {
+    let raw_new_session_ticket = vec![
+        0x00, 0x01, 0x51, 0x80, 0x00, 0x04, 0xde, 0xad, 0xbe, 0xef,
+    ];
+    let parsed_new_session_ticket = HandshakeMessageNewSessionTicket {
+        ticket_lifetime_hint: 86400,
+        ticket: vec![0xde, 0xad, 0xbe, 0xef],
+    };
+
+    let mut reader = BufReader::new(raw_new_session_ticket.as_slice());
+    let c = HandshakeMessageNewSessionTicket::unmarshal(&mut reader)?;
+    assert_eq!(
+        c, parsed_new_session_ticket,
+        "handshakeMessageNewSessionTicket unmarshal: got {c:?}, want {parsed_new_session_ticket:?}"
+    );
+
+    let mut raw = vec![];
+    {
+        let mut writer = BufWriter::<&mut Vec<u8>>::new(raw.as_mut());
+        c.marshal(&mut writer)?;
+    }
+    assert_eq!(
+        raw, raw_new_session_ticket,
+        "handshakeMessageNewSessionTicket marshal: got {raw:?}, want {raw_new_session_ticket:?}"
+    );
+    assert_eq!(raw.len(), c.size());
+
+    Ok(())
+}
@@ -7,6 +7,7 @@ pub mod handshake_message_client_hello;
 pub mod handshake_message_client_key_exchange;
 pub mod handshake_message_finished;
 pub mod handshake_message_hello_verify_request;
+pub mod handshake_message_new_session_ticket;
 pub mod handshake_message_server_hello;
 pub mod handshake_message_server_hello_done;
 pub mod handshake_message_server_key_exchange;
@@ -26,6 +27,7 @@ use handshake_message_client_hello::*;
 use handshake_message_client_key_exchange::*;
 use handshake_message_finished::*;
 use handshake_message_hello_verify_request::*;
+use handshake_message_new_session_ticket::*;
 use handshake_message_server_hello::*;
 use handshake_message_server_hello_done::*;
 use handshake_message_server_key_exchange::*;
@@ -33,6 +35,10 @@ use handshake_message_server_key_exchange::*;
 use super::content::*;
 use super::error::*;
 
+// A session id is opaque to the peer and at most 32 bytes long
+// https://tools.ietf.org/html/rfc5246#section-7.4.1.2
+pub(crate) const SESSION_ID_MAX_LENGTH: usize = 32;
+
 /// ## Specifications
 ///
 /// * [RFC 5246 §7.4]
@@ -44,6 +50,7 @@ pub enum HandshakeType {
     ClientHello = 1,
     ServerHello = 2,
     HelloVerifyRequest = 3,
+    NewSessionTicket = 4,
     Certificate = 11,
     ServerKeyExchange = 12,
     CertificateRequest = 13,
@@ -62,6 +69,7 @@ impl fmt::Display for HandshakeType {
             HandshakeType::ClientHello => write!(f, "ClientHello"),
             HandshakeType::ServerHello => write!(f, "ServerHello"),
             HandshakeType::HelloVerifyRequest => write!(f, "HelloVerifyRequest"),
+            HandshakeType::NewSessionTicket => write!(f, "NewSessionTicket"),
             HandshakeType::Certificate => write!(f, "Certificate"),
             HandshakeType::ServerKeyExchange => write!(f, "ServerKeyExchange"),
             HandshakeType::CertificateRequest => write!(f, "CertificateRequest"),
@@ -81,6 +89,7 @@ impl From<u8> for HandshakeType {
             1 => HandshakeType::ClientHello,
             2 => HandshakeType::ServerHello,
             3 => HandshakeType::HelloVerifyRequest,
+            4 => HandshakeType::NewSessionTicket,
             11 => HandshakeType::Certificate,
             12 => HandshakeType::ServerKeyExchange,
             13 => HandshakeType::CertificateRequest,
@@ -99,6 +108,7 @@ pub enum HandshakeMessage {
     ClientHello(HandshakeMessageClientHello),
     ServerHello(HandshakeMessageServerHello),
     HelloVerifyRequest(HandshakeMessageHelloVerifyRequest),
+    NewSessionTicket(HandshakeMessageNewSessionTicket),
     Certificate(HandshakeMessageCertificate),
     ServerKeyExchange(HandshakeMessageServerKeyExchange),
     CertificateRequest(HandshakeMessageCertificateRequest),
@@ -114,6 +124,7 @@ impl HandshakeMessage {
             HandshakeMessage::ClientHello(msg) => msg.handshake_type(),
             HandshakeMessage::ServerHello(msg) => msg.handshake_type(),
             HandshakeMessage::HelloVerifyRequest(msg) => msg.handshake_type(),
+            HandshakeMessage::NewSessionTicket(msg) => msg.handshake_type(),
             HandshakeMessage::Certificate(msg) => msg.handshake_type(),
             HandshakeMessage::ServerKeyExchange(msg) => msg.handshake_type(),
             HandshakeMessage::CertificateRequest(msg) => msg.handshake_type(),
@@ -129,6 +140,7 @@ impl HandshakeMessage {
             HandshakeMessage::ClientHello(msg) => msg.size(),
             HandshakeMessage::ServerHello(msg) => msg.size(),
             HandshakeMessage::HelloVerifyRequest(msg) => msg.size(),
+            HandshakeMessage::NewSessionTicket(msg) => msg.size(),
             HandshakeMessage::Certificate(msg) => msg.size(),
             HandshakeMessage::ServerKeyExchange(msg) => msg.size(),
             HandshakeMessage::CertificateRequest(msg) => msg.size(),
@@ -144,6 +156,7 @@ impl HandshakeMessage {
             HandshakeMessage::ClientHello(msg) => msg.marshal(writer)?,
             HandshakeMessage::ServerHello(msg) => msg.marshal(writer)?,
             HandshakeMessage::HelloVerifyRequest(msg) => msg.marshal(writer)?,
+            HandshakeMessage::NewSessionTicket(msg) => msg.marshal(writer)?,
             HandshakeMessage::Certificate(msg) => msg.marshal(writer)?,
             HandshakeMessage::ServerKeyExchange(msg) => msg.marshal(writer)?,
             HandshakeMessage::CertificateRequest(msg) => msg.marshal(writer)?,
@@ -210,6 +223,9 @@ impl Handshake {
             HandshakeType::HelloVerifyRequest => HandshakeMessage::HelloVerifyRequest(
                 HandshakeMessageHelloVerifyRequest::unmarshal(reader)?,
             ),
+            HandshakeType::NewSessionTicket => HandshakeMessage::NewSessionTicket(
+                HandshakeMessageNewSessionTicket::unmarshal(reader)?,
+            ),
             HandshakeType::Certificate => {
                 HandshakeMessage::Certificate(HandshakeMessageCertificate::unmarshal(reader)?)
             }
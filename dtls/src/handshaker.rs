@@ -11,6 +11,8 @@ use crate::content::*;
 use crate::crypto::*;
 use crate::error::*;
 use crate::extension::extension_use_srtp::*;
+use crate::handshake::HandshakeType;
+use crate::session::*;
 use crate::signature_hash_algorithm::*;
 
 use rustls::client::danger::ServerCertVerifier;
@@ -78,6 +80,29 @@ impl fmt::Display for HandshakeState {
 pub(crate) type VerifyPeerCertificateFn =
     Arc<dyn (Fn(&[Vec<u8>], &[CertificateDer<'static>]) -> Result<()>) + Send + Sync>;
 
+/// HandshakeDirection tells whether a handshake message passed to a
+/// HandshakeObserverFn was sent or received by the local end.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum HandshakeDirection {
+    Sent,
+    Received,
+}
+
+impl fmt::Display for HandshakeDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            HandshakeDirection::Sent => write!(f, "sent"),
+            HandshakeDirection::Received => write!(f, "received"),
+        }
+    }
+}
+
+/// HandshakeObserverFn is called with the direction, the type and the raw bytes
+/// (handshake header included, reassembled from its fragments) of every handshake
+/// message, once, in the order the messages are sent and received. Retransmissions
+/// are not reported.
+pub type HandshakeObserverFn = Arc<dyn Fn(HandshakeDirection, HandshakeType, &[u8]) + Send + Sync>;
+
 pub(crate) struct HandshakeConfig {
     pub(crate) local_psk_callback: Option<PskCallback>,
     pub(crate) local_psk_identity_hint: Option<Vec<u8>>,
@@ -96,6 +121,8 @@ pub(crate) struct HandshakeConfig {
     pub(crate) client_cert_verifier: Option<Arc<dyn ClientCertVerifier>>,
     pub(crate) retransmit_interval: tokio::time::Duration,
     pub(crate) initial_epoch: u16,
+    pub(crate) session_store: Option<Arc<dyn SessionStore + Send + Sync>>,
+    pub(crate) session_ticket_key: Option<SessionTicketKey>,
     //log           logging.LeveledLogger
     //mu sync.Mutex
 }
@@ -138,6 +165,8 @@ impl Default for HandshakeConfig {
             client_cert_verifier: None,
             retransmit_interval: tokio::time::Duration::from_secs(0),
             initial_epoch: 0,
+            session_store: None,
+            session_ticket_key: None,
         }
     }
 }
@@ -208,6 +237,68 @@ impl HandshakeConfig {
         // If nothing matches, return the first certificate.
         Ok(self.local_certificates[0].clone())
     }
+
+    /// signature_schemes returns the local signature schemes the remote also advertised, in
+    /// local preference order. If the remote didn't advertise any, all local schemes are returned.
+    pub(crate) fn signature_schemes(
+        &self,
+        remote_signature_schemes: &[SignatureHashAlgorithm],
+    ) -> Vec<SignatureHashAlgorithm> {
+        let shared: Vec<SignatureHashAlgorithm> = self
+            .local_signature_schemes
+            .iter()
+            .filter(|s| remote_signature_schemes.contains(s))
+            .copied()
+            .collect();
+
+        if shared.is_empty() {
+            self.local_signature_schemes.clone()
+        } else {
+            shared
+        }
+    }
+
+    /// can_authenticate reports whether one of the local certificates can sign for the given
+    /// cipher suite with a signature scheme the remote supports.
+    pub(crate) fn can_authenticate(
+        &self,
+        cipher_suite: &(dyn CipherSuite + Send + Sync),
+        remote_signature_schemes: &[SignatureHashAlgorithm],
+    ) -> bool {
+        if cipher_suite.is_psk() {
+            return true;
+        }
+
+        let signature_schemes = self.signature_schemes(remote_signature_schemes);
+        self.local_certificates.iter().any(|cert| {
+            cert.private_key
+                .is_compatible(cipher_suite.certificate_type())
+                && select_signature_scheme(&signature_schemes, &cert.private_key).is_ok()
+        })
+    }
+
+    /// select_certificate returns the certificate to present to the remote. When several
+    /// certificates are configured, the first one usable with the negotiated cipher suite and the
+    /// remote's signature schemes wins, otherwise this falls back to [`Self::get_certificate`].
+    pub(crate) fn select_certificate(
+        &self,
+        server_name: &str,
+        cipher_suite: Option<&(dyn CipherSuite + Send + Sync)>,
+        remote_signature_schemes: &[SignatureHashAlgorithm],
+    ) -> Result<Certificate> {
+        if let (true, Some(cipher_suite)) = (self.local_certificates.len() > 1, cipher_suite) {
+            let signature_schemes = self.signature_schemes(remote_signature_schemes);
+            if let Some(cert) = self.local_certificates.iter().find(|cert| {
+                cert.private_key
+                    .is_compatible(cipher_suite.certificate_type())
+                    && select_signature_scheme(&signature_schemes, &cert.private_key).is_ok()
+            }) {
+                return Ok(cert.clone());
+            }
+        }
+
+        self.get_certificate(server_name)
+    }
 }
 
 pub(crate) fn srv_cli_str(is_client: bool) -> String {
@@ -0,0 +1,262 @@
+#[cfg(test)]
+mod session_test;
+
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Cursor, Read};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use aes_gcm::aead::generic_array::GenericArray;
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit};
+use async_trait::async_trait;
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use rand::Rng;
+use tokio::sync::Mutex;
+
+use crate::cipher_suite::CipherSuiteId;
+use crate::error::*;
+
+/// SESSION_LIFETIME is how long a session can be resumed after the full
+/// handshake that created it, the upper limit RFC 5246 Appendix F.1.4
+/// suggests. It bounds both the sessions of a MemorySessionStore and the
+/// session tickets a server accepts.
+pub const SESSION_LIFETIME: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// DEFAULT_SESSION_CAPACITY is the number of sessions a MemorySessionStore
+/// created with `new` keeps before evicting the oldest one.
+pub const DEFAULT_SESSION_CAPACITY: usize = 1024;
+
+/// SESSION_TICKET_KEY_LENGTH is the length of a SessionTicketKey, an AES-256 key.
+pub const SESSION_TICKET_KEY_LENGTH: usize = 32;
+
+const SESSION_TICKET_NONCE_LENGTH: usize = 12;
+
+/// Session stores the state needed to resume a DTLS session with the
+/// abbreviated handshake described in RFC 5246 Section 7.3.
+///
+/// Resuming a session reuses the master secret of the full handshake it was
+/// created by, so a resumed connection does not get fresh ECDHE keys and has
+/// no forward secrecy with respect to that master secret: anyone who obtains a
+/// stored `Session` can decrypt every connection resumed from it. Stores should
+/// keep sessions in memory only and expire them, and applications that need
+/// forward secrecy for every connection should leave `Config::session_store`
+/// unset.
+#[derive(Default, Debug, Clone, PartialEq, Eq)]
+pub struct Session {
+    /// id is the session id the server assigned to this session, empty if the
+    /// server only issued a ticket.
+    pub id: Vec<u8>,
+    /// secret is the master secret of the session.
+    pub secret: Vec<u8>,
+    /// peer_certificates is the certificate chain the remote presented during
+    /// the full handshake, restored on resumption since no Certificate
+    /// message is exchanged then.
+    pub peer_certificates: Vec<Vec<u8>>,
+    /// cipher_suite is the cipher suite the session was negotiated with, a
+    /// resumed connection has to keep it.
+    pub cipher_suite: CipherSuiteId,
+    /// extended_master_secret tells if the master secret was derived with the
+    /// extended master secret extension, RFC 7627. A session is only resumed
+    /// by a handshake that negotiates the extension the same way.
+    pub extended_master_secret: bool,
+    /// ticket is the session ticket the server issued for this session, which
+    /// a client presents instead of the id, RFC 5077.
+    pub ticket: Vec<u8>,
+}
+
+/// SessionStore is the interface used to store and load sessions.
+///
+/// A client looks sessions up by its server name, a server by the session id
+/// the client offers in its ClientHello.
+#[async_trait]
+pub trait SessionStore {
+    /// set saves a session.
+    async fn set(&self, key: &[u8], session: Session) -> Result<()>;
+    /// get returns the session stored for key, or None if there is none.
+    async fn get(&self, key: &[u8]) -> Result<Option<Session>>;
+    /// del removes the session stored for key.
+    async fn del(&self, key: &[u8]) -> Result<()>;
+}
+
+/// MemorySessionStore is a SessionStore that keeps sessions in memory. Sessions
+/// expire after a time to live and the oldest one is evicted when the store is
+/// full.
+pub struct MemorySessionStore {
+    sessions: Mutex<HashMap<Vec<u8>, (Session, Instant)>>,
+    ttl: Duration,
+    capacity: usize,
+}
+
+impl Default for MemorySessionStore {
+    fn default() -> Self {
+        MemorySessionStore::with_limits(SESSION_LIFETIME, DEFAULT_SESSION_CAPACITY)
+    }
+}
+
+impl MemorySessionStore {
+    /// new creates a store keeping up to DEFAULT_SESSION_CAPACITY sessions for
+    /// SESSION_LIFETIME.
+    pub fn new() -> Self {
+        MemorySessionStore::default()
+    }
+
+    /// with_limits creates a store keeping up to capacity sessions for ttl.
+    pub fn with_limits(ttl: Duration, capacity: usize) -> Self {
+        MemorySessionStore {
+            sessions: Mutex::new(HashMap::new()),
+            ttl,
+            capacity,
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for MemorySessionStore {
+    async fn set(&self, key: &[u8], session: Session) -> Result<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+
+        let mut sessions = self.sessions.lock().await;
+        sessions.retain(|_, (_, created)| created.elapsed() < self.ttl);
+        if !sessions.contains_key(key) && sessions.len() >= self.capacity {
+            let oldest = sessions
+                .iter()
+                .min_by_key(|(_, (_, created))| *created)
+                .map(|(key, _)| key.clone());
+            if let Some(oldest) = oldest {
+                sessions.remove(&oldest);
+            }
+        }
+        sessions.insert(key.to_vec(), (session, Instant::now()));
+        Ok(())
+    }
+
+    async fn get(&self, key: &[u8]) -> Result<Option<Session>> {
+        let mut sessions = self.sessions.lock().await;
+        match sessions.get(key) {
+            Some((_, created)) if created.elapsed() >= self.ttl => {
+                sessions.remove(key);
+                Ok(None)
+            }
+            Some((session, _)) => Ok(Some(session.clone())),
+            None => Ok(None),
+        }
+    }
+
+    async fn del(&self, key: &[u8]) -> Result<()> {
+        let mut sessions = self.sessions.lock().await;
+        sessions.remove(key);
+        Ok(())
+    }
+}
+
+/// SessionTicketKey encrypts the session state a server hands to its clients
+/// as session ticket, RFC 5077. A server with a ticket key keeps no state for
+/// the sessions it issued tickets for, a client presenting a ticket the key
+/// decrypts resumes its session.
+///
+/// Anyone who obtains the key can decrypt the master secret of every ticket
+/// it sealed, so the key has to be as well protected as the sessions of a
+/// SessionStore, and it should be replaced regularly to limit what a leaked
+/// key exposes. Tickets sealed with a replaced key are no longer accepted and
+/// their clients fall back to a full handshake. Tickets expire after
+/// SESSION_LIFETIME.
+#[derive(Clone)]
+pub struct SessionTicketKey {
+    key: [u8; SESSION_TICKET_KEY_LENGTH],
+}
+
+impl fmt::Debug for SessionTicketKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SessionTicketKey").finish_non_exhaustive()
+    }
+}
+
+impl SessionTicketKey {
+    /// new creates a ticket key from key material, e.g. one shared by the
+    /// servers that should accept each other's tickets.
+    pub fn new(key: [u8; SESSION_TICKET_KEY_LENGTH]) -> Self {
+        SessionTicketKey { key }
+    }
+
+    /// generate creates a random ticket key.
+    pub fn generate() -> Self {
+        let mut key = [0u8; SESSION_TICKET_KEY_LENGTH];
+        rand::thread_rng().fill(&mut key);
+        SessionTicketKey { key }
+    }
+
+    // seal encrypts the resumable state of session into a ticket, nonce
+    // followed by the AES-256-GCM sealed state.
+    pub(crate) fn seal(&self, session: &Session) -> Result<Vec<u8>> {
+        let issued_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+
+        let mut state = vec![];
+        state.write_u64::<BigEndian>(issued_at)?;
+        state.write_u16::<BigEndian>(session.cipher_suite as u16)?;
+        state.write_u8(session.extended_master_secret as u8)?;
+        state.write_u8(session.secret.len() as u8)?;
+        state.extend_from_slice(&session.secret);
+        state.write_u16::<BigEndian>(session.peer_certificates.len() as u16)?;
+        for certificate in &session.peer_certificates {
+            state.write_u32::<BigEndian>(certificate.len() as u32)?;
+            state.extend_from_slice(certificate);
+        }
+
+        let mut nonce = [0u8; SESSION_TICKET_NONCE_LENGTH];
+        rand::thread_rng().fill(&mut nonce);
+
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(&self.key));
+        let sealed = cipher
+            .encrypt(GenericArray::from_slice(&nonce), state.as_slice())
+            .map_err(|err| Error::Other(err.to_string()))?;
+
+        let mut ticket = nonce.to_vec();
+        ticket.extend_from_slice(&sealed);
+        Ok(ticket)
+    }
+
+    // open decrypts a ticket sealed with this key. It returns None for
+    // tickets of another key, tampered or expired ones.
+    pub(crate) fn open(&self, ticket: &[u8]) -> Option<Session> {
+        if ticket.len() < SESSION_TICKET_NONCE_LENGTH {
+            return None;
+        }
+        let (nonce, sealed) = ticket.split_at(SESSION_TICKET_NONCE_LENGTH);
+
+        let cipher = Aes256Gcm::new(GenericArray::from_slice(&self.key));
+        let state = cipher.decrypt(GenericArray::from_slice(nonce), sealed).ok()?;
+
+        let mut reader = Cursor::new(state.as_slice());
+        let issued_at = UNIX_EPOCH + Duration::from_secs(reader.read_u64::<BigEndian>().ok()?);
+        let age = SystemTime::now().duration_since(issued_at).ok()?;
+        if age >= SESSION_LIFETIME {
+            return None;
+        }
+
+        let cipher_suite: CipherSuiteId = reader.read_u16::<BigEndian>().ok()?.into();
+        let extended_master_secret = reader.read_u8().ok()? != 0;
+        let mut secret = vec![0; reader.read_u8().ok()? as usize];
+        reader.read_exact(&mut secret).ok()?;
+        let mut peer_certificates = vec![];
+        for _ in 0..reader.read_u16::<BigEndian>().ok()? {
+            let mut certificate = vec![0; reader.read_u32::<BigEndian>().ok()? as usize];
+            reader.read_exact(&mut certificate).ok()?;
+            peer_certificates.push(certificate);
+        }
+
+        Some(Session {
+            id: vec![],
+            secret,
+            peer_certificates,
+            cipher_suite,
+            extended_master_secret,
+            ticket: vec![],
+        })
+    }
+}
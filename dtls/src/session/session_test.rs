@@ -0,0 +1,71 @@
+use super::*;
+
+fn test_session(id: u8) -> Session {
+    Session {
+        id: vec![id],
+        secret: vec![id; 48],
+        peer_certificates: vec![vec![1, 2, 3], vec![4, 5]],
+        cipher_suite: CipherSuiteId::Tls_Ecdhe_Ecdsa_With_Aes_128_Gcm_Sha256,
+        extended_master_secret: true,
+        ticket: vec![],
+    }
+}
+
+#[tokio::test]
+async fn test_memory_session_store_expires_sessions() -> Result<()> {
+    let store = MemorySessionStore::with_limits(Duration::from_millis(50), 8);
+    store.set(b"a", test_session(1)).await?;
+    assert_eq!(store.get(b"a").await?, Some(test_session(1)));
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    assert_eq!(store.get(b"a").await?, None, "session outlived the ttl");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_session_store_evicts_oldest() -> Result<()> {
+    let store = MemorySessionStore::with_limits(SESSION_LIFETIME, 2);
+    store.set(b"a", test_session(1)).await?;
+    store.set(b"b", test_session(2)).await?;
+    // Replacing a stored session doesn't evict another one
+    store.set(b"b", test_session(3)).await?;
+    assert!(store.get(b"a").await?.is_some());
+
+    store.set(b"c", test_session(4)).await?;
+    assert_eq!(store.get(b"a").await?, None, "oldest session wasn't evicted");
+    assert_eq!(store.get(b"b").await?, Some(test_session(3)));
+    assert_eq!(store.get(b"c").await?, Some(test_session(4)));
+
+    Ok(())
+}
+
+#[test]
+fn test_session_ticket_key_seal_open() -> Result<()> {
+    let key = SessionTicketKey::generate();
+    let session = test_session(1);
+    let ticket = key.seal(&session)?;
+
+    // The ticket carries the resumable state, not the id or ticket of the session
+    assert_eq!(
+        key.open(&ticket),
+        Some(Session {
+            id: vec![],
+            ..session
+        })
+    );
+
+    assert_eq!(
+        SessionTicketKey::generate().open(&ticket),
+        None,
+        "ticket opened with another key"
+    );
+
+    let mut tampered = ticket.clone();
+    let last = tampered.len() - 1;
+    tampered[last] ^= 0x01;
+    assert_eq!(key.open(&tampered), None, "tampered ticket opened");
+    assert_eq!(key.open(&ticket[..4]), None, "truncated ticket opened");
+
+    Ok(())
+}
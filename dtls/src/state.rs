@@ -15,6 +15,8 @@ use super::curve::named_curve::*;
 use super::extension::extension_use_srtp::SrtpProtectionProfile;
 use super::handshake::handshake_random::*;
 use super::prf::*;
+use super::session::Session;
+use super::signature_hash_algorithm::SignatureHashAlgorithm;
 use crate::error::*;
 
 // State holds the dtls connection state and implements both encoding.BinaryMarshaler and encoding.BinaryUnmarshaler
@@ -39,9 +41,14 @@ pub struct State {
     pub(crate) named_curve: NamedCurve,
     pub(crate) local_keypair: Option<NamedCurveKeypair>,
     pub(crate) cookie: Vec<u8>,
+    pub(crate) session_id: Vec<u8>, // empty unless a session store is configured
+    pub(crate) session_ticket: Vec<u8>, // the ticket the server issued in this handshake
+    pub(crate) new_session_ticket: bool, // the server sends a NewSessionTicket in this handshake
+    pub(crate) offered_session: Option<Session>, // the stored session a client offers to resume
     pub(crate) handshake_send_sequence: isize,
     pub(crate) handshake_recv_sequence: isize,
     pub(crate) server_name: String,
+    pub(crate) remote_signature_schemes: Vec<SignatureHashAlgorithm>, // from the ClientHello, used to pick a certificate
     pub(crate) remote_requested_certificate: bool, // Did we get a CertificateRequest
     pub(crate) local_certificates_verify: Vec<u8>, // cache CertificateVerify
     pub(crate) local_verify_data: Vec<u8>,         // cached VerifyData
@@ -88,9 +95,14 @@ impl Default for State {
             named_curve: NamedCurve::Unsupported,
             local_keypair: None,
             cookie: vec![],
+            session_id: vec![],
+            session_ticket: vec![],
+            new_session_ticket: false,
+            offered_session: None,
             handshake_send_sequence: 0,
             handshake_recv_sequence: 0,
             server_name: "".to_string(),
+            remote_signature_schemes: vec![],
             remote_requested_certificate: false, // Did we get a CertificateRequest
             local_certificates_verify: vec![],   // cache CertificateVerify
             local_verify_data: vec![],           // cached VerifyData
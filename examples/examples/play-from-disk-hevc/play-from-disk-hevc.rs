@@ -397,8 +397,7 @@ async fn offer_worker(
         .add_track(Arc::clone(&local_video_track) as Arc<dyn TrackLocal + Send + Sync>)
         .await?;
     tokio::spawn(async move {
-        let mut rtcp_buf = vec![0u8; 1500];
-        while let Ok((_, _)) = video_rtp_sender.read(&mut rtcp_buf).await {}
+        while let Ok((_, _)) = video_rtp_sender.read_rtcp().await {}
         Result::<()>::Ok(())
     });
     let notify1 = notify_connect.clone();
@@ -470,8 +469,7 @@ async fn offer_worker(
         .add_track(Arc::clone(&local_audio_track) as Arc<dyn TrackLocal + Send + Sync>)
         .await?;
     tokio::spawn(async move {
-        let mut rtcp_buf = vec![0u8; 1500];
-        while let Ok((_, _)) = audio_rtp_sender.read(&mut rtcp_buf).await {}
+        while let Ok((_, _)) = audio_rtp_sender.read_rtcp().await {}
         Result::<()>::Ok(())
     });
     let notify1 = notify_connect.clone();
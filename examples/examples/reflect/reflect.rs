@@ -173,12 +173,11 @@ async fn main() -> Result<()> {
             .await?;
 
         // Read incoming RTCP packets
-        // Before these packets are returned they are processed by interceptors. For things
-        // like NACK this needs to be called.
+        // Before these packets are returned they are processed by interceptors,
+        // which see them as they arrive even if they are not read.
         let m = s.to_owned();
         tokio::spawn(async move {
-            let mut rtcp_buf = vec![0u8; 1500];
-            while let Ok((_, _)) = rtp_sender.read(&mut rtcp_buf).await {}
+            while let Ok((_, _)) = rtp_sender.read_rtcp().await {}
             println!("{m} rtp_sender.read loop exit");
             Result::<()>::Ok(())
         });
@@ -1,6 +1,7 @@
-use std::net::IpAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::time::Duration;
 
+use turn::client::AllocateRetryPolicy;
 use util::vnet::net::*;
 
 use super::*;
@@ -43,6 +44,9 @@ pub(crate) const MAX_BUFFER_SIZE: usize = 1000 * 1000; // 1MB
 /// Wait time before binding requests can be deleted.
 pub(crate) const MAX_BINDING_REQUEST_TIMEOUT: Duration = Duration::from_millis(4000);
 
+/// How long close_and_wait waits for the tasks of the agent to exit.
+pub(crate) const CLOSE_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
 pub(crate) fn default_candidate_types() -> Vec<CandidateType> {
     vec![
         CandidateType::Host,
@@ -51,8 +55,34 @@ pub(crate) fn default_candidate_types() -> Vec<CandidateType> {
     ]
 }
 
+/// NominationMode is how the controlling agent nominates the candidate pair to use.
+#[derive(PartialEq, Eq, Debug, Copy, Clone, Default)]
+pub enum NominationMode {
+    /// Checks all pairs without USE-CANDIDATE first, then sends one more check
+    /// with USE-CANDIDATE for the best valid pair (RFC 8445 Section 8.1.1).
+    #[default]
+    Regular,
+
+    /// Sets USE-CANDIDATE on every check, so the first pair that succeeds is
+    /// selected. This connects faster, but not necessarily on the best pair.
+    Aggressive,
+}
+
 pub type InterfaceFilterFn = Box<dyn (Fn(&str) -> bool) + Send + Sync>;
 pub type IpFilterFn = Box<dyn (Fn(IpAddr) -> bool) + Send + Sync>;
+pub type TransactionIdGeneratorFn = Box<dyn (Fn() -> TransactionId) + Send + Sync>;
+
+/// A host candidate offered instead of the gathered candidates, see
+/// [`AgentConfig::static_candidate`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaticCandidate {
+    /// The address the UDP socket of the candidate is bound to. With a UDP mux, whose socket
+    /// is already bound, only its IP is used, advertised along with the port of the mux.
+    pub local_addr: SocketAddr,
+    /// The address the candidate is advertised with, e.g. the public address of a load balancer
+    /// forwarding to `local_addr`. If unset, the address the socket is bound to is advertised.
+    pub advertised_addr: Option<SocketAddr>,
+}
 
 /// Collects the arguments to `ice::Agent` construction into a single structure, for
 /// future-proofness of the interface.
@@ -111,11 +141,23 @@ pub struct AgentConfig {
     /// request or a nomination we set the pair as failed.
     pub max_binding_requests: Option<u16>,
 
+    /// The max amount of candidate pairs checked at once, the others wait for one of them to
+    /// succeed or fail. The pairs are checked by decreasing priority. 0, the default, checks all
+    /// the pairs at once.
+    pub max_in_flight_checks: usize,
+
+    /// The interval between two connectivity checks, it paces the checks sent each check_interval
+    /// to smooth their burst. Zero, the default, sends them all at once.
+    pub check_pacing_interval: Duration,
+
     pub is_controlling: bool,
 
     /// lite agents do not perform connectivity check and only provide host candidates.
     pub lite: bool,
 
+    /// How the agent nominates the candidate pair when it is controlling.
+    pub nomination_mode: NominationMode,
+
     /// It is used along with nat1to1ips to specify which candidate type the 1:1 NAT IP addresses
     /// should be mapped to. If unspecified or CandidateTypeHost, nat1to1ips are used to replace
     /// host candidate IPs. If CandidateTypeServerReflexive, it will insert a srflx candidate (as
@@ -155,6 +197,51 @@ pub struct AgentConfig {
 
     /// Include loopback addresses in the candidate list.
     pub include_loopback: bool,
+
+    /// The DSCP codepoint packets sent from host and server reflexive candidates are marked
+    /// with, e.g. 46 for Expedited Forwarding. 0 leaves them unmarked. Only native IPv4
+    /// sockets are marked; sockets of a UDPMux are not created by the agent and have to be
+    /// marked by the application.
+    pub dscp: u8,
+
+    /// The value of the SOFTWARE attribute added to the connectivity checks, none is added
+    /// if unset.
+    pub stun_software: Option<String>,
+
+    /// Leaves the FINGERPRINT attribute out of the connectivity checks. RFC 8445 requires it,
+    /// so only disable it when the remote agent is known not to need it.
+    pub disable_stun_fingerprint: bool,
+
+    /// A function generating the transaction IDs of the connectivity checks, random IDs are
+    /// used if unset. The IDs have to stay unique, responses are matched to checks by them.
+    pub stun_transaction_id_generator: Arc<Option<TransactionIdGeneratorFn>>,
+
+    /// The order of the attributes in the connectivity checks. Listed attributes come first in
+    /// the given order, the others follow in their default order. MESSAGE-INTEGRITY and
+    /// FINGERPRINT are always last.
+    pub stun_attribute_order: Vec<AttrType>,
+
+    /// How the allocations of the relay candidates are retried when the TURN server times out
+    /// or answers with a 5xx error code, before the TURN server is given up on.
+    pub relay_allocate_retry: AllocateRetryPolicy,
+
+    /// Bounds the time candidate gathering takes. When it elapses the gathering completes with
+    /// the candidates gathered so far, and the candidates of the STUN and TURN servers that
+    /// didn't answer yet are dropped. If unset, the gathering waits for every server to answer
+    /// or time out.
+    pub gathering_timeout: Option<Duration>,
+
+    /// Replaces candidate gathering with a single host candidate on a fixed address, for
+    /// deployments where the address the agent is reachable at is known in advance. No other
+    /// host, server reflexive or relay candidate is gathered.
+    pub static_candidate: Option<StaticCandidate>,
+
+    /// Defers the gathering of relay candidates, sparing the TURN allocations when a direct path
+    /// works. The gathering completes without them, and only if the agent hasn't connected this
+    /// long after the connectivity checks started, it resumes to gather the relay candidates,
+    /// which then have to be trickled to the remote agent. If unset, relay candidates are
+    /// gathered along with the others.
+    pub relay_gathering_delay: Option<Duration>,
 }
 
 impl AgentConfig {
@@ -215,6 +302,19 @@ impl AgentConfig {
         }
     }
 
+    pub(crate) fn check_static_candidate(&self) -> Result<()> {
+        if let Some(static_candidate) = &self.static_candidate {
+            let advertised_addr = static_candidate
+                .advertised_addr
+                .unwrap_or(static_candidate.local_addr);
+            if advertised_addr.ip().is_unspecified() {
+                return Err(Error::ErrStaticCandidateUnspecifiedAddress);
+            }
+        }
+
+        Ok(())
+    }
+
     pub(crate) fn init_ext_ip_mapping(
         &self,
         mdns_mode: MulticastDnsMode,
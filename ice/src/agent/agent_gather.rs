@@ -1,4 +1,4 @@
-use std::net::{Ipv4Addr, Ipv6Addr};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
 
@@ -33,10 +33,21 @@ pub(crate) struct GatherCandidatesInternalParams {
     pub(crate) ext_ip_mapper: Arc<Option<ExternalIpMapper>>,
     pub(crate) agent_internal: Arc<AgentInternal>,
     pub(crate) gathering_state: Arc<AtomicU8>,
+    pub(crate) gathering_timeout: Option<Duration>,
+    pub(crate) static_candidate: Option<StaticCandidateConn>,
+    pub(crate) relay_gathering_delay: Option<Duration>,
     pub(crate) chan_candidate_tx: ChanCandidateTx,
     pub(crate) include_loopback: bool,
 }
 
+/// The connection of the static candidate, bound once and reused by the gatherings that follow
+/// an ICE restart.
+#[derive(Clone)]
+pub(crate) struct StaticCandidateConn {
+    pub(crate) conn: Arc<dyn Conn + Send + Sync>,
+    pub(crate) advertised_addr: SocketAddr,
+}
+
 struct GatherCandidatesLocalParams {
     udp_network: UDPNetwork,
     network_types: Vec<NetworkType>,
@@ -88,7 +99,29 @@ impl Agent {
         )
         .await;
 
+        if let Some(static_candidate) = params.static_candidate {
+            if let Err(err) =
+                Self::gather_candidate_static(static_candidate, &params.agent_internal).await
+            {
+                log::error!(
+                    "[{}]: Failed to gather static candidate: {}",
+                    params.agent_internal.get_name(),
+                    err
+                );
+            }
+
+            Self::set_gathering_state(
+                &params.chan_candidate_tx,
+                &params.gathering_state,
+                GatheringState::Complete,
+            )
+            .await;
+            return;
+        }
+
         let wg = WaitGroup::new();
+        // The routines may outlive the gathering timeout, the agent waits for them on close
+        let agent_worker = params.agent_internal.worker();
 
         for t in &params.candidate_types {
             match t {
@@ -106,7 +139,7 @@ impl Agent {
                         include_loopback: params.include_loopback,
                     };
 
-                    let w = wg.worker();
+                    let w = (wg.worker(), agent_worker.clone());
                     tokio::spawn(async move {
                         let _d = w;
 
@@ -128,7 +161,7 @@ impl Agent {
                         net: Arc::clone(&params.net),
                         agent_internal: Arc::clone(&params.agent_internal),
                     };
-                    let w1 = wg.worker();
+                    let w1 = (wg.worker(), agent_worker.clone());
                     tokio::spawn(async move {
                         let _d = w1;
 
@@ -144,7 +177,7 @@ impl Agent {
                                 net: Arc::clone(&params.net),
                                 agent_internal: Arc::clone(&params.agent_internal),
                             };
-                            let w2 = wg.worker();
+                            let w2 = (wg.worker(), agent_worker.clone());
                             tokio::spawn(async move {
                                 let _d = w2;
 
@@ -153,11 +186,12 @@ impl Agent {
                         }
                     }
                 }
+                CandidateType::Relay if params.relay_gathering_delay.is_some() => {}
                 CandidateType::Relay => {
                     let urls = params.urls.clone();
                     let net = Arc::clone(&params.net);
                     let agent_internal = Arc::clone(&params.agent_internal);
-                    let w = wg.worker();
+                    let w = (wg.worker(), agent_worker.clone());
                     tokio::spawn(async move {
                         let _d = w;
 
@@ -169,7 +203,104 @@ impl Agent {
         }
 
         // Block until all STUN and TURN URLs have been gathered (or timed out)
-        wg.wait().await;
+        if let Some(gathering_timeout) = params.gathering_timeout {
+            if tokio::time::timeout(gathering_timeout, wg.wait())
+                .await
+                .is_err()
+            {
+                log::warn!(
+                    "[{}]: Gathering timed out after {:?}, completing with the candidates gathered so far",
+                    params.agent_internal.get_name(),
+                    gathering_timeout
+                );
+            }
+        } else {
+            wg.wait().await;
+        }
+
+        Self::set_gathering_state(
+            &params.chan_candidate_tx,
+            &params.gathering_state,
+            GatheringState::Complete,
+        )
+        .await;
+
+        if let Some(delay) = params.relay_gathering_delay {
+            if params.candidate_types.contains(&CandidateType::Relay) {
+                let w = params.agent_internal.worker();
+                tokio::spawn(async move {
+                    let _d = w;
+
+                    Self::gather_candidates_relay_deferred(params, delay).await;
+                });
+            }
+        }
+    }
+
+    /// gather_candidates_relay_deferred waits for the connectivity checks to start, then gathers
+    /// the relay candidates if the agent hasn't connected within `delay`, resuming the gathering
+    /// that completed without them.
+    async fn gather_candidates_relay_deferred(
+        params: GatherCandidatesInternalParams,
+        delay: Duration,
+    ) {
+        let started_ch = {
+            let started_ch_tx = params.agent_internal.started_ch_tx.lock().await;
+            (*started_ch_tx).as_ref().map(|tx| tx.subscribe())
+        };
+        if let Some(mut started_ch) = started_ch {
+            // The sender is dropped once the checks started or the agent closed
+            let _ = started_ch.recv().await;
+        }
+
+        tokio::time::sleep(delay).await;
+
+        if params.agent_internal.done_tx.lock().await.is_none() {
+            return;
+        }
+        let connection_state: ConnectionState = params
+            .agent_internal
+            .connection_state
+            .load(Ordering::SeqCst)
+            .into();
+        match connection_state {
+            ConnectionState::Connected | ConnectionState::Completed => {
+                log::debug!(
+                    "[{}]: Connected without relay candidates, not gathering them",
+                    params.agent_internal.get_name()
+                );
+                return;
+            }
+            // The candidates were released, it is too late
+            ConnectionState::Failed | ConnectionState::Closed => return,
+            _ => {}
+        }
+
+        // The gathering may have been restarted meanwhile
+        if params
+            .gathering_state
+            .compare_exchange(
+                GatheringState::Complete as u8,
+                GatheringState::Gathering as u8,
+                Ordering::SeqCst,
+                Ordering::SeqCst,
+            )
+            .is_err()
+        {
+            return;
+        }
+
+        log::info!(
+            "[{}]: Not connected {:?} after the connectivity checks started, gathering relay candidates",
+            params.agent_internal.get_name(),
+            delay
+        );
+        Self::gather_candidates_relay(
+            params.urls.clone(),
+            Arc::clone(&params.net),
+            Arc::clone(&params.agent_internal),
+        )
+        .await;
 
         Self::set_gathering_state(
             &params.chan_candidate_tx,
@@ -196,6 +327,63 @@ impl Agent {
         gathering_state.store(new_state as u8, Ordering::SeqCst);
     }
 
+    /// bind_static_candidate binds the socket of the static candidate, or with a UDP mux gets
+    /// the connection of the mux for ufrag, and returns it along with the address to advertise.
+    pub(crate) async fn bind_static_candidate(
+        static_candidate: &StaticCandidate,
+        udp_network: &UDPNetwork,
+        net: &Arc<Net>,
+        ufrag: &str,
+    ) -> Result<StaticCandidateConn> {
+        let (conn, default_addr) = if let UDPNetwork::Muxed(udp_mux) = udp_network {
+            // The mux socket is already bound, the candidate takes its port
+            let conn = Arc::clone(udp_mux).get_conn(ufrag).await?;
+            let port = conn.local_addr()?.port();
+            (conn, SocketAddr::new(static_candidate.local_addr.ip(), port))
+        } else {
+            let conn: Arc<dyn Conn + Send + Sync> = net.bind(static_candidate.local_addr).await?;
+            let local_addr = conn.local_addr()?;
+            (conn, local_addr)
+        };
+
+        Ok(StaticCandidateConn {
+            conn,
+            advertised_addr: static_candidate.advertised_addr.unwrap_or(default_addr),
+        })
+    }
+
+    /// gather_candidate_static adds the static candidate, in place of all the other candidates.
+    async fn gather_candidate_static(
+        static_candidate: StaticCandidateConn,
+        agent_internal: &Arc<AgentInternal>,
+    ) -> Result<()> {
+        let StaticCandidateConn {
+            conn,
+            advertised_addr,
+        } = static_candidate;
+
+        let host_config = CandidateHostConfig {
+            base_config: CandidateBaseConfig {
+                network: UDP.to_owned(),
+                address: advertised_addr.ip().to_string(),
+                port: advertised_addr.port(),
+                component: COMPONENT_RTP,
+                conn: Some(conn),
+                ..CandidateBaseConfig::default()
+            },
+            ..CandidateHostConfig::default()
+        };
+
+        let candidate: Arc<dyn Candidate + Send + Sync> =
+            Arc::new(host_config.new_candidate_host()?);
+        if let Err(err) = agent_internal.add_candidate(&candidate).await {
+            let _ = candidate.close().await;
+            return Err(err);
+        }
+
+        Ok(())
+    }
+
     async fn gather_candidates_local(params: GatherCandidatesLocalParams) {
         let GatherCandidatesLocalParams {
             udp_network,
@@ -806,17 +994,12 @@ impl Agent {
                         return Ok(());
                     };
 
-                let cfg = turn::client::ClientConfig {
-                    stun_serv_addr: String::new(),
-                    turn_serv_addr: turn_server_addr.clone(),
-                    username: url.username,
-                    password: url.password,
-                    realm: String::new(),
-                    software: String::new(),
-                    rto_in_ms: 0,
-                    conn: loc_conn,
-                    vnet: Some(Arc::clone(&net2)),
-                };
+                let mut cfg = turn::client::ClientConfig::new(loc_conn);
+                cfg.turn_serv_addr = turn_server_addr.clone();
+                cfg.username = url.username;
+                cfg.password = url.password;
+                cfg.vnet = Some(Arc::clone(&net2));
+                cfg.allocate_retry = agent_internal2.relay_allocate_retry;
                 let client = match turn::client::Client::new(cfg).await {
                     Ok(client) => Arc::new(client),
                     Err(err) => {
@@ -2,7 +2,9 @@ use std::str::FromStr;
 
 use ipnet::IpNet;
 use tokio::net::UdpSocket;
+use turn::client::AllocateRetryPolicy;
 use util::vnet::*;
+use util::Conn;
 
 use super::agent_vnet_test::*;
 use super::*;
@@ -513,3 +515,327 @@ async fn test_vnet_gather_muxed_udp() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_gather_timeout() -> Result<()> {
+    // A STUN server that never answers
+    let stun_server = UdpSocket::bind("127.0.0.1:0").await?;
+    let stun_server_url = Url {
+        scheme: SchemeType::Stun,
+        host: "127.0.0.1".to_owned(),
+        port: stun_server.local_addr()?.port(),
+        proto: ProtoType::Udp,
+        ..Default::default()
+    };
+
+    let a = Agent::new(AgentConfig {
+        urls: vec![stun_server_url],
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::ServerReflexive],
+        gathering_timeout: Some(Duration::from_millis(200)),
+        ..Default::default()
+    })
+    .await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let done_tx_clone = Arc::clone(&done_tx);
+            Box::pin(async move {
+                if c.is_none() {
+                    let mut tx = done_tx_clone.lock().await;
+                    tx.take();
+                }
+            })
+        },
+    ));
+
+    a.gather_candidates()?;
+
+    // The STUN transaction takes 5 seconds to time out, the gathering completes before
+    tokio::time::timeout(Duration::from_secs(2), done_rx.recv())
+        .await
+        .expect("gathering didn't complete on its timeout");
+    assert!(
+        GatheringState::from(a.gathering_state.load(Ordering::SeqCst)) == GatheringState::Complete
+    );
+    assert!(a.get_local_candidates().await?.is_empty());
+
+    a.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gather_static_candidate() -> Result<()> {
+    let advertised_addr: SocketAddr = "203.0.113.1:3478".parse()?;
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        static_candidate: Some(StaticCandidate {
+            local_addr: "127.0.0.1:0".parse()?,
+            advertised_addr: Some(advertised_addr),
+        }),
+        ..Default::default()
+    })
+    .await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let done_tx_clone = Arc::clone(&done_tx);
+            Box::pin(async move {
+                if c.is_none() {
+                    let mut tx = done_tx_clone.lock().await;
+                    tx.take();
+                }
+            })
+        },
+    ));
+
+    a.gather_candidates()?;
+    let _ = done_rx.recv().await;
+
+    let candidates = a.get_local_candidates().await?;
+    assert_eq!(candidates.len(), 1, "only the static candidate is gathered");
+    assert_eq!(candidates[0].candidate_type(), CandidateType::Host);
+    assert_eq!(candidates[0].address(), advertised_addr.ip().to_string());
+    assert_eq!(candidates[0].port(), advertised_addr.port());
+
+    a.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_static_candidate_with_unspecified_address() -> Result<()> {
+    let result = Agent::new(AgentConfig {
+        static_candidate: Some(StaticCandidate {
+            local_addr: "0.0.0.0:0".parse()?,
+            advertised_addr: None,
+        }),
+        ..Default::default()
+    })
+    .await;
+    assert!(matches!(
+        result,
+        Err(Error::ErrStaticCandidateUnspecifiedAddress)
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_static_candidate_address_in_use() -> Result<()> {
+    let taken = UdpSocket::bind("127.0.0.1:0").await?;
+    let result = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        static_candidate: Some(StaticCandidate {
+            local_addr: taken.local_addr()?,
+            advertised_addr: None,
+        }),
+        ..Default::default()
+    })
+    .await;
+    assert!(result.is_err(), "the bind error is returned to the caller");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gather_static_candidate_with_udp_mux() -> Result<()> {
+    let udp_socket = UdpSocket::bind("127.0.0.1:0").await?;
+    let mux_addr = udp_socket.local_addr()?;
+    let udp_mux = UDPMuxDefault::new(UDPMuxParams::new(udp_socket));
+
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        udp_network: UDPNetwork::Muxed(udp_mux),
+        static_candidate: Some(StaticCandidate {
+            local_addr: "127.0.0.1:0".parse()?,
+            advertised_addr: None,
+        }),
+        ..Default::default()
+    })
+    .await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let done_tx_clone = Arc::clone(&done_tx);
+            Box::pin(async move {
+                if c.is_none() {
+                    let mut tx = done_tx_clone.lock().await;
+                    tx.take();
+                }
+            })
+        },
+    ));
+
+    a.gather_candidates()?;
+    let _ = done_rx.recv().await;
+
+    let candidates = a.get_local_candidates().await?;
+    assert_eq!(candidates.len(), 1, "only the static candidate is gathered");
+    assert_eq!(candidates[0].address(), "127.0.0.1");
+    assert_eq!(candidates[0].port(), mux_addr.port(), "the mux port is used");
+
+    a.close().await?;
+
+    Ok(())
+}
+
+/// build_deferred_relay_vnet connects two LANs and a fake TURN server, which
+/// only records the requests it receives, to a router. The third net, of the
+/// TURN server, has to be kept alive with the others.
+async fn build_deferred_relay_vnet() -> Result<(
+    Arc<Mutex<router::Router>>,
+    Vec<Arc<net::Net>>,
+    Arc<dyn Conn + Send + Sync>,
+)> {
+    let wan = Arc::new(Mutex::new(router::Router::new(router::RouterConfig {
+        cidr: "0.0.0.0/0".to_owned(),
+        ..Default::default()
+    })?));
+
+    let mut nets = vec![];
+    for ip in ["192.168.0.1", "192.168.0.2", "192.168.0.3"] {
+        let n = Arc::new(net::Net::new(Some(net::NetConfig {
+            static_ips: vec![ip.to_owned()],
+            ..Default::default()
+        })));
+        connect_net2router(&n, &wan).await?;
+        nets.push(n);
+    }
+    start_router(&wan).await?;
+
+    let turn_server = nets[2].bind("192.168.0.3:3478".parse()?).await?;
+
+    Ok((wan, nets, turn_server))
+}
+
+fn deferred_relay_config(net: Arc<net::Net>) -> AgentConfig {
+    AgentConfig {
+        urls: vec![Url {
+            scheme: SchemeType::Turn,
+            host: "192.168.0.3".to_owned(),
+            port: 3478,
+            username: "user".to_owned(),
+            password: "pass".to_owned(),
+            proto: ProtoType::Udp,
+        }],
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::Host, CandidateType::Relay],
+        multicast_dns_mode: MulticastDnsMode::Disabled,
+        relay_allocate_retry: AllocateRetryPolicy::none(),
+        relay_gathering_delay: Some(Duration::from_millis(200)),
+        net: Some(net),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_gather_relay_deferred_connected() -> Result<()> {
+    let (wan, nets, turn_server) = build_deferred_relay_vnet().await?;
+
+    let (a_notifier, mut a_connected) = on_connected();
+    let a_agent = Arc::new(Agent::new(deferred_relay_config(Arc::clone(&nets[0]))).await?);
+    a_agent.on_connection_state_change(a_notifier);
+
+    let b_agent = Arc::new(
+        Agent::new(AgentConfig {
+            network_types: vec![NetworkType::Udp4],
+            multicast_dns_mode: MulticastDnsMode::Disabled,
+            net: Some(Arc::clone(&nets[1])),
+            ..Default::default()
+        })
+        .await?,
+    );
+
+    // The gathering completes with the host candidates only
+    let (_a_conn, _b_conn) = connect_with_vnet(&a_agent, &b_agent).await?;
+    let _ = a_connected.recv().await;
+
+    // No TURN allocation is attempted past the delay
+    let mut buf = vec![0u8; 1500];
+    assert!(
+        tokio::time::timeout(Duration::from_millis(500), turn_server.recv_from(&mut buf))
+            .await
+            .is_err(),
+        "TURN server should not be contacted"
+    );
+    for c in a_agent.get_local_candidates().await? {
+        assert_eq!(c.candidate_type(), CandidateType::Host);
+    }
+
+    a_agent.close().await?;
+    b_agent.close().await?;
+    {
+        let mut w = wan.lock().await;
+        w.stop().await?;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_gather_relay_deferred_not_connected() -> Result<()> {
+    let (wan, nets, turn_server) = build_deferred_relay_vnet().await?;
+
+    let a_agent = Arc::new(Agent::new(deferred_relay_config(Arc::clone(&nets[0]))).await?);
+
+    let (complete_tx, mut complete_rx) = mpsc::channel::<()>(2);
+    a_agent.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let complete_tx = complete_tx.clone();
+            Box::pin(async move {
+                if c.is_none() {
+                    let _ = complete_tx.send(()).await;
+                }
+            })
+        },
+    ));
+    a_agent.gather_candidates()?;
+    let _ = complete_rx.recv().await;
+
+    // The TURN server isn't contacted until the connectivity checks start
+    let mut buf = vec![0u8; 1500];
+    assert!(
+        tokio::time::timeout(Duration::from_millis(400), turn_server.recv_from(&mut buf))
+            .await
+            .is_err(),
+        "TURN server should not be contacted before the checks start"
+    );
+
+    // Without any remote candidate the agent can't connect
+    let (_cancel_tx, cancel_rx) = mpsc::channel(1);
+    let a_agent2 = Arc::clone(&a_agent);
+    tokio::spawn(async move {
+        let _ = a_agent2
+            .dial(
+                cancel_rx,
+                "remote".to_owned(),
+                "remotepassword1234567890".to_owned(),
+            )
+            .await;
+    });
+
+    tokio::time::timeout(Duration::from_secs(2), turn_server.recv_from(&mut buf))
+        .await
+        .expect("TURN server should be contacted after the delay")?;
+
+    // The gathering completes again once the allocation failed
+    tokio::time::timeout(Duration::from_secs(10), complete_rx.recv())
+        .await
+        .expect("gathering should complete again");
+
+    a_agent.close().await?;
+    {
+        let mut w = wan.lock().await;
+        w.stop().await?;
+    }
+
+    Ok(())
+}
@@ -1,7 +1,10 @@
 use portable_atomic::{AtomicBool, AtomicU64};
 
 use arc_swap::ArcSwapOption;
+use stun::textattrs::TextAttribute;
+use turn::client::AllocateRetryPolicy;
 use util::sync::Mutex as SyncMutex;
+use waitgroup::{WaitGroup, Worker};
 
 use super::agent_transport::*;
 use super::*;
@@ -27,6 +30,8 @@ pub struct AgentInternal {
 
     // State for closing
     pub(crate) done_tx: Mutex<Option<mpsc::Sender<()>>>,
+    // Serializes close, a concurrent close returns once the sockets are released
+    pub(crate) close_lock: Mutex<()>,
     // force candidate to be contacted immediately (instead of waiting for task ticker)
     pub(crate) force_candidate_contact_tx: mpsc::Sender<bool>,
     pub(crate) done_and_force_candidate_contact_rx:
@@ -36,6 +41,11 @@ pub struct AgentInternal {
     pub(crate) chan_candidate_pair_tx: Mutex<Option<mpsc::Sender<()>>>,
     pub(crate) chan_state_tx: Mutex<Option<mpsc::Sender<ConnectionState>>>,
 
+    // Tasks spawned by the agent, Agent::close_and_wait waits for them to exit
+    pub(crate) wg: SyncMutex<Option<WaitGroup>>,
+    // The recv_loop of the candidates, close waits for them to exit
+    pub(crate) recv_loop_wg: SyncMutex<Option<WaitGroup>>,
+
     pub(crate) on_connection_state_change_hdlr: ArcSwapOption<Mutex<OnConnectionStateChangeHdlrFn>>,
     pub(crate) on_selected_candidate_pair_change_hdlr:
         ArcSwapOption<Mutex<OnSelectedCandidatePairChangeHdlrFn>>,
@@ -44,6 +54,7 @@ pub struct AgentInternal {
     pub(crate) tie_breaker: AtomicU64,
     pub(crate) is_controlling: AtomicBool,
     pub(crate) lite: AtomicBool,
+    pub(crate) nomination_mode: NominationMode,
 
     pub(crate) start_time: SyncMutex<Instant>,
     pub(crate) nominated_pair: Mutex<Option<Arc<CandidatePair>>>,
@@ -81,6 +92,25 @@ pub struct AgentInternal {
     pub(crate) keepalive_interval: Duration,
     // How often should we run our internal taskLoop to check for state changes when connecting
     pub(crate) check_interval: Duration,
+    // How many candidate pairs are checked at once, 0 means all of them
+    pub(crate) max_in_flight_checks: usize,
+    // The interval between two connectivity checks, 0 means they are sent at once
+    pub(crate) check_pacing_interval: Duration,
+    // When the next paced connectivity check can be sent
+    pub(crate) next_paced_check: SyncMutex<Instant>,
+    // The DSCP codepoint sent packets are marked with, 0 leaves them unmarked
+    pub(crate) dscp: u8,
+
+    // How the connectivity checks are built
+    pub(crate) stun_software: Option<String>,
+    pub(crate) disable_stun_fingerprint: bool,
+    pub(crate) stun_transaction_id_generator: Arc<Option<TransactionIdGeneratorFn>>,
+    pub(crate) stun_attribute_order: Vec<AttrType>,
+
+    pub(crate) relay_allocate_retry: AllocateRetryPolicy,
+
+    // Shared with the Agent, candidates gathered once the gathering completed are dropped
+    pub(crate) gathering_state: Arc<AtomicU8>, //GatheringState,
 }
 
 impl AgentInternal {
@@ -98,6 +128,7 @@ impl AgentInternal {
             on_connected_rx: Mutex::new(Some(on_connected_rx)),
 
             done_tx: Mutex::new(Some(done_tx)),
+            close_lock: Mutex::new(()),
             force_candidate_contact_tx,
             done_and_force_candidate_contact_rx: Mutex::new(Some((
                 done_rx,
@@ -108,6 +139,9 @@ impl AgentInternal {
             chan_candidate_pair_tx: Mutex::new(Some(chan_candidate_pair_tx)),
             chan_state_tx: Mutex::new(Some(chan_state_tx)),
 
+            wg: SyncMutex::new(Some(WaitGroup::new())),
+            recv_loop_wg: SyncMutex::new(Some(WaitGroup::new())),
+
             on_connection_state_change_hdlr: ArcSwapOption::empty(),
             on_selected_candidate_pair_change_hdlr: ArcSwapOption::empty(),
             on_candidate_hdlr: ArcSwapOption::empty(),
@@ -115,6 +149,7 @@ impl AgentInternal {
             tie_breaker: AtomicU64::new(rand::random::<u64>()),
             is_controlling: AtomicBool::new(config.is_controlling),
             lite: AtomicBool::new(config.lite),
+            nomination_mode: config.nomination_mode,
 
             start_time: SyncMutex::new(Instant::now()),
             nominated_pair: Mutex::new(None),
@@ -122,6 +157,16 @@ impl AgentInternal {
             connection_state: AtomicU8::new(ConnectionState::New as u8),
 
             insecure_skip_verify: config.insecure_skip_verify,
+            max_in_flight_checks: config.max_in_flight_checks,
+            check_pacing_interval: config.check_pacing_interval,
+            next_paced_check: SyncMutex::new(Instant::now()),
+            dscp: config.dscp,
+            stun_software: config.stun_software.clone(),
+            disable_stun_fingerprint: config.disable_stun_fingerprint,
+            stun_transaction_id_generator: Arc::clone(&config.stun_transaction_id_generator),
+            stun_attribute_order: config.stun_attribute_order.clone(),
+            relay_allocate_retry: config.relay_allocate_retry,
+            gathering_state: Arc::new(AtomicU8::new(GatheringState::New as u8)),
 
             started_ch_tx: Mutex::new(Some(started_ch_tx)),
 
@@ -205,7 +250,7 @@ impl AgentInternal {
     }
 
     async fn contact(
-        &self,
+        self: &Arc<Self>,
         last_connection_state: &mut ConnectionState,
         checking_duration: &mut Instant,
     ) {
@@ -259,7 +304,10 @@ impl AgentInternal {
             done_and_force_candidate_contact_rx
         {
             let ai = Arc::clone(self);
+            let w = self.worker();
             tokio::spawn(async move {
+                let _d = w;
+
                 loop {
                     let mut interval = DEFAULT_CHECK_INTERVAL;
 
@@ -338,6 +386,7 @@ impl AgentInternal {
         if let Some(p) = p {
             p.nominated.store(true, Ordering::SeqCst);
             self.agent_conn.selected_pair.store(Some(p));
+            self.close_previous_selected_pair().await;
 
             self.update_connection_state(ConnectionState::Connected)
                 .await;
@@ -360,7 +409,7 @@ impl AgentInternal {
         }
     }
 
-    pub(crate) async fn ping_all_candidates(&self) {
+    pub(crate) async fn ping_all_candidates(self: &Arc<Self>) {
         log::trace!("[{}]: pinging all candidates", self.get_name(),);
 
         let mut pairs: Vec<(
@@ -369,18 +418,31 @@ impl AgentInternal {
         )> = vec![];
 
         {
-            let mut checklist = self.agent_conn.checklist.lock().await;
+            let checklist = self.agent_conn.checklist.lock().await;
             if checklist.is_empty() {
                 log::warn!(
                     "[{}]: pingAllCandidates called with no candidate pairs. Connection is not possible yet.",
                     self.get_name(),
                 );
             }
-            for p in &mut *checklist {
+
+            // The best pairs are checked first, and are the ones in flight when they are limited
+            let mut sorted: Vec<&Arc<CandidatePair>> = checklist.iter().collect();
+            sorted.sort_by_key(|p| std::cmp::Reverse(p.priority()));
+            let mut in_flight = sorted
+                .iter()
+                .filter(|p| p.state.load(Ordering::SeqCst) == CandidatePairState::InProgress as u8)
+                .count();
+
+            for p in sorted {
                 let p_state = p.state.load(Ordering::SeqCst);
                 if p_state == CandidatePairState::Waiting as u8 {
+                    if self.max_in_flight_checks != 0 && in_flight >= self.max_in_flight_checks {
+                        continue;
+                    }
                     p.state
                         .store(CandidatePairState::InProgress as u8, Ordering::SeqCst);
+                    in_flight += 1;
                 } else if p_state != CandidatePairState::InProgress as u8 {
                     continue;
                 }
@@ -393,6 +455,7 @@ impl AgentInternal {
                     );
                     p.state
                         .store(CandidatePairState::Failed as u8, Ordering::SeqCst);
+                    in_flight -= 1;
                 } else {
                     p.binding_request_count.fetch_add(1, Ordering::SeqCst);
                     let local = p.local.clone();
@@ -402,8 +465,38 @@ impl AgentInternal {
             }
         }
 
+        if self.check_pacing_interval.is_zero() {
+            for (local, remote) in &pairs {
+                self.ping_candidate(local, remote).await;
+            }
+            return;
+        }
+
+        // Paced checks are sent from their own timers, so the tick isn't held up by them
+        // and the pacing holds across ticks
         for (local, remote) in pairs {
-            self.ping_candidate(&local, &remote).await;
+            let now = Instant::now();
+            let at = {
+                let mut next_paced_check = self.next_paced_check.lock();
+                let at = std::cmp::max(*next_paced_check, now);
+                *next_paced_check = at + self.check_pacing_interval;
+                at
+            };
+
+            if at <= now {
+                self.ping_candidate(&local, &remote).await;
+                continue;
+            }
+
+            let ai = Arc::clone(self);
+            tokio::spawn(async move {
+                tokio::time::sleep_until(at).await;
+                // The agent closed while the check was waiting for its turn
+                if ai.done_tx.lock().await.is_none() {
+                    return;
+                }
+                ai.ping_candidate(&local, &remote).await;
+            });
         }
     }
 
@@ -556,6 +649,22 @@ impl AgentInternal {
         self: &Arc<Self>,
         c: &Arc<dyn Candidate + Send + Sync>,
     ) -> Result<()> {
+        if self.gathering_state.load(Ordering::SeqCst) == GatheringState::Complete as u8 {
+            log::debug!(
+                "[{}]: Dropping candidate gathered after the gathering completed: {}",
+                self.get_name(),
+                c
+            );
+            if let Err(err) = c.close().await {
+                log::warn!(
+                    "[{}]: Failed to close late candidate: {}",
+                    self.get_name(),
+                    err
+                );
+            }
+            return Ok(());
+        }
+
         let initialized_ch = {
             let started_ch_tx = self.started_ch_tx.lock().await;
             (*started_ch_tx).as_ref().map(|tx| tx.subscribe())
@@ -566,6 +675,17 @@ impl AgentInternal {
         let network_type = c.network_type();
         {
             let mut local_candidates = self.local_candidates.lock().await;
+            if self.done_tx.lock().await.is_none() {
+                // The agent closed while this candidate was gathered, stop its recv_loop
+                if let Err(err) = c.close().await {
+                    log::warn!(
+                        "[{}]: Failed to close late candidate: {}",
+                        self.get_name(),
+                        err
+                    );
+                }
+                return Ok(());
+            }
             if let Some(cands) = local_candidates.get(&network_type) {
                 for cand in cands {
                     if cand.equal(&**c) {
@@ -612,15 +732,25 @@ impl AgentInternal {
         Ok(())
     }
 
+    /// Closes the agent, once it returns the sockets of the candidates are closed
+    /// and their recv_loop exited. Closing again releases the candidates left, if
+    /// any, then returns ErrClosed.
     pub(crate) async fn close(&self) -> Result<()> {
-        {
-            let mut done_tx = self.done_tx.lock().await;
-            if done_tx.is_none() {
-                return Err(Error::ErrClosed);
-            }
-            done_tx.take();
-        };
+        let _close_lock = self.close_lock.lock().await;
+
+        let already_closed = self.done_tx.lock().await.take().is_none();
+
+        // The candidates are released whatever the state of the agent, a failed
+        // agent deleted them already but still holds the candidate pairs
         self.delete_all_candidates().await;
+        self.agent_conn.checklist.lock().await.clear();
+        self.agent_conn.selected_pair.store(None);
+        self.nominated_pair.lock().await.take();
+
+        if already_closed {
+            return Err(Error::ErrClosed);
+        }
+
         {
             let mut started_ch_tx = self.started_ch_tx.lock().await;
             started_ch_tx.take();
@@ -645,6 +775,32 @@ impl AgentInternal {
 
         self.agent_conn.done.store(true, Ordering::SeqCst);
 
+        let recv_loop_wg = self.recv_loop_wg.lock().take();
+        if let Some(wg) = recv_loop_wg {
+            wg.wait().await;
+        }
+
+        Ok(())
+    }
+
+    /// worker returns a worker of the wait group of the agent's tasks, None once
+    /// wait_tasks was called.
+    pub(crate) fn worker(&self) -> Option<Worker> {
+        self.wg.lock().as_ref().map(|wg| wg.worker())
+    }
+
+    /// wait_tasks waits for the tasks spawned by the agent to exit, for at most
+    /// CLOSE_WAIT_TIMEOUT.
+    pub(crate) async fn wait_tasks(&self) -> Result<()> {
+        let wg = self.wg.lock().take();
+        if let Some(wg) = wg {
+            if tokio::time::timeout(CLOSE_WAIT_TIMEOUT, wg.wait())
+                .await
+                .is_err()
+            {
+                return Err(Error::ErrCloseWaitTimeout);
+            }
+        }
         Ok(())
     }
 
@@ -653,6 +809,8 @@ impl AgentInternal {
     ///
     /// This is used for restarts, failures and on close.
     pub(crate) async fn delete_all_candidates(&self) {
+        self.close_previous_selected_pair().await;
+
         {
             let mut local_candidates = self.local_candidates.lock().await;
             for cs in local_candidates.values_mut() {
@@ -688,6 +846,21 @@ impl AgentInternal {
         }
     }
 
+    /// Closes the local candidate of the pair selected before an ICE restart, once
+    /// the data doesn't need it anymore.
+    pub(crate) async fn close_previous_selected_pair(&self) {
+        if let Some(pair) = self.agent_conn.previous_selected_pair.swap(None) {
+            if let Err(err) = pair.local.close().await {
+                log::warn!(
+                    "[{}]: Failed to close candidate {}: {}",
+                    self.get_name(),
+                    pair.local,
+                    err
+                );
+            }
+        }
+    }
+
     pub(crate) async fn find_remote_candidate(
         &self,
         network_type: NetworkType,
@@ -706,6 +879,52 @@ impl AgentInternal {
         None
     }
 
+    /// Builds a connectivity check carrying attrs, signed with the password of the remote
+    /// agent and shaped as configured by the stun_* options of the AgentConfig.
+    pub(crate) fn build_binding_request(
+        &self,
+        attrs: Vec<Box<dyn Setter>>,
+        remote_pwd: String,
+    ) -> Result<Message> {
+        let transaction_id = match &*self.stun_transaction_id_generator {
+            Some(generate) => generate(),
+            None => TransactionId::new(),
+        };
+
+        let mut setters: Vec<Box<dyn Setter>> =
+            vec![Box::new(BINDING_REQUEST), Box::new(transaction_id)];
+        setters.extend(attrs);
+        if let Some(software) = &self.stun_software {
+            setters.push(Box::new(TextAttribute::new(
+                ATTR_SOFTWARE,
+                software.clone(),
+            )));
+        }
+
+        let mut msg = Message::new();
+        msg.build(&setters)?;
+
+        if !self.stun_attribute_order.is_empty() {
+            let position = |typ: &AttrType| {
+                self.stun_attribute_order
+                    .iter()
+                    .position(|t| t == typ)
+                    .unwrap_or(self.stun_attribute_order.len())
+            };
+            msg.attributes.0.sort_by_key(|attr| position(&attr.typ));
+            msg.encode();
+        }
+
+        // MESSAGE-INTEGRITY and FINGERPRINT cover the attributes before them, so they are
+        // added last
+        MessageIntegrity::new_short_term_integrity(remote_pwd).add_to(&mut msg)?;
+        if !self.disable_stun_fingerprint {
+            FINGERPRINT.add_to(&mut msg)?;
+        }
+
+        Ok(msg)
+    }
+
     pub(crate) async fn send_binding_request(
         &self,
         m: &Message,
@@ -982,12 +1201,20 @@ impl AgentInternal {
         local: &Arc<dyn Candidate + Send + Sync>,
         remote: SocketAddr,
     ) -> bool {
-        self.find_remote_candidate(local.network_type(), remote)
+        if let Some(remote_candidate) = self
+            .find_remote_candidate(local.network_type(), remote)
             .await
-            .map_or(false, |remote_candidate| {
-                remote_candidate.seen(false);
-                true
-            })
+        {
+            remote_candidate.seen(false);
+            return true;
+        }
+
+        // The data keeps flowing on the pair selected before an ICE restart
+        self.agent_conn
+            .previous_selected_pair
+            .load()
+            .as_ref()
+            .is_some_and(|pair| pair.local.equal(&**local) && pair.remote.addr() == remote)
     }
 
     /// Sets the credentials of the remote agent.
@@ -1039,9 +1266,18 @@ impl AgentInternal {
         let cand = Arc::clone(candidate);
         if let Some(conn) = candidate.get_conn() {
             let conn = Arc::clone(conn);
+            if self.dscp != 0 {
+                set_dscp(&conn, self.dscp);
+            }
             let addr = candidate.addr();
             let ai = Arc::clone(self);
+            let w = (
+                self.worker(),
+                self.recv_loop_wg.lock().as_ref().map(|wg| wg.worker()),
+            );
             tokio::spawn(async move {
+                let _d = w;
+
                 let _ = ai
                     .recv_loop(cand, closed_ch_rx, initialized_ch, conn, addr)
                     .await;
@@ -1057,6 +1293,8 @@ impl AgentInternal {
         mut chan_candidate_rx: mpsc::Receiver<Option<Arc<dyn Candidate + Send + Sync>>>,
         mut chan_candidate_pair_rx: mpsc::Receiver<()>,
     ) {
+        // The handlers run outside of the tasks waited on by close_and_wait, so that
+        // they can close the agent and wait for it themselves.
         let ai = Arc::clone(self);
         tokio::spawn(async move {
             // CandidatePair and ConnectionState are usually changed at once.
@@ -3,14 +3,12 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use stun::agent::*;
 use stun::attributes::*;
-use stun::fingerprint::*;
-use stun::integrity::*;
 use stun::message::*;
 use stun::textattrs::*;
 use tokio::time::{Duration, Instant};
 
+use crate::agent::agent_config::NominationMode;
 use crate::agent::agent_internal::*;
 use crate::candidate::*;
 use crate::control::*;
@@ -20,7 +18,7 @@ use crate::use_candidate::*;
 #[async_trait]
 trait ControllingSelector {
     async fn start(&self);
-    async fn contact_candidates(&self);
+    async fn contact_candidates(self: &Arc<Self>);
     async fn ping_candidate(
         &self,
         local: &Arc<dyn Candidate + Send + Sync>,
@@ -44,7 +42,7 @@ trait ControllingSelector {
 #[async_trait]
 trait ControlledSelector {
     async fn start(&self);
-    async fn contact_candidates(&self);
+    async fn contact_candidates(self: &Arc<Self>);
     async fn ping_candidate(
         &self,
         local: &Arc<dyn Candidate + Send + Sync>,
@@ -116,38 +114,36 @@ impl AgentInternal {
                 // agent MUST NOT include the USE-CANDIDATE attribute in a Binding
                 // request.
 
-                let (msg, result) = {
+                let result = {
                     let ufrag_pwd = self.ufrag_pwd.lock().await;
                     let username =
                         ufrag_pwd.remote_ufrag.clone() + ":" + ufrag_pwd.local_ufrag.as_str();
-                    let mut msg = Message::new();
-                    let result = msg.build(&[
-                        Box::new(BINDING_REQUEST),
-                        Box::new(TransactionId::new()),
-                        Box::new(Username::new(ATTR_USERNAME, username)),
-                        Box::<UseCandidateAttr>::default(),
-                        Box::new(AttrControlling(self.tie_breaker.load(Ordering::SeqCst))),
-                        Box::new(PriorityAttr(pair.local.priority())),
-                        Box::new(MessageIntegrity::new_short_term_integrity(
-                            ufrag_pwd.remote_pwd.clone(),
-                        )),
-                        Box::new(FINGERPRINT),
-                    ]);
-                    (msg, result)
+                    self.build_binding_request(
+                        vec![
+                            Box::new(Username::new(ATTR_USERNAME, username)),
+                            Box::<UseCandidateAttr>::default(),
+                            Box::new(AttrControlling(self.tie_breaker.load(Ordering::SeqCst))),
+                            Box::new(PriorityAttr(pair.local.priority())),
+                        ],
+                        ufrag_pwd.remote_pwd.clone(),
+                    )
                 };
 
-                if let Err(err) = result {
-                    log::error!("{}", err);
-                    None
-                } else {
-                    log::trace!(
-                        "ping STUN (nominate candidate pair from {} to {}",
-                        pair.local,
-                        pair.remote
-                    );
-                    let local = pair.local.clone();
-                    let remote = pair.remote.clone();
-                    Some((msg, local, remote))
+                match result {
+                    Err(err) => {
+                        log::error!("{}", err);
+                        None
+                    }
+                    Ok(msg) => {
+                        log::trace!(
+                            "ping STUN (nominate candidate pair from {} to {}",
+                            pair.local,
+                            pair.remote
+                        );
+                        let local = pair.local.clone();
+                        let remote = pair.remote.clone();
+                        Some((msg, local, remote))
+                    }
                 }
             } else {
                 None
@@ -167,7 +163,7 @@ impl AgentInternal {
         }
     }
 
-    pub(crate) async fn contact_candidates(&self) {
+    pub(crate) async fn contact_candidates(self: &Arc<Self>) {
         if self.is_controlling.load(Ordering::SeqCst) {
             ControllingSelector::contact_candidates(self).await;
         } else {
@@ -225,7 +221,7 @@ impl ControllingSelector for AgentInternal {
         *self.start_time.lock() = Instant::now();
     }
 
-    async fn contact_candidates(&self) {
+    async fn contact_candidates(self: &Arc<Self>) {
         // A lite selector should not contact candidates
         if self.lite.load(Ordering::SeqCst) {
             // This only happens if both peers are lite. See RFC 8445 S6.1.1 and S6.2
@@ -242,6 +238,9 @@ impl ControllingSelector for AgentInternal {
                 log::trace!("[{}]: checking keepalive", self.get_name());
                 self.check_keepalive().await;
             }
+        } else if self.nomination_mode == NominationMode::Aggressive {
+            // Every check nominates its pair, the first one to succeed is selected
+            self.ping_all_candidates().await;
         } else if nominated_pair_is_some {
             self.nominate_pair().await;
         } else {
@@ -278,28 +277,23 @@ impl ControllingSelector for AgentInternal {
         local: &Arc<dyn Candidate + Send + Sync>,
         remote: &Arc<dyn Candidate + Send + Sync>,
     ) {
-        let (msg, result) = {
+        let result = {
             let ufrag_pwd = self.ufrag_pwd.lock().await;
             let username = ufrag_pwd.remote_ufrag.clone() + ":" + ufrag_pwd.local_ufrag.as_str();
-            let mut msg = Message::new();
-            let result = msg.build(&[
-                Box::new(BINDING_REQUEST),
-                Box::new(TransactionId::new()),
+            let mut attrs: Vec<Box<dyn Setter>> = vec![
                 Box::new(Username::new(ATTR_USERNAME, username)),
                 Box::new(AttrControlling(self.tie_breaker.load(Ordering::SeqCst))),
                 Box::new(PriorityAttr(local.priority())),
-                Box::new(MessageIntegrity::new_short_term_integrity(
-                    ufrag_pwd.remote_pwd.clone(),
-                )),
-                Box::new(FINGERPRINT),
-            ]);
-            (msg, result)
+            ];
+            if self.nomination_mode == NominationMode::Aggressive {
+                attrs.insert(1, Box::<UseCandidateAttr>::default());
+            }
+            self.build_binding_request(attrs, ufrag_pwd.remote_pwd.clone())
         };
 
-        if let Err(err) = result {
-            log::error!("{}", err);
-        } else {
-            self.send_binding_request(&msg, local, remote).await;
+        match result {
+            Err(err) => log::error!("{}", err),
+            Ok(msg) => self.send_binding_request(&msg, local, remote).await,
         }
     }
 
@@ -375,7 +369,8 @@ impl ControllingSelector for AgentInternal {
                 nominated_pair_is_none,
                 //self.agent_conn.get_selected_pair().await.is_none() //, {}
             );
-            if p.state.load(Ordering::SeqCst) == CandidatePairState::Succeeded as u8
+            if self.nomination_mode == NominationMode::Regular
+                && p.state.load(Ordering::SeqCst) == CandidatePairState::Succeeded as u8
                 && nominated_pair_is_none
                 && self.agent_conn.get_selected_pair().is_none()
             {
@@ -411,7 +406,7 @@ impl ControllingSelector for AgentInternal {
 impl ControlledSelector for AgentInternal {
     async fn start(&self) {}
 
-    async fn contact_candidates(&self) {
+    async fn contact_candidates(self: &Arc<Self>) {
         // A lite selector should not contact candidates
         if self.lite.load(Ordering::SeqCst) {
             self.validate_selected_pair().await;
@@ -430,28 +425,22 @@ impl ControlledSelector for AgentInternal {
         local: &Arc<dyn Candidate + Send + Sync>,
         remote: &Arc<dyn Candidate + Send + Sync>,
     ) {
-        let (msg, result) = {
+        let result = {
             let ufrag_pwd = self.ufrag_pwd.lock().await;
             let username = ufrag_pwd.remote_ufrag.clone() + ":" + ufrag_pwd.local_ufrag.as_str();
-            let mut msg = Message::new();
-            let result = msg.build(&[
-                Box::new(BINDING_REQUEST),
-                Box::new(TransactionId::new()),
-                Box::new(Username::new(ATTR_USERNAME, username)),
-                Box::new(AttrControlled(self.tie_breaker.load(Ordering::SeqCst))),
-                Box::new(PriorityAttr(local.priority())),
-                Box::new(MessageIntegrity::new_short_term_integrity(
-                    ufrag_pwd.remote_pwd.clone(),
-                )),
-                Box::new(FINGERPRINT),
-            ]);
-            (msg, result)
+            self.build_binding_request(
+                vec![
+                    Box::new(Username::new(ATTR_USERNAME, username)),
+                    Box::new(AttrControlled(self.tie_breaker.load(Ordering::SeqCst))),
+                    Box::new(PriorityAttr(local.priority())),
+                ],
+                ufrag_pwd.remote_pwd.clone(),
+            )
         };
 
-        if let Err(err) = result {
-            log::error!("{}", err);
-        } else {
-            self.send_binding_request(&msg, local, remote).await;
+        match result {
+            Err(err) => log::error!("{}", err),
+            Ok(msg) => self.send_binding_request(&msg, local, remote).await,
         }
     }
 
@@ -1,10 +1,11 @@
 use std::net::Ipv4Addr;
 use std::ops::Sub;
 use std::str::FromStr;
+use std::sync::atomic::AtomicU64;
 
 use async_trait::async_trait;
 use stun::message::*;
-use stun::textattrs::Username;
+use stun::textattrs::{TextAttribute, Username};
 use util::vnet::*;
 use util::Conn;
 use waitgroup::{WaitGroup, Worker};
@@ -165,6 +166,163 @@ async fn test_pair_priority() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_max_in_flight_checks() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        max_in_flight_checks: 2,
+        ..Default::default()
+    })
+    .await?;
+
+    let host_local: Arc<dyn Candidate + Send + Sync> = Arc::new(
+        CandidateHostConfig {
+            base_config: CandidateBaseConfig {
+                network: "udp".to_owned(),
+                address: "192.168.1.1".to_owned(),
+                port: 19216,
+                component: 1,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .new_candidate_host()?,
+    );
+    let remotes: Vec<Arc<dyn Candidate + Send + Sync>> = vec![
+        Arc::new(
+            CandidateRelayConfig {
+                base_config: CandidateBaseConfig {
+                    network: "udp".to_owned(),
+                    address: "1.2.3.4".to_owned(),
+                    port: 12340,
+                    component: 1,
+                    ..Default::default()
+                },
+                rel_addr: "4.3.2.1".to_owned(),
+                rel_port: 43210,
+                ..Default::default()
+            }
+            .new_candidate_relay()?,
+        ),
+        Arc::new(
+            CandidateServerReflexiveConfig {
+                base_config: CandidateBaseConfig {
+                    network: "udp".to_owned(),
+                    address: "10.10.10.2".to_owned(),
+                    port: 19218,
+                    component: 1,
+                    ..Default::default()
+                },
+                rel_addr: "4.3.2.1".to_owned(),
+                rel_port: 43212,
+            }
+            .new_candidate_server_reflexive()?,
+        ),
+        Arc::new(
+            CandidateHostConfig {
+                base_config: CandidateBaseConfig {
+                    network: "udp".to_owned(),
+                    address: "1.2.3.5".to_owned(),
+                    port: 12350,
+                    component: 1,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+            .new_candidate_host()?,
+        ),
+    ];
+    // the lowest priority pair is added first
+    for remote in &remotes {
+        a.internal
+            .add_pair(Arc::clone(&host_local), Arc::clone(remote))
+            .await;
+    }
+
+    let states = || async {
+        let checklist = a.internal.agent_conn.checklist.lock().await;
+        let mut pairs: Vec<(u64, u8)> = checklist
+            .iter()
+            .map(|p| (p.priority(), p.state.load(Ordering::SeqCst)))
+            .collect();
+        pairs.sort_by_key(|(priority, _)| std::cmp::Reverse(*priority));
+        pairs.into_iter().map(|(_, s)| s).collect::<Vec<u8>>()
+    };
+
+    // only the two best pairs are checked
+    a.internal.ping_all_candidates().await;
+    assert_eq!(
+        states().await,
+        vec![
+            CandidatePairState::InProgress as u8,
+            CandidatePairState::InProgress as u8,
+            CandidatePairState::Waiting as u8,
+        ]
+    );
+
+    // the last one is checked once one of them is done
+    {
+        let checklist = a.internal.agent_conn.checklist.lock().await;
+        let best = checklist.iter().max_by_key(|p| p.priority()).unwrap();
+        best.state
+            .store(CandidatePairState::Succeeded as u8, Ordering::SeqCst);
+    }
+    a.internal.ping_all_candidates().await;
+    assert_eq!(
+        states().await,
+        vec![
+            CandidatePairState::Succeeded as u8,
+            CandidatePairState::InProgress as u8,
+            CandidatePairState::InProgress as u8,
+        ]
+    );
+
+    a.close().await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_check_pacing_does_not_hold_the_tick() -> Result<()> {
+    let pacing = Duration::from_secs(10);
+    let a = Agent::new(AgentConfig {
+        check_pacing_interval: pacing,
+        ..Default::default()
+    })
+    .await?;
+
+    let host = |address: &str, port: u16| -> Result<Arc<dyn Candidate + Send + Sync>> {
+        Ok(Arc::new(
+            CandidateHostConfig {
+                base_config: CandidateBaseConfig {
+                    network: "udp".to_owned(),
+                    address: address.to_owned(),
+                    port,
+                    component: 1,
+                    ..Default::default()
+                },
+                ..Default::default()
+            }
+            .new_candidate_host()?,
+        ))
+    };
+    let local = host("192.168.1.1", 19216)?;
+    for (address, port) in [("1.2.3.4", 12340), ("1.2.3.5", 12350), ("1.2.3.6", 12360)] {
+        a.internal
+            .add_pair(Arc::clone(&local), host(address, port)?)
+            .await;
+    }
+
+    // the first check is sent right away, the other two are scheduled after it
+    let start = Instant::now();
+    tokio::time::timeout(Duration::from_secs(1), a.internal.ping_all_candidates())
+        .await
+        .expect("paced checks are sent from their own timers");
+    let next_paced_check = *a.internal.next_paced_check.lock();
+    assert!(next_paced_check >= start + pacing * 3);
+
+    a.close().await?;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_agent_get_stats() -> Result<()> {
     let (conn_a, conn_b, agent_a, agent_b) = pipe(None, None).await?;
@@ -186,6 +344,127 @@ async fn test_agent_get_stats() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_aggressive_nomination() -> Result<()> {
+    // Regular nomination would wait an hour before nominating a host pair,
+    // aggressive nomination selects the first pair that succeeds.
+    let (_, _, agent_a, agent_b) = pipe(
+        None,
+        Some(AgentConfig {
+            nomination_mode: NominationMode::Aggressive,
+            host_acceptance_min_wait: Some(Duration::from_secs(3600)),
+            ..Default::default()
+        }),
+    )
+    .await?;
+
+    let pair_a = agent_a
+        .get_selected_candidate_pair()
+        .expect("controlled agent has a selected pair");
+    let pair_b = agent_b
+        .get_selected_candidate_pair()
+        .expect("controlling agent has a selected pair");
+    assert_eq!(pair_a.local.address(), pair_b.remote.address());
+    assert_eq!(pair_a.local.port(), pair_b.remote.port());
+    assert_eq!(pair_a.remote.address(), pair_b.local.address());
+    assert_eq!(pair_a.remote.port(), pair_b.local.port());
+
+    agent_a.close().await?;
+    agent_b.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stun_binding_request_shape() -> Result<()> {
+    let attrs = || -> Vec<Box<dyn Setter>> {
+        vec![
+            Box::new(Username::new(ATTR_USERNAME, "remote:local".to_owned())),
+            Box::new(AttrControlling(4321)),
+            Box::new(PriorityAttr(1234)),
+        ]
+    };
+    let types = |m: &Message| m.attributes.0.iter().map(|a| a.typ).collect::<Vec<_>>();
+
+    let a = Agent::new(AgentConfig::default()).await?;
+    let m = a
+        .internal
+        .build_binding_request(attrs(), "pwd".to_owned())?;
+    assert_eq!(
+        types(&m),
+        vec![
+            ATTR_USERNAME,
+            ATTR_ICE_CONTROLLING,
+            ATTR_PRIORITY,
+            ATTR_MESSAGE_INTEGRITY,
+            ATTR_FINGERPRINT
+        ]
+    );
+    a.close().await?;
+
+    let a = Agent::new(AgentConfig {
+        stun_software: Some("client".to_owned()),
+        disable_stun_fingerprint: true,
+        stun_transaction_id_generator: Arc::new(Some(Box::new(|| TransactionId([7; 12])))),
+        stun_attribute_order: vec![ATTR_SOFTWARE, ATTR_PRIORITY],
+        ..Default::default()
+    })
+    .await?;
+    let mut m = a
+        .internal
+        .build_binding_request(attrs(), "pwd".to_owned())?;
+    assert_eq!(m.transaction_id, TransactionId([7; 12]));
+    assert_eq!(
+        types(&m),
+        vec![
+            ATTR_SOFTWARE,
+            ATTR_PRIORITY,
+            ATTR_USERNAME,
+            ATTR_ICE_CONTROLLING,
+            ATTR_MESSAGE_INTEGRITY
+        ]
+    );
+    assert_eq!(
+        TextAttribute::get_from_as(&m, ATTR_SOFTWARE)?.text,
+        "client"
+    );
+
+    // the reordered message still decodes and authenticates
+    let mut decoded = Message::new();
+    decoded.write(&m.raw)?;
+    MessageIntegrity::new_short_term_integrity("pwd".to_owned()).check(&mut decoded)?;
+    MessageIntegrity::new_short_term_integrity("pwd".to_owned()).check(&mut m)?;
+    a.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stun_binding_request_config_connectivity() -> Result<()> {
+    let counter = Arc::new(AtomicU64::new(0));
+    let transaction_id = move || {
+        let mut id = [0u8; 12];
+        id[4..].copy_from_slice(&counter.fetch_add(1, Ordering::SeqCst).to_be_bytes());
+        TransactionId(id)
+    };
+    let config = || AgentConfig {
+        stun_software: Some("client".to_owned()),
+        disable_stun_fingerprint: true,
+        stun_transaction_id_generator: Arc::new(Some(Box::new(transaction_id.clone()))),
+        stun_attribute_order: vec![ATTR_PRIORITY, ATTR_SOFTWARE],
+        ..Default::default()
+    };
+
+    let (_, _, agent_a, agent_b) = pipe(Some(config()), Some(config())).await?;
+    assert!(agent_a.get_selected_candidate_pair().is_some());
+    assert!(agent_b.get_selected_candidate_pair().is_some());
+
+    agent_a.close().await?;
+    agent_b.close().await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_on_selected_candidate_pair_change() -> Result<()> {
     let a = Agent::new(AgentConfig::default()).await?;
@@ -1928,6 +2207,72 @@ async fn test_agent_restart_both_side() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_agent_restart_keeps_conn() -> Result<()> {
+    let (a_conn, b_conn, agent_a, agent_b) = pipe(None, None).await?;
+
+    let (pair_changed_tx, mut pair_changed_rx) = mpsc::channel::<()>(1);
+    agent_a.on_selected_candidate_pair_change(Box::new(
+        move |_: &Arc<dyn Candidate + Send + Sync>, _: &Arc<dyn Candidate + Send + Sync>| {
+            let pair_changed_tx = pair_changed_tx.clone();
+            Box::pin(async move {
+                let _ = pair_changed_tx.try_send(());
+            })
+        },
+    ));
+
+    let (a_notifier, mut a_connected) = on_connected();
+    agent_a.on_connection_state_change(a_notifier);
+    let (b_notifier, mut b_connected) = on_connected();
+    agent_b.on_connection_state_change(b_notifier);
+
+    agent_a.restart("".to_owned(), "".to_owned()).await?;
+    agent_b.restart("".to_owned(), "".to_owned()).await?;
+    assert!(agent_a.get_selected_candidate_pair().is_none());
+
+    // The data keeps flowing on the previously selected pair during the restart
+    let mut buf = vec![0u8; 16];
+    a_conn.send(b"restarting").await?;
+    let n = tokio::time::timeout(Duration::from_secs(5), b_conn.recv(&mut buf))
+        .await
+        .expect("data isn't received during the restart")?;
+    assert_eq!(&buf[..n], b"restarting");
+    b_conn.send(b"restarting").await?;
+    let n = tokio::time::timeout(Duration::from_secs(5), a_conn.recv(&mut buf))
+        .await
+        .expect("data isn't received during the restart")?;
+    assert_eq!(&buf[..n], b"restarting");
+
+    let (ufrag, pwd) = agent_b.get_local_user_credentials().await;
+    agent_a.set_remote_credentials(ufrag, pwd).await?;
+    let (ufrag, pwd) = agent_a.get_local_user_credentials().await;
+    agent_b.set_remote_credentials(ufrag, pwd).await?;
+    gather_and_exchange_candidates(&agent_a, &agent_b).await?;
+
+    let _ = a_connected.recv().await;
+    let _ = b_connected.recv().await;
+    let _ = pair_changed_rx.recv().await;
+    assert!(agent_a.get_selected_candidate_pair().is_some());
+
+    // And on the new pair once it is selected
+    a_conn.send(b"restarted").await?;
+    let n = tokio::time::timeout(Duration::from_secs(5), b_conn.recv(&mut buf))
+        .await
+        .expect("data isn't received after the restart")?;
+    assert_eq!(&buf[..n], b"restarted");
+    assert!(agent_a
+        .internal
+        .agent_conn
+        .previous_selected_pair
+        .load()
+        .is_none());
+
+    agent_a.close().await?;
+    agent_b.close().await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_get_remote_credentials() -> Result<()> {
     let a = Agent::new(AgentConfig::default()).await?;
@@ -2201,3 +2546,50 @@ async fn test_lite_lifecycle() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_agent_close_releases_sockets() -> Result<()> {
+    let a = Agent::new(AgentConfig {
+        network_types: vec![NetworkType::Udp4],
+        candidate_types: vec![CandidateType::Host],
+        include_loopback: true,
+        ..Default::default()
+    })
+    .await?;
+
+    let (gathered_tx, mut gathered_rx) = mpsc::channel::<()>(1);
+    let gathered_tx = Arc::new(Mutex::new(Some(gathered_tx)));
+    a.on_candidate(Box::new(
+        move |c: Option<Arc<dyn Candidate + Send + Sync>>| {
+            let gathered_tx = Arc::clone(&gathered_tx);
+            Box::pin(async move {
+                if c.is_none() {
+                    gathered_tx.lock().await.take();
+                }
+            })
+        },
+    ));
+    a.gather_candidates()?;
+    let _ = gathered_rx.recv().await;
+
+    let addrs: Vec<SocketAddr> = a
+        .get_local_candidates()
+        .await?
+        .iter()
+        .map(|c| c.addr())
+        .collect();
+    assert!(!addrs.is_empty(), "expected gathered candidates");
+
+    a.close().await?;
+
+    // The ports are free again as soon as close returns
+    for addr in addrs {
+        let rebound = tokio::net::UdpSocket::bind(addr).await;
+        assert!(rebound.is_ok(), "{addr} wasn't released: {rebound:?}");
+    }
+
+    // Closing again doesn't panic nor hang, it reports the agent as closed
+    assert_eq!(a.close().await, Err(Error::ErrClosed));
+
+    Ok(())
+}
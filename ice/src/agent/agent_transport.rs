@@ -84,6 +84,9 @@ impl Agent {
 
 pub(crate) struct AgentConn {
     pub(crate) selected_pair: ArcSwapOption<CandidatePair>,
+    /// previous_selected_pair is the pair selected before an ICE restart, it keeps
+    /// carrying the data until a new pair is selected.
+    pub(crate) previous_selected_pair: ArcSwapOption<CandidatePair>,
     pub(crate) checklist: Mutex<Vec<Arc<CandidatePair>>>,
 
     pub(crate) buffer: Buffer,
@@ -96,6 +99,7 @@ impl AgentConn {
     pub(crate) fn new() -> Self {
         Self {
             selected_pair: ArcSwapOption::empty(),
+            previous_selected_pair: ArcSwapOption::empty(),
             checklist: Mutex::new(vec![]),
             // Make sure the buffer doesn't grow indefinitely.
             // NOTE: We actually won't get anywhere close to this limit.
@@ -206,6 +210,8 @@ impl Conn for AgentConn {
 
         let result = if let Some(pair) = self.get_selected_pair() {
             pair.write(buf).await
+        } else if let Some(pair) = self.previous_selected_pair.load_full() {
+            pair.write(buf).await
         } else if let Some(pair) = self.get_best_available_candidate_pair().await {
             pair.write(buf).await
         } else {
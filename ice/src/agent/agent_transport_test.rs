@@ -123,3 +123,26 @@ async fn test_conn_stats() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_agent_close_and_wait() -> Result<()> {
+    let (ca, _cb, a_agent, b_agent) = pipe(None, None).await?;
+    let sockets: Vec<std::sync::Weak<dyn Conn + Send + Sync>> = a_agent
+        .get_local_candidates()
+        .await?
+        .iter()
+        .filter_map(|c| c.get_conn().map(Arc::downgrade))
+        .collect();
+    assert!(!sockets.is_empty(), "expected gathered sockets");
+
+    a_agent.close_and_wait().await?;
+    assert!(
+        sockets.iter().all(|s| s.upgrade().is_none()),
+        "sockets should be released once close_and_wait returns"
+    );
+    assert!(ca.send(&[0u8; 10]).await.is_err());
+
+    b_agent.close_and_wait().await?;
+
+    Ok(())
+}
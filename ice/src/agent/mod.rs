@@ -35,10 +35,11 @@ use stun::message::*;
 use stun::xoraddr::*;
 use tokio::sync::{broadcast, mpsc, Mutex};
 use tokio::time::{Duration, Instant};
+use util::sync::Mutex as SyncMutex;
 use util::vnet::net::*;
 use util::Buffer;
 
-use crate::agent::agent_gather::GatherCandidatesInternalParams;
+use crate::agent::agent_gather::{GatherCandidatesInternalParams, StaticCandidateConn};
 use crate::candidate::*;
 use crate::error::*;
 use crate::external_ip_mapper::*;
@@ -114,6 +115,10 @@ pub struct Agent {
     // 1:1 D-NAT IP address mapping
     pub(crate) ext_ip_mapper: Arc<Option<ExternalIpMapper>>,
     pub(crate) gathering_state: Arc<AtomicU8>, //GatheringState,
+    pub(crate) gathering_timeout: Option<Duration>,
+    pub(crate) static_candidate: Option<StaticCandidate>,
+    pub(crate) static_candidate_conn: SyncMutex<Option<StaticCandidateConn>>,
+    pub(crate) relay_gathering_delay: Option<Duration>,
     pub(crate) candidate_types: Vec<CandidateType>,
     pub(crate) urls: Vec<Url>,
     pub(crate) network_types: Vec<NetworkType>,
@@ -176,6 +181,11 @@ impl Agent {
             return Err(Error::ErrUselessUrlsProvided);
         }
 
+        if let Err(err) = config.check_static_candidate() {
+            Self::close_multicast_conn(&mdns_conn).await;
+            return Err(err);
+        }
+
         let ext_ip_mapper = match config.init_ext_ip_mapping(mdns_mode, &candidate_types) {
             Ok(ext_ip_mapper) => ext_ip_mapper,
             Err(err) => {
@@ -197,6 +207,7 @@ impl Agent {
             Arc::new(Net::new(None))
         };
 
+        let gathering_state = Arc::clone(&ai.gathering_state);
         let agent = Self {
             udp_network: config.udp_network,
             internal: Arc::new(ai),
@@ -208,7 +219,11 @@ impl Agent {
             mdns_conn,
             net,
             ext_ip_mapper: Arc::new(ext_ip_mapper),
-            gathering_state: Arc::new(AtomicU8::new(0)), //GatheringState::New,
+            gathering_state,
+            gathering_timeout: config.gathering_timeout,
+            static_candidate: config.static_candidate,
+            static_candidate_conn: SyncMutex::new(None),
+            relay_gathering_delay: config.relay_gathering_delay,
             candidate_types,
             urls: config.urls.clone(),
             network_types: config.network_types.clone(),
@@ -290,7 +305,10 @@ impl Agent {
             let ai = Arc::clone(&self.internal);
             let host_candidate = Arc::clone(c);
             let mdns_conn = self.mdns_conn.clone();
+            let w = self.internal.worker();
             tokio::spawn(async move {
+                let _d = w;
+
                 if let Some(mdns_conn) = mdns_conn {
                     if let Ok(candidate) =
                         Self::resolve_and_add_multicast_candidate(mdns_conn, host_candidate).await
@@ -302,7 +320,10 @@ impl Agent {
         } else {
             let ai = Arc::clone(&self.internal);
             let candidate = Arc::clone(c);
+            let w = self.internal.worker();
             tokio::spawn(async move {
+                let _d = w;
+
                 ai.add_remote_candidate(&candidate).await;
             });
         }
@@ -355,6 +376,20 @@ impl Agent {
         self.internal.close().await
     }
 
+    /// Cleans up the Agent like close, then waits for all the tasks it spawned to exit.
+    /// Once it returns the sockets of the local candidates are released, unless the
+    /// application still holds on to the candidates.
+    ///
+    /// The event handlers don't run in the waited tasks, they can call close_and_wait.
+    /// The wait is bounded, if the tasks (e.g. a gathering stuck on an unresponsive
+    /// STUN or TURN server) are still running after 5 seconds it returns
+    /// Error::ErrCloseWaitTimeout, and the sockets are released once they exit.
+    pub async fn close_and_wait(&self) -> Result<()> {
+        let result = self.close().await;
+        let waited = self.internal.wait_tasks().await;
+        result.and(waited)
+    }
+
     /// Returns the selected pair or nil if there is none
     pub fn get_selected_candidate_pair(&self) -> Option<Arc<CandidatePair>> {
         self.internal.agent_conn.get_selected_pair()
@@ -376,6 +411,9 @@ impl Agent {
     ///
     /// Restart must only be called when `GatheringState` is `GatheringStateComplete`
     /// a user must then call `GatherCandidates` explicitly to start generating new ones.
+    ///
+    /// The data keeps being sent and received on the selected candidate pair until a new
+    /// pair is selected after the restart, so the conn of the agent isn't interrupted.
     pub async fn restart(&self, mut ufrag: String, mut pwd: String) -> Result<()> {
         if ufrag.is_empty() {
             ufrag = generate_ufrag();
@@ -406,6 +444,27 @@ impl Agent {
             }
         }
 
+        if let Some(static_candidate) = &self.static_candidate {
+            // The socket is bound once, the connection of a UDP mux is looked up by ufrag
+            let static_candidate_conn = match &self.udp_network {
+                UDPNetwork::Muxed(_) => None,
+                _ => self.static_candidate_conn.lock().clone(),
+            };
+            let static_candidate_conn = match static_candidate_conn {
+                Some(static_candidate_conn) => static_candidate_conn,
+                None => {
+                    Self::bind_static_candidate(
+                        static_candidate,
+                        &self.udp_network,
+                        &self.net,
+                        &ufrag,
+                    )
+                    .await?
+                }
+            };
+            *self.static_candidate_conn.lock() = Some(static_candidate_conn);
+        }
+
         // Clear all agent needed to take back to fresh state
         {
             let mut ufrag_pwd = self.internal.ufrag_pwd.lock().await;
@@ -424,8 +483,28 @@ impl Agent {
             *checklist = vec![];
         }
 
-        self.internal.set_selected_pair(None).await;
+        // The selected pair keeps carrying the data until the restarted agent selects
+        // a new one, so its local candidate is kept open
+        let previous_selected_pair =
+            self.internal
+                .agent_conn
+                .selected_pair
+                .swap(None)
+                .filter(|_| {
+                    self.internal.connection_state.load(Ordering::SeqCst)
+                        != ConnectionState::Failed as u8
+                });
+        if let Some(pair) = &previous_selected_pair {
+            let mut local_candidates = self.internal.local_candidates.lock().await;
+            if let Some(cs) = local_candidates.get_mut(&pair.local.network_type()) {
+                cs.retain(|c| !c.equal(&*pair.local));
+            }
+        }
         self.internal.delete_all_candidates().await;
+        self.internal
+            .agent_conn
+            .previous_selected_pair
+            .store(previous_selected_pair);
         self.internal.start().await;
 
         // Restart is used by NewAgent. Accept/Connect should be used to move to checking
@@ -468,10 +547,16 @@ impl Agent {
             ext_ip_mapper: Arc::clone(&self.ext_ip_mapper),
             agent_internal: Arc::clone(&self.internal),
             gathering_state: Arc::clone(&self.gathering_state),
+            gathering_timeout: self.gathering_timeout,
+            static_candidate: self.static_candidate_conn.lock().clone(),
+            relay_gathering_delay: self.relay_gathering_delay,
             chan_candidate_tx: Arc::clone(&self.internal.chan_candidate_tx),
             include_loopback: self.include_loopback,
         };
+        let w = self.internal.worker();
         tokio::spawn(async move {
+            let _d = w;
+
             Self::gather_candidates_internal(params).await;
         });
 
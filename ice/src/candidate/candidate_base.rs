@@ -29,6 +29,7 @@ pub struct CandidateBaseConfig {
     pub foundation: String,
     pub conn: Option<Arc<dyn util::Conn + Send + Sync>>,
     pub initialized_ch: Option<broadcast::Receiver<()>>,
+    pub extensions: Vec<CandidateExtension>,
 }
 
 pub struct CandidateBase {
@@ -41,6 +42,7 @@ pub struct CandidateBase {
     pub(crate) port: u16,
     pub(crate) related_address: Option<CandidateRelatedAddress>,
     pub(crate) tcp_type: TcpType,
+    pub(crate) extensions: Vec<CandidateExtension>,
 
     pub(crate) resolved_addr: SyncMutex<SocketAddr>,
 
@@ -71,6 +73,7 @@ impl Default for CandidateBase {
             port: 0,
             related_address: None,
             tcp_type: TcpType::default(),
+            extensions: vec![],
 
             resolved_addr: SyncMutex::new(SocketAddr::new(IpAddr::from([0, 0, 0, 0]), 0)),
 
@@ -203,6 +206,10 @@ impl Candidate for CandidateBase {
         self.tcp_type
     }
 
+    fn extensions(&self) -> Vec<CandidateExtension> {
+        self.extensions.clone()
+    }
+
     /// Returns the string representation of the ICECandidate.
     fn marshal(&self) -> String {
         let mut val = format!(
@@ -216,10 +223,6 @@ impl Candidate for CandidateBase {
             self.candidate_type()
         );
 
-        if self.tcp_type != TcpType::Unspecified {
-            val += format!(" tcptype {}", self.tcp_type()).as_str();
-        }
-
         if let Some(related_address) = self.related_address() {
             val += format!(
                 " raddr {} rport {}",
@@ -228,6 +231,14 @@ impl Candidate for CandidateBase {
             .as_str();
         }
 
+        if self.tcp_type != TcpType::Unspecified {
+            val += format!(" tcptype {}", self.tcp_type()).as_str();
+        }
+
+        for extension in &self.extensions {
+            val += format!(" {} {}", extension.key, extension.value).as_str();
+        }
+
         val
     }
 
@@ -421,36 +432,46 @@ pub fn unmarshal_candidate(raw: &str) -> Result<impl Candidate> {
     let mut rel_addr = String::new();
     let mut rel_port = 0;
     let mut tcp_type = TcpType::Unspecified;
+    let mut extensions = vec![];
+
+    let mut split2 = &split[8..];
+    if split2.first() == Some(&"raddr") {
+        if split2.len() < 4 {
+            return Err(Error::Other(format!(
+                "{:?}: incorrect length",
+                Error::ErrParseRelatedAddr
+            )));
+        }
 
-    if split.len() > 8 {
-        let split2 = &split[8..];
+        // RelatedAddress
+        split2[1].clone_into(&mut rel_addr);
 
-        if split2[0] == "raddr" {
-            if split2.len() < 4 {
-                return Err(Error::Other(format!(
-                    "{:?}: incorrect length",
-                    Error::ErrParseRelatedAddr
-                )));
-            }
+        // RelatedPort
+        rel_port = split2[3].parse()?;
 
-            // RelatedAddress
-            split2[1].clone_into(&mut rel_addr);
-
-            // RelatedPort
-            rel_port = split2[3].parse()?;
-        } else if split2[0] == "tcptype" {
-            if split2.len() < 2 {
-                return Err(Error::Other(format!(
-                    "{:?}: incorrect length",
-                    Error::ErrParseType
-                )));
-            }
+        split2 = &split2[4..];
+    }
 
-            tcp_type = TcpType::from(split2[1]);
+    // Extensions, e.g. "tcptype active generation 0 ufrag EsAw network-id 1"
+    for extension in split2.chunks(2) {
+        if extension.len() < 2 {
+            return Err(Error::Other(format!(
+                "{:?}: incorrect length",
+                Error::ErrParseType
+            )));
+        }
+
+        if extension[0] == "tcptype" {
+            tcp_type = TcpType::from(extension[1]);
+        } else {
+            extensions.push(CandidateExtension {
+                key: extension[0].to_owned(),
+                value: extension[1].to_owned(),
+            });
         }
     }
 
-    match typ {
+    let mut c = match typ {
         "host" => {
             let config = CandidateHostConfig {
                 base_config: CandidateBaseConfig {
@@ -521,5 +542,10 @@ pub fn unmarshal_candidate(raw: &str) -> Result<impl Candidate> {
             Error::ErrUnknownCandidateType,
             typ
         ))),
-    }
+    }?;
+
+    c.tcp_type = tcp_type;
+    c.extensions = extensions;
+
+    Ok(c)
 }
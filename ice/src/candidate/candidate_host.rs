@@ -32,6 +32,7 @@ impl CandidateHostConfig {
             network: self.base_config.network,
             network_type: AtomicU8::new(NetworkType::Udp4 as u8),
             conn: self.base_config.conn,
+            extensions: self.base_config.extensions,
             ..CandidateBase::default()
         };
 
@@ -46,6 +46,7 @@ impl CandidatePeerReflexiveConfig {
                 port: self.rel_port,
             }),
             conn: self.base_config.conn,
+            extensions: self.base_config.extensions,
             ..CandidateBase::default()
         };
 
@@ -48,6 +48,7 @@ impl CandidateRelayConfig {
                 port: self.rel_port,
             }),
             conn: self.base_config.conn,
+            extensions: self.base_config.extensions,
             relay_client: self.relay_client.clone(),
             ..CandidateBase::default()
         };
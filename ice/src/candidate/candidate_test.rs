@@ -409,3 +409,73 @@ fn test_candidate_marshal() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_candidate_marshal_browser_extensions() -> Result<()> {
+    let tests = vec![
+        // Chrome
+        (
+            "3406423489 1 udp 2113937151 192.168.1.5 56143 typ host generation 0 ufrag 5Ktf network-id 1 network-cost 10",
+            None,
+        ),
+        (
+            "842163049 1 udp 1677729535 203.0.113.7 56143 typ srflx raddr 192.168.1.5 rport 56143 generation 0 ufrag 5Ktf network-id 1 network-cost 10",
+            None,
+        ),
+        (
+            "1357493765 1 tcp 1518214911 192.168.1.5 9 typ host tcptype active generation 0 ufrag 5Ktf network-id 1 network-cost 10",
+            None,
+        ),
+        (
+            "2235166443 1 udp 2113937151 a1b2c3d4-e5f6-7890-abcd-ef1234567890.local 56143 typ host generation 0 ufrag 5Ktf network-cost 999",
+            None,
+        ),
+        // Firefox
+        (
+            "0 1 UDP 2122252543 192.168.1.5 49203 typ host",
+            Some("0 1 udp 2122252543 192.168.1.5 49203 typ host"),
+        ),
+        (
+            "2 1 TCP 2105524479 192.168.1.5 9 typ host tcptype active",
+            Some("2 1 tcp 2105524479 192.168.1.5 9 typ host tcptype active"),
+        ),
+        (
+            "1 1 UDP 1686052863 203.0.113.7 49203 typ srflx raddr 192.168.1.5 rport 49203",
+            Some("1 1 udp 1686052863 203.0.113.7 49203 typ srflx raddr 192.168.1.5 rport 49203"),
+        ),
+    ];
+
+    for (raw, expected) in tests {
+        let c = unmarshal_candidate(raw)?;
+        assert_eq!(c.marshal(), expected.unwrap_or(raw), "{raw}");
+    }
+
+    let c = unmarshal_candidate(
+        "1357493765 1 tcp 1518214911 192.168.1.5 9 typ host tcptype passive generation 0 ufrag 5Ktf network-id 1",
+    )?;
+    assert_eq!(c.tcp_type(), TcpType::Passive);
+    assert_eq!(
+        c.extensions(),
+        vec![
+            CandidateExtension {
+                key: "generation".to_owned(),
+                value: "0".to_owned(),
+            },
+            CandidateExtension {
+                key: "ufrag".to_owned(),
+                value: "5Ktf".to_owned(),
+            },
+            CandidateExtension {
+                key: "network-id".to_owned(),
+                value: "1".to_owned(),
+            },
+        ]
+    );
+
+    // an extension without value
+    assert!(
+        unmarshal_candidate("0 1 udp 2122252543 192.168.1.5 49203 typ host generation").is_err()
+    );
+
+    Ok(())
+}
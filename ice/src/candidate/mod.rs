@@ -74,6 +74,13 @@ pub trait Candidate: fmt::Display {
     fn candidate_type(&self) -> CandidateType;
     fn tcp_type(&self) -> TcpType;
 
+    /// Returns the extension attributes of the candidate other than `tcptype`, e.g.
+    /// `generation`, `ufrag`, `network-id` and `network-cost`, in the order they appear.
+    /// Candidates without extensions don't need to implement it.
+    fn extensions(&self) -> Vec<CandidateExtension> {
+        vec![]
+    }
+
     fn marshal(&self) -> String;
 
     fn addr(&self) -> SocketAddr;
@@ -170,6 +177,14 @@ impl fmt::Display for CandidateRelatedAddress {
     }
 }
 
+/// An extension attribute of a candidate (RFC 8839 Section 5.1), marshaled as
+/// `<key> <value>` after the related address.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct CandidateExtension {
+    pub key: String,
+    pub value: String,
+}
+
 /// Represent the ICE candidate pair state.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub enum CandidatePairState {
@@ -51,6 +51,10 @@ pub enum Error {
     #[error("the agent is closed")]
     ErrClosed,
 
+    /// Indicates the tasks of the agent did not exit in time after it was closed.
+    #[error("timed out waiting for the agent tasks to exit")]
+    ErrCloseWaitTimeout,
+
     /// Indicates agent does not have a valid candidate pair.
     #[error("no candidate pairs available")]
     ErrNoCandidatePairs,
@@ -126,6 +130,10 @@ pub enum Error {
     #[error("1:1 NAT IP mapping for srflx candidate ineffective")]
     ErrIneffectiveNat1to1IpMappingSrflx,
 
+    /// Indicates that a static candidate would be advertised with an unspecified IP address.
+    #[error("static candidate must be advertised with a specified IP address")]
+    ErrStaticCandidateUnspecifiedAddress,
+
     /// Indicates an invalid MulticastDNSHostName.
     #[error("invalid mDNS HostName, must end with .local and can only contain a single '.'")]
     ErrInvalidMulticastDnshostName,
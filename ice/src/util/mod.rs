@@ -139,6 +139,27 @@ pub async fn local_interfaces(
     ips
 }
 
+/// set_dscp marks the packets sent on conn with the given DSCP codepoint by setting the TOS
+/// byte of its socket. Only native IPv4 UDP sockets can be marked, conns of other kinds
+/// are left as they are.
+pub fn set_dscp(conn: &Arc<dyn Conn + Send + Sync>, dscp: u8) {
+    #[cfg(not(any(
+        target_os = "fuchsia",
+        target_os = "redox",
+        target_os = "solaris",
+        target_os = "illumos",
+    )))]
+    if let Some(socket) = conn.as_any().downcast_ref::<tokio::net::UdpSocket>() {
+        if !matches!(socket.local_addr(), Ok(SocketAddr::V4(_))) {
+            return;
+        }
+        // DSCP is the upper six bits of the TOS byte, ECN the lower two
+        if let Err(err) = socket.set_tos(u32::from(dscp) << 2) {
+            log::warn!("failed to set dscp {} on {:?}: {}", dscp, socket, err);
+        }
+    }
+}
+
 pub async fn listen_udp_in_port_range(
     vnet: &Arc<Net>,
     port_max: u16,
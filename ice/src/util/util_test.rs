@@ -32,3 +32,24 @@ async fn test_local_interfaces() -> Result<()> {
     );
     Ok(())
 }
+
+#[cfg(not(any(
+    target_os = "fuchsia",
+    target_os = "redox",
+    target_os = "solaris",
+    target_os = "illumos",
+)))]
+#[tokio::test]
+async fn test_set_dscp() -> Result<()> {
+    let vnet = Arc::new(Net::new(None));
+    let conn = vnet.bind(SocketAddr::from(([127, 0, 0, 1], 0))).await?;
+    set_dscp(&conn, 46);
+
+    let socket = conn
+        .as_any()
+        .downcast_ref::<tokio::net::UdpSocket>()
+        .expect("native udp socket");
+    assert_eq!(socket.tos()?, 46 << 2, "dscp is the upper six bits of tos");
+
+    Ok(())
+}
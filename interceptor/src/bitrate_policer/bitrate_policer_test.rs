@@ -0,0 +1,135 @@
+use bytes::Bytes;
+
+use super::*;
+use crate::mock::mock_stream::MockStream;
+
+/// packet returns a packet of 100 bytes, its 12 bytes header included.
+fn packet(sequence_number: u16) -> rtp::packet::Packet {
+    rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            sequence_number,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0u8; 88]),
+    }
+}
+
+fn now_fn(now: &Arc<SyncMutex<SystemTime>>) -> FnTimeGen {
+    let now = Arc::clone(now);
+    Arc::new(move || *now.lock().unwrap())
+}
+
+/// reader returns a reader of packets with increasing sequence numbers, each
+/// received 25ms after the previous one.
+fn reader(now: &Arc<SyncMutex<SystemTime>>) -> Arc<dyn RTPReader + Send + Sync> {
+    let now = Arc::clone(now);
+    let sequence_number = Arc::new(SyncMutex::new(0u16));
+    Arc::new(RTPReaderFn(Box::new(move |_, a| {
+        let pkt = {
+            let mut sequence_number = sequence_number.lock().unwrap();
+            if *sequence_number > 0 {
+                *now.lock().unwrap() += Duration::from_millis(25);
+            }
+            *sequence_number += 1;
+            packet(*sequence_number)
+        };
+        let a = a.clone();
+        Box::pin(async move { Ok((pkt, a)) })
+    })))
+}
+
+#[tokio::test]
+async fn test_bitrate_policer_drops_exceeding_packets() -> Result<()> {
+    let now = Arc::new(SyncMutex::new(SystemTime::UNIX_EPOCH));
+    // 8kbps with a burst of 100ms let 100 bytes through, then 25 bytes every 25ms
+    let builder = BitratePolicer::builder()
+        .with_video_max_bitrate(8_000)
+        .with_burst(Duration::from_millis(100))
+        .with_now_fn(now_fn(&now));
+    let policed_packets = builder.policed_packets();
+    let icpr = builder.build("")?;
+
+    let reader = icpr
+        .bind_remote_stream(
+            &StreamInfo {
+                ssrc: 1,
+                mime_type: "video/VP8".to_owned(),
+                ..Default::default()
+            },
+            reader(&now),
+        )
+        .await;
+
+    let mut buf = vec![0u8; 1500];
+    let (pkt, _) = reader.read(&mut buf, &Attributes::new()).await?;
+    assert_eq!(pkt.header.sequence_number, 1);
+    assert_eq!(policed_packets.get(), 0);
+
+    // the packets 2 to 4 find the bucket short of 100 bytes
+    let (pkt, _) = reader.read(&mut buf, &Attributes::new()).await?;
+    assert_eq!(pkt.header.sequence_number, 5);
+    assert_eq!(policed_packets.get(), 3);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bitrate_policer_marks_exceeding_packets() -> Result<()> {
+    let now = Arc::new(SyncMutex::new(SystemTime::UNIX_EPOCH));
+    let builder = BitratePolicer::builder()
+        .with_audio_max_bitrate(8_000)
+        .with_burst(Duration::from_millis(100))
+        .with_action(PolicingAction::Mark)
+        .with_now_fn(now_fn(&now));
+    let policed_packets = builder.policed_packets();
+    let icpr = builder.build("")?;
+
+    let reader = icpr
+        .bind_remote_stream(
+            &StreamInfo {
+                ssrc: 1,
+                mime_type: "audio/opus".to_owned(),
+                ..Default::default()
+            },
+            reader(&now),
+        )
+        .await;
+
+    let mut buf = vec![0u8; 1500];
+    let (_, attr) = reader.read(&mut buf, &Attributes::new()).await?;
+    assert_eq!(attr.get(&ATTR_KEY_POLICED), None);
+    let (_, attr) = reader.read(&mut buf, &Attributes::new()).await?;
+    assert_eq!(attr.get(&ATTR_KEY_POLICED), Some(&1));
+    assert_eq!(policed_packets.get(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bitrate_policer_ignores_kinds_without_ceiling() -> Result<()> {
+    let builder = BitratePolicer::builder().with_video_max_bitrate(8);
+    let policed_packets = builder.policed_packets();
+    let icpr = builder.build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            mime_type: "audio/opus".to_owned(),
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for sequence_number in 1..=3 {
+        stream.receive_rtp(packet(sequence_number)).await;
+        let pkt = stream.read_rtp().await.unwrap()?;
+        assert_eq!(pkt.header.sequence_number, sequence_number);
+    }
+    assert_eq!(policed_packets.get(), 0);
+
+    stream.close().await?;
+
+    Ok(())
+}
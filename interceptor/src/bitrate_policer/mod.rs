@@ -0,0 +1,282 @@
+#[cfg(test)]
+mod bitrate_policer_test;
+
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::{Duration, SystemTime};
+
+use util::MarshalSize;
+
+use crate::error::Result;
+use crate::shared_value::SharedValue;
+use crate::stream_info::StreamInfo;
+use crate::*;
+
+type FnTimeGen = Arc<dyn Fn() -> SystemTime + Sync + 'static + Send>;
+
+/// Attributes key set to 1 on the packets exceeding the bitrate ceiling when
+/// the policer is configured with [`PolicingAction::Mark`].
+pub const ATTR_KEY_POLICED: usize = 0x504f_4c43;
+
+/// PolicingAction tells what the BitratePolicer does with the packets
+/// exceeding the bitrate ceiling.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicingAction {
+    /// Drop the packets, they are never returned by the reader.
+    #[default]
+    Drop,
+    /// Return the packets with [`ATTR_KEY_POLICED`] set in their attributes,
+    /// leaving the dropping to the application.
+    Mark,
+}
+
+/// BitratePolicerBuilder can be used to configure BitratePolicer Interceptor
+#[derive(Default)]
+pub struct BitratePolicerBuilder {
+    audio_max_bitrate: Option<u64>,
+    video_max_bitrate: Option<u64>,
+    burst: Option<Duration>,
+    action: PolicingAction,
+    now: Option<FnTimeGen>,
+    policed_packets: SharedValue,
+}
+
+impl BitratePolicerBuilder {
+    /// with_audio_max_bitrate sets the ceiling, in bits per second, of each
+    /// incoming audio stream. Audio streams aren't policed by default.
+    pub fn with_audio_max_bitrate(mut self, bitrate: u64) -> BitratePolicerBuilder {
+        self.audio_max_bitrate = Some(bitrate);
+        self
+    }
+
+    /// with_video_max_bitrate sets the ceiling, in bits per second, of each
+    /// incoming video stream. Video streams aren't policed by default.
+    pub fn with_video_max_bitrate(mut self, bitrate: u64) -> BitratePolicerBuilder {
+        self.video_max_bitrate = Some(bitrate);
+        self
+    }
+
+    /// with_burst sets for how long a stream may send at twice its ceiling,
+    /// e.g. for a key frame, before being policed. Defaults to 500ms.
+    pub fn with_burst(mut self, burst: Duration) -> BitratePolicerBuilder {
+        self.burst = Some(burst);
+        self
+    }
+
+    /// with_action sets what is done with the packets exceeding the ceiling.
+    pub fn with_action(mut self, action: PolicingAction) -> BitratePolicerBuilder {
+        self.action = action;
+        self
+    }
+
+    /// with_now_fn sets an alternative for the time.Now function.
+    pub fn with_now_fn(mut self, now: FnTimeGen) -> BitratePolicerBuilder {
+        self.now = Some(now);
+        self
+    }
+
+    /// policed_packets returns the number of packets dropped or marked by all
+    /// the interceptors built by this builder. It keeps counting after the
+    /// builder is registered.
+    pub fn policed_packets(&self) -> SharedValue {
+        self.policed_packets.clone()
+    }
+}
+
+impl InterceptorBuilder for BitratePolicerBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(Arc::new(BitratePolicer {
+            audio_max_bitrate: self.audio_max_bitrate,
+            video_max_bitrate: self.video_max_bitrate,
+            burst: self.burst.unwrap_or(Duration::from_millis(500)),
+            action: self.action,
+            now: self.now.clone(),
+            policed_packets: self.policed_packets.clone(),
+        }))
+    }
+}
+
+/// BitratePolicer enforces a maximum bitrate on each incoming RTP stream,
+/// protecting against senders that ignore the bandwidth estimations sent with
+/// REMB or TWCC. The bitrate is measured with a token bucket per SSRC, and the
+/// packets finding it empty are dropped or marked depending on the
+/// PolicingAction. The ceiling is configured per media kind, the streams of
+/// a kind without ceiling are left alone.
+pub struct BitratePolicer {
+    audio_max_bitrate: Option<u64>,
+    video_max_bitrate: Option<u64>,
+    burst: Duration,
+    action: PolicingAction,
+    now: Option<FnTimeGen>,
+    policed_packets: SharedValue,
+}
+
+impl BitratePolicer {
+    /// builder returns a new BitratePolicerBuilder.
+    pub fn builder() -> BitratePolicerBuilder {
+        BitratePolicerBuilder::default()
+    }
+
+    fn max_bitrate(&self, info: &StreamInfo) -> Option<u64> {
+        let mime_type = info.mime_type.to_lowercase();
+        if mime_type.starts_with("audio/") {
+            self.audio_max_bitrate
+        } else if mime_type.starts_with("video/") {
+            self.video_max_bitrate
+        } else {
+            None
+        }
+    }
+}
+
+#[async_trait]
+impl Interceptor for BitratePolicer {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        let max_bitrate = match self.max_bitrate(info) {
+            Some(max_bitrate) => max_bitrate,
+            None => return reader,
+        };
+
+        Arc::new(BitratePolicerStream {
+            parent_rtp_reader: reader,
+            ssrc: info.ssrc,
+            bucket: SyncMutex::new(TokenBucket::new(max_bitrate, self.burst)),
+            action: self.action,
+            now: self.now.clone(),
+            policed_packets: self.policed_packets.clone(),
+        })
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// TokenBucket fills with bytes at the ceiling rate, up to `burst` worth of
+/// them, and each conforming packet takes its size out of it.
+struct TokenBucket {
+    bytes_per_sec: f64,
+    capacity: f64,
+    tokens: f64,
+    last_refill: Option<SystemTime>,
+}
+
+impl TokenBucket {
+    fn new(max_bitrate: u64, burst: Duration) -> Self {
+        let bytes_per_sec = max_bitrate as f64 / 8.0;
+        let capacity = bytes_per_sec * burst.as_secs_f64();
+        TokenBucket {
+            bytes_per_sec,
+            capacity,
+            tokens: capacity,
+            last_refill: None,
+        }
+    }
+
+    /// take reports whether a packet of `size` bytes received at `now` is
+    /// within the ceiling.
+    fn take(&mut self, now: SystemTime, size: usize) -> bool {
+        if let Some(last_refill) = self.last_refill {
+            // a clock going backwards doesn't refill the bucket
+            let elapsed = now.duration_since(last_refill).unwrap_or_default();
+            self.tokens =
+                (self.tokens + elapsed.as_secs_f64() * self.bytes_per_sec).min(self.capacity);
+        }
+        self.last_refill = Some(now);
+
+        let size = size as f64;
+        if self.tokens < size {
+            return false;
+        }
+        self.tokens -= size;
+        true
+    }
+}
+
+struct BitratePolicerStream {
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+    ssrc: u32,
+    bucket: SyncMutex<TokenBucket>,
+    action: PolicingAction,
+    now: Option<FnTimeGen>,
+    policed_packets: SharedValue,
+}
+
+#[async_trait]
+impl RTPReader for BitratePolicerStream {
+    /// read a rtp packet, policing the ones exceeding the ceiling
+    async fn read(
+        &self,
+        buf: &mut [u8],
+        a: &Attributes,
+    ) -> Result<(rtp::packet::Packet, Attributes)> {
+        loop {
+            let (pkt, mut attr) = self.parent_rtp_reader.read(buf, a).await?;
+
+            let now = if let Some(f) = &self.now {
+                f()
+            } else {
+                SystemTime::now()
+            };
+            let conforming = {
+                let mut bucket = self.bucket.lock().unwrap();
+                bucket.take(now, pkt.marshal_size())
+            };
+            if conforming {
+                return Ok((pkt, attr));
+            }
+
+            self.policed_packets.add(1);
+            log::trace!(
+                "ssrc {} exceeded its bitrate ceiling, {:?} packet {}",
+                self.ssrc,
+                self.action,
+                pkt.header.sequence_number
+            );
+            if self.action == PolicingAction::Mark {
+                attr.insert(ATTR_KEY_POLICED, 1);
+                return Ok((pkt, attr));
+            }
+        }
+    }
+}
@@ -1,23 +1,54 @@
+use std::collections::HashMap;
 use std::sync::Arc;
 
+use portable_atomic::{AtomicU64, Ordering};
+use util::sync::Mutex as SyncMutex;
+
 use crate::error::*;
 use crate::stream_info::StreamInfo;
 use crate::*;
 
+/// ErrorPolicy tells what a Chain does when one of its interceptors fails to read or write
+/// a packet.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// The error is returned to the caller.
+    #[default]
+    FailFast,
+    /// The error is logged and the packet is passed through unmodified, as if the interceptor
+    /// was not there. The errors of the interceptors and transports before it are still
+    /// returned.
+    Isolate,
+}
+
 /// Chain is an interceptor that runs all child interceptors in order.
 #[derive(Default)]
 pub struct Chain {
-    interceptors: Vec<Arc<dyn Interceptor + Send + Sync>>,
+    interceptors: Vec<(Arc<dyn Interceptor + Send + Sync>, ErrorPolicy)>,
 }
 
 impl Chain {
     /// new returns a new Chain interceptor.
     pub fn new(interceptors: Vec<Arc<dyn Interceptor + Send + Sync>>) -> Self {
-        Chain { interceptors }
+        Chain {
+            interceptors: interceptors
+                .into_iter()
+                .map(|icpr| (icpr, ErrorPolicy::FailFast))
+                .collect(),
+        }
     }
 
     pub fn add(&mut self, icpr: Arc<dyn Interceptor + Send + Sync>) {
-        self.interceptors.push(icpr);
+        self.add_with_policy(icpr, ErrorPolicy::FailFast);
+    }
+
+    /// add_with_policy adds an interceptor whose read and write errors are handled by policy.
+    pub fn add_with_policy(
+        &mut self,
+        icpr: Arc<dyn Interceptor + Send + Sync>,
+        policy: ErrorPolicy,
+    ) {
+        self.interceptors.push((icpr, policy));
     }
 }
 
@@ -29,8 +60,17 @@ impl Interceptor for Chain {
         &self,
         mut reader: Arc<dyn RTCPReader + Send + Sync>,
     ) -> Arc<dyn RTCPReader + Send + Sync> {
-        for icpr in &self.interceptors {
-            reader = icpr.bind_rtcp_reader(reader).await;
+        for (icpr, policy) in &self.interceptors {
+            reader = match policy {
+                ErrorPolicy::FailFast => icpr.bind_rtcp_reader(reader).await,
+                ErrorPolicy::Isolate => {
+                    let parent = Arc::new(Parent::new(reader));
+                    Arc::new(Isolated {
+                        inner: icpr.bind_rtcp_reader(Arc::clone(&parent) as _).await,
+                        parent,
+                    })
+                }
+            };
         }
         reader
     }
@@ -41,8 +81,17 @@ impl Interceptor for Chain {
         &self,
         mut writer: Arc<dyn RTCPWriter + Send + Sync>,
     ) -> Arc<dyn RTCPWriter + Send + Sync> {
-        for icpr in &self.interceptors {
-            writer = icpr.bind_rtcp_writer(writer).await;
+        for (icpr, policy) in &self.interceptors {
+            writer = match policy {
+                ErrorPolicy::FailFast => icpr.bind_rtcp_writer(writer).await,
+                ErrorPolicy::Isolate => {
+                    let parent = Arc::new(Parent::new(writer));
+                    Arc::new(Isolated {
+                        inner: icpr.bind_rtcp_writer(Arc::clone(&parent) as _).await,
+                        parent,
+                    })
+                }
+            };
         }
         writer
     }
@@ -54,15 +103,24 @@ impl Interceptor for Chain {
         info: &StreamInfo,
         mut writer: Arc<dyn RTPWriter + Send + Sync>,
     ) -> Arc<dyn RTPWriter + Send + Sync> {
-        for icpr in &self.interceptors {
-            writer = icpr.bind_local_stream(info, writer).await;
+        for (icpr, policy) in &self.interceptors {
+            writer = match policy {
+                ErrorPolicy::FailFast => icpr.bind_local_stream(info, writer).await,
+                ErrorPolicy::Isolate => {
+                    let parent = Arc::new(Parent::new(writer));
+                    Arc::new(Isolated {
+                        inner: icpr.bind_local_stream(info, Arc::clone(&parent) as _).await,
+                        parent,
+                    })
+                }
+            };
         }
         writer
     }
 
     /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
     async fn unbind_local_stream(&self, info: &StreamInfo) {
-        for icpr in &self.interceptors {
+        for (icpr, _) in &self.interceptors {
             icpr.unbind_local_stream(info).await;
         }
     }
@@ -74,15 +132,26 @@ impl Interceptor for Chain {
         info: &StreamInfo,
         mut reader: Arc<dyn RTPReader + Send + Sync>,
     ) -> Arc<dyn RTPReader + Send + Sync> {
-        for icpr in &self.interceptors {
-            reader = icpr.bind_remote_stream(info, reader).await;
+        for (icpr, policy) in &self.interceptors {
+            reader = match policy {
+                ErrorPolicy::FailFast => icpr.bind_remote_stream(info, reader).await,
+                ErrorPolicy::Isolate => {
+                    let parent = Arc::new(Parent::new(reader));
+                    Arc::new(Isolated {
+                        inner: icpr
+                            .bind_remote_stream(info, Arc::clone(&parent) as _)
+                            .await,
+                        parent,
+                    })
+                }
+            };
         }
         reader
     }
 
     /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
     async fn unbind_remote_stream(&self, info: &StreamInfo) {
-        for icpr in &self.interceptors {
+        for (icpr, _) in &self.interceptors {
             icpr.unbind_remote_stream(info).await;
         }
     }
@@ -90,7 +159,7 @@ impl Interceptor for Chain {
     /// close closes the Interceptor, cleaning up any data if necessary.
     async fn close(&self) -> Result<()> {
         let mut errs = vec![];
-        for icpr in &self.interceptors {
+        for (icpr, _) in &self.interceptors {
             if let Err(err) = icpr.close().await {
                 errs.push(err);
             }
@@ -98,3 +167,431 @@ impl Interceptor for Chain {
         flatten_errs(errs)
     }
 }
+
+/// What the reader or writer before an isolated interceptor returned to it during the
+/// current call.
+#[derive(Default)]
+enum Outcome<T> {
+    #[default]
+    NotCalled,
+    Passed(T),
+    Failed,
+}
+
+static NEXT_CALL_ID: AtomicU64 = AtomicU64::new(0);
+
+tokio::task_local! {
+    /// The id of the isolated call the current task is running, so that the concurrent
+    /// calls through the same interceptor each get their own outcome.
+    static CALL_ID: u64;
+}
+
+/// Parent wraps the reader or writer an isolated interceptor is bound to, to tell the errors
+/// coming from before the interceptor apart from the interceptor's own.
+struct Parent<T: ?Sized, O> {
+    next: Arc<T>,
+    outcomes: SyncMutex<HashMap<u64, Outcome<O>>>,
+}
+
+impl<T: ?Sized, O> Parent<T, O> {
+    fn new(next: Arc<T>) -> Self {
+        Parent {
+            next,
+            outcomes: SyncMutex::new(HashMap::new()),
+        }
+    }
+
+    /// record keeps the outcome of the call the current task runs. The calls the
+    /// interceptor makes from other tasks can't be matched to one and are not recorded.
+    fn record<R>(&self, result: &Result<R>, passed: impl FnOnce(&R) -> O) {
+        let Ok(id) = CALL_ID.try_with(|id| *id) else {
+            return;
+        };
+        let mut outcomes = self.outcomes.lock();
+        if let Some(outcome) = outcomes.get_mut(&id) {
+            *outcome = match result {
+                Ok(r) => Outcome::Passed(passed(r)),
+                Err(_) => Outcome::Failed,
+            };
+        }
+    }
+
+    /// call runs f as a new call through the interceptor, and returns its result along
+    /// with the outcome of the reader or writer before it.
+    async fn call<R>(&self, f: impl std::future::Future<Output = R>) -> (R, Outcome<O>) {
+        let id = NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed);
+        self.outcomes.lock().insert(id, Outcome::NotCalled);
+        let pending = PendingCall {
+            outcomes: &self.outcomes,
+            id,
+        };
+        let result = CALL_ID.scope(id, f).await;
+        (result, pending.finish())
+    }
+}
+
+/// PendingCall removes the outcome of a call from its parent once the call is over,
+/// including when the call is dropped before it completes.
+struct PendingCall<'a, O> {
+    outcomes: &'a SyncMutex<HashMap<u64, Outcome<O>>>,
+    id: u64,
+}
+
+impl<O> PendingCall<'_, O> {
+    fn finish(self) -> Outcome<O> {
+        self.outcomes.lock().remove(&self.id).unwrap_or_default()
+    }
+}
+
+impl<O> Drop for PendingCall<'_, O> {
+    fn drop(&mut self) {
+        self.outcomes.lock().remove(&self.id);
+    }
+}
+
+/// Isolated is what an interceptor with ErrorPolicy::Isolate is bound as.
+struct Isolated<T: ?Sized, O> {
+    inner: Arc<T>,
+    parent: Arc<Parent<T, O>>,
+}
+
+type RTPRead = (rtp::packet::Packet, Attributes);
+type RTCPRead = (Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes);
+
+fn clone_rtcp_read(r: &RTCPRead) -> RTCPRead {
+    (r.0.iter().map(|p| p.cloned()).collect(), r.1.clone())
+}
+
+#[async_trait]
+impl RTPReader for Parent<dyn RTPReader + Send + Sync, RTPRead> {
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<RTPRead> {
+        let result = self.next.read(buf, attributes).await;
+        self.record(&result, Clone::clone);
+        result
+    }
+}
+
+#[async_trait]
+impl RTPReader for Isolated<dyn RTPReader + Send + Sync, RTPRead> {
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<RTPRead> {
+        let (result, outcome) = self.parent.call(self.inner.read(buf, attributes)).await;
+        let err = match result {
+            Ok(r) => return Ok(r),
+            Err(err) => err,
+        };
+        match outcome {
+            Outcome::Failed => Err(err),
+            Outcome::Passed(r) => {
+                log::warn!("interceptor failed to read RTP, passing the packet through: {err}");
+                Ok(r)
+            }
+            Outcome::NotCalled => {
+                log::warn!("interceptor failed to read RTP, reading past it: {err}");
+                self.parent.next.read(buf, attributes).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RTPWriter for Parent<dyn RTPWriter + Send + Sync, usize> {
+    async fn write(&self, pkt: &rtp::packet::Packet, attributes: &Attributes) -> Result<usize> {
+        let result = self.next.write(pkt, attributes).await;
+        self.record(&result, |n| *n);
+        result
+    }
+}
+
+#[async_trait]
+impl RTPWriter for Isolated<dyn RTPWriter + Send + Sync, usize> {
+    async fn write(&self, pkt: &rtp::packet::Packet, attributes: &Attributes) -> Result<usize> {
+        let (result, outcome) = self.parent.call(self.inner.write(pkt, attributes)).await;
+        let err = match result {
+            Ok(n) => return Ok(n),
+            Err(err) => err,
+        };
+        match outcome {
+            Outcome::Failed => Err(err),
+            Outcome::Passed(n) => {
+                log::warn!("interceptor failed to write RTP after passing it on: {err}");
+                Ok(n)
+            }
+            Outcome::NotCalled => {
+                log::warn!("interceptor failed to write RTP, passing the packet through: {err}");
+                self.parent.next.write(pkt, attributes).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RTCPReader for Parent<dyn RTCPReader + Send + Sync, RTCPRead> {
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<RTCPRead> {
+        let result = self.next.read(buf, attributes).await;
+        self.record(&result, clone_rtcp_read);
+        result
+    }
+}
+
+#[async_trait]
+impl RTCPReader for Isolated<dyn RTCPReader + Send + Sync, RTCPRead> {
+    async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<RTCPRead> {
+        let (result, outcome) = self.parent.call(self.inner.read(buf, attributes)).await;
+        let err = match result {
+            Ok(r) => return Ok(r),
+            Err(err) => err,
+        };
+        match outcome {
+            Outcome::Failed => Err(err),
+            Outcome::Passed(r) => {
+                log::warn!("interceptor failed to read RTCP, passing the packets through: {err}");
+                Ok(r)
+            }
+            Outcome::NotCalled => {
+                log::warn!("interceptor failed to read RTCP, reading past it: {err}");
+                self.parent.next.read(buf, attributes).await
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl RTCPWriter for Parent<dyn RTCPWriter + Send + Sync, usize> {
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        attributes: &Attributes,
+    ) -> Result<usize> {
+        let result = self.next.write(pkts, attributes).await;
+        self.record(&result, |n| *n);
+        result
+    }
+}
+
+#[async_trait]
+impl RTCPWriter for Isolated<dyn RTCPWriter + Send + Sync, usize> {
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        attributes: &Attributes,
+    ) -> Result<usize> {
+        let (result, outcome) = self.parent.call(self.inner.write(pkts, attributes)).await;
+        let err = match result {
+            Ok(n) => return Ok(n),
+            Err(err) => err,
+        };
+        match outcome {
+            Outcome::Failed => Err(err),
+            Outcome::Passed(n) => {
+                log::warn!("interceptor failed to write RTCP after passing it on: {err}");
+                Ok(n)
+            }
+            Outcome::NotCalled => {
+                log::warn!("interceptor failed to write RTCP, passing the packets through: {err}");
+                self.parent.next.write(pkts, attributes).await
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::time::Duration;
+
+    use super::*;
+    use crate::mock::mock_interceptor::MockInterceptor;
+    use crate::mock::mock_stream::MockStream;
+    use crate::test::timeout_or_fail;
+
+    /// FlakyReader fails to process the packets with an odd sequence number, after reading them.
+    struct FlakyReader(Arc<dyn RTPReader + Send + Sync>);
+
+    #[async_trait]
+    impl RTPReader for FlakyReader {
+        async fn read(&self, buf: &mut [u8], attributes: &Attributes) -> Result<RTPRead> {
+            let (mut pkt, attributes) = self.0.read(buf, attributes).await?;
+            if pkt.header.sequence_number % 2 == 1 {
+                return Err(Error::Other("flaky".to_owned()));
+            }
+            pkt.header.marker = true;
+            Ok((pkt, attributes))
+        }
+    }
+
+    /// SlowFlakyWriter fails to write every packet, some time after passing it on.
+    struct SlowFlakyWriter(Arc<dyn RTPWriter + Send + Sync>);
+
+    #[async_trait]
+    impl RTPWriter for SlowFlakyWriter {
+        async fn write(&self, pkt: &rtp::packet::Packet, attributes: &Attributes) -> Result<usize> {
+            let _ = self.0.write(pkt, attributes).await;
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Err(Error::Other("flaky".to_owned()))
+        }
+    }
+
+    /// flaky_interceptor reads through FlakyReader and fails to write anything.
+    fn flaky_interceptor() -> Arc<dyn Interceptor + Send + Sync> {
+        Arc::new(MockInterceptor {
+            bind_remote_stream_fn: Some(Box::new(|_, reader| {
+                Box::pin(async move {
+                    Arc::new(FlakyReader(reader)) as Arc<dyn RTPReader + Send + Sync>
+                })
+            })),
+            bind_local_stream_fn: Some(Box::new(|_, _| {
+                Box::pin(async move {
+                    Arc::new(RTPWriterFn(Box::new(|_, _| {
+                        Box::pin(async move { Err(Error::Other("flaky".to_owned())) })
+                    }))) as Arc<dyn RTPWriter + Send + Sync>
+                })
+            })),
+            ..Default::default()
+        })
+    }
+
+    fn packet(sequence_number: u16) -> rtp::packet::Packet {
+        rtp::packet::Packet {
+            header: rtp::header::Header {
+                sequence_number,
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_chain_isolate() -> Result<()> {
+        let mut chain = Chain::default();
+        chain.add_with_policy(flaky_interceptor(), ErrorPolicy::Isolate);
+        let stream = MockStream::new(&StreamInfo::default(), Arc::new(chain)).await;
+
+        // the packets the interceptor handles come out of it
+        stream.receive_rtp(packet(2)).await;
+        let pkt = timeout_or_fail(Duration::from_millis(10), stream.read_rtp())
+            .await
+            .expect("read rtp")?;
+        assert_eq!(pkt.header.sequence_number, 2);
+        assert!(pkt.header.marker);
+
+        // the ones it fails on are passed through unmodified
+        stream.receive_rtp(packet(3)).await;
+        let pkt = timeout_or_fail(Duration::from_millis(10), stream.read_rtp())
+            .await
+            .expect("read rtp")?;
+        assert_eq!(pkt.header.sequence_number, 3);
+        assert!(!pkt.header.marker);
+
+        // and so are the packets it fails to write
+        stream.write_rtp(&packet(4)).await?;
+        let pkt = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+            .await
+            .expect("written rtp");
+        assert_eq!(pkt.header.sequence_number, 4);
+
+        stream.close().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chain_fail_fast() -> Result<()> {
+        let mut chain = Chain::default();
+        chain.add(flaky_interceptor());
+        let stream = MockStream::new(&StreamInfo::default(), Arc::new(chain)).await;
+
+        stream.receive_rtp(packet(3)).await;
+        let result = timeout_or_fail(Duration::from_millis(10), stream.read_rtp())
+            .await
+            .expect("read rtp");
+        assert_eq!(result, Err(Error::Other("flaky".to_owned())));
+
+        assert_eq!(
+            stream.write_rtp(&packet(4)).await,
+            Err(Error::Other("flaky".to_owned()))
+        );
+
+        stream.close().await?;
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chain_isolate_parent_error() -> Result<()> {
+        let mut chain = Chain::default();
+        chain.add_with_policy(flaky_interceptor(), ErrorPolicy::Isolate);
+
+        // the errors from before the isolated interceptor still reach the caller
+        let parent: Arc<dyn RTPReader + Send + Sync> = Arc::new(RTPReaderFn(Box::new(|_, _| {
+            Box::pin(async move { Err(Error::ErrIoEOF) })
+        })));
+        let reader = chain
+            .bind_remote_stream(&StreamInfo::default(), parent)
+            .await;
+        let mut buf = vec![0u8; 1500];
+        assert_eq!(
+            reader.read(&mut buf, &Attributes::new()).await.map(|_| ()),
+            Err(Error::ErrIoEOF)
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chain_isolate_concurrent_calls() -> Result<()> {
+        let icpr: Arc<dyn Interceptor + Send + Sync> = Arc::new(MockInterceptor {
+            bind_local_stream_fn: Some(Box::new(|_, writer| {
+                Box::pin(async move {
+                    Arc::new(SlowFlakyWriter(writer)) as Arc<dyn RTPWriter + Send + Sync>
+                })
+            })),
+            ..Default::default()
+        });
+        let mut chain = Chain::default();
+        chain.add_with_policy(icpr, ErrorPolicy::Isolate);
+
+        // the transport fails to write the packets with an odd sequence number
+        let parent: Arc<dyn RTPWriter + Send + Sync> = Arc::new(RTPWriterFn(Box::new(|pkt, _| {
+            let sequence_number = pkt.header.sequence_number;
+            Box::pin(async move {
+                if sequence_number % 2 == 1 {
+                    Err(Error::ErrIoEOF)
+                } else {
+                    Ok(sequence_number as usize)
+                }
+            })
+        })));
+        let writer = chain
+            .bind_local_stream(&StreamInfo::default(), parent)
+            .await;
+
+        // each call gets the outcome of its own write, even while the other is running
+        let (odd_pkt, even_pkt, attributes) = (packet(1), packet(2), Attributes::new());
+        let (odd, even) = tokio::join!(writer.write(&odd_pkt, &attributes), async {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+            writer.write(&even_pkt, &attributes).await
+        });
+        assert!(
+            odd.is_err(),
+            "the failed write should not be passed through"
+        );
+        assert_eq!(even, Ok(2));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_chain_isolate_cancelled_call() {
+        let next: Arc<dyn RTPWriter + Send + Sync> =
+            Arc::new(RTPWriterFn(Box::new(|_, _| Box::pin(async move { Ok(0) }))));
+        let parent = Parent::<dyn RTPWriter + Send + Sync, usize>::new(next);
+
+        // a call dropped before it completes does not leave its outcome behind
+        let result = tokio::time::timeout(
+            Duration::from_millis(5),
+            parent.call(std::future::pending::<()>()),
+        )
+        .await;
+        assert!(result.is_err(), "the call should time out");
+        assert!(parent.outcomes.lock().is_empty());
+    }
+}
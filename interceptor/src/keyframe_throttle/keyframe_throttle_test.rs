@@ -0,0 +1,81 @@
+use rtcp::payload_feedbacks::full_intra_request::FirEntry;
+use rtcp::receiver_report::ReceiverReport;
+use tokio::sync::mpsc;
+
+use super::*;
+
+type RtcpBatch = Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>;
+
+async fn bind_writer(
+    icpr: &Arc<dyn Interceptor + Send + Sync>,
+) -> (
+    Arc<dyn RTCPWriter + Send + Sync>,
+    mpsc::UnboundedReceiver<RtcpBatch>,
+) {
+    let (written_tx, written_rx) = mpsc::unbounded_channel();
+    let writer = Arc::new(RTCPWriterFn(Box::new(move |pkts, _| {
+        let _ = written_tx.send(pkts.iter().map(|p| p.cloned()).collect());
+        Box::pin(async move { Ok(0) })
+    })));
+    (icpr.bind_rtcp_writer(writer).await, written_rx)
+}
+
+fn pli(media_ssrc: u32) -> Box<dyn rtcp::packet::Packet + Send + Sync> {
+    Box::new(PictureLossIndication {
+        sender_ssrc: 0,
+        media_ssrc,
+    })
+}
+
+#[tokio::test]
+async fn test_keyframe_throttle_coalesces_requests() -> Result<()> {
+    let now = Arc::new(SyncMutex::new(SystemTime::UNIX_EPOCH));
+    let now2 = Arc::clone(&now);
+    let builder = KeyframeThrottle::builder()
+        .with_interval(Duration::from_millis(500))
+        .with_now_fn(Arc::new(move || *now2.lock().unwrap()));
+    let suppressed_requests = builder.suppressed_requests();
+    let icpr = builder.build("")?;
+    let (writer, mut written_rx) = bind_writer(&icpr).await;
+
+    // 50 viewers ask for a keyframe of the same track
+    for _ in 0..50 {
+        writer.write(&[pli(1)], &Attributes::new()).await?;
+    }
+    // a FIR is a keyframe request as well
+    let fir: Box<dyn rtcp::packet::Packet + Send + Sync> = Box::new(FullIntraRequest {
+        sender_ssrc: 0,
+        media_ssrc: 1,
+        fir: vec![FirEntry {
+            ssrc: 1,
+            sequence_number: 1,
+        }],
+    });
+    writer.write(&[fir], &Attributes::new()).await?;
+    // another track isn't throttled, nor are the other packets
+    writer
+        .write(
+            &[pli(2), Box::<ReceiverReport>::default(), pli(1)],
+            &Attributes::new(),
+        )
+        .await?;
+
+    let batch = written_rx.recv().await.unwrap();
+    assert_eq!(batch.len(), 1);
+    assert!(batch[0].equal(pli(1).as_ref()));
+    let batch = written_rx.recv().await.unwrap();
+    assert_eq!(batch.len(), 2);
+    assert!(batch[0].equal(pli(2).as_ref()));
+    assert!(batch[1].as_any().downcast_ref::<ReceiverReport>().is_some());
+    assert!(written_rx.try_recv().is_err());
+    assert_eq!(suppressed_requests.get(), 51);
+
+    // the next request goes through once the interval elapsed
+    *now.lock().unwrap() += Duration::from_millis(500);
+    writer.write(&[pli(1)], &Attributes::new()).await?;
+    let batch = written_rx.recv().await.unwrap();
+    assert!(batch[0].equal(pli(1).as_ref()));
+    assert_eq!(suppressed_requests.get(), 51);
+
+    Ok(())
+}
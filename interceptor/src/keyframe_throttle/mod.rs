@@ -0,0 +1,214 @@
+#[cfg(test)]
+mod keyframe_throttle_test;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::{Duration, SystemTime};
+
+use rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+
+use crate::error::Result;
+use crate::shared_value::SharedValue;
+use crate::stream_info::StreamInfo;
+use crate::*;
+
+type FnTimeGen = Arc<dyn Fn() -> SystemTime + Sync + 'static + Send>;
+
+/// KeyframeThrottleBuilder can be used to configure KeyframeThrottle Interceptor
+#[derive(Default)]
+pub struct KeyframeThrottleBuilder {
+    interval: Option<Duration>,
+    now: Option<FnTimeGen>,
+    suppressed_requests: SharedValue,
+}
+
+impl KeyframeThrottleBuilder {
+    /// with_interval sets the minimum time between two keyframe requests
+    /// sent for the same media SSRC. Defaults to 500ms.
+    pub fn with_interval(mut self, interval: Duration) -> KeyframeThrottleBuilder {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// with_now_fn sets an alternative for the time.Now function.
+    pub fn with_now_fn(mut self, now: FnTimeGen) -> KeyframeThrottleBuilder {
+        self.now = Some(now);
+        self
+    }
+
+    /// suppressed_requests returns the number of keyframe requests dropped by
+    /// all the interceptors built by this builder. It keeps counting after
+    /// the builder is registered.
+    pub fn suppressed_requests(&self) -> SharedValue {
+        self.suppressed_requests.clone()
+    }
+}
+
+impl InterceptorBuilder for KeyframeThrottleBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(Arc::new(KeyframeThrottle {
+            interval: self.interval.unwrap_or(Duration::from_millis(500)),
+            now: self.now.clone(),
+            last_requests: Arc::new(SyncMutex::new(HashMap::new())),
+            suppressed_requests: self.suppressed_requests.clone(),
+        }))
+    }
+}
+
+/// KeyframeThrottle coalesces the keyframe requests, PLI and FIR, sent to the
+/// remote peer: at most one is sent per interval for a given media SSRC, the
+/// others are dropped. In an SFU, the requests of all the viewers of a
+/// forwarded track are written to the peer connection of its publisher, which
+/// then only sees one request per interval however many viewers asked.
+pub struct KeyframeThrottle {
+    interval: Duration,
+    now: Option<FnTimeGen>,
+    last_requests: Arc<SyncMutex<HashMap<u32, SystemTime>>>,
+    suppressed_requests: SharedValue,
+}
+
+impl KeyframeThrottle {
+    /// builder returns a new KeyframeThrottleBuilder.
+    pub fn builder() -> KeyframeThrottleBuilder {
+        KeyframeThrottleBuilder::default()
+    }
+}
+
+#[async_trait]
+impl Interceptor for KeyframeThrottle {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        Arc::new(KeyframeThrottleWriter {
+            next_rtcp_writer: writer,
+            interval: self.interval,
+            now: self.now.clone(),
+            last_requests: Arc::clone(&self.last_requests),
+            suppressed_requests: self.suppressed_requests.clone(),
+        })
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        reader
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, info: &StreamInfo) {
+        let mut last_requests = self.last_requests.lock().unwrap();
+        last_requests.remove(&info.ssrc);
+    }
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct KeyframeThrottleWriter {
+    next_rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+    interval: Duration,
+    now: Option<FnTimeGen>,
+    last_requests: Arc<SyncMutex<HashMap<u32, SystemTime>>>,
+    suppressed_requests: SharedValue,
+}
+
+impl KeyframeThrottleWriter {
+    /// keyframe_request_ssrcs returns the media SSRCs a packet requests a
+    /// keyframe for, or None if it isn't a keyframe request.
+    fn keyframe_request_ssrcs(pkt: &(dyn rtcp::packet::Packet + Send + Sync)) -> Option<Vec<u32>> {
+        if let Some(pli) = pkt.as_any().downcast_ref::<PictureLossIndication>() {
+            Some(vec![pli.media_ssrc])
+        } else {
+            pkt.as_any()
+                .downcast_ref::<FullIntraRequest>()
+                .map(|fir| fir.fir.iter().map(|e| e.ssrc).collect())
+        }
+    }
+}
+
+#[async_trait]
+impl RTCPWriter for KeyframeThrottleWriter {
+    /// write a batch of rtcp packets, without the keyframe requests sent
+    /// less than an interval after the previous one for the same SSRC
+    async fn write(
+        &self,
+        pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+        attributes: &Attributes,
+    ) -> Result<usize> {
+        let now = if let Some(f) = &self.now {
+            f()
+        } else {
+            SystemTime::now()
+        };
+
+        let mut forwarded = Vec::with_capacity(pkts.len());
+        {
+            let mut last_requests = self.last_requests.lock().unwrap();
+            for pkt in pkts {
+                let ssrcs = match Self::keyframe_request_ssrcs(pkt.as_ref()) {
+                    Some(ssrcs) => ssrcs,
+                    None => {
+                        forwarded.push(pkt.cloned());
+                        continue;
+                    }
+                };
+
+                let mut requested = false;
+                for ssrc in ssrcs {
+                    let throttled = last_requests.get(&ssrc).is_some_and(|last| {
+                        now.duration_since(*last)
+                            .is_ok_and(|elapsed| elapsed < self.interval)
+                    });
+                    if !throttled {
+                        last_requests.insert(ssrc, now);
+                        requested = true;
+                    }
+                }
+
+                if requested {
+                    forwarded.push(pkt.cloned());
+                } else {
+                    self.suppressed_requests.add(1);
+                    log::trace!("suppressed keyframe request {}", pkt);
+                }
+            }
+        }
+
+        if forwarded.is_empty() {
+            return Ok(0);
+        }
+        self.next_rtcp_writer.write(&forwarded, attributes).await
+    }
+}
@@ -10,17 +10,22 @@ use async_trait::async_trait;
 use error::Result;
 use stream_info::StreamInfo;
 
+pub mod bitrate_policer;
 pub mod chain;
 mod error;
+pub mod keyframe_throttle;
 pub mod mock;
 pub mod nack;
 pub mod noop;
 pub mod registry;
+pub mod remb;
 pub mod report;
+pub mod shared_value;
 pub mod stats;
 pub mod stream_info;
 pub mod stream_reader;
 pub mod twcc;
+pub mod validator;
 
 pub use error::Error;
 
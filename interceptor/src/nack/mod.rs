@@ -14,3 +14,9 @@ fn stream_support_nack(info: &StreamInfo) -> bool {
 
     false
 }
+
+fn stream_is_rtx(info: &StreamInfo) -> bool {
+    info.mime_type
+        .split_once('/')
+        .is_some_and(|(_, subtype)| subtype.eq_ignore_ascii_case("rtx"))
+}
@@ -1,19 +1,20 @@
 mod responder_stream;
 #[cfg(test)]
 mod responder_test;
+mod rtx_stream;
 
 use std::collections::HashMap;
-use std::future::Future;
-use std::pin::Pin;
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use responder_stream::ResponderStream;
+use responder_stream::{ResponderStream, Retransmit};
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
 use rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
+use rtx_stream::RtxStream;
 use tokio::sync::Mutex;
 
 use crate::error::Result;
-use crate::nack::stream_support_nack;
+use crate::nack::{stream_is_rtx, stream_support_nack};
 use crate::stream_info::StreamInfo;
 use crate::{
     Attributes, Interceptor, InterceptorBuilder, RTCPReader, RTCPWriter, RTPReader, RTPWriter,
@@ -23,6 +24,8 @@ use crate::{
 #[derive(Default)]
 pub struct ResponderBuilder {
     log2_size: Option<u8>,
+    max_retransmits_per_packet: Option<u8>,
+    pli_on_max_retransmits: bool,
 }
 
 impl ResponderBuilder {
@@ -32,6 +35,22 @@ impl ResponderBuilder {
         self.log2_size = Some(log2_size);
         self
     }
+
+    /// with_max_retransmits_per_packet limits how many times a packet is retransmitted, further
+    /// NACKs for it are ignored so that a retransmit storm doesn't multiply the outbound traffic.
+    /// Packets are retransmitted as often as they are nacked by default.
+    pub fn with_max_retransmits_per_packet(mut self, max_retransmits: u8) -> ResponderBuilder {
+        self.max_retransmits_per_packet = Some(max_retransmits);
+        self
+    }
+
+    /// with_pli_on_max_retransmits makes the responder hand a Picture Loss Indication to the
+    /// application, along with the NACK, when a packet is nacked again after its last allowed
+    /// retransmission. The encoder can then recover the stream with a keyframe.
+    pub fn with_pli_on_max_retransmits(mut self, pli: bool) -> ResponderBuilder {
+        self.pli_on_max_retransmits = pli;
+        self
+    }
 }
 
 impl InterceptorBuilder for ResponderBuilder {
@@ -39,7 +58,10 @@ impl InterceptorBuilder for ResponderBuilder {
         Ok(Arc::new(Responder {
             internal: Arc::new(ResponderInternal {
                 log2_size: self.log2_size.unwrap_or(13), // 8192 = 1 << 13
+                max_retransmits_per_packet: self.max_retransmits_per_packet,
+                pli_on_max_retransmits: self.pli_on_max_retransmits,
                 streams: Arc::new(Mutex::new(HashMap::new())),
+                rtx_streams: Arc::new(Mutex::new(HashMap::new())),
             }),
         }))
     }
@@ -47,47 +69,64 @@ impl InterceptorBuilder for ResponderBuilder {
 
 pub struct ResponderInternal {
     log2_size: u8,
+    max_retransmits_per_packet: Option<u8>,
+    pli_on_max_retransmits: bool,
     streams: Arc<Mutex<HashMap<u32, Arc<ResponderStream>>>>,
+    // RTX streams, keyed by the ssrc of the stream they retransmit
+    rtx_streams: Arc<Mutex<HashMap<u32, Arc<RtxStream>>>>,
 }
 
 impl ResponderInternal {
-    async fn resend_packets(
-        streams: Arc<Mutex<HashMap<u32, Arc<ResponderStream>>>>,
-        nack: TransportLayerNack,
-    ) {
+    /// resend_packets resends the nacked packets in the background, it returns whether a packet
+    /// was refused for the first time because of the retransmission limit
+    async fn resend_packets(&self, nack: &TransportLayerNack) -> bool {
         let stream = {
-            let m = streams.lock().await;
+            let m = self.streams.lock().await;
             if let Some(stream) = m.get(&nack.media_ssrc) {
                 stream.clone()
             } else {
-                return;
+                return false;
             }
         };
+        let rtx_stream = {
+            let m = self.rtx_streams.lock().await;
+            m.get(&nack.media_ssrc).cloned()
+        };
 
+        let mut packets = vec![];
+        let mut capped = false;
         for n in &nack.nacks {
-            // can't use n.range() since this callback is async fn,
-            // instead, use NackPair into_iter()
-            let stream2 = Arc::clone(&stream);
-            let f = Box::new(
-                move |seq: u16| -> Pin<Box<dyn Future<Output = bool> + Send + 'static>> {
-                    let stream3 = Arc::clone(&stream2);
-                    Box::pin(async move {
-                        if let Some(p) = stream3.get(seq).await {
-                            let a = Attributes::new();
-                            if let Err(err) = stream3.next_rtp_writer.write(&p, &a).await {
-                                log::warn!("failed resending nacked packet: {}", err);
-                            }
-                        }
-                        true
-                    })
-                },
-            );
-            for packet_id in n.into_iter() {
-                if !f(packet_id).await {
-                    return;
+            for seq in n.into_iter() {
+                match stream
+                    .retransmit(seq, self.max_retransmits_per_packet)
+                    .await
+                {
+                    Retransmit::Packet(p) => packets.push(p),
+                    Retransmit::Missing => {}
+                    Retransmit::Capped { first } => capped |= first,
                 }
             }
         }
+
+        if !packets.is_empty() {
+            tokio::spawn(async move {
+                for p in packets {
+                    let a = Attributes::new();
+                    // Retransmit on the RTX stream if one is negotiated, the
+                    // remote might discard packets it has already seen otherwise
+                    let result = if let Some(rtx_stream) = &rtx_stream {
+                        rtx_stream.write(&p, &a).await
+                    } else {
+                        stream.next_rtp_writer.write(&p, &a).await
+                    };
+                    if let Err(err) = result {
+                        log::warn!("failed resending nacked packet: {}", err);
+                    }
+                }
+            });
+        }
+
+        capped
     }
 }
 
@@ -103,16 +142,20 @@ impl RTCPReader for ResponderRtcpReader {
         buf: &mut [u8],
         a: &Attributes,
     ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
-        let (pkts, attr) = { self.parent_rtcp_reader.read(buf, a).await? };
+        let (mut pkts, attr) = { self.parent_rtcp_reader.read(buf, a).await? };
+        let mut plis: Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> = vec![];
         for p in &pkts {
             if let Some(nack) = p.as_any().downcast_ref::<TransportLayerNack>() {
-                let nack = nack.clone();
-                let streams = Arc::clone(&self.internal.streams);
-                tokio::spawn(async move {
-                    ResponderInternal::resend_packets(streams, nack).await;
-                });
+                let capped = self.internal.resend_packets(nack).await;
+                if capped && self.internal.pli_on_max_retransmits {
+                    plis.push(Box::new(PictureLossIndication {
+                        sender_ssrc: nack.sender_ssrc,
+                        media_ssrc: nack.media_ssrc,
+                    }));
+                }
             }
         }
+        pkts.extend(plis);
 
         Ok((pkts, attr))
     }
@@ -160,11 +203,24 @@ impl Interceptor for Responder {
         info: &StreamInfo,
         writer: Arc<dyn RTPWriter + Send + Sync>,
     ) -> Arc<dyn RTPWriter + Send + Sync> {
+        if let Some(associated_stream) = &info.associated_stream {
+            if stream_is_rtx(info) {
+                let stream = Arc::new(RtxStream::new(info.ssrc, info.payload_type, writer.clone()));
+                let mut rtx_streams = self.internal.rtx_streams.lock().await;
+                rtx_streams.insert(associated_stream.ssrc, stream);
+            }
+            return writer;
+        }
+
         if !stream_support_nack(info) {
             return writer;
         }
 
-        let stream = Arc::new(ResponderStream::new(self.internal.log2_size, writer));
+        let stream = Arc::new(ResponderStream::new(
+            self.internal.log2_size,
+            info.memory_budget.clone(),
+            writer,
+        ));
         {
             let mut streams = self.internal.streams.lock().await;
             streams.insert(info.ssrc, Arc::clone(&stream));
@@ -175,6 +231,12 @@ impl Interceptor for Responder {
 
     /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
     async fn unbind_local_stream(&self, info: &StreamInfo) {
+        if let Some(associated_stream) = &info.associated_stream {
+            let mut rtx_streams = self.internal.rtx_streams.lock().await;
+            rtx_streams.remove(&associated_stream.ssrc);
+            return;
+        }
+
         let mut streams = self.internal.streams.lock().await;
         streams.remove(&info.ssrc);
     }
@@ -2,32 +2,71 @@ use std::sync::Arc;
 
 use async_trait::async_trait;
 use tokio::sync::Mutex;
+use util::sync::MemoryBudget;
+use util::MarshalSize;
 
 use crate::error::Result;
 use crate::nack::UINT16SIZE_HALF;
 use crate::{Attributes, RTPWriter};
 
+/// Retransmit is the outcome of a retransmission request for a sequence number.
+#[derive(Debug, PartialEq)]
+pub(super) enum Retransmit {
+    /// the packet to resend
+    Packet(rtp::packet::Packet),
+    /// the packet isn't in the send buffer anymore, or was never sent
+    Missing,
+    /// the packet was already retransmitted the maximum number of times, `first` is set for the
+    /// first request refused
+    Capped { first: bool },
+}
+
 struct ResponderStreamInternal {
     packets: Vec<Option<rtp::packet::Packet>>,
+    /// number of times each packet of the buffer was retransmitted
+    retransmits: Vec<u16>,
     size: u16,
     last_added: u16,
     started: bool,
+    /// charged with the packets of the buffer, a packet that doesn't fit isn't kept
+    memory_budget: Option<Arc<MemoryBudget>>,
 }
 
 impl ResponderStreamInternal {
     fn new(log2_size: u8) -> Self {
         ResponderStreamInternal {
             packets: vec![None; 1 << log2_size],
+            retransmits: vec![0; 1 << log2_size],
             size: 1 << log2_size,
             last_added: 0,
             started: false,
+            memory_budget: None,
+        }
+    }
+
+    /// set replaces the packet of a slot of the buffer, charging the memory budget
+    fn set(&mut self, seq: u16, packet: Option<&rtp::packet::Packet>) {
+        let slot = (seq % self.size) as usize;
+        let mut packet = packet.cloned();
+        if let Some(budget) = &self.memory_budget {
+            if let Some(old) = &self.packets[slot] {
+                budget.release(old.marshal_size());
+            }
+            if packet
+                .as_ref()
+                .is_some_and(|p| !budget.try_reserve(p.marshal_size()))
+            {
+                packet = None;
+            }
         }
+        self.packets[slot] = packet;
     }
 
     fn add(&mut self, packet: &rtp::packet::Packet) {
         let seq = packet.header.sequence_number;
         if !self.started {
-            self.packets[(seq % self.size) as usize] = Some(packet.clone());
+            self.set(seq, Some(packet));
+            self.retransmits[(seq % self.size) as usize] = 0;
             self.last_added = seq;
             self.started = true;
             return;
@@ -39,12 +78,13 @@ impl ResponderStreamInternal {
         } else if diff < UINT16SIZE_HALF {
             let mut i = self.last_added.wrapping_add(1);
             while i != seq {
-                self.packets[(i % self.size) as usize] = None;
+                self.set(i, None);
                 i = i.wrapping_add(1);
             }
         }
 
-        self.packets[(seq % self.size) as usize] = Some(packet.clone());
+        self.set(seq, Some(packet));
+        self.retransmits[(seq % self.size) as usize] = 0;
         self.last_added = seq;
     }
 
@@ -60,6 +100,37 @@ impl ResponderStreamInternal {
 
         self.packets[(seq % self.size) as usize].as_ref()
     }
+
+    /// retransmit returns the packet to resend for seq, counting the retransmissions to refuse
+    /// them beyond max_retransmits
+    fn retransmit(&mut self, seq: u16, max_retransmits: Option<u8>) -> Retransmit {
+        let packet = match self.get(seq) {
+            Some(packet) => packet.clone(),
+            None => return Retransmit::Missing,
+        };
+
+        let retransmits = &mut self.retransmits[(seq % self.size) as usize];
+        if let Some(max_retransmits) = max_retransmits {
+            if *retransmits >= max_retransmits as u16 {
+                let first = *retransmits == max_retransmits as u16;
+                *retransmits = max_retransmits as u16 + 1;
+                return Retransmit::Capped { first };
+            }
+        }
+        *retransmits = retransmits.saturating_add(1);
+
+        Retransmit::Packet(packet)
+    }
+}
+
+impl Drop for ResponderStreamInternal {
+    fn drop(&mut self) {
+        if let Some(budget) = &self.memory_budget {
+            for packet in self.packets.iter().flatten() {
+                budget.release(packet.marshal_size());
+            }
+        }
+    }
 }
 
 pub(super) struct ResponderStream {
@@ -68,9 +139,15 @@ pub(super) struct ResponderStream {
 }
 
 impl ResponderStream {
-    pub(super) fn new(log2_size: u8, writer: Arc<dyn RTPWriter + Send + Sync>) -> Self {
+    pub(super) fn new(
+        log2_size: u8,
+        memory_budget: Option<Arc<MemoryBudget>>,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Self {
+        let mut internal = ResponderStreamInternal::new(log2_size);
+        internal.memory_budget = memory_budget;
         ResponderStream {
-            internal: Mutex::new(ResponderStreamInternal::new(log2_size)),
+            internal: Mutex::new(internal),
             next_rtp_writer: writer,
         }
     }
@@ -80,9 +157,9 @@ impl ResponderStream {
         internal.add(pkt);
     }
 
-    pub(super) async fn get(&self, seq: u16) -> Option<rtp::packet::Packet> {
-        let internal = self.internal.lock().await;
-        internal.get(seq).cloned()
+    pub(super) async fn retransmit(&self, seq: u16, max_retransmits: Option<u8>) -> Retransmit {
+        let mut internal = self.internal.lock().await;
+        internal.retransmit(seq, max_retransmits)
     }
 }
 
@@ -173,4 +250,70 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_responder_stream_max_retransmits() {
+        let mut sb = ResponderStreamInternal::new(3);
+        let packet = |sequence_number: u16| rtp::packet::Packet {
+            header: rtp::header::Header {
+                sequence_number,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        sb.add(&packet(1));
+        sb.add(&packet(2));
+
+        assert_eq!(sb.retransmit(1, Some(2)), Retransmit::Packet(packet(1)));
+        assert_eq!(sb.retransmit(1, Some(2)), Retransmit::Packet(packet(1)));
+        assert_eq!(
+            sb.retransmit(1, Some(2)),
+            Retransmit::Capped { first: true }
+        );
+        assert_eq!(
+            sb.retransmit(1, Some(2)),
+            Retransmit::Capped { first: false }
+        );
+        assert_eq!(sb.retransmit(2, Some(2)), Retransmit::Packet(packet(2)));
+        assert_eq!(sb.retransmit(3, Some(2)), Retransmit::Missing);
+
+        // the count starts over when the slot is reused by a new packet
+        sb.add(&packet(9));
+        assert_eq!(sb.retransmit(9, Some(2)), Retransmit::Packet(packet(9)));
+        assert_eq!(sb.retransmit(9, None), Retransmit::Packet(packet(9)));
+        assert_eq!(
+            sb.retransmit(9, Some(2)),
+            Retransmit::Capped { first: true }
+        );
+    }
+
+    #[test]
+    fn test_responder_stream_memory_budget() {
+        let packet = |seq: u16| rtp::packet::Packet {
+            header: rtp::header::Header {
+                sequence_number: seq,
+                ..Default::default()
+            },
+            payload: vec![0u8; 88].into(),
+        };
+        let size = packet(0).marshal_size();
+        let budget = Arc::new(MemoryBudget::new(2 * size));
+
+        let mut sb = ResponderStreamInternal::new(3);
+        sb.memory_budget = Some(Arc::clone(&budget));
+        for seq in 0..3 {
+            sb.add(&packet(seq));
+        }
+        assert!(sb.get(1).is_some());
+        assert!(sb.get(2).is_none(), "packet beyond the budget kept");
+        assert_eq!(budget.used(), 2 * size);
+
+        // the slot reused by a new packet gives back the old one
+        sb.add(&packet(8));
+        assert!(sb.get(8).is_some());
+        assert_eq!(budget.used(), 2 * size);
+
+        drop(sb);
+        assert_eq!(budget.used(), 0);
+    }
 }
@@ -3,7 +3,7 @@ use tokio::time::Duration;
 
 use super::*;
 use crate::mock::mock_stream::MockStream;
-use crate::stream_info::RTCPFeedback;
+use crate::stream_info::{AssociatedStreamInfo, RTCPFeedback};
 use crate::test::timeout_or_fail;
 
 #[tokio::test]
@@ -74,3 +74,177 @@ async fn test_responder_interceptor() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_responder_interceptor_rtx() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> =
+        Responder::builder().with_log2_size(3).build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            payload_type: 96,
+            mime_type: "video/VP8".to_owned(),
+            rtcp_feedback: vec![RTCPFeedback {
+                typ: "nack".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        Arc::clone(&icpr),
+    )
+    .await;
+    let rtx_stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 2,
+            payload_type: 97,
+            mime_type: "video/rtx".to_owned(),
+            associated_stream: Some(AssociatedStreamInfo {
+                ssrc: 1,
+                payload_type: 96,
+            }),
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    for seq_num in [10, 11, 12] {
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    ssrc: 1,
+                    payload_type: 96,
+                    sequence_number: seq_num,
+                    timestamp: 3000 + seq_num as u32,
+                    ..Default::default()
+                },
+                payload: vec![0xAA, seq_num as u8].into(),
+            })
+            .await?;
+
+        let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+            .await
+            .expect("A packet");
+        assert_eq!(p.header.sequence_number, seq_num);
+    }
+
+    stream
+        .receive_rtcp(vec![Box::new(TransportLayerNack {
+            media_ssrc: 1,
+            sender_ssrc: 3,
+            nacks: vec![NackPair {
+                packet_id: 10,
+                lost_packets: 0b10,
+            }], // sequence numbers: 10, 12
+        })])
+        .await;
+
+    // nacked packets are resent on the rtx stream, prefixed with their original sequence number
+    let mut rtx_seq_num = None;
+    for seq_num in [10u16, 12] {
+        let p = timeout_or_fail(Duration::from_millis(50), rtx_stream.written_rtp())
+            .await
+            .expect("A retransmission");
+        assert_eq!(p.header.ssrc, 2);
+        assert_eq!(p.header.payload_type, 97);
+        assert_eq!(p.header.timestamp, 3000 + seq_num as u32);
+        assert_eq!(
+            &p.payload[..],
+            &[(seq_num >> 8) as u8, seq_num as u8, 0xAA, seq_num as u8]
+        );
+        if let Some(prev) = rtx_seq_num {
+            assert_eq!(p.header.sequence_number, u16::wrapping_add(prev, 1));
+        }
+        rtx_seq_num = Some(p.header.sequence_number);
+    }
+
+    let result = tokio::time::timeout(Duration::from_millis(10), stream.written_rtp()).await;
+    assert!(
+        result.is_err(),
+        "no retransmissions on the media stream expected"
+    );
+
+    stream.close().await?;
+    rtx_stream.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_responder_interceptor_max_retransmits_per_packet() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = Responder::builder()
+        .with_log2_size(3)
+        .with_max_retransmits_per_packet(2)
+        .with_pli_on_max_retransmits(true)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            rtcp_feedback: vec![RTCPFeedback {
+                typ: "nack".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    stream
+        .write_rtp(&rtp::packet::Packet {
+            header: rtp::header::Header {
+                sequence_number: 10,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await?;
+    let p = timeout_or_fail(Duration::from_millis(10), stream.written_rtp())
+        .await
+        .expect("A packet");
+    assert_eq!(p.header.sequence_number, 10);
+
+    let mut retransmits = 0;
+    let mut plis = 0;
+    for _ in 0..5 {
+        stream
+            .receive_rtcp(vec![Box::new(TransportLayerNack {
+                media_ssrc: 1,
+                sender_ssrc: 2,
+                nacks: vec![NackPair {
+                    packet_id: 10,
+                    lost_packets: 0,
+                }],
+            })])
+            .await;
+
+        let pkts = timeout_or_fail(Duration::from_millis(50), stream.read_rtcp())
+            .await
+            .expect("RTCP packets")?;
+        plis += pkts
+            .iter()
+            .filter(|p| {
+                p.as_any()
+                    .downcast_ref::<PictureLossIndication>()
+                    .is_some_and(|pli| pli.media_ssrc == 1 && pli.sender_ssrc == 2)
+            })
+            .count();
+
+        if let Ok(Some(p)) =
+            tokio::time::timeout(Duration::from_millis(20), stream.written_rtp()).await
+        {
+            assert_eq!(p.header.sequence_number, 10);
+            retransmits += 1;
+        }
+    }
+
+    // the third NACK is refused and asks for a keyframe once
+    assert_eq!(retransmits, 2);
+    assert_eq!(plis, 1);
+
+    stream.close().await?;
+
+    Ok(())
+}
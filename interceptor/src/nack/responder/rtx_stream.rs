@@ -0,0 +1,57 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use bytes::{BufMut, BytesMut};
+use portable_atomic::AtomicU16;
+
+use crate::error::Result;
+use crate::{Attributes, RTPWriter};
+
+/// RtxStream retransmits the packets of a stream on its associated RTX
+/// stream, as described in RFC 4588.
+pub(super) struct RtxStream {
+    ssrc: u32,
+    payload_type: u8,
+    sequence_number: AtomicU16,
+    next_rtp_writer: Arc<dyn RTPWriter + Send + Sync>,
+}
+
+impl RtxStream {
+    pub(super) fn new(
+        ssrc: u32,
+        payload_type: u8,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Self {
+        RtxStream {
+            ssrc,
+            payload_type,
+            sequence_number: AtomicU16::new(rand::random::<u16>()),
+            next_rtp_writer: writer,
+        }
+    }
+
+    /// packet wraps an original packet into a retransmission packet: it is sent with the
+    /// ssrc, payload type and sequence numbers of the RTX stream, and the payload is
+    /// prefixed with the original sequence number (OSN).
+    pub(super) fn packet(&self, pkt: &rtp::packet::Packet) -> rtp::packet::Packet {
+        let mut header = pkt.header.clone();
+        header.ssrc = self.ssrc;
+        header.payload_type = self.payload_type;
+        header.sequence_number = self.sequence_number.fetch_add(1, Ordering::SeqCst);
+        header.padding = false;
+
+        let mut payload = BytesMut::with_capacity(2 + pkt.payload.len());
+        payload.put_u16(pkt.header.sequence_number);
+        payload.extend_from_slice(&pkt.payload);
+
+        rtp::packet::Packet {
+            header,
+            payload: payload.freeze(),
+        }
+    }
+
+    /// write retransmits an original packet on the RTX stream
+    pub(super) async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
+        self.next_rtp_writer.write(&self.packet(pkt), a).await
+    }
+}
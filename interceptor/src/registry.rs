@@ -1,6 +1,6 @@
 use std::sync::Arc;
 
-use crate::chain::Chain;
+use crate::chain::{Chain, ErrorPolicy};
 use crate::error::Result;
 use crate::noop::NoOp;
 use crate::{Interceptor, InterceptorBuilder};
@@ -8,7 +8,7 @@ use crate::{Interceptor, InterceptorBuilder};
 /// Registry is a collector for interceptors.
 #[derive(Default)]
 pub struct Registry {
-    builders: Vec<Box<dyn InterceptorBuilder + Send + Sync>>,
+    builders: Vec<(Box<dyn InterceptorBuilder + Send + Sync>, ErrorPolicy)>,
 }
 
 impl Registry {
@@ -18,7 +18,17 @@ impl Registry {
 
     /// add adds a new InterceptorBuilder to the registry.
     pub fn add(&mut self, builder: Box<dyn InterceptorBuilder + Send + Sync>) {
-        self.builders.push(builder);
+        self.add_with_policy(builder, ErrorPolicy::FailFast);
+    }
+
+    /// add_with_policy adds a new InterceptorBuilder to the registry, the read and write errors
+    /// of the interceptors it builds are handled by policy.
+    pub fn add_with_policy(
+        &mut self,
+        builder: Box<dyn InterceptorBuilder + Send + Sync>,
+        policy: ErrorPolicy,
+    ) {
+        self.builders.push((builder, policy));
     }
 
     /// build constructs a single Interceptor from an InterceptorRegistry
@@ -37,8 +47,11 @@ impl Registry {
             return Ok(Chain::new(vec![Arc::new(NoOp {})]));
         }
 
-        let interceptors: Result<Vec<_>> = self.builders.iter().map(|b| b.build(id)).collect();
+        let mut chain = Chain::default();
+        for (builder, policy) in &self.builders {
+            chain.add_with_policy(builder.build(id)?, *policy);
+        }
 
-        Ok(Chain::new(interceptors?))
+        Ok(chain)
     }
 }
@@ -0,0 +1,370 @@
+mod remb_stream;
+#[cfg(test)]
+mod remb_test;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex as SyncMutex};
+use std::time::{Duration, SystemTime};
+
+use remb_stream::{RembStream, RembStreamStats};
+use rtcp::payload_feedbacks::receiver_estimated_maximum_bitrate::ReceiverEstimatedMaximumBitrate;
+use tokio::sync::{mpsc, Mutex};
+use waitgroup::WaitGroup;
+
+use crate::error::{Error, Result};
+use crate::shared_value::SharedValue;
+use crate::stream_info::StreamInfo;
+use crate::*;
+
+type FnTimeGen = Arc<dyn Fn() -> SystemTime + Sync + 'static + Send>;
+
+/// loss fraction above which the estimate is decreased
+const LOSS_HIGH: f64 = 0.1;
+/// loss fraction below which the estimate may increase
+const LOSS_LOW: f64 = 0.02;
+/// increase of the one way delay, in milliseconds per interval, taken as a
+/// sign of the path being overused
+const OVERUSE_THRESHOLD_MS: f64 = 12.5;
+/// the estimate drops to this fraction of the incoming bitrate on overuse
+const OVERUSE_FACTOR: f64 = 0.85;
+/// the estimate grows by this factor each interval the path is not congested
+const INCREASE_FACTOR: f64 = 1.08;
+/// the estimate doesn't grow beyond this multiple of the incoming bitrate, so a
+/// sender not using the bandwidth doesn't make it grow unbounded
+const MAX_INCOMING_RATIO: f64 = 1.5;
+
+/// RembGeneratorBuilder can be used to configure RembGenerator Interceptor
+#[derive(Default)]
+pub struct RembGeneratorBuilder {
+    interval: Option<Duration>,
+    initial_bitrate: Option<u64>,
+    min_bitrate: Option<u64>,
+    max_bitrate: Option<u64>,
+    now: Option<FnTimeGen>,
+    estimated_bitrate: SharedValue,
+}
+
+impl RembGeneratorBuilder {
+    /// with_interval sets the REMB send interval for the interceptor. Defaults to 1s.
+    pub fn with_interval(mut self, interval: Duration) -> RembGeneratorBuilder {
+        self.interval = Some(interval);
+        self
+    }
+
+    /// with_initial_bitrate sets the estimate, in bits per second, sent until
+    /// enough media is received to estimate it. Defaults to 300kbps.
+    pub fn with_initial_bitrate(mut self, bitrate: u64) -> RembGeneratorBuilder {
+        self.initial_bitrate = Some(bitrate);
+        self
+    }
+
+    /// with_min_bitrate sets the lowest estimate, in bits per second. Defaults to 30kbps.
+    pub fn with_min_bitrate(mut self, bitrate: u64) -> RembGeneratorBuilder {
+        self.min_bitrate = Some(bitrate);
+        self
+    }
+
+    /// with_max_bitrate sets the highest estimate, in bits per second. Defaults to 10Mbps.
+    pub fn with_max_bitrate(mut self, bitrate: u64) -> RembGeneratorBuilder {
+        self.max_bitrate = Some(bitrate);
+        self
+    }
+
+    /// with_now_fn sets an alternative for the time.Now function.
+    pub fn with_now_fn(mut self, now: FnTimeGen) -> RembGeneratorBuilder {
+        self.now = Some(now);
+        self
+    }
+
+    /// estimated_bitrate returns the estimate, in bits per second, last sent
+    /// in a REMB by the interceptors built by this builder. It keeps being
+    /// updated after the builder is registered, and is only meaningful when
+    /// the builder is used for a single PeerConnection.
+    pub fn estimated_bitrate(&self) -> SharedValue {
+        self.estimated_bitrate.clone()
+    }
+}
+
+impl InterceptorBuilder for RembGeneratorBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        let initial_bitrate = self.initial_bitrate.unwrap_or(300_000);
+        let (close_tx, close_rx) = mpsc::channel(1);
+        Ok(Arc::new(RembGenerator {
+            internal: Arc::new(RembGeneratorInternal {
+                interval: self.interval.unwrap_or(Duration::from_secs(1)),
+                now: self.now.clone(),
+                estimator: SyncMutex::new(BitrateEstimator {
+                    estimate: initial_bitrate as f64,
+                    min_bitrate: self.min_bitrate.unwrap_or(30_000) as f64,
+                    max_bitrate: self.max_bitrate.unwrap_or(10_000_000) as f64,
+                    last_tick: None,
+                }),
+                estimated_bitrate: self.estimated_bitrate.clone(),
+
+                streams: SyncMutex::new(HashMap::new()),
+                close_rx: Mutex::new(Some(close_rx)),
+            }),
+
+            wg: Mutex::new(Some(WaitGroup::new())),
+            close_tx: Mutex::new(Some(close_tx)),
+        }))
+    }
+}
+
+/// BitrateEstimator adapts the estimate to the loss and the one way delay
+/// variation measured over each interval, in the manner of the loss and delay
+/// based controllers of Google Congestion Control.
+struct BitrateEstimator {
+    estimate: f64,
+    min_bitrate: f64,
+    max_bitrate: f64,
+    last_tick: Option<SystemTime>,
+}
+
+impl BitrateEstimator {
+    /// update adapts the estimate to an interval where `incoming` bits per
+    /// second were received, with a `loss` fraction lost and the one way delay
+    /// increasing by `delay_gradient` milliseconds.
+    fn update(&mut self, incoming: f64, loss: f64, delay_gradient: Option<f64>) {
+        // nothing can be told about the path without media going through it
+        if incoming > 0.0 {
+            if loss > LOSS_HIGH {
+                self.estimate *= 1.0 - 0.5 * loss;
+            } else if delay_gradient.is_some_and(|gradient| gradient > OVERUSE_THRESHOLD_MS) {
+                self.estimate = self.estimate.min(OVERUSE_FACTOR * incoming);
+            } else if loss < LOSS_LOW && self.estimate < MAX_INCOMING_RATIO * incoming {
+                self.estimate =
+                    (self.estimate * INCREASE_FACTOR).min(MAX_INCOMING_RATIO * incoming);
+            }
+        }
+
+        self.estimate = self.estimate.clamp(self.min_bitrate, self.max_bitrate);
+    }
+}
+
+struct RembGeneratorInternal {
+    interval: Duration,
+    now: Option<FnTimeGen>,
+    estimator: SyncMutex<BitrateEstimator>,
+    estimated_bitrate: SharedValue,
+
+    streams: SyncMutex<HashMap<u32, Arc<RembStream>>>,
+    close_rx: Mutex<Option<mpsc::Receiver<()>>>,
+}
+
+impl RembGeneratorInternal {
+    /// tick updates the estimate with what was received since the previous
+    /// tick, and returns the REMB to send if any stream is being received.
+    fn tick(&self, sender_ssrc: u32) -> Option<ReceiverEstimatedMaximumBitrate> {
+        let now = if let Some(f) = &self.now {
+            f()
+        } else {
+            SystemTime::now()
+        };
+
+        let (mut ssrcs, stats): (Vec<u32>, Vec<RembStreamStats>) = {
+            let streams = self.streams.lock().unwrap();
+            streams
+                .iter()
+                .map(|(ssrc, stream)| (*ssrc, stream.tick()))
+                .unzip()
+        };
+
+        let mut estimator = self.estimator.lock().unwrap();
+        if let Some(last_tick) = estimator.last_tick {
+            let elapsed = now.duration_since(last_tick).unwrap_or_default();
+            let bytes: u64 = stats.iter().map(|s| s.bytes).sum();
+            let received: u64 = stats.iter().map(|s| s.received).sum();
+            let expected: u64 = stats.iter().map(|s| s.expected).sum();
+
+            let incoming = if elapsed.is_zero() {
+                0.0
+            } else {
+                (bytes * 8) as f64 / elapsed.as_secs_f64()
+            };
+            let loss = if expected == 0 {
+                0.0
+            } else {
+                1.0 - (received as f64 / expected as f64).min(1.0)
+            };
+            let delay_gradient = stats
+                .iter()
+                .filter_map(|s| s.delay_gradient)
+                .reduce(f64::max);
+            estimator.update(incoming, loss, delay_gradient);
+        }
+        estimator.last_tick = Some(now);
+
+        if ssrcs.is_empty() {
+            return None;
+        }
+        ssrcs.sort_unstable();
+
+        let bitrate = estimator.estimate as u64;
+        self.estimated_bitrate.set(bitrate);
+
+        Some(ReceiverEstimatedMaximumBitrate {
+            sender_ssrc,
+            bitrate: bitrate as f32,
+            ssrcs,
+        })
+    }
+}
+
+/// RembGenerator interceptor estimates the bitrate that can be received and
+/// sends it back to the senders with REMB feedback messages, for the incoming
+/// streams that negotiated goog-remb. It is the legacy bandwidth estimation
+/// of the clients not supporting transport-cc.
+pub struct RembGenerator {
+    internal: Arc<RembGeneratorInternal>,
+
+    pub(crate) wg: Mutex<Option<WaitGroup>>,
+    pub(crate) close_tx: Mutex<Option<mpsc::Sender<()>>>,
+}
+
+impl RembGenerator {
+    /// builder returns a new RembGeneratorBuilder.
+    pub fn builder() -> RembGeneratorBuilder {
+        RembGeneratorBuilder::default()
+    }
+
+    async fn is_closed(&self) -> bool {
+        let close_tx = self.close_tx.lock().await;
+        close_tx.is_none()
+    }
+
+    async fn run(
+        rtcp_writer: Arc<dyn RTCPWriter + Send + Sync>,
+        internal: Arc<RembGeneratorInternal>,
+    ) -> Result<()> {
+        let mut ticker = tokio::time::interval(internal.interval);
+        let mut close_rx = {
+            let mut close_rx = internal.close_rx.lock().await;
+            if let Some(close) = close_rx.take() {
+                close
+            } else {
+                return Err(Error::ErrInvalidCloseRx);
+            }
+        };
+
+        let sender_ssrc = rand::random::<u32>();
+        loop {
+            tokio::select! {
+                _ = ticker.tick() =>{
+                    if let Some(remb) = internal.tick(sender_ssrc) {
+                        let a = Attributes::new();
+                        if let Err(err) = rtcp_writer.write(&[Box::new(remb)], &a).await{
+                            log::warn!("failed sending remb: {}", err);
+                        }
+                    }
+                }
+                _ = close_rx.recv() =>{
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// stream_support_remb checks if a stream supports REMB feedback.
+fn stream_support_remb(info: &StreamInfo) -> bool {
+    info.rtcp_feedback.iter().any(|fb| fb.typ == "goog-remb")
+}
+
+#[async_trait]
+impl Interceptor for RembGenerator {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        if self.is_closed().await {
+            return writer;
+        }
+
+        let mut w = {
+            let wait_group = self.wg.lock().await;
+            wait_group.as_ref().map(|wg| wg.worker())
+        };
+        let writer2 = Arc::clone(&writer);
+        let internal = Arc::clone(&self.internal);
+        tokio::spawn(async move {
+            let _d = w.take();
+            if let Err(err) = RembGenerator::run(writer2, internal).await {
+                log::warn!("bind_rtcp_writer RembGenerator::run got error: {}", err);
+            }
+        });
+
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        if !stream_support_remb(info) {
+            return reader;
+        }
+
+        let stream = Arc::new(RembStream::new(
+            reader,
+            info.clock_rate,
+            self.internal.now.clone(),
+        ));
+        {
+            let mut streams = self.internal.streams.lock().unwrap();
+            streams.insert(info.ssrc, Arc::clone(&stream));
+        }
+
+        stream
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, info: &StreamInfo) {
+        let mut streams = self.internal.streams.lock().unwrap();
+        streams.remove(&info.ssrc);
+    }
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        {
+            let mut close_tx = self.close_tx.lock().await;
+            close_tx.take();
+        }
+
+        {
+            let mut wait_group = self.wg.lock().await;
+            if let Some(wg) = wait_group.take() {
+                wg.wait().await;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,152 @@
+use std::sync::Mutex as SyncMutex;
+use std::time::SystemTime;
+
+use util::MarshalSize;
+
+use super::FnTimeGen;
+use crate::error::Result;
+use crate::*;
+
+/// RembStreamStats is what a stream received since the last REMB interval.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub(super) struct RembStreamStats {
+    pub(super) bytes: u64,
+    pub(super) received: u64,
+    pub(super) expected: u64,
+    /// increase, in milliseconds, of the average one way delay variation
+    /// compared to the previous interval
+    pub(super) delay_gradient: Option<f64>,
+}
+
+#[derive(Default)]
+struct ReceiveLog {
+    bytes: u64,
+    received: u64,
+    ext_highest_seq: Option<i64>,
+    ext_highest_seq_at_tick: Option<i64>,
+    /// arrival time of the first packet, the one way delay variations are
+    /// measured against it
+    base_arrival: Option<SystemTime>,
+    /// last rtp timestamp and its unwrapped value, counted from the one of
+    /// the first packet
+    ext_timestamp: Option<(u32, i64)>,
+    delay_sum: f64,
+    delay_count: u64,
+    last_delay: Option<f64>,
+}
+
+pub(super) struct RembStream {
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+    clock_rate: f64,
+    now: Option<FnTimeGen>,
+    log: SyncMutex<ReceiveLog>,
+}
+
+impl RembStream {
+    pub(super) fn new(
+        parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+        clock_rate: u32,
+        now: Option<FnTimeGen>,
+    ) -> Self {
+        RembStream {
+            parent_rtp_reader,
+            clock_rate: if clock_rate == 0 {
+                90000.0
+            } else {
+                clock_rate as f64
+            },
+            now,
+            log: SyncMutex::new(ReceiveLog::default()),
+        }
+    }
+
+    pub(super) fn on_packet(&self, now: SystemTime, pkt: &rtp::packet::Packet) {
+        let mut log = self.log.lock().unwrap();
+        log.bytes += pkt.marshal_size() as u64;
+        log.received += 1;
+
+        let seq = pkt.header.sequence_number;
+        log.ext_highest_seq = Some(match log.ext_highest_seq {
+            Some(highest) => {
+                let diff = seq.wrapping_sub(highest as u16) as i16;
+                highest + (diff as i64).max(0)
+            }
+            None => seq as i64,
+        });
+        if log.ext_highest_seq_at_tick.is_none() {
+            // the first packet counts as expected
+            log.ext_highest_seq_at_tick = Some(seq as i64 - 1);
+        }
+
+        let timestamp = pkt.header.timestamp;
+        let ext_timestamp = match log.ext_timestamp {
+            Some((last, ext)) => ext + timestamp.wrapping_sub(last) as i32 as i64,
+            None => 0,
+        };
+        log.ext_timestamp = Some((timestamp, ext_timestamp));
+
+        let base_arrival = *log.base_arrival.get_or_insert(now);
+        let arrival = now
+            .duration_since(base_arrival)
+            .unwrap_or_default()
+            .as_secs_f64()
+            * 1000.0;
+        let sent = ext_timestamp as f64 / self.clock_rate * 1000.0;
+        log.delay_sum += arrival - sent;
+        log.delay_count += 1;
+    }
+
+    /// tick returns the stats of the interval that ends and starts a new one
+    pub(super) fn tick(&self) -> RembStreamStats {
+        let mut log = self.log.lock().unwrap();
+
+        let expected = match (log.ext_highest_seq, log.ext_highest_seq_at_tick) {
+            (Some(highest), Some(at_tick)) => (highest - at_tick).max(0) as u64,
+            _ => 0,
+        };
+        log.ext_highest_seq_at_tick = log.ext_highest_seq;
+
+        let delay = (log.delay_count != 0).then(|| log.delay_sum / log.delay_count as f64);
+        let delay_gradient = match (delay, log.last_delay) {
+            (Some(delay), Some(last_delay)) => Some(delay - last_delay),
+            _ => None,
+        };
+        if delay.is_some() {
+            log.last_delay = delay;
+        }
+
+        let stats = RembStreamStats {
+            bytes: log.bytes,
+            received: log.received,
+            expected,
+            delay_gradient,
+        };
+        log.bytes = 0;
+        log.received = 0;
+        log.delay_sum = 0.0;
+        log.delay_count = 0;
+
+        stats
+    }
+}
+
+#[async_trait]
+impl RTPReader for RembStream {
+    /// read a rtp packet
+    async fn read(
+        &self,
+        buf: &mut [u8],
+        a: &Attributes,
+    ) -> Result<(rtp::packet::Packet, Attributes)> {
+        let (pkt, attr) = self.parent_rtp_reader.read(buf, a).await?;
+
+        let now = if let Some(f) = &self.now {
+            f()
+        } else {
+            SystemTime::now()
+        };
+        self.on_packet(now, &pkt);
+
+        Ok((pkt, attr))
+    }
+}
@@ -0,0 +1,172 @@
+use bytes::Bytes;
+use util::Marshal;
+
+use super::*;
+use crate::mock::mock_stream::MockStream;
+use crate::stream_info::RTCPFeedback;
+use crate::test::timeout_or_fail;
+
+fn estimator(estimate: f64) -> BitrateEstimator {
+    BitrateEstimator {
+        estimate,
+        min_bitrate: 30_000.0,
+        max_bitrate: 10_000_000.0,
+        last_tick: None,
+    }
+}
+
+#[test]
+fn test_bitrate_estimator() {
+    // grows while nothing is lost nor delayed
+    let mut e = estimator(1_000_000.0);
+    e.update(1_000_000.0, 0.0, Some(0.0));
+    assert_eq!(e.estimate, 1_080_000.0);
+
+    // but not beyond what the sender uses
+    let mut e = estimator(1_000_000.0);
+    e.update(500_000.0, 0.0, Some(0.0));
+    assert_eq!(e.estimate, 1_000_000.0);
+    let mut e = estimator(1_000_000.0);
+    e.update(700_000.0, 0.0, None);
+    assert_eq!(e.estimate, 1_050_000.0);
+
+    // holds with a moderate loss
+    let mut e = estimator(1_000_000.0);
+    e.update(1_000_000.0, 0.05, Some(0.0));
+    assert_eq!(e.estimate, 1_000_000.0);
+
+    // decreases with a high loss
+    let mut e = estimator(1_000_000.0);
+    e.update(1_000_000.0, 0.2, Some(0.0));
+    assert_eq!(e.estimate, 900_000.0);
+
+    // drops below the incoming bitrate when the delay builds up
+    let mut e = estimator(2_000_000.0);
+    e.update(1_000_000.0, 0.0, Some(20.0));
+    assert_eq!(e.estimate, 850_000.0);
+
+    // holds without media and stays within bounds
+    let mut e = estimator(1_000_000.0);
+    e.update(0.0, 1.0, None);
+    assert_eq!(e.estimate, 1_000_000.0);
+    let mut e = estimator(40_000.0);
+    e.update(20_000.0, 0.0, Some(50.0));
+    assert_eq!(e.estimate, 30_000.0);
+}
+
+#[test]
+fn test_remb_stream_stats() {
+    let reader: Arc<dyn RTPReader + Send + Sync> = Arc::new(RTPReaderFn(Box::new(|_, _| {
+        Box::pin(async move { Err(Error::ErrIoEOF) })
+    })));
+    let stream = RembStream::new(reader, 90000, None);
+    let packet = |sequence_number: u16, timestamp: u32| rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            sequence_number,
+            timestamp,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0u8; 88]),
+    };
+
+    // 20ms of media per packet, 3 and 7 are lost, sequence numbers wrap
+    let base = SystemTime::UNIX_EPOCH;
+    for i in (0u16..10).filter(|i| *i != 3 && *i != 7) {
+        let arrival = base + Duration::from_millis(20 * i as u64);
+        stream.on_packet(arrival, &packet(i.wrapping_sub(5), 1800 * i as u32));
+    }
+    let stats = stream.tick();
+    assert_eq!(stats.bytes, 800);
+    assert_eq!(stats.received, 8);
+    assert_eq!(stats.expected, 10);
+    assert_eq!(stats.delay_gradient, None);
+
+    // the packets now take 30ms more to arrive
+    for i in 10u16..20 {
+        let arrival = base + Duration::from_millis(20 * i as u64 + 30);
+        stream.on_packet(arrival, &packet(i.wrapping_sub(5), 1800 * i as u32));
+    }
+    let stats = stream.tick();
+    assert_eq!(stats.received, 10);
+    assert_eq!(stats.expected, 10);
+    assert!((stats.delay_gradient.unwrap() - 30.0).abs() < 1e-6);
+}
+
+#[test]
+fn test_remb_stream_long_running() {
+    let reader: Arc<dyn RTPReader + Send + Sync> = Arc::new(RTPReaderFn(Box::new(|_, _| {
+        Box::pin(async move { Err(Error::ErrIoEOF) })
+    })));
+    let stream = RembStream::new(reader, 90000, None);
+    let packet = |sequence_number: u16, timestamp: u32| rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            sequence_number,
+            timestamp,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0u8; 88]),
+    };
+
+    // a packet every 10 minutes for 14 hours, the rtp timestamps wrap and are more
+    // than 2^31 apart from the first one, the delay stays the same
+    let base = SystemTime::UNIX_EPOCH;
+    let start_timestamp = u32::MAX - 90000;
+    for interval in 0u64..7 {
+        for i in interval * 12..(interval + 1) * 12 {
+            let arrival = base + Duration::from_secs(600 * i);
+            let timestamp = start_timestamp.wrapping_add((90000 * 600 * i) as u32);
+            stream.on_packet(arrival, &packet(i as u16, timestamp));
+        }
+        let stats = stream.tick();
+        assert_eq!(stats.received, 12);
+        if interval > 0 {
+            assert!(stats.delay_gradient.unwrap().abs() < 1e-6);
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_remb_generator_interceptor() -> Result<()> {
+    let builder = RembGenerator::builder()
+        .with_interval(Duration::from_millis(10))
+        .with_initial_bitrate(1_000_000);
+    let estimated_bitrate = builder.estimated_bitrate();
+    let icpr = builder.build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            clock_rate: 90000,
+            rtcp_feedback: vec![RTCPFeedback {
+                typ: "goog-remb".to_owned(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    let pkts = timeout_or_fail(Duration::from_millis(100), stream.written_rtcp())
+        .await
+        .expect("Write rtcp");
+    let remb = pkts[0]
+        .as_any()
+        .downcast_ref::<ReceiverEstimatedMaximumBitrate>()
+        .expect("REMB packet expected");
+    assert_eq!(remb.bitrate, 1_000_000.0);
+    assert_eq!(remb.ssrcs, vec![1]);
+    assert_eq!(estimated_bitrate.get(), 1_000_000);
+
+    // 1Mbps is sent as a mantissa of 250000 with an exponent of 2
+    let raw = remb.marshal()?;
+    assert_eq!(&raw[12..16], b"REMB");
+    assert_eq!(raw[16], 1);
+    assert_eq!(&raw[17..20], &[0x0b, 0xd0, 0x90]);
+
+    stream.close().await?;
+
+    Ok(())
+}
@@ -1,30 +1,65 @@
+#[cfg(test)]
+mod report_test;
+
 use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime};
 
-use tokio::sync::{mpsc, Mutex};
+use tokio::sync::{mpsc, Mutex, Notify};
+use util::sync::Mutex as SyncMutex;
 use waitgroup::WaitGroup;
 
 pub mod receiver;
+pub mod scheduler;
 pub mod sender;
 
 use receiver::{ReceiverReport, ReceiverReportInternal};
+use scheduler::{FnRandGen, ReportScheduler};
 use sender::{SenderReport, SenderReportInternal};
 
+use crate::chain::Chain;
 use crate::error::Result;
 use crate::{Interceptor, InterceptorBuilder};
 
 type FnTimeGen = Arc<dyn Fn() -> SystemTime + Sync + 'static + Send>;
 
+/// FnNextReport is called with the time of the next report each time it is computed.
+pub type FnNextReport = Arc<dyn Fn(SystemTime) + Sync + 'static + Send>;
+
+/// size of the IPv4 and UDP headers, counted in the size of the RTCP packets
+const IP_UDP_HEADER_SIZE: usize = 28;
+
+/// ReportKind is the kind of reports a ReportBuilder builds an interceptor for.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum ReportKind {
+    #[default]
+    Sender,
+    Receiver,
+    SenderAndReceiver,
+}
+
 /// ReceiverBuilder can be used to configure ReceiverReport Interceptor.
 #[derive(Default)]
 pub struct ReportBuilder {
-    is_rr: bool,
+    kind: ReportKind,
     interval: Option<Duration>,
     now: Option<FnTimeGen>,
+    rtcp_bandwidth: Option<u64>,
+    rand: Option<FnRandGen>,
+    next_report: Option<FnNextReport>,
 }
 
 impl ReportBuilder {
+    /// sender_and_receiver returns a new ReportBuilder of both the SenderReport and the
+    /// ReceiverReport interceptors. They report for the same participant, so when scheduled
+    /// with with_rtcp_bandwidth they share a single schedule and send their reports together.
+    pub fn sender_and_receiver() -> ReportBuilder {
+        ReportBuilder {
+            kind: ReportKind::SenderAndReceiver,
+            ..Default::default()
+        }
+    }
+
     /// with_interval sets send interval for the interceptor.
     pub fn with_interval(mut self, interval: Duration) -> ReportBuilder {
         self.interval = Some(interval);
@@ -37,15 +72,64 @@ impl ReportBuilder {
         self
     }
 
-    fn build_rr(&self) -> ReceiverReport {
+    /// with_rtcp_bandwidth schedules the reports following RFC 3550 section 6.3 so that
+    /// they use at most rtcp_bandwidth bytes per second, with the interval as the minimum
+    /// interval between reports. Without it the reports are sent every interval.
+    pub fn with_rtcp_bandwidth(mut self, rtcp_bandwidth: u64) -> ReportBuilder {
+        self.rtcp_bandwidth = Some(rtcp_bandwidth);
+        self
+    }
+
+    /// with_rand_fn sets an alternative for the random numbers the scheduled
+    /// report intervals are randomized with.
+    pub fn with_rand_fn(mut self, rand: FnRandGen) -> ReportBuilder {
+        self.rand = Some(rand);
+        self
+    }
+
+    /// with_next_report_fn sets a function called with the time of the next
+    /// report each time it is scheduled, when with_rtcp_bandwidth is set.
+    pub fn with_next_report_fn(mut self, next_report: FnNextReport) -> ReportBuilder {
+        self.next_report = Some(next_report);
+        self
+    }
+
+    fn build_schedule(&self, interval: Duration) -> Option<Arc<ReportSchedule>> {
+        let rtcp_bandwidth = self.rtcp_bandwidth?;
+        let now = if let Some(f) = &self.now {
+            f()
+        } else {
+            SystemTime::now()
+        };
+        Some(Arc::new(ReportSchedule {
+            scheduler: SyncMutex::new(ReportScheduler::new(
+                now,
+                rtcp_bandwidth,
+                interval,
+                self.rand.clone(),
+            )),
+            next_report: self.next_report.clone(),
+            rescheduled: Notify::new(),
+        }))
+    }
+
+    fn interval(&self) -> Duration {
+        self.interval.unwrap_or(Duration::from_secs(1))
+    }
+
+    /// build_rr builds a ReceiverReport, whose reports are sent by the SenderReport
+    /// sharing its schedule if scheduled_by_sender is set.
+    fn build_rr(
+        &self,
+        schedule: Option<Arc<ReportSchedule>>,
+        scheduled_by_sender: bool,
+    ) -> ReceiverReport {
         let (close_tx, close_rx) = mpsc::channel(1);
         ReceiverReport {
             internal: Arc::new(ReceiverReportInternal {
-                interval: if let Some(interval) = &self.interval {
-                    *interval
-                } else {
-                    Duration::from_secs(1)
-                },
+                interval: self.interval(),
+                schedule,
+                scheduled_by_sender,
                 now: self.now.clone(),
                 streams: Mutex::new(HashMap::new()),
                 close_rx: Mutex::new(Some(close_rx)),
@@ -56,15 +140,19 @@ impl ReportBuilder {
         }
     }
 
-    fn build_sr(&self) -> SenderReport {
+    /// build_sr builds a SenderReport, which also sends the reports of receiver_reports
+    /// when they share its schedule.
+    fn build_sr(
+        &self,
+        schedule: Option<Arc<ReportSchedule>>,
+        receiver_reports: Option<Arc<ReceiverReportInternal>>,
+    ) -> SenderReport {
         let (close_tx, close_rx) = mpsc::channel(1);
         SenderReport {
             internal: Arc::new(SenderReportInternal {
-                interval: if let Some(interval) = &self.interval {
-                    *interval
-                } else {
-                    Duration::from_secs(1)
-                },
+                interval: self.interval(),
+                schedule,
+                receiver_reports,
                 now: self.now.clone(),
                 streams: Mutex::new(HashMap::new()),
                 close_rx: Mutex::new(Some(close_rx)),
@@ -78,10 +166,84 @@ impl ReportBuilder {
 
 impl InterceptorBuilder for ReportBuilder {
     fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
-        if self.is_rr {
-            Ok(Arc::new(self.build_rr()))
-        } else {
-            Ok(Arc::new(self.build_sr()))
+        let schedule = self.build_schedule(self.interval());
+        match self.kind {
+            ReportKind::Sender => Ok(Arc::new(self.build_sr(schedule, None))),
+            ReportKind::Receiver => Ok(Arc::new(self.build_rr(schedule, false))),
+            ReportKind::SenderAndReceiver => {
+                let scheduled = schedule.is_some();
+                let rr = self.build_rr(schedule.clone(), scheduled);
+                let receiver_reports = scheduled.then(|| Arc::clone(&rr.internal));
+                let sr = self.build_sr(schedule, receiver_reports);
+                Ok(Arc::new(Chain::new(vec![Arc::new(sr), Arc::new(rr)])))
+            }
         }
     }
 }
+
+/// ReportSchedule holds the ReportScheduler of an interceptor whose reports are
+/// scheduled following RFC 3550.
+pub(crate) struct ReportSchedule {
+    scheduler: SyncMutex<ReportScheduler>,
+    next_report: Option<FnNextReport>,
+    /// rescheduled wakes the report loop when the timer is pulled in
+    rescheduled: Notify,
+}
+
+impl ReportSchedule {
+    /// wait returns how long to wait from now until the next report.
+    pub(crate) fn wait(&self, now: SystemTime) -> Duration {
+        let next_report_time = self.scheduler.lock().next_report_time();
+        if let Some(f) = &self.next_report {
+            f(next_report_time);
+        }
+        next_report_time
+            .duration_since(now)
+            .unwrap_or(Duration::ZERO)
+    }
+
+    /// rescheduled completes when the timer of schedule is pulled in by a member
+    /// leaving, and never if the reports are not scheduled.
+    pub(crate) async fn rescheduled(schedule: &Option<Arc<ReportSchedule>>) {
+        match schedule {
+            Some(schedule) => schedule.rescheduled.notified().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// on_timer_expired returns true if the reports are due at now.
+    pub(crate) fn on_timer_expired(&self, now: SystemTime, we_sent: bool) -> bool {
+        let mut scheduler = self.scheduler.lock();
+        scheduler.set_we_sent(we_sent);
+        scheduler.on_timer_expired(now)
+    }
+
+    /// on_reports_sent accounts for the reports of size bytes sent at now.
+    pub(crate) fn on_reports_sent(&self, now: SystemTime, size: usize) {
+        self.scheduler
+            .lock()
+            .on_report_sent(now, size + IP_UDP_HEADER_SIZE);
+    }
+
+    /// on_rtcp_received accounts for received RTCP packets of size bytes.
+    pub(crate) fn on_rtcp_received(&self, size: usize) {
+        self.scheduler
+            .lock()
+            .on_rtcp_received(size + IP_UDP_HEADER_SIZE);
+    }
+
+    pub(crate) fn member_joined(&self, is_sender: bool) {
+        self.scheduler.lock().member_joined(is_sender);
+    }
+
+    pub(crate) fn member_left(&self, now: SystemTime, was_sender: bool) {
+        self.scheduler.lock().member_left(now, was_sender);
+        self.rescheduled.notify_one();
+    }
+
+    /// leave marks the participant as leaving at now with a BYE of size bytes, and
+    /// returns true if the BYE can be sent right away.
+    pub(crate) fn leave(&self, now: SystemTime, size: usize) -> bool {
+        self.scheduler.lock().leave(now, size + IP_UDP_HEADER_SIZE)
+    }
+}
@@ -7,6 +7,7 @@ use std::time::{Duration, SystemTime};
 
 use receiver_stream::ReceiverStream;
 use tokio::sync::{mpsc, Mutex};
+use util::MarshalSize;
 use waitgroup::WaitGroup;
 
 use super::*;
@@ -15,6 +16,9 @@ use crate::*;
 
 pub(crate) struct ReceiverReportInternal {
     pub(crate) interval: Duration,
+    pub(crate) schedule: Option<Arc<ReportSchedule>>,
+    /// scheduled_by_sender is set when the SenderReport sharing the schedule sends the reports
+    pub(crate) scheduled_by_sender: bool,
     pub(crate) now: Option<FnTimeGen>,
     pub(crate) streams: Mutex<HashMap<u32, Arc<ReceiverStream>>>,
     pub(crate) close_rx: Mutex<Option<mpsc::Receiver<()>>>,
@@ -40,6 +44,10 @@ impl RTCPReader for ReceiverReportRtcpReader {
             SystemTime::now()
         };
 
+        if let Some(schedule) = &self.internal.schedule {
+            schedule.on_rtcp_received(pkts.iter().map(|p| p.marshal_size()).sum());
+        }
+
         for p in &pkts {
             if let Some(sr) = p
                 .as_any()
@@ -71,7 +79,7 @@ impl ReceiverReport {
     /// builder returns a new ReportBuilder.
     pub fn builder() -> ReportBuilder {
         ReportBuilder {
-            is_rr: true,
+            kind: ReportKind::Receiver,
             ..Default::default()
         }
     }
@@ -96,29 +104,43 @@ impl ReceiverReport {
         };
 
         loop {
+            let now = if let Some(f) = &internal.now {
+                f()
+            } else {
+                SystemTime::now()
+            };
+            let wait = internal.schedule.as_ref().map(|s| s.wait(now));
+
             tokio::select! {
-                _ = ticker.tick() =>{
+                _ = ticker.tick(), if wait.is_none() =>{
                     // TODO(cancel safety): This branch isn't cancel safe
-
+                    internal.send_reports(&rtcp_writer).await;
+                }
+                _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() =>{
                     let now = if let Some(f) = &internal.now {
                         f()
                     } else {
                         SystemTime::now()
                     };
-                    let streams:Vec<Arc<ReceiverStream>> = {
-                        let m = internal.streams.lock().await;
-                        m.values().cloned().collect()
-                    };
-                    for stream in streams {
-                        let pkt = stream.generate_report(now);
-
-                        let a = Attributes::new();
-                        if let Err(err) = rtcp_writer.write(&[Box::new(pkt)], &a).await{
-                            log::warn!("failed sending: {}", err);
+                    if let Some(schedule) = &internal.schedule {
+                        if schedule.on_timer_expired(now, false) {
+                            let size = internal.send_reports(&rtcp_writer).await;
+                            schedule.on_reports_sent(now, size);
                         }
                     }
                 }
+                _ = ReportSchedule::rescheduled(&internal.schedule) =>{}
                 _ = close_rx.recv() =>{
+                    // there are no local streams to send a BYE for, leaving only
+                    // stops the schedule from counting the members
+                    if let Some(schedule) = &internal.schedule {
+                        let now = if let Some(f) = &internal.now {
+                            f()
+                        } else {
+                            SystemTime::now()
+                        };
+                        schedule.leave(now, 0);
+                    }
                     return Ok(());
                 }
             }
@@ -126,6 +148,37 @@ impl ReceiverReport {
     }
 }
 
+impl ReceiverReportInternal {
+    /// send_reports sends a receiver report for each remote stream, and returns
+    /// the size of the sent reports.
+    pub(crate) async fn send_reports(
+        &self,
+        rtcp_writer: &Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> usize {
+        let now = if let Some(f) = &self.now {
+            f()
+        } else {
+            SystemTime::now()
+        };
+        let streams: Vec<Arc<ReceiverStream>> = {
+            let m = self.streams.lock().await;
+            m.values().cloned().collect()
+        };
+
+        let mut size = 0;
+        for stream in streams {
+            let pkt = stream.generate_report(now);
+            size += pkt.marshal_size();
+
+            let a = Attributes::new();
+            if let Err(err) = rtcp_writer.write(&[Box::new(pkt)], &a).await {
+                log::warn!("failed sending: {}", err);
+            }
+        }
+        size
+    }
+}
+
 #[async_trait]
 impl Interceptor for ReceiverReport {
     /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
@@ -146,7 +199,7 @@ impl Interceptor for ReceiverReport {
         &self,
         writer: Arc<dyn RTCPWriter + Send + Sync>,
     ) -> Arc<dyn RTCPWriter + Send + Sync> {
-        if self.is_closed().await {
+        if self.is_closed().await || self.internal.scheduled_by_sender {
             return writer;
         }
 
@@ -194,7 +247,12 @@ impl Interceptor for ReceiverReport {
         ));
         {
             let mut streams = self.internal.streams.lock().await;
-            streams.insert(info.ssrc, Arc::clone(&stream));
+            if streams.insert(info.ssrc, Arc::clone(&stream)).is_none() {
+                // each remote stream is a member sending RTP
+                if let Some(schedule) = &self.internal.schedule {
+                    schedule.member_joined(true);
+                }
+            }
         }
 
         stream
@@ -203,7 +261,16 @@ impl Interceptor for ReceiverReport {
     /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
     async fn unbind_remote_stream(&self, info: &StreamInfo) {
         let mut streams = self.internal.streams.lock().await;
-        streams.remove(&info.ssrc);
+        if streams.remove(&info.ssrc).is_some() {
+            if let Some(schedule) = &self.internal.schedule {
+                let now = if let Some(f) = &self.internal.now {
+                    f()
+                } else {
+                    SystemTime::now()
+                };
+                schedule.member_left(now, true);
+            }
+        }
     }
 
     /// close closes the Interceptor, cleaning up any data if necessary.
@@ -2,6 +2,8 @@
 use chrono::prelude::*;
 use rtp::extension::abs_send_time_extension::unix2ntp;
 
+use util::sync::Mutex as SyncMutex;
+
 use super::*;
 use crate::mock::mock_stream::MockStream;
 use crate::mock::mock_time::MockTime;
@@ -770,3 +772,74 @@ async fn test_receiver_interceptor_delay() -> Result<()> {
     stream.close().await?;
     Ok(())
 }
+
+#[tokio::test(start_paused = true)]
+async fn test_receiver_interceptor_scheduled_reports() -> Result<()> {
+    let start = tokio::time::Instant::now();
+    let time_gen = Arc::new(move || SystemTime::UNIX_EPOCH + start.elapsed());
+    let next_reports = Arc::new(SyncMutex::new(vec![]));
+    let next_report_fn = {
+        let next_reports = Arc::clone(&next_reports);
+        Arc::new(move |t: SystemTime| {
+            let mut next_reports = next_reports.lock();
+            if next_reports.last() != Some(&t) {
+                next_reports.push(t);
+            }
+        })
+    };
+
+    let icpr: Arc<dyn Interceptor + Send + Sync> = ReceiverReport::builder()
+        .with_interval(Duration::from_secs(1))
+        .with_rtcp_bandwidth(1000)
+        .with_rand_fn(Arc::new(|| 0.5))
+        .with_now_fn(time_gen)
+        .with_next_report_fn(next_report_fn)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // the first report is sent after half the minimum interval, randomized by
+    // 0.5 / (e - 3/2) to compensate for the timer reconsideration
+    let compensation = std::f64::consts::E - 1.5;
+    let pkts = stream.written_rtcp().await.unwrap();
+    assert_eq!(pkts.len(), 1);
+    let first = start.elapsed();
+    assert!(first >= Duration::from_millis(500).mul_f64(1.0 / compensation));
+    assert!(
+        first < Duration::from_millis(500).mul_f64(1.0 / compensation) + Duration::from_millis(2)
+    );
+
+    // the next ones after the minimum interval
+    let pkts = stream.written_rtcp().await.unwrap();
+    assert_eq!(pkts.len(), 1);
+    let second = start.elapsed();
+    assert!(second - first >= Duration::from_secs(1).mul_f64(1.0 / compensation));
+    assert!(
+        second - first
+            < Duration::from_secs(1).mul_f64(1.0 / compensation) + Duration::from_millis(2)
+    );
+
+    {
+        let next_reports = next_reports.lock();
+        assert!(next_reports.len() >= 3);
+        assert_eq!(
+            next_reports[0],
+            SystemTime::UNIX_EPOCH + Duration::from_millis(500).mul_f64(1.0 / compensation)
+        );
+        assert_eq!(
+            next_reports[1],
+            SystemTime::UNIX_EPOCH + first + Duration::from_secs(1).mul_f64(1.0 / compensation)
+        );
+    }
+
+    stream.close().await?;
+    Ok(())
+}
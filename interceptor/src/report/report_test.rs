@@ -0,0 +1,115 @@
+use bytes::Bytes;
+use rtcp::goodbye::Goodbye;
+use rtcp::receiver_report;
+use rtcp::sender_report;
+
+use super::*;
+use crate::error::Result;
+use crate::mock::mock_stream::MockStream;
+use crate::stream_info::StreamInfo;
+
+#[tokio::test(start_paused = true)]
+async fn test_report_interceptor_shared_schedule() -> Result<()> {
+    let start = tokio::time::Instant::now();
+    let time_gen = Arc::new(move || SystemTime::UNIX_EPOCH + start.elapsed());
+
+    let icpr: Arc<dyn Interceptor + Send + Sync> = ReportBuilder::sender_and_receiver()
+        .with_interval(Duration::from_secs(1))
+        .with_rtcp_bandwidth(10)
+        .with_rand_fn(Arc::new(|| 0.5))
+        .with_now_fn(time_gen)
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // each report is a sender report followed by a receiver report, sent at once on
+    // the expiry of the one timer they share. The interval is bound by the bandwidth,
+    // so it depends on both the remote member the receiver counts and the local
+    // stream the sender counts.
+    for _ in 0..3 {
+        let pkts = stream.written_rtcp().await.unwrap();
+        assert_eq!(pkts.len(), 1);
+        assert!(pkts[0]
+            .as_any()
+            .downcast_ref::<sender_report::SenderReport>()
+            .is_some());
+        let sent = start.elapsed();
+
+        let pkts = stream.written_rtcp().await.unwrap();
+        assert_eq!(pkts.len(), 1);
+        assert!(pkts[0]
+            .as_any()
+            .downcast_ref::<receiver_report::ReceiverReport>()
+            .is_some());
+        assert_eq!(start.elapsed(), sent);
+    }
+
+    stream.close().await?;
+
+    // leaving a small session sends the BYE for the local stream right away
+    let pkts = stream.last_written_rtcp().await.unwrap();
+    assert_eq!(pkts.len(), 1);
+    assert_eq!(
+        pkts[0].as_any().downcast_ref::<Goodbye>(),
+        Some(&Goodbye {
+            sources: vec![123456],
+            reason: Bytes::new(),
+        })
+    );
+
+    Ok(())
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_report_interceptor_unscheduled() -> Result<()> {
+    let icpr: Arc<dyn Interceptor + Send + Sync> = ReportBuilder::sender_and_receiver()
+        .with_interval(Duration::from_millis(50))
+        .build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 123456,
+            clock_rate: 90000,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    // without a schedule both interceptors send their reports every interval
+    let mut sender_reports = 0;
+    let mut receiver_reports = 0;
+    while sender_reports < 2 || receiver_reports < 2 {
+        let pkts = stream.written_rtcp().await.unwrap();
+        if pkts[0]
+            .as_any()
+            .downcast_ref::<sender_report::SenderReport>()
+            .is_some()
+        {
+            sender_reports += 1;
+        } else if pkts[0]
+            .as_any()
+            .downcast_ref::<receiver_report::ReceiverReport>()
+            .is_some()
+        {
+            receiver_reports += 1;
+        }
+    }
+
+    stream.close().await?;
+
+    // and no BYE is sent on close
+    if let Some(pkts) = stream.last_written_rtcp().await {
+        assert!(pkts[0].as_any().downcast_ref::<Goodbye>().is_none());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,239 @@
+#[cfg(test)]
+mod scheduler_test;
+
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// FnRandGen returns a uniformly distributed number in [0, 1).
+pub type FnRandGen = Arc<dyn Fn() -> f64 + Sync + 'static + Send>;
+
+/// fraction of the RTCP bandwidth shared by the senders when they are at most
+/// this fraction of the members, RFC 3550 section 6.2
+const SENDER_FRACTION: f64 = 0.25;
+/// e - 3/2, compensates the randomized interval for the timer reconsideration
+/// converging to a lower value than intended, RFC 3550 section 6.3.1
+const COMPENSATION: f64 = std::f64::consts::E - 1.5;
+/// average size, in bytes, assumed for the RTCP packets before any is sent or
+/// received: a sender report with one reception block and the IP/UDP headers
+const INITIAL_AVG_RTCP_SIZE: f64 = 80.0;
+/// a participant leaving a session with fewer members sends its BYE immediately,
+/// RFC 3550 section 6.3.7
+const BYE_RECONSIDERATION_MEMBERS: usize = 50;
+
+/// ReportScheduler computes when RTCP reports are sent following the rules of
+/// RFC 3550 section 6.3: the interval grows with the members of the session so
+/// that RTCP keeps within its bandwidth, is randomized, and is reconsidered when
+/// it expires, when members leave and when the participant itself leaves.
+///
+/// The scheduler doesn't read any clock, the current time is passed to each call,
+/// so with a deterministic rand function the schedule is fully reproducible.
+pub struct ReportScheduler {
+    rtcp_bandwidth: f64,
+    min_interval: Duration,
+    rand: FnRandGen,
+
+    /// tp is the last time a report was sent
+    tp: SystemTime,
+    /// tn is the next time a report is scheduled
+    tn: SystemTime,
+    /// pmembers is the count of members when tn was last computed
+    pmembers: usize,
+    members: usize,
+    senders: usize,
+    we_sent: bool,
+    avg_rtcp_size: f64,
+    initial: bool,
+    leaving: bool,
+}
+
+impl ReportScheduler {
+    /// new schedules the first report of a participant joining at now. rtcp_bandwidth
+    /// is in bytes per second and min_interval is the minimum interval between reports,
+    /// which is halved before the first report. rand defaults to a uniform random number.
+    pub fn new(
+        now: SystemTime,
+        rtcp_bandwidth: u64,
+        min_interval: Duration,
+        rand: Option<FnRandGen>,
+    ) -> Self {
+        let mut s = ReportScheduler {
+            rtcp_bandwidth: rtcp_bandwidth as f64,
+            min_interval,
+            rand: rand.unwrap_or_else(|| Arc::new(rand::random::<f64>)),
+
+            tp: now,
+            tn: now,
+            pmembers: 1,
+            members: 1,
+            senders: 0,
+            we_sent: false,
+            avg_rtcp_size: INITIAL_AVG_RTCP_SIZE,
+            initial: true,
+            leaving: false,
+        };
+        s.tn = now + s.randomized_interval();
+        s
+    }
+
+    /// interval returns the deterministic report interval T for the current state of
+    /// the session, before it is randomized.
+    pub fn interval(&self) -> Duration {
+        let min_interval = if self.initial {
+            self.min_interval / 2
+        } else {
+            self.min_interval
+        };
+
+        let mut n = self.members as f64;
+        let mut bandwidth = self.rtcp_bandwidth;
+        if self.senders as f64 <= self.members as f64 * SENDER_FRACTION {
+            if self.we_sent {
+                bandwidth *= SENDER_FRACTION;
+                n = self.senders as f64;
+            } else {
+                bandwidth *= 1.0 - SENDER_FRACTION;
+                n -= self.senders as f64;
+            }
+        }
+
+        if bandwidth <= 0.0 {
+            return min_interval;
+        }
+        Duration::from_secs_f64(self.avg_rtcp_size * n / bandwidth).max(min_interval)
+    }
+
+    fn randomized_interval(&self) -> Duration {
+        let factor = ((self.rand)().clamp(0.0, 1.0) + 0.5) / COMPENSATION;
+        self.interval().mul_f64(factor)
+    }
+
+    /// next_report_time returns the time the report timer expires.
+    pub fn next_report_time(&self) -> SystemTime {
+        self.tn
+    }
+
+    /// last_report_time returns the time the last report was sent, or the time
+    /// the participant joined if none was.
+    pub fn last_report_time(&self) -> SystemTime {
+        self.tp
+    }
+
+    /// members returns the count of members of the session, including the participant.
+    pub fn members(&self) -> usize {
+        self.members
+    }
+
+    /// senders returns the count of members that sent RTP since the last reports.
+    pub fn senders(&self) -> usize {
+        self.senders
+    }
+
+    /// is_leaving returns true once leave was called.
+    pub fn is_leaving(&self) -> bool {
+        self.leaving
+    }
+
+    /// set_we_sent records whether the participant sent RTP since its last reports,
+    /// it is counted among the senders while it does.
+    pub fn set_we_sent(&mut self, we_sent: bool) {
+        if self.leaving || self.we_sent == we_sent {
+            return;
+        }
+        self.we_sent = we_sent;
+        if we_sent {
+            self.senders += 1;
+        } else {
+            self.senders = self.senders.saturating_sub(1);
+        }
+    }
+
+    /// member_joined counts a new member of the session. The timer is left as it is,
+    /// the larger session is only taken into account when the timer expires.
+    pub fn member_joined(&mut self, is_sender: bool) {
+        if self.leaving {
+            return;
+        }
+        self.members += 1;
+        if is_sender {
+            self.senders += 1;
+        }
+    }
+
+    /// member_left removes a member that sent a BYE or timed out and pulls the timer
+    /// in proportionally to the smaller session, RFC 3550 section 6.3.4. Once leaving,
+    /// it counts the BYEs of the other members instead, RFC 3550 section 6.3.7.
+    pub fn member_left(&mut self, now: SystemTime, was_sender: bool) {
+        if self.leaving {
+            self.members += 1;
+            return;
+        }
+
+        self.members = self.members.saturating_sub(1).max(1);
+        if was_sender {
+            self.senders = self.senders.saturating_sub(1);
+        }
+
+        if self.members < self.pmembers {
+            let ratio = self.members as f64 / self.pmembers as f64;
+            if let Ok(remaining) = self.tn.duration_since(now) {
+                self.tn = now + remaining.mul_f64(ratio);
+            }
+            if let Ok(elapsed) = now.duration_since(self.tp) {
+                self.tp = now - elapsed.mul_f64(ratio);
+            }
+            self.pmembers = self.members;
+        }
+    }
+
+    /// on_rtcp_received accounts for a received compound RTCP packet of size bytes,
+    /// including the IP and UDP headers, in the average RTCP packet size.
+    pub fn on_rtcp_received(&mut self, size: usize) {
+        self.update_avg_rtcp_size(size);
+    }
+
+    /// on_timer_expired reconsiders the interval when the timer expires at now. It
+    /// returns true if the report, or the BYE once leaving, is due and should be sent,
+    /// otherwise the timer is rescheduled to the later next_report_time.
+    pub fn on_timer_expired(&mut self, now: SystemTime) -> bool {
+        self.tn = self.tp + self.randomized_interval();
+        self.tn <= now
+    }
+
+    /// on_report_sent accounts for the report of size bytes, including the IP and UDP
+    /// headers, sent at now and schedules the next one.
+    pub fn on_report_sent(&mut self, now: SystemTime, size: usize) {
+        self.update_avg_rtcp_size(size);
+        self.tp = now;
+        self.initial = false;
+        self.tn = now + self.randomized_interval();
+        self.pmembers = self.members;
+    }
+
+    /// leave is called when the participant leaves the session at now with a BYE of
+    /// bye_size bytes. It returns true if the BYE can be sent immediately, otherwise
+    /// the BYE is scheduled like an initial report of a session whose members are the
+    /// participants that sent a BYE since, RFC 3550 section 6.3.7.
+    pub fn leave(&mut self, now: SystemTime, bye_size: usize) -> bool {
+        if self.leaving {
+            return false;
+        }
+        self.leaving = true;
+        if self.members < BYE_RECONSIDERATION_MEMBERS {
+            return true;
+        }
+
+        self.tp = now;
+        self.members = 1;
+        self.pmembers = 1;
+        self.initial = true;
+        self.we_sent = false;
+        self.senders = 0;
+        self.avg_rtcp_size = bye_size as f64;
+        self.tn = now + self.randomized_interval();
+        false
+    }
+
+    fn update_avg_rtcp_size(&mut self, size: usize) {
+        self.avg_rtcp_size = size as f64 / 16.0 + self.avg_rtcp_size * 15.0 / 16.0;
+    }
+}
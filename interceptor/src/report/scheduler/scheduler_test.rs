@@ -0,0 +1,247 @@
+use super::*;
+
+fn fixed_rand(r: f64) -> Option<FnRandGen> {
+    Some(Arc::new(move || r))
+}
+
+fn secs(secs: f64) -> Duration {
+    Duration::from_secs_f64(secs)
+}
+
+/// scheduled returns the randomized interval for rand = 0.5
+fn scheduled(interval: Duration) -> Duration {
+    interval.mul_f64(1.0 / COMPENSATION)
+}
+
+#[test]
+fn test_scheduler_initial_interval() {
+    let t0 = SystemTime::UNIX_EPOCH;
+    let s = ReportScheduler::new(t0, 1000, Duration::from_secs(5), fixed_rand(0.5));
+
+    assert_eq!(s.members(), 1);
+    assert_eq!(s.senders(), 0);
+    assert_eq!(s.last_report_time(), t0);
+    // the minimum interval is halved before the first report
+    assert_eq!(s.interval(), Duration::from_millis(2500));
+    assert_eq!(
+        s.next_report_time(),
+        t0 + scheduled(Duration::from_millis(2500))
+    );
+}
+
+#[test]
+fn test_scheduler_randomization() {
+    let t0 = SystemTime::UNIX_EPOCH;
+    let min_interval = Duration::from_secs(5);
+
+    let s = ReportScheduler::new(t0, 1000, min_interval, fixed_rand(0.0));
+    assert_eq!(
+        s.next_report_time(),
+        t0 + Duration::from_millis(2500).mul_f64(0.5 / COMPENSATION)
+    );
+
+    let s = ReportScheduler::new(t0, 1000, min_interval, fixed_rand(1.0));
+    assert_eq!(
+        s.next_report_time(),
+        t0 + Duration::from_millis(2500).mul_f64(1.5 / COMPENSATION)
+    );
+
+    for _ in 0..100 {
+        let s = ReportScheduler::new(t0, 1000, min_interval, None);
+        let t = s.next_report_time().duration_since(t0).unwrap();
+        assert!(t >= Duration::from_millis(2500).mul_f64(0.5 / COMPENSATION));
+        assert!(t <= Duration::from_millis(2500).mul_f64(1.5 / COMPENSATION));
+    }
+}
+
+#[test]
+fn test_scheduler_bandwidth_share() {
+    let t0 = SystemTime::UNIX_EPOCH;
+    let mut s = ReportScheduler::new(t0, 1000, Duration::from_secs(5), fixed_rand(0.5));
+
+    // 100 receivers share 3/4 of the bandwidth
+    for _ in 0..99 {
+        s.member_joined(false);
+    }
+    assert_eq!(s.members(), 100);
+    assert_eq!(s.interval(), secs(80.0 * 100.0 / 750.0));
+
+    // a single sender, which is us, gets 1/4 of the bandwidth to itself
+    s.set_we_sent(true);
+    assert_eq!(s.senders(), 1);
+    assert_eq!(s.interval(), Duration::from_millis(2500));
+
+    // the 99 other receivers still share 3/4 of the bandwidth
+    s.set_we_sent(false);
+    s.member_joined(true);
+    assert_eq!(s.members(), 101);
+    assert_eq!(s.senders(), 1);
+    assert_eq!(s.interval(), secs(80.0 * 100.0 / 750.0));
+
+    // with more than 1/4 of senders, all members share the whole bandwidth
+    for _ in 0..49 {
+        s.member_joined(true);
+    }
+    assert_eq!(s.senders(), 50);
+    assert_eq!(s.interval(), secs(80.0 * 150.0 / 1000.0));
+}
+
+#[test]
+fn test_scheduler_joining_members() {
+    let t0 = SystemTime::UNIX_EPOCH;
+    let mut s = ReportScheduler::new(t0, 1000, Duration::from_secs(5), fixed_rand(0.5));
+    let tn0 = s.next_report_time();
+
+    // members joining don't move the timer
+    for _ in 0..99 {
+        s.member_joined(false);
+    }
+    assert_eq!(s.next_report_time(), tn0);
+
+    // but when it expires the report is reconsidered and postponed
+    assert!(!s.on_timer_expired(tn0));
+    let tn1 = s.next_report_time();
+    assert_eq!(tn1, t0 + scheduled(secs(80.0 * 100.0 / 750.0)));
+    assert!(tn1 > tn0);
+
+    // the timer expiring early doesn't send either
+    assert!(!s.on_timer_expired(tn1 - Duration::from_millis(1)));
+    assert_eq!(s.next_report_time(), tn1);
+
+    assert!(s.on_timer_expired(tn1));
+    s.on_report_sent(tn1, 80);
+    assert_eq!(s.last_report_time(), tn1);
+    assert_eq!(
+        s.next_report_time(),
+        tn1 + scheduled(secs(80.0 * 100.0 / 750.0))
+    );
+}
+
+#[test]
+fn test_scheduler_avg_rtcp_size() {
+    let t0 = SystemTime::UNIX_EPOCH;
+    let mut s = ReportScheduler::new(t0, 1000, Duration::ZERO, fixed_rand(0.5));
+    for _ in 0..9 {
+        s.member_joined(false);
+    }
+    assert_eq!(s.interval(), secs(80.0 * 10.0 / 750.0));
+
+    s.on_rtcp_received(240);
+    assert_eq!(s.interval(), secs(90.0 * 10.0 / 750.0));
+
+    let t1 = t0 + Duration::from_secs(1);
+    s.on_report_sent(t1, 250);
+    assert_eq!(s.interval(), secs(100.0 * 10.0 / 750.0));
+    assert_eq!(
+        s.next_report_time(),
+        t1 + scheduled(secs(100.0 * 10.0 / 750.0))
+    );
+}
+
+#[test]
+fn test_scheduler_reverse_reconsideration() {
+    let t0 = SystemTime::UNIX_EPOCH;
+    let mut s = ReportScheduler::new(t0, 100, Duration::from_secs(5), fixed_rand(0.5));
+    for _ in 0..9 {
+        s.member_joined(false);
+    }
+
+    let t1 = t0 + Duration::from_secs(1);
+    s.on_report_sent(t1, 80);
+    let tn = s.next_report_time();
+    assert_eq!(tn, t1 + scheduled(secs(80.0 * 10.0 / 75.0)));
+
+    // a member joining and leaving before the next report doesn't move the timer
+    let now = t1 + Duration::from_secs(4);
+    s.member_joined(false);
+    s.member_left(now, false);
+    assert_eq!(s.members(), 10);
+    assert_eq!(s.next_report_time(), tn);
+    assert_eq!(s.last_report_time(), t1);
+
+    // a member leaving pulls both tn and tp towards now
+    s.member_left(now, false);
+    assert_eq!(s.members(), 9);
+    assert_eq!(
+        s.next_report_time(),
+        now + tn.duration_since(now).unwrap().mul_f64(0.9)
+    );
+    assert_eq!(
+        s.last_report_time(),
+        now - Duration::from_secs(4).mul_f64(0.9)
+    );
+
+    // half the members leaving roughly halves the remaining time
+    let tn = s.next_report_time();
+    for _ in 0..4 {
+        s.member_left(now, false);
+    }
+    assert_eq!(s.members(), 5);
+    let remaining = tn.duration_since(now).unwrap().as_secs_f64();
+    let reconsidered = s
+        .next_report_time()
+        .duration_since(now)
+        .unwrap()
+        .as_secs_f64();
+    assert!((reconsidered - remaining * 5.0 / 9.0).abs() < 1e-6);
+
+    // the participant itself is always a member
+    for _ in 0..10 {
+        s.member_left(now, false);
+    }
+    assert_eq!(s.members(), 1);
+}
+
+#[test]
+fn test_scheduler_bye_small_session() {
+    let t0 = SystemTime::UNIX_EPOCH;
+    let mut s = ReportScheduler::new(t0, 1000, Duration::from_secs(5), fixed_rand(0.5));
+    for _ in 0..48 {
+        s.member_joined(false);
+    }
+
+    assert!(s.leave(t0, 60), "BYE is sent immediately with 49 members");
+    assert!(s.is_leaving());
+    assert!(!s.leave(t0, 60), "leave only sends a single BYE");
+}
+
+#[test]
+fn test_scheduler_bye_reconsideration() {
+    let t0 = SystemTime::UNIX_EPOCH;
+    let mut s = ReportScheduler::new(t0, 1000, Duration::from_secs(5), fixed_rand(0.5));
+    s.set_we_sent(true);
+    for _ in 0..99 {
+        s.member_joined(false);
+    }
+
+    let t1 = t0 + Duration::from_secs(10);
+    s.on_report_sent(t1, 80);
+
+    // leaving a large session schedules the BYE like an initial report of a
+    // session with the participant as its only member
+    let now = t1 + Duration::from_secs(1);
+    assert!(!s.leave(now, 60));
+    assert_eq!(s.members(), 1);
+    assert_eq!(s.senders(), 0);
+    assert_eq!(s.last_report_time(), now);
+    let tn = s.next_report_time();
+    assert_eq!(tn, now + scheduled(Duration::from_millis(2500)));
+
+    // only the BYEs of the other members are counted from now on
+    s.member_joined(true);
+    s.set_we_sent(true);
+    assert_eq!(s.members(), 1);
+    assert_eq!(s.senders(), 0);
+    for _ in 0..60 {
+        s.member_left(now, false);
+    }
+    assert_eq!(s.members(), 61);
+    assert_eq!(s.next_report_time(), tn);
+
+    // so many members leaving at once postpone the BYE
+    assert!(!s.on_timer_expired(tn));
+    let tn = s.next_report_time();
+    assert_eq!(tn, now + scheduled(secs(60.0 * 61.0 / 750.0)));
+
+    assert!(s.on_timer_expired(tn));
+}
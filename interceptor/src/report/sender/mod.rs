@@ -5,8 +5,11 @@ mod sender_test;
 use std::collections::HashMap;
 use std::time::{Duration, SystemTime};
 
+use bytes::Bytes;
+use rtcp::goodbye::Goodbye;
 use sender_stream::SenderStream;
 use tokio::sync::{mpsc, Mutex};
+use util::MarshalSize;
 use waitgroup::WaitGroup;
 
 use super::*;
@@ -15,6 +18,9 @@ use crate::*;
 
 pub(crate) struct SenderReportInternal {
     pub(crate) interval: Duration,
+    pub(crate) schedule: Option<Arc<ReportSchedule>>,
+    /// receiver_reports are sent along with the sender reports when they share the schedule
+    pub(crate) receiver_reports: Option<Arc<ReceiverReportInternal>>,
     pub(crate) now: Option<FnTimeGen>,
     pub(crate) streams: Mutex<HashMap<u32, Arc<SenderStream>>>,
     pub(crate) close_rx: Mutex<Option<mpsc::Receiver<()>>>,
@@ -32,7 +38,7 @@ impl SenderReport {
     /// builder returns a new ReportBuilder.
     pub fn builder() -> ReportBuilder {
         ReportBuilder {
-            is_rr: false,
+            kind: ReportKind::Sender,
             ..Default::default()
         }
     }
@@ -57,28 +63,41 @@ impl SenderReport {
         };
 
         loop {
+            let now = if let Some(f) = &internal.now {
+                f()
+            } else {
+                SystemTime::now()
+            };
+            let wait = internal.schedule.as_ref().map(|s| s.wait(now));
+
             tokio::select! {
-                _ = ticker.tick() =>{
+                _ = ticker.tick(), if wait.is_none() =>{
                     // TODO(cancel safety): This branch isn't cancel safe
+                    internal.send_reports(&rtcp_writer).await;
+                }
+                _ = tokio::time::sleep(wait.unwrap_or_default()), if wait.is_some() =>{
                     let now = if let Some(f) = &internal.now {
                         f()
                     } else {
                         SystemTime::now()
                     };
-                    let streams:Vec<Arc<SenderStream>> = {
-                        let m = internal.streams.lock().await;
-                        m.values().cloned().collect()
-                    };
-                    for stream in streams {
-                        let pkt = stream.generate_report(now).await;
-
-                        let a = Attributes::new();
-                        if let Err(err) = rtcp_writer.write(&[Box::new(pkt)], &a).await{
-                            log::warn!("failed sending: {}", err);
+                    // the local streams are taken as sending
+                    let we_sent = !internal.streams.lock().await.is_empty();
+                    if let Some(schedule) = &internal.schedule {
+                        if schedule.on_timer_expired(now, we_sent) {
+                            let mut size = internal.send_reports(&rtcp_writer).await;
+                            if let Some(receiver_reports) = &internal.receiver_reports {
+                                size += receiver_reports.send_reports(&rtcp_writer).await;
+                            }
+                            schedule.on_reports_sent(now, size);
                         }
                     }
                 }
+                _ = ReportSchedule::rescheduled(&internal.schedule) =>{}
                 _ = close_rx.recv() =>{
+                    if let Some(schedule) = &internal.schedule {
+                        internal.send_bye(schedule, &rtcp_writer).await;
+                    }
                     return Ok(());
                 }
             }
@@ -86,6 +105,81 @@ impl SenderReport {
     }
 }
 
+impl SenderReportInternal {
+    /// send_reports sends a sender report for each local stream, and returns
+    /// the size of the sent reports.
+    async fn send_reports(&self, rtcp_writer: &Arc<dyn RTCPWriter + Send + Sync>) -> usize {
+        let now = if let Some(f) = &self.now {
+            f()
+        } else {
+            SystemTime::now()
+        };
+        let streams: Vec<Arc<SenderStream>> = {
+            let m = self.streams.lock().await;
+            m.values().cloned().collect()
+        };
+
+        let mut size = 0;
+        for stream in streams {
+            let pkt = stream.generate_report(now).await;
+            size += pkt.marshal_size();
+
+            let a = Attributes::new();
+            if let Err(err) = rtcp_writer.write(&[Box::new(pkt)], &a).await {
+                log::warn!("failed sending: {}", err);
+            }
+        }
+        size
+    }
+
+    /// send_bye leaves the session with a BYE for the local streams, sent right away or,
+    /// in a large session, once the BYE reconsideration allows it, RFC 3550 section 6.3.7.
+    async fn send_bye(
+        &self,
+        schedule: &ReportSchedule,
+        rtcp_writer: &Arc<dyn RTCPWriter + Send + Sync>,
+    ) {
+        let now = if let Some(f) = &self.now {
+            f()
+        } else {
+            SystemTime::now()
+        };
+        let bye = Goodbye {
+            sources: self.streams.lock().await.keys().copied().collect(),
+            reason: Bytes::new(),
+        };
+        if bye.sources.is_empty() {
+            schedule.leave(now, 0);
+            return;
+        }
+
+        if !schedule.leave(now, bye.marshal_size()) {
+            loop {
+                let now = if let Some(f) = &self.now {
+                    f()
+                } else {
+                    SystemTime::now()
+                };
+                tokio::time::sleep(schedule.wait(now)).await;
+
+                let now = if let Some(f) = &self.now {
+                    f()
+                } else {
+                    SystemTime::now()
+                };
+                if schedule.on_timer_expired(now, false) {
+                    break;
+                }
+            }
+        }
+
+        let a = Attributes::new();
+        if let Err(err) = rtcp_writer.write(&[Box::new(bye)], &a).await {
+            log::warn!("failed sending: {}", err);
+        }
+    }
+}
+
 #[async_trait]
 impl Interceptor for SenderReport {
     /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
@@ -0,0 +1,24 @@
+use std::sync::Arc;
+
+use portable_atomic::{AtomicU64, Ordering};
+
+/// SharedValue is a value an InterceptorBuilder shares with all the interceptors
+/// it builds, e.g. a counter of the packets they dropped. The application keeps
+/// a clone of it, which follows the interceptors after the builder is registered.
+#[derive(Default, Debug, Clone)]
+pub struct SharedValue(Arc<AtomicU64>);
+
+impl SharedValue {
+    /// get returns the current value.
+    pub fn get(&self) -> u64 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn add(&self, n: u64) {
+        self.0.fetch_add(n, Ordering::Relaxed);
+    }
+
+    pub(crate) fn set(&self, value: u64) {
+        self.0.store(value, Ordering::Relaxed);
+    }
+}
@@ -1,3 +1,7 @@
+use std::sync::Arc;
+
+use util::sync::MemoryBudget;
+
 use crate::Attributes;
 
 /// RTPHeaderExtension represents a negotiated RFC5285 RTP header extension.
@@ -21,6 +25,9 @@ pub struct StreamInfo {
     pub sdp_fmtp_line: String,
     pub rtcp_feedback: Vec<RTCPFeedback>,
     pub associated_stream: Option<AssociatedStreamInfo>,
+    /// memory_budget, when set, is charged with the packets the interceptors buffer
+    /// for the stream, like the ones kept to answer NACKs
+    pub memory_budget: Option<Arc<MemoryBudget>>,
 }
 
 /// AssociatedStreamInfo provides a mapping from an auxiliary stream (RTX, FEC,
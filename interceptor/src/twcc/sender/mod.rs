@@ -28,19 +28,29 @@ impl SenderBuilder {
         self.init_sequence_nr = init_sequence_nr;
         self
     }
+
+    /// build_sender constructs a new Sender, keeping its type so that e.g. its
+    /// next_sequence_nr can be inspected. A custom InterceptorBuilder can wrap it
+    /// to keep track of the Senders built for a registry.
+    pub fn build_sender(&self) -> Sender {
+        Sender {
+            next_sequence_nr: Arc::new(AtomicU32::new(self.init_sequence_nr)),
+            streams: Mutex::new(HashMap::new()),
+        }
+    }
 }
 
 impl InterceptorBuilder for SenderBuilder {
     /// build constructs a new SenderInterceptor
     fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
-        Ok(Arc::new(Sender {
-            next_sequence_nr: Arc::new(AtomicU32::new(self.init_sequence_nr)),
-            streams: Mutex::new(HashMap::new()),
-        }))
+        Ok(Arc::new(self.build_sender()))
     }
 }
 
-/// Sender adds transport wide sequence numbers as header extension to each RTP packet
+/// Sender adds transport wide sequence numbers as header extension to each RTP packet.
+/// The sequence numbers are drawn from a single counter shared by all the local streams
+/// bound to the Sender, i.e. all the SSRCs sent over the transport, and wrap around
+/// after 65535.
 pub struct Sender {
     next_sequence_nr: Arc<AtomicU32>,
     streams: Mutex<HashMap<u32, Arc<SenderStream>>>,
@@ -51,6 +61,12 @@ impl Sender {
     pub fn builder() -> SenderBuilder {
         SenderBuilder::default()
     }
+
+    /// next_sequence_nr returns the transport wide sequence number the next packet sent
+    /// will be stamped with.
+    pub fn next_sequence_nr(&self) -> u16 {
+        self.next_sequence_nr.load(Ordering::SeqCst) as u16
+    }
 }
 
 #[async_trait]
@@ -27,6 +27,7 @@ impl RTPWriter for SenderStream {
     async fn write(&self, pkt: &rtp::packet::Packet, a: &Attributes) -> Result<usize> {
         let sequence_number = self.next_sequence_nr.fetch_add(1, Ordering::SeqCst);
 
+        // the counter is wider than the extension, truncating it wraps around at 65535
         let tcc_ext = TransportCcExtension {
             transport_sequence: sequence_number as u16,
         };
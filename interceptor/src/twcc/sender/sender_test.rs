@@ -83,3 +83,61 @@ async fn test_twcc_sender_interceptor() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_twcc_sender_interceptor_shared_sequence_nr() -> Result<()> {
+    // start right before the wraparound of the 16 bit sequence numbers
+    let sender = Arc::new(
+        Sender::builder()
+            .with_init_sequence_nr(65534)
+            .build_sender(),
+    );
+    assert_eq!(sender.next_sequence_nr(), 65534);
+
+    let mut streams = vec![];
+    for ssrc in [1, 2] {
+        streams.push(
+            MockStream::new(
+                &StreamInfo {
+                    ssrc,
+                    rtp_header_extensions: vec![RTPHeaderExtension {
+                        uri: TRANSPORT_CC_URI.to_owned(),
+                        id: 1,
+                    }],
+                    ..Default::default()
+                },
+                Arc::clone(&sender) as Arc<dyn Interceptor + Send + Sync>,
+            )
+            .await,
+        );
+    }
+
+    // alternate between the SSRCs, the transport sequence numbers keep increasing
+    let mut transport_sequences = vec![];
+    for i in 0..4 {
+        let stream = &streams[i % 2];
+        stream
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    ssrc: (i % 2) as u32 + 1,
+                    sequence_number: i as u16,
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .await?;
+
+        let p = stream.written_rtp().await.expect("written rtp packet");
+        let mut extension_header = p.header.get_extension(1).unwrap();
+        let twcc = TransportCcExtension::unmarshal(&mut extension_header)?;
+        transport_sequences.push(twcc.transport_sequence);
+    }
+    assert_eq!(transport_sequences, vec![65534, 65535, 0, 1]);
+    assert_eq!(sender.next_sequence_nr(), 2);
+
+    for stream in streams {
+        stream.close().await?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,144 @@
+#[cfg(test)]
+mod validator_test;
+
+use std::sync::Arc;
+
+use crate::error::{Error, Result};
+use crate::shared_value::SharedValue;
+use crate::stream_info::StreamInfo;
+use crate::*;
+
+/// ValidatorBuilder can be used to configure Validator Interceptor
+#[derive(Default)]
+pub struct ValidatorBuilder {
+    rejected_packets: SharedValue,
+}
+
+impl ValidatorBuilder {
+    /// rejected_packets returns the number of packets rejected by all the
+    /// interceptors built by this builder. It keeps counting after the builder
+    /// is registered.
+    pub fn rejected_packets(&self) -> SharedValue {
+        self.rejected_packets.clone()
+    }
+}
+
+impl InterceptorBuilder for ValidatorBuilder {
+    fn build(&self, _id: &str) -> Result<Arc<dyn Interceptor + Send + Sync>> {
+        Ok(Arc::new(Validator {
+            rejected_packets: self.rejected_packets.clone(),
+        }))
+    }
+}
+
+/// Validator drops structurally invalid RTP packets received from the remote
+/// peer, so they never reach the following interceptors and depacketizers.
+/// A packet is invalid if it can't be unmarshaled, e.g. because its CSRCs,
+/// header extensions or padding claim more bytes than present, or if it fails
+/// rtp::packet::Packet::validate. It has to be registered before the other
+/// interceptors to protect them.
+pub struct Validator {
+    rejected_packets: SharedValue,
+}
+
+impl Validator {
+    /// builder returns a new ValidatorBuilder.
+    pub fn builder() -> ValidatorBuilder {
+        ValidatorBuilder::default()
+    }
+}
+
+#[async_trait]
+impl Interceptor for Validator {
+    /// bind_rtcp_reader lets you modify any incoming RTCP packets. It is called once per sender/receiver, however this might
+    /// change in the future. The returned method will be called once per packet batch.
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn RTCPReader + Send + Sync>,
+    ) -> Arc<dyn RTCPReader + Send + Sync> {
+        reader
+    }
+
+    /// bind_rtcp_writer lets you modify any outgoing RTCP packets. It is called once per PeerConnection. The returned method
+    /// will be called once per packet batch.
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        writer
+    }
+
+    /// bind_local_stream lets you modify any outgoing RTP packets. It is called once for per LocalStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_local_stream(
+        &self,
+        _info: &StreamInfo,
+        writer: Arc<dyn RTPWriter + Send + Sync>,
+    ) -> Arc<dyn RTPWriter + Send + Sync> {
+        writer
+    }
+
+    /// unbind_local_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_local_stream(&self, _info: &StreamInfo) {}
+
+    /// bind_remote_stream lets you modify any incoming RTP packets. It is called once for per RemoteStream. The returned method
+    /// will be called once per rtp packet.
+    async fn bind_remote_stream(
+        &self,
+        _info: &StreamInfo,
+        reader: Arc<dyn RTPReader + Send + Sync>,
+    ) -> Arc<dyn RTPReader + Send + Sync> {
+        Arc::new(ValidatorStream {
+            parent_rtp_reader: reader,
+            rejected_packets: self.rejected_packets.clone(),
+        })
+    }
+
+    /// unbind_remote_stream is called when the Stream is removed. It can be used to clean up any data related to that track.
+    async fn unbind_remote_stream(&self, _info: &StreamInfo) {}
+
+    /// close closes the Interceptor, cleaning up any data if necessary.
+    async fn close(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+struct ValidatorStream {
+    parent_rtp_reader: Arc<dyn RTPReader + Send + Sync>,
+    rejected_packets: SharedValue,
+}
+
+#[async_trait]
+impl RTPReader for ValidatorStream {
+    /// read a rtp packet, skipping the invalid ones
+    async fn read(
+        &self,
+        buf: &mut [u8],
+        a: &Attributes,
+    ) -> Result<(rtp::packet::Packet, Attributes)> {
+        loop {
+            let err = match self.parent_rtp_reader.read(buf, a).await {
+                Ok((pkt, attr)) => match pkt.validate() {
+                    Ok(()) => return Ok((pkt, attr)),
+                    Err(err) => Error::Rtp(err),
+                },
+                Err(err) if is_unmarshal_error(&err) => err,
+                Err(err) => return Err(err),
+            };
+
+            self.rejected_packets.add(1);
+            log::trace!("rejected invalid RTP packet: {}", err);
+        }
+    }
+}
+
+/// is_unmarshal_error reports whether err is the failure to unmarshal a packet
+/// rather than one of the stream below.
+fn is_unmarshal_error(err: &Error) -> bool {
+    let err = match err {
+        Error::Rtp(_) => return true,
+        Error::Srtp(srtp::Error::Util(err)) | Error::Util(err) => err,
+        _ => return false,
+    };
+    err.downcast_ref::<rtp::Error>().is_some()
+}
@@ -0,0 +1,89 @@
+use std::sync::Mutex as SyncMutex;
+
+use bytes::Bytes;
+
+use super::*;
+use crate::mock::mock_stream::MockStream;
+
+#[tokio::test]
+async fn test_validator_drops_invalid_packets() -> Result<()> {
+    let builder = Validator::builder();
+    let rejected_packets = builder.rejected_packets();
+    let icpr = builder.build("")?;
+
+    let stream = MockStream::new(
+        &StreamInfo {
+            ssrc: 1,
+            ..Default::default()
+        },
+        icpr,
+    )
+    .await;
+
+    let packet = |version: u8, sequence_number: u16| rtp::packet::Packet {
+        header: rtp::header::Header {
+            version,
+            sequence_number,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[1, 2, 3]),
+    };
+    stream.receive_rtp(packet(2, 1)).await;
+    stream.receive_rtp(packet(1, 2)).await;
+    stream.receive_rtp(packet(2, 3)).await;
+
+    for sequence_number in [1, 3] {
+        let pkt = stream.read_rtp().await.unwrap()?;
+        assert_eq!(pkt.header.sequence_number, sequence_number);
+    }
+    assert_eq!(rejected_packets.get(), 1);
+
+    stream.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validator_skips_unmarshal_errors() -> Result<()> {
+    let builder = Validator::builder();
+    let rejected_packets = builder.rejected_packets();
+    let icpr = builder.build("")?;
+
+    // the stream below fails to unmarshal twice before it delivers a packet,
+    // then reports a failure of its own
+    let results: Arc<SyncMutex<Vec<Result<rtp::packet::Packet>>>> = Arc::new(SyncMutex::new(vec![
+        Err(Error::ErrIoEOF),
+        Ok(rtp::packet::Packet {
+            header: rtp::header::Header {
+                version: 2,
+                sequence_number: 7,
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        Err(srtp::Error::Util(rtp::Error::ErrHeaderSizeInsufficientForExtension.into()).into()),
+        Err(rtp::Error::ErrShortPacket.into()),
+    ]));
+    let reader = Arc::new(RTPReaderFn(Box::new(move |_, a| {
+        let result = results.lock().unwrap().pop().unwrap();
+        let a = a.clone();
+        Box::pin(async move { result.map(|pkt| (pkt, a)) })
+    })));
+
+    let reader = icpr
+        .bind_remote_stream(&StreamInfo::default(), reader)
+        .await;
+
+    let mut buf = vec![0u8; 1500];
+    let (pkt, _) = reader.read(&mut buf, &Attributes::new()).await?;
+    assert_eq!(pkt.header.sequence_number, 7);
+    assert_eq!(rejected_packets.get(), 2);
+
+    assert!(matches!(
+        reader.read(&mut buf, &Attributes::new()).await,
+        Err(Error::ErrIoEOF)
+    ));
+    assert_eq!(rejected_packets.get(), 2);
+
+    Ok(())
+}
@@ -45,6 +45,23 @@ pub struct SampleBuilder<T: Depacketizer> {
     /// number of padding packets detected and dropped. This number will be a subset of
     /// `dropped_packets`
     padding_packets: u16,
+
+    /// number of packets dropped because they arrived after their sequence number had
+    /// been released. This number will be a subset of `dropped_packets`
+    late_packets: u16,
+
+    /// number of packets dropped because their sample could not be completed. This
+    /// number will be a subset of `dropped_packets`
+    gap_packets: u16,
+
+    /// sequence number following the last packet released from the buffer
+    released_tail: Option<u16>,
+
+    /// whether the stream is sent with discontinuous transmission
+    dtx: bool,
+
+    /// duration, in RTP timestamp units, of the last sample not followed by a silence
+    last_sample_samples: Option<u32>,
 }
 
 impl<T: Depacketizer> SampleBuilder<T> {
@@ -68,6 +85,11 @@ impl<T: Depacketizer> SampleBuilder<T> {
             prepared: SampleSequenceLocation::new(),
             dropped_packets: 0,
             padding_packets: 0,
+            late_packets: 0,
+            gap_packets: 0,
+            released_tail: None,
+            dtx: false,
+            last_sample_samples: None,
         }
     }
 
@@ -77,6 +99,16 @@ impl<T: Depacketizer> SampleBuilder<T> {
         self
     }
 
+    /// with_dtx handles a stream sent with discontinuous transmission, such as Opus with
+    /// usedtx=1. Little more than a frame of comfort noise now and then is sent during the
+    /// silence and the first packet after it carries the marker bit, so the sample before
+    /// that packet lasts as long as the previous one rather than up to the next packet. The silence shows up as the gap between the
+    /// packet timestamps of the two samples.
+    pub fn with_dtx(mut self) -> Self {
+        self.dtx = true;
+        self
+    }
+
     fn too_old(&self, location: &SampleSequenceLocation) -> bool {
         if self.max_late_timestamp == 0 {
             return false;
@@ -130,6 +162,18 @@ impl<T: Depacketizer> SampleBuilder<T> {
 
     fn release_packet(&mut self, i: u16) {
         self.buffer[i as usize] = None;
+        self.released_tail = Some(i.wrapping_add(1));
+    }
+
+    /// Returns true if the packet with the given sequence number precedes the packets
+    /// already released from the buffer.
+    fn is_late(&self, sequence_number: u16) -> bool {
+        self.released_tail
+            .map(|tail| {
+                let diff = tail.wrapping_sub(sequence_number);
+                diff != 0 && diff <= 0xFFFF / 2
+            })
+            .unwrap_or(false)
     }
 
     /// Clears all buffers that have already been consumed by
@@ -182,6 +226,7 @@ impl<T: Depacketizer> SampleBuilder<T> {
                 if !matches!(err, BuildError::InvalidPartition(_)) {
                     // In the InvalidPartition case `build_sample` will have already adjusted `dropped_packets`.
                     self.dropped_packets += 1;
+                    self.gap_packets += 1;
                 }
 
                 // could not build the sample so drop it
@@ -199,6 +244,12 @@ impl<T: Depacketizer> SampleBuilder<T> {
     /// this memory make sure to copy before calling push
     pub fn push(&mut self, p: Packet) {
         let sequence_number = p.header.sequence_number;
+        if self.is_late(sequence_number) {
+            // the sample this packet belongs to was already built or dropped
+            self.dropped_packets += 1;
+            self.late_packets += 1;
+            return;
+        }
         self.buffer[sequence_number as usize] = Some(p);
         match self.filled.compare(sequence_number) {
             Comparison::Void => {
@@ -285,11 +336,15 @@ impl<T: Depacketizer> SampleBuilder<T> {
 
         let sample_timestamp = self.fetch_timestamp(&self.active).unwrap_or(0);
         let mut after_timestamp = sample_timestamp;
+        let mut after_silence = false;
 
         // scan for any packet after the current and use that time stamp as the diff point
         for i in consume.tail..self.active.tail {
             if let Some(ref packet) = self.buffer[i as usize] {
                 after_timestamp = packet.header.timestamp;
+                // with DTX, a talkspurt directly following the sample means the timestamps
+                // jump over a silence rather than over lost packets
+                after_silence = self.dtx && i == consume.tail && packet.header.marker;
                 break;
             }
         }
@@ -313,6 +368,8 @@ impl<T: Depacketizer> SampleBuilder<T> {
             self.dropped_packets += consume.count();
             if is_padding {
                 self.padding_packets += consume.count();
+            } else {
+                self.gap_packets += consume.count();
             }
             self.purge_consumed_location(&consume, true);
             self.purge_consumed_buffers();
@@ -341,7 +398,14 @@ impl<T: Depacketizer> SampleBuilder<T> {
             data.extend_from_slice(&p);
             i = i.wrapping_add(1);
         }
-        let samples = after_timestamp - sample_timestamp;
+        let mut samples = after_timestamp - sample_timestamp;
+        if after_silence {
+            if let Some(last_sample_samples) = self.last_sample_samples {
+                samples = samples.min(last_sample_samples);
+            }
+        } else {
+            self.last_sample_samples = Some(samples);
+        }
 
         let sample = Sample {
             data: Bytes::copy_from_slice(&data),
@@ -350,10 +414,14 @@ impl<T: Depacketizer> SampleBuilder<T> {
             packet_timestamp: sample_timestamp,
             prev_dropped_packets: self.dropped_packets,
             prev_padding_packets: self.padding_packets,
+            prev_late_packets: self.late_packets,
+            prev_gap_packets: self.gap_packets,
         };
 
         self.dropped_packets = 0;
         self.padding_packets = 0;
+        self.late_packets = 0;
+        self.gap_packets = 0;
         self.last_sample_timestamp = Some(sample_timestamp);
 
         self.prepared_samples[self.prepared.tail as usize] = Some(sample);
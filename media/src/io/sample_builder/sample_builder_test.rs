@@ -418,6 +418,7 @@ pub fn test_sample_builder() {
                     duration: Duration::from_secs(2),
                     packet_timestamp: 7,
                     prev_dropped_packets: 1,
+                    prev_gap_packets: 1,
                     ..Default::default()
                 },
             ],
@@ -817,6 +818,7 @@ pub fn test_sample_builder() {
                 duration: Duration::from_secs(2),
                 packet_timestamp: 4000,
                 prev_dropped_packets: 12,
+                prev_gap_packets: 12,
                 ..Default::default()
             }],
             with_head_checker: true,
@@ -1319,6 +1321,7 @@ fn test_sample_builder_max_late() {
             duration: Duration::from_secs(1),
             packet_timestamp: 500,
             prev_dropped_packets: 4998,
+            prev_gap_packets: 4998,
             ..Default::default()
         }),
         "Failed to build samples after large gap"
@@ -1497,3 +1500,110 @@ fn test_sample_builder_data() {
     // only the last packet should be dropped
     assert_eq!(j, 0x1FFFF);
 }
+
+// SampleBuilder should tell packets arriving after their sample was built apart from lost ones
+#[test]
+fn test_sample_builder_drop_reasons() {
+    let mut s = SampleBuilder::new(10, FakeDepacketizer::new(), 1);
+    let packet = |sequence_number: u16, timestamp: u32| Packet {
+        header: Header {
+            sequence_number,
+            timestamp,
+            ..Default::default()
+        },
+        payload: bytes!(0x01),
+    };
+
+    let mut samples = vec![];
+    // Packet 1 is received again after its sample was built, packet 6 is lost
+    let packets = [(0, 1), (1, 1), (2, 2), (3, 3), (1, 1), (4, 4), (5, 5)]
+        .into_iter()
+        .chain((7..20).map(|i| (i, i as u32)));
+    for (sequence_number, timestamp) in packets {
+        s.push(packet(sequence_number, timestamp));
+        while let Some(sample) = s.pop() {
+            samples.push(sample);
+        }
+    }
+
+    // (packet_timestamp, prev_dropped_packets, prev_late_packets, prev_gap_packets)
+    let reasons: Vec<(u32, u16, u16, u16)> = samples
+        .iter()
+        .map(|s| {
+            (
+                s.packet_timestamp,
+                s.prev_dropped_packets,
+                s.prev_late_packets,
+                s.prev_gap_packets,
+            )
+        })
+        .take(5)
+        .collect();
+    assert_eq!(
+        reasons,
+        vec![
+            (1, 0, 0, 0),
+            (2, 0, 0, 0),
+            // the duplicate of packet 1 arrived too late
+            (3, 1, 1, 0),
+            (4, 0, 0, 0),
+            // sample 5 could not be completed without packet 6
+            (7, 2, 0, 2),
+        ]
+    );
+}
+
+// SampleBuilder should not stretch the sample before a DTX silence over it
+#[test]
+fn test_sample_builder_dtx() {
+    let packet = |sequence_number: u16, timestamp: u32, marker: bool| Packet {
+        header: Header {
+            sequence_number,
+            timestamp,
+            marker,
+            ..Default::default()
+        },
+        payload: bytes!(0x78, 0x01, 0x02),
+    };
+    // 20ms frames at 48kHz, a second of silence after the third one
+    let packets = [
+        packet(0, 0, true),
+        packet(1, 960, false),
+        packet(2, 1920, false),
+        packet(3, 2880 + 48000, true),
+        packet(4, 3840 + 48000, false),
+    ];
+
+    let durations = |mut s: SampleBuilder<rtp::codecs::opus::OpusPacket>| {
+        let mut durations = vec![];
+        for p in packets.iter().cloned() {
+            s.push(p);
+            while let Some(sample) = s.pop() {
+                durations.push((sample.packet_timestamp, sample.duration));
+            }
+        }
+        durations
+    };
+
+    let frame = Duration::from_millis(20);
+    assert_eq!(
+        durations(SampleBuilder::new(10, rtp::codecs::opus::OpusPacket, 48000).with_dtx()),
+        vec![
+            (0, frame),
+            (960, frame),
+            (1920, frame),
+            (2880 + 48000, frame)
+        ]
+    );
+
+    // without DTX the silence is part of the sample before it
+    assert_eq!(
+        durations(SampleBuilder::new(10, rtp::codecs::opus::OpusPacket, 48000)),
+        vec![
+            (0, frame),
+            (960, frame),
+            (1920, frame + Duration::from_secs(1)),
+            (2880 + 48000, frame)
+        ]
+    );
+}
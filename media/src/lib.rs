@@ -59,13 +59,30 @@ pub struct Sample {
     /// #   duration: Duration::from_secs(0),
     /// #   packet_timestamp: 0,
     /// #   prev_dropped_packets: 10,
-    /// #   prev_padding_packets: 15
+    /// #   prev_padding_packets: 15,
+    /// #   prev_late_packets: 0,
+    /// #   prev_gap_packets: 0,
     /// # };
     /// #
     /// let adjusted_dropped =
     /// sample.prev_dropped_packets.saturating_sub(sample.prev_padding_packets);
     /// ```
     pub prev_padding_packets: u16,
+
+    /// The number of packets that were dropped prior to building this sample because they
+    /// arrived after the packets around them had already been built into a sample or dropped.
+    ///
+    /// Many late packets suggest the `max_late` window of the
+    /// [`SampleBuilder`](crate::io::sample_builder::SampleBuilder) is too small for the
+    /// reordering of the network.
+    pub prev_late_packets: u16,
+
+    /// The number of packets that were dropped prior to building this sample because the sample
+    /// they belonged to could not be completed, e.g. a packet of it was lost.
+    ///
+    /// Together with [`Sample::prev_padding_packets`] and [`Sample::prev_late_packets`] this
+    /// accounts for all of [`Sample::prev_dropped_packets`].
+    pub prev_gap_packets: u16,
 }
 
 impl Default for Sample {
@@ -77,6 +94,8 @@ impl Default for Sample {
             packet_timestamp: 0,
             prev_dropped_packets: 0,
             prev_padding_packets: 0,
+            prev_late_packets: 0,
+            prev_gap_packets: 0,
         }
     }
 }
@@ -104,6 +123,12 @@ impl PartialEq for Sample {
         if self.prev_padding_packets != other.prev_padding_packets {
             equal = false;
         }
+        if self.prev_late_packets != other.prev_late_packets {
+            equal = false;
+        }
+        if self.prev_gap_packets != other.prev_gap_packets {
+            equal = false;
+        }
 
         equal
     }
@@ -242,12 +242,10 @@ fn test_h264_partition_head_checker_is_partition_head() -> Result<()> {
 #[test]
 fn test_h264_payloader_payload_sps_and_pps_handling() -> Result<()> {
     let mut pck = H264Payloader::default();
-    let expected = vec![
-        Bytes::from_static(&[
-            0x78, 0x00, 0x03, 0x07, 0x00, 0x01, 0x00, 0x03, 0x08, 0x02, 0x03,
-        ]),
-        Bytes::from_static(&[0x05, 0x04, 0x05]),
-    ];
+    let expected = vec![Bytes::from_static(&[
+        0x78, 0x00, 0x03, 0x07, 0x00, 0x01, 0x00, 0x03, 0x08, 0x02, 0x03, 0x00, 0x03, 0x05, 0x04,
+        0x05,
+    ])];
 
     // When packetizing SPS and PPS are emitted with following NALU
     let res = pck.payload(1500, &Bytes::from_static(&[0x07, 0x00, 0x01]))?;
@@ -261,3 +259,95 @@ fn test_h264_payloader_payload_sps_and_pps_handling() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_h264_payloader_payload_sps_pps_and_idr() -> Result<()> {
+    let mut pck = H264Payloader::default();
+    let mut payload = vec![];
+    payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0xc0, 0x1f]);
+    payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x01, 0x68, 0xce, 0x3c, 0x80]);
+    payload.extend_from_slice(&[0x00, 0x00, 0x00, 0x01, 0x65]);
+    payload.extend((0..20u8).map(|i| i + 0x10));
+
+    // SPS and PPS are aggregated, the IDR is too large to be sent alone
+    let actual = pck.payload(14, &Bytes::from(payload))?;
+    let expected = vec![
+        Bytes::from_static(&[
+            0x78, 0x00, 0x04, 0x67, 0x42, 0xc0, 0x1f, 0x00, 0x04, 0x68, 0xce, 0x3c, 0x80,
+        ]),
+        Bytes::from_static(&[
+            0x7c, 0x85, 0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17, 0x18, 0x19, 0x1a, 0x1b,
+        ]),
+        Bytes::from_static(&[0x7c, 0x45, 0x1c, 0x1d, 0x1e, 0x1f, 0x20, 0x21, 0x22, 0x23]),
+    ];
+    assert_eq!(
+        actual, expected,
+        "SPS and PPS aren't aggregated before the FU-As"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_h264_payloader_payload_aggregation() -> Result<()> {
+    let mut pck = H264Payloader::default();
+    let payload = Bytes::from_static(&[
+        0x00, 0x00, 0x01, 0x06, 0x01, 0x02, 0x00, 0x00, 0x01, 0x41, 0x03, 0x04, 0x00, 0x00, 0x01,
+        0x41, 0x05, 0x06, 0x07, 0x08, 0x09,
+    ]);
+
+    // NALUs fitting together are aggregated, the rest is sent as a single NALU
+    let actual = pck.payload(11, &payload)?;
+    let expected = vec![
+        Bytes::from_static(&[
+            0x78, 0x00, 0x03, 0x06, 0x01, 0x02, 0x00, 0x03, 0x41, 0x03, 0x04,
+        ]),
+        Bytes::from_static(&[0x41, 0x05, 0x06, 0x07, 0x08, 0x09]),
+    ];
+    assert_eq!(actual, expected);
+
+    // a single NALU is never wrapped in a STAP-A
+    let actual = pck.payload(1500, &Bytes::from_static(&[0x41, 0x05, 0x06]))?;
+    assert_eq!(actual, vec![Bytes::from_static(&[0x41, 0x05, 0x06])]);
+
+    // SPS and PPS that don't fit in a STAP-A are sent as single NALUs
+    let payload = Bytes::from_static(&[
+        0x00, 0x00, 0x01, 0x67, 0x42, 0xc0, 0x00, 0x00, 0x01, 0x68, 0xce, 0x3c, 0x00, 0x00, 0x01,
+        0x65, 0x88,
+    ]);
+    let actual = pck.payload(5, &payload)?;
+    let expected = vec![
+        Bytes::from_static(&[0x67, 0x42, 0xc0]),
+        Bytes::from_static(&[0x68, 0xce, 0x3c]),
+        Bytes::from_static(&[0x65, 0x88]),
+    ];
+    assert_eq!(actual, expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_h264_payloader_depacketize_round_trip() -> Result<()> {
+    let mut pck = H264Payloader::default();
+    let payload = Bytes::from_static(&[
+        0x00, 0x00, 0x00, 0x01, 0x67, 0x42, 0xc0, 0x1f, 0x00, 0x00, 0x00, 0x01, 0x68, 0xce, 0x3c,
+        0x80, 0x00, 0x00, 0x00, 0x01, 0x65, 0x88, 0x84,
+    ]);
+
+    // SPS, PPS and IDR in a STAP-A
+    let payloads = pck.payload(1500, &payload)?;
+    assert_eq!(payloads.len(), 1);
+    assert_eq!(payloads[0][0] & NALU_TYPE_BITMASK, STAPA_NALU_TYPE);
+    let mut pkt = H264Packet::default();
+    assert_eq!(pkt.depacketize(&payloads[0])?, payload);
+
+    // a single NALU
+    let payloads = pck.payload(1500, &Bytes::from_static(&[0x41, 0x9a, 0x02]))?;
+    assert_eq!(payloads.len(), 1);
+    assert_eq!(
+        pkt.depacketize(&payloads[0])?,
+        Bytes::from_static(&[0x00, 0x00, 0x00, 0x01, 0x41, 0x9a, 0x02])
+    );
+
+    Ok(())
+}
@@ -50,13 +50,18 @@ impl H264Payloader {
         (-1, -1)
     }
 
-    fn emit(&mut self, nalu: &Bytes, mtu: usize, payloads: &mut Vec<Bytes>) {
+    fn emit(
+        &mut self,
+        nalu: &Bytes,
+        mtu: usize,
+        payloads: &mut Vec<Bytes>,
+        aggregated: &mut Vec<Bytes>,
+    ) {
         if nalu.is_empty() {
             return;
         }
 
         let nalu_type = nalu[0] & NALU_TYPE_BITMASK;
-        let nalu_ref_idc = nalu[0] & NALU_REF_IDC_BITMASK;
 
         if nalu_type == AUD_NALU_TYPE || nalu_type == FILLER_NALU_TYPE {
             return;
@@ -67,33 +72,65 @@ impl H264Payloader {
             self.pps_nalu = Some(nalu.clone());
             return;
         } else if let (Some(sps_nalu), Some(pps_nalu)) = (&self.sps_nalu, &self.pps_nalu) {
-            // Pack current NALU with SPS and PPS as STAP-A
-            let sps_len = (sps_nalu.len() as u16).to_be_bytes();
-            let pps_len = (pps_nalu.len() as u16).to_be_bytes();
-
-            let mut stap_a_nalu = Vec::with_capacity(1 + 2 + sps_nalu.len() + 2 + pps_nalu.len());
-            stap_a_nalu.push(OUTPUT_STAP_AHEADER);
-            stap_a_nalu.extend(sps_len);
-            stap_a_nalu.extend_from_slice(sps_nalu);
-            stap_a_nalu.extend(pps_len);
-            stap_a_nalu.extend_from_slice(pps_nalu);
-            if stap_a_nalu.len() <= mtu {
-                payloads.push(Bytes::from(stap_a_nalu));
-            }
-        }
-
-        if self.sps_nalu.is_some() && self.pps_nalu.is_some() {
+            // SPS and PPS are emitted with the following NALU, in one STAP-A if they fit
+            Self::aggregate(sps_nalu, mtu, payloads, aggregated);
+            Self::aggregate(pps_nalu, mtu, payloads, aggregated);
             self.sps_nalu = None;
             self.pps_nalu = None;
         }
 
-        // Single NALU
-        if nalu.len() <= mtu {
-            payloads.push(nalu.clone());
+        Self::aggregate(nalu, mtu, payloads, aggregated);
+    }
+
+    /// aggregate adds nalu to the NALUs aggregated in the next STAP-A if they all fit
+    /// in mtu. Otherwise the aggregated NALUs are flushed, and nalu starts the next
+    /// aggregation if it fits in a single NALU packet or is fragmented in FU-As.
+    fn aggregate(nalu: &Bytes, mtu: usize, payloads: &mut Vec<Bytes>, aggregated: &mut Vec<Bytes>) {
+        let stap_a_size = STAPA_HEADER_SIZE
+            + aggregated
+                .iter()
+                .chain(std::iter::once(nalu))
+                .map(|n| STAPA_NALU_LENGTH_SIZE + n.len())
+                .sum::<usize>();
+        if !aggregated.is_empty() && stap_a_size <= mtu {
+            aggregated.push(nalu.clone());
             return;
         }
 
-        // FU-A
+        Self::flush(payloads, aggregated);
+        if nalu.len() <= mtu {
+            aggregated.push(nalu.clone());
+        } else {
+            Self::fragment(nalu, mtu, payloads);
+        }
+    }
+
+    /// flush emits the aggregated NALUs, a single one as is and several in a STAP-A.
+    fn flush(payloads: &mut Vec<Bytes>, aggregated: &mut Vec<Bytes>) {
+        if aggregated.len() == 1 {
+            payloads.push(aggregated.remove(0));
+        } else if !aggregated.is_empty() {
+            let mut stap_a_nalu = BytesMut::with_capacity(
+                STAPA_HEADER_SIZE
+                    + aggregated
+                        .iter()
+                        .map(|n| STAPA_NALU_LENGTH_SIZE + n.len())
+                        .sum::<usize>(),
+            );
+            stap_a_nalu.put_u8(OUTPUT_STAP_AHEADER);
+            for nalu in aggregated.drain(..) {
+                stap_a_nalu.put_u16(nalu.len() as u16);
+                stap_a_nalu.put(nalu);
+            }
+            payloads.push(stap_a_nalu.freeze());
+        }
+    }
+
+    /// fragment emits nalu in FU-As of at most mtu bytes.
+    fn fragment(nalu: &Bytes, mtu: usize, payloads: &mut Vec<Bytes>) {
+        let nalu_type = nalu[0] & NALU_TYPE_BITMASK;
+        let nalu_ref_idc = nalu[0] & NALU_REF_IDC_BITMASK;
+
         let max_fragment_size = mtu as isize - FUA_HEADER_SIZE as isize;
 
         // The FU payload consists of fragments of the payload of the fragmented
@@ -158,17 +195,20 @@ impl H264Payloader {
 }
 
 impl Payloader for H264Payloader {
-    /// Payload fragments a H264 packet across one or more byte arrays
+    /// Payload fragments a H264 packet across one or more byte arrays. The NALUs
+    /// of the packet that fit in mtu together are aggregated in STAP-As, those that
+    /// fit alone are sent as single NALU packets and the others are fragmented in FU-As.
     fn payload(&mut self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>> {
         if payload.is_empty() || mtu == 0 {
             return Ok(vec![]);
         }
 
         let mut payloads = vec![];
+        let mut aggregated = vec![];
 
         let (mut next_ind_start, mut next_ind_len) = H264Payloader::next_ind(payload, 0);
         if next_ind_start == -1 {
-            self.emit(payload, mtu, &mut payloads);
+            self.emit(payload, mtu, &mut payloads, &mut aggregated);
         } else {
             while next_ind_start != -1 {
                 let prev_start = (next_ind_start + next_ind_len) as usize;
@@ -180,13 +220,20 @@ impl Payloader for H264Payloader {
                         &payload.slice(prev_start..next_ind_start as usize),
                         mtu,
                         &mut payloads,
+                        &mut aggregated,
                     );
                 } else {
                     // Emit until end of stream, no end indicator found
-                    self.emit(&payload.slice(prev_start..), mtu, &mut payloads);
+                    self.emit(
+                        &payload.slice(prev_start..),
+                        mtu,
+                        &mut payloads,
+                        &mut aggregated,
+                    );
                 }
             }
         }
+        H264Payloader::flush(&mut payloads, &mut aggregated);
 
         Ok(payloads)
     }
@@ -6,21 +6,63 @@ use bytes::Bytes;
 use crate::error::{Error, Result};
 use crate::packetizer::{Depacketizer, Payloader};
 
+/// Opus frames of at most this size carry no audio, the encoder produces them
+/// periodically during silence when discontinuous transmission is on
+const OPUS_DTX_FRAME_MAX_SIZE: usize = 2;
+
 #[derive(Default, Debug, Copy, Clone)]
-pub struct OpusPayloader;
+pub struct OpusPayloader {
+    /// enable_dtx tells the frames the encoder produces during silence apart from
+    /// audio: they are still sent, for the decoder to keep generating comfort noise,
+    /// and only the first packet of audio after them carries the marker bit, as
+    /// RFC 3551 has it for audio sent with silence suppression
+    pub enable_dtx: bool,
+    /// whether the last frame carried audio
+    in_talkspurt: bool,
+    /// whether the frame of the last call to payload starts a talkspurt
+    talkspurt_start: bool,
+}
+
+impl OpusPayloader {
+    /// new creates an OpusPayloader, with discontinuous transmission if enable_dtx
+    /// is set.
+    pub fn new(enable_dtx: bool) -> Self {
+        OpusPayloader {
+            enable_dtx,
+            ..Default::default()
+        }
+    }
+}
 
 impl Payloader for OpusPayloader {
     fn payload(&mut self, mtu: usize, payload: &Bytes) -> Result<Vec<Bytes>> {
         if payload.is_empty() || mtu == 0 {
+            self.in_talkspurt = false;
             return Ok(vec![]);
         }
 
+        if self.enable_dtx && payload.len() <= OPUS_DTX_FRAME_MAX_SIZE {
+            self.in_talkspurt = false;
+            self.talkspurt_start = false;
+        } else {
+            self.talkspurt_start = !self.in_talkspurt;
+            self.in_talkspurt = true;
+        }
+
         Ok(vec![payload.clone()])
     }
 
     fn clone_to(&self) -> Box<dyn Payloader + Send + Sync> {
         Box::new(*self)
     }
+
+    fn marker(&self, index: usize, count: usize) -> bool {
+        if self.enable_dtx {
+            self.talkspurt_start
+        } else {
+            index == count - 1
+        }
+    }
 }
 
 /// OpusPacket represents the Opus header that is stored in the payload of an RTP Packet
@@ -1,4 +1,6 @@
 use super::*;
+use crate::packetizer::{new_packetizer, Packetizer};
+use crate::sequence::new_fixed_sequencer;
 
 #[test]
 fn test_opus_unmarshal() -> Result<()> {
@@ -19,7 +21,7 @@ fn test_opus_unmarshal() -> Result<()> {
 
 #[test]
 fn test_opus_payload() -> Result<()> {
-    let mut pck = OpusPayloader;
+    let mut pck = OpusPayloader::default();
     let empty = Bytes::from_static(&[]);
     let payload = Bytes::from_static(&[0x90, 0x90, 0x90]);
 
@@ -38,6 +40,52 @@ fn test_opus_payload() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_opus_payload_dtx() -> Result<()> {
+    let frame = Bytes::from_static(&[0x78, 0x90, 0x90]);
+    let dtx_frame = Bytes::from_static(&[0x78, 0x01]);
+    let frames = [&frame, &frame, &dtx_frame, &dtx_frame, &frame, &frame];
+
+    let packetize = |payloader: OpusPayloader| -> Result<Vec<(Bytes, bool)>> {
+        let mut packetizer = new_packetizer(
+            1200,
+            111,
+            1234,
+            Box::new(payloader),
+            Box::new(new_fixed_sequencer(0)),
+            48000,
+        );
+        let mut packets = vec![];
+        for f in frames {
+            for p in packetizer.packetize(f, 960)? {
+                packets.push((p.payload, p.header.marker));
+            }
+        }
+        Ok(packets)
+    };
+
+    // the frames of the silence are sent too, the first frame of each talkspurt is marked
+    assert_eq!(
+        packetize(OpusPayloader::new(true))?,
+        vec![
+            (frame.clone(), true),
+            (frame.clone(), false),
+            (dtx_frame.clone(), false),
+            (dtx_frame.clone(), false),
+            (frame.clone(), true),
+            (frame.clone(), false),
+        ]
+    );
+
+    // without DTX every packet is marked
+    assert_eq!(
+        packetize(OpusPayloader::default())?,
+        frames.iter().map(|f| ((*f).clone(), true)).collect::<Vec<_>>()
+    );
+
+    Ok(())
+}
+
 #[test]
 fn test_opus_is_partition_head() -> Result<()> {
     let opus = OpusPacket;
@@ -31,6 +31,10 @@ pub enum Error {
 
     #[error("packet is not large enough")]
     ErrShortPacket,
+    #[error("RTP version must be 2")]
+    ErrInvalidVersion,
+    #[error("RTP packet can carry at most 15 CSRCs")]
+    ErrTooManyCsrc,
     #[error("invalid nil packet")]
     ErrNilPacket,
     #[error("too many PDiff")]
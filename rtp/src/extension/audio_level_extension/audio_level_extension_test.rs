@@ -64,3 +64,33 @@ fn test_audio_level_extension_level_overflow() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_audio_level_extension_from_pcm() -> Result<()> {
+    let tests: Vec<(&str, Vec<i16>, u8, bool)> = vec![
+        ("empty", vec![], MAX_AUDIO_LEVEL, false),
+        ("digital silence", vec![0; 160], MAX_AUDIO_LEVEL, false),
+        ("full scale square", vec![i16::MIN; 160], 0, true),
+        (
+            "half scale square",
+            [16384, -16384].repeat(80),
+            6, // 20 * log10(2)
+            true,
+        ),
+        ("quiet", [33, -33].repeat(80), 60, false),
+    ];
+
+    for (name, samples, level, voice) in tests {
+        let ext = AudioLevelExtension::from_pcm(&samples);
+        assert_eq!(ext, AudioLevelExtension { level, voice }, "{name}");
+    }
+
+    // The level is carried in the low 7 bits, the V bit is the high bit
+    let ext = AudioLevelExtension::from_pcm(&[16384, -16384]);
+    let mut dst = BytesMut::with_capacity(ext.marshal_size());
+    dst.resize(ext.marshal_size(), 0);
+    ext.marshal_to(&mut dst)?;
+    assert_eq!(dst.freeze(), Bytes::from_static(&[0x80 | 6]));
+
+    Ok(())
+}
@@ -10,6 +10,13 @@ use crate::error::Error;
 // AUDIO_LEVEL_EXTENSION_SIZE One byte header size
 pub const AUDIO_LEVEL_EXTENSION_SIZE: usize = 1;
 
+/// MAX_AUDIO_LEVEL is the level of silence, 127 -dBov
+pub const MAX_AUDIO_LEVEL: u8 = 127;
+
+/// VOICE_ACTIVITY_LEVEL is the level below which (louder than -50 dBov)
+/// from_pcm considers the audio to be voice
+pub const VOICE_ACTIVITY_LEVEL: u8 = 50;
+
 /// AudioLevelExtension is a extension payload format described in
 ///
 /// Implementation based on:
@@ -40,6 +47,42 @@ pub struct AudioLevelExtension {
     pub voice: bool,
 }
 
+impl AudioLevelExtension {
+    /// from_pcm computes the level of the 16-bit PCM samples a packet carries
+    /// as described in [RFC 6464 Appendix A], the root mean square of the
+    /// samples in -dBov. The voice flag is set when the level is louder than
+    /// [`VOICE_ACTIVITY_LEVEL`], a sender with a voice activity detector should
+    /// set it from the detector instead.
+    ///
+    /// [RFC 6464 Appendix A]: https://tools.ietf.org/html/rfc6464#appendix-A
+    pub fn from_pcm(samples: &[i16]) -> Self {
+        let level = if samples.is_empty() {
+            MAX_AUDIO_LEVEL
+        } else {
+            let sum: f64 = samples
+                .iter()
+                .map(|&s| {
+                    let s = s as f64 / 32768.0;
+                    s * s
+                })
+                .sum();
+            let rms = (sum / samples.len() as f64).sqrt();
+            if rms > 0.0 {
+                (-20.0 * rms.log10())
+                    .round()
+                    .clamp(0.0, MAX_AUDIO_LEVEL as f64) as u8
+            } else {
+                MAX_AUDIO_LEVEL
+            }
+        };
+
+        AudioLevelExtension {
+            level,
+            voice: level < VOICE_ACTIVITY_LEVEL,
+        }
+    }
+}
+
 impl Unmarshal for AudioLevelExtension {
     /// Unmarshal parses the passed byte slice and stores the result in the members
     fn unmarshal<B>(raw_packet: &mut B) -> Result<Self, util::Error>
@@ -135,6 +135,9 @@ impl Unmarshal for Header {
                         if extid == EXTENSION_ID_RESERVED {
                             break;
                         }
+                        if curr_offset + len > end {
+                            return Err(Error::ErrHeaderSizeInsufficientForExtension.into());
+                        }
 
                         extensions.push(Extension {
                             id: extid,
@@ -157,9 +160,15 @@ impl Unmarshal for Header {
 
                         let extid = b;
                         curr_offset += 1;
+                        if curr_offset >= end {
+                            return Err(Error::ErrHeaderSizeInsufficientForExtension.into());
+                        }
 
                         let len = raw_packet.get_u8() as usize;
                         curr_offset += 1;
+                        if curr_offset + len > end {
+                            return Err(Error::ErrHeaderSizeInsufficientForExtension.into());
+                        }
 
                         extensions.push(Extension {
                             id: extid,
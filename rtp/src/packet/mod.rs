@@ -40,26 +40,8 @@ impl Unmarshal for Packet {
         Self: Sized,
         B: Buf,
     {
-        let header = Header::unmarshal(raw_packet)?;
-        let payload_len = raw_packet.remaining();
-        let payload = raw_packet.copy_to_bytes(payload_len);
-        if header.padding {
-            if payload_len > 0 {
-                let padding_len = payload[payload_len - 1] as usize;
-                if padding_len <= payload_len {
-                    Ok(Packet {
-                        header,
-                        payload: payload.slice(..payload_len - padding_len),
-                    })
-                } else {
-                    Err(Error::ErrShortPacket.into())
-                }
-            } else {
-                Err(Error::ErrShortPacket.into())
-            }
-        } else {
-            Ok(Packet { header, payload })
-        }
+        let (packet, _) = Packet::unmarshal_with_padding_size(raw_packet)?;
+        Ok(packet)
     }
 }
 
@@ -112,6 +94,55 @@ impl Marshal for Packet {
     }
 }
 
+impl Packet {
+    /// unmarshal_with_padding_size parses the packet like unmarshal and also returns
+    /// the number of padding bytes that followed the payload, including the trailing
+    /// padding length byte, or 0 if the packet is not padded.
+    pub fn unmarshal_with_padding_size<B>(raw_packet: &mut B) -> Result<(Self, u8), util::Error>
+    where
+        B: Buf,
+    {
+        let header = Header::unmarshal(raw_packet)?;
+        let payload_len = raw_packet.remaining();
+        let payload = raw_packet.copy_to_bytes(payload_len);
+        if header.padding {
+            if payload_len > 0 {
+                let padding_len = payload[payload_len - 1] as usize;
+                if padding_len <= payload_len {
+                    Ok((
+                        Packet {
+                            header,
+                            payload: payload.slice(..payload_len - padding_len),
+                        },
+                        padding_len as u8,
+                    ))
+                } else {
+                    Err(Error::ErrShortPacket.into())
+                }
+            } else {
+                Err(Error::ErrShortPacket.into())
+            }
+        } else {
+            Ok((Packet { header, payload }, 0))
+        }
+    }
+
+    /// validate checks the structure of a received packet beyond what unmarshal
+    /// tolerates: the version must be 2 and the CSRC count must fit the header.
+    /// unmarshal already rejects padding and header extensions that claim more
+    /// bytes than present.
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.header.version != 2 {
+            return Err(Error::ErrInvalidVersion);
+        }
+        if self.header.csrc.len() > 15 {
+            return Err(Error::ErrTooManyCsrc);
+        }
+
+        Ok(())
+    }
+}
+
 /// getPadding Returns the padding required to make the length a multiple of 4
 fn get_padding(len: usize) -> usize {
     if len % 4 == 0 {
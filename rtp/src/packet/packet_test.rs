@@ -141,6 +141,39 @@ fn test_padding() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_unmarshal_with_padding_size() -> Result<()> {
+    let raw_pkt = Bytes::from_static(&[
+        0xa0, 0x60, 0x19, 0x58, 0x63, 0xff, 0x7d, 0x7c, 0x4b, 0x98, 0xd4, 0x0a, 0x67, 0x4d, 0x00,
+        0x29, 0x9a, 0x64, 0x03, 0xc0, 0x11, 0x3f, 0x2c, 0xd4, 0x04, 0x04, 0x05, 0x00, 0x00, 0x03,
+        0x03, 0xe8, 0x00, 0x00, 0xea, 0x60, 0x04, 0x00, 0x00, 0x03,
+    ]);
+    let (packet, padding_size) = Packet::unmarshal_with_padding_size(&mut raw_pkt.clone())?;
+    assert_eq!(packet, Packet::unmarshal(&mut raw_pkt.clone())?);
+    assert_eq!(padding_size, 3, "padding size mismatch");
+
+    // a padding only packet, as libwebrtc sends to keep the bitrate steady
+    let mut raw_pkt = BytesMut::from(
+        &[
+            0xa0, 0x60, 0x19, 0x58, 0x63, 0xff, 0x7d, 0x7c, 0x4b, 0x98, 0xd4, 0x0a,
+        ][..],
+    );
+    raw_pkt.extend_from_slice(&[0u8; 223]);
+    raw_pkt.extend_from_slice(&[224]);
+    let (packet, padding_size) = Packet::unmarshal_with_padding_size(&mut raw_pkt.freeze())?;
+    assert!(packet.payload.is_empty(), "payload should be empty");
+    assert_eq!(padding_size, 224, "padding size mismatch");
+
+    let raw_pkt = Bytes::from_static(&[
+        0x80, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, 0x1c, 0x64, 0x27, 0x82, 0x98, 0x36, 0xbe,
+        0x88,
+    ]);
+    let (_, padding_size) = Packet::unmarshal_with_padding_size(&mut raw_pkt.clone())?;
+    assert_eq!(padding_size, 0, "unpadded packet should have no padding");
+
+    Ok(())
+}
+
 #[test]
 fn test_packet_marshal_unmarshal() -> Result<()> {
     let pkt = Packet {
@@ -1241,3 +1274,123 @@ fn test_round_trip() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_unmarshal_malformed() {
+    let cases: Vec<(&str, &[u8], Error)> = vec![
+        (
+            "OneByteExtensionOverrunsPacket",
+            &[
+                0x90, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, // timestamp
+                0x1c, 0x64, 0x27, 0x82, // SSRC
+                0xBE, 0xDE, 0x00, 0x01, // have 1 extension word
+                0x1F, 0xAA, 0xBB, 0xCC, // claims 16 bytes
+            ],
+            Error::ErrHeaderSizeInsufficientForExtension,
+        ),
+        (
+            "OneByteExtensionOverrunsIntoPayload",
+            &[
+                0x90, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, // timestamp
+                0x1c, 0x64, 0x27, 0x82, // SSRC
+                0xBE, 0xDE, 0x00, 0x01, // have 1 extension word
+                0x17, 0xAA, 0xBB, 0xCC, // claims 8 bytes
+                0x98, 0x36, 0xbe, 0x88, 0x9e, // payload
+            ],
+            Error::ErrHeaderSizeInsufficientForExtension,
+        ),
+        (
+            "TwoByteExtensionMissingLength",
+            &[
+                0x90, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, // timestamp
+                0x1c, 0x64, 0x27, 0x82, // SSRC
+                0x10, 0x00, 0x00, 0x01, // have 1 extension word
+                0x00, 0x00, 0x00, 0x05, // padding, then an id without a length
+            ],
+            Error::ErrHeaderSizeInsufficientForExtension,
+        ),
+        (
+            "TwoByteExtensionOverrunsPacket",
+            &[
+                0x90, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, // timestamp
+                0x1c, 0x64, 0x27, 0x82, // SSRC
+                0x10, 0x00, 0x00, 0x01, // have 1 extension word
+                0x05, 0xFF, 0xAA, 0xBB, // claims 255 bytes
+            ],
+            Error::ErrHeaderSizeInsufficientForExtension,
+        ),
+        (
+            "PaddingOverrunsPacket",
+            &[
+                0xa0, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, // timestamp
+                0x1c, 0x64, 0x27, 0x82, // SSRC
+                0x98, 0x36, 0xbe, 0x09, // claims 9 bytes of padding
+            ],
+            Error::ErrShortPacket,
+        ),
+    ];
+
+    for (name, input, expected) in cases {
+        let mut input = input;
+        let err = Packet::unmarshal(&mut input).expect_err(name);
+        assert_eq!(expected, err, "{name}");
+    }
+}
+
+#[test]
+fn test_validate() -> Result<()> {
+    let mut raw: &[u8] = &[
+        0x80, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, // timestamp
+        0x1c, 0x64, 0x27, 0x82, // SSRC
+        0x98, 0x36, 0xbe, 0x88,
+    ];
+    let packet = Packet::unmarshal(&mut raw)?;
+    packet.validate()?;
+
+    // version 1
+    let mut raw: &[u8] = &[
+        0x40, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, // timestamp
+        0x1c, 0x64, 0x27, 0x82, // SSRC
+        0x98, 0x36, 0xbe, 0x88,
+    ];
+    let packet = Packet::unmarshal(&mut raw)?;
+    assert_eq!(packet.validate(), Err(Error::ErrInvalidVersion));
+
+    let packet = Packet {
+        header: Header {
+            version: 2,
+            csrc: vec![0; 16],
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    assert_eq!(packet.validate(), Err(Error::ErrTooManyCsrc));
+
+    Ok(())
+}
+
+#[test]
+fn test_unmarshal_mutated_does_not_panic() {
+    let valid: &[u8] = &[
+        0xb1, 0xe0, 0x69, 0x8f, 0xd9, 0xc2, 0x93, 0xda, // timestamp
+        0x1c, 0x64, 0x27, 0x82, // SSRC
+        0x00, 0x00, 0x11, 0x11, // CSRC
+        0xBE, 0xDE, 0x00, 0x02, // have 2 extension words
+        0x10, 0xAA, 0x21, 0xBB, 0xCC, 0xDD, 0x00, 0x00, // extensions
+        0x98, 0x36, 0xbe, 0x88, 0x9e, 0x00, 0x00, 0x03, // payload and padding
+    ];
+
+    for len in 0..valid.len() {
+        let mut truncated = &valid[..len];
+        let _ = Packet::unmarshal(&mut truncated);
+    }
+    for i in 0..valid.len() {
+        for bit in 0..8 {
+            let mut mutated = valid.to_vec();
+            mutated[i] ^= 1 << bit;
+            if let Ok(packet) = Packet::unmarshal(&mut &mutated[..]) {
+                let _ = packet.validate();
+            }
+        }
+    }
+}
@@ -18,6 +18,12 @@ use crate::sequence::*;
 pub trait Payloader: fmt::Debug {
     fn payload(&mut self, mtu: usize, b: &Bytes) -> Result<Vec<Bytes>>;
     fn clone_to(&self) -> Box<dyn Payloader + Send + Sync>;
+
+    /// marker tells whether the payload at index of the count the last call to payload
+    /// returned carries the marker bit. By default the last payload of each frame does.
+    fn marker(&self, index: usize, count: usize) -> bool {
+        index == count - 1
+    }
 }
 
 impl Clone for Box<dyn Payloader + Send + Sync> {
@@ -92,6 +98,29 @@ pub fn new_packetizer(
     payloader: Box<dyn Payloader + Send + Sync>,
     sequencer: Box<dyn Sequencer + Send + Sync>,
     clock_rate: u32,
+) -> impl Packetizer {
+    new_packetizer_with_timestamp(
+        mtu,
+        payload_type,
+        ssrc,
+        payloader,
+        sequencer,
+        clock_rate,
+        rand::random::<u32>(),
+    )
+}
+
+/// new_packetizer_with_timestamp returns a packetizer whose first packet carries
+/// the given RTP timestamp instead of a random one. Timestamps advance from it
+/// and wrap around at u32::MAX
+pub fn new_packetizer_with_timestamp(
+    mtu: usize,
+    payload_type: u8,
+    ssrc: u32,
+    payloader: Box<dyn Payloader + Send + Sync>,
+    sequencer: Box<dyn Sequencer + Send + Sync>,
+    clock_rate: u32,
+    timestamp: u32,
 ) -> impl Packetizer {
     PacketizerImpl {
         mtu,
@@ -99,7 +128,7 @@ pub fn new_packetizer(
         ssrc,
         payloader,
         sequencer,
-        timestamp: rand::random::<u32>(),
+        timestamp,
         clock_rate,
         abs_send_time: 0,
         time_gen: None,
@@ -121,7 +150,7 @@ impl Packetizer for PacketizerImpl {
                     version: 2,
                     padding: false,
                     extension: false,
-                    marker: i == payloads_len - 1,
+                    marker: self.payloader.marker(i, payloads_len),
                     payload_type: self.payload_type,
                     sequence_number: self.sequencer.next_sequence_number(),
                     timestamp: self.timestamp, //TODO: Figure out how to do timestamps
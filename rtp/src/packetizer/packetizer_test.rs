@@ -108,3 +108,23 @@ fn test_packetizer_timestamp_rollover_does_not_panic() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_packetizer_initial_state_wraps() -> Result<()> {
+    let g722 = Box::new(g7xx::G722Payloader {});
+    let seq = Box::new(new_fixed_sequencer(u16::MAX));
+
+    let payload = Bytes::from_static(&[0; 10]);
+    let mut packetizer =
+        new_packetizer_with_timestamp(100, 98, 0x1234ABCD, g722, seq, 8000, u32::MAX - 79);
+
+    let first = packetizer.packetize(&payload, 160)?;
+    let second = packetizer.packetize(&payload, 160)?;
+
+    assert_eq!(first[0].header.sequence_number, u16::MAX);
+    assert_eq!(first[0].header.timestamp, u32::MAX - 79);
+    assert_eq!(second[0].header.sequence_number, 0);
+    assert_eq!(second[0].header.timestamp, 80);
+
+    Ok(())
+}
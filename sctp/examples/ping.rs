@@ -62,12 +62,8 @@ async fn main() -> Result<(), Error> {
     conn.connect(server).await.unwrap();
     println!("connecting {server}..");
 
-    let config = Config {
-        net_conn: conn,
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
-        name: "client".to_owned(),
-    };
+    let mut config = Config::new(conn);
+    config.name = "client".to_owned();
     let a = Association::client(config).await?;
     println!("created a client");
 
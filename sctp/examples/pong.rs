@@ -62,12 +62,8 @@ async fn main() -> Result<(), Error> {
     let conn = DisconnectedPacketConn::new(Arc::new(UdpSocket::bind(host).await.unwrap()));
     println!("listening {}...", conn.local_addr().unwrap());
 
-    let config = Config {
-        net_conn: Arc::new(conn),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
-        name: "server".to_owned(),
-    };
+    let mut config = Config::new(Arc::new(conn));
+    config.name = "server".to_owned();
     let a = Association::server(config).await?;
     println!("created a server");
 
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod association_internal_test;
 
+use arc_swap::ArcSwapOption;
 use async_trait::async_trait;
 use portable_atomic::AtomicBool;
 
@@ -15,6 +16,8 @@ pub struct AssociationInternal {
     pub(crate) max_message_size: Arc<AtomicU32>,
     pub(crate) inflight_queue_length: Arc<AtomicUsize>,
     pub(crate) will_send_shutdown: Arc<AtomicBool>,
+    /// the error of the ABORT received from the peer, if any
+    pub(crate) abort_error: Arc<ArcSwapOption<Error>>,
     awake_write_loop_ch: Arc<mpsc::Sender<()>>,
 
     peer_verification_tag: u32,
@@ -23,12 +26,14 @@ pub struct AssociationInternal {
     pub(crate) my_next_tsn: u32, // nextTSN
     peer_last_tsn: u32,          // lastRcvdTSN
     min_tsn2measure_rtt: u32,    // for RTT measurement
-    will_send_forward_tsn: bool,
+    pub(crate) will_send_forward_tsn: bool,
     will_retransmit_fast: bool,
     will_retransmit_reconfig: bool,
 
     will_send_shutdown_ack: bool,
     will_send_shutdown_complete: bool,
+    // HEARTBEATs sent since the last HEARTBEAT-ACK of the peer
+    heartbeats_unacked: u32,
 
     // Reconfig
     my_next_rsn: u32,
@@ -42,19 +47,27 @@ pub struct AssociationInternal {
     pub(crate) my_max_num_outbound_streams: u16,
     my_cookie: Option<ParamStateCookie>,
     payload_queue: PayloadQueue,
-    inflight_queue: PayloadQueue,
+    pub(crate) inflight_queue: PayloadQueue,
     pending_queue: Arc<PendingQueue>,
     control_queue: ControlQueue,
     pub(crate) mtu: u32,
     max_payload_size: u32, // max DATA chunk payload size
-    cumulative_tsn_ack_point: u32,
-    advanced_peer_tsn_ack_point: u32,
+    pub(crate) cumulative_tsn_ack_point: u32,
+    // TSN ranges the latest SACK acknowledged beyond cumulative_tsn_ack_point
+    pub(crate) peer_gap_ack_blocks: Vec<(u32, u32)>,
+    pub(crate) advanced_peer_tsn_ack_point: u32,
     use_forward_tsn: bool,
 
     // Congestion control parameters
     pub(crate) max_receive_buffer_size: u32,
-    pub(crate) cwnd: u32,     // my congestion window size
-    rwnd: u32,                // calculated peer's receiver windows size
+    pub(crate) max_reassembly_bytes: u32,
+    reassembly_swept_at: SystemTime,
+    no_delay: bool,
+    pub(crate) memory_budget: Option<Arc<MemoryBudget>>,
+    pub(crate) cwnd: u32, // my congestion window size
+    #[cfg(feature = "test-utils")]
+    pub(crate) fixed_cwnd: Option<u32>, // pins cwnd, for tests only
+    rwnd: u32,            // calculated peer's receiver windows size
     pub(crate) ssthresh: u32, // slow start threshold
     partial_bytes_acked: u32,
     pub(crate) in_fast_recovery: bool,
@@ -108,6 +121,12 @@ impl AssociationInternal {
             config.max_message_size
         };
 
+        let max_reassembly_bytes = if config.max_reassembly_bytes == 0 {
+            max_receive_buffer_size.saturating_mul(DEFAULT_MAX_REASSEMBLY_BYTES_FACTOR)
+        } else {
+            config.max_reassembly_bytes
+        };
+
         let inflight_queue_length = Arc::new(AtomicUsize::new(0));
 
         let mut tsn = random::<u32>();
@@ -130,6 +149,7 @@ impl AssociationInternal {
             max_message_size: Arc::new(AtomicU32::new(max_message_size)),
 
             will_send_shutdown: Arc::new(AtomicBool::default()),
+            abort_error: Arc::new(ArcSwapOption::empty()),
             awake_write_loop_ch,
             peer_verification_tag: 0,
             my_verification_tag: random::<u32>(),
@@ -142,6 +162,7 @@ impl AssociationInternal {
             will_retransmit_reconfig: false,
             will_send_shutdown_ack: false,
             will_send_shutdown_complete: false,
+            heartbeats_unacked: 0,
 
             my_next_rsn: tsn,
             reconfigs: HashMap::new(),
@@ -160,11 +181,18 @@ impl AssociationInternal {
             mtu,
             max_payload_size: mtu - (COMMON_HEADER_SIZE + DATA_CHUNK_HEADER_SIZE),
             cumulative_tsn_ack_point: tsn - 1,
+            peer_gap_ack_blocks: vec![],
             advanced_peer_tsn_ack_point: tsn - 1,
             use_forward_tsn: false,
 
             max_receive_buffer_size,
+            max_reassembly_bytes,
+            no_delay: config.no_delay,
+            memory_budget: config.memory_budget.clone(),
+            reassembly_swept_at: SystemTime::now(),
             cwnd,
+            #[cfg(feature = "test-utils")]
+            fixed_cwnd: None,
             rwnd: 0,
             ssthresh: 0,
             partial_bytes_acked: 0,
@@ -228,6 +256,42 @@ impl AssociationInternal {
         }
     }
 
+    /// send_heartbeat probes the peer with a HEARTBEAT. Nothing is sent until the
+    /// association is established. It fails with Error::ErrPeerUnreachable, recorded as
+    /// the abort error of the association, once HEARTBEAT_MAX_RETRANS HEARTBEATs in a
+    /// row went unacknowledged.
+    /// caller must hold self.lock
+    pub(crate) fn send_heartbeat(&mut self) -> Result<()> {
+        if self.get_state() != AssociationState::Established {
+            return Ok(());
+        }
+        if self.heartbeats_unacked >= HEARTBEAT_MAX_RETRANS {
+            self.set_abort_error(Error::ErrPeerUnreachable);
+            return Err(Error::ErrPeerUnreachable);
+        }
+        log::trace!("[{}] sending HEARTBEAT", self.name);
+
+        let sent_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        let outbound = Packet {
+            source_port: self.source_port,
+            destination_port: self.destination_port,
+            verification_tag: self.peer_verification_tag,
+            chunks: vec![Box::new(ChunkHeartbeat {
+                params: vec![Box::new(ParamHeartbeatInfo {
+                    heartbeat_information: Bytes::copy_from_slice(&sent_at.to_be_bytes()),
+                })],
+            })],
+        };
+
+        self.heartbeats_unacked += 1;
+        self.control_queue.push_back(outbound);
+        self.awake_write_loop();
+        Ok(())
+    }
+
     /// caller must hold self.lock
     fn send_cookie_echo(&mut self) -> Result<()> {
         if let Some(stored_cookie_echo) = &self.stored_cookie_echo {
@@ -331,11 +395,14 @@ impl AssociationInternal {
     fn unregister_stream(&mut self, stream_identifier: u16) {
         let s = self.streams.remove(&stream_identifier);
         if let Some(s) = s {
+            self.pending_queue
+                .set_stream_priority(stream_identifier, DEFAULT_STREAM_PRIORITY);
             // NOTE: shutdown is not used here because it resets the stream.
             if !s.read_shutdown.swap(true, Ordering::SeqCst) {
                 s.read_notifier.notify_waiters();
             }
             s.write_shutdown.store(true, Ordering::SeqCst);
+            s.buffer_released_notifier.notify_waiters();
         }
     }
 
@@ -629,6 +696,17 @@ impl AssociationInternal {
         }
     }
 
+    /// set_cwnd updates the congestion window, unless it is pinned by set_fixed_cwnd.
+    pub(crate) fn set_cwnd(&mut self, cwnd: u32) {
+        #[cfg(feature = "test-utils")]
+        if let Some(fixed_cwnd) = self.fixed_cwnd {
+            self.cwnd = fixed_cwnd;
+            return;
+        }
+
+        self.cwnd = cwnd;
+    }
+
     /// get_state atomically returns the state of the Association.
     fn get_state(&self) -> AssociationState {
         self.state.load(Ordering::SeqCst).into()
@@ -940,7 +1018,11 @@ impl AssociationInternal {
         let mut stream_handle_data = false;
         if can_push {
             if let Some(_s) = self.get_or_create_stream(d.stream_identifier) {
-                if self.get_my_receiver_window_credit().await > 0 {
+                let budget_exhausted = self
+                    .memory_budget
+                    .as_ref()
+                    .is_some_and(|budget| budget.is_exhausted());
+                if self.get_my_receiver_window_credit().await > 0 && !budget_exhausted {
                     // Pass the new chunk to stream level as soon as it arrives
                     self.payload_queue.push(d.clone(), self.peer_last_tsn);
                     stream_handle_data = true;
@@ -975,11 +1057,70 @@ impl AssociationInternal {
             if let Some(s) = self.streams.get_mut(&d.stream_identifier) {
                 s.handle_data(d.clone()).await;
             }
+
+            self.discard_expired_messages().await;
+            if self.get_num_bytes_in_reassembly_queues().await > self.max_reassembly_bytes {
+                // The peer keeps sending fragments of messages it never completes,
+                // abort the association instead of buffering them any longer.
+                log::warn!(
+                    "[{}] reassembly queues exceeded {} bytes, aborting",
+                    self.name,
+                    self.max_reassembly_bytes
+                );
+                self.send_abort(OUT_OF_RESOURCE);
+                return Err(Error::ErrReassemblyBytesExceeded);
+            }
         }
 
         self.handle_peer_last_tsn_and_acknowledgement(immediate_sack)
     }
 
+    /// discard_expired_messages removes the messages of the partially reliable
+    /// streams that are still incomplete REASSEMBLY_TIMEOUT after one of their
+    /// fragments was received. The messages of the reliable streams are kept, their
+    /// missing fragments are still retransmitted, and max_reassembly_bytes bounds
+    /// what they hold. It runs at most once a second.
+    async fn discard_expired_messages(&mut self) {
+        let now = SystemTime::now();
+        match now.duration_since(self.reassembly_swept_at) {
+            Ok(elapsed) if elapsed >= Duration::from_secs(1) => {}
+            _ => return,
+        }
+        self.reassembly_swept_at = now;
+
+        if let Some(deadline) = now.checked_sub(REASSEMBLY_TIMEOUT) {
+            for s in self.streams.values() {
+                s.discard_expired(deadline, self.use_forward_tsn).await;
+            }
+        }
+    }
+
+    /// send_abort queues an ABORT chunk with the given error cause.
+    fn send_abort(&mut self, code: ErrorCauseCode) {
+        let abort = ChunkAbort {
+            error_causes: vec![ErrorCause {
+                code,
+                raw: Bytes::new(),
+            }],
+        };
+        let packet = Packet {
+            verification_tag: self.peer_verification_tag,
+            source_port: self.source_port,
+            destination_port: self.destination_port,
+            chunks: vec![Box::new(abort)],
+        };
+        self.control_queue.push_back(packet);
+        self.awake_write_loop();
+    }
+
+    async fn get_num_bytes_in_reassembly_queues(&self) -> u32 {
+        let mut bytes_queued = 0;
+        for s in self.streams.values() {
+            bytes_queued += s.get_num_bytes_in_reassembly_queue().await as u32;
+        }
+        bytes_queued
+    }
+
     /// A common routine for handle_data and handle_forward_tsn routines
     fn handle_peer_last_tsn_and_acknowledgement(
         &mut self,
@@ -1035,10 +1176,7 @@ impl AssociationInternal {
     }
 
     pub(crate) async fn get_my_receiver_window_credit(&self) -> u32 {
-        let mut bytes_queued = 0;
-        for s in self.streams.values() {
-            bytes_queued += s.get_num_bytes_in_reassembly_queue().await as u32;
-        }
+        let bytes_queued = self.get_num_bytes_in_reassembly_queues().await;
 
         if bytes_queued >= self.max_receive_buffer_size {
             0
@@ -1066,7 +1204,7 @@ impl AssociationInternal {
 
     /// create_stream creates a stream. The caller should hold the lock and check no stream exists for this id.
     fn create_stream(&mut self, stream_identifier: u16, accept: bool) -> Option<Arc<Stream>> {
-        let s = Arc::new(Stream::new(
+        let mut s = Stream::new(
             format!("{}:{}", stream_identifier, self.name),
             stream_identifier,
             self.max_payload_size,
@@ -1074,7 +1212,9 @@ impl AssociationInternal {
             Arc::clone(&self.state),
             self.awake_write_loop_ch.clone(),
             Arc::clone(&self.pending_queue),
-        ));
+        );
+        s.memory_budget = self.memory_budget.clone();
+        let s = Arc::new(s);
 
         if accept {
             if self.accept_ch_tx.try_send(Arc::clone(&s)).is_ok() {
@@ -1265,8 +1405,8 @@ impl AssociationInternal {
             //      outstanding DATA chunk(s) acknowledged, and 2) the destination's
             //      path MTU.
             if !self.in_fast_recovery && self.pending_queue.len() > 0 {
-                self.cwnd += std::cmp::min(total_bytes_acked as u32, self.cwnd); // TCP way
-                                                                                 // self.cwnd += min32(uint32(total_bytes_acked), self.mtu) // SCTP way (slow)
+                self.set_cwnd(self.cwnd + std::cmp::min(total_bytes_acked as u32, self.cwnd)); // TCP way
+                                                                                               // self.cwnd += min32(uint32(total_bytes_acked), self.mtu) // SCTP way (slow)
                 log::trace!(
                     "[{}] updated cwnd={} ssthresh={} acked={} (SS)",
                     self.name,
@@ -1301,7 +1441,7 @@ impl AssociationInternal {
             //      reset partial_bytes_acked to (partial_bytes_acked - cwnd).
             if self.partial_bytes_acked >= self.cwnd && self.pending_queue.len() > 0 {
                 self.partial_bytes_acked -= self.cwnd;
-                self.cwnd += self.mtu;
+                self.set_cwnd(self.cwnd + self.mtu);
                 log::trace!(
                     "[{}] updated cwnd={} ssthresh={} acked={} (CA)",
                     self.name,
@@ -1349,7 +1489,7 @@ impl AssociationInternal {
                             self.in_fast_recovery = true;
                             self.fast_recover_exit_point = htna;
                             self.ssthresh = std::cmp::max(self.cwnd / 2, 4 * self.mtu);
-                            self.cwnd = self.ssthresh;
+                            self.set_cwnd(self.ssthresh);
                             self.partial_bytes_acked = 0;
                             self.will_retransmit_fast = true;
 
@@ -1414,6 +1554,17 @@ impl AssociationInternal {
             return Ok(vec![]);
         }
 
+        self.peer_gap_ack_blocks = d
+            .gap_ack_blocks
+            .iter()
+            .map(|g| {
+                (
+                    d.cumulative_tsn_ack.wrapping_add(g.start as u32),
+                    d.cumulative_tsn_ack.wrapping_add(g.end as u32),
+                )
+            })
+            .collect();
+
         // Process selective ack
         let (bytes_acked_per_stream, htna) = self.process_selective_ack(d).await?;
 
@@ -1974,7 +2125,7 @@ impl AssociationInternal {
 
     /// bundle_data_chunks_into_packets packs DATA chunks into packets. It tries to bundle
     /// DATA chunks into a packet so long as the resulting packet size does not exceed
-    /// the path MTU.
+    /// the path MTU, unless no_delay is set and every chunk gets a packet of its own.
     fn bundle_data_chunks_into_packets(&self, chunks: Vec<ChunkPayloadData>) -> Vec<Packet> {
         let mut packets = vec![];
         let mut chunks_to_send = vec![];
@@ -1986,7 +2137,9 @@ impl AssociationInternal {
             //   single packet.  Furthermore, DATA chunks being retransmitted MAY be
             //   bundled with new DATA chunks, as long as the resulting packet size
             //   does not exceed the path MTU.
-            if bytes_in_packet + c.user_data.len() as u32 > self.mtu {
+            if (self.no_delay && !chunks_to_send.is_empty())
+                || bytes_in_packet + c.user_data.len() as u32 > self.mtu
+            {
                 packets.push(self.create_packet(chunks_to_send));
                 chunks_to_send = vec![];
                 bytes_in_packet = COMMON_HEADER_SIZE;
@@ -2128,6 +2281,25 @@ impl AssociationInternal {
         }
     }
 
+    /// handle_abort records the causes of the ABORT received from the peer, the reads of the
+    /// streams then fail with them once the association is closed
+    fn handle_abort(&mut self, c: &ChunkAbort) -> Error {
+        log::debug!("[{}] ABORT received: {}", self.name, c);
+        let err = Error::ErrAbortReceived(c.error_causes.iter().map(|cause| cause.code).collect());
+        self.set_abort_error(err.clone());
+        err
+    }
+
+    /// set_abort_error records the error the association ended with, the reads of the
+    /// streams fail with it
+    fn set_abort_error(&mut self, err: Error) {
+        let abort_error = Arc::new(err);
+        self.abort_error.store(Some(Arc::clone(&abort_error)));
+        for s in self.streams.values() {
+            s.abort_error.store(Some(Arc::clone(&abort_error)));
+        }
+    }
+
     fn pack(p: Packet) -> Vec<Packet> {
         vec![p]
     }
@@ -2167,12 +2339,16 @@ impl AssociationInternal {
             } else {
                 self.handle_init(p, c).await?
             }
-        } else if chunk_any.downcast_ref::<ChunkAbort>().is_some()
-            || chunk_any.downcast_ref::<ChunkError>().is_some()
-        {
+        } else if let Some(c) = chunk_any.downcast_ref::<ChunkAbort>() {
+            return Err(self.handle_abort(c));
+        } else if chunk_any.downcast_ref::<ChunkError>().is_some() {
             return Err(Error::ErrChunk);
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkHeartbeat>() {
             self.handle_heartbeat(c).await?
+        } else if chunk_any.downcast_ref::<ChunkHeartbeatAck>().is_some() {
+            log::trace!("[{}] chunkHeartbeatAck", self.name);
+            self.heartbeats_unacked = 0;
+            vec![]
         } else if let Some(c) = chunk_any.downcast_ref::<ChunkCookieEcho>() {
             self.handle_cookie_echo(c).await?
         } else if chunk_any.downcast_ref::<ChunkCookieAck>().is_some() {
@@ -2346,7 +2522,7 @@ impl RtxTimerObserver for AssociationInternal {
                 //      cwnd = 1*MTU
 
                 self.ssthresh = std::cmp::max(self.cwnd / 2, 4 * self.mtu);
-                self.cwnd = self.mtu;
+                self.set_cwnd(self.mtu);
                 log::trace!(
                     "[{}] updated cwnd={} ssthresh={} inflight={} (RTO)",
                     self.name,
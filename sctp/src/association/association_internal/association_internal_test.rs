@@ -69,10 +69,8 @@ fn create_association_internal(config: Config) -> AssociationInternal {
 #[test]
 fn test_create_forward_tsn_forward_one_abandoned() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
 
     a.cumulative_tsn_ack_point = 9;
@@ -102,10 +100,8 @@ fn test_create_forward_tsn_forward_one_abandoned() -> Result<()> {
 #[test]
 fn test_create_forward_tsn_forward_two_abandoned_with_the_same_si() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
 
     a.cumulative_tsn_ack_point = 9;
@@ -173,10 +169,8 @@ fn test_create_forward_tsn_forward_two_abandoned_with_the_same_si() -> Result<()
 #[tokio::test]
 async fn test_handle_forward_tsn_forward_3unreceived_chunks() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
     a.use_forward_tsn = true;
 
@@ -212,10 +206,8 @@ async fn test_handle_forward_tsn_forward_3unreceived_chunks() -> Result<()> {
 #[tokio::test]
 async fn test_handle_forward_tsn_forward_1for1_missing() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
     a.use_forward_tsn = true;
 
@@ -265,10 +257,8 @@ async fn test_handle_forward_tsn_forward_1for1_missing() -> Result<()> {
 #[tokio::test]
 async fn test_handle_forward_tsn_forward_1for2_missing() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
     a.use_forward_tsn = true;
 
@@ -316,10 +306,8 @@ async fn test_handle_forward_tsn_forward_1for2_missing() -> Result<()> {
 #[tokio::test]
 async fn test_handle_forward_tsn_dup_forward_tsn_chunk_should_generate_sack() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
     a.use_forward_tsn = true;
 
@@ -350,10 +338,8 @@ async fn test_assoc_create_new_stream() -> Result<()> {
     let (awake_write_loop_ch_tx, _awake_write_loop_ch_rx) = mpsc::channel(1);
     let mut a = AssociationInternal::new(
         Config {
-            net_conn: Arc::new(DumbConn {}),
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
             name: "client".to_owned(),
+            ..Config::new(Arc::new(DumbConn {}))
         },
         close_loop_ch_tx,
         accept_ch_tx,
@@ -394,10 +380,8 @@ async fn test_assoc_create_new_stream() -> Result<()> {
 
 async fn handle_init_test(name: &str, initial_state: AssociationState, expect_err: bool) {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
     a.set_state(initial_state);
     let pkt = Packet {
@@ -484,10 +468,8 @@ async fn test_assoc_handle_init() -> Result<()> {
 #[tokio::test]
 async fn test_assoc_max_message_size_default() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
     assert_eq!(
         a.max_message_size.load(Ordering::SeqCst),
@@ -529,10 +511,9 @@ async fn test_assoc_max_message_size_default() -> Result<()> {
 #[tokio::test]
 async fn test_assoc_max_message_size_explicit() -> Result<()> {
     let mut a = create_association_internal(Config {
-        net_conn: Arc::new(DumbConn {}),
-        max_receive_buffer_size: 0,
         max_message_size: 30000,
         name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
     });
 
     assert_eq!(
@@ -571,3 +552,272 @@ async fn test_assoc_max_message_size_explicit() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_assoc_max_reassembly_bytes_default() -> Result<()> {
+    let a = create_association_internal(Config {
+        name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
+    });
+
+    assert_eq!(
+        a.max_reassembly_bytes,
+        4 * INITIAL_RECV_BUF_SIZE,
+        "should match"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_max_reassembly_bytes_exceeded() -> Result<()> {
+    let mut a = create_association_internal(Config {
+        max_reassembly_bytes: 8,
+        name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
+    });
+
+    let stream = a.create_stream(0, false);
+    assert!(stream.is_some(), "should succeed");
+
+    let p = a
+        .handle_data(&ChunkPayloadData {
+            beginning_fragment: true,
+            tsn: a.peer_last_tsn + 1,
+            stream_identifier: 0,
+            stream_sequence_number: 0,
+            user_data: Bytes::from_static(b"ABCDEF"),
+            ..Default::default()
+        })
+        .await?;
+    assert!(p.is_empty(), "should return empty");
+    assert!(a.control_queue.is_empty(), "should not abort");
+
+    let result = a
+        .handle_data(&ChunkPayloadData {
+            beginning_fragment: true,
+            tsn: a.peer_last_tsn + 1,
+            stream_identifier: 0,
+            stream_sequence_number: 1,
+            user_data: Bytes::from_static(b"GHIJKL"),
+            ..Default::default()
+        })
+        .await;
+    assert_eq!(
+        result.err(),
+        Some(Error::ErrReassemblyBytesExceeded),
+        "should be Error::ErrReassemblyBytesExceeded"
+    );
+
+    let packet = a.control_queue.pop_front();
+    assert!(packet.is_some(), "should queue an ABORT");
+    if let Some(packet) = packet {
+        let abort = packet.chunks[0].as_any().downcast_ref::<ChunkAbort>();
+        assert!(abort.is_some(), "should be an ABORT chunk");
+        if let Some(abort) = abort {
+            assert_eq!(abort.error_causes[0].code, OUT_OF_RESOURCE);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_reassembly_timeout_reliable() -> Result<()> {
+    let mut a = create_association_internal(Config {
+        name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
+    });
+    a.use_forward_tsn = true;
+    a.set_state(AssociationState::Established);
+
+    let stream = a.create_stream(0, false);
+    assert!(stream.is_some(), "should succeed");
+    let stream = stream.unwrap();
+
+    a.handle_data(&ChunkPayloadData {
+        beginning_fragment: true,
+        tsn: a.peer_last_tsn + 1,
+        stream_identifier: 0,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"ABCDEF"),
+        since: SystemTime::now() - REASSEMBLY_TIMEOUT * 2,
+        ..Default::default()
+    })
+    .await?;
+
+    // the incomplete message times out, the association goes on without it
+    a.reassembly_swept_at = SystemTime::now() - Duration::from_secs(2);
+    a.handle_data(&ChunkPayloadData {
+        beginning_fragment: true,
+        ending_fragment: true,
+        tsn: a.peer_last_tsn + 2,
+        stream_identifier: 0,
+        stream_sequence_number: 1,
+        user_data: Bytes::from_static(b"MNOPQR"),
+        ..Default::default()
+    })
+    .await?;
+    assert!(a.control_queue.is_empty(), "should not abort");
+    assert_eq!(a.get_state(), AssociationState::Established);
+
+    // a reliable message is kept, its retransmitted fragments complete it
+    a.handle_data(&ChunkPayloadData {
+        ending_fragment: true,
+        tsn: a.peer_last_tsn + 1,
+        stream_identifier: 0,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"GHIJKL"),
+        ..Default::default()
+    })
+    .await?;
+
+    let mut buf = vec![0u8; 16];
+    let n = stream.read(&mut buf).await?;
+    assert_eq!(
+        &buf[..n],
+        b"ABCDEFGHIJKL",
+        "the late message should be read"
+    );
+    let n = stream.read(&mut buf).await?;
+    assert_eq!(&buf[..n], b"MNOPQR");
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_reassembly_timeout_partially_reliable() -> Result<()> {
+    let mut a = create_association_internal(Config {
+        name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
+    });
+    a.use_forward_tsn = true;
+
+    let stream = a.create_stream(0, false);
+    assert!(stream.is_some(), "should succeed");
+    let stream = stream.unwrap();
+    stream.set_reliability_params(false, ReliabilityType::Rexmit, 0);
+
+    a.handle_data(&ChunkPayloadData {
+        beginning_fragment: true,
+        tsn: a.peer_last_tsn + 1,
+        stream_identifier: 0,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"ABCDEF"),
+        since: SystemTime::now() - REASSEMBLY_TIMEOUT * 2,
+        ..Default::default()
+    })
+    .await?;
+
+    a.reassembly_swept_at = SystemTime::now() - Duration::from_secs(2);
+    a.handle_data(&ChunkPayloadData {
+        beginning_fragment: true,
+        ending_fragment: true,
+        tsn: a.peer_last_tsn + 1,
+        stream_identifier: 0,
+        stream_sequence_number: 1,
+        user_data: Bytes::from_static(b"GHIJKL"),
+        ..Default::default()
+    })
+    .await?;
+    assert!(a.control_queue.is_empty(), "should not abort");
+
+    let mut buf = vec![0u8; 16];
+    let n = stream.read(&mut buf).await?;
+    assert_eq!(
+        &buf[..n],
+        b"GHIJKL",
+        "the expired message should be skipped"
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_heartbeat() -> Result<()> {
+    let mut a = create_association_internal(Config {
+        heartbeat_interval: Some(Duration::from_secs(1)),
+        name: "client".to_owned(),
+        ..Config::new(Arc::new(DumbConn {}))
+    });
+
+    a.send_heartbeat()?;
+    assert!(
+        a.control_queue.is_empty(),
+        "should not send HEARTBEAT before established"
+    );
+
+    a.set_state(AssociationState::Established);
+    a.send_heartbeat()?;
+    let packet = a.control_queue.pop_front();
+    assert!(packet.is_some(), "should queue a HEARTBEAT");
+    let heartbeat = packet.unwrap();
+    assert!(heartbeat.chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkHeartbeat>()
+        .is_some());
+
+    // The peer echoes the heartbeat info back in a HEARTBEAT-ACK
+    let ack = Packet {
+        verification_tag: a.my_verification_tag,
+        source_port: a.destination_port,
+        destination_port: a.source_port,
+        chunks: vec![Box::new(ChunkHeartbeatAck {
+            params: vec![Box::new(ParamHeartbeatInfo {
+                heartbeat_information: Bytes::from_static(&[1, 2, 3, 4]),
+            })],
+        })],
+    };
+    let ack = Packet::unmarshal(&ack.marshal()?)?;
+    assert!(ack.chunks[0]
+        .as_any()
+        .downcast_ref::<ChunkHeartbeatAck>()
+        .is_some());
+    a.handle_chunk(&ack, &ack.chunks[0]).await?;
+    assert!(
+        a.control_queue.is_empty(),
+        "should not answer HEARTBEAT-ACK"
+    );
+
+    // The acknowledged HEARTBEAT doesn't count, the peer is unreachable once it leaves
+    // HEARTBEAT_MAX_RETRANS of them in a row unanswered
+    for _ in 0..HEARTBEAT_MAX_RETRANS {
+        a.send_heartbeat()?;
+    }
+    assert_eq!(a.control_queue.len(), HEARTBEAT_MAX_RETRANS as usize);
+    assert_eq!(a.send_heartbeat(), Err(Error::ErrPeerUnreachable));
+    assert_eq!(
+        a.abort_error.load().as_deref(),
+        Some(&Error::ErrPeerUnreachable)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_bundle_data_chunks_into_packets() {
+    let chunks = || -> Vec<ChunkPayloadData> {
+        (0..3)
+            .map(|i| ChunkPayloadData {
+                beginning_fragment: true,
+                ending_fragment: true,
+                tsn: 10 + i,
+                stream_identifier: 1,
+                user_data: Bytes::from_static(b"0123456789"),
+                ..Default::default()
+            })
+            .collect()
+    };
+
+    for (no_delay, expected) in [(false, vec![3]), (true, vec![1, 1, 1])] {
+        let a = create_association_internal(Config {
+            no_delay,
+            name: "client".to_owned(),
+            ..Config::new(Arc::new(DumbConn {}))
+        });
+
+        let packets = a.bundle_data_chunks_into_packets(chunks());
+        let chunks_per_packet: Vec<usize> = packets.iter().map(|p| p.chunks.len()).collect();
+        assert_eq!(chunks_per_packet, expected, "no_delay: {no_delay}");
+    }
+}
@@ -31,10 +31,9 @@ async fn create_new_association_pair(
     // Setup client
     tokio::spawn(async move {
         let client = Association::client(Config {
-            net_conn: ca,
             max_receive_buffer_size: recv_buf_size,
-            max_message_size: 0,
             name: "client".to_owned(),
+            ..Config::new(ca)
         })
         .await;
 
@@ -47,10 +46,9 @@ async fn create_new_association_pair(
     // Setup server
     tokio::spawn(async move {
         let server = Association::server(Config {
-            net_conn: cb,
             max_receive_buffer_size: recv_buf_size,
-            max_message_size: 0,
             name: "server".to_owned(),
+            ..Config::new(cb)
         })
         .await;
 
@@ -1543,6 +1541,268 @@ async fn test_assoc_congestion_control_congestion_avoidance() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_assoc_congestion_state() -> Result<()> {
+    const SI: u16 = 6;
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, mut a1) =
+        create_new_association_pair(&br, Arc::new(ca), Arc::new(cb), AckMode::NoDelay, 0).await?;
+
+    let (s0, s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
+
+    let state = a0.congestion_state().await;
+    {
+        let a = a0.association_internal.lock().await;
+        assert_eq!(state.cwnd, a.cwnd);
+        assert_eq!(state.ssthresh, a.ssthresh);
+        assert_eq!(state.rto, Duration::from_millis(a.rto_mgr.get_rto()));
+    }
+    assert_eq!(state.bytes_in_flight, 0);
+
+    s0.write_sctp(
+        &Bytes::from_static(&[0u8; 1000]),
+        PayloadProtocolIdentifier::Binary,
+    )
+    .await?;
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert_eq!(a0.congestion_state().await.bytes_in_flight, 1000);
+
+    flush_buffers(&br, &a0, &a1).await;
+    let mut buf = vec![0u8; 1500];
+    assert_eq!(s1.read_sctp(&mut buf).await?.0, 1000);
+    assert_eq!(a0.congestion_state().await.bytes_in_flight, 0);
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn test_assoc_set_congestion_state() -> Result<()> {
+    const SI: u16 = 6;
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, mut a1) =
+        create_new_association_pair(&br, Arc::new(ca), Arc::new(cb), AckMode::NoDelay, 0).await?;
+
+    let (_s0, _s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
+
+    a0.set_congestion_state(8000, 6000).await;
+    let state = a0.congestion_state().await;
+    assert_eq!(state.cwnd, 8000);
+    assert_eq!(state.ssthresh, 6000);
+
+    // a pinned window wins over the override
+    a0.set_fixed_cwnd(Some(4000)).await;
+    a0.set_congestion_state(8000, 5000).await;
+    let state = a0.congestion_state().await;
+    assert_eq!(state.cwnd, 4000);
+    assert_eq!(state.ssthresh, 5000);
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn test_assoc_congestion_control_fixed_cwnd() -> Result<()> {
+    const MAX_RECEIVE_BUFFER_SIZE: u32 = 64 * 1024;
+    const SI: u16 = 6;
+    const N_PACKETS_TO_SEND: u32 = 200;
+    const FIXED_CWND: u32 = 4000;
+
+    let sbuf = vec![0u8; 1000];
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, mut a1) = create_new_association_pair(
+        &br,
+        Arc::new(ca),
+        Arc::new(cb),
+        AckMode::Normal,
+        MAX_RECEIVE_BUFFER_SIZE,
+    )
+    .await?;
+
+    let (s0, s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
+
+    a0.set_fixed_cwnd(Some(FIXED_CWND)).await;
+
+    for _ in 0..N_PACKETS_TO_SEND {
+        s0.write_sctp(
+            &Bytes::from(sbuf.clone()),
+            PayloadProtocolIdentifier::Binary,
+        )
+        .await?;
+    }
+
+    let mut rbuf = vec![0u8; 3000];
+    let mut n_packets_received = 0u32;
+    while s0.buffered_amount() > 0 && n_packets_received < N_PACKETS_TO_SEND {
+        loop {
+            let n = br.tick().await;
+            if n == 0 {
+                break;
+            }
+        }
+
+        {
+            let a = a0.association_internal.lock().await;
+            assert_eq!(a.cwnd, FIXED_CWND, "cwnd should stay pinned");
+            assert!(a.inflight_queue.get_num_bytes() <= FIXED_CWND as usize);
+        }
+
+        loop {
+            let readable = {
+                let q = s1.reassembly_queue.lock().await;
+                q.is_readable()
+            };
+            if !readable {
+                break;
+            }
+            s1.read_sctp(&mut rbuf).await?;
+            n_packets_received += 1;
+        }
+    }
+
+    br.process().await;
+
+    assert_eq!(
+        n_packets_received, N_PACKETS_TO_SEND,
+        "unexpected num of packets received"
+    );
+
+    // the congestion control takes over again once the window is released
+    a0.set_fixed_cwnd(None).await;
+    for _ in 0..10 {
+        s0.write_sctp(
+            &Bytes::from(sbuf.clone()),
+            PayloadProtocolIdentifier::Binary,
+        )
+        .await?;
+    }
+    while s0.buffered_amount() > 0 {
+        loop {
+            let n = br.tick().await;
+            if n == 0 {
+                break;
+            }
+        }
+        while s1.reassembly_queue.lock().await.is_readable() {
+            s1.read_sctp(&mut rbuf).await?;
+        }
+    }
+    br.process().await;
+    {
+        let a = a0.association_internal.lock().await;
+        assert!(a.cwnd > FIXED_CWND, "cwnd should grow again");
+    }
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
+
+#[cfg(feature = "test-utils")]
+#[tokio::test]
+async fn test_assoc_stream_priority() -> Result<()> {
+    const MAX_RECEIVE_BUFFER_SIZE: u32 = 64 * 1024;
+    const SI_LOW: u16 = 1;
+    const SI_HIGH: u16 = 2;
+    const N_LOW_PACKETS_TO_SEND: u32 = 200;
+    const N_HIGH_PACKETS_TO_SEND: u32 = 5;
+    const FIXED_CWND: u32 = 4000;
+
+    let sbuf = vec![0u8; 1000];
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, mut a1) = create_new_association_pair(
+        &br,
+        Arc::new(ca),
+        Arc::new(cb),
+        AckMode::Normal,
+        MAX_RECEIVE_BUFFER_SIZE,
+    )
+    .await?;
+
+    let (s0_low, s1_low) = establish_session_pair(&br, &a0, &mut a1, SI_LOW).await?;
+    let (s0_high, s1_high) = establish_session_pair(&br, &a0, &mut a1, SI_HIGH).await?;
+    assert_eq!(s0_low.priority(), DEFAULT_STREAM_PRIORITY);
+    s0_low.set_priority(128);
+    s0_high.set_priority(1024);
+    assert_eq!(s0_high.priority(), 1024);
+
+    a0.set_fixed_cwnd(Some(FIXED_CWND)).await;
+
+    // the low priority stream saturates the congestion window...
+    for _ in 0..N_LOW_PACKETS_TO_SEND {
+        s0_low
+            .write_sctp(
+                &Bytes::from(sbuf.clone()),
+                PayloadProtocolIdentifier::Binary,
+            )
+            .await?;
+    }
+    // ...before the high priority messages are written
+    for _ in 0..N_HIGH_PACKETS_TO_SEND {
+        s0_high
+            .write_sctp(
+                &Bytes::from_static(b"urgent"),
+                PayloadProtocolIdentifier::Binary,
+            )
+            .await?;
+    }
+
+    let mut rbuf = vec![0u8; 3000];
+    let mut n_low_packets_received = 0u32;
+    let mut n_high_packets_received = 0u32;
+    let mut n_low_packets_received_before_high = None;
+    while n_low_packets_received < N_LOW_PACKETS_TO_SEND {
+        loop {
+            let n = br.tick().await;
+            if n == 0 {
+                break;
+            }
+        }
+
+        while s1_high.reassembly_queue.lock().await.is_readable() {
+            let n = s1_high.read(&mut rbuf).await?;
+            assert_eq!(&rbuf[..n], b"urgent");
+            n_high_packets_received += 1;
+        }
+        if n_high_packets_received == N_HIGH_PACKETS_TO_SEND
+            && n_low_packets_received_before_high.is_none()
+        {
+            n_low_packets_received_before_high = Some(n_low_packets_received);
+        }
+        while s1_low.reassembly_queue.lock().await.is_readable() {
+            s1_low.read(&mut rbuf).await?;
+            n_low_packets_received += 1;
+        }
+    }
+
+    br.process().await;
+
+    assert_eq!(n_high_packets_received, N_HIGH_PACKETS_TO_SEND);
+    // the high priority messages are sent with the next congestion window, they don't
+    // wait for the low priority stream to drain
+    let n_low_packets_received_before_high = n_low_packets_received_before_high.unwrap();
+    assert!(
+        n_low_packets_received_before_high <= 2 * FIXED_CWND / 1000,
+        "{n_low_packets_received_before_high} low priority packets received before the high priority ones"
+    );
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
+
 //use std::io::Write;
 
 #[tokio::test]
@@ -2073,11 +2333,12 @@ async fn test_assoc_abort() -> Result<()> {
         a.create_packet(vec![Box::new(abort)]).marshal()?
     };
 
-    let (_s0, _s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
+    let (_s0, s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
 
     // Both associations are established
     assert_eq!(a0.get_state(), AssociationState::Established);
     assert_eq!(a1.get_state(), AssociationState::Established);
+    assert_eq!(a1.abort_error(), None);
 
     let result = a0.net_conn.send(&packet).await;
     assert!(result.is_ok(), "must be ok");
@@ -2091,6 +2352,12 @@ async fn test_assoc_abort() -> Result<()> {
     assert_eq!(a0.get_state(), AssociationState::Established);
     assert_eq!(a1.get_state(), AssociationState::Closed);
 
+    // The causes of the ABORT are reported to the association and its streams
+    let expected = Error::ErrAbortReceived(vec![PROTOCOL_VIOLATION]);
+    assert_eq!(a1.abort_error(), Some(expected.clone()));
+    let mut buf = vec![0u8; 32];
+    assert_eq!(s1.read_sctp(&mut buf).await, Err(expected));
+
     close_association_pair(&br, a0, a1).await;
 
     Ok(())
@@ -2199,10 +2466,8 @@ async fn test_stats() -> Result<()> {
 
     let conn = Arc::new(FakeEchoConn::type_erased());
     let a = Association::client(Config {
-        net_conn: Arc::clone(&conn) as Arc<dyn Conn + Send + Sync>,
-        max_receive_buffer_size: 0,
-        max_message_size: 0,
         name: "client".to_owned(),
+        ..Config::new(Arc::clone(&conn) as Arc<dyn Conn + Send + Sync>)
     })
     .await?;
 
@@ -2234,10 +2499,8 @@ async fn create_assocs() -> Result<(Association, Association)> {
 
     tokio::spawn(async move {
         let a = Association::client(Config {
-            net_conn: Arc::new(udp1),
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
             name: "client".to_owned(),
+            ..Config::new(Arc::new(udp1))
         })
         .await?;
 
@@ -2248,10 +2511,8 @@ async fn create_assocs() -> Result<(Association, Association)> {
 
     tokio::spawn(async move {
         let a = Association::server(Config {
-            net_conn: Arc::new(udp2),
-            max_receive_buffer_size: 0,
-            max_message_size: 0,
             name: "server".to_owned(),
+            ..Config::new(Arc::new(udp2))
         })
         .await?;
 
@@ -2592,10 +2853,8 @@ async fn test_association_handle_packet_before_init() -> Result<()> {
 
         let (a, _) = Association::new(
             Config {
-                net_conn: Arc::new(a_conn),
-                max_message_size: 0,
-                max_receive_buffer_size: 0,
                 name: "client".to_owned(),
+                ..Config::new(Arc::new(a_conn))
             },
             true,
         )
@@ -2614,3 +2873,291 @@ async fn test_association_handle_packet_before_init() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_association_handshake_timeout() -> Result<()> {
+    for is_client in [true, false] {
+        // Nobody answers on the other end of the pipe
+        let (conn, _peer_conn) = pipe();
+        let config = Config {
+            handshake_timeout: Some(Duration::from_millis(100)),
+            name: "timeout".to_owned(),
+            ..Config::new(Arc::new(conn))
+        };
+
+        let result = if is_client {
+            Association::client(config).await
+        } else {
+            Association::server(config).await
+        };
+        assert_eq!(
+            result.err(),
+            Some(Error::ErrAssociationHandshakeTimeout),
+            "is_client={is_client} should time out"
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_association_heartbeat_peer_unreachable() -> Result<()> {
+    let interval = Duration::from_millis(20);
+    let (ca, cb) = pipe();
+    let (client, server) = tokio::join!(
+        Association::client(Config {
+            heartbeat_interval: Some(interval),
+            name: "client".to_owned(),
+            ..Config::new(Arc::new(ca))
+        }),
+        Association::server(Config {
+            name: "server".to_owned(),
+            ..Config::new(Arc::new(cb))
+        }),
+    );
+    let (client, server) = (client?, server?);
+    let stream = client
+        .open_stream(0, PayloadProtocolIdentifier::Binary)
+        .await?;
+
+    // The peer acknowledges the HEARTBEATs, the association stays up however many are sent
+    tokio::time::sleep(interval * (HEARTBEAT_MAX_RETRANS + 3)).await;
+    assert_eq!(client.abort_error(), None);
+
+    // The peer goes away without a word, its end of the pipe is no longer read
+    server.close().await?;
+
+    let mut buf = vec![0u8; 1024];
+    let result = tokio::time::timeout(Duration::from_secs(5), stream.read(&mut buf)).await;
+    assert_eq!(
+        result.ok(),
+        Some(Err(Error::ErrPeerUnreachable)),
+        "reads should fail once the peer stopped acknowledging the HEARTBEATs"
+    );
+    assert_eq!(client.abort_error(), Some(Error::ErrPeerUnreachable));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_sack_state() -> Result<()> {
+    const SI: u16 = 6;
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, mut a1) =
+        create_new_association_pair(&br, Arc::new(ca), Arc::new(cb), AckMode::NoDelay, 0).await?;
+
+    let (s0, s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
+
+    let state = a0.sack_state().await;
+    let tsn = state.cumulative_tsn_ack_point;
+    assert!(state.gap_ack_blocks.is_empty());
+    assert_eq!(state.advanced_peer_tsn_ack_point, tsn);
+    assert!(!state.forward_tsn_pending);
+
+    // The first DATA chunk is lost, the peer acknowledges the second one in a gap block
+    br.drop_next_nwrites(0, 1);
+    for _ in 0..2 {
+        s0.write_sctp(
+            &Bytes::from_static(&[0u8; 1000]),
+            PayloadProtocolIdentifier::Binary,
+        )
+        .await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    for _ in 0..5 {
+        br.tick().await;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    let state = a0.sack_state().await;
+    assert_eq!(state.cumulative_tsn_ack_point, tsn);
+    assert_eq!(state.gap_ack_blocks, vec![(tsn + 2, tsn + 2)]);
+
+    // The retransmission fills the gap
+    flush_buffers(&br, &a0, &a1).await;
+    let mut buf = vec![0u8; 1500];
+    for _ in 0..2 {
+        assert_eq!(s1.read_sctp(&mut buf).await?.0, 1000);
+    }
+    let state = a0.sack_state().await;
+    assert_eq!(state.cumulative_tsn_ack_point, tsn + 2);
+    assert!(state.gap_ack_blocks.is_empty());
+
+    // An abandoned chunk is skipped with a FORWARD-TSN
+    s0.set_reliability_params(false, ReliabilityType::Rexmit, 0);
+    br.drop_next_nwrites(0, 1);
+    for _ in 0..2 {
+        s0.write_sctp(
+            &Bytes::from_static(&[0u8; 1000]),
+            PayloadProtocolIdentifier::Binary,
+        )
+        .await?;
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    flush_buffers(&br, &a0, &a1).await;
+    let state = a0.sack_state().await;
+    assert_eq!(state.cumulative_tsn_ack_point, tsn + 4);
+    assert_eq!(state.advanced_peer_tsn_ack_point, tsn + 4);
+    assert!(state.gap_ack_blocks.is_empty());
+    assert!(!state.forward_tsn_pending);
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_assoc_memory_budget() -> Result<()> {
+    const SI: u16 = 7;
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, mut a1) =
+        create_new_association_pair(&br, Arc::new(ca), Arc::new(cb), AckMode::NoDelay, 0).await?;
+
+    let budget0 = Arc::new(MemoryBudget::new(3000));
+    let budget1 = Arc::new(MemoryBudget::new(1500));
+    a0.association_internal.lock().await.memory_budget = Some(Arc::clone(&budget0));
+    a1.association_internal.lock().await.memory_budget = Some(Arc::clone(&budget1));
+
+    let (s0, s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
+    assert_eq!(budget0.used(), 0);
+    assert_eq!(budget1.used(), 0);
+
+    // The message written is accounted until the peer acknowledges it
+    let result = s0
+        .write_sctp(
+            &Bytes::from_static(&[0u8; 4000]),
+            PayloadProtocolIdentifier::Binary,
+        )
+        .await;
+    assert_eq!(result, Err(Error::ErrMemoryBudgetExceeded));
+    s0.write_sctp(
+        &Bytes::from_static(&[0u8; 1000]),
+        PayloadProtocolIdentifier::Binary,
+    )
+    .await?;
+    assert_eq!(budget0.used(), 1000);
+    flush_buffers(&br, &a0, &a1).await;
+    assert_eq!(budget0.used(), 0);
+
+    // The message received is accounted until it is read
+    assert_eq!(budget1.used(), 1000);
+    let mut buf = vec![0u8; 1500];
+    assert_eq!(s1.read_sctp(&mut buf).await?.0, 1000);
+    assert_eq!(budget1.used(), 0);
+
+    // Once the budget is exhausted the DATA chunks are dropped
+    for _ in 0..3 {
+        s0.write_sctp(
+            &Bytes::from_static(&[0u8; 1000]),
+            PayloadProtocolIdentifier::Binary,
+        )
+        .await?;
+        for _ in 0..3 {
+            br.tick().await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+    assert_eq!(budget1.used(), 2000);
+    assert_eq!(budget0.used(), 1000);
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_finish() -> Result<()> {
+    const SI: u16 = 8;
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, mut a1) =
+        create_new_association_pair(&br, Arc::new(ca), Arc::new(cb), AckMode::NoDelay, 0).await?;
+
+    let (s0, s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
+
+    for _ in 0..3 {
+        s0.write_sctp(
+            &Bytes::from_static(&[0u8; 1000]),
+            PayloadProtocolIdentifier::Binary,
+        )
+        .await?;
+    }
+    assert_eq!(s0.buffered_amount(), 3000);
+
+    // finish waits for the peer to acknowledge the data written
+    let finish = tokio::spawn({
+        let s0 = Arc::clone(&s0);
+        async move { s0.finish().await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    assert!(!finish.is_finished());
+    assert_eq!(
+        s0.write_sctp(
+            &Bytes::from_static(b"late"),
+            PayloadProtocolIdentifier::Binary
+        )
+        .await,
+        Err(Error::ErrStreamClosed)
+    );
+
+    flush_buffers(&br, &a0, &a1).await;
+    tokio::time::timeout(Duration::from_secs(1), finish)
+        .await
+        .expect("finish didn't return")
+        .unwrap()?;
+    assert_eq!(s0.buffered_amount(), 0);
+
+    let mut buf = vec![0u8; 1500];
+    for _ in 0..3 {
+        assert_eq!(s1.read_sctp(&mut buf).await?.0, 1000);
+    }
+
+    close_association_pair(&br, a0, a1).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_stream_finish_association_closed() -> Result<()> {
+    const SI: u16 = 9;
+
+    let (br, ca, cb) = Bridge::new(0, None, None);
+
+    let (a0, mut a1) =
+        create_new_association_pair(&br, Arc::new(ca), Arc::new(cb), AckMode::NoDelay, 0).await?;
+
+    let (s0, _s1) = establish_session_pair(&br, &a0, &mut a1, SI).await?;
+
+    s0.write_sctp(
+        &Bytes::from_static(&[0u8; 1000]),
+        PayloadProtocolIdentifier::Binary,
+    )
+    .await?;
+
+    // The data is never delivered before the association is closed
+    let finish = tokio::spawn({
+        let s0 = Arc::clone(&s0);
+        async move { s0.finish().await }
+    });
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    a0.close().await?;
+
+    let result = tokio::time::timeout(Duration::from_secs(1), finish)
+        .await
+        .expect("finish didn't return")
+        .unwrap();
+    assert_eq!(
+        result,
+        Err(Error::ErrStreamFinishFailed {
+            unacknowledged: 1000
+        })
+    );
+
+    a1.close().await?;
+
+    Ok(())
+}
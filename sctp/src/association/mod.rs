@@ -8,14 +8,16 @@ use std::collections::{HashMap, VecDeque};
 use std::fmt;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+use arc_swap::ArcSwapOption;
 use association_internal::*;
 use association_stats::*;
 use bytes::{Bytes, BytesMut};
 use portable_atomic::{AtomicBool, AtomicU32, AtomicU8, AtomicUsize};
 use rand::random;
 use tokio::sync::{broadcast, mpsc, Mutex};
+use util::sync::MemoryBudget;
 use util::Conn;
 
 use crate::chunk::chunk_abort::ChunkAbort;
@@ -59,6 +61,17 @@ pub(crate) const INITIAL_RECV_BUF_SIZE: u32 = 1024 * 1024;
 pub(crate) const COMMON_HEADER_SIZE: u32 = 12;
 pub(crate) const DATA_CHUNK_HEADER_SIZE: u32 = 16;
 pub(crate) const DEFAULT_MAX_MESSAGE_SIZE: u32 = 65536;
+/// default cap on the bytes held in reassembly, as a multiple of the receive buffer size
+pub(crate) const DEFAULT_MAX_REASSEMBLY_BYTES_FACTOR: u32 = 4;
+/// time after which an incomplete message is discarded from reassembly
+pub(crate) const REASSEMBLY_TIMEOUT: Duration = Duration::from_secs(60);
+/// default time to wait for the handshake to complete, the time it takes the
+/// T1-init timer to give up: RTO_INITIAL doubled up to RTO_MAX on each of the
+/// MAX_INIT_RETRANS retransmissions.
+pub(crate) const DEFAULT_HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(333);
+/// number of consecutive HEARTBEATs the peer may leave unacknowledged before it is
+/// considered unreachable, Path.Max.Retrans of RFC 4960 Section 15.
+pub(crate) const HEARTBEAT_MAX_RETRANS: u32 = 5;
 
 /// other constants
 pub(crate) const ACCEPT_CH_SIZE: usize = 16;
@@ -171,15 +184,93 @@ impl fmt::Display for AckState {
     }
 }
 
+/// CongestionState is a snapshot of the congestion control of an association.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct CongestionState {
+    /// congestion window, in bytes
+    pub cwnd: u32,
+    /// slow-start threshold, in bytes
+    pub ssthresh: u32,
+    /// bytes sent and not yet acknowledged
+    pub bytes_in_flight: usize,
+    /// current retransmission timeout
+    pub rto: Duration,
+}
+
+/// SackState is a snapshot of what the peer acknowledged of the DATA chunks sent on an
+/// association, to relate the chunks abandoned under partial reliability (RFC 3758) to
+/// the SACKs actually received.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SackState {
+    /// TSN up to which the peer acknowledged every DATA chunk
+    pub cumulative_tsn_ack_point: u32,
+    /// TSN ranges, inclusive, that the latest SACK of the peer acknowledged beyond
+    /// cumulative_tsn_ack_point
+    pub gap_ack_blocks: Vec<(u32, u32)>,
+    /// TSN up to which the chunks are either acknowledged or abandoned, the one a
+    /// FORWARD-TSN moves the cumulative TSN of the peer to
+    pub advanced_peer_tsn_ack_point: u32,
+    /// true while a FORWARD-TSN is waiting to be sent
+    pub forward_tsn_pending: bool,
+}
+
 /// Config collects the arguments to create_association construction into
-/// a single structure
+/// a single structure. It is created with Config::new, so that adding fields
+/// isn't a breaking change.
+#[non_exhaustive]
 pub struct Config {
     pub net_conn: Arc<dyn Conn + Send + Sync>,
     pub max_receive_buffer_size: u32,
     pub max_message_size: u32,
+    /// max_reassembly_bytes caps the bytes of incoming messages the association holds
+    /// while reassembling them. A peer going over it is aborted with an "Out Of Resource"
+    /// error cause. 0 defaults to 4 times max_receive_buffer_size, which a peer honoring
+    /// our receiver window never reaches.
+    pub max_reassembly_bytes: u32,
+    /// handshake_timeout bounds the time Association::client and Association::server
+    /// wait for the handshake to complete before failing with
+    /// Error::ErrAssociationHandshakeTimeout. None defaults to the time the INIT
+    /// retransmissions take to give up.
+    pub handshake_timeout: Option<Duration>,
+    /// no_delay sends every DATA chunk in a packet of its own. By default the DATA chunks
+    /// queued together are bundled into as few packets as the path MTU allows, which saves
+    /// the per-packet overhead for bulk transfers. Without bundling a stream of small
+    /// messages is carried one message per packet, so the receiver gets each of them as
+    /// soon as its packet arrives rather than with the packet completing a bundle.
+    pub no_delay: bool,
+    /// heartbeat_interval makes the association send a HEARTBEAT to the peer at this
+    /// interval once established. A peer leaving HEARTBEAT_MAX_RETRANS of them in a row
+    /// unacknowledged is considered unreachable: the association is closed and
+    /// Association::abort_error returns Error::ErrPeerUnreachable. None sends no
+    /// HEARTBEAT, the peer's HEARTBEATs are answered either way.
+    pub heartbeat_interval: Option<Duration>,
+    /// memory_budget is charged with the messages buffered by the streams, the ones written
+    /// and not yet acknowledged by the peer and the ones received and not yet read. Writing
+    /// a message that doesn't fit in it fails with Error::ErrMemoryBudgetExceeded. Once it
+    /// is exhausted, incoming DATA chunks are dropped, like when the receive buffer is full.
+    /// The budget can be shared with other buffers.
+    pub memory_budget: Option<Arc<MemoryBudget>>,
     pub name: String,
 }
 
+impl Config {
+    /// new creates a Config for an association over net_conn, with the defaults for
+    /// everything else, which can then be set on the returned Config.
+    pub fn new(net_conn: Arc<dyn Conn + Send + Sync>) -> Self {
+        Config {
+            net_conn,
+            max_receive_buffer_size: 0,
+            max_message_size: 0,
+            max_reassembly_bytes: 0,
+            handshake_timeout: None,
+            no_delay: false,
+            heartbeat_interval: None,
+            memory_budget: None,
+            name: String::new(),
+        }
+    }
+}
+
 ///Association represents an SCTP association
 ///13.2.  Parameters Necessary per Association (i.e., the TCB)
 ///Peer : Tag value to be sent in every packet and is received
@@ -203,6 +294,7 @@ pub struct Association {
     max_message_size: Arc<AtomicU32>,
     inflight_queue_length: Arc<AtomicUsize>,
     will_send_shutdown: Arc<AtomicBool>,
+    abort_error: Arc<ArcSwapOption<Error>>,
     awake_write_loop_ch: Arc<mpsc::Sender<()>>,
     close_loop_ch_rx: Mutex<broadcast::Receiver<()>>,
     accept_ch_rx: Mutex<mpsc::Receiver<Arc<Stream>>>,
@@ -216,31 +308,46 @@ pub struct Association {
 impl Association {
     /// server accepts a SCTP stream over a conn
     pub async fn server(config: Config) -> Result<Self> {
-        let (a, mut handshake_completed_ch_rx) = Association::new(config, false).await?;
+        let handshake_timeout = config
+            .handshake_timeout
+            .unwrap_or(DEFAULT_HANDSHAKE_TIMEOUT);
+        let (a, handshake_completed_ch_rx) = Association::new(config, false).await?;
 
-        if let Some(err_opt) = handshake_completed_ch_rx.recv().await {
-            if let Some(err) = err_opt {
-                Err(err)
-            } else {
-                Ok(a)
-            }
-        } else {
-            Err(Error::ErrAssociationHandshakeClosed)
-        }
+        a.wait_for_handshake(handshake_completed_ch_rx, handshake_timeout)
+            .await
     }
 
     /// Client opens a SCTP stream over a conn
     pub async fn client(config: Config) -> Result<Self> {
-        let (a, mut handshake_completed_ch_rx) = Association::new(config, true).await?;
+        let handshake_timeout = config
+            .handshake_timeout
+            .unwrap_or(DEFAULT_HANDSHAKE_TIMEOUT);
+        let (a, handshake_completed_ch_rx) = Association::new(config, true).await?;
+
+        a.wait_for_handshake(handshake_completed_ch_rx, handshake_timeout)
+            .await
+    }
 
-        if let Some(err_opt) = handshake_completed_ch_rx.recv().await {
-            if let Some(err) = err_opt {
-                Err(err)
-            } else {
-                Ok(a)
+    async fn wait_for_handshake(
+        self,
+        mut handshake_completed_ch_rx: mpsc::Receiver<Option<Error>>,
+        handshake_timeout: Duration,
+    ) -> Result<Self> {
+        match tokio::time::timeout(handshake_timeout, handshake_completed_ch_rx.recv()).await {
+            Ok(Some(None)) => Ok(self),
+            Ok(Some(Some(err))) => Err(err),
+            Ok(None) => Err(Error::ErrAssociationHandshakeClosed),
+            Err(_) => {
+                log::warn!(
+                    "[{}] handshake did not complete within {:?}",
+                    self.name,
+                    handshake_timeout
+                );
+                // Stop the loops and timers, the net_conn belongs to the caller
+                let mut ai = self.association_internal.lock().await;
+                let _ = ai.close().await;
+                Err(Error::ErrAssociationHandshakeTimeout)
             }
-        } else {
-            Err(Error::ErrAssociationHandshakeClosed)
         }
     }
 
@@ -285,13 +392,17 @@ impl Association {
 
     async fn new(config: Config, is_client: bool) -> Result<(Self, mpsc::Receiver<Option<Error>>)> {
         let net_conn = Arc::clone(&config.net_conn);
+        let heartbeat_interval = config.heartbeat_interval;
 
         let (awake_write_loop_ch_tx, awake_write_loop_ch_rx) = mpsc::channel(1);
         let (accept_ch_tx, accept_ch_rx) = mpsc::channel(ACCEPT_CH_SIZE);
         let (handshake_completed_ch_tx, handshake_completed_ch_rx) = mpsc::channel(1);
         let (close_loop_ch_tx, close_loop_ch_rx) = broadcast::channel(1);
-        let (close_loop_ch_rx1, close_loop_ch_rx2) =
-            (close_loop_ch_tx.subscribe(), close_loop_ch_tx.subscribe());
+        let (close_loop_ch_rx1, close_loop_ch_rx2, close_loop_ch_rx3) = (
+            close_loop_ch_tx.subscribe(),
+            close_loop_ch_tx.subscribe(),
+            close_loop_ch_tx.subscribe(),
+        );
         let awake_write_loop_ch = Arc::new(awake_write_loop_ch_tx);
 
         let ai = AssociationInternal::new(
@@ -309,6 +420,7 @@ impl Association {
         let max_message_size = Arc::clone(&ai.max_message_size);
         let inflight_queue_length = Arc::clone(&ai.inflight_queue_length);
         let will_send_shutdown = Arc::clone(&ai.will_send_shutdown);
+        let abort_error = Arc::clone(&ai.abort_error);
 
         let mut init = ChunkInit {
             initial_tsn: ai.my_next_tsn,
@@ -368,6 +480,15 @@ impl Association {
                 awake_write_loop_ch_rx,
             ));
 
+            if let Some(interval) = heartbeat_interval {
+                tokio::spawn(Association::heartbeat_loop(
+                    name.clone(),
+                    interval,
+                    close_loop_ch_rx3,
+                    Arc::clone(&association_internal),
+                ));
+            }
+
             if is_client {
                 ai.set_state(AssociationState::CookieWait);
                 ai.stored_init = Some(init);
@@ -386,6 +507,7 @@ impl Association {
                 max_message_size,
                 inflight_queue_length,
                 will_send_shutdown,
+                abort_error,
                 awake_write_loop_ch,
                 close_loop_ch_rx: Mutex::new(close_loop_ch_rx),
                 accept_ch_rx: Mutex::new(accept_ch_rx),
@@ -453,6 +575,34 @@ impl Association {
         log::debug!("[{}] read_loop exited", name);
     }
 
+    async fn heartbeat_loop(
+        name: String,
+        interval: Duration,
+        mut close_loop_ch: broadcast::Receiver<()>,
+        association_internal: Arc<Mutex<AssociationInternal>>,
+    ) {
+        log::debug!("[{}] heartbeat_loop entered", name);
+
+        let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+        loop {
+            tokio::select! {
+                _ = close_loop_ch.recv() => break,
+                _ = ticker.tick() => {}
+            };
+
+            let mut ai = association_internal.lock().await;
+            if let Err(err) = ai.send_heartbeat() {
+                log::warn!("[{}] {}, closing association", name, err);
+                if let Err(err) = ai.close().await {
+                    log::warn!("[{}] failed to close association: {:?}", name, err);
+                }
+                break;
+            }
+        }
+
+        log::debug!("[{}] heartbeat_loop exited", name);
+    }
+
     async fn write_loop(
         name: String,
         bytes_sent: Arc<AtomicUsize>,
@@ -572,6 +722,13 @@ impl Association {
         accept_ch_rx.recv().await
     }
 
+    /// abort_error returns [`Error::ErrAbortReceived`] with the causes the peer gave if it
+    /// aborted the association, or [`Error::ErrPeerUnreachable`] if it stopped answering
+    /// the HEARTBEATs.
+    pub fn abort_error(&self) -> Option<Error> {
+        self.abort_error.load().as_deref().cloned()
+    }
+
     /// max_message_size returns the maximum message size you can send.
     pub fn max_message_size(&self) -> u32 {
         self.max_message_size.load(Ordering::SeqCst)
@@ -583,6 +740,52 @@ impl Association {
             .store(max_message_size, Ordering::SeqCst);
     }
 
+    /// congestion_state returns a snapshot of the congestion control of the association.
+    pub async fn congestion_state(&self) -> CongestionState {
+        let ai = self.association_internal.lock().await;
+        CongestionState {
+            cwnd: ai.cwnd,
+            ssthresh: ai.ssthresh,
+            bytes_in_flight: ai.inflight_queue.get_num_bytes(),
+            rto: Duration::from_millis(ai.rto_mgr.get_rto()),
+        }
+    }
+
+    /// sack_state returns a snapshot of what the peer acknowledged of the DATA chunks sent,
+    /// for diagnostics.
+    pub async fn sack_state(&self) -> SackState {
+        let ai = self.association_internal.lock().await;
+        SackState {
+            cumulative_tsn_ack_point: ai.cumulative_tsn_ack_point,
+            gap_ack_blocks: ai.peer_gap_ack_blocks.clone(),
+            advanced_peer_tsn_ack_point: ai.advanced_peer_tsn_ack_point,
+            forward_tsn_pending: ai.will_send_forward_tsn,
+        }
+    }
+
+    /// set_congestion_state overrides the congestion window and the slow-start threshold once,
+    /// the congestion control then carries on from these values. A window pinned by
+    /// set_fixed_cwnd takes precedence. It is only available with the test-utils feature.
+    #[cfg(feature = "test-utils")]
+    pub async fn set_congestion_state(&self, cwnd: u32, ssthresh: u32) {
+        let mut ai = self.association_internal.lock().await;
+        ai.set_cwnd(cwnd);
+        ai.ssthresh = ssthresh;
+    }
+
+    /// set_fixed_cwnd pins the congestion window to cwnd bytes, so neither the slow-start
+    /// and congestion avoidance growth nor the reductions on loss change it. None hands the
+    /// window back to the congestion control. It exists to make retransmission and flow
+    /// control deterministic in tests and is only available with the test-utils feature.
+    #[cfg(feature = "test-utils")]
+    pub async fn set_fixed_cwnd(&self, cwnd: Option<u32>) {
+        let mut ai = self.association_internal.lock().await;
+        ai.fixed_cwnd = cwnd;
+        if let Some(cwnd) = cwnd {
+            ai.cwnd = cwnd;
+        }
+    }
+
     /// set_state atomically sets the state of the Association.
     fn set_state(&self, new_state: AssociationState) {
         let old_state = AssociationState::from(self.state.swap(new_state as u8, Ordering::SeqCst));
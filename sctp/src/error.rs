@@ -2,6 +2,8 @@ use std::io;
 
 use thiserror::Error;
 
+use crate::error_cause::ErrorCauseCode;
+
 pub type Result<T> = std::result::Result<T, Error>;
 
 #[derive(Debug, Error, PartialEq, Eq, Clone)]
@@ -159,6 +161,13 @@ pub enum Error {
 
     #[error("abort chunk, with following errors")]
     ErrChunk,
+    #[error(
+        "association aborted by the peer: {}",
+        .0.iter().map(|cause| cause.to_string()).collect::<Vec<_>>().join(", ")
+    )]
+    ErrAbortReceived(Vec<ErrorCauseCode>),
+    #[error("peer unreachable, it did not acknowledge the heartbeats")]
+    ErrPeerUnreachable,
     #[error("shutdown called in non-Established state")]
     ErrShutdownNonEstablished,
     #[error("association closed before connecting")]
@@ -167,6 +176,8 @@ pub enum Error {
     ErrAssociationInitFailed,
     #[error("association handshake closed")]
     ErrAssociationHandshakeClosed,
+    #[error("association handshake timed out")]
+    ErrAssociationHandshakeTimeout,
     #[error("silently discard")]
     ErrSilentlyDiscard,
     #[error("the init not stored to send")]
@@ -205,11 +216,19 @@ pub enum Error {
     ErrHandshakeInitAck,
     #[error("handshake failed (COOKIE ECHO)")]
     ErrHandshakeCookieEcho,
+    #[error("reassembly queues exceeded the maximum size")]
+    ErrReassemblyBytesExceeded,
 
     #[error("outbound packet larger than maximum message size")]
     ErrOutboundPacketTooLarge,
+    #[error("outbound packet exceeds the memory budget")]
+    ErrMemoryBudgetExceeded,
     #[error("Stream closed")]
     ErrStreamClosed,
+    #[error("stream closed with {unacknowledged} bytes unacknowledged")]
+    ErrStreamFinishFailed { unacknowledged: usize },
+    #[error("message being read partially was abandoned")]
+    ErrPartialMessageAbandoned,
     #[error("Short buffer (size: {size:?}) to be filled")]
     ErrShortBuffer { size: usize },
     #[error("Io EOF")]
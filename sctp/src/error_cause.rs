@@ -6,21 +6,21 @@ use crate::error::{Error, Result};
 
 /// errorCauseCode is a cause code that appears in either a ERROR or ABORT chunk
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
-pub(crate) struct ErrorCauseCode(pub(crate) u16);
-
-pub(crate) const INVALID_STREAM_IDENTIFIER: ErrorCauseCode = ErrorCauseCode(1);
-pub(crate) const MISSING_MANDATORY_PARAMETER: ErrorCauseCode = ErrorCauseCode(2);
-pub(crate) const STALE_COOKIE_ERROR: ErrorCauseCode = ErrorCauseCode(3);
-pub(crate) const OUT_OF_RESOURCE: ErrorCauseCode = ErrorCauseCode(4);
-pub(crate) const UNRESOLVABLE_ADDRESS: ErrorCauseCode = ErrorCauseCode(5);
-pub(crate) const UNRECOGNIZED_CHUNK_TYPE: ErrorCauseCode = ErrorCauseCode(6);
-pub(crate) const INVALID_MANDATORY_PARAMETER: ErrorCauseCode = ErrorCauseCode(7);
-pub(crate) const UNRECOGNIZED_PARAMETERS: ErrorCauseCode = ErrorCauseCode(8);
-pub(crate) const NO_USER_DATA: ErrorCauseCode = ErrorCauseCode(9);
-pub(crate) const COOKIE_RECEIVED_WHILE_SHUTTING_DOWN: ErrorCauseCode = ErrorCauseCode(10);
-pub(crate) const RESTART_OF_AN_ASSOCIATION_WITH_NEW_ADDRESSES: ErrorCauseCode = ErrorCauseCode(11);
-pub(crate) const USER_INITIATED_ABORT: ErrorCauseCode = ErrorCauseCode(12);
-pub(crate) const PROTOCOL_VIOLATION: ErrorCauseCode = ErrorCauseCode(13);
+pub struct ErrorCauseCode(pub u16);
+
+pub const INVALID_STREAM_IDENTIFIER: ErrorCauseCode = ErrorCauseCode(1);
+pub const MISSING_MANDATORY_PARAMETER: ErrorCauseCode = ErrorCauseCode(2);
+pub const STALE_COOKIE_ERROR: ErrorCauseCode = ErrorCauseCode(3);
+pub const OUT_OF_RESOURCE: ErrorCauseCode = ErrorCauseCode(4);
+pub const UNRESOLVABLE_ADDRESS: ErrorCauseCode = ErrorCauseCode(5);
+pub const UNRECOGNIZED_CHUNK_TYPE: ErrorCauseCode = ErrorCauseCode(6);
+pub const INVALID_MANDATORY_PARAMETER: ErrorCauseCode = ErrorCauseCode(7);
+pub const UNRECOGNIZED_PARAMETERS: ErrorCauseCode = ErrorCauseCode(8);
+pub const NO_USER_DATA: ErrorCauseCode = ErrorCauseCode(9);
+pub const COOKIE_RECEIVED_WHILE_SHUTTING_DOWN: ErrorCauseCode = ErrorCauseCode(10);
+pub const RESTART_OF_AN_ASSOCIATION_WITH_NEW_ADDRESSES: ErrorCauseCode = ErrorCauseCode(11);
+pub const USER_INITIATED_ABORT: ErrorCauseCode = ErrorCauseCode(12);
+pub const PROTOCOL_VIOLATION: ErrorCauseCode = ErrorCauseCode(13);
 
 impl fmt::Display for ErrorCauseCode {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -1,13 +1,13 @@
-use std::collections::VecDeque;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::atomic::Ordering;
 
-use portable_atomic::{AtomicBool, AtomicUsize};
+use portable_atomic::AtomicUsize;
 use tokio::sync::{Mutex, Semaphore};
-use util::sync::RwLock;
+use util::sync::{Mutex as SyncMutex, RwLock};
 
 use crate::chunk::chunk_payload_data::ChunkPayloadData;
-
-// TODO: benchmark performance between multiple Atomic+Mutex vs one Mutex<PendingQueueInternal>
+use crate::stream::DEFAULT_STREAM_PRIORITY;
 
 // Some tests push a lot of data before starting to process any data...
 #[cfg(test)]
@@ -22,6 +22,139 @@ const QUEUE_APPEND_LARGE: usize = (QUEUE_BYTES_LIMIT * 2) / 3;
 /// Basic queue for either ordered or unordered chunks.
 pub(crate) type PendingBaseQueue = VecDeque<ChunkPayloadData>;
 
+/// Scale of the virtual time, so that the bytes of a chunk divided by the weight of its
+/// class keep enough precision.
+const VIRTUAL_TIME_SCALE: u64 = 1 << 16;
+
+/// The chunks of the streams of the same priority.
+#[derive(Debug, Default)]
+struct PendingClass {
+    unordered_queue: PendingBaseQueue,
+    ordered_queue: PendingBaseQueue,
+    /// start tag of the next chunk of the class
+    virtual_time: u64,
+}
+
+impl PendingClass {
+    fn is_empty(&self) -> bool {
+        self.unordered_queue.is_empty() && self.ordered_queue.is_empty()
+    }
+}
+
+/// The chunks waiting to be sent, by priority. The classes share the congestion window in
+/// proportion to their priority with start-time fair queueing: the class with the lowest
+/// virtual time is served next, and it advances by the size of each chunk sent divided by
+/// the priority. Within a class, unordered chunks go first and the rest is sent in the order
+/// it was queued, so with a single priority the chunks are sent as they were queued.
+#[derive(Debug, Default)]
+struct PendingClasses {
+    classes: BTreeMap<u16, PendingClass>,
+    /// start tag of the last chunk sent
+    virtual_time: u64,
+    /// priority and queue of the message being sent, until its last fragment is popped
+    selected: Option<(u16, bool)>,
+    /// priority of the class of the last chunk peeked, which is the next one popped
+    peeked: Option<u16>,
+}
+
+impl PendingClasses {
+    fn push(&mut self, priority: u16, c: ChunkPayloadData) {
+        let virtual_time = self.virtual_time;
+        let class = self
+            .classes
+            .entry(priority)
+            .or_insert_with(|| PendingClass {
+                virtual_time,
+                ..Default::default()
+            });
+        // a class becoming active doesn't get the credit of the time it was idle
+        if class.is_empty() {
+            class.virtual_time = class.virtual_time.max(virtual_time);
+        }
+
+        if c.unordered {
+            class.unordered_queue.push_back(c);
+        } else {
+            class.ordered_queue.push_back(c);
+        }
+    }
+
+    /// next_class returns the priority of the class the next chunk is sent from.
+    fn next_class(&self) -> Option<u16> {
+        if let Some((priority, _)) = self.selected {
+            return Some(priority);
+        }
+
+        self.classes
+            .iter()
+            .filter(|(_, class)| !class.is_empty())
+            .min_by_key(|(priority, class)| (class.virtual_time, Reverse(**priority)))
+            .map(|(priority, _)| *priority)
+    }
+
+    fn peek(&mut self) -> Option<ChunkPayloadData> {
+        let priority = self.next_class()?;
+        self.peeked = Some(priority);
+        let class = self.classes.get(&priority)?;
+
+        if let Some((_, unordered)) = self.selected {
+            return if unordered {
+                class.unordered_queue.front().cloned()
+            } else {
+                class.ordered_queue.front().cloned()
+            };
+        }
+
+        class
+            .unordered_queue
+            .front()
+            .or_else(|| class.ordered_queue.front())
+            .cloned()
+    }
+
+    fn pop(&mut self, beginning_fragment: bool, unordered: bool) -> Option<ChunkPayloadData> {
+        let priority = self.peeked.take().or_else(|| self.next_class())?;
+        let class = self.classes.get_mut(&priority)?;
+
+        let popped = if let Some((_, selected_unordered)) = self.selected {
+            let popped = if selected_unordered {
+                class.unordered_queue.pop_front()
+            } else {
+                class.ordered_queue.pop_front()
+            };
+            if let Some(p) = &popped {
+                if p.ending_fragment {
+                    self.selected = None;
+                }
+            }
+            popped
+        } else {
+            if !beginning_fragment {
+                return None;
+            }
+            let popped = if unordered {
+                class.unordered_queue.pop_front()
+            } else {
+                class.ordered_queue.pop_front()
+            };
+            if let Some(p) = &popped {
+                if !p.ending_fragment {
+                    self.selected = Some((priority, unordered));
+                }
+            }
+            popped
+        };
+
+        if let Some(p) = &popped {
+            self.virtual_time = class.virtual_time;
+            class.virtual_time +=
+                p.user_data.len() as u64 * VIRTUAL_TIME_SCALE / priority.max(1) as u64;
+        }
+
+        popped
+    }
+}
+
 /// A queue for both ordered and unordered chunks.
 #[derive(Debug)]
 pub(crate) struct PendingQueue {
@@ -34,12 +167,11 @@ pub(crate) struct PendingQueue {
     semaphore_lock: Mutex<()>,
     semaphore: Semaphore,
 
-    unordered_queue: RwLock<PendingBaseQueue>,
-    ordered_queue: RwLock<PendingBaseQueue>,
+    classes: SyncMutex<PendingClasses>,
+    /// priority of the streams that were given one, by stream identifier
+    stream_priorities: RwLock<HashMap<u16, u16>>,
     queue_len: AtomicUsize,
     n_bytes: AtomicUsize,
-    selected: AtomicBool,
-    unordered_is_selected: AtomicBool,
 }
 
 impl Default for PendingQueue {
@@ -53,18 +185,36 @@ impl PendingQueue {
         Self {
             semaphore_lock: Mutex::default(),
             semaphore: Semaphore::new(QUEUE_BYTES_LIMIT),
-            unordered_queue: Default::default(),
-            ordered_queue: Default::default(),
+            classes: Default::default(),
+            stream_priorities: Default::default(),
             queue_len: Default::default(),
             n_bytes: Default::default(),
-            selected: Default::default(),
-            unordered_is_selected: Default::default(),
         }
     }
 
+    /// set_stream_priority sets the priority of the chunks of a stream queued from now on.
+    pub(crate) fn set_stream_priority(&self, stream_identifier: u16, priority: u16) {
+        let mut stream_priorities = self.stream_priorities.write();
+        if priority == DEFAULT_STREAM_PRIORITY {
+            stream_priorities.remove(&stream_identifier);
+        } else {
+            stream_priorities.insert(stream_identifier, priority);
+        }
+    }
+
+    /// stream_priority returns the priority of a stream.
+    pub(crate) fn stream_priority(&self, stream_identifier: u16) -> u16 {
+        let stream_priorities = self.stream_priorities.read();
+        stream_priorities
+            .get(&stream_identifier)
+            .copied()
+            .unwrap_or(DEFAULT_STREAM_PRIORITY)
+    }
+
     /// Appends a chunk to the back of the pending queue.
     pub(crate) async fn push(&self, c: ChunkPayloadData) {
         let user_data_len = c.user_data.len();
+        let priority = self.stream_priority(c.stream_identifier);
 
         {
             let _sem_lock = self.semaphore_lock.lock().await;
@@ -72,13 +222,7 @@ impl PendingQueue {
             // unwrap ok because we never close the semaphore unless we have dropped self
             permits.unwrap().forget();
 
-            if c.unordered {
-                let mut unordered_queue = self.unordered_queue.write();
-                unordered_queue.push_back(c);
-            } else {
-                let mut ordered_queue = self.ordered_queue.write();
-                ordered_queue.push_back(c);
-            }
+            self.classes.lock().push(priority, c);
         }
 
         self.n_bytes.fetch_add(user_data_len, Ordering::SeqCst);
@@ -116,19 +260,16 @@ impl PendingQueue {
         // lock this for the whole duration
         let _sem_lock = self.semaphore_lock.lock().await;
 
+        // all the chunks of the message are of the same stream, and are queued in the same class
+        // even if its priority changes meanwhile
+        let priority = self.stream_priority(chunks[0].stream_identifier);
         for chunk in chunks.into_iter() {
             let user_data_len = chunk.user_data.len();
             let permits = self.semaphore.acquire_many(user_data_len as u32).await;
             // unwrap ok because we never close the semaphore unless we have dropped self
             permits.unwrap().forget();
 
-            if chunk.unordered {
-                let mut unordered_queue = self.unordered_queue.write();
-                unordered_queue.push_back(chunk);
-            } else {
-                let mut ordered_queue = self.ordered_queue.write();
-                ordered_queue.push_back(chunk);
-            }
+            self.classes.lock().push(priority, chunk);
             self.n_bytes.fetch_add(user_data_len, Ordering::SeqCst);
             self.queue_len.fetch_add(1, Ordering::SeqCst);
         }
@@ -137,24 +278,30 @@ impl PendingQueue {
     /// Assumes that A) enough permits have been acquired and forget from the semaphore and that the semaphore_lock is held
     fn append_unlimited(&self, chunks: Vec<ChunkPayloadData>, total_user_data_len: usize) {
         let chunks_len = chunks.len();
-        let unordered = chunks
+        let first = chunks
             .first()
-            .expect("chunks to not be empty because of the above check")
-            .unordered;
+            .expect("chunks to not be empty because of the above check");
+        let (unordered, priority) = (
+            first.unordered,
+            self.stream_priority(first.stream_identifier),
+        );
         if unordered {
-            let mut unordered_queue = self.unordered_queue.write();
             assert!(
                 chunks.iter().all(|c| c.unordered),
                 "expected all chunks to be unordered"
             );
-            unordered_queue.extend(chunks);
         } else {
-            let mut ordered_queue = self.ordered_queue.write();
             assert!(
                 chunks.iter().all(|c| !c.unordered),
                 "expected all chunks to be ordered"
             );
-            ordered_queue.extend(chunks);
+        }
+
+        {
+            let mut classes = self.classes.lock();
+            for c in chunks {
+                classes.push(priority, c);
+            }
         }
 
         self.n_bytes
@@ -162,28 +309,10 @@ impl PendingQueue {
         self.queue_len.fetch_add(chunks_len, Ordering::SeqCst);
     }
 
+    /// Returns the chunk to send next, pop with its beginning_fragment and unordered flags
+    /// removes it from the queue.
     pub(crate) fn peek(&self) -> Option<ChunkPayloadData> {
-        if self.selected.load(Ordering::SeqCst) {
-            if self.unordered_is_selected.load(Ordering::SeqCst) {
-                let unordered_queue = self.unordered_queue.read();
-                return unordered_queue.front().cloned();
-            } else {
-                let ordered_queue = self.ordered_queue.read();
-                return ordered_queue.front().cloned();
-            }
-        }
-
-        let c = {
-            let unordered_queue = self.unordered_queue.read();
-            unordered_queue.front().cloned()
-        };
-
-        if c.is_some() {
-            return c;
-        }
-
-        let ordered_queue = self.ordered_queue.read();
-        ordered_queue.front().cloned()
+        self.classes.lock().peek()
     }
 
     pub(crate) fn pop(
@@ -191,50 +320,7 @@ impl PendingQueue {
         beginning_fragment: bool,
         unordered: bool,
     ) -> Option<ChunkPayloadData> {
-        let popped = if self.selected.load(Ordering::SeqCst) {
-            let popped = if self.unordered_is_selected.load(Ordering::SeqCst) {
-                let mut unordered_queue = self.unordered_queue.write();
-                unordered_queue.pop_front()
-            } else {
-                let mut ordered_queue = self.ordered_queue.write();
-                ordered_queue.pop_front()
-            };
-            if let Some(p) = &popped {
-                if p.ending_fragment {
-                    self.selected.store(false, Ordering::SeqCst);
-                }
-            }
-            popped
-        } else {
-            if !beginning_fragment {
-                return None;
-            }
-            if unordered {
-                let popped = {
-                    let mut unordered_queue = self.unordered_queue.write();
-                    unordered_queue.pop_front()
-                };
-                if let Some(p) = &popped {
-                    if !p.ending_fragment {
-                        self.selected.store(true, Ordering::SeqCst);
-                        self.unordered_is_selected.store(true, Ordering::SeqCst);
-                    }
-                }
-                popped
-            } else {
-                let popped = {
-                    let mut ordered_queue = self.ordered_queue.write();
-                    ordered_queue.pop_front()
-                };
-                if let Some(p) = &popped {
-                    if !p.ending_fragment {
-                        self.selected.store(true, Ordering::SeqCst);
-                        self.unordered_is_selected.store(false, Ordering::SeqCst);
-                    }
-                }
-                popped
-            }
-        };
+        let popped = self.classes.lock().pop(beginning_fragment, unordered);
 
         if let Some(p) = &popped {
             let user_data_len = p.user_data.len();
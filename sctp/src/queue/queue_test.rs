@@ -439,10 +439,74 @@ async fn test_pending_queue_append() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_pending_queue_priority() -> Result<()> {
+    const SI_LOW: u16 = 1;
+    const SI_HIGH: u16 = 2;
+
+    let make_stream_chunk = |tsn, stream_identifier| ChunkPayloadData {
+        stream_identifier,
+        ..make_data_chunk(tsn, false, NO_FRAGMENT)
+    };
+    let pop_stream = |pq: &PendingQueue| {
+        let c = pq.peek().expect("peek error");
+        let (beginning_fragment, unordered) = (c.beginning_fragment, c.unordered);
+        let popped = pq.pop(beginning_fragment, unordered).unwrap();
+        assert_eq!(popped.tsn, c.tsn, "popped chunk should be the peeked one");
+        popped.stream_identifier
+    };
+
+    let pq = PendingQueue::new();
+    pq.set_stream_priority(SI_LOW, 128);
+    pq.set_stream_priority(SI_HIGH, 1024);
+    assert_eq!(pq.stream_priority(SI_LOW), 128);
+    assert_eq!(pq.stream_priority(3), 256);
+
+    for tsn in 0..20 {
+        pq.push(make_stream_chunk(tsn, SI_LOW)).await;
+    }
+    for _ in 0..10 {
+        assert_eq!(pop_stream(&pq), SI_LOW);
+    }
+
+    // the high priority stream doesn't wait for the low priority one to drain, and gets
+    // 8 times the bandwidth, without the credit of the time it was idle
+    for tsn in 20..44 {
+        pq.push(make_stream_chunk(tsn, SI_HIGH)).await;
+    }
+    let popped: Vec<u16> = (0..19).map(|_| pop_stream(&pq)).collect();
+    assert_eq!(popped[..9], [SI_HIGH; 9]);
+    assert_eq!(popped[9], SI_LOW);
+    assert_eq!(popped[10..18], [SI_HIGH; 8]);
+    assert_eq!(popped[18], SI_LOW);
+    assert_eq!(pq.len(), 15);
+
+    // a fragmented message isn't interleaved with the chunks of another stream
+    let pq = PendingQueue::new();
+    pq.set_stream_priority(SI_HIGH, 1024);
+    pq.push(ChunkPayloadData {
+        stream_identifier: SI_LOW,
+        ..make_data_chunk(0, false, FRAG_BEGIN)
+    })
+    .await;
+    assert_eq!(pop_stream(&pq), SI_LOW);
+    pq.push(make_stream_chunk(1, SI_HIGH)).await;
+    pq.push(ChunkPayloadData {
+        stream_identifier: SI_LOW,
+        ..make_data_chunk(2, false, FRAG_END)
+    })
+    .await;
+    assert_eq!(pop_stream(&pq), SI_LOW);
+    assert_eq!(pop_stream(&pq), SI_HIGH);
+
+    Ok(())
+}
+
 ///////////////////////////////////////////////////////////////////
 //reassembly_queue_test
 ///////////////////////////////////////////////////////////////////
 use std::sync::Arc;
+use std::time::{Duration, SystemTime};
 
 use portable_atomic::AtomicUsize;
 
@@ -928,6 +992,171 @@ fn test_reassembly_queue_forward_tsn_for_unordered_fragments() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_reassembly_queue_discard_expired_fragments() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    let org_ppi = PayloadProtocolIdentifier::Binary;
+    let now = SystemTime::now();
+    let old = now - Duration::from_secs(120);
+
+    let chunk = ChunkPayloadData {
+        payload_type: org_ppi,
+        beginning_fragment: true,
+        tsn: 10,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"ABC"),
+        since: old,
+        ..Default::default()
+    };
+    assert!(!rq.push(chunk), "chunk set should not be complete yet");
+
+    let chunk = ChunkPayloadData {
+        payload_type: org_ppi,
+        beginning_fragment: true,
+        ending_fragment: true,
+        tsn: 12,
+        stream_sequence_number: 1,
+        user_data: Bytes::from_static(b"123"),
+        ..Default::default()
+    };
+    assert!(rq.push(chunk), "chunk set should be complete");
+
+    let chunk = ChunkPayloadData {
+        payload_type: org_ppi,
+        unordered: true,
+        beginning_fragment: true,
+        tsn: 13,
+        user_data: Bytes::from_static(b"DEF"),
+        since: old,
+        ..Default::default()
+    };
+    assert!(!rq.push(chunk), "chunk set should not be complete yet");
+
+    let chunk = ChunkPayloadData {
+        payload_type: org_ppi,
+        unordered: true,
+        beginning_fragment: true,
+        tsn: 15,
+        user_data: Bytes::from_static(b"SOS"),
+        ..Default::default()
+    };
+    assert!(!rq.push(chunk), "chunk set should not be complete yet");
+    assert_eq!(rq.get_num_bytes(), 12, "num bytes mismatch");
+    assert!(!rq.is_readable(), "should not be readable");
+
+    let n = rq.discard_expired(now - Duration::from_secs(60));
+    assert_eq!(n, 6, "num bytes discarded mismatch");
+    assert_eq!(rq.get_num_bytes(), 6, "num bytes mismatch");
+    assert_eq!(rq.ordered.len(), 1, "there should be one chunk set left");
+    assert_eq!(rq.next_ssn, 1, "next_ssn should skip the discarded message");
+    assert_eq!(
+        rq.unordered_chunks.len(),
+        1,
+        "there should be one chunk kept"
+    );
+    assert!(rq.is_readable(), "should be readable");
+
+    let mut buf = vec![0u8; 16];
+    let (n, ppi) = rq.read(&mut buf)?;
+    assert_eq!(&buf[..n], b"123", "data mismatch");
+    assert_eq!(ppi, org_ppi, "should have valid ppi");
+
+    Ok(())
+}
+
+#[test]
+fn test_reassembly_queue_read_partial() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    let org_ppi = PayloadProtocolIdentifier::Binary;
+    let mut buf = vec![0u8; 4];
+
+    // Nothing to read before the first fragment
+    assert_eq!(Err(Error::ErrTryAgain), rq.read_partial(&mut buf));
+
+    rq.push(ChunkPayloadData {
+        payload_type: org_ppi,
+        beginning_fragment: true,
+        tsn: 1,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"ABCDEF"),
+        ..Default::default()
+    });
+    assert!(
+        rq.is_partially_readable(),
+        "first fragment should be readable"
+    );
+    assert!(!rq.is_readable(), "message should not be complete");
+
+    let (n, ppi, end_of_message) = rq.read_partial(&mut buf)?;
+    assert_eq!(&buf[..n], b"ABCD", "data should match");
+    assert_eq!(ppi, org_ppi, "should have valid ppi");
+    assert!(!end_of_message, "message should not be over");
+    let (n, _, end_of_message) = rq.read_partial(&mut buf)?;
+    assert_eq!(&buf[..n], b"EF", "data should match");
+    assert!(!end_of_message, "message should not be over");
+    assert_eq!(rq.get_num_bytes(), 0, "num bytes mismatch");
+
+    // The third fragment can't be read before the second one
+    rq.push(ChunkPayloadData {
+        payload_type: org_ppi,
+        ending_fragment: true,
+        tsn: 3,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"IJ"),
+        ..Default::default()
+    });
+    assert!(!rq.is_partially_readable(), "second fragment is missing");
+    assert_eq!(Err(Error::ErrTryAgain), rq.read_partial(&mut buf));
+
+    rq.push(ChunkPayloadData {
+        payload_type: org_ppi,
+        tsn: 2,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"GH"),
+        ..Default::default()
+    });
+    let (n, _, end_of_message) = rq.read_partial(&mut buf)?;
+    assert_eq!(&buf[..n], b"GHIJ", "data should match");
+    assert!(end_of_message, "message should be over");
+    assert_eq!(rq.get_num_bytes(), 0, "num bytes mismatch");
+    assert_eq!(rq.next_ssn, 1, "next_ssn should be incremented");
+    assert!(rq.ordered.is_empty(), "message should be removed");
+
+    Ok(())
+}
+
+#[test]
+fn test_reassembly_queue_read_partial_abandoned() -> Result<()> {
+    let mut rq = ReassemblyQueue::new(0);
+
+    rq.push(ChunkPayloadData {
+        payload_type: PayloadProtocolIdentifier::Binary,
+        beginning_fragment: true,
+        tsn: 1,
+        stream_sequence_number: 0,
+        user_data: Bytes::from_static(b"ABC"),
+        ..Default::default()
+    });
+
+    let mut buf = vec![0u8; 16];
+    let (n, _, end_of_message) = rq.read_partial(&mut buf)?;
+    assert_eq!(&buf[..n], b"ABC", "data should match");
+    assert!(!end_of_message, "message should not be over");
+
+    // The sender gives up on the rest of the message
+    rq.forward_tsn_for_ordered(0);
+    assert!(rq.is_partially_readable(), "reader should be notified");
+    assert_eq!(
+        Err(Error::ErrPartialMessageAbandoned),
+        rq.read_partial(&mut buf)
+    );
+    assert_eq!(Err(Error::ErrTryAgain), rq.read_partial(&mut buf));
+
+    Ok(())
+}
+
 #[test]
 fn test_chunk_set_empty_chunk_set() -> Result<()> {
     let cset = ChunkSet::new(0, PayloadProtocolIdentifier::default());
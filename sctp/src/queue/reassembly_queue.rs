@@ -1,4 +1,5 @@
 use std::cmp::Ordering;
+use std::time::SystemTime;
 
 use crate::chunk::chunk_payload_data::{ChunkPayloadData, PayloadProtocolIdentifier};
 use crate::error::{Error, Result};
@@ -113,6 +114,10 @@ pub(crate) struct ReassemblyQueue {
     pub(crate) unordered: Vec<ChunkSet>,
     pub(crate) unordered_chunks: Vec<ChunkPayloadData>,
     pub(crate) n_bytes: usize,
+    /// SSN of the ordered message being read partially, see `read_partial`
+    pub(crate) partial_ssn: Option<u16>,
+    /// TSN of the next fragment of the message being read partially
+    pub(crate) partial_next_tsn: u32,
 }
 
 impl ReassemblyQueue {
@@ -129,6 +134,8 @@ impl ReassemblyQueue {
             unordered: vec![],
             unordered_chunks: vec![],
             n_bytes: 0,
+            partial_ssn: None,
+            partial_next_tsn: 0,
         }
     }
 
@@ -245,7 +252,10 @@ impl ReassemblyQueue {
         // Check ordered sets
         if !self.ordered.is_empty() {
             let cset = &self.ordered[0];
-            if cset.is_complete() && sna16lte(cset.ssn, self.next_ssn) {
+            if cset.is_complete()
+                && sna16lte(cset.ssn, self.next_ssn)
+                && self.partial_ssn != Some(cset.ssn)
+            {
                 return true;
             }
         }
@@ -259,7 +269,7 @@ impl ReassemblyQueue {
         } else if !self.ordered.is_empty() {
             // Now, check ordered
             let cset = &self.ordered[0];
-            if !cset.is_complete() {
+            if !cset.is_complete() || self.partial_ssn == Some(cset.ssn) {
                 return Err(Error::ErrTryAgain);
             }
             if sna16gt(cset.ssn, self.next_ssn) {
@@ -297,6 +307,95 @@ impl ReassemblyQueue {
         }
     }
 
+    /// is_partially_readable tells whether `read_partial` has data to return, or an
+    /// error to report.
+    pub(crate) fn is_partially_readable(&self) -> bool {
+        let cset = match self.ordered.first() {
+            Some(cset) => cset,
+            None => return self.partial_ssn.is_some(),
+        };
+
+        match self.partial_ssn {
+            Some(ssn) if ssn == cset.ssn => cset
+                .chunks
+                .first()
+                .is_some_and(|c| c.tsn == self.partial_next_tsn),
+            Some(_) => true,
+            None => {
+                sna16lte(cset.ssn, self.next_ssn)
+                    && cset.chunks.first().is_some_and(|c| c.beginning_fragment)
+            }
+        }
+    }
+
+    /// read_partial reads the next ordered message as its fragments arrive, without waiting
+    /// for it to be complete. The bytes are returned in order, along with the PPI of the
+    /// message and whether its last byte was read. Unordered messages are left to `read`.
+    ///
+    /// Returns `Error::ErrPartialMessageAbandoned` if the rest of the message being read was
+    /// dropped, e.g. skipped by a FORWARD TSN.
+    pub(crate) fn read_partial(
+        &mut self,
+        buf: &mut [u8],
+    ) -> Result<(usize, PayloadProtocolIdentifier, bool)> {
+        let cset = match self.ordered.first_mut() {
+            Some(cset) if self.partial_ssn.is_none() || self.partial_ssn == Some(cset.ssn) => cset,
+            _ if self.partial_ssn.take().is_some() => {
+                return Err(Error::ErrPartialMessageAbandoned);
+            }
+            _ => return Err(Error::ErrTryAgain),
+        };
+
+        if self.partial_ssn.is_none() {
+            // Start reading the message once its first fragment is in
+            let first = match cset.chunks.first() {
+                Some(c) if c.beginning_fragment && sna16lte(cset.ssn, self.next_ssn) => c,
+                _ => return Err(Error::ErrTryAgain),
+            };
+            self.partial_ssn = Some(cset.ssn);
+            self.partial_next_tsn = first.tsn;
+        }
+
+        let ppi = cset.ppi;
+        let mut n_written = 0;
+        let mut end_of_message = false;
+        while n_written < buf.len() {
+            let c = match cset.chunks.first_mut() {
+                Some(c) if c.tsn == self.partial_next_tsn => c,
+                _ => break,
+            };
+
+            let n = std::cmp::min(c.user_data.len(), buf.len() - n_written);
+            buf[n_written..n_written + n].copy_from_slice(&c.user_data[..n]);
+            n_written += n;
+            if n < c.user_data.len() {
+                c.user_data = c.user_data.slice(n..);
+                break;
+            }
+
+            let c = cset.chunks.remove(0);
+            self.partial_next_tsn = c.tsn.wrapping_add(1);
+            if c.ending_fragment {
+                end_of_message = true;
+                break;
+            }
+        }
+        self.subtract_num_bytes(n_written);
+
+        if end_of_message {
+            let ssn = self.ordered.remove(0).ssn;
+            if ssn == self.next_ssn {
+                // From RFC 4960 Sec 6.5:
+                self.next_ssn = self.next_ssn.wrapping_add(1);
+            }
+            self.partial_ssn = None;
+        } else if n_written == 0 && !buf.is_empty() {
+            return Err(Error::ErrTryAgain);
+        }
+
+        Ok((n_written, ppi, end_of_message))
+    }
+
     /// Use last_ssn to locate a chunkSet then remove it if the set has
     /// not been complete
     pub(crate) fn forward_tsn_for_ordered(&mut self, last_ssn: u16) {
@@ -339,6 +438,38 @@ impl ReassemblyQueue {
         }
     }
 
+    /// Remove the incomplete messages that have a fragment received before
+    /// `deadline`, and return the number of bytes freed. The ordered messages
+    /// removed are skipped as a FORWARD TSN would, so that the messages queued
+    /// after them can be read.
+    pub(crate) fn discard_expired(&mut self, deadline: SystemTime) -> usize {
+        let n_bytes = self.n_bytes;
+        let expired = |c: &ChunkPayloadData| c.since < deadline;
+
+        let mut last_ssn = None;
+        for s in &self.ordered {
+            if !s.is_complete() && s.chunks.iter().any(expired) {
+                last_ssn = match last_ssn {
+                    Some(ssn) if sna16gt(ssn, s.ssn) => Some(ssn),
+                    _ => Some(s.ssn),
+                };
+            }
+        }
+        if let Some(last_ssn) = last_ssn {
+            self.forward_tsn_for_ordered(last_ssn);
+        }
+
+        let num_bytes = self
+            .unordered_chunks
+            .iter()
+            .filter(|c| expired(c))
+            .fold(0, |n, c| n + c.user_data.len());
+        self.subtract_num_bytes(num_bytes);
+        self.unordered_chunks.retain(|c| !expired(c));
+
+        n_bytes - self.n_bytes
+    }
+
     pub(crate) fn subtract_num_bytes(&mut self, n_bytes: usize) {
         if self.n_bytes >= n_bytes {
             self.n_bytes -= n_bytes;
@@ -7,6 +7,7 @@ use std::pin::Pin;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::task::{Context, Poll};
+use std::time::SystemTime;
 use std::{fmt, io};
 
 use arc_swap::ArcSwapOption;
@@ -14,6 +15,7 @@ use bytes::Bytes;
 use portable_atomic::{AtomicBool, AtomicU16, AtomicU32, AtomicU8, AtomicUsize};
 use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
 use tokio::sync::{mpsc, Mutex, Notify};
+use util::sync::MemoryBudget;
 
 use crate::association::AssociationState;
 use crate::chunk::chunk_payload_data::{ChunkPayloadData, PayloadProtocolIdentifier};
@@ -54,6 +56,10 @@ impl From<u8> for ReliabilityType {
     }
 }
 
+/// The priority of a stream that wasn't given one, the normal priority of the data channels,
+/// RFC 8831 section 6.4.
+pub const DEFAULT_STREAM_PRIORITY: u16 = 256;
+
 pub type OnBufferedAmountLowFn =
     Box<dyn (FnMut() -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>) + Send + Sync>;
 
@@ -72,6 +78,7 @@ pub struct Stream {
     pub(crate) reassembly_queue: Mutex<ReassemblyQueue>,
     pub(crate) sequence_number: AtomicU16,
     pub(crate) read_notifier: Notify,
+    pub(crate) partial_reads: AtomicBool,
     pub(crate) read_shutdown: AtomicBool,
     pub(crate) write_shutdown: AtomicBool,
     pub(crate) unordered: AtomicBool,
@@ -79,7 +86,13 @@ pub struct Stream {
     pub(crate) reliability_value: AtomicU32,
     pub(crate) buffered_amount: AtomicUsize,
     pub(crate) buffered_amount_low: AtomicUsize,
+    /// notified when buffered_amount decreases or the association lets go of the stream
+    pub(crate) buffer_released_notifier: Notify,
     pub(crate) on_buffered_amount_low: ArcSwapOption<Mutex<OnBufferedAmountLowFn>>,
+    /// set when the peer aborts the association, the reads fail with it
+    pub(crate) abort_error: ArcSwapOption<Error>,
+    /// charged with buffered_amount and the bytes of the reassembly_queue
+    pub(crate) memory_budget: Option<Arc<MemoryBudget>>,
     pub(crate) name: String,
 }
 
@@ -128,6 +141,7 @@ impl Stream {
             reassembly_queue: Mutex::new(ReassemblyQueue::new(stream_identifier)),
             sequence_number: AtomicU16::new(0),
             read_notifier: Notify::new(),
+            partial_reads: AtomicBool::new(false),
             read_shutdown: AtomicBool::new(false),
             write_shutdown: AtomicBool::new(false),
             unordered: AtomicBool::new(false),
@@ -135,7 +149,10 @@ impl Stream {
             reliability_value: AtomicU32::new(0),
             buffered_amount: AtomicUsize::new(0),
             buffered_amount_low: AtomicUsize::new(0),
+            buffer_released_notifier: Notify::new(),
             on_buffered_amount_low: ArcSwapOption::empty(),
+            abort_error: ArcSwapOption::empty(),
+            memory_budget: None,
             name,
         }
     }
@@ -145,6 +162,13 @@ impl Stream {
         self.stream_identifier
     }
 
+    fn check_aborted(&self) -> Result<()> {
+        match self.abort_error.load().as_deref() {
+            Some(err) => Err(err.clone()),
+            None => Ok(()),
+        }
+    }
+
     /// set_default_payload_type sets the default payload type used by write.
     pub fn set_default_payload_type(&self, default_payload_type: PayloadProtocolIdentifier) {
         self.default_payload_type
@@ -166,6 +190,22 @@ impl Stream {
         self.reliability_value.store(rel_val, Ordering::SeqCst);
     }
 
+    /// set_priority sets the priority of the messages written from now on. Under congestion,
+    /// the streams with messages to send share the congestion window in proportion to their
+    /// priority, e.g. a stream with twice the priority of another sends twice as many bytes.
+    /// Streams of the same priority send their messages in the order they were written.
+    /// The default is [`DEFAULT_STREAM_PRIORITY`].
+    pub fn set_priority(&self, priority: u16) {
+        log::debug!("[{}] priority: {}", self.name, priority);
+        self.pending_queue
+            .set_stream_priority(self.stream_identifier, priority);
+    }
+
+    /// priority returns the priority of the stream.
+    pub fn priority(&self) -> u16 {
+        self.pending_queue.stream_priority(self.stream_identifier)
+    }
+
     /// Reads a packet of len(p) bytes, dropping the Payload Protocol Identifier.
     ///
     /// Returns `Error::ErrShortBuffer` if `p` is too short.
@@ -179,15 +219,20 @@ impl Stream {
     ///
     /// Returns `Error::ErrShortBuffer` if `p` is too short.
     /// Returns `(0, PayloadProtocolIdentifier::Unknown)` if the reading half of this stream is shutdown or it (the stream) was reset.
+    /// Returns `Error::ErrAbortReceived` if the peer aborted the association.
     pub async fn read_sctp(&self, p: &mut [u8]) -> Result<(usize, PayloadProtocolIdentifier)> {
         loop {
             if self.read_shutdown.load(Ordering::SeqCst) {
+                self.check_aborted()?;
                 return Ok((0, PayloadProtocolIdentifier::Unknown));
             }
 
             let result = {
                 let mut reassembly_queue = self.reassembly_queue.lock().await;
-                reassembly_queue.read(p)
+                let n_bytes = reassembly_queue.get_num_bytes();
+                let result = reassembly_queue.read(p);
+                self.charge_reassembly(n_bytes, &reassembly_queue);
+                result
             };
 
             match result {
@@ -200,16 +245,74 @@ impl Stream {
         }
     }
 
+    /// Reads the next ordered message as its fragments arrive, instead of once it is complete,
+    /// so that large messages don't need to be buffered whole. Returns the number of bytes
+    /// read, the Payload Protocol Identifier and whether the end of the message was reached.
+    /// Unordered messages are only returned by `read_sctp`.
+    ///
+    /// Returns `Error::ErrPartialMessageAbandoned` if the rest of the message being read was
+    /// abandoned by the sender.
+    /// Returns `(0, PayloadProtocolIdentifier::Unknown, false)` if the reading half of this stream is shutdown or it (the stream) was reset.
+    /// Returns `Error::ErrAbortReceived` if the peer aborted the association.
+    pub async fn read_partial(
+        &self,
+        p: &mut [u8],
+    ) -> Result<(usize, PayloadProtocolIdentifier, bool)> {
+        self.partial_reads.store(true, Ordering::SeqCst);
+        loop {
+            if self.read_shutdown.load(Ordering::SeqCst) {
+                self.check_aborted()?;
+                return Ok((0, PayloadProtocolIdentifier::Unknown, false));
+            }
+
+            let result = {
+                let mut reassembly_queue = self.reassembly_queue.lock().await;
+                let n_bytes = reassembly_queue.get_num_bytes();
+                let result = reassembly_queue.read_partial(p);
+                self.charge_reassembly(n_bytes, &reassembly_queue);
+                result
+            };
+
+            match result {
+                Err(Error::ErrTryAgain) => {
+                    // wait for the next chunk to become available
+                    self.read_notifier.notified().await;
+                }
+                _ => return result,
+            }
+        }
+    }
+
+    /// charge_reassembly charges the memory budget with the change of the bytes held by the
+    /// reassembly queue since it held n_bytes. Received data is accounted even beyond the
+    /// budget, the association stops accepting DATA chunks once it is exhausted.
+    fn charge_reassembly(&self, n_bytes: usize, reassembly_queue: &ReassemblyQueue) {
+        if let Some(budget) = &self.memory_budget {
+            let new_n_bytes = reassembly_queue.get_num_bytes();
+            if new_n_bytes > n_bytes {
+                budget.reserve(new_n_bytes - n_bytes);
+            } else {
+                budget.release(n_bytes - new_n_bytes);
+            }
+        }
+    }
+
+    /// is_readable tells whether a reader waiting on read_notifier can make progress.
+    fn is_readable(&self, reassembly_queue: &ReassemblyQueue) -> bool {
+        reassembly_queue.is_readable()
+            || (self.partial_reads.load(Ordering::SeqCst)
+                && reassembly_queue.is_partially_readable())
+    }
+
     pub(crate) async fn handle_data(&self, pd: ChunkPayloadData) {
         let readable = {
             let mut reassembly_queue = self.reassembly_queue.lock().await;
-            if reassembly_queue.push(pd) {
-                let readable = reassembly_queue.is_readable();
-                log::debug!("[{}] reassemblyQueue readable={}", self.name, readable);
-                readable
-            } else {
-                false
-            }
+            let n_bytes = reassembly_queue.get_num_bytes();
+            reassembly_queue.push(pd);
+            self.charge_reassembly(n_bytes, &reassembly_queue);
+            let readable = self.is_readable(&reassembly_queue);
+            log::debug!("[{}] reassemblyQueue readable={}", self.name, readable);
+            readable
         };
 
         if readable {
@@ -228,8 +331,10 @@ impl Stream {
         // the reassembly_queue.
         let readable = {
             let mut reassembly_queue = self.reassembly_queue.lock().await;
+            let n_bytes = reassembly_queue.get_num_bytes();
             reassembly_queue.forward_tsn_for_ordered(ssn);
-            reassembly_queue.is_readable()
+            self.charge_reassembly(n_bytes, &reassembly_queue);
+            self.is_readable(&reassembly_queue)
         };
 
         // Notify the reader asynchronously if there's a data chunk to read.
@@ -247,8 +352,42 @@ impl Stream {
         // the reassembly_queue.
         let readable = {
             let mut reassembly_queue = self.reassembly_queue.lock().await;
+            let n_bytes = reassembly_queue.get_num_bytes();
             reassembly_queue.forward_tsn_for_unordered(new_cumulative_tsn);
-            reassembly_queue.is_readable()
+            self.charge_reassembly(n_bytes, &reassembly_queue);
+            self.is_readable(&reassembly_queue)
+        };
+
+        // Notify the reader asynchronously if there's a data chunk to read.
+        if readable {
+            self.read_notifier.notify_one();
+        }
+    }
+
+    /// Removes the incomplete messages that are still waiting for fragments received
+    /// before the deadline. Only the messages of a partially reliable stream can be
+    /// abandoned, and only if the association supports FORWARD TSN.
+    pub(crate) async fn discard_expired(&self, deadline: SystemTime, use_forward_tsn: bool) {
+        let reliability_type: ReliabilityType = self.reliability_type.load(Ordering::SeqCst).into();
+        if reliability_type == ReliabilityType::Reliable || !use_forward_tsn {
+            return;
+        }
+
+        let readable = {
+            let mut reassembly_queue = self.reassembly_queue.lock().await;
+
+            let n = reassembly_queue.discard_expired(deadline);
+            if let Some(budget) = &self.memory_budget {
+                budget.release(n);
+            }
+            if n > 0 {
+                log::debug!(
+                    "[{}] discarded {} bytes of expired messages from reassemblyQueue",
+                    self.name,
+                    n
+                );
+            }
+            self.is_readable(&reassembly_queue)
         };
 
         // Notify the reader asynchronously if there's a data chunk to read.
@@ -298,6 +437,12 @@ impl Stream {
             _ => {}
         };
 
+        if let Some(budget) = &self.memory_budget {
+            if !budget.try_reserve(p.len()) {
+                return Err(Error::ErrMemoryBudgetExceeded);
+            }
+        }
+
         Ok(self.packetize(p, ppi))
     }
 
@@ -397,6 +542,43 @@ impl Stream {
         Ok(())
     }
 
+    /// Finishes this stream gracefully: stops writing, waits until the peer acknowledged all
+    /// the data written, then shuts down both halves like [`Stream::shutdown`], which resets
+    /// the stream. Unlike a plain shutdown, the tail of the data can't be lost to the reset.
+    ///
+    /// Fails with [`Error::ErrStreamFinishFailed`], giving the bytes still unacknowledged, if
+    /// the association closes before the data is acknowledged.
+    pub async fn finish(&self) -> Result<()> {
+        if self.read_shutdown.load(Ordering::SeqCst) && self.write_shutdown.load(Ordering::SeqCst) {
+            return Ok(());
+        }
+        self.write_shutdown.store(true, Ordering::SeqCst);
+
+        loop {
+            let released = self.buffer_released_notifier.notified();
+            tokio::pin!(released);
+            // Register before checking, not to miss a release in between
+            released.as_mut().enable();
+
+            let unacknowledged = self.buffered_amount();
+            if unacknowledged == 0 {
+                break;
+            }
+            if self.get_state() != AssociationState::Established
+                || self.abort_error.load().is_some()
+            {
+                return Err(Error::ErrStreamFinishFailed { unacknowledged });
+            }
+
+            released.await;
+        }
+
+        if !self.read_shutdown.swap(true, Ordering::SeqCst) {
+            self.read_notifier.notify_waiters();
+        }
+        self.send_reset_request(self.stream_identifier).await
+    }
+
     /// buffered_amount returns the number of bytes of data currently queued to be sent over this stream.
     pub fn buffered_amount(&self) -> usize {
         self.buffered_amount.load(Ordering::SeqCst)
@@ -429,6 +611,9 @@ impl Stream {
         }
 
         let from_amount = self.buffered_amount.load(Ordering::SeqCst);
+        if let Some(budget) = &self.memory_budget {
+            budget.release(from_amount.min(n_bytes_released as usize));
+        }
         let new_amount = if from_amount < n_bytes_released as usize {
             self.buffered_amount.store(0, Ordering::SeqCst);
             log::error!(
@@ -444,6 +629,7 @@ impl Stream {
 
             from_amount - n_bytes_released as usize
         };
+        self.buffer_released_notifier.notify_waiters();
 
         let buffered_amount_low = self.buffered_amount_low.load(Ordering::SeqCst);
 
@@ -517,6 +703,18 @@ impl Stream {
     }
 }
 
+impl Drop for Stream {
+    fn drop(&mut self) {
+        // gives back what is still buffered once the association let go of the stream
+        if let Some(budget) = &self.memory_budget {
+            budget.release(
+                self.buffered_amount.load(Ordering::SeqCst)
+                    + self.reassembly_queue.get_mut().get_num_bytes(),
+            );
+        }
+    }
+}
+
 /// Default capacity of the temporary read buffer used by [`PollStream`].
 const DEFAULT_READ_BUF_SIZE: usize = 8192;
 
@@ -583,6 +781,7 @@ pub struct PollStream {
     shutdown_fut: ShutdownFut,
 
     read_buf_cap: usize,
+    read_partial: bool,
 }
 
 impl PollStream {
@@ -594,6 +793,7 @@ impl PollStream {
             write_fut: None,
             shutdown_fut: ShutdownFut::Idle,
             read_buf_cap: DEFAULT_READ_BUF_SIZE,
+            read_partial: false,
         }
     }
 
@@ -637,6 +837,13 @@ impl PollStream {
     pub fn set_read_buf_capacity(&mut self, capacity: usize) {
         self.read_buf_cap = capacity
     }
+
+    /// Makes `poll_read` return the bytes of ordered messages as their fragments arrive (see
+    /// [`Stream::read_partial`]), rather than whole messages, which must then fit the read
+    /// buffer. The messages are read back to back, without boundaries.
+    pub fn set_read_partial(&mut self, read_partial: bool) {
+        self.read_partial = read_partial
+    }
 }
 
 impl AsyncRead for PollStream {
@@ -655,12 +862,26 @@ impl AsyncRead for PollStream {
                 // be shorter than the lifetime of `read_fut`.
                 let stream = self.stream.clone();
                 let mut temp_buf = vec![0; self.read_buf_cap];
-                self.read_fut = ReadFut::Reading(Box::pin(async move {
-                    stream.read(temp_buf.as_mut_slice()).await.map(|n| {
-                        temp_buf.truncate(n);
-                        temp_buf
-                    })
-                }));
+                self.read_fut = if self.read_partial {
+                    ReadFut::Reading(Box::pin(async move {
+                        loop {
+                            let (n, _, end_of_message) =
+                                stream.read_partial(temp_buf.as_mut_slice()).await?;
+                            // skip empty messages, an empty buffer means EOF
+                            if n > 0 || !end_of_message {
+                                temp_buf.truncate(n);
+                                return Ok(temp_buf);
+                            }
+                        }
+                    }))
+                } else {
+                    ReadFut::Reading(Box::pin(async move {
+                        stream.read(temp_buf.as_mut_slice()).await.map(|n| {
+                            temp_buf.truncate(n);
+                            temp_buf
+                        })
+                    }))
+                };
                 self.read_fut.get_reading_mut()
             }
             ReadFut::Reading(ref mut fut) => fut,
@@ -828,6 +1049,7 @@ impl fmt::Debug for PollStream {
         f.debug_struct("PollStream")
             .field("stream", &self.stream)
             .field("read_buf_cap", &self.read_buf_cap)
+            .field("read_partial", &self.read_partial)
             .finish()
     }
 }
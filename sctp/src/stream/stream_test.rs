@@ -228,3 +228,51 @@ async fn test_poll_stream() -> std::result::Result<(), io::Error> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_poll_stream_read_partial() -> std::result::Result<(), io::Error> {
+    let (awake_write_loop_ch_tx, _awake_write_loop_ch_rx) = mpsc::channel(1);
+    let s = Arc::new(Stream::new(
+        "test_poll_stream_read_partial".to_owned(),
+        0,
+        4096,
+        Arc::new(AtomicU32::new(4096)),
+        Arc::new(AtomicU8::new(AssociationState::Established as u8)),
+        Arc::new(awake_write_loop_ch_tx),
+        Arc::new(PendingQueue::new()),
+    ));
+    let mut poll_stream = PollStream::new(s.clone());
+    poll_stream.set_read_partial(true);
+
+    // the first fragment is read before the message is complete
+    s.handle_data(ChunkPayloadData {
+        beginning_fragment: true,
+        tsn: 1,
+        user_data: Bytes::from_static(&[0, 1, 2, 3, 4]),
+        payload_type: PayloadProtocolIdentifier::Binary,
+        ..Default::default()
+    })
+    .await;
+    let mut buf = [0; 5];
+    poll_stream.read_exact(&mut buf).await?;
+    assert_eq!(buf, [0, 1, 2, 3, 4]);
+
+    // a read waiting for the next fragment is woken up when it arrives
+    let sc = s.clone();
+    tokio::spawn(async move {
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        sc.handle_data(ChunkPayloadData {
+            ending_fragment: true,
+            tsn: 2,
+            user_data: Bytes::from_static(&[5, 6, 7]),
+            payload_type: PayloadProtocolIdentifier::Binary,
+            ..Default::default()
+        })
+        .await;
+    });
+    let mut buf = [0; 3];
+    poll_stream.read_exact(&mut buf).await?;
+    assert_eq!(buf, [5, 6, 7]);
+
+    Ok(())
+}
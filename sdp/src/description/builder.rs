@@ -0,0 +1,289 @@
+use crate::description::common::*;
+use crate::description::media::*;
+use crate::description::session::*;
+use crate::direction::Direction;
+use crate::error::{Error, Result};
+
+/// SessionDescriptionBuilder builds a [`SessionDescription`] line by line. The origin,
+/// session name and timing, the `o=`, `s=` and `t=` lines every description needs, must be
+/// given before it builds.
+///
+/// ```
+/// use sdp::description::builder::MediaDescriptionBuilder;
+/// use sdp::direction::Direction;
+/// use sdp::SessionDescription;
+///
+/// let sdp = SessionDescription::builder()
+///     .origin("-", 4596489990601351948, 2, "127.0.0.1")
+///     .session_name("-")
+///     .timing(0, 0)
+///     .fingerprint("sha-256", "4A:AD:B9:B1:3F:82:18:3B:54:02:12:DF:3E:5D:49:6B:19:E5:7C:AB")
+///     .media(
+///         MediaDescriptionBuilder::new("audio")
+///             .mid("0")
+///             .direction(Direction::RecvOnly)
+///             .codec(111, "opus", 48000, 2, "minptime=10;useinbandfec=1"),
+///     )
+///     .build()
+///     .unwrap();
+/// assert!(sdp.marshal().contains("a=rtpmap:111 opus/48000/2\r\n"));
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct SessionDescriptionBuilder {
+    origin: Option<Origin>,
+    session_name: Option<SessionName>,
+    timing: Option<Timing>,
+    connection_information: Option<ConnectionInformation>,
+    attributes: Vec<Attribute>,
+    media: Vec<MediaDescriptionBuilder>,
+}
+
+impl SessionDescription {
+    /// builder returns a [`SessionDescriptionBuilder`].
+    pub fn builder() -> SessionDescriptionBuilder {
+        SessionDescriptionBuilder::default()
+    }
+}
+
+impl SessionDescriptionBuilder {
+    /// origin sets the `o=` line, with an IPv4 unicast address
+    pub fn origin(
+        mut self,
+        username: &str,
+        session_id: u64,
+        session_version: u64,
+        unicast_address: &str,
+    ) -> Self {
+        self.origin = Some(Origin {
+            username: username.to_owned(),
+            session_id,
+            session_version,
+            network_type: "IN".to_owned(),
+            address_type: "IP4".to_owned(),
+            unicast_address: unicast_address.to_owned(),
+        });
+        self
+    }
+
+    /// session_name sets the `s=` line
+    pub fn session_name(mut self, session_name: &str) -> Self {
+        self.session_name = Some(session_name.to_owned());
+        self
+    }
+
+    /// timing sets the `t=` line, `t=0 0` describes a session that is not bounded in time
+    pub fn timing(mut self, start_time: u64, stop_time: u64) -> Self {
+        self.timing = Some(Timing {
+            start_time,
+            stop_time,
+        });
+        self
+    }
+
+    /// connection sets the session level `c=` line, with an IPv4 address
+    pub fn connection(mut self, address: &str) -> Self {
+        self.connection_information = Some(ipv4_connection(address));
+        self
+    }
+
+    /// attribute adds `a=key` or `a=key:value`
+    pub fn attribute(mut self, key: &str, value: Option<&str>) -> Self {
+        self.attributes
+            .push(Attribute::new(key.to_owned(), value.map(str::to_owned)));
+        self
+    }
+
+    /// fingerprint adds `a=fingerprint:<algorithm> <value>`
+    pub fn fingerprint(self, algorithm: &str, value: &str) -> Self {
+        self.attribute("fingerprint", Some(&format!("{algorithm} {value}")))
+    }
+
+    /// ice_lite adds `a=ice-lite`
+    pub fn ice_lite(self) -> Self {
+        self.attribute("ice-lite", None)
+    }
+
+    /// bundle adds `a=group:BUNDLE` with the given mids
+    pub fn bundle(self, mids: &[&str]) -> Self {
+        self.attribute("group", Some(&format!("BUNDLE {}", mids.join(" "))))
+    }
+
+    /// media adds a media description, built along with the session description
+    pub fn media(mut self, media: MediaDescriptionBuilder) -> Self {
+        self.media.push(media);
+        self
+    }
+
+    /// build returns the session description, or an error naming the first required line
+    /// that is missing
+    pub fn build(self) -> Result<SessionDescription> {
+        let origin = self.origin.ok_or(Error::SdpMissingLine("o="))?;
+        let session_name = self.session_name.ok_or(Error::SdpMissingLine("s="))?;
+        let timing = self.timing.ok_or(Error::SdpMissingLine("t="))?;
+
+        Ok(SessionDescription {
+            version: 0,
+            origin,
+            session_name,
+            connection_information: self.connection_information,
+            time_descriptions: vec![TimeDescription {
+                timing,
+                repeat_times: vec![],
+            }],
+            attributes: self.attributes,
+            media_descriptions: self
+                .media
+                .into_iter()
+                .map(MediaDescriptionBuilder::build)
+                .collect::<Result<_>>()?,
+            ..Default::default()
+        })
+    }
+}
+
+/// MediaDescriptionBuilder builds a [`MediaDescription`]. It starts from the `m=` and `c=`
+/// lines JSEP uses, port 9 over UDP/TLS/RTP/SAVPF and address 0.0.0.0, and needs at least
+/// one format.
+#[derive(Debug, Clone)]
+pub struct MediaDescriptionBuilder {
+    media: MediaDescription,
+}
+
+impl MediaDescription {
+    /// builder returns a [`MediaDescriptionBuilder`] for the given media, such as audio or video.
+    pub fn builder(media: &str) -> MediaDescriptionBuilder {
+        MediaDescriptionBuilder::new(media)
+    }
+}
+
+impl MediaDescriptionBuilder {
+    pub fn new(media: &str) -> Self {
+        MediaDescriptionBuilder {
+            media: MediaDescription::new_jsep_media_description(media.to_owned(), vec![]),
+        }
+    }
+
+    /// port sets the port of the `m=` line
+    pub fn port(mut self, port: isize) -> Self {
+        self.media.media_name.port = RangedPort {
+            value: port,
+            range: None,
+        };
+        self
+    }
+
+    /// protos sets the transport protocols of the `m=` line
+    pub fn protos(mut self, protos: &[&str]) -> Self {
+        self.media.media_name.protos = protos.iter().map(|p| (*p).to_owned()).collect();
+        self
+    }
+
+    /// format adds a format to the `m=` line, such as webrtc-datachannel
+    pub fn format(mut self, format: &str) -> Self {
+        self.media.media_name.formats.push(format.to_owned());
+        self
+    }
+
+    /// connection sets the `c=` line, with an IPv4 address
+    pub fn connection(mut self, address: &str) -> Self {
+        self.media.connection_information = Some(ipv4_connection(address));
+        self
+    }
+
+    /// attribute adds `a=key` or `a=key:value`
+    pub fn attribute(mut self, key: &str, value: Option<&str>) -> Self {
+        self.media
+            .attributes
+            .push(Attribute::new(key.to_owned(), value.map(str::to_owned)));
+        self
+    }
+
+    /// mid adds `a=mid`
+    pub fn mid(self, mid: &str) -> Self {
+        self.attribute("mid", Some(mid))
+    }
+
+    /// direction adds `a=sendrecv`, `a=sendonly`, `a=recvonly` or `a=inactive`
+    pub fn direction(self, direction: Direction) -> Self {
+        self.attribute(&direction.to_string(), None)
+    }
+
+    /// codec adds the payload type to the `m=` line along with its `a=rtpmap` and, if fmtp is
+    /// not empty, `a=fmtp`
+    pub fn codec(
+        mut self,
+        payload_type: u8,
+        name: &str,
+        clock_rate: u32,
+        channels: u16,
+        fmtp: &str,
+    ) -> Self {
+        self.media = self.media.with_codec(
+            payload_type,
+            name.to_owned(),
+            clock_rate,
+            channels,
+            fmtp.to_owned(),
+        );
+        self
+    }
+
+    /// rtcp_feedback adds `a=rtcp-fb` for the payload type, such as nack pli
+    pub fn rtcp_feedback(self, payload_type: u8, feedback: &str) -> Self {
+        self.attribute("rtcp-fb", Some(&format!("{payload_type} {feedback}")))
+    }
+
+    /// extmap adds `a=extmap` for the header extension uri
+    pub fn extmap(self, id: u16, uri: &str) -> Self {
+        self.attribute("extmap", Some(&format!("{id} {uri}")))
+    }
+
+    /// ice_credentials adds `a=ice-ufrag` and `a=ice-pwd`
+    pub fn ice_credentials(self, ufrag: &str, pwd: &str) -> Self {
+        self.attribute("ice-ufrag", Some(ufrag))
+            .attribute("ice-pwd", Some(pwd))
+    }
+
+    /// fingerprint adds `a=fingerprint:<algorithm> <value>`
+    pub fn fingerprint(self, algorithm: &str, value: &str) -> Self {
+        self.attribute("fingerprint", Some(&format!("{algorithm} {value}")))
+    }
+
+    /// setup adds `a=setup`, such as actpass, active or passive
+    pub fn setup(self, role: &str) -> Self {
+        self.attribute("setup", Some(role))
+    }
+
+    /// rtcp_mux adds `a=rtcp-mux`
+    pub fn rtcp_mux(self) -> Self {
+        self.attribute("rtcp-mux", None)
+    }
+
+    /// ssrc adds `a=ssrc` with the cname of the source
+    pub fn ssrc(self, ssrc: u32, cname: &str) -> Self {
+        self.attribute("ssrc", Some(&format!("{ssrc} cname:{cname}")))
+    }
+
+    /// build returns the media description, or an error if the `m=` line has no format
+    pub fn build(self) -> Result<MediaDescription> {
+        if self.media.media_name.formats.is_empty() {
+            return Err(Error::SdpInvalidValue(format!(
+                "m={} without any format",
+                self.media.media_name.media
+            )));
+        }
+        Ok(self.media)
+    }
+}
+
+fn ipv4_connection(address: &str) -> ConnectionInformation {
+    ConnectionInformation {
+        network_type: "IN".to_owned(),
+        address_type: "IP4".to_owned(),
+        address: Some(Address {
+            address: address.to_owned(),
+            ttl: None,
+            range: None,
+        }),
+    }
+}
@@ -65,7 +65,7 @@ pub type EncryptionKey = String;
 
 /// Attribute describes the "a=" field which represents the primary means for
 /// extending SDP.
-#[derive(Debug, Default, Clone)]
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Attribute {
     pub key: String,
     pub value: Option<String>,
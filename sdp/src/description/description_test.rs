@@ -2,10 +2,14 @@ use std::io::Cursor;
 
 use url::Url;
 
+use super::builder::*;
 use super::common::*;
+use super::diff::*;
 use super::media::*;
 use super::session::*;
+use crate::direction::Direction;
 use crate::error::{Error, Result};
+use crate::util::Codec;
 
 const CANONICAL_MARSHAL_SDP: &str = "v=0\r\n\
      o=jdoe 2890844526 2890842807 IN IP4 10.47.16.5\r\n\
@@ -605,3 +609,230 @@ fn test_unmarshal_non_nil_address() -> Result<()> {
     }
     Ok(())
 }
+
+const DIFF_OLD_SDP: &str = "v=0\r\n\
+     o=- 4596489990601351948 2 IN IP4 127.0.0.1\r\n\
+     s=-\r\n\
+     t=0 0\r\n\
+     a=group:BUNDLE 0\r\n\
+     m=audio 9 UDP/TLS/RTP/SAVPF 111 0\r\n\
+     c=IN IP4 0.0.0.0\r\n\
+     a=mid:0\r\n\
+     a=sendrecv\r\n\
+     a=rtpmap:111 opus/48000/2\r\n\
+     a=fmtp:111 minptime=10\r\n\
+     a=rtpmap:0 PCMU/8000\r\n";
+
+const DIFF_NEW_SDP: &str = "v=0\r\n\
+     o=- 4596489990601351948 3 IN IP4 127.0.0.1\r\n\
+     s=-\r\n\
+     t=0 0\r\n\
+     a=group:BUNDLE 0 1\r\n\
+     m=audio 9 UDP/TLS/RTP/SAVPF 111 8\r\n\
+     c=IN IP4 0.0.0.0\r\n\
+     a=mid:0\r\n\
+     a=recvonly\r\n\
+     a=rtpmap:111 opus/48000/2\r\n\
+     a=fmtp:111 minptime=10;useinbandfec=1\r\n\
+     a=rtpmap:8 PCMA/8000\r\n\
+     m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+     c=IN IP4 0.0.0.0\r\n\
+     a=mid:1\r\n\
+     a=rtpmap:96 VP8/90000\r\n";
+
+#[test]
+fn test_session_description_diff() -> Result<()> {
+    let old = SessionDescription::unmarshal(&mut Cursor::new(DIFF_OLD_SDP.as_bytes()))?;
+    let new = SessionDescription::unmarshal(&mut Cursor::new(DIFF_NEW_SDP.as_bytes()))?;
+
+    assert!(SessionDescriptionDiff::new(&old, &old).is_empty());
+
+    let diff = SessionDescriptionDiff::new(&old, &new);
+    assert_eq!(
+        diff.attributes,
+        AttributesDiff {
+            added: vec![Attribute::new(
+                "group".to_owned(),
+                Some("BUNDLE 0 1".to_owned())
+            )],
+            removed: vec![Attribute::new(
+                "group".to_owned(),
+                Some("BUNDLE 0".to_owned())
+            )],
+        }
+    );
+    assert_eq!(diff.media_descriptions.len(), 2);
+
+    if let MediaDescriptionDiff::Modified(changes) = &diff.media_descriptions[0] {
+        assert_eq!(changes.index, 0);
+        assert_eq!(changes.mid.as_deref(), Some("0"));
+        assert!(changes.media.is_none());
+        assert!(changes.port.is_none());
+        assert_eq!(
+            changes.formats,
+            Some(Change {
+                old: vec!["111".to_owned(), "0".to_owned()],
+                new: vec!["111".to_owned(), "8".to_owned()],
+            })
+        );
+        assert_eq!(
+            changes.direction,
+            Some(Change {
+                old: Direction::SendRecv,
+                new: Direction::RecvOnly,
+            })
+        );
+
+        let codec_names =
+            |codecs: &[Codec]| -> Vec<String> { codecs.iter().map(|c| c.name.clone()).collect() };
+        assert_eq!(codec_names(&changes.codecs.added), vec!["PCMA"]);
+        assert_eq!(codec_names(&changes.codecs.removed), vec!["PCMU"]);
+        assert_eq!(changes.codecs.modified.len(), 1);
+        assert_eq!(changes.codecs.modified[0].old.fmtp, "minptime=10");
+        assert_eq!(
+            changes.codecs.modified[0].new.fmtp,
+            "minptime=10;useinbandfec=1"
+        );
+
+        assert_eq!(changes.attributes.added.len(), 3);
+        assert_eq!(changes.attributes.removed.len(), 3);
+    } else {
+        panic!("media description 0 should be modified");
+    }
+
+    assert_eq!(
+        diff.media_descriptions[1],
+        MediaDescriptionDiff::Added {
+            index: 1,
+            mid: Some("1".to_owned()),
+        }
+    );
+
+    let diff = SessionDescriptionDiff::new(&new, &old);
+    assert_eq!(
+        diff.media_descriptions[1],
+        MediaDescriptionDiff::Removed {
+            index: 1,
+            mid: Some("1".to_owned()),
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_session_description_builder() -> Result<()> {
+    let sdp = SessionDescription::builder()
+        .origin("-", 4596489990601351948, 2, "127.0.0.1")
+        .session_name("-")
+        .timing(0, 0)
+        .fingerprint("sha-256", "4A:AD:B9:B1")
+        .bundle(&["0", "1"])
+        .media(
+            MediaDescription::builder("audio")
+                .mid("0")
+                .direction(Direction::SendRecv)
+                .ice_credentials("ufrag", "pwd")
+                .setup("actpass")
+                .rtcp_mux()
+                .codec(111, "opus", 48000, 2, "minptime=10;useinbandfec=1")
+                .extmap(1, "urn:ietf:params:rtp-hdrext:ssrc-audio-level"),
+        )
+        .media(
+            MediaDescription::builder("video")
+                .mid("1")
+                .direction(Direction::RecvOnly)
+                .codec(96, "VP8", 90000, 0, "")
+                .rtcp_feedback(96, "nack pli"),
+        )
+        .build()?;
+
+    let expected = "v=0\r\n\
+        o=- 4596489990601351948 2 IN IP4 127.0.0.1\r\n\
+        s=-\r\n\
+        t=0 0\r\n\
+        a=fingerprint:sha-256 4A:AD:B9:B1\r\n\
+        a=group:BUNDLE 0 1\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111\r\n\
+        c=IN IP4 0.0.0.0\r\n\
+        a=mid:0\r\n\
+        a=sendrecv\r\n\
+        a=ice-ufrag:ufrag\r\n\
+        a=ice-pwd:pwd\r\n\
+        a=setup:actpass\r\n\
+        a=rtcp-mux\r\n\
+        a=rtpmap:111 opus/48000/2\r\n\
+        a=fmtp:111 minptime=10;useinbandfec=1\r\n\
+        a=extmap:1 urn:ietf:params:rtp-hdrext:ssrc-audio-level\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\n\
+        c=IN IP4 0.0.0.0\r\n\
+        a=mid:1\r\n\
+        a=recvonly\r\n\
+        a=rtpmap:96 VP8/90000\r\n\
+        a=rtcp-fb:96 nack pli\r\n";
+    assert_eq!(sdp.marshal(), expected);
+
+    let mut reader = Cursor::new(expected.as_bytes());
+    let parsed = SessionDescription::unmarshal(&mut reader)?;
+    assert_eq!(parsed.marshal(), expected);
+
+    Ok(())
+}
+
+#[test]
+fn test_session_description_builder_missing_lines() {
+    let result = SessionDescription::builder()
+        .session_name("-")
+        .timing(0, 0)
+        .build();
+    assert_eq!(result.err(), Some(Error::SdpMissingLine("o=")));
+
+    let result = SessionDescription::builder()
+        .origin("-", 1, 1, "127.0.0.1")
+        .timing(0, 0)
+        .build();
+    assert_eq!(result.err(), Some(Error::SdpMissingLine("s=")));
+
+    let result = SessionDescription::builder()
+        .origin("-", 1, 1, "127.0.0.1")
+        .session_name("-")
+        .build();
+    assert_eq!(result.err(), Some(Error::SdpMissingLine("t=")));
+
+    let result = SessionDescription::builder()
+        .origin("-", 1, 1, "127.0.0.1")
+        .session_name("-")
+        .timing(0, 0)
+        .media(MediaDescriptionBuilder::new("audio").mid("0"))
+        .build();
+    assert!(matches!(result, Err(Error::SdpInvalidValue(_))));
+}
+
+#[test]
+fn test_end_of_candidates() -> Result<()> {
+    let tests = vec![
+        ("", false),
+        (
+            "a=end-of-candidates\r\nm=audio 9 UDP/TLS/RTP/SAVPF 111\r\n",
+            true,
+        ),
+        (
+            "m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=end-of-candidates\r\n",
+            true,
+        ),
+        (
+            "m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=candidate:0 1 UDP 1 1.2.3.4 5000 typ host\r\n",
+            false,
+        ),
+    ];
+
+    for (lines, expected) in tests {
+        let input = format!("{BASE_SDP}t=0 0\r\n{lines}");
+        let mut reader = Cursor::new(input.as_bytes());
+        let sdp = SessionDescription::unmarshal(&mut reader)?;
+        assert_eq!(sdp.end_of_candidates(), expected, "{input}");
+        assert_eq!(sdp.marshal(), input);
+    }
+
+    Ok(())
+}
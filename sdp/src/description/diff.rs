@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+
+use super::common::Attribute;
+use super::media::MediaDescription;
+use super::session::{SessionDescription, ATTR_KEY_MID};
+use crate::direction::Direction;
+use crate::util::{merge_codec_attribute, Codec};
+
+/// Change holds the old and the new value of a field that differs between
+/// two descriptions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change<T> {
+    pub old: T,
+    pub new: T,
+}
+
+impl<T: PartialEq> Change<T> {
+    fn new(old: T, new: T) -> Option<Self> {
+        if old == new {
+            None
+        } else {
+            Some(Change { old, new })
+        }
+    }
+}
+
+/// AttributesDiff lists the `a=` lines present in only one of two descriptions.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AttributesDiff {
+    /// attributes only present in the new description
+    pub added: Vec<Attribute>,
+    /// attributes only present in the old description
+    pub removed: Vec<Attribute>,
+}
+
+impl AttributesDiff {
+    fn new(old: &[Attribute], new: &[Attribute]) -> Self {
+        let mut removed = old.to_vec();
+        let mut added = vec![];
+        for a in new {
+            if let Some(i) = removed.iter().position(|r| r == a) {
+                removed.remove(i);
+            } else {
+                added.push(a.clone());
+            }
+        }
+
+        AttributesDiff { added, removed }
+    }
+
+    /// is_empty returns true if no attribute was added or removed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// CodecsDiff lists the codecs of a media description that changed, sorted
+/// by payload type.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct CodecsDiff {
+    /// codecs whose payload type is only present in the new description
+    pub added: Vec<Codec>,
+    /// codecs whose payload type is only present in the old description
+    pub removed: Vec<Codec>,
+    /// codecs whose rtpmap, fmtp or rtcp-fb lines changed
+    pub modified: Vec<Change<Codec>>,
+}
+
+impl CodecsDiff {
+    fn new(old: &MediaDescription, new: &MediaDescription) -> Self {
+        let mut old = codecs(old);
+        let mut diff = CodecsDiff::default();
+
+        let mut new: Vec<Codec> = codecs(new).into_values().collect();
+        new.sort_by_key(|c| c.payload_type);
+        for codec in new {
+            match old.remove(&codec.payload_type) {
+                Some(old_codec) => {
+                    if let Some(change) = Change::new(old_codec, codec) {
+                        diff.modified.push(change);
+                    }
+                }
+                None => diff.added.push(codec),
+            }
+        }
+
+        diff.removed = old.into_values().collect();
+        diff.removed.sort_by_key(|c| c.payload_type);
+
+        diff
+    }
+
+    /// is_empty returns true if no codec changed
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.modified.is_empty()
+    }
+}
+
+/// MediaDescriptionChanges describes how a media description present in both
+/// descriptions changed.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct MediaDescriptionChanges {
+    /// position of the media description in both descriptions
+    pub index: usize,
+    /// mid of the media description in the new description
+    pub mid: Option<String>,
+    /// media type of the `m=` line
+    pub media: Option<Change<String>>,
+    /// port of the `m=` line, a port of 0 rejects the media description
+    pub port: Option<Change<isize>>,
+    /// transport protocols of the `m=` line
+    pub protos: Option<Change<Vec<String>>>,
+    /// formats of the `m=` line
+    pub formats: Option<Change<Vec<String>>>,
+    /// direction attribute of the media description
+    pub direction: Option<Change<Direction>>,
+    /// codecs described by the rtpmap, fmtp and rtcp-fb attributes
+    pub codecs: CodecsDiff,
+    /// every attribute added or removed, including the ones the direction and
+    /// codecs changes are derived from
+    pub attributes: AttributesDiff,
+}
+
+impl MediaDescriptionChanges {
+    fn new(index: usize, old: &MediaDescription, new: &MediaDescription) -> Self {
+        MediaDescriptionChanges {
+            index,
+            mid: mid(new),
+            media: Change::new(old.media_name.media.clone(), new.media_name.media.clone()),
+            port: Change::new(old.media_name.port.value, new.media_name.port.value),
+            protos: Change::new(old.media_name.protos.clone(), new.media_name.protos.clone()),
+            formats: Change::new(
+                old.media_name.formats.clone(),
+                new.media_name.formats.clone(),
+            ),
+            direction: Change::new(direction(old), direction(new)),
+            codecs: CodecsDiff::new(old, new),
+            attributes: AttributesDiff::new(&old.attributes, &new.attributes),
+        }
+    }
+
+    /// is_empty returns true if nothing changed
+    pub fn is_empty(&self) -> bool {
+        self.media.is_none()
+            && self.port.is_none()
+            && self.protos.is_none()
+            && self.formats.is_none()
+            && self.direction.is_none()
+            && self.codecs.is_empty()
+            && self.attributes.is_empty()
+    }
+}
+
+/// MediaDescriptionDiff describes a media description that differs between
+/// two descriptions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaDescriptionDiff {
+    /// Added is a media description only present in the new description
+    Added { index: usize, mid: Option<String> },
+    /// Removed is a media description only present in the old description
+    Removed { index: usize, mid: Option<String> },
+    /// Modified is a media description present in both descriptions
+    Modified(Box<MediaDescriptionChanges>),
+}
+
+/// SessionDescriptionDiff is the difference between two SessionDescriptions
+/// at the media description and attribute level, e.g. to inspect what a
+/// renegotiation offer changes before applying it.
+///
+/// Media descriptions are matched by their position, since JSEP neither
+/// removes nor reorders them during renegotiation. The origin and timing
+/// fields are not compared.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SessionDescriptionDiff {
+    /// session level attributes added or removed
+    pub attributes: AttributesDiff,
+    /// media descriptions that changed, in the order they appear
+    pub media_descriptions: Vec<MediaDescriptionDiff>,
+}
+
+impl SessionDescriptionDiff {
+    /// new computes the changes from old to new
+    pub fn new(old: &SessionDescription, new: &SessionDescription) -> Self {
+        let mut media_descriptions = vec![];
+        let n = std::cmp::max(old.media_descriptions.len(), new.media_descriptions.len());
+        for index in 0..n {
+            match (
+                old.media_descriptions.get(index),
+                new.media_descriptions.get(index),
+            ) {
+                (Some(old), Some(new)) => {
+                    let changes = MediaDescriptionChanges::new(index, old, new);
+                    if !changes.is_empty() {
+                        media_descriptions.push(MediaDescriptionDiff::Modified(Box::new(changes)));
+                    }
+                }
+                (None, Some(new)) => media_descriptions.push(MediaDescriptionDiff::Added {
+                    index,
+                    mid: mid(new),
+                }),
+                (Some(old), None) => media_descriptions.push(MediaDescriptionDiff::Removed {
+                    index,
+                    mid: mid(old),
+                }),
+                (None, None) => {}
+            }
+        }
+
+        SessionDescriptionDiff {
+            attributes: AttributesDiff::new(&old.attributes, &new.attributes),
+            media_descriptions,
+        }
+    }
+
+    /// is_empty returns true if the descriptions have the same attributes and
+    /// media descriptions
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty() && self.media_descriptions.is_empty()
+    }
+}
+
+fn mid(m: &MediaDescription) -> Option<String> {
+    m.attribute(ATTR_KEY_MID)
+        .flatten()
+        .map(|mid| mid.to_owned())
+}
+
+fn direction(m: &MediaDescription) -> Direction {
+    m.attributes
+        .iter()
+        .map(|a| Direction::new(&a.key))
+        .find(|d| *d != Direction::Unspecified)
+        .unwrap_or_default()
+}
+
+fn codecs(m: &MediaDescription) -> HashMap<u8, Codec> {
+    let mut codecs = HashMap::new();
+    for a in &m.attributes {
+        // Malformed attributes are ignored, like when negotiating
+        let _ = merge_codec_attribute(&a.to_string(), &mut codecs);
+    }
+    codecs
+}
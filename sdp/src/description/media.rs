@@ -4,7 +4,11 @@ use std::fmt;
 use url::Url;
 
 use crate::description::common::*;
+use crate::description::session::{
+    ATTR_KEY_END_OF_CANDIDATES, ATTR_KEY_FRAMERATE, ATTR_KEY_IMAGEATTR,
+};
 use crate::extmap::*;
+use crate::imageattr::*;
 
 /// Constants for extmap key
 pub const EXT_MAP_VALUE_TRANSPORT_CC_KEY: isize = 3;
@@ -80,6 +84,29 @@ impl MediaDescription {
         None
     }
 
+    /// image_attrs returns the `a=imageattr` attributes (RFC 6236) of the media description,
+    /// skipping the ones that fail to parse
+    pub fn image_attrs(&self) -> Vec<ImageAttr> {
+        self.attributes
+            .iter()
+            .filter(|a| a.key == ATTR_KEY_IMAGEATTR)
+            .filter_map(|a| ImageAttr::unmarshal(&mut a.to_string().as_bytes()).ok())
+            .collect()
+    }
+
+    /// framerate returns the maximum video frame rate of `a=framerate` (RFC 4566)
+    pub fn framerate(&self) -> Option<f64> {
+        self.attribute(ATTR_KEY_FRAMERATE)
+            .flatten()
+            .and_then(|framerate| framerate.trim().parse::<f64>().ok())
+    }
+
+    /// end_of_candidates returns true if the media description has the trickle ICE
+    /// `a=end-of-candidates` attribute (RFC 8840), no more candidates follow for the section
+    pub fn end_of_candidates(&self) -> bool {
+        self.has_attribute(ATTR_KEY_END_OF_CANDIDATES)
+    }
+
     /// new_jsep_media_description creates a new MediaName with
     /// some settings that are required by the JSEP spec.
     pub fn new_jsep_media_description(codec_type: String, _codec_prefs: Vec<&str>) -> Self {
@@ -183,10 +210,32 @@ impl MediaDescription {
         self.with_value_attribute("candidate".to_string(), value)
     }
 
+    /// with_end_of_candidates adds `a=end-of-candidates` (RFC 8840) to the media description,
+    /// once
+    pub fn with_end_of_candidates(self) -> Self {
+        if self.end_of_candidates() {
+            self
+        } else {
+            self.with_property_attribute(ATTR_KEY_END_OF_CANDIDATES.to_owned())
+        }
+    }
+
     pub fn with_extmap(self, e: ExtMap) -> Self {
         self.with_property_attribute(e.marshal())
     }
 
+    /// with_image_attr adds an `a=imageattr` attribute (RFC 6236) to the media description
+    pub fn with_image_attr(mut self, image_attr: ImageAttr) -> Self {
+        self.attributes.push(image_attr.convert());
+        self
+    }
+
+    /// with_framerate adds the maximum video frame rate `a=framerate` (RFC 4566) to the media
+    /// description
+    pub fn with_framerate(self, framerate: f64) -> Self {
+        self.with_value_attribute(ATTR_KEY_FRAMERATE.to_owned(), framerate.to_string())
+    }
+
     /// with_transport_cc_extmap adds an extmap to the media description
     pub fn with_transport_cc_extmap(self) -> Self {
         let uri = {
@@ -288,4 +337,16 @@ mod tests {
 
         assert_eq!(media_description.attribute("ptime"), Some(Some("1")));
     }
+
+    #[test]
+    fn test_end_of_candidates() {
+        let media_description = MediaDescription::default();
+        assert!(!media_description.end_of_candidates());
+
+        let media_description = media_description
+            .with_end_of_candidates()
+            .with_end_of_candidates();
+        assert!(media_description.end_of_candidates());
+        assert_eq!(media_description.attributes.len(), 1);
+    }
 }
@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod description_test;
 
+pub mod builder;
 pub mod common;
+pub mod diff;
 pub mod media;
 pub mod session;
@@ -23,6 +23,7 @@ pub const ATTR_KEY_MSID_SEMANTIC: &str = "msid-semantic";
 pub const ATTR_KEY_CONNECTION_SETUP: &str = "setup";
 pub const ATTR_KEY_MID: &str = "mid";
 pub const ATTR_KEY_ICELITE: &str = "ice-lite";
+pub const ATTR_KEY_RTCP: &str = "rtcp";
 pub const ATTR_KEY_RTCPMUX: &str = "rtcp-mux";
 pub const ATTR_KEY_RTCPRSIZE: &str = "rtcp-rsize";
 pub const ATTR_KEY_INACTIVE: &str = "inactive";
@@ -31,6 +32,10 @@ pub const ATTR_KEY_SEND_ONLY: &str = "sendonly";
 pub const ATTR_KEY_SEND_RECV: &str = "sendrecv";
 pub const ATTR_KEY_EXT_MAP: &str = "extmap";
 pub const ATTR_KEY_EXTMAP_ALLOW_MIXED: &str = "extmap-allow-mixed";
+pub const ATTR_KEY_CONTENT: &str = "content";
+pub const ATTR_KEY_IMAGEATTR: &str = "imageattr";
+pub const ATTR_KEY_FRAMERATE: &str = "framerate";
+pub const ATTR_KEY_MAX_MESSAGE_SIZE: &str = "max-message-size";
 
 /// Constants for semantic tokens used in JSEP
 pub const SEMANTIC_TOKEN_LIP_SYNCHRONIZATION: &str = "LS";
@@ -359,37 +364,39 @@ impl SessionDescription {
         self
     }
 
-    fn build_codec_map(&self) -> HashMap<u8, Codec> {
+    fn build_codec_map(&self) -> (HashMap<u8, Codec>, Vec<Error>) {
         let mut codecs: HashMap<u8, Codec> = HashMap::new();
+        let mut errors = vec![];
 
         for m in &self.media_descriptions {
             for a in &m.attributes {
-                let attr = a.to_string();
-                if attr.starts_with("rtpmap:") {
-                    if let Ok(codec) = parse_rtpmap(&attr) {
-                        merge_codecs(codec, &mut codecs);
-                    }
-                } else if attr.starts_with("fmtp:") {
-                    if let Ok(codec) = parse_fmtp(&attr) {
-                        merge_codecs(codec, &mut codecs);
-                    }
-                } else if attr.starts_with("rtcp-fb:") {
-                    if let Ok(codec) = parse_rtcp_fb(&attr) {
-                        merge_codecs(codec, &mut codecs);
-                    }
+                if let Err(err) = merge_codec_attribute(&a.to_string(), &mut codecs) {
+                    errors.push(err);
                 }
             }
         }
 
-        codecs
+        (codecs, errors)
     }
 
-    /// get_codec_for_payload_type scans the SessionDescription for the given payload type and returns the codec
+    /// codec_attribute_errors returns an Error::InvalidCodecAttribute for every rtpmap, fmtp
+    /// or rtcp-fb attribute of the media descriptions that can't be parsed. These attributes
+    /// are ignored when looking up codecs.
+    pub fn codec_attribute_errors(&self) -> Vec<Error> {
+        self.build_codec_map().1
+    }
+
+    /// get_codec_for_payload_type scans the SessionDescription for the given payload type and returns the codec.
+    /// If the payload type is only described by malformed attributes, the error names the first of them.
     pub fn get_codec_for_payload_type(&self, payload_type: u8) -> Result<Codec> {
-        let codecs = self.build_codec_map();
+        let (codecs, errors) = self.build_codec_map();
 
         if let Some(codec) = codecs.get(&payload_type) {
             Ok(codec.clone())
+        } else if let Some(err) = errors.into_iter().find(|err| {
+            matches!(err, Error::InvalidCodecAttribute { payload_type: pt, .. } if *pt == Some(payload_type))
+        }) {
+            Err(err)
         } else {
             Err(Error::PayloadTypeNotFound)
         }
@@ -398,7 +405,7 @@ impl SessionDescription {
     /// get_payload_type_for_codec scans the SessionDescription for a codec that matches the provided codec
     /// as closely as possible and returns its payload type
     pub fn get_payload_type_for_codec(&self, wanted: &Codec) -> Result<u8> {
-        let codecs = self.build_codec_map();
+        let (codecs, _) = self.build_codec_map();
 
         for (payload_type, codec) in codecs.iter() {
             if codecs_match(wanted, codec) {
@@ -424,6 +431,16 @@ impl SessionDescription {
         None
     }
 
+    /// end_of_candidates returns true if `a=end-of-candidates` (RFC 8840) is present at the
+    /// session level, or in any media description
+    pub fn end_of_candidates(&self) -> bool {
+        self.has_attribute(ATTR_KEY_END_OF_CANDIDATES)
+            || self
+                .media_descriptions
+                .iter()
+                .any(|m| m.end_of_candidates())
+    }
+
     /// Marshal takes a SDP struct to text
     ///
     /// <https://tools.ietf.org/html/rfc4566#section-5>
@@ -28,12 +28,23 @@ pub enum Error {
     SdpInvalidValue(String),
     #[error("sdp: empty time_descriptions")]
     SdpEmptyTimeDescription,
+    #[error("sdp: missing {0} line")]
+    SdpMissingLine(&'static str),
     #[error("parse int: {0}")]
     ParseInt(#[from] ParseIntError),
     #[error("parse url: {0}")]
     ParseUrl(#[from] url::ParseError),
     #[error("parse extmap: {0}")]
     ParseExtMap(String),
+    #[error("parse imageattr: {0}")]
+    ParseImageAttr(String),
+    #[error("invalid codec attribute \"a={attribute}\": {source}")]
+    InvalidCodecAttribute {
+        attribute: String,
+        /// payload_type the attribute is about, if it could be parsed
+        payload_type: Option<u8>,
+        source: Box<Error>,
+    },
     #[error("{} --> {} <-- {}", .s.substring(0,*.p), .s.substring(*.p, *.p+1), .s.substring(*.p+1, .s.len()))]
     SyntaxError { s: String, p: usize },
 }
@@ -0,0 +1,111 @@
+use std::io::BufReader;
+
+use super::*;
+use crate::description::media::MediaDescription;
+
+const EXAMPLE_ATTR_IMAGEATTR1: &str =
+    "imageattr:97 send [x=800,y=640,sar=1.1,q=0.6] [x=480,y=320] recv [x=330,y=250]";
+const EXAMPLE_ATTR_IMAGEATTR2: &str =
+    "imageattr:* recv [x=[320:16:640],y=[240:16:480],par=[1.2-1.3]] [x=[176,352],y=[144,288]]";
+const EXAMPLE_ATTR_IMAGEATTR3: &str = "imageattr:100 send * recv [x=[1:1280],y=[1:720]]";
+const FAILING_ATTR_IMAGEATTR1: &str = "imageattr:97";
+const FAILING_ATTR_IMAGEATTR2: &str = "imageattr:97 recv [x=320]";
+const FAILING_ATTR_IMAGEATTR3: &str = "imageattr:97 recv [x=[320:16:640:1],y=240]";
+const FAILING_ATTR_IMAGEATTR4: &str = "imageattr:97 recv [x=320,y=240] recv [x=640,y=480]";
+const FAILING_ATTR_IMAGEATTR5: &str = "imageattr:97 recv * [x=640,y=480]";
+
+#[test]
+fn test_image_attr() -> Result<()> {
+    for line in [
+        EXAMPLE_ATTR_IMAGEATTR1,
+        EXAMPLE_ATTR_IMAGEATTR2,
+        EXAMPLE_ATTR_IMAGEATTR3,
+    ] {
+        let mut reader = BufReader::new(line.as_bytes());
+        let actual = ImageAttr::unmarshal(&mut reader)?;
+        assert_eq!(actual.marshal(), line);
+    }
+
+    for line in [
+        FAILING_ATTR_IMAGEATTR1,
+        FAILING_ATTR_IMAGEATTR2,
+        FAILING_ATTR_IMAGEATTR3,
+        FAILING_ATTR_IMAGEATTR4,
+        FAILING_ATTR_IMAGEATTR5,
+    ] {
+        let mut reader = BufReader::new(line.as_bytes());
+        assert!(ImageAttr::unmarshal(&mut reader).is_err(), "{line}");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_image_attr_sets() -> Result<()> {
+    let mut reader = BufReader::new(EXAMPLE_ATTR_IMAGEATTR1.as_bytes());
+    let image_attr = ImageAttr::unmarshal(&mut reader)?;
+
+    assert_eq!(image_attr.payload_type, Some(97));
+    let send = image_attr.send.unwrap();
+    assert_eq!(send.len(), 2);
+    assert_eq!(send[0].x, ImageAttrRange::Value(800));
+    assert_eq!(send[0].sar.as_deref(), Some("1.1"));
+    assert_eq!(send[0].q, Some(0.6));
+    assert_eq!(
+        image_attr.recv,
+        Some(vec![ImageAttrSet {
+            x: ImageAttrRange::Value(330),
+            y: ImageAttrRange::Value(250),
+            ..Default::default()
+        }])
+    );
+
+    let mut reader = BufReader::new(EXAMPLE_ATTR_IMAGEATTR2.as_bytes());
+    let image_attr = ImageAttr::unmarshal(&mut reader)?;
+    assert_eq!(image_attr.payload_type, None);
+    assert_eq!(image_attr.send, None);
+    let recv = image_attr.recv.unwrap();
+    assert_eq!(
+        recv[0].x,
+        ImageAttrRange::Range {
+            min: 320,
+            step: Some(16),
+            max: 640
+        }
+    );
+    assert_eq!((recv[0].x.max(), recv[0].y.max()), (640, 480));
+    assert_eq!((recv[1].x.max(), recv[1].y.max()), (352, 288));
+
+    let mut reader = BufReader::new(EXAMPLE_ATTR_IMAGEATTR3.as_bytes());
+    let image_attr = ImageAttr::unmarshal(&mut reader)?;
+    assert_eq!(image_attr.send, Some(vec![]));
+    assert_eq!(image_attr.recv, Some(vec![ImageAttrSet::new(1280, 720)]));
+
+    Ok(())
+}
+
+#[test]
+fn test_media_description_image_attr_and_framerate() {
+    let image_attr = ImageAttr {
+        payload_type: None,
+        send: None,
+        recv: Some(vec![ImageAttrSet::new(1280, 720)]),
+    };
+    let media = MediaDescription::new_jsep_media_description("video".to_owned(), vec![])
+        .with_image_attr(image_attr.clone())
+        .with_framerate(29.97);
+
+    assert_eq!(
+        media.attribute("imageattr"),
+        Some(Some("* recv [x=[1:1280],y=[1:720]]"))
+    );
+    assert_eq!(media.attribute("framerate"), Some(Some("29.97")));
+    assert_eq!(media.image_attrs(), vec![image_attr]);
+    assert_eq!(media.framerate(), Some(29.97));
+
+    let media = MediaDescription::new_jsep_media_description("video".to_owned(), vec![])
+        .with_value_attribute("imageattr".to_owned(), "97 recv [x=320]".to_owned())
+        .with_value_attribute("framerate".to_owned(), "fast".to_owned());
+    assert!(media.image_attrs().is_empty());
+    assert_eq!(media.framerate(), None);
+}
@@ -0,0 +1,322 @@
+#[cfg(test)]
+mod imageattr_test;
+
+use std::{fmt, io};
+
+use super::error::{Error, Result};
+use crate::description::common::*;
+
+const IMAGE_ATTR_SEND: &str = "send";
+const IMAGE_ATTR_RECV: &str = "recv";
+const IMAGE_ATTR_WILDCARD: &str = "*";
+
+/// ImageAttr represents the image resolutions an endpoint sends and accepts for a payload
+/// type, as signaled by `a=imageattr` (RFC 6236).
+///
+/// `a=imageattr:97 send [x=800,y=640,sar=1.1,q=0.6] [x=480,y=320] recv [x=[320:16:640],y=[240:16:480]]`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageAttr {
+    /// payload_type the attribute applies to, None for all of them (`*`)
+    pub payload_type: Option<u8>,
+    /// send lists the sets the endpoint can send, in order of preference. None when the
+    /// attribute has no send direction, an empty list for any resolution (`send *`).
+    pub send: Option<Vec<ImageAttrSet>>,
+    /// recv lists the sets the endpoint accepts, in order of preference. None when the
+    /// attribute has no recv direction, an empty list for any resolution (`recv *`).
+    pub recv: Option<Vec<ImageAttrSet>>,
+}
+
+/// ImageAttrSet is a single `[x=...,y=...]` set of an `a=imageattr` attribute.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImageAttrSet {
+    pub x: ImageAttrRange,
+    pub y: ImageAttrRange,
+    /// sar is the sample aspect ratio, kept as signaled
+    pub sar: Option<String>,
+    /// par is the picture aspect ratio range, kept as signaled
+    pub par: Option<String>,
+    /// q is the preference of the set, from 0 to 1
+    pub q: Option<f32>,
+}
+
+/// ImageAttrRange is the width or height of an [`ImageAttrSet`], a single value
+/// (`x=640`), a range with an optional step (`x=[320:16:640]`) or a list (`x=[320,640]`).
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageAttrRange {
+    Value(u32),
+    Range {
+        min: u32,
+        step: Option<u32>,
+        max: u32,
+    },
+    List(Vec<u32>),
+}
+
+impl Default for ImageAttrRange {
+    fn default() -> Self {
+        ImageAttrRange::Value(0)
+    }
+}
+
+impl ImageAttrRange {
+    /// max returns the largest value of the range
+    pub fn max(&self) -> u32 {
+        match self {
+            ImageAttrRange::Value(v) => *v,
+            ImageAttrRange::Range { max, .. } => *max,
+            ImageAttrRange::List(values) => values.iter().copied().max().unwrap_or_default(),
+        }
+    }
+
+    fn unmarshal(s: &str) -> Result<Self> {
+        let Some(inner) = s.strip_prefix('[').and_then(|s| s.strip_suffix(']')) else {
+            return Ok(ImageAttrRange::Value(s.parse::<u32>()?));
+        };
+
+        if inner.contains(':') {
+            let values = inner
+                .split(':')
+                .map(str::parse::<u32>)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            match values[..] {
+                [min, max] => Ok(ImageAttrRange::Range {
+                    min,
+                    step: None,
+                    max,
+                }),
+                [min, step, max] => Ok(ImageAttrRange::Range {
+                    min,
+                    step: Some(step),
+                    max,
+                }),
+                _ => Err(Error::ParseImageAttr(s.to_owned())),
+            }
+        } else {
+            let values = inner
+                .split(',')
+                .map(str::parse::<u32>)
+                .collect::<std::result::Result<Vec<_>, _>>()?;
+            Ok(ImageAttrRange::List(values))
+        }
+    }
+}
+
+impl fmt::Display for ImageAttrRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ImageAttrRange::Value(v) => write!(f, "{v}"),
+            ImageAttrRange::Range {
+                min,
+                step: Some(step),
+                max,
+            } => write!(f, "[{min}:{step}:{max}]"),
+            ImageAttrRange::Range {
+                min,
+                step: None,
+                max,
+            } => write!(f, "[{min}:{max}]"),
+            ImageAttrRange::List(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "[{}]", values.join(","))
+            }
+        }
+    }
+}
+
+impl ImageAttrSet {
+    /// new returns a set accepting any resolution up to width x height
+    pub fn new(max_width: u32, max_height: u32) -> Self {
+        ImageAttrSet {
+            x: ImageAttrRange::Range {
+                min: 1,
+                step: None,
+                max: max_width,
+            },
+            y: ImageAttrRange::Range {
+                min: 1,
+                step: None,
+                max: max_height,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn unmarshal(s: &str) -> Result<Self> {
+        let inner = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| Error::ParseImageAttr(s.to_owned()))?;
+
+        let mut x = None;
+        let mut y = None;
+        let mut set = ImageAttrSet::default();
+        for key_value in split_top_level(inner) {
+            let (key, value) = key_value
+                .split_once('=')
+                .ok_or_else(|| Error::ParseImageAttr(s.to_owned()))?;
+            match key {
+                "x" => x = Some(ImageAttrRange::unmarshal(value)?),
+                "y" => y = Some(ImageAttrRange::unmarshal(value)?),
+                "sar" => set.sar = Some(value.to_owned()),
+                "par" => set.par = Some(value.to_owned()),
+                "q" => {
+                    set.q = Some(
+                        value
+                            .parse::<f32>()
+                            .map_err(|_| Error::ParseImageAttr(s.to_owned()))?,
+                    )
+                }
+                _ => return Err(Error::ParseImageAttr(s.to_owned())),
+            }
+        }
+
+        match (x, y) {
+            (Some(x), Some(y)) => {
+                set.x = x;
+                set.y = y;
+                Ok(set)
+            }
+            _ => Err(Error::ParseImageAttr(format!(
+                "{s} -- x and y are required"
+            ))),
+        }
+    }
+}
+
+impl fmt::Display for ImageAttrSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[x={},y={}", self.x, self.y)?;
+        if let Some(sar) = &self.sar {
+            write!(f, ",sar={sar}")?;
+        }
+        if let Some(par) = &self.par {
+            write!(f, ",par={par}")?;
+        }
+        if let Some(q) = &self.q {
+            write!(f, ",q={q}")?;
+        }
+        write!(f, "]")
+    }
+}
+
+impl fmt::Display for ImageAttr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.payload_type {
+            Some(payload_type) => write!(f, "{payload_type}")?,
+            None => write!(f, "{IMAGE_ATTR_WILDCARD}")?,
+        }
+
+        for (direction, sets) in [(IMAGE_ATTR_SEND, &self.send), (IMAGE_ATTR_RECV, &self.recv)] {
+            if let Some(sets) = sets {
+                write!(f, " {direction}")?;
+                if sets.is_empty() {
+                    write!(f, " {IMAGE_ATTR_WILDCARD}")?;
+                }
+                for set in sets {
+                    write!(f, " {set}")?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ImageAttr {
+    /// converts this object to an Attribute
+    pub fn convert(&self) -> Attribute {
+        Attribute {
+            key: "imageattr".to_string(),
+            value: Some(self.to_string()),
+        }
+    }
+
+    /// unmarshal creates an ImageAttr from a string
+    pub fn unmarshal<R: io::BufRead>(reader: &mut R) -> Result<Self> {
+        let mut line = String::new();
+        reader.read_line(&mut line)?;
+        let parts: Vec<&str> = line.trim().splitn(2, ':').collect();
+        if parts.len() != 2 {
+            return Err(Error::ParseImageAttr(line));
+        }
+
+        let mut fields = parts[1].split_whitespace();
+        let payload_type = match fields.next() {
+            Some(IMAGE_ATTR_WILDCARD) => None,
+            Some(payload_type) => Some(payload_type.parse::<u8>()?),
+            None => return Err(Error::ParseImageAttr(line)),
+        };
+
+        let mut image_attr = ImageAttr {
+            payload_type,
+            ..Default::default()
+        };
+        // the direction whose sets the fields are added to
+        let mut direction = None;
+        for field in fields {
+            if field == IMAGE_ATTR_SEND || field == IMAGE_ATTR_RECV {
+                let sets = image_attr.sets_mut(field);
+                if sets.is_some() {
+                    return Err(Error::ParseImageAttr(line));
+                }
+                *sets = Some(vec![]);
+                direction = Some(field);
+                continue;
+            }
+
+            let Some(sets) = direction.and_then(|d| image_attr.sets_mut(d).as_mut()) else {
+                return Err(Error::ParseImageAttr(line));
+            };
+            if field == IMAGE_ATTR_WILDCARD {
+                if !sets.is_empty() {
+                    return Err(Error::ParseImageAttr(line));
+                }
+                direction = None;
+            } else {
+                sets.push(ImageAttrSet::unmarshal(field)?);
+            }
+        }
+
+        if image_attr.send.is_none() && image_attr.recv.is_none() {
+            return Err(Error::ParseImageAttr(format!(
+                "{} -- send or recv is required",
+                line.trim()
+            )));
+        }
+
+        Ok(image_attr)
+    }
+
+    fn sets_mut(&mut self, direction: &str) -> &mut Option<Vec<ImageAttrSet>> {
+        if direction == IMAGE_ATTR_SEND {
+            &mut self.send
+        } else {
+            &mut self.recv
+        }
+    }
+
+    /// marshal creates a string from an ImageAttr
+    pub fn marshal(&self) -> String {
+        "imageattr:".to_string() + self.to_string().as_str()
+    }
+}
+
+/// split_top_level splits s on the commas that are not within brackets
+fn split_top_level(s: &str) -> Vec<&str> {
+    let mut parts = vec![];
+    let mut depth = 0;
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        match c {
+            '[' => depth += 1,
+            ']' => depth -= 1,
+            ',' if depth == 0 => {
+                parts.push(&s[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&s[start..]);
+    parts
+}
@@ -111,21 +111,34 @@ impl fmt::Display for Codec {
     }
 }
 
+/// split_codec_attribute splits a `<key>:<payload type> <value>` codec attribute into the
+/// payload type and the value, tolerating extra whitespace around them
+fn split_codec_attribute(attr: &str) -> Result<(u8, &str)> {
+    let (_, rest) = attr.split_once(':').ok_or(Error::MissingColon)?;
+    let (payload_type, value) = rest
+        .trim()
+        .split_once(char::is_whitespace)
+        .ok_or(Error::MissingWhitespace)?;
+
+    Ok((payload_type.parse::<u8>()?, value.trim()))
+}
+
+/// codec_attribute_payload_type returns the payload type a codec attribute is about, if it
+/// can be told even though the attribute is malformed
+fn codec_attribute_payload_type(attr: &str) -> Option<u8> {
+    let (_, rest) = attr.split_once(':')?;
+    rest.split_whitespace().next()?.parse::<u8>().ok()
+}
+
 pub(crate) fn parse_rtpmap(rtpmap: &str) -> Result<Codec> {
     // a=rtpmap:<payload type> <encoding name>/<clock rate>[/<encoding parameters>]
-    let split: Vec<&str> = rtpmap.split_whitespace().collect();
-    if split.len() != 2 {
-        return Err(Error::MissingWhitespace);
-    }
-
-    let pt_split: Vec<&str> = split[0].split(':').collect();
-    if pt_split.len() != 2 {
-        return Err(Error::MissingColon);
-    }
-    let payload_type = pt_split[1].parse::<u8>()?;
+    let (payload_type, value) = split_codec_attribute(rtpmap)?;
 
-    let split: Vec<&str> = split[1].split('/').collect();
+    let split: Vec<&str> = value.split('/').map(str::trim).collect();
     let name = split[0].to_string();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        return Err(Error::SdpInvalidValue(value.to_string()));
+    }
     let parts = split.len();
     let clock_rate = if parts > 1 {
         split[1].parse::<u32>()?
@@ -149,41 +162,45 @@ pub(crate) fn parse_rtpmap(rtpmap: &str) -> Result<Codec> {
 
 pub(crate) fn parse_fmtp(fmtp: &str) -> Result<Codec> {
     // a=fmtp:<format> <format specific parameters>
-    let split: Vec<&str> = fmtp.split_whitespace().collect();
-    if split.len() != 2 {
-        return Err(Error::MissingWhitespace);
-    }
-
-    let fmtp = split[1].to_string();
-
-    let split: Vec<&str> = split[0].split(':').collect();
-    if split.len() != 2 {
-        return Err(Error::MissingColon);
-    }
-    let payload_type = split[1].parse::<u8>()?;
+    let (payload_type, value) = split_codec_attribute(fmtp)?;
 
     Ok(Codec {
         payload_type,
-        fmtp,
+        fmtp: normalize_fmtp(value),
         ..Default::default()
     })
 }
 
-pub(crate) fn parse_rtcp_fb(rtcp_fb: &str) -> Result<Codec> {
-    // a=ftcp-fb:<payload type> <RTCP feedback type> [<RTCP feedback parameter>]
-    let split: Vec<&str> = rtcp_fb.splitn(2, ' ').collect();
-    if split.len() != 2 {
-        return Err(Error::MissingWhitespace);
+/// normalize_fmtp drops the whitespace and the empty parameters around the `;` separators,
+/// and keeps the last value of a repeated parameter, e.g. `a=1; b=2;a=3;` becomes `a=3;b=2`
+fn normalize_fmtp(fmtp: &str) -> String {
+    let mut params: Vec<(&str, String)> = vec![];
+    for param in fmtp.split(';').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, param) = match param.split_once('=') {
+            Some((key, value)) => (key.trim(), format!("{}={}", key.trim(), value.trim())),
+            None => (param, param.to_string()),
+        };
+        if let Some(existing) = params.iter_mut().find(|(k, _)| k.eq_ignore_ascii_case(key)) {
+            existing.1 = param;
+        } else {
+            params.push((key, param));
+        }
     }
 
-    let pt_split: Vec<&str> = split[0].split(':').collect();
-    if pt_split.len() != 2 {
-        return Err(Error::MissingColon);
-    }
+    params
+        .into_iter()
+        .map(|(_, param)| param)
+        .collect::<Vec<String>>()
+        .join(";")
+}
+
+pub(crate) fn parse_rtcp_fb(rtcp_fb: &str) -> Result<Codec> {
+    // a=ftcp-fb:<payload type> <RTCP feedback type> [<RTCP feedback parameter>]
+    let (payload_type, value) = split_codec_attribute(rtcp_fb)?;
 
     Ok(Codec {
-        payload_type: pt_split[1].parse::<u8>()?,
-        rtcp_feedback: vec![split[1].to_string()],
+        payload_type,
+        rtcp_feedback: vec![value.split_whitespace().collect::<Vec<&str>>().join(" ")],
         ..Default::default()
     })
 }
@@ -211,6 +228,36 @@ pub(crate) fn merge_codecs(mut codec: Codec, codecs: &mut HashMap<u8, Codec>) {
     }
 }
 
+/// merge_codec_attribute merges the codec described by an rtpmap, fmtp or
+/// rtcp-fb attribute into codecs, other attributes are ignored. A malformed
+/// attribute isn't merged, and the error names it.
+pub(crate) fn merge_codec_attribute(attr: &str, codecs: &mut HashMap<u8, Codec>) -> Result<()> {
+    let codec = if attr.starts_with("rtpmap:") {
+        parse_rtpmap(attr)
+    } else if attr.starts_with("fmtp:") {
+        parse_fmtp(attr)
+    } else if attr.starts_with("rtcp-fb:*") {
+        // Wildcard feedback applies to every payload type, which codecs doesn't track
+        return Ok(());
+    } else if attr.starts_with("rtcp-fb:") {
+        parse_rtcp_fb(attr)
+    } else {
+        return Ok(());
+    };
+
+    match codec {
+        Ok(codec) => {
+            merge_codecs(codec, codecs);
+            Ok(())
+        }
+        Err(err) => Err(Error::InvalidCodecAttribute {
+            attribute: attr.to_string(),
+            payload_type: codec_attribute_payload_type(attr),
+            source: Box::new(err),
+        }),
+    }
+}
+
 fn equivalent_fmtp(want: &str, got: &str) -> bool {
     let mut want_split: Vec<&str> = want.split(';').collect();
     let mut got_split: Vec<&str> = got.split(';').collect();
@@ -149,6 +149,98 @@ fn test_get_codec_for_payload_type() -> Result<()> {
     Ok(())
 }
 
+fn session_description_with_attributes(attributes: &[&str]) -> SessionDescription {
+    SessionDescription {
+        media_descriptions: vec![MediaDescription {
+            attributes: attributes
+                .iter()
+                .map(|a| Attribute::new(a.to_string(), None))
+                .collect(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_get_codec_for_payload_type_tolerates_whitespace() -> Result<()> {
+    let sdp = session_description_with_attributes(&[
+        "rtpmap:96  H264/90000 ",
+        "fmtp:96 profile-level-id=42e01f; packetization-mode = 1;level-asymmetry-allowed=1;",
+        "rtcp-fb:96 nack  pli",
+        "rtcp-fb:* transport-cc",
+        "rtpmap:111 opus/48000/2",
+        "fmtp:111 minptime=10;useinbandfec=0;;useinbandfec=1",
+    ]);
+    assert!(sdp.codec_attribute_errors().is_empty());
+
+    assert_eq!(
+        sdp.get_codec_for_payload_type(96)?,
+        Codec {
+            payload_type: 96,
+            name: "H264".to_string(),
+            clock_rate: 90000,
+            fmtp: "profile-level-id=42e01f;packetization-mode=1;level-asymmetry-allowed=1"
+                .to_string(),
+            rtcp_feedback: vec!["nack pli".to_string()],
+            ..Default::default()
+        }
+    );
+    assert_eq!(
+        sdp.get_codec_for_payload_type(111)?,
+        Codec {
+            payload_type: 111,
+            name: "opus".to_string(),
+            clock_rate: 48000,
+            encoding_parameters: "2".to_string(),
+            fmtp: "minptime=10;useinbandfec=1".to_string(),
+            ..Default::default()
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_codec_attribute_errors() {
+    let sdp = session_description_with_attributes(&[
+        "rtpmap:8 PCMA/8000Hz",
+        "fmtp:9",
+        "rtpmap:x VP8/90000",
+        "rtpmap:0 PCMU/8000",
+    ]);
+
+    let errors = sdp.codec_attribute_errors();
+    assert_eq!(errors.len(), 3);
+    assert_eq!(
+        errors[0].to_string(),
+        "invalid codec attribute \"a=rtpmap:8 PCMA/8000Hz\": parse int: invalid digit found in string"
+    );
+    assert!(matches!(
+        &errors[1],
+        Error::InvalidCodecAttribute { attribute, payload_type: Some(9), source }
+            if attribute == "fmtp:9" && **source == Error::MissingWhitespace
+    ));
+    assert!(matches!(
+        &errors[2],
+        Error::InvalidCodecAttribute {
+            payload_type: None,
+            ..
+        }
+    ));
+
+    // The broken line is reported instead of a missing payload type
+    assert_eq!(
+        sdp.get_codec_for_payload_type(8).unwrap_err().to_string(),
+        errors[0].to_string()
+    );
+    assert_eq!(
+        sdp.get_codec_for_payload_type(7),
+        Err(Error::PayloadTypeNotFound)
+    );
+    assert!(sdp.get_codec_for_payload_type(0).is_ok());
+}
+
 #[test]
 fn test_new_session_id() -> Result<()> {
     let mut min = 0x7FFFFFFFFFFFFFFFu64;
@@ -19,7 +19,9 @@ pub struct SessionKeys {
 /// You can provide either a KeyingMaterialExporter to export keys
 /// or directly pass the keys themselves.
 /// After a Config is passed to a session it must not be modified.
+/// It is created with Config::default, so that adding fields isn't a breaking change.
 #[derive(Default)]
+#[non_exhaustive]
 pub struct Config {
     pub keys: SessionKeys,
     pub profile: ProtectionProfile,
@@ -32,6 +34,11 @@ pub struct Config {
 
     pub local_rtcp_options: Option<ContextOption>,
     pub remote_rtcp_options: Option<ContextOption>,
+
+    /// MKIs identifying the local/remote master keys, carried on every packet so
+    /// that keys can be rotated during the session. None when packets carry no MKI.
+    pub local_mki: Option<Vec<u8>>,
+    pub remote_mki: Option<Vec<u8>>,
 }
 
 impl Config {
@@ -5,8 +5,10 @@ mod srtcp_test;
 #[cfg(test)]
 mod srtp_test;
 
+use std::borrow::Cow;
 use std::collections::HashMap;
 
+use bytes::{Bytes, BytesMut};
 use util::replay_detector::*;
 
 use crate::cipher::cipher_aead_aes_gcm::*;
@@ -22,6 +24,7 @@ pub mod srtp;
 const MAX_ROC: u32 = u32::MAX;
 const SEQ_NUM_MEDIAN: u16 = 1 << 15;
 const SEQ_NUM_MAX: u16 = u16::MAX;
+const MAX_MKI_LEN: usize = 128;
 
 /// Encrypt/Decrypt state for a single SRTP SSRC
 #[derive(Default)]
@@ -90,8 +93,17 @@ impl SrtpSsrcState {
 /// Context represents a SRTP cryptographic context
 /// Context can only be used for one-way operations
 /// it must either used ONLY for encryption or ONLY for decryption
+///
+/// A context created with an MKI (Master Key Identifier, RFC 3711 S3.1) carries it on every
+/// packet, so that several master keys can be active at once and rotated without rekeying
+/// the whole session.
 pub struct Context {
+    profile: ProtectionProfile,
     cipher: Box<dyn Cipher + Send>,
+    /// mki identifies the master key of cipher, empty when the context does not use MKIs
+    mki: Vec<u8>,
+    /// mki_ciphers holds the other master keys, by MKI
+    mki_ciphers: HashMap<Vec<u8>, Box<dyn Cipher + Send>>,
 
     srtp_ssrc_states: HashMap<u32, SrtpSsrcState>,
     srtcp_ssrc_states: HashMap<u32, SrtcpSsrcState>,
@@ -109,24 +121,31 @@ impl Context {
         srtp_ctx_opt: Option<ContextOption>,
         srtcp_ctx_opt: Option<ContextOption>,
     ) -> Result<Context> {
-        let key_len = profile.key_len();
-        let salt_len = profile.salt_len();
+        Context::new_with_mki(
+            master_key,
+            master_salt,
+            profile,
+            &[],
+            srtp_ctx_opt,
+            srtcp_ctx_opt,
+        )
+    }
 
-        if master_key.len() != key_len {
-            return Err(Error::SrtpMasterKeyLength(key_len, master_key.len()));
-        } else if master_salt.len() != salt_len {
-            return Err(Error::SrtpSaltLength(salt_len, master_salt.len()));
+    /// new_with_mki creates a new SRTP Context whose packets carry the MKI of their master key.
+    /// All the MKIs of the context have the length of mki, an empty mki disables them.
+    pub fn new_with_mki(
+        master_key: &[u8],
+        master_salt: &[u8],
+        profile: ProtectionProfile,
+        mki: &[u8],
+        srtp_ctx_opt: Option<ContextOption>,
+        srtcp_ctx_opt: Option<ContextOption>,
+    ) -> Result<Context> {
+        if mki.len() > MAX_MKI_LEN {
+            return Err(Error::SrtpMkiLength(MAX_MKI_LEN, mki.len()));
         }
 
-        let cipher: Box<dyn Cipher + Send> = match profile {
-            ProtectionProfile::Aes128CmHmacSha1_32 | ProtectionProfile::Aes128CmHmacSha1_80 => {
-                Box::new(CipherAesCmHmacSha1::new(profile, master_key, master_salt)?)
-            }
-
-            ProtectionProfile::AeadAes128Gcm | ProtectionProfile::AeadAes256Gcm => {
-                Box::new(CipherAeadAesGcm::new(profile, master_key, master_salt)?)
-            }
-        };
+        let cipher = Context::new_cipher(master_key, master_salt, profile)?;
 
         let srtp_ctx_opt = if let Some(ctx_opt) = srtp_ctx_opt {
             ctx_opt
@@ -141,7 +160,10 @@ impl Context {
         };
 
         Ok(Context {
+            profile,
             cipher,
+            mki: mki.to_vec(),
+            mki_ciphers: HashMap::new(),
             srtp_ssrc_states: HashMap::new(),
             srtcp_ssrc_states: HashMap::new(),
             new_srtp_replay_detector: srtp_ctx_opt,
@@ -149,6 +171,143 @@ impl Context {
         })
     }
 
+    fn new_cipher(
+        master_key: &[u8],
+        master_salt: &[u8],
+        profile: ProtectionProfile,
+    ) -> Result<Box<dyn Cipher + Send>> {
+        let key_len = profile.key_len();
+        let salt_len = profile.salt_len();
+
+        if master_key.len() != key_len {
+            return Err(Error::SrtpMasterKeyLength(key_len, master_key.len()));
+        } else if master_salt.len() != salt_len {
+            return Err(Error::SrtpSaltLength(salt_len, master_salt.len()));
+        }
+
+        Ok(match profile {
+            ProtectionProfile::Aes128CmHmacSha1_32 | ProtectionProfile::Aes128CmHmacSha1_80 => {
+                Box::new(CipherAesCmHmacSha1::new(profile, master_key, master_salt)?)
+            }
+
+            ProtectionProfile::AeadAes128Gcm | ProtectionProfile::AeadAes256Gcm => {
+                Box::new(CipherAeadAesGcm::new(profile, master_key, master_salt)?)
+            }
+        })
+    }
+
+    /// mki returns the MKI of the master key packets are encrypted with, empty when the
+    /// context does not use MKIs.
+    pub fn mki(&self) -> &[u8] {
+        &self.mki
+    }
+
+    /// add_master_key adds a master key identified by mki. Packets carrying mki are decrypted
+    /// with it, and set_mki makes it the key packets are encrypted with.
+    pub fn add_master_key(
+        &mut self,
+        mki: &[u8],
+        master_key: &[u8],
+        master_salt: &[u8],
+    ) -> Result<()> {
+        if self.mki.is_empty() {
+            return Err(Error::ErrMkiDisabled);
+        } else if mki.len() != self.mki.len() {
+            return Err(Error::SrtpMkiLength(self.mki.len(), mki.len()));
+        } else if mki == self.mki || self.mki_ciphers.contains_key(mki) {
+            return Err(Error::SrtpMkiExists(mki.to_vec()));
+        }
+
+        let cipher = Context::new_cipher(master_key, master_salt, self.profile)?;
+        self.mki_ciphers.insert(mki.to_vec(), cipher);
+
+        Ok(())
+    }
+
+    /// set_mki switches the master key packets are encrypted with to the one identified by mki.
+    /// The previous key is kept, so that packets still in flight can be decrypted.
+    pub fn set_mki(&mut self, mki: &[u8]) -> Result<()> {
+        if mki == self.mki {
+            return Ok(());
+        }
+
+        let cipher = self
+            .mki_ciphers
+            .remove(mki)
+            .ok_or_else(|| Error::SrtpMkiNotFound(mki.to_vec()))?;
+        let previous = std::mem::replace(&mut self.cipher, cipher);
+        self.mki_ciphers
+            .insert(std::mem::replace(&mut self.mki, mki.to_vec()), previous);
+
+        Ok(())
+    }
+
+    /// remove_master_key retires the master key identified by mki, packets carrying it are
+    /// rejected from then on. The key packets are encrypted with can't be removed.
+    pub fn remove_master_key(&mut self, mki: &[u8]) -> Result<()> {
+        if mki == self.mki {
+            return Err(Error::SrtpMkiInUse(mki.to_vec()));
+        }
+
+        self.mki_ciphers
+            .remove(mki)
+            .map(|_| ())
+            .ok_or_else(|| Error::SrtpMkiNotFound(mki.to_vec()))
+    }
+
+    /// remove_decrypt_master_key retires the master key identified by mki from a context
+    /// only used for decryption, where the key packets are encrypted with does not matter.
+    /// The last key can't be removed.
+    pub(crate) fn remove_decrypt_master_key(&mut self, mki: &[u8]) -> Result<()> {
+        if mki == self.mki {
+            let Some(other) = self.mki_ciphers.keys().next().cloned() else {
+                return Err(Error::SrtpMkiInUse(mki.to_vec()));
+            };
+            self.set_mki(&other)?;
+        }
+        self.remove_master_key(mki)
+    }
+
+    /// get_cipher returns the cipher of the master key identified by mki.
+    fn get_cipher(&mut self, mki: &[u8]) -> Result<&mut Box<dyn Cipher + Send>> {
+        if mki == self.mki {
+            Ok(&mut self.cipher)
+        } else {
+            self.mki_ciphers
+                .get_mut(mki)
+                .ok_or_else(|| Error::SrtpMkiNotFound(mki.to_vec()))
+        }
+    }
+
+    /// insert_mki inserts the MKI in a protected packet, in front of its auth tag.
+    fn insert_mki(&self, packet: Bytes, auth_tag_len: usize) -> Bytes {
+        if self.mki.is_empty() {
+            return packet;
+        }
+
+        let tail_offset = packet.len() - auth_tag_len;
+        let mut out = BytesMut::with_capacity(packet.len() + self.mki.len());
+        out.extend_from_slice(&packet[..tail_offset]);
+        out.extend_from_slice(&self.mki);
+        out.extend_from_slice(&packet[tail_offset..]);
+        out.freeze()
+    }
+
+    /// remove_mki splits the MKI, in front of the auth tag, off a protected packet at least
+    /// auth_tag_len plus the MKI length long.
+    fn remove_mki<'a>(&self, packet: &'a [u8], auth_tag_len: usize) -> (Vec<u8>, Cow<'a, [u8]>) {
+        if self.mki.is_empty() {
+            return (vec![], Cow::Borrowed(packet));
+        }
+
+        let tail_offset = packet.len() - auth_tag_len;
+        let mki_offset = tail_offset - self.mki.len();
+        let mut out = Vec::with_capacity(packet.len() - self.mki.len());
+        out.extend_from_slice(&packet[..mki_offset]);
+        out.extend_from_slice(&packet[tail_offset..]);
+        (packet[mki_offset..tail_offset].to_vec(), Cow::Owned(out))
+    }
+
     fn get_srtp_ssrc_state(&mut self, ssrc: u32) -> &mut SrtpSsrcState {
         let s = SrtpSsrcState {
             ssrc,
@@ -10,7 +10,13 @@ impl Context {
         let mut buf = encrypted;
         rtcp::header::Header::unmarshal(&mut buf)?;
 
-        let index = self.cipher.get_rtcp_index(encrypted);
+        let auth_tag_len = self.cipher.rtcp_auth_tag_len();
+        if encrypted.len() < auth_tag_len + self.mki.len() {
+            return Err(Error::ErrTooShortRtcp);
+        }
+        let (mki, encrypted) = self.remove_mki(encrypted, auth_tag_len);
+
+        let index = self.cipher.get_rtcp_index(&encrypted);
         let ssrc = u32::from_be_bytes([encrypted[4], encrypted[5], encrypted[6], encrypted[7]]);
 
         if let Some(replay_detector) = &mut self.get_srtcp_ssrc_state(ssrc).replay_detector {
@@ -19,7 +25,9 @@ impl Context {
             }
         }
 
-        let dst = self.cipher.decrypt_rtcp(encrypted, index, ssrc)?;
+        let dst = self
+            .get_cipher(&mki)?
+            .decrypt_rtcp(&encrypted, index, ssrc)?;
 
         if let Some(replay_detector) = &mut self.get_srtcp_ssrc_state(ssrc).replay_detector {
             replay_detector.accept();
@@ -49,6 +57,7 @@ impl Context {
             state.srtcp_index
         };
 
-        self.cipher.encrypt_rtcp(decrypted, index, ssrc)
+        let dst = self.cipher.encrypt_rtcp(decrypted, index, ssrc)?;
+        Ok(self.insert_mki(dst, self.cipher.rtcp_auth_tag_len()))
     }
 }
@@ -294,3 +294,36 @@ fn test_encrypt_invalid_srtcp() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_rtcp_mki_key_rotation() -> Result<()> {
+    let profile = ProtectionProfile::AeadAes128Gcm;
+    let rotated_key = [0x3e; 16];
+    let rotated_salt = [0x7c; 12];
+
+    let new_context =
+        || Context::new_with_mki(&[0xfd; 16], &[0xa9; 12], profile, &[0x01], None, None);
+    let mut encrypt_context = new_context()?;
+    let mut decrypt_context = new_context()?;
+    for context in [&mut encrypt_context, &mut decrypt_context] {
+        context.add_master_key(&[0x02], &rotated_key, &rotated_salt)?;
+    }
+
+    for (i, test_case) in RTCP_TEST_CASES.iter().enumerate() {
+        if i == 1 {
+            encrypt_context.set_mki(&[0x02])?;
+        }
+
+        let encrypted = encrypt_context.encrypt_rtcp(&test_case.decrypted)?;
+        // With AEAD the MKI follows the SRTCP index
+        assert_eq!(
+            encrypted[encrypted.len() - 1],
+            if i == 0 { 0x01 } else { 0x02 }
+        );
+
+        let decrypted = decrypt_context.decrypt_rtcp(&encrypted)?;
+        assert_eq!(decrypted, test_case.decrypted);
+    }
+
+    Ok(())
+}
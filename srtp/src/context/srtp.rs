@@ -11,7 +11,7 @@ impl Context {
         header: &rtp::header::Header,
     ) -> Result<Bytes> {
         let auth_tag_len = self.cipher.rtp_auth_tag_len();
-        if encrypted.len() < header.marshal_size() + auth_tag_len {
+        if encrypted.len() < header.marshal_size() + auth_tag_len + self.mki.len() {
             return Err(Error::ErrTooShortRtp);
         }
 
@@ -26,7 +26,10 @@ impl Context {
             }
         }
 
-        let dst = self.cipher.decrypt_rtp(encrypted, header, roc)?;
+        let (mki, encrypted) = self.remove_mki(encrypted, auth_tag_len);
+        let dst = self
+            .get_cipher(&mki)?
+            .decrypt_rtp(&encrypted, header, roc)?;
         {
             let state = self.get_srtp_ssrc_state(header.ssrc);
             if let Some(replay_detector) = &mut state.replay_detector {
@@ -45,6 +48,30 @@ impl Context {
         self.decrypt_rtp_with_header(encrypted, &header)
     }
 
+    /// decrypt_stream decrypts a capture of SRTP packets, in the order they were received,
+    /// e.g. read back from a file with the keys exported from the DTLS session. The rollover
+    /// counter of each SSRC starts at initial_roc with its first packet in the capture, which
+    /// may have any sequence number, and is then tracked across the whole capture. A packet
+    /// failing to decrypt yields an error and doesn't stop the rest of the capture.
+    pub fn decrypt_stream<'a, I>(
+        &'a mut self,
+        initial_roc: u32,
+        packets: I,
+    ) -> impl Iterator<Item = Result<Bytes>> + 'a
+    where
+        I: IntoIterator<Item = Bytes>,
+        I::IntoIter: 'a,
+    {
+        packets.into_iter().map(move |encrypted| {
+            let mut buf = &encrypted[..];
+            let header = rtp::header::Header::unmarshal(&mut buf)?;
+            if !self.srtp_ssrc_states.contains_key(&header.ssrc) {
+                self.set_roc(header.ssrc, initial_roc);
+            }
+            self.decrypt_rtp_with_header(&encrypted, &header)
+        })
+    }
+
     pub fn encrypt_rtp_with_header(
         &mut self,
         payload: &[u8],
@@ -62,6 +89,7 @@ impl Context {
         }
 
         let dst = self.cipher.encrypt_rtp(payload, header, roc)?;
+        let dst = self.insert_mki(dst, self.cipher.rtp_auth_tag_len());
 
         self.get_srtp_ssrc_state(header.ssrc)
             .update_rollover_count(header.sequence_number, diff);
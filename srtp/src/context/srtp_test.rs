@@ -248,6 +248,62 @@ fn test_rtp_burst_loss_with_set_roc() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_rtp_decrypt_stream() -> Result<()> {
+    let mut encrypt_context = build_test_context()?;
+    encrypt_context.set_roc(1, 3);
+    encrypt_context.set_roc(2, 3);
+
+    // A capture starting close to a rollover and spanning several of them, with two SSRCs
+    let mut raws = vec![];
+    let mut capture = vec![];
+    for i in (0xfe00u32..0x4_0000u32).step_by(0x80) {
+        for ssrc in [1, 2] {
+            let pkt = rtp::packet::Packet {
+                header: rtp::header::Header {
+                    version: 2,
+                    ssrc,
+                    sequence_number: i as _,
+                    ..Default::default()
+                },
+                payload: vec![(i >> 16) as u8, (i >> 8) as u8, i as u8].into(),
+            };
+            let raw = pkt.marshal()?;
+            capture.push(encrypt_context.encrypt_rtp(&raw)?);
+            raws.push(raw);
+        }
+    }
+    assert_eq!(encrypt_context.get_roc(1), Some(6));
+
+    // A corrupted packet is reported without losing track of the rollovers
+    let corrupted = 100;
+    let mut packet = capture[corrupted].to_vec();
+    let last = packet.len() - 1;
+    packet[last] ^= 0xff;
+    capture[corrupted] = packet.into();
+
+    let mut decrypt_context = build_test_context()?;
+    let decrypted: Vec<_> = decrypt_context.decrypt_stream(3, capture.clone()).collect();
+    assert_eq!(decrypted.len(), raws.len());
+    for (i, (dec, raw)) in decrypted.into_iter().zip(&raws).enumerate() {
+        if i == corrupted {
+            assert!(dec.is_err(), "corrupted packet must fail to decrypt");
+        } else {
+            assert_eq!(&dec?, raw, "packet {i} invalid decryption");
+        }
+    }
+    assert_eq!(decrypt_context.get_roc(1), Some(6));
+    assert_eq!(decrypt_context.get_roc(2), Some(6));
+
+    // The capture can't be decrypted with a wrong initial ROC
+    let mut decrypt_context = build_test_context()?;
+    assert!(decrypt_context
+        .decrypt_stream(2, capture)
+        .all(|dec| dec.is_err()));
+
+    Ok(())
+}
+
 #[test]
 fn test_decrypt_invalid_srtp() -> Result<()> {
     let mut decrypt_context = build_test_context()?;
@@ -262,3 +318,97 @@ fn test_decrypt_invalid_srtp() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_rtp_mki_key_rotation() -> Result<()> {
+    let master_key = [0x0d; 16];
+    let master_salt = [0x62; 14];
+    let rotated_key = [0x3e; 16];
+    let rotated_salt = [0x7c; 14];
+    let auth_tag_len = ProtectionProfile::Aes128CmHmacSha1_80.rtp_auth_tag_len();
+
+    let new_context = |mki: &[u8]| {
+        Context::new_with_mki(
+            &master_key,
+            &master_salt,
+            ProtectionProfile::Aes128CmHmacSha1_80,
+            mki,
+            None,
+            None,
+        )
+    };
+    let mut encrypt_context = new_context(&[0x00, 0x01])?;
+    let mut decrypt_context = new_context(&[0x00, 0x01])?;
+    let mut plain_context = new_context(&[])?;
+    for context in [&mut encrypt_context, &mut decrypt_context] {
+        context.add_master_key(&[0x00, 0x02], &rotated_key, &rotated_salt)?;
+    }
+
+    for sequence_number in 0..10u16 {
+        if sequence_number == 5 {
+            encrypt_context.set_mki(&[0x00, 0x02])?;
+        }
+        let pkt = rtp::packet::Packet {
+            header: rtp::header::Header {
+                sequence_number,
+                ..Default::default()
+            },
+            payload: RTP_TEST_CASE_DECRYPTED.clone(),
+        };
+        let pkt_raw = pkt.marshal()?;
+
+        let encrypted = encrypt_context.encrypt_rtp(&pkt_raw)?;
+        let mki_offset = encrypted.len() - auth_tag_len - 2;
+        let expected_mki: &[u8] = if sequence_number < 5 {
+            &[0x00, 0x01]
+        } else {
+            &[0x00, 0x02]
+        };
+        assert_eq!(&encrypted[mki_offset..mki_offset + 2], expected_mki);
+
+        // The MKI sits between the payload and the auth tag, which does not cover it
+        if sequence_number < 5 {
+            let plain = plain_context.encrypt_rtp(&pkt_raw)?;
+            assert_eq!(&encrypted[..mki_offset], &plain[..mki_offset]);
+            assert_eq!(&encrypted[mki_offset + 2..], &plain[mki_offset..]);
+        }
+
+        let decrypted = decrypt_context.decrypt_rtp(&encrypted)?;
+        assert_eq!(
+            decrypted, pkt_raw,
+            "RTP packet with SeqNum invalid decryption"
+        );
+    }
+
+    // A retired key no longer decrypts packets
+    let mut stale_context = new_context(&[0x00, 0x01])?;
+    assert_eq!(
+        decrypt_context.remove_master_key(&[0x00, 0x01]),
+        Err(Error::SrtpMkiInUse(vec![0x00, 0x01]))
+    );
+    decrypt_context.set_mki(&[0x00, 0x02])?;
+    decrypt_context.remove_master_key(&[0x00, 0x01])?;
+    let pkt = rtp::packet::Packet {
+        header: rtp::header::Header {
+            sequence_number: 10,
+            ..Default::default()
+        },
+        payload: RTP_TEST_CASE_DECRYPTED.clone(),
+    };
+    let encrypted = stale_context.encrypt_rtp(&pkt.marshal()?)?;
+    assert_eq!(
+        decrypt_context.decrypt_rtp(&encrypted),
+        Err(Error::SrtpMkiNotFound(vec![0x00, 0x01]))
+    );
+
+    assert_eq!(
+        encrypt_context.add_master_key(&[0x02], &rotated_key, &rotated_salt),
+        Err(Error::SrtpMkiLength(2, 1))
+    );
+    assert_eq!(
+        plain_context.add_master_key(&[0x00, 0x02], &rotated_key, &rotated_salt),
+        Err(Error::ErrMkiDisabled)
+    );
+
+    Ok(())
+}
@@ -36,6 +36,8 @@ pub enum Error {
     ErrStartedChannelUsedIncorrectly,
     #[error("exceeded the maximum number of packets")]
     ErrExceededMaxPackets,
+    #[error("context does not use MKI")]
+    ErrMkiDisabled,
 
     #[error("stream has not been inited, unable to close")]
     ErrStreamNotInited,
@@ -52,6 +54,14 @@ pub enum Error {
     SrtpMasterKeyLength(usize, usize),
     #[error("SRTP Salt must be len {0}, got {1}")]
     SrtpSaltLength(usize, usize),
+    #[error("SRTP MKI must be len {0}, got {1}")]
+    SrtpMkiLength(usize, usize),
+    #[error("no SRTP master key with MKI {0:?}")]
+    SrtpMkiNotFound(Vec<u8>),
+    #[error("SRTP master key with MKI {0:?} exists")]
+    SrtpMkiExists(Vec<u8>),
+    #[error("SRTP master key with MKI {0:?} is in use")]
+    SrtpMkiInUse(Vec<u8>),
     #[error("SyntaxError: {0}")]
     ExtMapParse(String),
     #[error("srtp ssrc={0} index={1}: duplicated")]
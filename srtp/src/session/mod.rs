@@ -27,6 +27,7 @@ const DEFAULT_SESSION_SRTCP_REPLAY_PROTECTION_WINDOW: usize = 64;
 /// instead of making everyone re-implement
 pub struct Session {
     local_context: Arc<Mutex<Context>>,
+    remote_context: Arc<Mutex<Context>>,
     streams_map: Arc<Mutex<HashMap<u32, Arc<Stream>>>>,
     new_stream_rx: Arc<Mutex<mpsc::Receiver<Arc<Stream>>>>,
     close_stream_tx: mpsc::Sender<u32>,
@@ -41,18 +42,20 @@ impl Session {
         config: Config,
         is_rtp: bool,
     ) -> Result<Self> {
-        let local_context = Context::new(
+        let local_context = Context::new_with_mki(
             &config.keys.local_master_key,
             &config.keys.local_master_salt,
             config.profile,
+            config.local_mki.as_deref().unwrap_or_default(),
             config.local_rtp_options,
             config.local_rtcp_options,
         )?;
 
-        let mut remote_context = Context::new(
+        let remote_context = Context::new_with_mki(
             &config.keys.remote_master_key,
             &config.keys.remote_master_salt,
             config.profile,
+            config.remote_mki.as_deref().unwrap_or_default(),
             if config.remote_rtp_options.is_none() {
                 Some(srtp_replay_protection(
                     DEFAULT_SESSION_SRTP_REPLAY_PROTECTION_WINDOW,
@@ -69,6 +72,8 @@ impl Session {
             },
         )?;
 
+        let remote_context = Arc::new(Mutex::new(remote_context));
+        let cloned_remote_context = Arc::clone(&remote_context);
         let streams_map = Arc::new(Mutex::new(HashMap::new()));
         let (mut new_stream_tx, new_stream_rx) = mpsc::channel(8);
         let (close_stream_tx, mut close_stream_rx) = mpsc::channel(8);
@@ -88,7 +93,7 @@ impl Session {
                     &cloned_streams_map,
                     &cloned_close_stream_tx,
                     &mut new_stream_tx,
-                    &mut remote_context,
+                    &cloned_remote_context,
                     is_rtp,
                 );
                 let close_stream = close_stream_rx.recv();
@@ -109,6 +114,7 @@ impl Session {
 
         Ok(Session {
             local_context: Arc::new(Mutex::new(local_context)),
+            remote_context,
             streams_map,
             new_stream_rx: Arc::new(Mutex::new(new_stream_rx)),
             close_stream_tx,
@@ -129,7 +135,7 @@ impl Session {
         streams_map: &Arc<Mutex<HashMap<u32, Arc<Stream>>>>,
         close_stream_tx: &mpsc::Sender<u32>,
         new_stream_tx: &mut mpsc::Sender<Arc<Stream>>,
-        remote_context: &mut Context,
+        remote_context: &Arc<Mutex<Context>>,
         is_rtp: bool,
     ) -> Result<()> {
         let n = udp_rx.recv(buf).await?;
@@ -137,11 +143,13 @@ impl Session {
             return Err(Error::SessionEof);
         }
 
+        let mut remote_context = remote_context.lock().await;
         let decrypted = if is_rtp {
             remote_context.decrypt_rtp(&buf[0..n])?
         } else {
             remote_context.decrypt_rtcp(&buf[0..n])?
         };
+        drop(remote_context);
 
         let mut buf = &decrypted[..];
         let ssrcs = if is_rtp {
@@ -209,6 +217,11 @@ impl Session {
         stream
     }
 
+    /// stream_count returns how many SSRCs the session demultiplexes
+    pub async fn stream_count(&self) -> usize {
+        self.streams_map.lock().await.len()
+    }
+
     /// accept returns a stream to handle RTCP for a single SSRC
     pub async fn accept(&self) -> Result<Arc<Stream>> {
         let mut new_stream_rx = self.new_stream_rx.lock().await;
@@ -220,6 +233,51 @@ impl Session {
         }
     }
 
+    /// add_local_master_key adds a local master key identified by mki, to be switched to
+    /// with set_local_mki. The session must have been configured with a local MKI.
+    pub async fn add_local_master_key(
+        &self,
+        mki: &[u8],
+        master_key: &[u8],
+        master_salt: &[u8],
+    ) -> Result<()> {
+        let mut local_context = self.local_context.lock().await;
+        local_context.add_master_key(mki, master_key, master_salt)
+    }
+
+    /// set_local_mki switches the master key packets are written with to the one
+    /// identified by mki.
+    pub async fn set_local_mki(&self, mki: &[u8]) -> Result<()> {
+        let mut local_context = self.local_context.lock().await;
+        local_context.set_mki(mki)
+    }
+
+    /// remove_local_master_key retires the local master key identified by mki.
+    pub async fn remove_local_master_key(&self, mki: &[u8]) -> Result<()> {
+        let mut local_context = self.local_context.lock().await;
+        local_context.remove_master_key(mki)
+    }
+
+    /// add_remote_master_key adds a remote master key identified by mki, the packets
+    /// read carrying mki are decrypted with it. The session must have been configured
+    /// with a remote MKI.
+    pub async fn add_remote_master_key(
+        &self,
+        mki: &[u8],
+        master_key: &[u8],
+        master_salt: &[u8],
+    ) -> Result<()> {
+        let mut remote_context = self.remote_context.lock().await;
+        remote_context.add_master_key(mki, master_key, master_salt)
+    }
+
+    /// remove_remote_master_key retires the remote master key identified by mki, the
+    /// packets read carrying it are dropped from then on.
+    pub async fn remove_remote_master_key(&self, mki: &[u8]) -> Result<()> {
+        let mut remote_context = self.remote_context.lock().await;
+        remote_context.remove_decrypt_master_key(mki)
+    }
+
     pub async fn close(&self) -> Result<()> {
         self.close_session_tx.send(()).await?;
 
@@ -36,6 +36,9 @@ async fn build_session_srtcp_pair() -> Result<(Session, Session)> {
 
         local_rtcp_options: None,
         remote_rtcp_options: None,
+
+        local_mki: None,
+        remote_mki: None,
     };
 
     let cb = Config {
@@ -62,6 +65,9 @@ async fn build_session_srtcp_pair() -> Result<(Session, Session)> {
 
         local_rtcp_options: None,
         remote_rtcp_options: None,
+
+        local_mki: None,
+        remote_mki: None,
     };
 
     let sa = Session::new(Arc::new(ua), ca, false).await?;
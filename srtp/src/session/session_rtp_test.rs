@@ -40,6 +40,9 @@ async fn build_session_srtp_pair() -> Result<(Session, Session)> {
 
         local_rtcp_options: None,
         remote_rtcp_options: None,
+
+        local_mki: None,
+        remote_mki: None,
     };
 
     let cb = Config {
@@ -66,6 +69,9 @@ async fn build_session_srtp_pair() -> Result<(Session, Session)> {
 
         local_rtcp_options: None,
         remote_rtcp_options: None,
+
+        local_mki: None,
+        remote_mki: None,
     };
 
     let sa = Session::new(Arc::new(ua), ca, true).await?;
@@ -306,3 +312,61 @@ async fn test_session_srtp_replay_protection() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_session_srtp_mki_key_rotation() -> Result<()> {
+    let (ua, ub) = util::conn::conn_pipe::pipe();
+
+    let new_config = || Config {
+        profile: ProtectionProfile::Aes128CmHmacSha1_80,
+        keys: SessionKeys {
+            local_master_key: vec![0xE1; 16],
+            local_master_salt: vec![0x0E; 14],
+            remote_master_key: vec![0xE1; 16],
+            remote_master_salt: vec![0x0E; 14],
+        },
+        local_mki: Some(vec![0x01]),
+        remote_mki: Some(vec![0x01]),
+        ..Default::default()
+    };
+    let sa = Session::new(Arc::new(ua), new_config(), true).await?;
+    let sb = Session::new(Arc::new(ub), new_config(), true).await?;
+
+    sa.add_local_master_key(&[0x02], &[0x3E; 16], &[0x7C; 14])
+        .await?;
+    sb.add_remote_master_key(&[0x02], &[0x3E; 16], &[0x7C; 14])
+        .await?;
+
+    let test_payload = Bytes::from_static(&[0x00, 0x01, 0x03, 0x04]);
+    let read_stream = sb.open(TEST_SSRC).await;
+    for sequence_number in 0..10u16 {
+        if sequence_number == 5 {
+            sa.set_local_mki(&[0x02]).await?;
+            sa.remove_local_master_key(&[0x01]).await?;
+        }
+
+        let packet = rtp::packet::Packet {
+            header: rtp::header::Header {
+                ssrc: TEST_SSRC,
+                sequence_number,
+                ..Default::default()
+            },
+            payload: test_payload.clone(),
+        };
+        sa.write_rtp(&packet).await?;
+
+        let read = payload_srtp(&read_stream, RTP_HEADER_SIZE, &test_payload).await?;
+        assert_eq!(read, sequence_number);
+    }
+
+    sb.remove_remote_master_key(&[0x01]).await?;
+    assert_eq!(
+        sb.remove_remote_master_key(&[0x02]).await,
+        Err(Error::SrtpMkiInUse(vec![0x02]))
+    );
+
+    sa.close().await?;
+    sb.close().await?;
+
+    Ok(())
+}
@@ -78,17 +78,12 @@ async fn main() -> Result<(), Error> {
 
     let turn_server_addr = format!("{host}:{port}");
 
-    let cfg = ClientConfig {
-        stun_serv_addr: turn_server_addr.clone(),
-        turn_serv_addr: turn_server_addr,
-        username: cred[0].to_string(),
-        password: cred[1].to_string(),
-        realm: realm.to_string(),
-        software: String::new(),
-        rto_in_ms: 0,
-        conn: Arc::new(conn),
-        vnet: None,
-    };
+    let mut cfg = ClientConfig::new(Arc::new(conn));
+    cfg.stun_serv_addr = turn_server_addr.clone();
+    cfg.turn_serv_addr = turn_server_addr;
+    cfg.username = cred[0].to_string();
+    cfg.password = cred[1].to_string();
+    cfg.realm = realm.to_string();
 
     let client = Client::new(cfg).await?;
 
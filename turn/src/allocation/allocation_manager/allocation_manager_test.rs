@@ -9,7 +9,7 @@ use util::vnet::net::*;
 
 use super::*;
 use crate::auth::{generate_auth_key, AuthHandler};
-use crate::client::{Client, ClientConfig};
+use crate::client::{AllocateRetryPolicy, Client, ClientConfig};
 use crate::error::Result;
 use crate::proto::lifetime::DEFAULT_LIFETIME;
 use crate::relay::relay_none::*;
@@ -441,6 +441,7 @@ async fn create_client(username: String, server_port: u16) -> Result<Client> {
         rto_in_ms: 0,
         conn,
         vnet: None,
+        allocate_retry: AllocateRetryPolicy::default(),
     })
     .await
 }
@@ -89,6 +89,7 @@ async fn test_new_long_term_auth_handler() -> Result<()> {
         rto_in_ms: 0,
         conn,
         vnet: None,
+        allocate_retry: AllocateRetryPolicy::default(),
     })
     .await?;
 
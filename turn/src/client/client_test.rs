@@ -1,4 +1,5 @@
 use std::net::IpAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 use tokio::net::UdpSocket;
 use tokio::time::Duration;
@@ -23,6 +24,7 @@ async fn create_listening_test_client(rto_in_ms: u16) -> Result<Client> {
         rto_in_ms,
         conn: Arc::new(conn),
         vnet: None,
+        allocate_retry: AllocateRetryPolicy::default(),
     })
     .await?;
 
@@ -44,6 +46,7 @@ async fn create_listening_test_client_with_stun_serv() -> Result<Client> {
         rto_in_ms: 0,
         conn: Arc::new(conn),
         vnet: None,
+        allocate_retry: AllocateRetryPolicy::default(),
     })
     .await?;
 
@@ -167,6 +170,7 @@ async fn test_client_nonce_expiration() -> Result<()> {
         rto_in_ms: 0,
         conn,
         vnet: None,
+        allocate_retry: AllocateRetryPolicy::default(),
     })
     .await?;
 
@@ -189,3 +193,126 @@ async fn test_client_nonce_expiration() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_allocate_retry_policy_backoff() {
+    let policy = AllocateRetryPolicy {
+        max_retries: 5,
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_millis(500),
+    };
+
+    let backoffs: Vec<Duration> = (1..=5).map(|retry| policy.backoff(retry)).collect();
+    assert_eq!(
+        backoffs,
+        vec![
+            Duration::from_millis(100),
+            Duration::from_millis(200),
+            Duration::from_millis(400),
+            Duration::from_millis(500),
+            Duration::from_millis(500),
+        ]
+    );
+    assert_eq!(policy.backoff(u16::MAX), Duration::from_millis(500));
+}
+
+/// Runs a fake TURN server answering every Allocate request with the given
+/// error code, returns its port and the number of requests it received.
+async fn run_failing_allocate_server(code: ErrorCode) -> Result<(u16, Arc<AtomicUsize>)> {
+    let conn = UdpSocket::bind("127.0.0.1:0").await?;
+    let port = conn.local_addr()?.port();
+    let requests = Arc::new(AtomicUsize::new(0));
+
+    let requests2 = Arc::clone(&requests);
+    tokio::spawn(async move {
+        let mut buf = vec![0u8; 1500];
+        while let Ok((n, from)) = conn.recv_from(&mut buf).await {
+            let mut req = Message::new();
+            req.raw = buf[..n].to_vec();
+            if req.decode().is_err() {
+                continue;
+            }
+            requests2.fetch_add(1, Ordering::SeqCst);
+
+            let mut res = Message::new();
+            res.build(&[
+                Box::new(req.transaction_id),
+                Box::new(MessageType::new(METHOD_ALLOCATE, CLASS_ERROR_RESPONSE)),
+                Box::new(ErrorCodeAttribute {
+                    code,
+                    reason: vec![],
+                }),
+            ])
+            .unwrap();
+            let _ = conn.send_to(&res.raw, from).await;
+        }
+    });
+
+    Ok((port, requests))
+}
+
+async fn create_allocate_test_client(
+    server_port: u16,
+    allocate_retry: AllocateRetryPolicy,
+) -> Result<Client> {
+    let conn = Arc::new(UdpSocket::bind("127.0.0.1:0").await?);
+
+    let client = Client::new(ClientConfig {
+        stun_serv_addr: String::new(),
+        turn_serv_addr: format!("127.0.0.1:{server_port}"),
+        username: "foo".to_owned(),
+        password: "pass".to_owned(),
+        realm: String::new(),
+        software: String::new(),
+        rto_in_ms: 0,
+        conn,
+        vnet: None,
+        allocate_retry,
+    })
+    .await?;
+
+    client.listen().await?;
+
+    Ok(client)
+}
+
+#[tokio::test]
+async fn test_client_allocate_retry() -> Result<()> {
+    let (server_port, requests) = run_failing_allocate_server(CODE_INSUFFICIENT_CAPACITY).await?;
+
+    let client = create_allocate_test_client(
+        server_port,
+        AllocateRetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(10),
+            max_backoff: Duration::from_millis(20),
+        },
+    )
+    .await?;
+
+    let result = client.allocate().await;
+    assert!(
+        matches!(result, Err(Error::ErrAllocateServerError(_))),
+        "{:?}",
+        result.err()
+    );
+    assert_eq!(requests.load(Ordering::SeqCst), 3);
+
+    client.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_client_allocate_no_retry_on_client_error() -> Result<()> {
+    let (server_port, requests) = run_failing_allocate_server(CODE_FORBIDDEN).await?;
+
+    let client = create_allocate_test_client(server_port, AllocateRetryPolicy::default()).await?;
+
+    assert!(client.allocate().await.is_err());
+    assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+    client.close().await?;
+
+    Ok(())
+}
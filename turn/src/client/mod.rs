@@ -10,6 +10,7 @@ pub mod transaction;
 use std::net::SocketAddr;
 use std::str::FromStr;
 use std::sync::Arc;
+use std::time::Duration;
 
 use async_trait::async_trait;
 use base64::prelude::BASE64_STANDARD;
@@ -55,7 +56,55 @@ const MAX_READ_QUEUE_SIZE: usize = 1024;
 // 6: 31500 ms  +32000
 // -: 63500 ms  failed
 
-/// ClientConfig is a bag of config parameters for Client.
+/// AllocateRetryPolicy controls how many times Client::allocate retries an
+/// allocation that failed transiently, because the TURN server didn't answer
+/// or answered with a 5xx error code. The n-th retry is made after a backoff
+/// of initial_backoff * 2^(n-1), capped at max_backoff. Other failures, like
+/// a 401 for wrong credentials, are returned right away. The default policy
+/// doesn't retry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocateRetryPolicy {
+    pub max_retries: u16,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for AllocateRetryPolicy {
+    fn default() -> Self {
+        AllocateRetryPolicy {
+            max_retries: 0,
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(8),
+        }
+    }
+}
+
+impl AllocateRetryPolicy {
+    /// A policy returning the first failure without retrying, the default.
+    pub fn none() -> Self {
+        Self::default()
+    }
+
+    /// Returns the backoff before the given retry, counted from 1.
+    pub fn backoff(&self, retry: u16) -> Duration {
+        let exp = retry.saturating_sub(1).min(31) as u32;
+        self.initial_backoff
+            .saturating_mul(1 << exp)
+            .min(self.max_backoff)
+    }
+
+    fn should_retry(&self, retry: u16, err: &Error) -> bool {
+        retry <= self.max_retries
+            && matches!(
+                err,
+                Error::ErrAllocateTimeout | Error::ErrAllocateServerError(_)
+            )
+    }
+}
+
+/// ClientConfig is a bag of config parameters for Client. It is created with
+/// ClientConfig::new, so that adding fields isn't a breaking change.
+#[non_exhaustive]
 pub struct ClientConfig {
     pub stun_serv_addr: String, // STUN server address (e.g. "stun.abc.com:3478")
     pub turn_serv_addr: String, // TURN server address (e.g. "turn.abc.com:3478")
@@ -66,6 +115,26 @@ pub struct ClientConfig {
     pub rto_in_ms: u16,
     pub conn: Arc<dyn Conn + Send + Sync>,
     pub vnet: Option<Arc<Net>>,
+    pub allocate_retry: AllocateRetryPolicy,
+}
+
+impl ClientConfig {
+    /// new creates a ClientConfig for a client over conn, with the defaults for
+    /// everything else, which can then be set on the returned ClientConfig.
+    pub fn new(conn: Arc<dyn Conn + Send + Sync>) -> Self {
+        ClientConfig {
+            stun_serv_addr: String::new(),
+            turn_serv_addr: String::new(),
+            username: String::new(),
+            password: String::new(),
+            realm: String::new(),
+            software: String::new(),
+            rto_in_ms: 0,
+            conn,
+            vnet: None,
+            allocate_retry: AllocateRetryPolicy::default(),
+        }
+    }
 }
 
 struct ClientInternal {
@@ -535,8 +604,24 @@ impl ClientInternal {
         let tr_res = self
             .perform_transaction(&msg, &self.turn_serv_addr.clone(), false)
             .await?;
+        if let Some(err) = tr_res.err {
+            log::debug!("allocate transaction failed: {}", err);
+            return Err(Error::ErrAllocateTimeout);
+        }
         let res = tr_res.msg;
 
+        // A 5xx is returned instead of the 401 asking for credentials when the
+        // server can't take the request at the moment.
+        if res.typ.class == CLASS_ERROR_RESPONSE {
+            let mut code = ErrorCodeAttribute::default();
+            if code.get_from(&res).is_ok() && is_server_error(code.code) {
+                return Err(Error::ErrAllocateServerError(format!(
+                    "{} (error {})",
+                    res.typ, code
+                )));
+            }
+        }
+
         // Anonymous allocate failed, trying to authenticate.
         let nonce = Nonce::get_from_as(&res, ATTR_NONCE)?;
         self.realm = Realm::get_from_as(&res, ATTR_REALM)?;
@@ -565,6 +650,10 @@ impl ClientInternal {
         let tr_res = self
             .perform_transaction(&msg, &self.turn_serv_addr.clone(), false)
             .await?;
+        if let Some(err) = tr_res.err {
+            log::debug!("allocate transaction failed: {}", err);
+            return Err(Error::ErrAllocateTimeout);
+        }
         let res = tr_res.msg;
 
         if res.typ.class == CLASS_ERROR_RESPONSE {
@@ -572,6 +661,11 @@ impl ClientInternal {
             let result = code.get_from(&res);
             if result.is_err() {
                 return Err(Error::Other(format!("{}", res.typ)));
+            } else if is_server_error(code.code) {
+                return Err(Error::ErrAllocateServerError(format!(
+                    "{} (error {})",
+                    res.typ, code
+                )));
             } else {
                 return Err(Error::Other(format!("{} (error {})", res.typ, code)));
             }
@@ -604,17 +698,26 @@ impl ClientInternal {
     }
 }
 
+/// is_server_error returns true for the 5xx error codes, which report a
+/// failure of the server rather than of the request.
+fn is_server_error(code: ErrorCode) -> bool {
+    (500..600).contains(&code.0)
+}
+
 /// Client is a STUN server client.
 #[derive(Clone)]
 pub struct Client {
     client_internal: Arc<Mutex<ClientInternal>>,
+    allocate_retry: AllocateRetryPolicy,
 }
 
 impl Client {
     pub async fn new(config: ClientConfig) -> Result<Self> {
+        let allocate_retry = config.allocate_retry;
         let ci = ClientInternal::new(config).await?;
         Ok(Client {
             client_internal: Arc::new(Mutex::new(ci)),
+            allocate_retry,
         })
     }
 
@@ -623,10 +726,34 @@ impl Client {
         ci.listen().await
     }
 
+    /// Creates an allocation on the TURN server, retrying transient failures
+    /// as configured by the AllocateRetryPolicy of the client.
     pub async fn allocate(&self) -> Result<impl Conn> {
-        let config = {
-            let mut ci = self.client_internal.lock().await;
-            ci.allocate().await?
+        let mut retry = 0;
+        let config = loop {
+            let result = {
+                let mut ci = self.client_internal.lock().await;
+                ci.allocate().await
+            };
+
+            match result {
+                Ok(config) => break config,
+                Err(err) => {
+                    retry += 1;
+                    if !self.allocate_retry.should_retry(retry, &err) {
+                        return Err(err);
+                    }
+
+                    let backoff = self.allocate_retry.backoff(retry);
+                    log::debug!(
+                        "allocate failed ({}), retry {} in {:?}",
+                        err,
+                        retry,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                }
+            }
         };
 
         Ok(RelayConn::new(Arc::clone(&self.client_internal), config).await)
@@ -47,6 +47,10 @@ pub enum Error {
     ErrStunserverAddressNotSet,
     #[error("only one Allocate() caller is allowed")]
     ErrOneAllocateOnly,
+    #[error("allocate request timed out")]
+    ErrAllocateTimeout,
+    #[error("allocate failed with server error: {0}")]
+    ErrAllocateServerError(String),
     #[error("already allocated")]
     ErrAlreadyAllocated,
     #[error("non-STUN message from STUN server")]
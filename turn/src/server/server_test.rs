@@ -81,6 +81,7 @@ async fn test_server_simple() -> Result<()> {
         rto_in_ms: 0,
         conn,
         vnet: None,
+        allocate_retry: AllocateRetryPolicy::default(),
     })
     .await?;
 
@@ -235,6 +236,7 @@ async fn test_server_vnet_send_binding_request() -> Result<()> {
         rto_in_ms: 0,
         conn: lconn,
         vnet: Some(Arc::clone(&v.netl0)),
+        allocate_retry: AllocateRetryPolicy::default(),
     })
     .await?;
 
@@ -272,6 +274,7 @@ async fn test_server_vnet_echo_via_relay() -> Result<()> {
         rto_in_ms: 0,
         conn: lconn,
         vnet: Some(Arc::clone(&v.netl0)),
+        allocate_retry: AllocateRetryPolicy::default(),
     })
     .await?;
 
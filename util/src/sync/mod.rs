@@ -1,3 +1,4 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::{ops, sync};
 
 /// A synchronous mutual exclusion primitive useful for protecting shared data.
@@ -97,3 +98,147 @@ impl<T> ops::DerefMut for RwLockWriteGuard<'_, T> {
         &mut self.0
     }
 }
+
+/// MemoryBudget accounts the bytes held by several buffers against a shared
+/// limit, so that together they can't grow beyond it. A budget can be carved out
+/// of a parent one, to keep some of the buffers to a share of the parent's limit.
+#[derive(Debug)]
+pub struct MemoryBudget {
+    limit: usize,
+    used: AtomicUsize,
+    parent: Option<sync::Arc<MemoryBudget>>,
+}
+
+impl MemoryBudget {
+    /// Creates a budget of `limit` bytes, none of them used.
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            used: AtomicUsize::new(0),
+            parent: None,
+        }
+    }
+
+    /// Creates a budget of `limit` bytes whose bytes are also accounted in `parent`,
+    /// they fit only if they fit in both.
+    pub fn with_parent(parent: sync::Arc<MemoryBudget>, limit: usize) -> Self {
+        Self {
+            limit,
+            used: AtomicUsize::new(0),
+            parent: Some(parent),
+        }
+    }
+
+    /// Returns the number of bytes of the budget.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Returns the number of bytes currently accounted.
+    pub fn used(&self) -> usize {
+        self.used.load(Ordering::SeqCst)
+    }
+
+    /// Returns true once the accounted bytes reached the limit.
+    pub fn is_exhausted(&self) -> bool {
+        self.used() >= self.limit
+            || self
+                .parent
+                .as_ref()
+                .is_some_and(|parent| parent.is_exhausted())
+    }
+
+    /// Accounts `n` more bytes if they fit in the budget, returns false
+    /// without accounting them otherwise.
+    pub fn try_reserve(&self, n: usize) -> bool {
+        let reserved = self
+            .used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                used.checked_add(n).filter(|&used| used <= self.limit)
+            })
+            .is_ok();
+        if !reserved {
+            return false;
+        }
+        if let Some(parent) = &self.parent {
+            if !parent.try_reserve(n) {
+                self.used.fetch_sub(n, Ordering::SeqCst);
+                return false;
+            }
+        }
+        true
+    }
+
+    /// Accounts `n` more bytes, even beyond the limit, for data that was
+    /// already accepted.
+    pub fn reserve(&self, n: usize) {
+        self.used.fetch_add(n, Ordering::SeqCst);
+        if let Some(parent) = &self.parent {
+            parent.reserve(n);
+        }
+    }
+
+    /// Gives back `n` bytes previously accounted.
+    pub fn release(&self, n: usize) {
+        let used = self
+            .used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                Some(used.saturating_sub(n))
+            })
+            .unwrap_or_default();
+        if let Some(parent) = &self.parent {
+            parent.release(n.min(used));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_memory_budget() {
+        let budget = MemoryBudget::new(100);
+        assert!(budget.try_reserve(60));
+        assert!(!budget.try_reserve(41));
+        assert!(budget.try_reserve(40));
+        assert!(budget.is_exhausted());
+
+        budget.release(50);
+        assert_eq!(budget.used(), 50);
+        assert!(!budget.is_exhausted());
+
+        budget.reserve(70);
+        assert_eq!(budget.used(), 120);
+        assert!(!budget.try_reserve(1));
+
+        budget.release(200);
+        assert_eq!(budget.used(), 0);
+    }
+
+    #[test]
+    fn test_memory_budget_with_parent() {
+        let parent = sync::Arc::new(MemoryBudget::new(100));
+        let child = MemoryBudget::with_parent(sync::Arc::clone(&parent), 50);
+
+        // the child is kept to its share of the parent
+        assert!(child.try_reserve(40));
+        assert!(!child.try_reserve(20));
+        assert_eq!(parent.used(), 40);
+        assert!(parent.try_reserve(60));
+
+        // and to what the parent has left
+        child.release(40);
+        assert!(parent.try_reserve(30));
+        assert!(!child.try_reserve(20));
+        assert_eq!(child.used(), 0);
+        assert_eq!(parent.used(), 90);
+        assert!(child.try_reserve(10));
+        assert!(child.is_exhausted());
+
+        // releasing more than the child used gives back only its own bytes
+        child.release(100);
+        assert_eq!(child.used(), 0);
+        assert_eq!(parent.used(), 90);
+    }
+}
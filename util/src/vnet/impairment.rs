@@ -0,0 +1,83 @@
+#[cfg(test)]
+mod impairment_test;
+
+use rand::Rng;
+use tokio::time::Duration;
+
+// Impairment degrades the link of a router the way a real network does: chunks
+// traversing the router are dropped, delayed, reordered or duplicated at random.
+// It is built with the with_* methods, e.g.
+// Impairment::default().with_loss(0.05).with_delay(Duration::from_millis(20), Duration::from_millis(10))
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Impairment {
+    // probability in [0, 1] that a chunk is dropped
+    pub loss: f64,
+    // fixed delay added to every chunk
+    pub delay: Duration,
+    // random delay in [0, jitter) added to every chunk
+    pub jitter: Duration,
+    // probability in [0, 1] that a chunk is held back by reorder_delay, so
+    // that the chunks following it overtake it
+    pub reorder: f64,
+    pub reorder_delay: Duration,
+    // probability in [0, 1] that a chunk is delivered twice
+    pub duplicate: f64,
+}
+
+impl Impairment {
+    // with_loss sets the probability that a chunk is dropped
+    pub fn with_loss(mut self, loss: f64) -> Self {
+        self.loss = loss;
+        self
+    }
+
+    // with_delay sets the fixed delay and the maximum random delay of every chunk
+    pub fn with_delay(mut self, delay: Duration, jitter: Duration) -> Self {
+        self.delay = delay;
+        self.jitter = jitter;
+        self
+    }
+
+    // with_reorder sets the probability that a chunk is held back by reorder_delay
+    pub fn with_reorder(mut self, reorder: f64, reorder_delay: Duration) -> Self {
+        self.reorder = reorder;
+        self.reorder_delay = reorder_delay;
+        self
+    }
+
+    // with_duplicate sets the probability that a chunk is delivered twice
+    pub fn with_duplicate(mut self, duplicate: f64) -> Self {
+        self.duplicate = duplicate;
+        self
+    }
+
+    // schedule decides the fate of a chunk: it returns the delay of each copy of
+    // the chunk to deliver, which is empty if the chunk is dropped.
+    pub(crate) fn schedule(&self) -> Vec<Duration> {
+        let mut rng = rand::thread_rng();
+        if rng.gen_bool(self.loss.clamp(0.0, 1.0)) {
+            return vec![];
+        }
+
+        let copies = if rng.gen_bool(self.duplicate.clamp(0.0, 1.0)) {
+            2
+        } else {
+            1
+        };
+
+        (0..copies)
+            .map(|_| {
+                let mut d = self.delay;
+                if !self.jitter.is_zero() {
+                    d += Duration::from_nanos(
+                        rng.gen_range(0..self.jitter.as_nanos().min(u64::MAX as u128) as u64),
+                    );
+                }
+                if rng.gen_bool(self.reorder.clamp(0.0, 1.0)) {
+                    d += self.reorder_delay;
+                }
+                d
+            })
+            .collect()
+    }
+}
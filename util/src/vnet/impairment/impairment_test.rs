@@ -0,0 +1,52 @@
+use super::*;
+
+#[test]
+fn test_impairment_default_passes_through() {
+    let impairment = Impairment::default();
+    for _ in 0..100 {
+        assert_eq!(impairment.schedule(), vec![Duration::from_secs(0)]);
+    }
+}
+
+#[test]
+fn test_impairment_loss() {
+    let impairment = Impairment::default().with_loss(1.0);
+    for _ in 0..100 {
+        assert!(impairment.schedule().is_empty(), "should drop every chunk");
+    }
+
+    let impairment = Impairment::default().with_loss(0.5);
+    let dropped = (0..1000)
+        .filter(|_| impairment.schedule().is_empty())
+        .count();
+    assert!(
+        (300..700).contains(&dropped),
+        "should drop about half of the chunks, dropped {dropped}"
+    );
+}
+
+#[test]
+fn test_impairment_delay() {
+    let delay = Duration::from_millis(20);
+    let jitter = Duration::from_millis(10);
+    let impairment = Impairment::default().with_delay(delay, jitter);
+    for _ in 0..100 {
+        let delays = impairment.schedule();
+        assert_eq!(delays.len(), 1);
+        assert!(
+            delays[0] >= delay && delays[0] < delay + jitter,
+            "{delays:?}"
+        );
+    }
+}
+
+#[test]
+fn test_impairment_reorder_and_duplicate() {
+    let reorder_delay = Duration::from_millis(30);
+    let impairment = Impairment::default()
+        .with_reorder(1.0, reorder_delay)
+        .with_duplicate(1.0);
+    for _ in 0..100 {
+        assert_eq!(impairment.schedule(), vec![reorder_delay, reorder_delay]);
+    }
+}
@@ -2,6 +2,7 @@ pub mod chunk;
 pub(crate) mod chunk_queue;
 pub(crate) mod conn;
 pub(crate) mod conn_map;
+pub mod impairment;
 pub mod interface;
 pub mod nat;
 pub mod net;
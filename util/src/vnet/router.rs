@@ -20,6 +20,7 @@ use tokio::time::Duration;
 use crate::error::*;
 use crate::vnet::chunk::*;
 use crate::vnet::chunk_queue::*;
+use crate::vnet::impairment::*;
 use crate::vnet::interface::*;
 use crate::vnet::nat::*;
 use crate::vnet::net::*;
@@ -59,6 +60,8 @@ pub struct RouterConfig {
     pub min_delay: Duration,
     // Max Jitter
     pub max_jitter: Duration,
+    // Impairment applied to the chunks traversing this router
+    pub impairment: Option<Impairment>,
 }
 
 // NIC is a network interface controller that interfaces Router
@@ -83,6 +86,7 @@ pub struct RouterInternal {
     pub(crate) nat: NetworkAddressTranslator,       // read-only
     pub(crate) nics: HashMap<String, Weak<Mutex<dyn Nic + Send + Sync>>>, // read-only
     pub(crate) chunk_filters: Vec<ChunkFilterFn>,   // requires mutex [x]
+    pub(crate) impairment: Option<Impairment>,      // requires mutex [x]
     pub(crate) last_id: u8, // requires mutex [x], used to assign the last digit of IPv4 address
 }
 
@@ -275,6 +279,7 @@ impl Router {
             nat_type: config.nat_type,
             ipv4net,
             nics: HashMap::new(),
+            impairment: config.impairment,
             ..Default::default()
         };
 
@@ -409,6 +414,13 @@ impl Router {
         router_internal.chunk_filters.push(filter);
     }
 
+    // SetImpairment sets, or clears with None, the impairment applied to the chunks
+    // traversing this router. It is applied after the chunk filters.
+    pub async fn set_impairment(&self, impairment: Option<Impairment>) {
+        let mut router_internal = self.router_internal.lock().await;
+        router_internal.impairment = impairment;
+    }
+
     pub(crate) async fn push(&self, mut c: Box<dyn Chunk + Send + Sync>) {
         log::debug!("[{}] route {}", self.name, c);
         if self.done.is_some() {
@@ -487,33 +499,28 @@ impl Router {
                     continue; // discard
                 }
 
-                let dst_ip = c.get_destination_ip();
+                let delays = match &ri.impairment {
+                    Some(impairment) => impairment.schedule(),
+                    None => vec![Duration::from_secs(0)],
+                };
+                if delays.is_empty() {
+                    log::debug!("[{}] impairment dropped {}", name, c);
+                }
 
-                // check if the destination is in our subnet
-                if ipv4net.contains(&dst_ip) {
-                    // search for the destination NIC
-                    if let Some(nic) = ri.nics.get(&dst_ip.to_string()).and_then(|p| p.upgrade()) {
-                        // found the NIC, forward the chunk to the NIC.
-                        // call to NIC must unlock mutex
-                        let ni = nic.lock().await;
-                        ni.on_inbound_chunk(c).await;
+                for delay in delays {
+                    let c = c.clone_to();
+                    if delay.is_zero() {
+                        Router::route(name, ipv4net, c, &ri).await?;
                     } else {
-                        // NIC not found. drop it.
-                        log::debug!("[{}] {} unreachable", name, c);
-                    }
-                } else {
-                    // the destination is outside of this subnet
-                    // is this WAN?
-                    if let Some(parent) = &ri.parent.clone().and_then(|p| p.upgrade()) {
-                        // Pass it to the parent via NAT
-                        if let Some(to_parent) = ri.nat.translate_outbound(&*c).await? {
-                            // call to parent router mutex unlock mutex
-                            let p = parent.lock().await;
-                            p.push(to_parent).await;
-                        }
-                    } else {
-                        // this WAN. No route for this chunk
-                        log::debug!("[{}] no route found for {}", name, c);
+                        let name = name.to_owned();
+                        let router_internal = Arc::clone(router_internal);
+                        tokio::spawn(async move {
+                            tokio::time::sleep(delay).await;
+                            let ri = router_internal.lock().await;
+                            if let Err(err) = Router::route(&name, ipv4net, c, &ri).await {
+                                log::warn!("[{}] failed to route delayed chunk: {}", name, err);
+                            }
+                        });
                     }
                 }
             } else {
@@ -523,6 +530,47 @@ impl Router {
 
         Ok(d)
     }
+
+    // route forwards a chunk to the NIC of its destination, or to the parent router
+    // caller must hold the mutex
+    async fn route(
+        name: &str,
+        ipv4net: IpNet,
+        c: Box<dyn Chunk + Send + Sync>,
+        ri: &RouterInternal,
+    ) -> Result<()> {
+        let dst_ip = c.get_destination_ip();
+
+        // check if the destination is in our subnet
+        if ipv4net.contains(&dst_ip) {
+            // search for the destination NIC
+            if let Some(nic) = ri.nics.get(&dst_ip.to_string()).and_then(|p| p.upgrade()) {
+                // found the NIC, forward the chunk to the NIC.
+                // call to NIC must unlock mutex
+                let ni = nic.lock().await;
+                ni.on_inbound_chunk(c).await;
+            } else {
+                // NIC not found. drop it.
+                log::debug!("[{}] {} unreachable", name, c);
+            }
+        } else {
+            // the destination is outside of this subnet
+            // is this WAN?
+            if let Some(parent) = &ri.parent.clone().and_then(|p| p.upgrade()) {
+                // Pass it to the parent via NAT
+                if let Some(to_parent) = ri.nat.translate_outbound(&*c).await? {
+                    // call to parent router mutex unlock mutex
+                    let p = parent.lock().await;
+                    p.push(to_parent).await;
+                }
+            } else {
+                // this WAN. No route for this chunk
+                log::debug!("[{}] no route found for {}", name, c);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 impl RouterInternal {
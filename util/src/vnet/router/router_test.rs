@@ -365,6 +365,93 @@ async fn test_router_standalone_add_chunk_filter() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_router_standalone_impairment() -> Result<()> {
+    let wan = Arc::new(Mutex::new(Router::new(RouterConfig {
+        cidr: "1.2.3.0/24".to_string(),
+        impairment: Some(Impairment::default().with_duplicate(1.0)),
+        ..Default::default()
+    })?));
+
+    let mut nics = vec![];
+    let mut ips = vec![];
+    for i in 0..2 {
+        let dn = DummyNic {
+            net: Net::new(Some(NetConfig::default())),
+            on_inbound_chunk_handler: 0,
+            ..Default::default()
+        };
+        let nic = Arc::new(Mutex::new(dn));
+
+        {
+            let n = Arc::clone(&nic) as Arc<Mutex<dyn Nic + Send + Sync>>;
+            let mut w = wan.lock().await;
+            w.add_net(n).await?;
+        }
+        {
+            let n = nic.lock().await;
+            n.set_router(Arc::clone(&wan)).await?;
+        }
+
+        {
+            let n = nic.lock().await;
+            if let Some(eth0) = n.get_interface("eth0").await {
+                let addrs = eth0.addrs();
+                assert_eq!(addrs.len(), 1, "should match");
+                ips.push(SocketAddr::new(addrs[0].addr(), 1111 * (i + 1)));
+            }
+        }
+
+        nics.push(nic);
+    }
+
+    {
+        let mut r = wan.lock().await;
+        r.start().await?;
+
+        // every chunk is duplicated
+        for i in 0..3u8 {
+            let mut c = ChunkUdp::new(ips[0], ips[1]);
+            c.user_data = vec![i];
+            r.push(Box::new(c)).await;
+        }
+    }
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    {
+        let n = nics[1].lock().await;
+        assert_eq!(n.cbs0.load(Ordering::SeqCst), 6, "should be duplicated");
+    }
+
+    {
+        let r = wan.lock().await;
+        r.set_impairment(Some(Impairment::default().with_loss(1.0)))
+            .await;
+
+        // every chunk is dropped
+        for i in 0..3u8 {
+            let mut c = ChunkUdp::new(ips[0], ips[1]);
+            c.user_data = vec![i];
+            r.push(Box::new(c)).await;
+        }
+    }
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    {
+        let mut r = wan.lock().await;
+        r.stop().await?;
+    }
+
+    {
+        let n = nics[1].lock().await;
+        assert_eq!(n.cbs0.load(Ordering::SeqCst), 6, "should be dropped");
+    }
+
+    Ok(())
+}
+
 async fn delay_sub_test(title: String, min_delay: Duration, max_jitter: Duration) -> Result<()> {
     let wan = Arc::new(Mutex::new(Router::new(RouterConfig {
         cidr: "1.2.3.0/24".to_string(),
@@ -0,0 +1,71 @@
+use std::sync::atomic::Ordering;
+
+use portable_atomic::AtomicUsize;
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+/// HandshakeLimiter bounds how many DTLS handshakes the PeerConnections of an
+/// API run at the same time. Handshakes over the limit wait for a permit.
+#[derive(Default)]
+pub(crate) struct HandshakeLimiter {
+    /// semaphore is None when the number of handshakes is unlimited
+    semaphore: Option<Semaphore>,
+    in_flight: AtomicUsize,
+    queued: AtomicUsize,
+}
+
+impl HandshakeLimiter {
+    pub(crate) fn new(max_concurrent: Option<usize>) -> Self {
+        HandshakeLimiter {
+            semaphore: max_concurrent.map(|permits| Semaphore::new(permits.max(1))),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn queued(&self) -> usize {
+        self.queued.load(Ordering::SeqCst)
+    }
+
+    /// acquire waits until a handshake may start. The handshake counts as in
+    /// flight until the returned permit is dropped.
+    pub(crate) async fn acquire(&self) -> HandshakePermit<'_> {
+        let permit = match &self.semaphore {
+            Some(semaphore) => {
+                let _queued = Counted::new(&self.queued);
+                // The semaphore is never closed
+                semaphore.acquire().await.ok()
+            }
+            None => None,
+        };
+
+        HandshakePermit {
+            _permit: permit,
+            _in_flight: Counted::new(&self.in_flight),
+        }
+    }
+}
+
+pub(crate) struct HandshakePermit<'a> {
+    _permit: Option<SemaphorePermit<'a>>,
+    _in_flight: Counted<'a>,
+}
+
+/// Counted increments a counter for as long as it is alive, which keeps the
+/// counter right when the waiting future is dropped.
+struct Counted<'a>(&'a AtomicUsize);
+
+impl<'a> Counted<'a> {
+    fn new(counter: &'a AtomicUsize) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Counted(counter)
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
@@ -8,6 +8,7 @@ use interceptor::report::receiver::ReceiverReport;
 use interceptor::report::sender::SenderReport;
 use interceptor::twcc::receiver::Receiver;
 use interceptor::twcc::sender::Sender;
+use interceptor::validator::Validator;
 
 use crate::api::media_engine::MediaEngine;
 use crate::error::Result;
@@ -30,6 +31,16 @@ pub fn register_default_interceptors(
     Ok(registry)
 }
 
+/// configure_validator will setup the dropping of structurally invalid RTP packets
+/// received from the remote peer. It isn't part of register_default_interceptors and has
+/// to be called before the other interceptors are configured, so they never see the
+/// invalid packets. To count the dropped packets add [`Validator::builder`] to the
+/// registry directly and keep its rejected_packets counter.
+pub fn configure_validator(mut registry: Registry) -> Registry {
+    registry.add(Box::new(Validator::builder()));
+    registry
+}
+
 /// configure_rtcp_reports will setup everything necessary for generating Sender and Receiver Reports
 pub fn configure_rtcp_reports(mut registry: Registry) -> Registry {
     let receiver = Box::new(ReceiverReport::builder());
@@ -169,3 +180,48 @@ pub fn configure_twcc_receiver_only(
     registry.add(receiver);
     Ok(registry)
 }
+
+/// configure_playout_delay will register the playout-delay header extension for video.
+/// Once negotiated, senders can request a playout delay with [`RTCRtpSender::set_playout_delay`]
+/// and the delay requested by the remote is reported in the [`Attributes`] returned by
+/// [`TrackRemote::read`].
+///
+/// [`RTCRtpSender::set_playout_delay`]: crate::rtp_transceiver::rtp_sender::RTCRtpSender::set_playout_delay
+/// [`Attributes`]: interceptor::Attributes
+/// [`TrackRemote::read`]: crate::track::track_remote::TrackRemote::read
+pub fn configure_playout_delay(
+    registry: Registry,
+    media_engine: &mut MediaEngine,
+) -> Result<Registry> {
+    media_engine.register_header_extension(
+        RTCRtpHeaderExtensionCapability {
+            uri: sdp::extmap::PLAYOUT_DELAY_URI.to_owned(),
+        },
+        RTPCodecType::Video,
+        None,
+    )?;
+
+    Ok(registry)
+}
+
+/// configure_audio_level will register the ssrc-audio-level header extension (RFC 6464)
+/// for audio. Once negotiated, the level of every sample written with
+/// [`SampleWriter::with_audio_level`] is sent to the remote, and the levels sent by the
+/// remote are reported by [`RTCRtpReceiver::get_synchronization_sources`].
+///
+/// [`SampleWriter::with_audio_level`]: crate::track::track_local::track_local_static_sample::SampleWriter::with_audio_level
+/// [`RTCRtpReceiver::get_synchronization_sources`]: crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver::get_synchronization_sources
+pub fn configure_audio_level(
+    registry: Registry,
+    media_engine: &mut MediaEngine,
+) -> Result<Registry> {
+    media_engine.register_header_extension(
+        RTCRtpHeaderExtensionCapability {
+            uri: sdp::extmap::AUDIO_LEVEL_URI.to_owned(),
+        },
+        RTPCodecType::Audio,
+        None,
+    )?;
+
+    Ok(registry)
+}
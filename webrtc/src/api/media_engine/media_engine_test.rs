@@ -1,6 +1,8 @@
 use std::io::Cursor;
 
+use bytes::Bytes;
 use regex::Regex;
+use rtp::packetizer::Packetizer;
 
 use super::*;
 use crate::api::media_engine::MIME_TYPE_OPUS;
@@ -169,6 +171,65 @@ a=fmtp:111 minptime=10; useinbandfec=1
         assert_eq!(opus_codec.capability.mime_type, "audio/OPUS");
     }
 
+    //"Opus DTX and stereo"
+    {
+        const OPUS_DTX_MONO: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+m=audio 9 UDP/TLS/RTP/SAVPF 111
+a=rtpmap:111 opus/48000/2
+a=fmtp:111 minptime=10;useinbandfec=1;usedtx=1;stereo=0
+";
+
+        let mut m = MediaEngine::default();
+        m.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: MIME_TYPE_OPUS.to_owned(),
+                    clock_rate: 48000,
+                    channels: 2,
+                    sdp_fmtp_line: "minptime=10;useinbandfec=1;stereo=1;sprop-stereo=1".to_owned(),
+                    rtcp_feedback: vec![],
+                },
+                payload_type: 111,
+                ..Default::default()
+            },
+            RTPCodecType::Audio,
+        )?;
+
+        let remote = must_parse(OPUS_DTX_MONO)?;
+        let remote_codecs = codecs_from_media_description(&remote.media_descriptions[0])?;
+        assert_eq!(
+            m.match_remote_codec(&remote_codecs[0], RTPCodecType::Audio, &[], &[])?,
+            CodecMatch::Exact,
+            "the Opus parameters are not negotiated"
+        );
+
+        m.update_from_remote_description(&remote).await?;
+        assert!(m.negotiated_audio.load(Ordering::SeqCst));
+
+        // the remote prefers DTX, so only the first packet of audio after a silence is marked
+        let (opus_codec, _) = m.get_codec_by_payload(111).await?;
+        let mut packetizer = rtp::packetizer::new_packetizer(
+            1200,
+            111,
+            1234,
+            opus_codec.capability.payloader_for_codec()?,
+            Box::new(rtp::sequence::new_fixed_sequencer(0)),
+            48000,
+        );
+        let frame = Bytes::from_static(&[0x78, 0x90, 0x90]);
+        let dtx_frame = Bytes::from_static(&[0x78, 0x01]);
+        let mut markers = vec![];
+        for f in [&frame, &frame, &dtx_frame, &frame] {
+            for p in packetizer.packetize(f, 960)? {
+                markers.push(p.header.marker);
+            }
+        }
+        assert_eq!(markers, vec![true, false, false, true]);
+    }
+
     //"Handle different fmtp"
     {
         const OPUS_NO_FMTP: &str = "v=0
@@ -659,6 +720,30 @@ async fn test_media_engine_double_register() -> Result<()> {
     Ok(())
 }
 
+/// A RTX codec takes the clock rate of the codec it retransmits and references it with apt
+#[test]
+fn test_media_engine_register_rtx_codec() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+
+    m.register_rtx_codec(97, 96, RTPCodecType::Video)?;
+    let rtx = m
+        .video_codecs
+        .iter()
+        .find(|codec| codec.payload_type == 97)
+        .expect("rtx codec registered");
+    assert_eq!(rtx.capability.mime_type, "video/rtx");
+    assert_eq!(rtx.capability.clock_rate, 90000);
+    assert_eq!(rtx.capability.sdp_fmtp_line, "apt=96");
+
+    assert!(matches!(
+        m.register_rtx_codec(99, 42, RTPCodecType::Video),
+        Err(Error::ErrCodecNotFound)
+    ));
+
+    Ok(())
+}
+
 async fn validate(m: &MediaEngine) -> Result<()> {
     m.update_header_extension(2, "test-extension", RTPCodecType::Audio)
         .await?;
@@ -363,12 +363,55 @@ impl MediaEngine {
         }
     }
 
+    /// register_rtx_codec adds a RTX codec (RFC 4588) to the MediaEngine that retransmits the
+    /// already registered codec with payload type `apt`. Once negotiated, NACKed packets are
+    /// resent on a separate RTX stream announced with `a=ssrc-group:FID`, if outgoing RTX
+    /// streams are enabled with [`SettingEngine::enable_sender_rtx`].
+    /// register_rtx_codec is not safe for concurrent use.
+    ///
+    /// [`SettingEngine::enable_sender_rtx`]: crate::api::setting_engine::SettingEngine::enable_sender_rtx
+    pub fn register_rtx_codec(
+        &mut self,
+        payload_type: PayloadType,
+        apt: PayloadType,
+        typ: RTPCodecType,
+    ) -> Result<()> {
+        let codecs = match typ {
+            RTPCodecType::Audio => &self.audio_codecs,
+            RTPCodecType::Video => &self.video_codecs,
+            _ => return Err(Error::ErrUnknownType),
+        };
+        let clock_rate = codecs
+            .iter()
+            .find(|codec| codec.payload_type == apt)
+            .map(|codec| codec.capability.clock_rate)
+            .ok_or(Error::ErrCodecNotFound)?;
+
+        self.register_codec(
+            RTCRtpCodecParameters {
+                capability: RTCRtpCodecCapability {
+                    mime_type: format!("{typ}/rtx"),
+                    clock_rate,
+                    channels: 0,
+                    sdp_fmtp_line: format!("apt={apt}"),
+                    rtcp_feedback: vec![],
+                },
+                payload_type,
+                ..Default::default()
+            },
+            typ,
+        )
+    }
+
     /// Adds a header extension to the MediaEngine
     /// To determine the negotiated value use [`MediaEngine::get_header_extension_id`] after signaling is complete.
     ///
     /// The `allowed_direction` controls for which transceiver directions the extension matches. If
     /// set to `None` it matches all directions. The `SendRecv` direction would match all transceiver
     /// directions apart from `Inactive`. Inactive only matches inactive.
+    ///
+    /// A `Sendonly` extension is only stamped on outgoing packets and a `Recvonly` one is only
+    /// offered for receiving, the direction is signaled in the extmap attribute of the SDP.
     pub fn register_header_extension(
         &mut self,
         extension: RTCRtpHeaderExtensionCapability,
@@ -723,6 +766,42 @@ impl MediaEngine {
         }
     }
 
+    /// get_header_extension_direction returns the direction a header extension was registered
+    /// with, `None` if it is allowed in all directions.
+    pub(crate) fn get_header_extension_direction(
+        &self,
+        uri: &str,
+    ) -> Option<RTCRtpTransceiverDirection> {
+        self.header_extensions
+            .iter()
+            .find(|ext| ext.uri == uri)
+            .and_then(|ext| ext.allowed_direction)
+    }
+
+    /// get_negotiated_header_extensions returns the id and URI of the header extensions,
+    /// the `a=extmap`s of a media section, that were negotiated for typ in direction,
+    /// sorted by id.
+    pub(crate) fn get_negotiated_header_extensions(
+        &self,
+        extensions: &HashMap<String, isize>,
+        typ: RTPCodecType,
+        direction: RTCRtpTransceiverDirection,
+    ) -> Vec<(u8, String)> {
+        let negotiated_header_extensions = self.negotiated_header_extensions.lock();
+        let mut header_extensions: Vec<(u8, String)> = negotiated_header_extensions
+            .iter()
+            .filter(|(_, e)| {
+                extensions.contains_key(&e.uri)
+                    && e.is_matching_direction(direction)
+                    && (e.is_audio && typ == RTPCodecType::Audio
+                        || e.is_video && typ == RTPCodecType::Video)
+            })
+            .map(|(id, e)| (*id as u8, e.uri.clone()))
+            .collect();
+        header_extensions.sort();
+        header_extensions
+    }
+
     pub(crate) fn get_rtp_parameters_by_kind(
         &self,
         typ: RTPCodecType,
@@ -1,27 +1,33 @@
 #[cfg(test)]
 mod api_test;
 
+pub(crate) mod handshake_limiter;
 pub mod interceptor_registry;
 pub mod media_engine;
+mod peer_connection_registry;
 pub mod setting_engine;
 
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
+use handshake_limiter::HandshakeLimiter;
 use interceptor::registry::Registry;
 use interceptor::Interceptor;
 use media_engine::*;
+use peer_connection_registry::PeerConnectionRegistry;
 use rcgen::KeyPair;
 use setting_engine::*;
+use tokio::time::Instant;
 
 use crate::data_channel::data_channel_parameters::DataChannelParameters;
 use crate::data_channel::RTCDataChannel;
 use crate::dtls_transport::RTCDtlsTransport;
-use crate::error::{Error, Result};
+use crate::error::{flatten_errs, Error, Result};
 use crate::ice_transport::ice_gatherer::{RTCIceGatherOptions, RTCIceGatherer};
 use crate::ice_transport::RTCIceTransport;
 use crate::peer_connection::certificate::RTCCertificate;
 use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::peer_connection_handle::RTCPeerConnectionHandle;
 use crate::peer_connection::RTCPeerConnection;
 use crate::rtp_transceiver::rtp_codec::RTPCodecType;
 use crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver;
@@ -29,6 +35,9 @@ use crate::rtp_transceiver::rtp_sender::RTCRtpSender;
 use crate::sctp_transport::RTCSctpTransport;
 use crate::track::track_local::TrackLocal;
 
+/// How often drain checks whether the PeerConnections became idle
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
 /// API bundles the global functions of the WebRTC and ORTC API.
 /// Some of these functions are also exported globally using the
 /// defaultAPI object. Note that the global version of the API
@@ -37,6 +46,8 @@ pub struct API {
     pub(crate) setting_engine: Arc<SettingEngine>,
     pub(crate) media_engine: Arc<MediaEngine>,
     pub(crate) interceptor_registry: Registry,
+    pub(crate) peer_connections: PeerConnectionRegistry,
+    pub(crate) dtls_handshakes: Arc<HandshakeLimiter>,
 }
 
 impl API {
@@ -45,7 +56,75 @@ impl API {
         &self,
         configuration: RTCConfiguration,
     ) -> Result<RTCPeerConnection> {
-        RTCPeerConnection::new(self, configuration).await
+        if self.peer_connections.is_draining() {
+            return Err(Error::ErrDraining);
+        }
+
+        let pc = RTCPeerConnection::new(self, configuration).await?;
+        self.peer_connections
+            .register(RTCPeerConnectionHandle::new(&pc));
+        Ok(pc)
+    }
+
+    /// peer_connections returns the PeerConnections created by the API that
+    /// aren't closed yet.
+    pub fn peer_connections(&self) -> Vec<RTCPeerConnectionHandle> {
+        self.peer_connections.active()
+    }
+
+    /// is_draining returns true once drain has been called
+    pub fn is_draining(&self) -> bool {
+        self.peer_connections.is_draining()
+    }
+
+    /// dtls_handshakes_in_flight returns how many DTLS handshakes of the
+    /// PeerConnections created by the API are running.
+    pub fn dtls_handshakes_in_flight(&self) -> usize {
+        self.dtls_handshakes.in_flight()
+    }
+
+    /// dtls_handshakes_queued returns how many DTLS handshakes are waiting
+    /// because APIBuilder::with_max_concurrent_dtls_handshakes was reached.
+    pub fn dtls_handshakes_queued(&self) -> usize {
+        self.dtls_handshakes.queued()
+    }
+
+    /// drain gracefully shuts down the PeerConnections of the API, e.g. before
+    /// a server is replaced by a new version. The API stops creating
+    /// PeerConnections and the existing ones refuse new data channels and
+    /// tracks, local or remote. Each PeerConnection is closed as soon as it is
+    /// idle, see [`RTCPeerConnectionHandle::is_idle`], and the ones still busy
+    /// are closed when the grace period elapses. drain returns once all of
+    /// them are closed.
+    pub async fn drain(&self, grace_period: Duration) -> Result<()> {
+        self.peer_connections
+            .draining
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+
+        let deadline = Instant::now() + grace_period;
+        let mut close_errs = vec![];
+        loop {
+            let peer_connections = self.peer_connections();
+            if peer_connections.is_empty() {
+                break;
+            }
+
+            let expired = Instant::now() >= deadline;
+            for pc in peer_connections {
+                if expired || pc.is_idle().await {
+                    if let Err(err) = pc.close().await {
+                        close_errs.push(err);
+                    }
+                }
+            }
+            if expired {
+                break;
+            }
+
+            tokio::time::sleep(DRAIN_POLL_INTERVAL.min(deadline - Instant::now())).await;
+        }
+
+        flatten_errs(close_errs)
     }
 
     /// new_ice_gatherer creates a new ice gatherer.
@@ -95,11 +174,13 @@ impl API {
             certificates = vec![cert];
         };
 
-        Ok(RTCDtlsTransport::new(
+        let mut dtls_transport = RTCDtlsTransport::new(
             ice_transport,
             certificates,
             Arc::clone(&self.setting_engine),
-        ))
+        );
+        dtls_transport.handshake_limiter = Some(Arc::clone(&self.dtls_handshakes));
+        Ok(dtls_transport)
     }
 
     /// new_sctp_transport creates a new SCTPTransport.
@@ -112,6 +193,7 @@ impl API {
         Ok(RTCSctpTransport::new(
             dtls_transport,
             Arc::clone(&self.setting_engine),
+            Arc::clone(&self.peer_connections.draining),
         ))
     }
 
@@ -186,6 +268,7 @@ pub struct APIBuilder {
     setting_engine: Option<Arc<SettingEngine>>,
     media_engine: Option<Arc<MediaEngine>>,
     interceptor_registry: Option<Registry>,
+    max_concurrent_dtls_handshakes: Option<usize>,
 }
 
 impl APIBuilder {
@@ -212,6 +295,8 @@ impl APIBuilder {
             } else {
                 Registry::new()
             },
+            peer_connections: PeerConnectionRegistry::default(),
+            dtls_handshakes: Arc::new(HandshakeLimiter::new(self.max_concurrent_dtls_handshakes)),
         }
     }
 
@@ -235,4 +320,16 @@ impl APIBuilder {
         self.interceptor_registry = Some(interceptor_registry);
         self
     }
+
+    /// with_max_concurrent_dtls_handshakes limits how many DTLS handshakes the
+    /// PeerConnections of the API run at the same time, e.g. to smooth the CPU
+    /// load of a server during a connection storm. Further handshakes wait
+    /// until one of the running ones finishes. By default the number of
+    /// handshakes is unlimited. A limit of 0 is treated as 1. Note that two
+    /// PeerConnections of the same API which connect to each other take two
+    /// permits.
+    pub fn with_max_concurrent_dtls_handshakes(mut self, max_concurrent: usize) -> Self {
+        self.max_concurrent_dtls_handshakes = Some(max_concurrent);
+        self
+    }
 }
@@ -0,0 +1,36 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use portable_atomic::AtomicBool;
+use util::sync::Mutex as SyncMutex;
+
+use crate::peer_connection::peer_connection_handle::RTCPeerConnectionHandle;
+
+/// PeerConnectionRegistry keeps a handle to every PeerConnection an API
+/// created, so they can be listed and drained.
+#[derive(Default)]
+pub(crate) struct PeerConnectionRegistry {
+    /// draining is shared with the PeerConnections, which refuse new data
+    /// channels and tracks once it is set
+    pub(crate) draining: Arc<AtomicBool>,
+    handles: SyncMutex<Vec<RTCPeerConnectionHandle>>,
+}
+
+impl PeerConnectionRegistry {
+    pub(crate) fn is_draining(&self) -> bool {
+        self.draining.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn register(&self, handle: RTCPeerConnectionHandle) {
+        let mut handles = self.handles.lock();
+        handles.retain(|h| !h.is_closed());
+        handles.push(handle);
+    }
+
+    /// active returns the handles of the PeerConnections that aren't closed
+    pub(crate) fn active(&self) -> Vec<RTCPeerConnectionHandle> {
+        let mut handles = self.handles.lock();
+        handles.retain(|h| !h.is_closed());
+        handles.clone()
+    }
+}
@@ -1,19 +1,34 @@
 #[cfg(test)]
 mod setting_engine_test;
 
+use std::net::SocketAddr;
 use std::sync::Arc;
 
 use dtls::extension::extension_use_srtp::SrtpProtectionProfile;
-use ice::agent::agent_config::{InterfaceFilterFn, IpFilterFn};
+use dtls::session::{SessionStore, SessionTicketKey};
+use ice::agent::agent_config::{
+    InterfaceFilterFn, IpFilterFn, NominationMode, StaticCandidate, TransactionIdGeneratorFn,
+};
 use ice::mdns::MulticastDnsMode;
 use ice::network_type::NetworkType;
 use ice::udp_network::UDPNetwork;
+use sdp::description::session::SessionDescription;
+use stun::attributes::AttrType;
+use tokio::runtime::Handle;
 use tokio::time::Duration;
+use turn::client::AllocateRetryPolicy;
 use util::vnet::net::*;
 
+use crate::dtls_transport::dtls_fingerprint::{
+    DEFAULT_FINGERPRINT_ALGORITHM, SUPPORTED_FINGERPRINT_ALGORITHMS,
+};
 use crate::dtls_transport::dtls_role::DTLSRole;
 use crate::error::{Error, Result};
 use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
+use crate::ice_transport::ice_role::RTCIceRole;
+use crate::peer_connection::sdp::sdp_type::RTCSdpType;
+use crate::peer_connection::{SIMULCAST_MAX_PROBE_ROUTINES, SIMULCAST_PROBE_COUNT};
+use crate::rtp_transceiver::rtp_codec::{RTCRtpCodecParameters, RTPCodecType};
 use crate::RECEIVE_MTU;
 
 #[derive(Default, Clone)]
@@ -30,11 +45,16 @@ pub struct Timeout {
     pub ice_srflx_acceptance_min_wait: Option<Duration>,
     pub ice_prflx_acceptance_min_wait: Option<Duration>,
     pub ice_relay_acceptance_min_wait: Option<Duration>,
+    pub ice_gathering_timeout: Option<Duration>,
+    pub ice_relay_gathering_delay: Option<Duration>,
+    pub ice_check_pacing_interval: Option<Duration>,
+    pub idle_timeout: Option<Duration>,
 }
 
 #[derive(Default, Clone)]
 pub struct Candidates {
     pub ice_lite: bool,
+    pub ice_nomination: NominationMode,
     pub ice_network_types: Vec<NetworkType>,
     pub interface_filter: Arc<Option<InterfaceFilterFn>>,
     pub ip_filter: Arc<Option<IpFilterFn>>,
@@ -45,8 +65,25 @@ pub struct Candidates {
     pub username_fragment: String,
     pub password: String,
     pub include_loopback_candidate: bool,
+    pub ice_stun_software: Option<String>,
+    pub disable_ice_stun_fingerprint: bool,
+    pub ice_stun_transaction_id_generator: Arc<Option<TransactionIdGeneratorFn>>,
+    pub ice_stun_attribute_order: Vec<AttrType>,
+    pub ice_relay_allocate_retry: AllocateRetryPolicy,
+    pub ice_static_candidate: Option<StaticCandidate>,
+    pub ice_max_in_flight_checks: usize,
 }
 
+/// OutgoingSdpHookFn is the callback set by [`SettingEngine::set_outgoing_sdp_hook`].
+pub type OutgoingSdpHookFn = dyn Fn(RTCSdpType, &mut SessionDescription) + Send + Sync;
+
+/// CodecFilterFn is the callback set by [`SettingEngine::set_codec_filter`].
+pub type CodecFilterFn = dyn Fn(RTPCodecType, &RTCRtpCodecParameters) -> bool + Send + Sync;
+
+/// RemoteCertificateVerifierFn is the callback set by
+/// [`SettingEngine::set_remote_certificate_verifier`].
+pub type RemoteCertificateVerifierFn = dyn Fn(&[Vec<u8>]) -> bool + Send + Sync;
+
 #[derive(Default, Clone)]
 pub struct ReplayProtection {
     pub dtls: usize,
@@ -54,6 +91,25 @@ pub struct ReplayProtection {
     pub srtcp: usize,
 }
 
+/// DscpPolicy holds the DSCP codepoints of each class of packets, the default follows
+/// the recommendations of RFC 8837.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DscpPolicy {
+    pub audio: u8,
+    pub video: u8,
+    pub data: u8,
+}
+
+impl Default for DscpPolicy {
+    fn default() -> Self {
+        DscpPolicy {
+            audio: 46, // EF
+            video: 34, // AF41
+            data: 10,  // AF11
+        }
+    }
+}
+
 /// SettingEngine allows influencing behavior in ways that are not
 /// supported by the WebRTC API. This allows us to support additional
 /// use-cases without deviating from the WebRTC API elsewhere.
@@ -65,8 +121,15 @@ pub struct SettingEngine {
     pub(crate) replay_protection: ReplayProtection,
     pub(crate) sdp_media_level_fingerprints: bool,
     pub(crate) answering_dtls_role: DTLSRole,
+    pub(crate) ice_role: RTCIceRole,
     pub(crate) disable_certificate_fingerprint_verification: bool,
+    pub(crate) remote_certificate_verifier: Option<Arc<RemoteCertificateVerifierFn>>,
     pub(crate) allow_insecure_verification_algorithm: bool,
+    pub(crate) dtls_session_store: Option<Arc<dyn SessionStore + Send + Sync>>,
+    pub(crate) dtls_session_ticket_key: Option<SessionTicketKey>,
+    pub(crate) dtls_fingerprint_algorithms: Vec<String>,
+    pub(crate) dscp: u8,
+    pub(crate) dscp_policy: Option<DscpPolicy>,
     pub(crate) disable_srtp_replay_protection: bool,
     pub(crate) disable_srtcp_replay_protection: bool,
     pub(crate) vnet: Option<Arc<Net>>,
@@ -77,8 +140,19 @@ pub struct SettingEngine {
     pub(crate) disable_media_engine_copy: bool,
     pub(crate) srtp_protection_profiles: Vec<SrtpProtectionProfile>,
     pub(crate) receive_mtu: usize,
+    pub(crate) simulcast_max_probe_routines: u64,
+    pub(crate) simulcast_probe_count: usize,
     pub(crate) mid_generator: Option<Arc<dyn Fn(isize) -> String + Send + Sync>>,
+    pub(crate) outgoing_sdp_hook: Option<Arc<OutgoingSdpHookFn>>,
+    pub(crate) codec_filter: Option<Arc<CodecFilterFn>>,
     pub(crate) enable_sender_rtx: bool,
+    pub(crate) enable_datagram_channels: bool,
+    pub(crate) sctp_no_delay: bool,
+    pub(crate) sctp_heartbeat_interval: Option<Duration>,
+    pub(crate) sctp_handshake_timeout: Option<Duration>,
+    pub(crate) memory_budget: Option<usize>,
+    pub(crate) max_inbound_ssrcs: Option<usize>,
+    pub(crate) runtime: Option<Handle>,
 }
 
 impl SettingEngine {
@@ -90,6 +164,37 @@ impl SettingEngine {
             RECEIVE_MTU
         }
     }
+
+    /// get_simulcast_max_probe_routines returns the configured limit of simulcast probe routines.
+    /// If it is configured to 0 it returns the default
+    pub(crate) fn get_simulcast_max_probe_routines(&self) -> u64 {
+        if self.simulcast_max_probe_routines != 0 {
+            self.simulcast_max_probe_routines
+        } else {
+            SIMULCAST_MAX_PROBE_ROUTINES
+        }
+    }
+
+    /// get_simulcast_probe_count returns the configured simulcast probe count. If it is configured
+    /// to 0 it returns the default
+    pub(crate) fn get_simulcast_probe_count(&self) -> usize {
+        if self.simulcast_probe_count != 0 {
+            self.simulcast_probe_count
+        } else {
+            SIMULCAST_PROBE_COUNT
+        }
+    }
+
+    /// get_dtls_fingerprint_algorithms returns the algorithms local certificate fingerprints
+    /// are signaled with. If none are configured it returns the default
+    pub(crate) fn get_dtls_fingerprint_algorithms(&self) -> Vec<String> {
+        if !self.dtls_fingerprint_algorithms.is_empty() {
+            self.dtls_fingerprint_algorithms.clone()
+        } else {
+            vec![DEFAULT_FINGERPRINT_ALGORITHM.to_owned()]
+        }
+    }
+
     /// detach_data_channels enables detaching data channels. When enabled
     /// data channels have to be detached in the OnOpen callback using the
     /// DataChannel.Detach method.
@@ -97,6 +202,44 @@ impl SettingEngine {
         self.detach.data_channels = true;
     }
 
+    /// set_sctp_no_delay controls whether the SCTP association carrying the data channels
+    /// bundles the DATA chunks queued together into as few packets as possible, the default,
+    /// or sends each of them in a packet of its own. The setting applies to all the data
+    /// channels of a PeerConnection as they share the association.
+    pub fn set_sctp_no_delay(&mut self, no_delay: bool) {
+        self.sctp_no_delay = no_delay;
+    }
+
+    /// set_sctp_heartbeat_interval makes the SCTP association carrying the data channels send
+    /// a HEARTBEAT to the remote peer at the given interval. A remote that stops acknowledging
+    /// them is detected as unreachable after a few intervals and the association, with the
+    /// data channels on it, is closed. The HEARTBEATs are not STUN and don't refresh the ICE
+    /// consent of the transport. None, the default, sends no HEARTBEAT.
+    pub fn set_sctp_heartbeat_interval(&mut self, interval: Option<Duration>) {
+        self.sctp_heartbeat_interval = interval;
+    }
+
+    /// set_sctp_handshake_timeout bounds the time the SCTP association carrying the data
+    /// channels takes to be established once DTLS is connected. Starting the SCTP transport
+    /// fails when it runs out. None, the default, waits as long as the INIT retransmissions
+    /// take to give up.
+    pub fn set_sctp_handshake_timeout(&mut self, timeout: Option<Duration>) {
+        self.sctp_handshake_timeout = timeout;
+    }
+
+    /// set_memory_budget caps the bytes each PeerConnection buffers, together, in the SCTP
+    /// association carrying its data channels and in the send history the NACK responder
+    /// interceptor keeps to retransmit its packets. The send history can use at most half
+    /// of the budget, so that media can't starve the data channels. Once the budget is used
+    /// up, the data channel messages sent fail with `Error::ErrMemoryBudgetExceeded` of the
+    /// sctp crate, the incoming ones are dropped until the application reads the ones
+    /// received, and the packets sent aren't kept for retransmission. The bytes used are
+    /// reported by the `memory_budget_used` of the PeerConnection stats. None, the default,
+    /// sets no limit.
+    pub fn set_memory_budget(&mut self, limit: Option<usize>) {
+        self.memory_budget = limit;
+    }
+
     /// set_srtp_protection_profiles allows the user to override the default srtp Protection Profiles
     /// The default srtp protection profiles are provided by the function `defaultSrtpProtectionProfiles`
     pub fn set_srtp_protection_profiles(&mut self, profiles: Vec<SrtpProtectionProfile>) {
@@ -118,6 +261,47 @@ impl SettingEngine {
         self.timeout.ice_keepalive_interval = keep_alive_interval;
     }
 
+    /// set_ice_gathering_timeout bounds the time ICE candidate gathering takes. When it elapses
+    /// the gathering state moves to Complete with the candidates gathered so far, and the
+    /// STUN and TURN servers that didn't answer yet are given up on. By default the gathering
+    /// waits for every server to answer or time out.
+    pub fn set_ice_gathering_timeout(&mut self, t: Duration) {
+        self.timeout.ice_gathering_timeout = Some(t);
+    }
+
+    /// set_ice_relay_gathering_delay defers the gathering of relay candidates to spare the
+    /// TURN allocations when a direct path works. The gathering completes without them, and
+    /// resumes to gather them only if ICE hasn't connected this long after the connectivity
+    /// checks started. The late relay candidates are only signaled through on_ice_candidate,
+    /// so the remote peer has to support trickle ICE.
+    pub fn set_ice_relay_gathering_delay(&mut self, t: Duration) {
+        self.timeout.ice_relay_gathering_delay = Some(t);
+    }
+
+    /// set_max_in_flight_checks caps the number of ICE candidate pairs checked at once, the
+    /// others wait for one of them to succeed or fail. The pairs are still checked by decreasing
+    /// priority, so the best ones are tried first. 0, the default, checks all the pairs at once.
+    pub fn set_max_in_flight_checks(&mut self, max: usize) {
+        self.candidates.ice_max_in_flight_checks = max;
+    }
+
+    /// set_ice_check_pacing_interval sets the interval between two ICE connectivity checks,
+    /// smoothing the burst of checks sent when there are many candidate pairs. By default the
+    /// checks are sent at once.
+    pub fn set_ice_check_pacing_interval(&mut self, t: Duration) {
+        self.timeout.ice_check_pacing_interval = Some(t);
+    }
+
+    /// set_idle_timeout closes a PeerConnection once no RTP, RTCP nor SCTP packet was
+    /// received for the duration t, firing on_peer_connection_state_change with Closed.
+    /// It catches the remote peers which disappear without closing the connection sooner
+    /// than the ICE timeouts, since the ICE keepalives don't count as traffic, nor do the
+    /// packets sent to a peer that no longer answers. By
+    /// default PeerConnections are never closed for being idle.
+    pub fn set_idle_timeout(&mut self, t: Duration) {
+        self.timeout.idle_timeout = Some(t);
+    }
+
     /// set_host_acceptance_min_wait sets the icehost_acceptance_min_wait
     pub fn set_host_acceptance_min_wait(&mut self, t: Option<Duration>) {
         self.timeout.ice_host_acceptance_min_wait = t;
@@ -150,6 +334,63 @@ impl SettingEngine {
         self.candidates.ice_lite = lite;
     }
 
+    /// set_ice_nomination configures how the ice agent nominates the candidate pair when it is
+    /// controlling. NominationMode::Aggressive connects faster, but may settle on a worse pair
+    /// than the default NominationMode::Regular.
+    pub fn set_ice_nomination(&mut self, nomination_mode: NominationMode) {
+        self.candidates.ice_nomination = nomination_mode;
+    }
+
+    /// set_ice_stun_software sets the SOFTWARE attribute of the ICE connectivity checks,
+    /// None, the default, leaves it out.
+    pub fn set_ice_stun_software(&mut self, software: Option<String>) {
+        self.candidates.ice_stun_software = software;
+    }
+
+    /// set_ice_relay_allocate_retry sets how the TURN allocations of the relay candidates are
+    /// retried when the TURN server times out or answers with a 5xx error code. By default
+    /// they are not retried.
+    pub fn set_ice_relay_allocate_retry(&mut self, policy: AllocateRetryPolicy) {
+        self.candidates.ice_relay_allocate_retry = policy;
+    }
+
+    /// set_ice_static_candidate makes the ICE agent offer a single host candidate, bound to
+    /// local_addr and advertised with advertised_addr or else the bound address, instead of
+    /// gathering candidates. It is meant for tests and for servers with a known, fixed address.
+    /// With set_udp_network using a UDP mux, the candidate takes the port of the mux. The agent
+    /// fails to be created if the socket can't be bound, e.g. when the address is in use.
+    pub fn set_ice_static_candidate(
+        &mut self,
+        local_addr: SocketAddr,
+        advertised_addr: Option<SocketAddr>,
+    ) {
+        self.candidates.ice_static_candidate = Some(StaticCandidate {
+            local_addr,
+            advertised_addr,
+        });
+    }
+
+    /// disable_ice_stun_fingerprint leaves the FINGERPRINT attribute out of the ICE
+    /// connectivity checks. RFC 8445 requires it, so the remote agent may drop the checks.
+    pub fn disable_ice_stun_fingerprint(&mut self, is_disabled: bool) {
+        self.candidates.disable_ice_stun_fingerprint = is_disabled;
+    }
+
+    /// set_ice_stun_transaction_id_generator sets the function generating the transaction IDs
+    /// of the ICE connectivity checks. The IDs have to be unique, as responses are matched to
+    /// the checks by them.
+    pub fn set_ice_stun_transaction_id_generator(&mut self, generator: TransactionIdGeneratorFn) {
+        self.candidates.ice_stun_transaction_id_generator = Arc::new(Some(generator));
+    }
+
+    /// set_ice_stun_attribute_order orders the attributes of the ICE connectivity checks.
+    /// The listed attributes come first, MESSAGE-INTEGRITY and FINGERPRINT always last.
+    /// Together with the options above this makes the checks match, or differ from, the
+    /// STUN messages of a specific client.
+    pub fn set_ice_stun_attribute_order(&mut self, order: Vec<AttrType>) {
+        self.candidates.ice_stun_attribute_order = order;
+    }
+
     /// set_network_types configures what types of candidate networks are supported
     /// during local and server reflexive gathering.
     pub fn set_network_types(&mut self, candidate_types: Vec<NetworkType>) {
@@ -217,6 +458,17 @@ impl SettingEngine {
         Ok(())
     }
 
+    /// set_ice_role forces the role the ICE agent takes, whatever the role derived from
+    /// the offer/answer exchange and the ice-lite attributes (RFC 8445 S6.1.1) is. This
+    /// may be useful in test setups or when debugging issues with tie-breaking. Both agents
+    /// ending up controlling, or both controlled, never connect, so a misused override breaks
+    /// the negotiation.
+    ///
+    /// RTCIceRole::Unspecified, the default, derives the role.
+    pub fn set_ice_role(&mut self, role: RTCIceRole) {
+        self.ice_role = role;
+    }
+
     /// set_vnet sets the VNet instance that is passed to ice
     /// VNet is a virtual network layer, allowing users to simulate
     /// different topologies, latency, loss and jitter. This can be useful for
@@ -249,11 +501,53 @@ impl SettingEngine {
         self.disable_certificate_fingerprint_verification = is_disabled;
     }
 
+    /// set_remote_certificate_verifier sets a callback invoked during the DTLS handshake with
+    /// the DER encoded certificate chain of the remote peer, e.g. to check it against a pinned
+    /// public key or a private CA. Returning false aborts the handshake and the DTLS transport
+    /// fails with [`Error::ErrRemoteCertificateRejected`](crate::Error::ErrRemoteCertificateRejected).
+    ///
+    /// The certificate still has to match the fingerprint of the remote SessionDescription,
+    /// so a peer able to rewrite the signaling can't impersonate the remote without a
+    /// certificate the callback accepts.
+    pub fn set_remote_certificate_verifier(
+        &mut self,
+        f: impl Fn(&[Vec<u8>]) -> bool + Send + Sync + 'static,
+    ) {
+        self.remote_certificate_verifier = Some(Arc::new(f));
+    }
+
     /// allow_insecure_verification_algorithm allows the usage of certain signature verification
     /// algorithm that are known to be vulnerable or deprecated.
     pub fn allow_insecure_verification_algorithm(&mut self, is_allowed: bool) {
         self.allow_insecure_verification_algorithm = is_allowed;
     }
+
+    /// set_dtls_session_store lets reconnecting peers resume an earlier DTLS session with
+    /// an abbreviated handshake, saving a round trip and the key exchange before data
+    /// channels and SRTP are up. As DTLS client a session is reused when the remote offers
+    /// the same certificate fingerprint again, as DTLS server the store is looked up by
+    /// the session id the client offers. Share one store between the PeerConnections that
+    /// should be able to resume each other's sessions, e.g. dtls::session::MemorySessionStore.
+    ///
+    /// Resumed connections reuse the master secret of the original handshake, so they
+    /// don't have forward secrecy: anyone who obtains a stored session can decrypt every
+    /// connection resumed from it. The store decides how long sessions live.
+    pub fn set_dtls_session_store(&mut self, store: Arc<dyn SessionStore + Send + Sync>) {
+        self.dtls_session_store = Some(store);
+    }
+
+    /// set_dtls_session_ticket_key lets PeerConnections that are the DTLS server issue
+    /// session tickets, RFC 5077, and resume the sessions of the tickets clients present
+    /// without keeping the sessions themselves. Clients with a session store ask for
+    /// tickets and keep them in their store. Use the same key for the PeerConnections
+    /// that should accept each other's tickets.
+    ///
+    /// Anyone who obtains the key can decrypt the master secret of every ticket it sealed,
+    /// so replace it regularly; tickets of a replaced key fall back to a full handshake.
+    pub fn set_dtls_session_ticket_key(&mut self, key: SessionTicketKey) {
+        self.dtls_session_ticket_key = Some(key);
+    }
+
     /// set_dtls_replay_protection_window sets a replay attack protection window size of dtls_transport connection.
     pub fn set_dtls_replay_protection_window(&mut self, n: usize) {
         self.replay_protection.dtls = n;
@@ -290,6 +584,40 @@ impl SettingEngine {
         self.candidates.include_loopback_candidate = allow_loopback;
     }
 
+    /// set_dscp marks the packets sent over the ICE transport with a DSCP codepoint for QoS,
+    /// e.g. 46 (EF) for audio or 34 (AF41) for video. 0, the default, leaves them unmarked.
+    /// The marking applies to the whole transport, so when audio and video are bundled they
+    /// share the codepoint; use separate PeerConnections to mark them differently. Only
+    /// packets sent from host and server reflexive candidates over IPv4 are marked, the
+    /// socket of a UDPMux has to be marked by the application.
+    pub fn set_dscp(&mut self, dscp: u8) -> Result<()> {
+        if dscp > 0x3f {
+            return Err(Error::ErrSettingEngineSetDscp);
+        }
+
+        self.dscp = dscp;
+        Ok(())
+    }
+
+    /// set_dscp_policy marks audio, video and data packets with distinct DSCP codepoints,
+    /// also when they are bundled on one transport. The socket of the selected candidate
+    /// pair is re-marked before a packet of another class is sent, which serializes the
+    /// sends of a transport. RTP and RTCP are classified by the kind of the sender that
+    /// owns their SSRC, unknown SSRCs count as video, DTLS and SCTP count as data, STUN
+    /// keeps the marking of the previous packet. Only native IPv4 UDP sockets can be
+    /// marked; elsewhere, e.g. on a UDPMux, TURN or on platforms without IP_TOS, the
+    /// packets keep the single codepoint of set_dscp, if any. None turns the policy off.
+    pub fn set_dscp_policy(&mut self, policy: Option<DscpPolicy>) -> Result<()> {
+        if let Some(policy) = &policy {
+            if policy.audio > 0x3f || policy.video > 0x3f || policy.data > 0x3f {
+                return Err(Error::ErrSettingEngineSetDscpPolicy);
+            }
+        }
+
+        self.dscp_policy = policy;
+        Ok(())
+    }
+
     /// set_sdp_media_level_fingerprints configures the logic for dtls_transport Fingerprint insertion
     /// If true, fingerprints will be inserted in the sdp at the fingerprint
     /// level, instead of the session level. This helps with compatibility with
@@ -298,6 +626,24 @@ impl SettingEngine {
         self.sdp_media_level_fingerprints = sdp_media_level_fingerprints;
     }
 
+    /// set_dtls_fingerprint_algorithms selects the hash function algorithms the fingerprints of
+    /// the local certificates are signaled with in the SessionDescription, in the given order.
+    /// This helps with compatibility with peers that expect a fingerprint other than sha-256.
+    /// Supported are "sha-1", "sha-256", "sha-384" and "sha-512", an empty list restores the
+    /// default of "sha-256".
+    pub fn set_dtls_fingerprint_algorithms(&mut self, algorithms: Vec<String>) -> Result<()> {
+        let algorithms: Vec<String> = algorithms.iter().map(|a| a.to_lowercase()).collect();
+        if algorithms
+            .iter()
+            .any(|a| !SUPPORTED_FINGERPRINT_ALGORITHMS.contains(&a.as_str()))
+        {
+            return Err(Error::ErrUnsupportedFingerprintAlgorithm);
+        }
+
+        self.dtls_fingerprint_algorithms = algorithms;
+        Ok(())
+    }
+
     // SetICETCPMux enables ICE-TCP when set to a non-nil value. Make sure that
     // NetworkTypeTCP4 or NetworkTypeTCP6 is enabled as well.
     //pub fn SetICETCPMux(&mut self, tcpMux ice.TCPMux) {
@@ -322,6 +668,32 @@ impl SettingEngine {
         self.receive_mtu = receive_mtu;
     }
 
+    /// set_simulcast_max_probe_routines limits how many incoming SSRCs without a negotiated
+    /// track are probed for their mid and rid at the same time, fewer than max_probe_routines.
+    /// SSRCs arriving while max_probe_routines - 1 are being probed are ignored and on_track
+    /// won't fire for them.
+    /// Leave this 0 for the default of 25
+    pub fn set_simulcast_max_probe_routines(&mut self, max_probe_routines: u64) {
+        self.simulcast_max_probe_routines = max_probe_routines;
+    }
+
+    /// set_max_inbound_ssrcs caps how many distinct SSRCs the remote can send RTP or RTCP on.
+    /// New SSRCs which weren't signaled are dropped once the cap is reached, without probing
+    /// them or firing on_track, so a peer sending packets with many SSRCs can't exhaust the
+    /// resources of the PeerConnection. The SSRCs in use and the streams dropped are reported
+    /// by `inbound_ssrcs` and `inbound_ssrcs_dropped` of the PeerConnection stats.
+    /// None, the default, sets no cap.
+    pub fn set_max_inbound_ssrcs(&mut self, max_inbound_ssrcs: Option<usize>) {
+        self.max_inbound_ssrcs = max_inbound_ssrcs;
+    }
+
+    /// set_simulcast_probe_count sets how many RTP packets of an incoming SSRC without a
+    /// negotiated track are read while looking for its mid and rid before giving up.
+    /// Leave this 0 for the default of 10
+    pub fn set_simulcast_probe_count(&mut self, probe_count: usize) {
+        self.simulcast_probe_count = probe_count;
+    }
+
     /// Sets a callback used to generate mid for transceivers created by this side of the RTCPeerconnection.
     /// By having separate "naming schemes" for mids generated by either side of a connection, it's
     /// possible to reduce complexity when handling SDP offers/answers clashing.
@@ -336,10 +708,53 @@ impl SettingEngine {
         self.mid_generator = Some(Arc::new(f));
     }
 
+    /// Sets a callback invoked by create_offer and create_answer with the generated
+    /// SessionDescription, right before it is marshaled. The callback may modify it, e.g. to add
+    /// an application specific attribute, and the RTCSessionDescription returned carries the
+    /// modified description.
+    ///
+    /// The modified description is applied by set_local_description like any other one, so the
+    /// callback must keep it consistent with the state of the RTCPeerConnection.
+    pub fn set_outgoing_sdp_hook(
+        &mut self,
+        f: impl Fn(RTCSdpType, &mut SessionDescription) + Send + Sync + 'static,
+    ) {
+        self.outgoing_sdp_hook = Some(Arc::new(f));
+    }
+
+    /// set_codec_filter sets a callback consulted for each codec of a transceiver every time
+    /// create_offer and create_answer generate its media section. The codecs it returns false
+    /// for aren't offered nor answered, along with their RTX codec, e.g. to stop offering H264
+    /// once its hardware encoder became unavailable. Unlike the codecs registered in the
+    /// MediaEngine, the callback can change its mind between two negotiations.
+    ///
+    /// A transceiver sending a track that is left without any codec fails the negotiation
+    /// with [`Error::ErrSenderWithNoCodecs`], a transceiver only receiving is rejected.
+    pub fn set_codec_filter(
+        &mut self,
+        f: impl Fn(RTPCodecType, &RTCRtpCodecParameters) -> bool + Send + Sync + 'static,
+    ) {
+        self.codec_filter = Some(Arc::new(f));
+    }
+
     /// enable_sender_rtx allows outgoing rtx streams to be created where applicable.
     /// RTPSender will create an RTP retransmission stream for each source stream where a retransmission
     /// codec is configured.
     pub fn enable_sender_rtx(&mut self, is_enabled: bool) {
         self.enable_sender_rtx = is_enabled;
     }
+
+    /// set_runtime_handle makes the PeerConnection spawn its internal tasks on the given runtime
+    /// instead of the ambient one, the one the API is called from. WebRTC work can then be kept
+    /// on dedicated threads, away from the runtime of the application.
+    pub fn set_runtime_handle(&mut self, runtime: Handle) {
+        self.runtime = Some(runtime);
+    }
+
+    /// enable_datagram_channels allows RTCPeerConnection::create_datagram_channel to be used.
+    /// Datagrams share the DTLS transport with SCTP, so both peers have to enable them, otherwise
+    /// the remote SCTP association silently discards the datagrams.
+    pub fn enable_datagram_channels(&mut self, is_enabled: bool) {
+        self.enable_datagram_channels = is_enabled;
+    }
 }
@@ -1,8 +1,11 @@
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use waitgroup::WaitGroup;
 
 use super::*;
 use crate::api::media_engine::MediaEngine;
 use crate::api::APIBuilder;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::*;
 use crate::rtp_transceiver::rtp_codec::RTPCodecType;
 
@@ -51,6 +54,35 @@ fn test_detach_data_channels() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_set_simulcast_probing() -> Result<()> {
+    let mut s = SettingEngine::default();
+
+    assert_eq!(
+        s.get_simulcast_max_probe_routines(),
+        SIMULCAST_MAX_PROBE_ROUTINES
+    );
+    assert_eq!(s.get_simulcast_probe_count(), SIMULCAST_PROBE_COUNT);
+
+    s.set_simulcast_max_probe_routines(100);
+    s.set_simulcast_probe_count(4);
+    assert_eq!(s.get_simulcast_max_probe_routines(), 100);
+    assert_eq!(s.get_simulcast_probe_count(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_set_sctp_handshake_timeout() -> Result<()> {
+    let mut s = SettingEngine::default();
+    assert_eq!(s.sctp_handshake_timeout, None);
+
+    s.set_sctp_handshake_timeout(Some(Duration::from_secs(5)));
+    assert_eq!(s.sctp_handshake_timeout, Some(Duration::from_secs(5)));
+
+    Ok(())
+}
+
 #[test]
 fn test_set_nat_1to1_ips() -> Result<()> {
     let mut s = SettingEngine::default();
@@ -94,6 +126,138 @@ fn test_set_answering_dtls_role() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_set_dtls_fingerprint_algorithms() -> Result<()> {
+    let mut s = SettingEngine::default();
+    assert_eq!(
+        s.get_dtls_fingerprint_algorithms(),
+        vec!["sha-256".to_owned()]
+    );
+
+    s.set_dtls_fingerprint_algorithms(vec!["SHA-384".to_owned(), "sha-1".to_owned()])?;
+    assert_eq!(
+        s.get_dtls_fingerprint_algorithms(),
+        vec!["sha-384".to_owned(), "sha-1".to_owned()]
+    );
+
+    assert!(
+        s.set_dtls_fingerprint_algorithms(vec!["md5".to_owned()])
+            .is_err(),
+        "set_dtls_fingerprint_algorithms only accepts supported algorithms"
+    );
+
+    s.set_dtls_fingerprint_algorithms(vec![])?;
+    assert_eq!(
+        s.get_dtls_fingerprint_algorithms(),
+        vec!["sha-256".to_owned()]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_set_dscp() -> Result<()> {
+    let mut s = SettingEngine::default();
+    s.set_dscp(46)?;
+    assert_eq!(s.dscp, 46);
+
+    assert!(
+        s.set_dscp(64).is_err(),
+        "set_dscp can only be called with a 6-bit codepoint"
+    );
+    assert_eq!(s.dscp, 46);
+
+    Ok(())
+}
+
+#[test]
+fn test_set_dscp_policy() -> Result<()> {
+    let mut s = SettingEngine::default();
+    s.set_dscp_policy(Some(DscpPolicy::default()))?;
+    assert_eq!(
+        s.dscp_policy,
+        Some(DscpPolicy {
+            audio: 46,
+            video: 34,
+            data: 10
+        })
+    );
+
+    assert!(
+        s.set_dscp_policy(Some(DscpPolicy {
+            video: 64,
+            ..Default::default()
+        }))
+        .is_err(),
+        "set_dscp_policy can only be called with 6-bit codepoints"
+    );
+    assert!(s.dscp_policy.is_some());
+
+    s.set_dscp_policy(None)?;
+    assert!(s.dscp_policy.is_none());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_ice_nomination() -> Result<()> {
+    let mut s = SettingEngine::default();
+    assert_eq!(s.candidates.ice_nomination, NominationMode::Regular);
+    s.set_ice_nomination(NominationMode::Aggressive);
+    assert_eq!(s.candidates.ice_nomination, NominationMode::Aggressive);
+
+    // Regular nomination would wait an hour to nominate a host pair
+    s.set_host_acceptance_min_wait(Some(Duration::from_secs(3600)));
+    let api = APIBuilder::new().with_setting_engine(s).build();
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut pc_answer, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    wg.wait().await;
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_ice_stun_requests() -> Result<()> {
+    let mut s = SettingEngine::default();
+    s.set_ice_stun_software(Some("client".to_owned()));
+    s.disable_ice_stun_fingerprint(true);
+    let counter = AtomicU64::new(0);
+    s.set_ice_stun_transaction_id_generator(Box::new(move || {
+        let mut id = [0u8; 12];
+        id[4..].copy_from_slice(&counter.fetch_add(1, Ordering::SeqCst).to_be_bytes());
+        stun::agent::TransactionId(id)
+    }));
+    s.set_ice_stun_attribute_order(vec![
+        stun::attributes::ATTR_SOFTWARE,
+        stun::attributes::ATTR_PRIORITY,
+    ]);
+    assert_eq!(s.candidates.ice_stun_software.as_deref(), Some("client"));
+    assert!(s.candidates.disable_ice_stun_fingerprint);
+    assert!(s.candidates.ice_stun_transaction_id_generator.is_some());
+
+    // the reshaped checks still connect
+    let api = APIBuilder::new().with_setting_engine(s).build();
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut pc_answer, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    wg.wait().await;
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
 #[test]
 fn test_set_replay_protection() -> Result<()> {
     let mut s = SettingEngine::default();
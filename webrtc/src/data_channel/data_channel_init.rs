@@ -1,3 +1,5 @@
+use super::data_channel_priority::RTCPriorityType;
+
 /// DataChannelConfig can be used to configure properties of the underlying
 /// channel such as data reliability.
 ///
@@ -32,4 +34,9 @@ pub struct RTCDataChannelInit {
     /// to negotiate the channel and create an DataChannel with the same id
     /// at the other peer.
     pub negotiated: Option<u16>,
+
+    /// priority describes how the channel shares the SCTP congestion window with
+    /// the other channels of the PeerConnection. The default value of None
+    /// gives it the low priority, like every other channel by default.
+    pub priority: Option<RTCPriorityType>,
 }
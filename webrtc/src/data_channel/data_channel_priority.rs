@@ -0,0 +1,122 @@
+use std::fmt;
+
+use data::message::message_channel_open::{
+    CHANNEL_PRIORITY_BELOW_NORMAL, CHANNEL_PRIORITY_EXTRA_HIGH, CHANNEL_PRIORITY_HIGH,
+    CHANNEL_PRIORITY_NORMAL,
+};
+use serde::{Deserialize, Serialize};
+
+/// PriorityType indicates the priority of a data channel. Under congestion, the
+/// data channels with messages to send share the SCTP congestion window in
+/// proportion to their priority, each level getting twice the share of the one
+/// below it.
+///
+/// ## Specifications
+///
+/// * [W3C]
+/// * [RFC 8831]
+///
+/// [W3C]: https://w3c.github.io/webrtc-priority/#rtc-priority-type
+/// [RFC 8831]: https://www.rfc-editor.org/rfc/rfc8831#section-6.4
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RTCPriorityType {
+    #[serde(rename = "very-low")]
+    VeryLow,
+
+    /// PriorityTypeLow is the default priority of the data channels.
+    #[serde(rename = "low")]
+    #[default]
+    Low,
+
+    #[serde(rename = "medium")]
+    Medium,
+
+    #[serde(rename = "high")]
+    High,
+}
+
+const PRIORITY_TYPE_VERY_LOW_STR: &str = "very-low";
+const PRIORITY_TYPE_LOW_STR: &str = "low";
+const PRIORITY_TYPE_MEDIUM_STR: &str = "medium";
+const PRIORITY_TYPE_HIGH_STR: &str = "high";
+
+/// Maps the priority of a DATA_CHANNEL_OPEN message to its level, RFC 8831 section 6.4.
+impl From<u16> for RTCPriorityType {
+    fn from(v: u16) -> Self {
+        if v <= CHANNEL_PRIORITY_BELOW_NORMAL {
+            RTCPriorityType::VeryLow
+        } else if v <= CHANNEL_PRIORITY_NORMAL {
+            RTCPriorityType::Low
+        } else if v <= CHANNEL_PRIORITY_HIGH {
+            RTCPriorityType::Medium
+        } else {
+            RTCPriorityType::High
+        }
+    }
+}
+
+impl From<RTCPriorityType> for u16 {
+    fn from(v: RTCPriorityType) -> Self {
+        match v {
+            RTCPriorityType::VeryLow => CHANNEL_PRIORITY_BELOW_NORMAL,
+            RTCPriorityType::Low => CHANNEL_PRIORITY_NORMAL,
+            RTCPriorityType::Medium => CHANNEL_PRIORITY_HIGH,
+            RTCPriorityType::High => CHANNEL_PRIORITY_EXTRA_HIGH,
+        }
+    }
+}
+
+impl fmt::Display for RTCPriorityType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            RTCPriorityType::VeryLow => PRIORITY_TYPE_VERY_LOW_STR,
+            RTCPriorityType::Low => PRIORITY_TYPE_LOW_STR,
+            RTCPriorityType::Medium => PRIORITY_TYPE_MEDIUM_STR,
+            RTCPriorityType::High => PRIORITY_TYPE_HIGH_STR,
+        };
+        write!(f, "{s}")
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_priority_type_channel_priority() {
+        let tests = vec![
+            (0, RTCPriorityType::VeryLow),
+            (128, RTCPriorityType::VeryLow),
+            (129, RTCPriorityType::Low),
+            (256, RTCPriorityType::Low),
+            (512, RTCPriorityType::Medium),
+            (513, RTCPriorityType::High),
+            (1024, RTCPriorityType::High),
+            (u16::MAX, RTCPriorityType::High),
+        ];
+
+        for (channel_priority, expected_priority) in tests {
+            let priority = RTCPriorityType::from(channel_priority);
+            assert_eq!(priority, expected_priority, "testCase: {channel_priority}");
+            assert_eq!(
+                RTCPriorityType::from(u16::from(priority)),
+                priority,
+                "testCase: {channel_priority}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_priority_type_string() {
+        let tests = vec![
+            (RTCPriorityType::VeryLow, "very-low"),
+            (RTCPriorityType::Low, "low"),
+            (RTCPriorityType::Medium, "medium"),
+            (RTCPriorityType::High, "high"),
+        ];
+
+        for (priority, expected_string) in tests {
+            assert_eq!(priority.to_string(), expected_string)
+        }
+    }
+}
@@ -7,9 +7,11 @@ use tokio::time::Duration;
 use waitgroup::WaitGroup;
 
 use super::*;
-use crate::api::media_engine::MediaEngine;
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_VP8};
+use crate::api::setting_engine::SettingEngine;
 use crate::api::{APIBuilder, API};
 use crate::data_channel::data_channel_init::RTCDataChannelInit;
+use crate::data_channel::data_channel_priority::RTCPriorityType;
 //use log::LevelFilter;
 //use std::io::Write;
 use crate::dtls_transport::dtls_parameters::DTLSParameters;
@@ -23,8 +25,11 @@ use crate::ice_transport::ice_role::RTCIceRole;
 use crate::ice_transport::RTCIceTransport;
 use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::peer_connection_test::*;
-use crate::peer_connection::RTCPeerConnection;
+use crate::peer_connection::{OnDataChannelHdlrFn, RTCPeerConnection};
+use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
 use crate::sctp_transport::sctp_transport_capabilities::SCTPTransportCapabilities;
+use crate::sctp_transport::sctp_transport_state::RTCSctpTransportState;
+use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
 
 // EXPECTED_LABEL represents the label of the data channel we are trying to test.
 // Some other channels may have been created during initialization (in the Wasm
@@ -610,6 +615,58 @@ async fn test_data_channel_parameters_protocol_exchange() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_data_channel_parameters_priority_exchange() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let options = RTCDataChannelInit {
+        priority: Some(RTCPriorityType::High),
+        ..Default::default()
+    };
+
+    let (mut offer_pc, mut answer_pc, dc, done_tx, done_rx) =
+        set_up_data_channel_parameters_test(&api, Some(options)).await?;
+
+    // Check if parameters are correctly set
+    assert_eq!(
+        RTCPriorityType::High,
+        dc.priority(),
+        "Priority should match DataChannelConfig"
+    );
+
+    let done_tx = Arc::new(Mutex::new(Some(done_tx)));
+    answer_pc.on_data_channel(Box::new(move |d: Arc<RTCDataChannel>| {
+        // Make sure this is the data channel we were looking for. (Not the one
+        // created in signalPair).
+        if d.label() != EXPECTED_LABEL {
+            assert_eq!(
+                RTCPriorityType::Low,
+                d.priority(),
+                "Priority should default to low"
+            );
+            return Box::pin(async {});
+        }
+        // Check if parameters are correctly set
+        assert_eq!(
+            RTCPriorityType::High,
+            d.priority(),
+            "Priority should match what channel creator declared"
+        );
+
+        let done_tx2 = Arc::clone(&done_tx);
+        Box::pin(async move {
+            let mut done = done_tx2.lock().await;
+            done.take();
+        })
+    }));
+
+    close_reliability_param_test(&mut offer_pc, &mut answer_pc, done_rx).await?;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_data_channel_parameters_negotiated_exchange() -> Result<()> {
     let mut m = MediaEngine::default();
@@ -1375,6 +1432,153 @@ async fn test_data_channel_non_standard_session_description() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_data_channel_max_message_size() -> Result<()> {
+    let api = APIBuilder::new().build();
+
+    let (offer_pc, answer_pc) = new_pair(&api).await?;
+
+    let dc = offer_pc.create_data_channel(EXPECTED_LABEL, None).await?;
+    assert_eq!(dc.max_message_size().await, None);
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    dc.on_open(Box::new(move || {
+        Box::pin(async move {
+            let _ = open_tx.send(()).await;
+        })
+    }));
+
+    let offer = offer_pc.create_offer(None).await?;
+    let mut offer_gathering_complete = offer_pc.gathering_complete_promise().await;
+    offer_pc.set_local_description(offer).await?;
+    let _ = offer_gathering_complete.recv().await;
+
+    let offer = offer_pc.local_description().await.unwrap();
+    assert!(offer.sdp.contains("a=max-message-size:65536\r\n"));
+    answer_pc.set_remote_description(offer).await?;
+
+    let answer = answer_pc.create_answer(None).await?;
+    let mut answer_gathering_complete = answer_pc.gathering_complete_promise().await;
+    answer_pc.set_local_description(answer).await?;
+    let _ = answer_gathering_complete.recv().await;
+
+    // The answerer only accepts small messages
+    let mut answer = answer_pc.local_description().await.unwrap();
+    answer.sdp = answer
+        .sdp
+        .replace("a=max-message-size:65536", "a=max-message-size:1024");
+    offer_pc.set_remote_description(answer).await?;
+
+    let _ = open_rx.recv().await;
+
+    assert_eq!(dc.max_message_size().await, Some(1024));
+    assert_eq!(dc.send(&Bytes::from(vec![0u8; 1024])).await?, 1024);
+    assert!(matches!(
+        dc.send(&Bytes::from(vec![0u8; 1025])).await,
+        Err(Error::ErrMessageTooLarge(1024))
+    ));
+    assert!(matches!(
+        dc.send_text("a".repeat(1025)).await,
+        Err(Error::ErrMessageTooLarge(1024))
+    ));
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_data_channel_outlives_stopped_transceivers() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let mut s = SettingEngine::default();
+    s.set_sctp_heartbeat_interval(Some(Duration::from_millis(50)));
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+
+    let (mut offer_pc, mut answer_pc) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    offer_pc.add_track(track).await?;
+
+    let (message_tx, mut message_rx) = mpsc::channel::<Bytes>(1);
+    answer_pc.on_data_channel(Box::new(move |d: Arc<RTCDataChannel>| {
+        if d.label() != EXPECTED_LABEL {
+            return Box::pin(async {});
+        }
+        let message_tx = message_tx.clone();
+        Box::pin(async move {
+            d.on_message(Box::new(move |msg: DataChannelMessage| {
+                let message_tx = message_tx.clone();
+                Box::pin(async move {
+                    let _ = message_tx.send(msg.data).await;
+                })
+            }));
+        })
+    }));
+
+    let dc = offer_pc.create_data_channel(EXPECTED_LABEL, None).await?;
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    dc.on_open(Box::new(move || {
+        Box::pin(async move {
+            let _ = open_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut offer_pc, &mut answer_pc).await?;
+    let _ = open_rx.recv().await;
+
+    dc.send_text("with media".to_owned()).await?;
+    assert_eq!(
+        message_rx.recv().await,
+        Some(Bytes::from_static(b"with media"))
+    );
+
+    // Stop every transceiver and renegotiate, which rejects all the media sections
+    for transceiver in offer_pc.get_transceivers().await {
+        transceiver.stop().await?;
+    }
+
+    let offer = offer_pc.create_offer(None).await?;
+    offer_pc.set_local_description(offer).await?;
+    answer_pc
+        .set_remote_description(offer_pc.local_description().await.unwrap())
+        .await?;
+    let answer = answer_pc.create_answer(None).await?;
+    answer_pc.set_local_description(answer).await?;
+    offer_pc
+        .set_remote_description(answer_pc.local_description().await.unwrap())
+        .await?;
+
+    // No media nor message flows for a while, only the heartbeats. The remote
+    // acknowledges them, so the association isn't taken for dead however many are sent.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    for pc in [&offer_pc, &answer_pc] {
+        assert_eq!(pc.sctp().state(), RTCSctpTransportState::Connected);
+    }
+    assert_eq!(dc.ready_state(), RTCDataChannelState::Open);
+
+    dc.send_text("without media".to_owned()).await?;
+    assert_eq!(
+        message_rx.recv().await,
+        Some(Bytes::from_static(b"without media"))
+    );
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
+
 struct TestOrtcStack {
     //api      *API
     gatherer: Arc<RTCIceGatherer>,
@@ -1611,3 +1815,58 @@ async fn test_data_channel_ortc_e2e() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_data_channel_with_prefix() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut offer_pc, mut answer_pc) = new_pair(&api).await?;
+
+    let (handled_tx, mut handled_rx) = mpsc::channel::<(&'static str, String)>(8);
+    let handler = |name: &'static str| -> OnDataChannelHdlrFn {
+        let handled_tx = handled_tx.clone();
+        Box::new(move |d: Arc<RTCDataChannel>| {
+            let handled_tx = handled_tx.clone();
+            Box::pin(async move {
+                let _ = handled_tx.send((name, d.label().to_owned())).await;
+            })
+        })
+    };
+    answer_pc.on_data_channel(handler("default"));
+    answer_pc.on_data_channel_with_prefix("ctrl/", handler("ctrl"));
+    answer_pc.on_data_channel_with_prefix("file/", handler("stale"));
+    answer_pc.on_data_channel_with_prefix("file/", handler("file"));
+    answer_pc.on_data_channel_with_prefix("file/big/", handler("big file"));
+
+    let labels = ["ctrl/0", "file/1", "file/big/2", "Ctrl/3", "chat"];
+    for label in labels {
+        offer_pc.create_data_channel(label, None).await?;
+    }
+
+    signal_pair(&mut offer_pc, &mut answer_pc).await?;
+
+    let mut handled = vec![];
+    for _ in labels {
+        let h = tokio::time::timeout(Duration::from_secs(5), handled_rx.recv())
+            .await
+            .expect("data channel not handled");
+        handled.extend(h);
+    }
+    handled.sort_by(|a, b| a.1.cmp(&b.1));
+    assert_eq!(
+        handled,
+        vec![
+            ("default", "Ctrl/3".to_owned()),
+            ("default", "chat".to_owned()),
+            ("ctrl", "ctrl/0".to_owned()),
+            ("file", "file/1".to_owned()),
+            ("big file", "file/big/2".to_owned()),
+        ]
+    );
+
+    close_pair_now(&offer_pc, &answer_pc).await;
+
+    Ok(())
+}
@@ -4,6 +4,7 @@ mod data_channel_test;
 pub mod data_channel_init;
 pub mod data_channel_message;
 pub mod data_channel_parameters;
+pub mod data_channel_priority;
 pub mod data_channel_state;
 
 use std::future::Future;
@@ -17,9 +18,11 @@ use bytes::Bytes;
 use data::message::message_channel_open::ChannelType;
 use data_channel_message::*;
 use data_channel_parameters::*;
+use data_channel_priority::RTCPriorityType;
 use data_channel_state::RTCDataChannelState;
 use portable_atomic::{AtomicBool, AtomicU16, AtomicU8, AtomicUsize};
 use sctp::stream::OnBufferedAmountLowFn;
+use tokio::runtime::Handle;
 use tokio::sync::{Mutex, Notify};
 use util::sync::Mutex as SyncMutex;
 
@@ -64,6 +67,7 @@ pub struct RTCDataChannel {
     pub(crate) max_retransmits: Option<u16>,
     pub(crate) protocol: String,
     pub(crate) negotiated: bool,
+    pub(crate) priority: RTCPriorityType,
     pub(crate) id: AtomicU16,
     pub(crate) ready_state: Arc<AtomicU8>, // DataChannelState
     pub(crate) buffered_amount_low_threshold: AtomicUsize,
@@ -112,6 +116,7 @@ impl RTCDataChannel {
             ordered: params.ordered,
             max_packet_lifetime: params.max_packet_life_time,
             max_retransmits: params.max_retransmits,
+            priority: params.priority,
             ready_state: Arc::new(AtomicU8::new(RTCDataChannelState::Connecting as u8)),
             detach_called: Arc::new(AtomicBool::new(false)),
 
@@ -168,7 +173,7 @@ impl RTCDataChannel {
 
             let cfg = data::data_channel::Config {
                 channel_type,
-                priority: data::message::message_channel_open::CHANNEL_PRIORITY_NORMAL,
+                priority: self.priority.into(),
                 reliability_parameter,
                 label: self.label.clone(),
                 protocol: self.protocol.clone(),
@@ -231,7 +236,7 @@ impl RTCDataChannel {
 
         let detach_data_channels = self.setting_engine.detach.data_channels;
         let detach_called = Arc::clone(&self.detach_called);
-        tokio::spawn(async move {
+        crate::spawn(self.setting_engine.runtime.as_ref(), async move {
             if let Some(f) = on_open_handler {
                 f().await;
 
@@ -285,8 +290,10 @@ impl RTCDataChannel {
             let on_close_handler = Arc::clone(&self.on_close_handler);
             let on_error_handler = Arc::clone(&self.on_error_handler);
             let notify_rx = self.notify_tx.clone();
-            tokio::spawn(async move {
+            let runtime = self.setting_engine.runtime.clone();
+            crate::spawn(self.setting_engine.runtime.as_ref(), async move {
                 RTCDataChannel::read_loop(
+                    runtime,
                     notify_rx,
                     dc,
                     ready_state,
@@ -306,6 +313,7 @@ impl RTCDataChannel {
     }
 
     async fn read_loop(
+        runtime: Option<Handle>,
         notify_rx: Arc<Notify>,
         data_channel: Arc<data::data_channel::DataChannel>,
         ready_state: Arc<AtomicU8>,
@@ -326,7 +334,7 @@ impl RTCDataChannel {
                             ready_state.store(RTCDataChannelState::Closed as u8, Ordering::SeqCst);
 
                             let on_close_handler2 = Arc::clone(&on_close_handler);
-                            tokio::spawn(async move {
+                            crate::spawn(runtime.as_ref(), async move {
                                 if let Some(handler) = &*on_close_handler2.load() {
                                     let mut f = handler.lock().await;
                                     f().await;
@@ -340,7 +348,7 @@ impl RTCDataChannel {
                             ready_state.store(RTCDataChannelState::Closed as u8, Ordering::SeqCst);
 
                             let on_error_handler2 = Arc::clone(&on_error_handler);
-                            tokio::spawn(async move {
+                            crate::spawn(runtime.as_ref(), async move {
                                 if let Some(handler) = &*on_error_handler2.load() {
                                     let mut f = handler.lock().await;
                                     f(err.into()).await;
@@ -348,7 +356,7 @@ impl RTCDataChannel {
                             });
 
                             let on_close_handler2 = Arc::clone(&on_close_handler);
-                            tokio::spawn(async move {
+                            crate::spawn(runtime.as_ref(), async move {
                                 if let Some(handler) = &*on_close_handler2.load() {
                                     let mut f = handler.lock().await;
                                     f().await;
@@ -375,6 +383,7 @@ impl RTCDataChannel {
     /// send sends the binary message to the DataChannel peer
     pub async fn send(&self, data: &Bytes) -> Result<usize> {
         self.ensure_open()?;
+        self.ensure_message_size(data.len()).await?;
 
         let data_channel = self.data_channel.lock().await;
         if let Some(dc) = &*data_channel {
@@ -387,10 +396,12 @@ impl RTCDataChannel {
     /// send_text sends the text message to the DataChannel peer
     pub async fn send_text(&self, s: impl Into<String>) -> Result<usize> {
         self.ensure_open()?;
+        let data = Bytes::from(s.into());
+        self.ensure_message_size(data.len()).await?;
 
         let data_channel = self.data_channel.lock().await;
         if let Some(dc) = &*data_channel {
-            Ok(dc.write_data_channel(&Bytes::from(s.into()), true).await?)
+            Ok(dc.write_data_channel(&data, true).await?)
         } else {
             Err(Error::ErrClosedPipe)
         }
@@ -404,6 +415,23 @@ impl RTCDataChannel {
         }
     }
 
+    async fn ensure_message_size(&self, size: usize) -> Result<()> {
+        match self.max_message_size().await {
+            Some(max_message_size) if size > max_message_size => {
+                Err(Error::ErrMessageTooLarge(max_message_size))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// max_message_size returns the size of the largest message that can be
+    /// sent on the DataChannel, as negotiated by its SCTPTransport. It is None
+    /// until the DataChannel is opened over a transport.
+    pub async fn max_message_size(&self) -> Option<usize> {
+        let sctp_transport = self.transport().await?.upgrade()?;
+        Some(sctp_transport.max_message_size())
+    }
+
     /// detach allows you to detach the underlying datachannel. This provides
     /// an idiomatic API to work with, however it disables the OnMessage callback.
     /// Before calling Detach you have to enable this behavior by calling
@@ -482,6 +510,12 @@ impl RTCDataChannel {
         self.negotiated
     }
 
+    /// priority represents the priority of this DataChannel, which is shared with
+    /// the remote peer when the channel is announced in-band.
+    pub fn priority(&self) -> RTCPriorityType {
+        self.priority
+    }
+
     /// ID represents the ID for this DataChannel. The value is initially
     /// null, which is what will be returned if the ID was not provided at
     /// channel creation time, and the DTLS role of the SCTP transport has not
@@ -0,0 +1,121 @@
+use std::time::Duration;
+
+use tokio::sync::mpsc;
+
+use super::*;
+use crate::api::setting_engine::SettingEngine;
+use crate::api::APIBuilder;
+use crate::peer_connection::peer_connection_test::{close_pair_now, new_pair, signal_pair};
+
+#[test]
+fn test_datagram_framing() -> Result<()> {
+    let record = marshal_datagram(b"input")?;
+    assert_eq!(
+        &record[..],
+        &[DATAGRAM_MARKER, 0, 5, b'i', b'n', b'p', b'u', b't'][..]
+    );
+    assert!(match_datagram(&record));
+    assert_eq!(
+        unmarshal_datagrams(&record)?,
+        vec![Bytes::from_static(b"input")]
+    );
+
+    // a record may carry several datagrams, including empty ones
+    let record = [DATAGRAM_MARKER, 0, 1, 7, 0, 0, 0, 2, 8, 9];
+    assert_eq!(
+        unmarshal_datagrams(&record)?,
+        vec![
+            Bytes::from_static(&[7]),
+            Bytes::new(),
+            Bytes::from_static(&[8, 9])
+        ]
+    );
+
+    // SCTP packets start with the source port 5000
+    assert!(!match_datagram(&[0x13, 0x88, 0x13, 0x88]));
+    assert!(!match_datagram(&[]));
+
+    Ok(())
+}
+
+#[test]
+fn test_datagram_framing_errors() {
+    assert!(matches!(
+        marshal_datagram(&[0u8; MAX_DATAGRAM_SIZE + 1]),
+        Err(Error::ErrDatagramTooLarge)
+    ));
+
+    for record in [
+        &[DATAGRAM_MARKER, 0][..],
+        &[DATAGRAM_MARKER, 0, 3, 1, 2][..],
+        &[0x13, 0x88][..],
+    ] {
+        assert!(
+            matches!(
+                unmarshal_datagrams(record),
+                Err(Error::ErrDatagramTruncated)
+            ),
+            "{record:?}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn test_datagram_channel_not_enabled() -> Result<()> {
+    let api = APIBuilder::new().build();
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+
+    assert!(matches!(
+        pc_offer.create_datagram_channel(),
+        Err(Error::ErrDatagramChannelsNotEnabled)
+    ));
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_datagram_channel_send_recv() -> Result<()> {
+    let mut s = SettingEngine::default();
+    s.enable_datagram_channels(true);
+    let api = APIBuilder::new().with_setting_engine(s).build();
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let offer_channel = pc_offer.create_datagram_channel()?;
+    let answer_channel = pc_answer.create_datagram_channel()?;
+
+    assert!(matches!(
+        offer_channel.send(&Bytes::from_static(b"early")).await,
+        Err(Error::ErrDtlsTransportNotStarted)
+    ));
+
+    // the data channel opening proves SCTP still works next to the datagrams
+    let (data_channel_tx, mut data_channel_rx) = mpsc::channel::<()>(1);
+    pc_answer.on_data_channel(Box::new(move |_| {
+        let data_channel_tx = data_channel_tx.clone();
+        Box::pin(async move {
+            let _ = data_channel_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    let _ = data_channel_rx.recv().await;
+
+    // datagrams may be lost, so keep sending until one arrives
+    let sender = tokio::spawn(async move {
+        loop {
+            let _ = offer_channel.send(&Bytes::from_static(b"state")).await;
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    });
+
+    let mut buf = vec![0u8; MAX_DATAGRAM_SIZE];
+    let n = tokio::time::timeout(Duration::from_secs(10), answer_channel.recv(&mut buf))
+        .await
+        .expect("timed out waiting for a datagram")?;
+    assert_eq!(&buf[..n], b"state");
+
+    sender.abort();
+    close_pair_now(&pc_offer, &pc_answer).await;
+    Ok(())
+}
@@ -0,0 +1,190 @@
+#[cfg(test)]
+mod datagram_channel_test;
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::{Buf, BufMut, Bytes, BytesMut};
+use dtls::conn::DTLSConn;
+use tokio::runtime::Handle;
+use util::{Buffer, Conn};
+
+use crate::dtls_transport::RTCDtlsTransport;
+use crate::error::{Error, Result};
+use crate::mux::endpoint::Endpoint;
+
+/// DATAGRAM_MARKER is the first byte of every DTLS record carrying datagrams. Every other
+/// record belongs to the SCTP association, whose packets start with the SCTP source port,
+/// i.e. 5000 (0x1388) in WebRTC, so the two can't be mistaken for each other.
+pub(crate) const DATAGRAM_MARKER: u8 = 0xD6;
+
+/// MAX_DATAGRAM_SIZE is the largest datagram that can be sent. It keeps a datagram and its
+/// framing within a single DTLS record that fits the path MTU of most networks.
+pub const MAX_DATAGRAM_SIZE: usize = 1200;
+
+/// the largest plaintext a DTLS record can carry
+const RECEIVE_BUFFER_SIZE: usize = 16384;
+
+/// The maximum amount of data that can be buffered before new datagrams are dropped.
+const MAX_BUFFER_SIZE: usize = 1000 * 1000; // 1MB
+
+/// match_datagram is a MatchFunc that accepts DTLS records carrying datagrams
+pub(crate) fn match_datagram(b: &[u8]) -> bool {
+    b.first() == Some(&DATAGRAM_MARKER)
+}
+
+/// marshal_datagram frames data as a DTLS record: the marker followed by the
+/// datagram prefixed with its 16-bit length.
+pub(crate) fn marshal_datagram(data: &[u8]) -> Result<Bytes> {
+    if data.len() > MAX_DATAGRAM_SIZE {
+        return Err(Error::ErrDatagramTooLarge);
+    }
+
+    let mut record = BytesMut::with_capacity(3 + data.len());
+    record.put_u8(DATAGRAM_MARKER);
+    record.put_u16(data.len() as u16);
+    record.put_slice(data);
+    Ok(record.freeze())
+}
+
+/// unmarshal_datagrams returns the datagrams of a DTLS record, a record may
+/// carry any number of length-prefixed datagrams after the marker.
+pub(crate) fn unmarshal_datagrams(record: &[u8]) -> Result<Vec<Bytes>> {
+    if !match_datagram(record) {
+        return Err(Error::ErrDatagramTruncated);
+    }
+
+    let mut reader = &record[1..];
+    let mut datagrams = vec![];
+    while reader.has_remaining() {
+        if reader.remaining() < 2 {
+            return Err(Error::ErrDatagramTruncated);
+        }
+        let length = reader.get_u16() as usize;
+        if reader.remaining() < length {
+            return Err(Error::ErrDatagramTruncated);
+        }
+        datagrams.push(Bytes::copy_from_slice(&reader[..length]));
+        reader.advance(length);
+    }
+
+    Ok(datagrams)
+}
+
+/// DatagramDemux splits the application data of a DTLS connection into the
+/// datagrams and the packets of the SCTP association.
+pub(crate) struct DatagramDemux {
+    datagrams: Buffer,
+    sctp: Buffer,
+}
+
+impl DatagramDemux {
+    pub(crate) fn new() -> Self {
+        DatagramDemux {
+            datagrams: Buffer::new(0, MAX_BUFFER_SIZE),
+            sctp: Buffer::new(0, MAX_BUFFER_SIZE),
+        }
+    }
+
+    /// start reads conn until it fails and dispatches every record it reads
+    pub(crate) fn start(&self, conn: Arc<DTLSConn>, runtime: Option<&Handle>) {
+        let datagrams = self.datagrams.clone();
+        let sctp = self.sctp.clone();
+        crate::spawn(runtime, async move {
+            DatagramDemux::read_loop(conn, &datagrams, &sctp).await;
+            datagrams.close().await;
+            sctp.close().await;
+        });
+    }
+
+    async fn read_loop(conn: Arc<DTLSConn>, datagrams: &Buffer, sctp: &Buffer) {
+        let mut buf = vec![0u8; RECEIVE_BUFFER_SIZE];
+        loop {
+            let n = match conn.recv(&mut buf).await {
+                Ok(n) => n,
+                Err(err) => {
+                    log::debug!("datagram demux: ending read loop: {}", err);
+                    return;
+                }
+            };
+
+            if !match_datagram(&buf[..n]) {
+                if !DatagramDemux::dispatch(sctp, &buf[..n]).await {
+                    return;
+                }
+                continue;
+            }
+
+            match unmarshal_datagrams(&buf[..n]) {
+                Ok(records) => {
+                    for datagram in records {
+                        if !DatagramDemux::dispatch(datagrams, &datagram).await {
+                            return;
+                        }
+                    }
+                }
+                Err(err) => log::warn!("datagram demux: dropping record: {}", err),
+            }
+        }
+    }
+
+    /// dispatch writes a packet to buffer, it returns false once the buffer is closed
+    async fn dispatch(buffer: &Buffer, packet: &[u8]) -> bool {
+        match buffer.write(packet).await {
+            Ok(_) => true,
+            // Expected when packets are received faster than they are read
+            Err(util::Error::ErrBufferFull) => {
+                log::trace!("datagram demux: buffer is full, dropping packet");
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// sctp_conn returns the conn the SCTP association runs over, it reads the
+    /// records that don't carry datagrams and writes to conn.
+    pub(crate) fn sctp_conn(&self, conn: Arc<DTLSConn>) -> Arc<dyn Conn + Send + Sync> {
+        Arc::new(Endpoint {
+            id: 0,
+            buffer: self.sctp.clone(),
+            match_fn: Box::new(|b| !match_datagram(b)),
+            next_conn: conn,
+            endpoints: Arc::new(tokio::sync::Mutex::new(HashMap::new())),
+            dscp_marker: None,
+        })
+    }
+
+    pub(crate) async fn close(&self) {
+        self.datagrams.close().await;
+        self.sctp.close().await;
+    }
+}
+
+/// RTCDatagramChannel sends and receives datagrams over the DTLS transport of
+/// a peer connection, next to SCTP but without its overhead. Datagrams are
+/// neither retransmitted nor ordered, so it suits data that is stale as soon
+/// as newer data exists, e.g. the input state of a game.
+pub struct RTCDatagramChannel {
+    pub(crate) dtls_transport: Arc<RTCDtlsTransport>,
+    pub(crate) demux: Arc<DatagramDemux>,
+}
+
+impl RTCDatagramChannel {
+    /// send sends data as a single datagram. It fails until the DTLS transport
+    /// is connected and if data is larger than MAX_DATAGRAM_SIZE.
+    pub async fn send(&self, data: &Bytes) -> Result<usize> {
+        let record = marshal_datagram(data)?;
+        let conn = match self.dtls_transport.conn().await {
+            Some(conn) => conn,
+            None => return Err(Error::ErrDtlsTransportNotStarted),
+        };
+        conn.send(&record).await?;
+        Ok(data.len())
+    }
+
+    /// recv waits for the next datagram and reads it into buf. Datagrams that
+    /// arrive while the buffer is full are dropped.
+    pub async fn recv(&self, buf: &mut [u8]) -> Result<usize> {
+        Ok(self.demux.datagrams.read(buf, None).await?)
+    }
+}
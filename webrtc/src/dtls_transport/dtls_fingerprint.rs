@@ -1,4 +1,6 @@
 use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
 
 /// DTLSFingerprint specifies the hash function algorithm and certificate
 /// fingerprint as described in [RFC 4572].
@@ -20,3 +22,26 @@ pub struct RTCDtlsFingerprint {
     /// <https://tools.ietf.org/html/rfc4572#section-5>.
     pub value: String,
 }
+
+/// The hash function algorithms fingerprints can be computed with, in
+/// order of preference.
+pub const SUPPORTED_FINGERPRINT_ALGORITHMS: [&str; 4] = ["sha-256", "sha-384", "sha-512", "sha-1"];
+
+/// DEFAULT_FINGERPRINT_ALGORITHM is the algorithm local fingerprints are
+/// signaled with unless the SettingEngine selects others.
+pub(crate) const DEFAULT_FINGERPRINT_ALGORITHM: &str = "sha-256";
+
+/// fingerprint_value hashes a DER encoded certificate with the given algorithm and
+/// returns the lowercase, colon separated hex string, or None if the algorithm
+/// isn't supported.
+pub(crate) fn fingerprint_value(algorithm: &str, certificate: &[u8]) -> Option<String> {
+    let hashed = match algorithm.to_lowercase().as_str() {
+        "sha-1" => Sha1::digest(certificate).to_vec(),
+        "sha-256" => Sha256::digest(certificate).to_vec(),
+        "sha-384" => Sha384::digest(certificate).to_vec(),
+        "sha-512" => Sha512::digest(certificate).to_vec(),
+        _ => return None,
+    };
+    let values: Vec<String> = hashed.iter().map(|x| format! {"{x:02x}"}).collect();
+    Some(values.join(":"))
+}
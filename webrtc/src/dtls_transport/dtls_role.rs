@@ -52,30 +52,41 @@ impl fmt::Display for DTLSRole {
 
 /// Iterate a SessionDescription from a remote to determine if an explicit
 /// role can been determined from it. The decision is made from the first role we we parse.
-/// If no role can be found we return DTLSRoleAuto
+/// A setup attribute at the session level applies to the media sections without one, as
+/// per RFC 8866 Section 5.13. If no role can be found we return DTLSRoleAuto
 impl From<&SessionDescription> for DTLSRole {
     fn from(session_description: &SessionDescription) -> Self {
+        let session_setup = session_description
+            .attributes
+            .iter()
+            .find(|attribute| attribute.key == "setup");
+
         for media_section in &session_description.media_descriptions {
-            for attribute in &media_section.attributes {
-                if attribute.key == "setup" {
-                    if let Some(value) = &attribute.value {
-                        match value.as_str() {
-                            "active" => return DTLSRole::Client,
-                            "passive" => return DTLSRole::Server,
-                            _ => return DTLSRole::Auto,
-                        };
-                    } else {
-                        return DTLSRole::Auto;
-                    }
-                }
+            let setup = media_section
+                .attributes
+                .iter()
+                .find(|attribute| attribute.key == "setup")
+                .or(session_setup);
+            if let Some(setup) = setup {
+                return DTLSRole::from_setup(setup.value.as_deref());
             }
         }
 
-        DTLSRole::Auto
+        session_setup.map_or(DTLSRole::Auto, |setup| {
+            DTLSRole::from_setup(setup.value.as_deref())
+        })
     }
 }
 
 impl DTLSRole {
+    fn from_setup(value: Option<&str>) -> Self {
+        match value {
+            Some("active") => DTLSRole::Client,
+            Some("passive") => DTLSRole::Server,
+            _ => DTLSRole::Auto,
+        }
+    }
+
     pub(crate) fn to_connection_role(self) -> ConnectionRole {
         match self {
             DTLSRole::Client => ConnectionRole::Active,
@@ -131,6 +142,15 @@ m=application 47299 DTLS/SCTP 5000
 c=IN IP4 192.168.20.129
 a=setup:";
 
+        const SESSION_SETUP_DECLARED: &str = "v=0
+o=- 4596489990601351948 2 IN IP4 127.0.0.1
+s=-
+t=0 0
+a=setup:passive
+m=application 47299 DTLS/SCTP 5000
+c=IN IP4 192.168.20.129
+";
+
         let tests = vec![
             ("No MediaDescriptions", NO_MEDIA.to_owned(), DTLSRole::Auto),
             (
@@ -153,6 +173,16 @@ a=setup:";
                 format!("{}{}\n", MEDIA_SETUP_DECLARED, "active"),
                 DTLSRole::Client,
             ),
+            (
+                "Session setup:passive, no media setup",
+                SESSION_SETUP_DECLARED.to_owned(),
+                DTLSRole::Server,
+            ),
+            (
+                "Session setup:passive, media setup:active",
+                format!("{}{}\n", SESSION_SETUP_DECLARED, "a=setup:active"),
+                DTLSRole::Client,
+            ),
         ];
 
         for (name, session_description_str, expected_role) in tests {
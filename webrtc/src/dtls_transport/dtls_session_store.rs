@@ -0,0 +1,47 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use dtls::session::{Session, SessionStore};
+
+use crate::dtls_transport::dtls_fingerprint::RTCDtlsFingerprint;
+
+/// RemoteSessionStore files the sessions of a DTLS client under the fingerprint
+/// of the remote certificate instead of the server name. The server name of a
+/// WebRTC peer is just its current address, which changes when it reconnects
+/// from another network, while its certificate stays the same.
+pub(crate) struct RemoteSessionStore {
+    store: Arc<dyn SessionStore + Send + Sync>,
+    key: Vec<u8>,
+}
+
+impl RemoteSessionStore {
+    pub(crate) fn new(
+        store: Arc<dyn SessionStore + Send + Sync>,
+        fingerprint: &RTCDtlsFingerprint,
+    ) -> Self {
+        RemoteSessionStore {
+            store,
+            key: format!(
+                "{} {}",
+                fingerprint.algorithm.to_lowercase(),
+                fingerprint.value.to_lowercase()
+            )
+            .into_bytes(),
+        }
+    }
+}
+
+#[async_trait]
+impl SessionStore for RemoteSessionStore {
+    async fn set(&self, _key: &[u8], session: Session) -> Result<(), dtls::Error> {
+        self.store.set(&self.key, session).await
+    }
+
+    async fn get(&self, _key: &[u8]) -> Result<Option<Session>, dtls::Error> {
+        self.store.get(&self.key).await
+    }
+
+    async fn del(&self, _key: &[u8]) -> Result<(), dtls::Error> {
+        self.store.del(&self.key).await
+    }
+}
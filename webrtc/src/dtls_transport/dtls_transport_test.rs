@@ -1,5 +1,7 @@
+use dtls::session::SessionTicketKey;
 use ice::mdns::MulticastDnsMode;
 use ice::network_type::NetworkType;
+use rcgen::KeyPair;
 use regex::Regex;
 use tokio::time::Duration;
 use waitgroup::WaitGroup;
@@ -8,6 +10,7 @@ use super::*;
 use crate::api::media_engine::MediaEngine;
 use crate::api::APIBuilder;
 use crate::data_channel::RTCDataChannel;
+use crate::dtls_transport::dtls_fingerprint::{fingerprint_value, RTCDtlsFingerprint};
 use crate::ice_transport::ice_candidate::RTCIceCandidate;
 use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
@@ -202,3 +205,336 @@ async fn test_peer_connection_dtls_role_setting_engine_client() -> Result<()> {
 
     run_test(DTLSRole::Client).await
 }
+
+#[tokio::test]
+async fn test_validate_fingerprint_mismatch() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+    let transport = pc.dtls_transport();
+
+    let remote_cert = b"not a real certificate";
+    {
+        let mut remote_parameters = transport.remote_parameters.lock().await;
+        remote_parameters.fingerprints = vec![RTCDtlsFingerprint {
+            algorithm: "sha-256".to_owned(),
+            value: "AA:BB".to_owned(),
+        }];
+    }
+
+    match transport.validate_fingerprint(remote_cert).await {
+        Err(Error::ErrDtlsFingerprintMismatch { expected, got }) => {
+            assert_eq!(expected, vec!["sha-256 aa:bb".to_owned()]);
+            assert!(got.starts_with("sha-256 "));
+            assert_eq!(got.len(), "sha-256 ".len() + 32 * 3 - 1);
+        }
+        other => panic!("expected fingerprint mismatch, got {other:?}"),
+    }
+
+    pc.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validate_fingerprint_algorithms() -> Result<()> {
+    let mut s = SettingEngine::default();
+    s.set_dtls_fingerprint_algorithms(vec!["sha-384".to_owned(), "sha-256".to_owned()])?;
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+    let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+    let transport = pc.dtls_transport();
+
+    // Local fingerprints are signaled in the selected algorithms
+    let local_fingerprints = transport.get_local_parameters()?.fingerprints;
+    let algorithms: Vec<&str> = local_fingerprints
+        .iter()
+        .map(|f| f.algorithm.as_str())
+        .collect();
+    assert_eq!(algorithms, vec!["sha-384", "sha-256"]);
+
+    let remote_cert = b"not a real certificate";
+    let sha384 = fingerprint_value("sha-384", remote_cert).unwrap();
+
+    // Unsupported algorithms are skipped as long as another one matches
+    {
+        let mut remote_parameters = transport.remote_parameters.lock().await;
+        remote_parameters.fingerprints = vec![
+            RTCDtlsFingerprint {
+                algorithm: "md5".to_owned(),
+                value: "aa:bb".to_owned(),
+            },
+            RTCDtlsFingerprint {
+                algorithm: "SHA-384".to_owned(),
+                value: sha384.to_uppercase(),
+            },
+        ];
+    }
+    transport.validate_fingerprint(remote_cert).await?;
+
+    {
+        let mut remote_parameters = transport.remote_parameters.lock().await;
+        remote_parameters.fingerprints = vec![RTCDtlsFingerprint {
+            algorithm: "md5".to_owned(),
+            value: "aa:bb".to_owned(),
+        }];
+    }
+    assert!(matches!(
+        transport.validate_fingerprint(remote_cert).await,
+        Err(Error::ErrUnsupportedFingerprintAlgorithm)
+    ));
+
+    pc.close().await?;
+
+    Ok(())
+}
+
+// Counts the lookups that found a session, i.e. the client offering it and the server resuming it
+#[derive(Default)]
+struct CountingSessionStore {
+    store: dtls::session::MemorySessionStore,
+    hits: AtomicU8,
+}
+
+#[async_trait::async_trait]
+impl SessionStore for CountingSessionStore {
+    async fn set(
+        &self,
+        key: &[u8],
+        session: dtls::session::Session,
+    ) -> std::result::Result<(), dtls::Error> {
+        self.store.set(key, session).await
+    }
+
+    async fn get(
+        &self,
+        key: &[u8],
+    ) -> std::result::Result<Option<dtls::session::Session>, dtls::Error> {
+        let session = self.store.get(key).await?;
+        if session.is_some() {
+            self.hits.fetch_add(1, Ordering::SeqCst);
+        }
+        Ok(session)
+    }
+
+    async fn del(&self, key: &[u8]) -> std::result::Result<(), dtls::Error> {
+        self.store.del(key).await
+    }
+}
+
+#[tokio::test]
+async fn test_dtls_session_resumption() -> Result<()> {
+    // With a ticket key the server resumes the session of the ticket and doesn't
+    // look it up, only the client's lookup of the ticket counts
+    for (ticket_key, resumed_hits) in [(None, 2), (Some(SessionTicketKey::generate()), 1)] {
+        let store = Arc::new(CountingSessionStore::default());
+
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let mut s = SettingEngine::default();
+        s.set_dtls_session_store(store.clone());
+        if let Some(ticket_key) = ticket_key {
+            s.set_dtls_session_ticket_key(ticket_key);
+        }
+        let api = APIBuilder::new()
+            .with_media_engine(m)
+            .with_setting_engine(s)
+            .build();
+
+        // Resumption needs the peers to come back with the same certificates
+        let offer_cert = RTCCertificate::from_key_pair(KeyPair::generate()?)?;
+        let answer_cert = RTCCertificate::from_key_pair(KeyPair::generate()?)?;
+
+        for want_hits in [0, resumed_hits] {
+            let mut pc_offer = api
+                .new_peer_connection(RTCConfiguration {
+                    certificates: vec![offer_cert.clone()],
+                    ..Default::default()
+                })
+                .await?;
+            let mut pc_answer = api
+                .new_peer_connection(RTCConfiguration {
+                    certificates: vec![answer_cert.clone()],
+                    ..Default::default()
+                })
+                .await?;
+
+            let wg = WaitGroup::new();
+            until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+            until_connection_state(&mut pc_answer, &wg, RTCPeerConnectionState::Connected).await;
+
+            signal_pair(&mut pc_offer, &mut pc_answer).await?;
+            wg.wait().await;
+
+            close_pair_now(&pc_offer, &pc_answer).await;
+
+            assert_eq!(store.hits.load(Ordering::SeqCst), want_hits);
+        }
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_selected_srtp_protection_profile() -> Result<()> {
+    for (answer_profiles, want) in [
+        (vec![], SrtpProtectionProfile::Srtp_Aead_Aes_128_Gcm),
+        (
+            vec![SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_32],
+            SrtpProtectionProfile::Srtp_Aes128_Cm_Hmac_Sha1_32,
+        ),
+    ] {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let offer_api = APIBuilder::new().with_media_engine(m).build();
+
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let mut s = SettingEngine::default();
+        s.set_srtp_protection_profiles(answer_profiles);
+        let answer_api = APIBuilder::new()
+            .with_media_engine(m)
+            .with_setting_engine(s)
+            .build();
+
+        let mut pc_offer = offer_api
+            .new_peer_connection(RTCConfiguration::default())
+            .await?;
+        let mut pc_answer = answer_api
+            .new_peer_connection(RTCConfiguration::default())
+            .await?;
+
+        assert_eq!(
+            pc_offer
+                .dtls_transport()
+                .selected_srtp_protection_profile()
+                .await,
+            None
+        );
+
+        let wg = WaitGroup::new();
+        until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+        until_connection_state(&mut pc_answer, &wg, RTCPeerConnectionState::Connected).await;
+
+        signal_pair(&mut pc_offer, &mut pc_answer).await?;
+        wg.wait().await;
+
+        for pc in [&pc_offer, &pc_answer] {
+            assert_eq!(
+                pc.dtls_transport().selected_srtp_protection_profile().await,
+                Some(want)
+            );
+        }
+
+        close_pair_now(&pc_offer, &pc_answer).await;
+    }
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_dtls_transport_on_handshake_message() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let mut observed = vec![];
+    for pc in [&pc_offer, &pc_answer] {
+        let messages = Arc::new(std::sync::Mutex::new(vec![]));
+        let messages2 = Arc::clone(&messages);
+        pc.dtls_transport()
+            .on_handshake_message(Box::new(move |direction, typ, raw| {
+                assert_eq!(HandshakeType::from(raw[0]), typ);
+                messages2.lock().unwrap().push((direction, typ));
+            }));
+        observed.push(messages);
+    }
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut pc_offer, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut pc_answer, &wg, RTCPeerConnectionState::Connected).await;
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    wg.wait().await;
+
+    let messages = |i: usize, direction: HandshakeDirection| -> Vec<HandshakeType> {
+        observed[i]
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(d, _)| *d == direction)
+            .map(|(_, typ)| *typ)
+            .collect()
+    };
+    // The answerer is the DTLS client by default
+    let client_sent = messages(1, HandshakeDirection::Sent);
+    assert_eq!(client_sent.first(), Some(&HandshakeType::ClientHello));
+    assert_eq!(client_sent.last(), Some(&HandshakeType::Finished));
+    assert_eq!(messages(0, HandshakeDirection::Received), client_sent);
+    let server_sent = messages(0, HandshakeDirection::Sent);
+    assert!(server_sent.contains(&HandshakeType::Certificate));
+    assert_eq!(messages(1, HandshakeDirection::Received), server_sent);
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_remote_certificate_verifier() -> Result<()> {
+    let offer_cert = RTCCertificate::from_key_pair(KeyPair::generate()?)?;
+    let other_cert = RTCCertificate::from_key_pair(KeyPair::generate()?)?;
+
+    for (pinned_cert, want) in [
+        (&offer_cert, RTCPeerConnectionState::Connected),
+        (&other_cert, RTCPeerConnectionState::Failed),
+    ] {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let offer_api = APIBuilder::new().with_media_engine(m).build();
+
+        let pinned = pinned_cert.dtls_certificate.certificate[0].to_vec();
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let mut s = SettingEngine::default();
+        s.set_remote_certificate_verifier(move |certs| certs.first() == Some(&pinned));
+        let answer_api = APIBuilder::new()
+            .with_media_engine(m)
+            .with_setting_engine(s)
+            .build();
+
+        let mut pc_offer = offer_api
+            .new_peer_connection(RTCConfiguration {
+                certificates: vec![offer_cert.clone()],
+                ..Default::default()
+            })
+            .await?;
+        let mut pc_answer = answer_api
+            .new_peer_connection(RTCConfiguration::default())
+            .await?;
+
+        let wg = WaitGroup::new();
+        until_connection_state(&mut pc_answer, &wg, want).await;
+
+        signal_pair(&mut pc_offer, &mut pc_answer).await?;
+        wg.wait().await;
+
+        let dtls_state = pc_answer.dtls_transport().state();
+        if want == RTCPeerConnectionState::Connected {
+            assert_eq!(dtls_state, RTCDtlsTransportState::Connected);
+        } else {
+            assert_eq!(dtls_state, RTCDtlsTransportState::Failed);
+        }
+
+        close_pair_now(&pc_offer, &pc_answer).await;
+    }
+
+    Ok(())
+}
@@ -9,18 +9,25 @@ use bytes::Bytes;
 use dtls::config::ClientAuthType;
 use dtls::conn::DTLSConn;
 use dtls::extension::extension_use_srtp::SrtpProtectionProfile;
+use dtls::handshake::HandshakeType;
+use dtls::handshaker::{HandshakeDirection, HandshakeObserverFn};
+use dtls::session::SessionStore;
 use dtls_role::*;
+use dtls_session_store::RemoteSessionStore;
 use interceptor::stream_info::StreamInfo;
 use interceptor::{Interceptor, RTCPReader, RTPReader};
-use portable_atomic::{AtomicBool, AtomicU8};
-use sha2::{Digest, Sha256};
+use portable_atomic::{AtomicBool, AtomicU64, AtomicU8};
 use srtp::protection_profile::ProtectionProfile;
 use srtp::session::Session;
 use srtp::stream::Stream;
 use tokio::sync::{mpsc, Mutex};
+use util::sync::MemoryBudget;
 use util::Conn;
 
+use crate::api::handshake_limiter::HandshakeLimiter;
 use crate::api::setting_engine::SettingEngine;
+use crate::datagram_channel::DatagramDemux;
+use crate::dtls_transport::dtls_fingerprint::fingerprint_value;
 use crate::dtls_transport::dtls_parameters::DTLSParameters;
 use crate::dtls_transport::dtls_transport_state::RTCDtlsTransportState;
 use crate::error::{flatten_errs, Error, Result};
@@ -30,6 +37,7 @@ use crate::ice_transport::RTCIceTransport;
 use crate::mux::endpoint::Endpoint;
 use crate::mux::mux_func::{match_dtls, match_srtcp, match_srtp, MatchFunc};
 use crate::peer_connection::certificate::RTCCertificate;
+use crate::peer_connection::connection_timing::PhaseTimer;
 use crate::rtp_transceiver::SSRC;
 use crate::stats::stats_collector::StatsCollector;
 
@@ -39,8 +47,12 @@ mod dtls_transport_test;
 pub mod dtls_fingerprint;
 pub mod dtls_parameters;
 pub mod dtls_role;
+pub(crate) mod dtls_session_store;
 pub mod dtls_transport_state;
 
+/// The media buffers can use one MEDIA_MEMORY_BUDGET_SHARE-th of the memory budget
+const MEDIA_MEMORY_BUDGET_SHARE: usize = 2;
+
 pub(crate) fn default_srtp_protection_profiles() -> Vec<SrtpProtectionProfile> {
     vec![
         SrtpProtectionProfile::Srtp_Aead_Aes_128_Gcm,
@@ -56,6 +68,11 @@ pub type OnDTLSTransportStateChangeHdlrFn = Box<
         + Sync,
 >;
 
+/// OnDTLSHandshakeMessageHdlrFn is called with the direction, the type and the raw
+/// bytes of every DTLS handshake message, see [`dtls::handshaker::HandshakeObserverFn`]
+pub type OnDTLSHandshakeMessageHdlrFn =
+    Box<dyn Fn(HandshakeDirection, HandshakeType, &[u8]) + Send + Sync>;
+
 /// DTLSTransport allows an application access to information about the DTLS
 /// transport over which RTP and RTCP packets are sent and received by
 /// RTPSender and RTPReceiver, as well other data such as SCTP packets sent
@@ -71,6 +88,7 @@ pub struct RTCDtlsTransport {
     pub(crate) state: AtomicU8, //DTLSTransportState,
     pub(crate) srtp_protection_profile: Mutex<ProtectionProfile>,
     pub(crate) on_state_change_handler: ArcSwapOption<Mutex<OnDTLSTransportStateChangeHdlrFn>>,
+    pub(crate) on_handshake_message_handler: Arc<ArcSwapOption<OnDTLSHandshakeMessageHdlrFn>>,
     pub(crate) conn: Mutex<Option<Arc<DTLSConn>>>,
 
     pub(crate) srtp_session: Mutex<Option<Arc<Session>>>,
@@ -85,6 +103,28 @@ pub struct RTCDtlsTransport {
     pub(crate) srtp_ready_rx: Mutex<Option<mpsc::Receiver<()>>>,
 
     pub(crate) dtls_matcher: Option<MatchFunc>,
+
+    /// handshake_timer measures the time from connecting until connected
+    pub(crate) handshake_timer: PhaseTimer,
+
+    /// datagram_demux splits datagrams from SCTP when datagram channels are enabled
+    pub(crate) datagram_demux: Option<Arc<DatagramDemux>>,
+
+    /// memory_budget shared by the buffers of the PeerConnection, see
+    /// SettingEngine::set_memory_budget
+    pub(crate) memory_budget: Option<Arc<MemoryBudget>>,
+
+    /// media_memory_budget is the share of memory_budget the media buffers can use, so
+    /// that they can't starve the SCTP association
+    pub(crate) media_memory_budget: Option<Arc<MemoryBudget>>,
+
+    /// handshake_limiter of the API that created the transport, see
+    /// APIBuilder::with_max_concurrent_dtls_handshakes
+    pub(crate) handshake_limiter: Option<Arc<HandshakeLimiter>>,
+
+    /// inbound_ssrcs_dropped counts the streams of new inbound SSRCs dropped because of
+    /// SettingEngine::set_max_inbound_ssrcs
+    pub(crate) inbound_ssrcs_dropped: AtomicU64,
 }
 
 impl RTCDtlsTransport {
@@ -94,15 +134,29 @@ impl RTCDtlsTransport {
         setting_engine: Arc<SettingEngine>,
     ) -> Self {
         let (srtp_ready_tx, srtp_ready_rx) = mpsc::channel(1);
+        let memory_budget = setting_engine
+            .memory_budget
+            .map(|limit| Arc::new(MemoryBudget::new(limit)));
+        let media_memory_budget = memory_budget.as_ref().map(|budget| {
+            Arc::new(MemoryBudget::with_parent(
+                Arc::clone(budget),
+                budget.limit() / MEDIA_MEMORY_BUDGET_SHARE,
+            ))
+        });
         RTCDtlsTransport {
             ice_transport,
             certificates,
-            setting_engine,
             srtp_ready_signal: Arc::new(AtomicBool::new(false)),
             srtp_ready_tx: Mutex::new(Some(srtp_ready_tx)),
             srtp_ready_rx: Mutex::new(Some(srtp_ready_rx)),
             state: AtomicU8::new(RTCDtlsTransportState::New as u8),
             dtls_matcher: Some(Box::new(match_dtls)),
+            datagram_demux: setting_engine
+                .enable_datagram_channels
+                .then(|| Arc::new(DatagramDemux::new())),
+            memory_budget,
+            media_memory_budget,
+            setting_engine,
             ..Default::default()
         }
     }
@@ -112,6 +166,26 @@ impl RTCDtlsTransport {
         conn.clone()
     }
 
+    /// selected_srtp_protection_profile returns the SRTP protection profile
+    /// negotiated by the DTLS handshake. It is None until the DTLS transport is
+    /// connected, or if the peers didn't agree on a profile.
+    pub async fn selected_srtp_protection_profile(&self) -> Option<SrtpProtectionProfile> {
+        match self.conn().await?.selected_srtpprotection_profile() {
+            SrtpProtectionProfile::Unsupported => None,
+            profile => Some(profile),
+        }
+    }
+
+    /// sctp_conn returns the conn to run the SCTP association over, which
+    /// leaves out the datagrams when datagram channels are enabled.
+    pub(crate) async fn sctp_conn(&self) -> Option<Arc<dyn Conn + Send + Sync>> {
+        let conn = self.conn().await?;
+        Some(match &self.datagram_demux {
+            Some(demux) => demux.sctp_conn(conn),
+            None => conn,
+        })
+    }
+
     /// returns the currently-configured ICETransport or None
     /// if one has not been configured
     pub fn ice_transport(&self) -> &RTCIceTransport {
@@ -134,13 +208,21 @@ impl RTCDtlsTransport {
             .store(Some(Arc::new(Mutex::new(f))));
     }
 
+    /// on_handshake_message sets a handler that is called with every DTLS handshake
+    /// message sent or received, e.g. to keep a record of the handshake for auditing.
+    /// It only observes the messages and must be set before the transport is started
+    /// to see the whole handshake.
+    pub fn on_handshake_message(&self, f: OnDTLSHandshakeMessageHdlrFn) {
+        self.on_handshake_message_handler.store(Some(Arc::new(f)));
+    }
+
     /// state returns the current dtls_transport transport state.
     pub fn state(&self) -> RTCDtlsTransportState {
         self.state.load(Ordering::SeqCst).into()
     }
 
     /// write_rtcp sends a user provided RTCP packet to the connected peer. If no peer is connected the
-    /// packet is discarded.
+    /// packet is discarded. The packets are sent as a single compound RTCP packet in one datagram.
     pub async fn write_rtcp(
         &self,
         pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
@@ -158,8 +240,9 @@ impl RTCDtlsTransport {
     pub fn get_local_parameters(&self) -> Result<DTLSParameters> {
         let mut fingerprints = vec![];
 
+        let algorithms = self.setting_engine.get_dtls_fingerprint_algorithms();
         for c in &self.certificates {
-            fingerprints.extend(c.get_fingerprints());
+            fingerprints.extend(c.fingerprints_with(&algorithms));
         }
 
         Ok(DTLSParameters {
@@ -181,10 +264,8 @@ impl RTCDtlsTransport {
             *srtp_protection_profile
         };
 
-        let mut srtp_config = srtp::config::Config {
-            profile,
-            ..Default::default()
-        };
+        let mut srtp_config = srtp::config::Config::default();
+        srtp_config.profile = profile;
 
         if self.setting_engine.replay_protection.srtp != 0 {
             srtp_config.remote_rtp_options = Some(srtp::option::srtp_replay_protection(
@@ -222,10 +303,8 @@ impl RTCDtlsTransport {
             };
         }
 
-        let mut srtcp_config = srtp::config::Config {
-            profile,
-            ..Default::default()
-        };
+        let mut srtcp_config = srtp::config::Config::default();
+        srtcp_config.profile = profile;
         if self.setting_engine.replay_protection.srtcp != 0 {
             srtcp_config.remote_rtcp_options = Some(srtp::option::srtcp_replay_protection(
                 self.setting_engine.replay_protection.srtcp,
@@ -283,6 +362,36 @@ impl RTCDtlsTransport {
         srtcp_session.clone()
     }
 
+    /// inbound_ssrc_count returns how many inbound SSRCs the SRTP session demultiplexes
+    pub(crate) async fn inbound_ssrc_count(&self) -> usize {
+        match self.get_srtp_session().await {
+            Some(srtp_session) => srtp_session.stream_count().await,
+            None => 0,
+        }
+    }
+
+    /// admit_inbound_stream closes the stream of a new inbound SSRC and returns false if
+    /// the session demultiplexes more SSRCs than SettingEngine::set_max_inbound_ssrcs allows
+    pub(crate) async fn admit_inbound_stream(&self, session: &Session, stream: &Stream) -> bool {
+        let Some(max_inbound_ssrcs) = self.setting_engine.max_inbound_ssrcs else {
+            return true;
+        };
+        if session.stream_count().await <= max_inbound_ssrcs {
+            return true;
+        }
+
+        self.inbound_ssrcs_dropped.fetch_add(1, Ordering::SeqCst);
+        log::debug!(
+            "dropping inbound ssrc({}), {} SSRCs are in use already",
+            stream.get_ssrc(),
+            max_inbound_ssrcs
+        );
+        if let Err(err) = stream.close().await {
+            log::warn!("Failed to close inbound stream {}", err);
+        }
+        false
+    }
+
     pub(crate) async fn role(&self) -> DTLSRole {
         // If remote has an explicit role use the inverse
         {
@@ -333,22 +442,46 @@ impl RTCDtlsTransport {
             let mut srtcp_endpoint = self.srtcp_endpoint.lock().await;
             *srtcp_endpoint = self.ice_transport.new_endpoint(Box::new(match_srtcp)).await;
         }
+        let remote_fingerprint = remote_parameters.fingerprints.first().cloned();
         {
             let mut rp = self.remote_parameters.lock().await;
             *rp = remote_parameters;
         }
+        let role = self.role().await;
+
+        // Sessions are saved by the server under their id, a client looks them up
+        // by the remote certificate it expects to see
+        let session_store = match &self.setting_engine.dtls_session_store {
+            Some(store) if role == DTLSRole::Client => remote_fingerprint.map(|fingerprint| {
+                Arc::new(RemoteSessionStore::new(Arc::clone(store), &fingerprint))
+                    as Arc<dyn SessionStore + Send + Sync>
+            }),
+            Some(store) => Some(Arc::clone(store)),
+            None => None,
+        };
 
-        let certificate = if let Some(cert) = self.certificates.first() {
-            cert.dtls_certificate.clone()
-        } else {
+        if self.certificates.is_empty() {
             return Err(Error::ErrNonCertificate);
-        };
+        }
+        let certificates = self
+            .certificates
+            .iter()
+            .map(|cert| cert.dtls_certificate.clone())
+            .collect();
+        let on_handshake_message_handler = Arc::clone(&self.on_handshake_message_handler);
+        let handshake_observer: HandshakeObserverFn = Arc::new(move |direction, typ, raw| {
+            if let Some(handler) = &*on_handshake_message_handler.load() {
+                handler(direction, typ, raw);
+            }
+        });
+
+        self.handshake_timer.start();
         self.state_change(RTCDtlsTransportState::Connecting).await;
 
         Ok((
-            self.role().await,
+            role,
             dtls::config::Config {
-                certificates: vec![certificate],
+                certificates,
                 srtp_protection_profiles: if !self
                     .setting_engine
                     .srtp_protection_profiles
@@ -361,6 +494,9 @@ impl RTCDtlsTransport {
                 client_auth: ClientAuthType::RequireAnyClientCert,
                 insecure_skip_verify: true,
                 insecure_verification: self.setting_engine.allow_insecure_verification_algorithm,
+                session_store,
+                session_ticket_key: self.setting_engine.dtls_session_ticket_key.clone(),
+                handshake_observer: Some(handshake_observer),
                 ..Default::default()
             },
         ))
@@ -368,6 +504,7 @@ impl RTCDtlsTransport {
 
     /// start DTLS transport negotiation with the parameters of the remote DTLS transport
     pub async fn start(&self, remote_parameters: DTLSParameters) -> Result<()> {
+        let certificate_rejected = Arc::new(AtomicBool::new(false));
         let dtls_conn_result = if let Some(dtls_endpoint) =
             self.ice_transport.new_endpoint(Box::new(match_dtls)).await
         {
@@ -375,6 +512,24 @@ impl RTCDtlsTransport {
             if self.setting_engine.replay_protection.dtls != 0 {
                 dtls_config.replay_protection_window = self.setting_engine.replay_protection.dtls;
             }
+            if let Some(verifier) = &self.setting_engine.remote_certificate_verifier {
+                let verifier = Arc::clone(verifier);
+                let rejected = Arc::clone(&certificate_rejected);
+                dtls_config.verify_peer_certificate = Some(Arc::new(move |certs, _| {
+                    if verifier(certs) {
+                        Ok(())
+                    } else {
+                        rejected.store(true, Ordering::SeqCst);
+                        Err(dtls::Error::ErrInvalidCertificate)
+                    }
+                }));
+            }
+
+            // Wait for our turn if the API limits concurrent handshakes
+            let _permit = match &self.handshake_limiter {
+                Some(limiter) => Some(limiter.acquire().await),
+                None => None,
+            };
 
             // Connect as DTLS Client/Server, function is blocking and we
             // must not hold the DTLSTransport lock
@@ -405,6 +560,10 @@ impl RTCDtlsTransport {
             Ok(dtls_conn) => dtls_conn,
             Err(err) => {
                 self.state_change(RTCDtlsTransportState::Failed).await;
+                if certificate_rejected.load(Ordering::SeqCst) {
+                    log::error!("DTLS remote certificate rejected by the certificate verifier");
+                    return Err(Error::ErrRemoteCertificateRejected);
+                }
                 return Err(err.into());
             }
         };
@@ -457,6 +616,13 @@ impl RTCDtlsTransport {
             .disable_certificate_fingerprint_verification
         {
             if let Err(err) = self.validate_fingerprint(&remote_certs[0]).await {
+                if let Error::ErrDtlsFingerprintMismatch { expected, got } = &err {
+                    log::error!(
+                        "DTLS fingerprint mismatch, expected one of {:?} but remote certificate has {}",
+                        expected,
+                        got
+                    );
+                }
                 if let Err(close_err) = dtls_conn.close().await {
                     log::error!("{}", close_err);
                 }
@@ -467,9 +633,14 @@ impl RTCDtlsTransport {
         }
 
         {
+            let dtls_conn = Arc::new(dtls_conn);
+            if let Some(demux) = &self.datagram_demux {
+                demux.start(Arc::clone(&dtls_conn), self.setting_engine.runtime.as_ref());
+            }
             let mut conn = self.conn.lock().await;
-            *conn = Some(Arc::new(dtls_conn));
+            *conn = Some(dtls_conn);
         }
+        self.handshake_timer.complete();
         self.state_change(RTCDtlsTransportState::Connected).await;
 
         self.start_srtp().await
@@ -528,6 +699,10 @@ impl RTCDtlsTransport {
             }
         }
 
+        if let Some(demux) = &self.datagram_demux {
+            demux.close().await;
+        }
+
         if let Some(conn) = self.conn().await {
             // dtls_transport connection may be closed on sctp close.
             match conn.close().await {
@@ -547,23 +722,37 @@ impl RTCDtlsTransport {
 
     pub(crate) async fn validate_fingerprint(&self, remote_cert: &[u8]) -> Result<()> {
         let remote_parameters = self.remote_parameters.lock().await;
-        for fp in &remote_parameters.fingerprints {
-            if fp.algorithm != "sha-256" {
-                return Err(Error::ErrUnsupportedFingerprintAlgorithm);
-            }
 
-            let mut h = Sha256::new();
-            h.update(remote_cert);
-            let hashed = h.finalize();
-            let values: Vec<String> = hashed.iter().map(|x| format! {"{x:02x}"}).collect();
-            let remote_value = values.join(":").to_lowercase();
+        let mut got = vec![];
+        for fp in &remote_parameters.fingerprints {
+            let algorithm = fp.algorithm.to_lowercase();
+            let remote_value = match fingerprint_value(&algorithm, remote_cert) {
+                Some(value) => value,
+                None => continue,
+            };
 
             if remote_value == fp.value.to_lowercase() {
                 return Ok(());
             }
+
+            let got_fingerprint = format!("{algorithm} {remote_value}");
+            if !got.contains(&got_fingerprint) {
+                got.push(got_fingerprint);
+            }
         }
 
-        Err(Error::ErrNoMatchingCertificateFingerprint)
+        if got.is_empty() {
+            return Err(Error::ErrUnsupportedFingerprintAlgorithm);
+        }
+
+        Err(Error::ErrDtlsFingerprintMismatch {
+            expected: remote_parameters
+                .fingerprints
+                .iter()
+                .map(|fp| format!("{} {}", fp.algorithm, fp.value.to_lowercase()))
+                .collect(),
+            got: got.join(", "),
+        })
     }
 
     pub(crate) fn ensure_ice_conn(&self) -> Result<()> {
@@ -602,9 +791,11 @@ impl RTCDtlsTransport {
         //log::debug!("streams_for_ssrc: srtp_session.listen ssrc={}", ssrc);
         let rtp_read_stream = srtp_session.open(ssrc).await;
         let rtp_stream_reader = Arc::clone(&rtp_read_stream) as Arc<dyn RTPReader + Send + Sync>;
-        let rtp_interceptor = interceptor
-            .bind_remote_stream(stream_info, rtp_stream_reader)
-            .await;
+        let rtp_interceptor = crate::in_runtime(
+            self.setting_engine.runtime.as_ref(),
+            interceptor.bind_remote_stream(stream_info, rtp_stream_reader),
+        )
+        .await;
 
         let srtcp_session = self
             .get_srtcp_session()
@@ -613,7 +804,11 @@ impl RTCDtlsTransport {
         //log::debug!("streams_for_ssrc: srtcp_session.listen ssrc={}", ssrc);
         let rtcp_read_stream = srtcp_session.open(ssrc).await;
         let rtcp_stream_reader = Arc::clone(&rtcp_read_stream) as Arc<dyn RTCPReader + Send + Sync>;
-        let rtcp_interceptor = interceptor.bind_rtcp_reader(rtcp_stream_reader).await;
+        let rtcp_interceptor = crate::in_runtime(
+            self.setting_engine.runtime.as_ref(),
+            interceptor.bind_rtcp_reader(rtcp_stream_reader),
+        )
+        .await;
 
         Ok((
             rtp_read_stream,
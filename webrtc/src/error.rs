@@ -26,6 +26,11 @@ pub enum Error {
     #[error("connection closed")]
     ErrConnectionClosed,
 
+    /// ErrDraining indicates that a PeerConnection, data channel or track is
+    /// created while the API is draining its PeerConnections.
+    #[error("API is draining its PeerConnections")]
+    ErrDraining,
+
     /// ErrDataChannelNotOpen indicates an operation executed when the data
     /// channel is not (yet) open.
     #[error("data channel not open")]
@@ -124,6 +129,24 @@ pub enum Error {
     #[error("protocol is larger then 65535 bytes")]
     ErrProtocolTooLarge,
 
+    /// ErrRtcpMuxRequired indicates that a media section of the remote
+    /// description doesn't negotiate rtcp-mux (RFC 5761), RTP and RTCP are
+    /// only supported on the same transport
+    #[error(
+        "remote description doesn't negotiate rtcp-mux for media section {0}, which is required"
+    )]
+    ErrRtcpMuxRequired(String),
+
+    /// ErrSessionDescriptionInvalidRtcp indicates that the a=rtcp attribute
+    /// (RFC 3605) of a media section doesn't start with a port
+    #[error("invalid a=rtcp attribute: {0}")]
+    ErrSessionDescriptionInvalidRtcp(String),
+
+    /// ErrMessageTooLarge indicates that a message sent on a DataChannel is
+    /// larger than the max message size negotiated with the remote
+    #[error("message is larger than the max message size of {0} bytes")]
+    ErrMessageTooLarge(usize),
+
     /// ErrSenderNotCreatedByConnection indicates remove_track was called with a
     /// [`rtp_sender::RTCRtpSender`] not created by this PeerConnection
     #[error("RtpSender not created by this PeerConnection")]
@@ -246,8 +269,18 @@ pub enum Error {
     ErrNoRemoteCertificate,
     #[error("identity provider is not implemented")]
     ErrIdentityProviderNotImplemented,
+    #[deprecated(note = "fingerprint mismatches are reported as ErrDtlsFingerprintMismatch")]
     #[error("remote certificate does not match any fingerprint")]
     ErrNoMatchingCertificateFingerprint,
+    /// ErrDtlsFingerprintMismatch indicates the certificate presented by the remote during the
+    /// DTLS handshake doesn't hash to any of the fingerprints it signaled in its SessionDescription.
+    #[error("remote certificate fingerprint {got} does not match any of {expected:?}")]
+    ErrDtlsFingerprintMismatch { expected: Vec<String>, got: String },
+    /// ErrRemoteCertificateRejected indicates the certificate presented by the remote during
+    /// the DTLS handshake was rejected by the callback of
+    /// [`SettingEngine::set_remote_certificate_verifier`](crate::api::setting_engine::SettingEngine::set_remote_certificate_verifier).
+    #[error("remote certificate rejected by the certificate verifier")]
+    ErrRemoteCertificateRejected,
     #[error("unsupported fingerprint algorithm")]
     ErrUnsupportedFingerprintAlgorithm,
     #[error("ICE connection not started")]
@@ -367,6 +400,10 @@ pub enum Error {
     ErrSDPMediaSectionMultipleTrackInvalid,
     #[error("set_answering_dtlsrole must DTLSRoleClient or DTLSRoleServer")]
     ErrSettingEngineSetAnsweringDTLSRole,
+    #[error("set_dscp must be called with a 6-bit DSCP codepoint")]
+    ErrSettingEngineSetDscp,
+    #[error("set_dscp_policy must be called with 6-bit DSCP codepoints")]
+    ErrSettingEngineSetDscpPolicy,
     #[error("can't rollback from stable state")]
     ErrSignalingStateCannotRollback,
     #[error(
@@ -399,6 +436,23 @@ pub enum Error {
     #[error("not long enough to be a RTP Packet")]
     ErrRTPTooShort,
 
+    #[error("enable datagram channels by calling SettingEngine::enable_datagram_channels")]
+    ErrDatagramChannelsNotEnabled,
+    #[error("datagram is larger than the maximum datagram size")]
+    ErrDatagramTooLarge,
+    #[error("datagram framing is truncated")]
+    ErrDatagramTruncated,
+
+    #[cfg(feature = "whip")]
+    #[error("WHIP/WHEP endpoint responded with status {0}")]
+    ErrWhipUnexpectedStatus(u16),
+    #[cfg(feature = "whip")]
+    #[error("WHIP/WHEP endpoint didn't return the location of the session")]
+    ErrWhipNoSession,
+    #[cfg(feature = "whip")]
+    #[error("WHIP/WHEP session is not established")]
+    ErrWhipNotConnected,
+
     #[error("{0}")]
     Util(#[from] util::Error),
     #[error("{0}")]
@@ -430,6 +484,9 @@ pub enum Error {
     ParseInt(#[from] ParseIntError),
     #[error("parse url: {0}")]
     ParseUrl(#[from] url::ParseError),
+    #[cfg(feature = "whip")]
+    #[error("http: {0}")]
+    Http(String),
 
     /// Error parsing a given PEM string.
     #[error("invalid PEM: {0}")]
@@ -450,6 +507,20 @@ impl<T> From<MpscSendError<T>> for Error {
     }
 }
 
+#[cfg(feature = "whip")]
+impl From<hyper::Error> for Error {
+    fn from(e: hyper::Error) -> Self {
+        Error::Http(e.to_string())
+    }
+}
+
+#[cfg(feature = "whip")]
+impl From<hyper_util::client::legacy::Error> for Error {
+    fn from(e: hyper_util::client::legacy::Error) -> Self {
+        Error::Http(e.to_string())
+    }
+}
+
 impl From<Error> for interceptor::Error {
     fn from(e: Error) -> Self {
         // this is a bit lol, but we do preserve the stack trace
@@ -457,6 +528,18 @@ impl From<Error> for interceptor::Error {
     }
 }
 
+impl Error {
+    /// sctp_abort_causes returns the causes the remote gave when it aborted the SCTP association,
+    /// if this error is the result of such an abort.
+    pub fn sctp_abort_causes(&self) -> Option<&[sctp::error_cause::ErrorCauseCode]> {
+        match self {
+            Error::Sctp(sctp::Error::ErrAbortReceived(causes))
+            | Error::Data(data::Error::Sctp(sctp::Error::ErrAbortReceived(causes))) => Some(causes),
+            _ => None,
+        }
+    }
+}
+
 impl PartialEq<ice::Error> for Error {
     fn eq(&self, other: &ice::Error) -> bool {
         if let Error::Ice(e) = self {
@@ -1,12 +1,13 @@
 use std::fmt;
 use std::sync::Arc;
 
-use ice::candidate::candidate_base::CandidateBaseConfig;
+use ice::candidate::candidate_base::{unmarshal_candidate, CandidateBaseConfig};
 use ice::candidate::candidate_host::CandidateHostConfig;
 use ice::candidate::candidate_peer_reflexive::CandidatePeerReflexiveConfig;
 use ice::candidate::candidate_relay::CandidateRelayConfig;
 use ice::candidate::candidate_server_reflexive::CandidateServerReflexiveConfig;
-use ice::candidate::Candidate;
+use ice::candidate::{Candidate, CandidateExtension};
+use ice::tcp_type::TcpType;
 use serde::{Deserialize, Serialize};
 
 use crate::error::{Error, Result};
@@ -35,6 +36,10 @@ pub struct RTCIceCandidate {
     pub related_address: String,
     pub related_port: u16,
     pub tcp_type: String,
+    /// extension attributes of the candidate string other than `tcptype`,
+    /// e.g. `generation`, `ufrag`, `network-id` and `network-cost`
+    #[serde(default)]
+    pub extensions: Vec<CandidateExtension>,
 }
 
 /// Conversion for ice_candidates
@@ -66,11 +71,30 @@ impl From<&Arc<dyn Candidate + Send + Sync>> for RTCIceCandidate {
             tcp_type: c.tcp_type().to_string(),
             related_address,
             related_port,
+            extensions: c.extensions(),
         }
     }
 }
 
 impl RTCIceCandidate {
+    /// from_sdp_string parses a candidate line, with or without its `a=` and `candidate:`
+    /// prefixes, e.g. `candidate:1357493765 1 tcp 1518214911 192.168.1.5 9 typ host tcptype active`
+    pub fn from_sdp_string(raw: &str) -> Result<Self> {
+        let raw = raw.trim();
+        let raw = raw.strip_prefix("a=").unwrap_or(raw);
+        let raw = raw.strip_prefix("candidate:").unwrap_or(raw);
+
+        let candidate: Arc<dyn Candidate + Send + Sync> = Arc::new(unmarshal_candidate(raw)?);
+        Ok(RTCIceCandidate::from(&candidate))
+    }
+
+    /// to_sdp_string returns the candidate line, with its `candidate:` prefix, the way
+    /// it is signaled in an ICECandidateInit
+    pub fn to_sdp_string(&self) -> Result<String> {
+        let candidate = self.to_ice()?;
+        Ok(format!("candidate:{}", candidate.marshal()))
+    }
+
     pub(crate) fn to_ice(&self) -> Result<impl Candidate> {
         let candidate_id = self.stats_id.clone();
         let base_config = CandidateBaseConfig {
@@ -79,9 +103,9 @@ impl RTCIceCandidate {
             address: self.address.clone(),
             port: self.port,
             component: self.component,
-            //tcp_type: ice.NewTCPType(c.TCPType),
             foundation: self.foundation.clone(),
             priority: self.priority,
+            extensions: self.extensions.clone(),
             ..Default::default()
         };
 
@@ -89,7 +113,7 @@ impl RTCIceCandidate {
             RTCIceCandidateType::Host => {
                 let config = CandidateHostConfig {
                     base_config,
-                    ..Default::default()
+                    tcp_type: TcpType::from(self.tcp_type.as_str()),
                 };
                 config.new_candidate_host()?
             }
@@ -124,16 +148,46 @@ impl RTCIceCandidate {
         Ok(c)
     }
 
+    /// extension returns the value of the extension attribute with the given key
+    pub fn extension(&self, key: &str) -> Option<&str> {
+        self.extensions
+            .iter()
+            .find(|e| e.key == key)
+            .map(|e| e.value.as_str())
+    }
+
+    /// generation returns the ICE generation the candidate belongs to, as
+    /// signaled by Chrome
+    pub fn generation(&self) -> Option<u32> {
+        self.extension("generation").and_then(|v| v.parse().ok())
+    }
+
+    /// username_fragment returns the ICE username fragment the candidate
+    /// belongs to
+    pub fn username_fragment(&self) -> Option<&str> {
+        self.extension("ufrag")
+    }
+
+    /// network_id returns the id of the network interface the candidate was
+    /// gathered on, as signaled by Chrome
+    pub fn network_id(&self) -> Option<u32> {
+        self.extension("network-id").and_then(|v| v.parse().ok())
+    }
+
+    /// network_cost returns the cost of the network interface the candidate
+    /// was gathered on, as signaled by Chrome
+    pub fn network_cost(&self) -> Option<u32> {
+        self.extension("network-cost").and_then(|v| v.parse().ok())
+    }
+
     /// to_json returns an ICECandidateInit
     /// as indicated by the spec <https://w3c.github.io/webrtc-pc/#dom-rtcicecandidate-tojson>
     pub fn to_json(&self) -> Result<RTCIceCandidateInit> {
-        let candidate = self.to_ice()?;
-
         Ok(RTCIceCandidateInit {
-            candidate: format!("candidate:{}", candidate.marshal()),
+            candidate: self.to_sdp_string()?,
             sdp_mid: Some("".to_owned()),
             sdp_mline_index: Some(0u16),
-            username_fragment: None,
+            username_fragment: self.username_fragment().map(|u| u.to_owned()),
         })
     }
 }
@@ -199,4 +253,82 @@ mod test {
             }
         }
     }
+
+    #[test]
+    fn test_ice_candidate_browser_round_trip() -> Result<()> {
+        let tests = vec![
+            "candidate:3406423489 1 udp 2113937151 192.168.1.5 56143 typ host generation 0 ufrag 5Ktf network-id 1 network-cost 10",
+            "candidate:842163049 1 udp 1677729535 203.0.113.7 56143 typ srflx raddr 192.168.1.5 rport 56143 generation 0 ufrag 5Ktf network-id 1 network-cost 10",
+            "candidate:1357493765 1 tcp 1518214911 192.168.1.5 9 typ host tcptype active generation 0 ufrag 5Ktf network-id 1 network-cost 10",
+        ];
+
+        for raw in tests {
+            let c: Arc<dyn Candidate + Send + Sync> = Arc::new(
+                ice::candidate::candidate_base::unmarshal_candidate(&raw["candidate:".len()..])?,
+            );
+            let candidate = RTCIceCandidate::from(&c);
+            assert_eq!(candidate.generation(), Some(0));
+            assert_eq!(candidate.username_fragment(), Some("5Ktf"));
+            assert_eq!(candidate.network_id(), Some(1));
+            assert_eq!(candidate.network_cost(), Some(10));
+
+            let init = candidate.to_json()?;
+            assert_eq!(init.candidate, raw);
+            assert_eq!(init.username_fragment.as_deref(), Some("5Ktf"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ice_candidate_from_sdp_string_tcp() -> Result<()> {
+        let tests = vec![
+            (
+                "candidate:1357493765 1 tcp 1518214911 192.168.1.5 9 typ host tcptype active generation 0 ufrag 5Ktf network-id 1 network-cost 10",
+                "192.168.1.5",
+                9,
+                "active",
+            ),
+            (
+                "candidate:2999745851 1 tcp 1518280447 192.168.1.5 56144 typ host tcptype passive generation 0 ufrag 5Ktf network-id 1 network-cost 10",
+                "192.168.1.5",
+                56144,
+                "passive",
+            ),
+            (
+                "candidate:4233069003 1 tcp 1518151935 2001:db8::1 9 typ host tcptype so generation 0 ufrag 5Ktf network-id 2 network-cost 10",
+                "2001:db8::1",
+                9,
+                "so",
+            ),
+        ];
+
+        for (raw, address, port, tcp_type) in tests {
+            for line in [
+                raw.to_owned(),
+                format!("a={raw}"),
+                raw["candidate:".len()..].to_owned(),
+            ] {
+                let candidate = RTCIceCandidate::from_sdp_string(&line)?;
+                assert_eq!(candidate.protocol, RTCIceProtocol::Tcp);
+                assert_eq!(candidate.typ, RTCIceCandidateType::Host);
+                assert_eq!(candidate.address, address);
+                assert_eq!(candidate.port, port);
+                assert_eq!(candidate.tcp_type, tcp_type);
+                assert_eq!(candidate.username_fragment(), Some("5Ktf"));
+
+                assert_eq!(candidate.to_sdp_string()?, raw);
+            }
+        }
+
+        let candidate = RTCIceCandidate::from_sdp_string(
+            "a=candidate:3406423489 1 udp 2113937151 192.168.1.5 56143 typ host generation 0",
+        )?;
+        assert_eq!(candidate.protocol, RTCIceProtocol::Udp);
+        assert_eq!(candidate.tcp_type, "unspecified");
+
+        assert!(RTCIceCandidate::from_sdp_string("candidate:1 1 tcp").is_err());
+
+        Ok(())
+    }
 }
@@ -18,6 +18,7 @@ use crate::ice_transport::ice_candidate_type::RTCIceCandidateType;
 use crate::ice_transport::ice_gatherer_state::RTCIceGathererState;
 use crate::ice_transport::ice_parameters::RTCIceParameters;
 use crate::ice_transport::ice_server::RTCIceServer;
+use crate::peer_connection::connection_timing::PhaseTimer;
 use crate::peer_connection::policy::ice_transport_policy::RTCIceTransportPolicy;
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::SourceStatsType::*;
@@ -63,6 +64,9 @@ pub struct RTCIceGatherer {
 
     // Used for gathering_complete_promise
     pub(crate) on_gathering_complete_handler: Arc<ArcSwapOption<Mutex<OnGatheringCompleteHdlrFn>>>,
+
+    /// gathering_timer measures the time until the first local candidate
+    pub(crate) gathering_timer: Arc<PhaseTimer>,
 }
 
 impl RTCIceGatherer {
@@ -109,6 +113,7 @@ impl RTCIceGatherer {
         let mut config = ice::agent::agent_config::AgentConfig {
             udp_network: self.setting_engine.udp_network.clone(),
             lite: self.setting_engine.candidates.ice_lite,
+            nomination_mode: self.setting_engine.candidates.ice_nomination,
             urls: self.validated_servers.clone(),
             disconnected_timeout: self.setting_engine.timeout.ice_disconnected_timeout,
             failed_timeout: self.setting_engine.timeout.ice_failed_timeout,
@@ -132,6 +137,29 @@ impl RTCIceGatherer {
                 .clone(),
             local_ufrag: self.setting_engine.candidates.username_fragment.clone(),
             local_pwd: self.setting_engine.candidates.password.clone(),
+            dscp: self.setting_engine.dscp,
+            stun_software: self.setting_engine.candidates.ice_stun_software.clone(),
+            disable_stun_fingerprint: self.setting_engine.candidates.disable_ice_stun_fingerprint,
+            stun_transaction_id_generator: self
+                .setting_engine
+                .candidates
+                .ice_stun_transaction_id_generator
+                .clone(),
+            stun_attribute_order: self
+                .setting_engine
+                .candidates
+                .ice_stun_attribute_order
+                .clone(),
+            relay_allocate_retry: self.setting_engine.candidates.ice_relay_allocate_retry,
+            gathering_timeout: self.setting_engine.timeout.ice_gathering_timeout,
+            relay_gathering_delay: self.setting_engine.timeout.ice_relay_gathering_delay,
+            max_in_flight_checks: self.setting_engine.candidates.ice_max_in_flight_checks,
+            check_pacing_interval: self
+                .setting_engine
+                .timeout
+                .ice_check_pacing_interval
+                .unwrap_or_default(),
+            static_candidate: self.setting_engine.candidates.ice_static_candidate,
             //TODO: TCPMux:                 self.setting_engine.iceTCPMux,
             //TODO: ProxyDialer:            self.setting_engine.iceProxyDialer,
             ..Default::default()
@@ -146,7 +174,14 @@ impl RTCIceGatherer {
 
         config.network_types.extend(requested_network_types);
 
-        *agent = Some(Arc::new(ice::agent::Agent::new(config).await?));
+        // the agent starts its routines on the runtime of the PeerConnection
+        let new_agent = match &self.setting_engine.runtime {
+            Some(runtime) => crate::spawn(Some(runtime), ice::agent::Agent::new(config))
+                .await
+                .map_err(|err| Error::new(err.to_string()))??,
+            None => ice::agent::Agent::new(config).await?,
+        };
+        *agent = Some(Arc::new(new_agent));
 
         Ok(())
     }
@@ -157,7 +192,10 @@ impl RTCIceGatherer {
         self.set_state(RTCIceGathererState::Gathering).await;
 
         if let Some(agent) = self.get_agent().await {
+            self.gathering_timer.start();
+
             let state = Arc::clone(&self.state);
+            let gathering_timer = Arc::clone(&self.gathering_timer);
             let on_local_candidate_handler = Arc::clone(&self.on_local_candidate_handler);
             let on_state_change_handler = Arc::clone(&self.on_state_change_handler);
             let on_gathering_complete_handler = Arc::clone(&self.on_gathering_complete_handler);
@@ -169,6 +207,9 @@ impl RTCIceGatherer {
                     let on_state_change_handler_clone = Arc::clone(&on_state_change_handler);
                     let on_gathering_complete_handler_clone =
                         Arc::clone(&on_gathering_complete_handler);
+                    if candidate.is_some() {
+                        gathering_timer.complete();
+                    }
 
                     Box::pin(async move {
                         if let Some(cand) = candidate {
@@ -199,6 +240,7 @@ impl RTCIceGatherer {
                 },
             ));
 
+            let _runtime = self.setting_engine.runtime.as_ref().map(|rt| rt.enter());
             agent.gather_candidates()?;
         }
 
@@ -207,6 +249,16 @@ impl RTCIceGatherer {
 
     /// Close prunes all local candidates, and closes the ports.
     pub async fn close(&self) -> Result<()> {
+        self.close_agent(false).await
+    }
+
+    /// close_and_wait closes the ICEGatherer like close, then waits for the tasks of
+    /// the ICE agent to exit, the ports are released once it returns.
+    pub async fn close_and_wait(&self) -> Result<()> {
+        self.close_agent(true).await
+    }
+
+    async fn close_agent(&self, wait: bool) -> Result<()> {
         self.set_state(RTCIceGathererState::Closed).await;
 
         let agent = {
@@ -215,7 +267,11 @@ impl RTCIceGatherer {
         };
 
         if let Some(agent) = agent {
-            agent.close().await?;
+            if wait {
+                agent.close_and_wait().await?;
+            } else {
+                agent.close().await?;
+            }
         }
 
         Ok(())
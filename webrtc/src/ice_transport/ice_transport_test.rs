@@ -4,9 +4,11 @@ use waitgroup::WaitGroup;
 
 use super::*;
 use crate::api::media_engine::MediaEngine;
+use crate::api::setting_engine::SettingEngine;
 use crate::api::APIBuilder;
 use crate::error::Result;
 use crate::ice_transport::ice_connection_state::RTCIceConnectionState;
+use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::{
     close_pair_now, new_pair, signal_pair, until_connection_state,
@@ -95,6 +97,8 @@ async fn test_ice_transport_get_selected_candidate_pair() -> Result<()> {
         .get_selected_candidate_pair()
         .await;
     assert!(answerer_selected_pair.is_none());
+    assert_eq!(offerer.selected_local_addr().await, None);
+    assert_eq!(offerer.selected_remote_addr().await, None);
 
     signal_pair(&mut offerer, &mut answerer).await?;
 
@@ -116,6 +120,200 @@ async fn test_ice_transport_get_selected_candidate_pair() -> Result<()> {
         .await;
     assert!(answerer_selected_pair.is_some());
 
+    // Both ends see the same sockets, without a NAT in between
+    let offerer_local_addr = offerer.selected_local_addr().await;
+    assert!(offerer_local_addr.is_some());
+    assert_eq!(offerer_local_addr, answerer.selected_remote_addr().await);
+    assert_eq!(
+        offerer.selected_remote_addr().await,
+        answerer.selected_local_addr().await
+    );
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(())
+}
+
+async fn negotiated_ice_roles(
+    offer_engine: SettingEngine,
+    answer_engine: SettingEngine,
+) -> Result<(RTCIceRole, RTCIceRole)> {
+    let mut pcs = vec![];
+    for setting_engine in [offer_engine, answer_engine] {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let api = APIBuilder::new()
+            .with_media_engine(m)
+            .with_setting_engine(setting_engine)
+            .build();
+        pcs.push(api.new_peer_connection(RTCConfiguration::default()).await?);
+    }
+    let (mut offerer, mut answerer) = (pcs.remove(0), pcs.remove(0));
+
+    assert_eq!(
+        offerer.sctp().transport().ice_transport().role().await,
+        RTCIceRole::Unspecified
+    );
+
+    let peer_connection_connected = WaitGroup::new();
+    until_connection_state(
+        &mut offerer,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+    until_connection_state(
+        &mut answerer,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+
+    peer_connection_connected.wait().await;
+
+    let roles = (
+        offerer.sctp().transport().ice_transport().role().await,
+        answerer.sctp().transport().ice_transport().role().await,
+    );
+
+    close_pair_now(&offerer, &answerer).await;
+
+    Ok(roles)
+}
+
+#[tokio::test]
+async fn test_ice_transport_role() -> Result<()> {
+    let lite = || {
+        let mut s = SettingEngine::default();
+        s.set_lite(true);
+        s
+    };
+    let forced = |role: RTCIceRole| {
+        let mut s = SettingEngine::default();
+        s.set_ice_role(role);
+        s
+    };
+
+    // Neither agent is lite, the offerer is controlling
+    assert_eq!(
+        negotiated_ice_roles(SettingEngine::default(), SettingEngine::default()).await?,
+        (RTCIceRole::Controlling, RTCIceRole::Controlled)
+    );
+
+    // The full agent is controlling, whether it offers or answers
+    assert_eq!(
+        negotiated_ice_roles(SettingEngine::default(), lite()).await?,
+        (RTCIceRole::Controlling, RTCIceRole::Controlled)
+    );
+    assert_eq!(
+        negotiated_ice_roles(lite(), SettingEngine::default()).await?,
+        (RTCIceRole::Controlled, RTCIceRole::Controlling)
+    );
+
+    // The setting engine overrides the derived roles
+    assert_eq!(
+        negotiated_ice_roles(
+            forced(RTCIceRole::Controlled),
+            forced(RTCIceRole::Controlling)
+        )
+        .await?,
+        (RTCIceRole::Controlled, RTCIceRole::Controlling)
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ice_transport_parameters() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut offerer, mut answerer) = new_pair(&api).await?;
+
+    assert!(answerer
+        .dtls_transport()
+        .ice_transport()
+        .get_remote_parameters()
+        .await
+        .is_none());
+
+    let peer_connection_connected = WaitGroup::new();
+    until_connection_state(
+        &mut offerer,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+    until_connection_state(
+        &mut answerer,
+        &peer_connection_connected,
+        RTCPeerConnectionState::Connected,
+    )
+    .await;
+
+    signal_pair(&mut offerer, &mut answerer).await?;
+    peer_connection_connected.wait().await;
+
+    let offerer_local = offerer
+        .dtls_transport()
+        .ice_transport()
+        .get_local_parameters()
+        .await?;
+    let offer = offerer.local_description().await.unwrap();
+    assert!(offer
+        .sdp
+        .contains(&format!("a=ice-ufrag:{}", offerer_local.username_fragment)));
+    assert_eq!(
+        answerer
+            .dtls_transport()
+            .ice_transport()
+            .get_remote_parameters()
+            .await,
+        Some(offerer_local)
+    );
+    assert_eq!(
+        offerer
+            .dtls_transport()
+            .ice_transport()
+            .get_remote_parameters()
+            .await,
+        Some(
+            answerer
+                .dtls_transport()
+                .ice_transport()
+                .get_local_parameters()
+                .await?
+        )
+    );
+
+    let params = RTCIceParameters {
+        username_fragment: "ufrag".to_owned(),
+        password: "0123456789abcdefghijkl".to_owned(),
+        ice_lite: false,
+    };
+    offerer
+        .dtls_transport()
+        .ice_transport()
+        .set_remote_parameters(&params)
+        .await?;
+    assert_eq!(
+        offerer
+            .dtls_transport()
+            .ice_transport()
+            .get_remote_parameters()
+            .await,
+        Some(params)
+    );
+    assert!(offerer
+        .dtls_transport()
+        .ice_transport()
+        .set_remote_parameters(&RTCIceParameters::default())
+        .await
+        .is_err());
+
     close_pair_now(&offerer, &answerer).await;
 
     Ok(())
@@ -1,4 +1,5 @@
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
@@ -10,16 +11,18 @@ use ice_candidate::RTCIceCandidate;
 use ice_candidate_pair::RTCIceCandidatePair;
 use ice_gatherer::RTCIceGatherer;
 use ice_role::RTCIceRole;
-use portable_atomic::AtomicU8;
+use portable_atomic::{AtomicBool, AtomicU8};
 use tokio::sync::{mpsc, Mutex};
 use util::Conn;
 
 use crate::error::{flatten_errs, Error, Result};
 use crate::ice_transport::ice_parameters::RTCIceParameters;
 use crate::ice_transport::ice_transport_state::RTCIceTransportState;
+use crate::mux::dscp_marker::DscpMarker;
 use crate::mux::endpoint::Endpoint;
 use crate::mux::mux_func::MatchFunc;
 use crate::mux::{Config, Mux};
+use crate::peer_connection::connection_timing::PhaseTimer;
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::ICETransportStats;
 use crate::stats::StatsReportType::Transport;
@@ -69,7 +72,12 @@ pub struct RTCIceTransport {
     on_selected_candidate_pair_change_handler:
         Arc<ArcSwapOption<Mutex<OnSelectedCandidatePairChangeHdlrFn>>>,
     state: Arc<AtomicU8>, // ICETransportState
+    remote_end_of_candidates: AtomicBool,
     internal: Mutex<ICETransportInternal>,
+    /// connecting_timer measures the time from checking until connected
+    pub(crate) connecting_timer: Arc<PhaseTimer>,
+    /// dscp_marker marks the sent packets if the SettingEngine has a DSCP policy
+    pub(crate) dscp_marker: Option<Arc<DscpMarker>>,
 }
 
 impl RTCIceTransport {
@@ -77,6 +85,10 @@ impl RTCIceTransport {
     pub(crate) fn new(gatherer: Arc<RTCIceGatherer>) -> Self {
         RTCIceTransport {
             state: Arc::new(AtomicU8::new(RTCIceTransportState::New as u8)),
+            dscp_marker: gatherer
+                .setting_engine
+                .dscp_policy
+                .map(|policy| Arc::new(DscpMarker::new(policy))),
             gatherer,
             ..Default::default()
         }
@@ -95,6 +107,26 @@ impl RTCIceTransport {
         None
     }
 
+    /// selected_local_addr returns the address of the local socket the selected candidate pair
+    /// sends from. For a server reflexive candidate it is the address the socket is bound to
+    /// rather than the public one, for a relayed candidate it is the address allocated on the
+    /// TURN server. None until a candidate pair is selected.
+    pub async fn selected_local_addr(&self) -> Option<SocketAddr> {
+        let agent = self.gatherer.get_agent().await?;
+        let local = agent.get_selected_candidate_pair()?.local.clone();
+        local
+            .get_conn()
+            .and_then(|conn| conn.local_addr().ok())
+            .or_else(|| Some(local.addr()))
+    }
+
+    /// selected_remote_addr returns the address of the remote socket the selected candidate
+    /// pair sends to. None until a candidate pair is selected.
+    pub async fn selected_remote_addr(&self) -> Option<SocketAddr> {
+        let agent = self.gatherer.get_agent().await?;
+        Some(agent.get_selected_candidate_pair()?.remote.addr())
+    }
+
     /// Start incoming connectivity checks based on its configured role.
     pub async fn start(&self, params: &RTCIceParameters, role: Option<RTCIceRole>) -> Result<()> {
         if self.state() != RTCIceTransportState::New {
@@ -105,11 +137,17 @@ impl RTCIceTransport {
 
         if let Some(agent) = self.gatherer.get_agent().await {
             let state = Arc::clone(&self.state);
+            let connecting_timer = Arc::clone(&self.connecting_timer);
 
             let on_connection_state_change_handler =
                 Arc::clone(&self.on_connection_state_change_handler);
             agent.on_connection_state_change(Box::new(move |ice_state: ConnectionState| {
                 let s = RTCIceTransportState::from(ice_state);
+                match s {
+                    RTCIceTransportState::Checking => connecting_timer.start(),
+                    RTCIceTransportState::Connected => connecting_timer.complete(),
+                    _ => {}
+                }
                 let on_connection_state_change_handler_clone =
                     Arc::clone(&on_connection_state_change_handler);
                 state.store(s as u8, Ordering::SeqCst);
@@ -147,6 +185,10 @@ impl RTCIceTransport {
                 RTCIceRole::Controlled
             };
 
+            if let Some(dscp_marker) = &self.dscp_marker {
+                dscp_marker.set_agent(Arc::clone(&agent));
+            }
+
             let (cancel_tx, cancel_rx) = mpsc::channel(1);
             {
                 let mut internal = self.internal.lock().await;
@@ -181,6 +223,8 @@ impl RTCIceTransport {
             let config = Config {
                 conn: Arc::clone(&conn),
                 buffer_size: self.gatherer.setting_engine.get_receive_mtu(),
+                runtime: self.gatherer.setting_engine.runtime.clone(),
+                dscp_marker: self.dscp_marker.clone(),
             };
 
             {
@@ -212,11 +256,22 @@ impl RTCIceTransport {
         } else {
             return Err(Error::ErrICEAgentNotExist);
         }
+        self.remote_end_of_candidates.store(false, Ordering::SeqCst);
         self.gatherer.gather().await
     }
 
     /// Stop irreversibly stops the ICETransport.
     pub async fn stop(&self) -> Result<()> {
+        self.stop_internal(false).await
+    }
+
+    /// stop_and_wait stops the ICETransport like stop, then waits for its tasks and
+    /// the tasks of the ICE agent to exit, the sockets are released once it returns.
+    pub async fn stop_and_wait(&self) -> Result<()> {
+        self.stop_internal(true).await
+    }
+
+    async fn stop_internal(&self, wait: bool) -> Result<()> {
         self.set_state(RTCIceTransportState::Closed);
 
         let mut errs: Vec<Error> = vec![];
@@ -224,7 +279,11 @@ impl RTCIceTransport {
             let mut internal = self.internal.lock().await;
             internal.cancel_tx.take();
             if let Some(mut mux) = internal.mux.take() {
-                mux.close().await;
+                if wait {
+                    mux.close_and_wait().await;
+                } else {
+                    mux.close().await;
+                }
             }
             if let Some(conn) = internal.conn.take() {
                 if let Err(err) = conn.close().await {
@@ -233,7 +292,12 @@ impl RTCIceTransport {
             }
         }
 
-        if let Err(err) = self.gatherer.close().await {
+        let result = if wait {
+            self.gatherer.close_and_wait().await
+        } else {
+            self.gatherer.close().await
+        };
+        if let Err(err) = result {
             errs.push(err);
         }
 
@@ -265,6 +329,12 @@ impl RTCIceTransport {
         self.ensure_gatherer().await?;
 
         if let Some(agent) = self.gatherer.get_agent().await {
+            let _runtime = self
+                .gatherer
+                .setting_engine
+                .runtime
+                .as_ref()
+                .map(|rt| rt.enter());
             for rc in remote_candidates {
                 let c: Arc<dyn Candidate + Send + Sync> = Arc::new(rc.to_ice()?);
                 agent.add_remote_candidate(&c)?;
@@ -275,7 +345,8 @@ impl RTCIceTransport {
         }
     }
 
-    /// adds a candidate associated with the remote ICETransport.
+    /// adds a candidate associated with the remote ICETransport. None signals
+    /// that the remote won't send more candidates.
     pub async fn add_remote_candidate(
         &self,
         remote_candidate: Option<RTCIceCandidate>,
@@ -285,7 +356,15 @@ impl RTCIceTransport {
         if let Some(agent) = self.gatherer.get_agent().await {
             if let Some(r) = remote_candidate {
                 let c: Arc<dyn Candidate + Send + Sync> = Arc::new(r.to_ice()?);
+                let _runtime = self
+                    .gatherer
+                    .setting_engine
+                    .runtime
+                    .as_ref()
+                    .map(|rt| rt.enter());
                 agent.add_remote_candidate(&c)?;
+            } else {
+                self.remote_end_of_candidates.store(true, Ordering::SeqCst);
             }
 
             Ok(())
@@ -294,6 +373,13 @@ impl RTCIceTransport {
         }
     }
 
+    /// remote_end_of_candidates returns true once the remote signaled that it
+    /// gathered all of its candidates, with an empty candidate or with
+    /// `a=end-of-candidates` in its description. It is reset by an ICE restart.
+    pub fn remote_end_of_candidates(&self) -> bool {
+        self.remote_end_of_candidates.load(Ordering::SeqCst)
+    }
+
     /// State returns the current ice transport state.
     pub fn state(&self) -> RTCIceTransportState {
         RTCIceTransportState::from(self.state.load(Ordering::SeqCst))
@@ -312,6 +398,13 @@ impl RTCIceTransport {
         }
     }
 
+    /// packets returns the number of DTLS, SRTP and SRTCP packets received through
+    /// the transport, None until the transport is started
+    pub(crate) async fn packets(&self) -> Option<u64> {
+        let internal = self.internal.lock().await;
+        internal.mux.as_ref().map(|mux| mux.packets())
+    }
+
     pub(crate) async fn ensure_gatherer(&self) -> Result<()> {
         if self.gatherer.get_agent().await.is_none() {
             self.gatherer.create_agent().await
@@ -328,6 +421,39 @@ impl RTCIceTransport {
         }
     }
 
+    /// get_local_parameters returns the ICE parameters of the local agent, the ufrag and pwd
+    /// signaled to the remote.
+    pub async fn get_local_parameters(&self) -> Result<RTCIceParameters> {
+        self.gatherer.get_local_parameters().await
+    }
+
+    /// get_remote_parameters returns the ICE parameters of the remote agent, None until they
+    /// are known from the remote SessionDescription or set with start or
+    /// set_remote_parameters. The remote ice_lite isn't kept and is always false.
+    pub async fn get_remote_parameters(&self) -> Option<RTCIceParameters> {
+        let agent = self.gatherer.get_agent().await?;
+        let (username_fragment, password) = agent.get_remote_user_credentials().await;
+        if username_fragment.is_empty() {
+            return None;
+        }
+
+        Some(RTCIceParameters {
+            username_fragment,
+            password,
+            ice_lite: false,
+        })
+    }
+
+    /// set_remote_parameters sets the ICE parameters of the remote agent, e.g. when they are
+    /// exchanged out of band instead of in a SessionDescription. Calling it after start
+    /// switches the connectivity checks to the new credentials, as after an ICE restart of
+    /// the remote.
+    pub async fn set_remote_parameters(&self, params: &RTCIceParameters) -> Result<()> {
+        self.ensure_gatherer().await?;
+        self.set_remote_credentials(params.username_fragment.clone(), params.password.clone())
+            .await
+    }
+
     pub(crate) async fn have_remote_credentials_change(
         &self,
         new_ufrag: &str,
@@ -347,6 +473,7 @@ impl RTCIceTransport {
         new_pwd: String,
     ) -> Result<()> {
         if let Some(agent) = self.gatherer.get_agent().await {
+            self.remote_end_of_candidates.store(false, Ordering::SeqCst);
             Ok(agent.set_remote_credentials(new_ufrag, new_pwd).await?)
         } else {
             Err(Error::ErrICEAgentNotExist)
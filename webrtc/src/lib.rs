@@ -12,6 +12,9 @@ pub mod ice_transport;
 /// WebRTC DataChannel can be used for peer-to-peer transmitting arbitrary binary data.
 pub mod data_channel;
 
+/// Unreliable, unordered datagrams sent over the DTLS transport without SCTP.
+pub mod datagram_channel;
+
 /// Module responsible for multiplexing data streams of different protocols on one socket. Custom [`mux::endpoint::Endpoint`] with [`mux::mux_func::MatchFunc`] can be used for parsing your application-specific byte stream.
 pub mod mux; // TODO: why is this public? does someone really extend WebRTC stack?
 
@@ -29,6 +32,10 @@ pub mod rtp_transceiver;
 pub mod sctp_transport;
 pub mod track;
 
+/// HTTP signaling with WHIP and WHEP endpoints, enabled by the `whip` feature.
+#[cfg(feature = "whip")]
+pub mod whip;
+
 pub use error::Error;
 
 #[macro_use]
@@ -42,3 +49,37 @@ pub(crate) const RECEIVE_MTU: usize = 1460;
 pub(crate) const SDP_ATTRIBUTE_RID: &str = "rid";
 pub(crate) const SDP_ATTRIBUTE_SIMULCAST: &str = "simulcast";
 pub(crate) const GENERATED_CERTIFICATE_ORIGIN: &str = "WebRTC";
+
+/// spawn runs an internal task on the runtime set with
+/// [`SettingEngine::set_runtime_handle`](api::setting_engine::SettingEngine::set_runtime_handle),
+/// or on the ambient runtime without one. The tasks that the transports spawn from within it
+/// run on the same runtime.
+pub(crate) fn spawn<F>(
+    runtime: Option<&tokio::runtime::Handle>,
+    future: F,
+) -> tokio::task::JoinHandle<F::Output>
+where
+    F: std::future::Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match runtime {
+        Some(runtime) => runtime.spawn(future),
+        None => tokio::spawn(future),
+    }
+}
+
+/// in_runtime polls a future with the runtime set with
+/// [`SettingEngine::set_runtime_handle`](api::setting_engine::SettingEngine::set_runtime_handle)
+/// entered, so that the tasks it spawns, e.g. those of the interceptors it binds, run on that
+/// runtime too. The runtime is only entered for each poll, the future stays `Send`.
+pub(crate) async fn in_runtime<F>(runtime: Option<&tokio::runtime::Handle>, future: F) -> F::Output
+where
+    F: std::future::Future,
+{
+    let mut future = std::pin::pin!(future);
+    std::future::poll_fn(|cx| {
+        let _runtime = runtime.map(|runtime| runtime.enter());
+        future.as_mut().poll(cx)
+    })
+    .await
+}
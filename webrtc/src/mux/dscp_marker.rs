@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use arc_swap::ArcSwapOption;
+use ice::agent::Agent;
+use tokio::sync::Mutex;
+use util::sync::Mutex as SyncMutex;
+use util::Conn;
+
+use crate::api::setting_engine::DscpPolicy;
+use crate::rtp_transceiver::rtp_codec::RTPCodecType;
+use crate::rtp_transceiver::SSRC;
+
+/// DscpMarker marks the packets sent through a Mux with the DSCP codepoint of their class,
+/// re-marking the socket of the selected candidate pair whenever the class changes.
+pub struct DscpMarker {
+    policy: DscpPolicy,
+    agent: ArcSwapOption<Agent>,
+    ssrc_kinds: SyncMutex<HashMap<SSRC, RTPCodecType>>,
+    /// marked is the conn and codepoint the socket was last marked with, it is held
+    /// while sending so that concurrent sends of other classes wait for their turn
+    marked: Mutex<Option<(usize, u8)>>,
+}
+
+impl DscpMarker {
+    pub fn new(policy: DscpPolicy) -> Self {
+        DscpMarker {
+            policy,
+            agent: ArcSwapOption::empty(),
+            ssrc_kinds: SyncMutex::new(HashMap::new()),
+            marked: Mutex::new(None),
+        }
+    }
+
+    /// set_agent sets the agent whose selected candidate pair is marked
+    pub(crate) fn set_agent(&self, agent: Arc<Agent>) {
+        self.agent.store(Some(agent));
+    }
+
+    /// set_ssrc_kind classifies the RTP and RTCP packets of ssrc as audio or video
+    pub(crate) fn set_ssrc_kind(&self, ssrc: SSRC, kind: RTPCodecType) {
+        self.ssrc_kinds.lock().insert(ssrc, kind);
+    }
+
+    /// dscp returns the codepoint of the packet in buf, or None if it
+    /// should keep the current marking of the socket.
+    pub(crate) fn dscp(&self, buf: &[u8]) -> Option<u8> {
+        match *buf.first()? {
+            // DTLS, which also carries SCTP
+            20..=63 => Some(self.policy.data),
+            128..=191 => {
+                let ssrc_offset = match buf.get(1)? {
+                    // RTCP packet types, RFC 5761 section 4
+                    192..=223 => 4,
+                    _ => 8,
+                };
+                let ssrc = buf.get(ssrc_offset..ssrc_offset + 4)?;
+                let ssrc = SSRC::from_be_bytes([ssrc[0], ssrc[1], ssrc[2], ssrc[3]]);
+                match self.ssrc_kinds.lock().get(&ssrc) {
+                    Some(RTPCodecType::Audio) => Some(self.policy.audio),
+                    _ => Some(self.policy.video),
+                }
+            }
+            // STUN, ZRTP and TURN channels
+            _ => None,
+        }
+    }
+
+    /// send marks the socket of the selected candidate pair for the packet in buf
+    /// and sends it on conn.
+    pub(crate) async fn send(
+        &self,
+        conn: &Arc<dyn Conn + Send + Sync>,
+        buf: &[u8],
+    ) -> util::Result<usize> {
+        let mut marked = self.marked.lock().await;
+
+        if let (Some(dscp), Some(agent)) = (self.dscp(buf), &*self.agent.load()) {
+            if let Some(socket) = agent
+                .get_selected_candidate_pair()
+                .and_then(|pair| pair.local.get_conn().cloned())
+            {
+                let mark = (Arc::as_ptr(&socket) as *const () as usize, dscp);
+                if *marked != Some(mark) {
+                    ice::util::set_dscp(&socket, dscp);
+                    *marked = Some(mark);
+                }
+            }
+        }
+
+        conn.send(buf).await
+    }
+}
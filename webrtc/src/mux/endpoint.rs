@@ -7,6 +7,7 @@ use async_trait::async_trait;
 use tokio::sync::Mutex;
 use util::{Buffer, Conn};
 
+use crate::mux::dscp_marker::DscpMarker;
 use crate::mux::mux_func::MatchFunc;
 
 /// Endpoint implements net.Conn. It is used to read muxed packets.
@@ -16,6 +17,7 @@ pub struct Endpoint {
     pub(crate) match_fn: MatchFunc,
     pub(crate) next_conn: Arc<dyn Conn + Send + Sync>,
     pub(crate) endpoints: Arc<Mutex<HashMap<usize, Arc<Endpoint>>>>,
+    pub(crate) dscp_marker: Option<Arc<DscpMarker>>,
 }
 
 impl Endpoint {
@@ -52,7 +54,10 @@ impl Conn for Endpoint {
 
     /// writes bytes to the underlying conn
     async fn send(&self, buf: &[u8]) -> Result<usize> {
-        self.next_conn.send(buf).await
+        match &self.dscp_marker {
+            Some(dscp_marker) => dscp_marker.send(&self.next_conn, buf).await,
+            None => self.next_conn.send(buf).await,
+        }
     }
 
     async fn send_to(&self, _buf: &[u8], _target: SocketAddr) -> Result<usize> {
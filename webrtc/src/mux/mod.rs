@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod mux_test;
 
+pub mod dscp_marker;
 pub mod endpoint;
 pub mod mux_func;
 
@@ -8,11 +9,14 @@ use std::collections::HashMap;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use portable_atomic::AtomicUsize;
+use portable_atomic::{AtomicU64, AtomicUsize};
+use tokio::runtime::Handle;
 use tokio::sync::{mpsc, Mutex};
 use util::{Buffer, Conn};
+use waitgroup::WaitGroup;
 
 use crate::error::Result;
+use crate::mux::dscp_marker::DscpMarker;
 use crate::mux::endpoint::Endpoint;
 use crate::mux::mux_func::MatchFunc;
 use crate::util::Error;
@@ -27,6 +31,10 @@ const MAX_BUFFER_SIZE: usize = 1000 * 1000; // 1MB
 pub struct Config {
     pub conn: Arc<dyn Conn + Send + Sync>,
     pub buffer_size: usize,
+    /// runtime the read loop is spawned on, the ambient one if None
+    pub runtime: Option<Handle>,
+    /// dscp_marker marks the sent packets by their class, if any
+    pub dscp_marker: Option<Arc<DscpMarker>>,
 }
 
 /// Mux allows multiplexing
@@ -36,25 +44,38 @@ pub struct Mux {
     next_conn: Arc<dyn Conn + Send + Sync>,
     endpoints: Arc<Mutex<HashMap<usize, Arc<Endpoint>>>>,
     buffer_size: usize,
+    // packets received from next_conn
+    packets: Arc<AtomicU64>,
+    dscp_marker: Option<Arc<DscpMarker>>,
     closed_ch_tx: Option<mpsc::Sender<()>>,
+    // waited on by close_and_wait until the read loop exits
+    wg: Arc<Mutex<Option<WaitGroup>>>,
 }
 
 impl Mux {
     pub fn new(config: Config) -> Self {
         let (closed_ch_tx, closed_ch_rx) = mpsc::channel(1);
+        let wg = WaitGroup::new();
+        let w = wg.worker();
         let m = Mux {
             id: Arc::new(AtomicUsize::new(0)),
             next_conn: Arc::clone(&config.conn),
             endpoints: Arc::new(Mutex::new(HashMap::new())),
             buffer_size: config.buffer_size,
+            packets: Arc::new(AtomicU64::new(0)),
+            dscp_marker: config.dscp_marker.clone(),
             closed_ch_tx: Some(closed_ch_tx),
+            wg: Arc::new(Mutex::new(Some(wg))),
         };
 
         let buffer_size = m.buffer_size;
         let next_conn = Arc::clone(&m.next_conn);
         let endpoints = Arc::clone(&m.endpoints);
-        tokio::spawn(async move {
-            Mux::read_loop(buffer_size, next_conn, closed_ch_rx, endpoints).await;
+        let packets = Arc::clone(&m.packets);
+        crate::spawn(config.runtime.as_ref(), async move {
+            let _d = w;
+
+            Mux::read_loop(buffer_size, next_conn, closed_ch_rx, endpoints, packets).await;
         });
 
         m
@@ -72,6 +93,7 @@ impl Mux {
             match_fn: f,
             next_conn: Arc::clone(&self.next_conn),
             endpoints: Arc::clone(&self.endpoints),
+            dscp_marker: self.dscp_marker.clone(),
         });
 
         endpoints.insert(e.id, Arc::clone(&e));
@@ -79,6 +101,11 @@ impl Mux {
         e
     }
 
+    /// packets returns the number of packets received through the Mux so far
+    pub(crate) fn packets(&self) -> u64 {
+        self.packets.load(Ordering::SeqCst)
+    }
+
     /// remove_endpoint removes an endpoint from the Mux
     pub async fn remove_endpoint(&mut self, e: &Endpoint) {
         let mut endpoints = self.endpoints.lock().await;
@@ -93,11 +120,22 @@ impl Mux {
         endpoints.clear();
     }
 
+    /// close_and_wait closes the Mux like close, then waits for its read loop to exit.
+    pub async fn close_and_wait(&mut self) {
+        self.close().await;
+
+        let wg = self.wg.lock().await.take();
+        if let Some(wg) = wg {
+            wg.wait().await;
+        }
+    }
+
     async fn read_loop(
         buffer_size: usize,
         next_conn: Arc<dyn Conn + Send + Sync>,
         mut closed_ch_rx: mpsc::Receiver<()>,
         endpoints: Arc<Mutex<HashMap<usize, Arc<Endpoint>>>>,
+        packets: Arc<AtomicU64>,
     ) {
         let mut buf = vec![0u8; buffer_size];
         let mut n = 0usize;
@@ -107,6 +145,7 @@ impl Mux {
                 result = next_conn.recv(&mut buf) => {
                     if let Ok(m) = result{
                         n = m;
+                        packets.fetch_add(1, Ordering::SeqCst);
                     }
                 }
             };
@@ -17,6 +17,8 @@ async fn test_no_endpoints() -> crate::error::Result<()> {
     let (ca, _) = pipe();
 
     let mut m = Mux::new(Config {
+        runtime: None,
+        dscp_marker: None,
         conn: Arc::new(ca),
         buffer_size: TEST_PIPE_BUFFER_SIZE,
     });
@@ -98,6 +100,8 @@ async fn test_non_fatal_read() -> Result<()> {
     });
 
     let mut m = Mux::new(Config {
+        runtime: None,
+        dscp_marker: None,
         conn,
         buffer_size: TEST_PIPE_BUFFER_SIZE,
     });
@@ -124,6 +128,8 @@ async fn test_non_fatal_dispatch() -> Result<()> {
     let (ca, cb) = pipe();
 
     let mut m = Mux::new(Config {
+        runtime: None,
+        dscp_marker: None,
         conn: Arc::new(ca),
         buffer_size: TEST_PIPE_BUFFER_SIZE,
     });
@@ -140,3 +146,58 @@ async fn test_non_fatal_dispatch() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_packets() -> Result<()> {
+    let (ca, cb) = pipe();
+
+    let mut m = Mux::new(Config {
+        runtime: None,
+        dscp_marker: None,
+        conn: Arc::new(ca),
+        buffer_size: TEST_PIPE_BUFFER_SIZE,
+    });
+    assert_eq!(m.packets(), 0);
+
+    let e = m.new_endpoint(Box::new(match_all)).await;
+    let mut buff = vec![0u8; TEST_PIPE_BUFFER_SIZE];
+
+    cb.send(b"inbound").await?;
+    e.recv(&mut buff).await?;
+    assert_eq!(m.packets(), 1);
+
+    // the packets sent don't count, they don't tell the peer is still there
+    e.send(b"outbound").await?;
+    cb.recv(&mut buff).await?;
+    assert_eq!(m.packets(), 1);
+
+    m.close().await;
+
+    Ok(())
+}
+
+#[test]
+fn test_dscp_marker_classify() {
+    let marker = DscpMarker::new(crate::api::setting_engine::DscpPolicy::default());
+    marker.set_ssrc_kind(1, crate::rtp_transceiver::rtp_codec::RTPCodecType::Audio);
+
+    let rtp = |ssrc: u32| {
+        let mut buf = vec![0x80, 111, 0, 1, 0, 0, 0, 0];
+        buf.extend_from_slice(&ssrc.to_be_bytes());
+        buf
+    };
+    let rtcp = |ssrc: u32| {
+        let mut buf = vec![0x80, 200, 0, 6];
+        buf.extend_from_slice(&ssrc.to_be_bytes());
+        buf
+    };
+
+    assert_eq!(marker.dscp(&rtp(1)), Some(46), "audio rtp");
+    assert_eq!(marker.dscp(&rtcp(1)), Some(46), "audio rtcp");
+    assert_eq!(marker.dscp(&rtp(2)), Some(34), "unknown ssrc is video");
+    assert_eq!(marker.dscp(&rtcp(2)), Some(34), "unknown ssrc is video");
+    assert_eq!(marker.dscp(&[23, 0xfe, 0xfd]), Some(10), "dtls is data");
+    assert_eq!(marker.dscp(&[0, 1, 0, 0]), None, "stun keeps the marking");
+    assert_eq!(marker.dscp(&[0x80, 111, 0]), None, "truncated rtp");
+    assert_eq!(marker.dscp(&[]), None);
+}
@@ -6,9 +6,11 @@ use rcgen::{CertificateParams, KeyPair};
 use ring::rand::SystemRandom;
 use ring::rsa;
 use ring::signature::{EcdsaKeyPair, Ed25519KeyPair};
-use sha2::{Digest, Sha256};
 
-use crate::dtls_transport::dtls_fingerprint::RTCDtlsFingerprint;
+use crate::dtls_transport::dtls_fingerprint::{
+    fingerprint_value, RTCDtlsFingerprint, DEFAULT_FINGERPRINT_ALGORITHM,
+    SUPPORTED_FINGERPRINT_ALGORITHMS,
+};
 use crate::error::{Error, Result};
 use crate::peer_connection::math_rand_alpha;
 use crate::stats::stats_collector::StatsCollector;
@@ -192,18 +194,32 @@ impl RTCCertificate {
     /// TODO: return a fingerprint computed with the digest algorithm used in the certificate
     /// signature.
     pub fn get_fingerprints(&self) -> Vec<RTCDtlsFingerprint> {
-        let mut fingerprints = Vec::new();
+        self.fingerprints_with(&[DEFAULT_FINGERPRINT_ALGORITHM])
+    }
 
-        for c in &self.dtls_certificate.certificate {
-            let mut h = Sha256::new();
-            h.update(c.as_ref());
-            let hashed = h.finalize();
-            let values: Vec<String> = hashed.iter().map(|x| format! {"{x:02x}"}).collect();
+    /// fingerprints returns the fingerprints of this certificate in every algorithm of
+    /// SUPPORTED_FINGERPRINT_ALGORITHMS, for peers that expect one other than SHA-256.
+    pub fn fingerprints(&self) -> Vec<RTCDtlsFingerprint> {
+        self.fingerprints_with(&SUPPORTED_FINGERPRINT_ALGORITHMS)
+    }
+
+    /// fingerprints_with returns the fingerprints of this certificate in the given
+    /// algorithms, skipping the ones that aren't supported.
+    pub(crate) fn fingerprints_with<S: AsRef<str>>(
+        &self,
+        algorithms: &[S],
+    ) -> Vec<RTCDtlsFingerprint> {
+        let mut fingerprints = Vec::new();
 
-            fingerprints.push(RTCDtlsFingerprint {
-                algorithm: "sha-256".to_owned(),
-                value: values.join(":"),
-            });
+        for algorithm in algorithms {
+            for c in &self.dtls_certificate.certificate {
+                if let Some(value) = fingerprint_value(algorithm.as_ref(), c.as_ref()) {
+                    fingerprints.push(RTCDtlsFingerprint {
+                        algorithm: algorithm.as_ref().to_lowercase(),
+                        value,
+                    });
+                }
+            }
         }
 
         fingerprints
@@ -283,6 +299,33 @@ mod test {
         Ok(())
     }
 
+    #[test]
+    fn test_certificate_fingerprints() -> Result<()> {
+        let kp = KeyPair::generate_for(&rcgen::PKCS_ECDSA_P256_SHA256)?;
+        let cert = RTCCertificate::from_key_pair(kp)?;
+
+        let fingerprints = cert.fingerprints();
+        let algorithms: Vec<&str> = fingerprints.iter().map(|f| f.algorithm.as_str()).collect();
+        assert_eq!(algorithms, SUPPORTED_FINGERPRINT_ALGORITHMS);
+
+        // Each value is the colon separated hex of the digest
+        for (fingerprint, digest_len) in fingerprints.iter().zip([32, 48, 64, 20]) {
+            assert_eq!(fingerprint.value.len(), digest_len * 3 - 1);
+            assert_eq!(fingerprint.value, fingerprint.value.to_lowercase());
+        }
+
+        let sha256 = cert.get_fingerprints();
+        assert_eq!(sha256.len(), 1);
+        assert_eq!(sha256[0].value, fingerprints[0].value);
+
+        let selected = cert.fingerprints_with(&["SHA-384", "md5"]);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].algorithm, "sha-384");
+        assert_eq!(selected[0].value, fingerprints[1].value);
+
+        Ok(())
+    }
+
     #[cfg(feature = "pem")]
     #[test]
     fn test_certificate_serialize_pem_and_from_pem() -> Result<()> {
@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use tokio::time::Instant;
+use util::sync::Mutex;
+
+/// RTCConnectionTiming reports how long each phase of the connection
+/// establishment took. A phase is `None` until it has both started and
+/// completed, and only its first run is measured, e.g. an ICE restart doesn't
+/// overwrite the initial ICE timing.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq)]
+pub struct RTCConnectionTiming {
+    /// time from the start of ICE gathering until the first local candidate
+    /// was gathered
+    pub ice_gathering: Option<Duration>,
+    /// time from the ICE transport entering the checking state until it was
+    /// connected
+    pub ice_connecting: Option<Duration>,
+    /// time from the start of the DTLS handshake until the DTLS transport was
+    /// connected
+    pub dtls_handshake: Option<Duration>,
+    /// time from the start of the SCTP transport until its association was
+    /// established
+    pub sctp_open: Option<Duration>,
+}
+
+/// PhaseTimer records when a phase of the connection establishment started
+/// and completed.
+#[derive(Default, Debug)]
+pub(crate) struct PhaseTimer {
+    span: Mutex<(Option<Instant>, Option<Instant>)>,
+}
+
+impl PhaseTimer {
+    /// start marks the beginning of the phase, unless it already started
+    pub(crate) fn start(&self) {
+        let mut span = self.span.lock();
+        if span.0.is_none() {
+            span.0 = Some(Instant::now());
+        }
+    }
+
+    /// complete marks the end of a started phase, unless it already completed
+    pub(crate) fn complete(&self) {
+        let mut span = self.span.lock();
+        if span.0.is_some() && span.1.is_none() {
+            span.1 = Some(Instant::now());
+        }
+    }
+
+    /// elapsed returns the duration of the phase once it completed
+    pub(crate) fn elapsed(&self) -> Option<Duration> {
+        match *self.span.lock() {
+            (Some(started), Some(completed)) => Some(completed - started),
+            _ => None,
+        }
+    }
+}
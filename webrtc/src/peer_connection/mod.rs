@@ -9,20 +9,22 @@ pub mod sdp;
 
 pub mod certificate;
 pub mod configuration;
+pub mod connection_timing;
 pub(crate) mod operation;
+pub mod peer_connection_handle;
 mod peer_connection_internal;
+mod peer_connection_span;
 pub mod peer_connection_state;
 pub mod policy;
 pub mod signaling_state;
 
 use std::future::Future;
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
-use ::ice::candidate::candidate_base::unmarshal_candidate;
-use ::ice::candidate::Candidate;
 use ::sdp::description::session::*;
 use ::sdp::util::ConnectionRole;
 use arc_swap::ArcSwapOption;
@@ -34,6 +36,7 @@ use rand::{thread_rng, Rng};
 use rcgen::KeyPair;
 use smol_str::SmolStr;
 use srtp::stream::Stream;
+use tokio::runtime::Handle;
 use tokio::sync::{mpsc, Mutex};
 
 use crate::api::media_engine::MediaEngine;
@@ -43,6 +46,7 @@ use crate::data_channel::data_channel_init::RTCDataChannelInit;
 use crate::data_channel::data_channel_parameters::DataChannelParameters;
 use crate::data_channel::data_channel_state::RTCDataChannelState;
 use crate::data_channel::RTCDataChannel;
+use crate::datagram_channel::RTCDatagramChannel;
 use crate::dtls_transport::dtls_fingerprint::RTCDtlsFingerprint;
 use crate::dtls_transport::dtls_parameters::DTLSParameters;
 use crate::dtls_transport::dtls_role::{
@@ -65,8 +69,10 @@ use crate::ice_transport::ice_transport_state::RTCIceTransportState;
 use crate::ice_transport::RTCIceTransport;
 use crate::peer_connection::certificate::RTCCertificate;
 use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::connection_timing::RTCConnectionTiming;
 use crate::peer_connection::offer_answer_options::{RTCAnswerOptions, RTCOfferOptions};
 use crate::peer_connection::operation::{Operation, Operations};
+use crate::peer_connection::peer_connection_span::PeerConnectionSpan;
 use crate::peer_connection::peer_connection_state::{
     NegotiationNeededState, RTCPeerConnectionState,
 };
@@ -76,7 +82,9 @@ use crate::peer_connection::sdp::*;
 use crate::peer_connection::signaling_state::{
     check_next_signaling_state, RTCSignalingState, StateChangeOp,
 };
-use crate::rtp_transceiver::rtp_codec::{RTCRtpHeaderExtensionCapability, RTPCodecType};
+use crate::rtp_transceiver::rtp_codec::{
+    codec_rtx_search, RTCRtpHeaderExtensionCapability, RTPCodecType,
+};
 use crate::rtp_transceiver::rtp_receiver::RTCRtpReceiver;
 use crate::rtp_transceiver::rtp_sender::RTCRtpSender;
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
@@ -87,6 +95,7 @@ use crate::rtp_transceiver::{
 use crate::sctp_transport::sctp_transport_capabilities::SCTPTransportCapabilities;
 use crate::sctp_transport::sctp_transport_state::RTCSctpTransportState;
 use crate::sctp_transport::RTCSctpTransport;
+use crate::stats::connection_quality::QualityScore;
 use crate::stats::StatsReport;
 use crate::track::track_local::TrackLocal;
 use crate::track::track_remote::TrackRemote;
@@ -102,6 +111,10 @@ pub(crate) const SIMULCAST_MAX_PROBE_ROUTINES: u64 = 25;
 
 pub(crate) const MEDIA_SECTION_APPLICATION: &str = "application";
 
+/// CLOSE_WAIT_TIMEOUT is how long close_and_wait waits for the tasks of the
+/// PeerConnection to exit
+pub(crate) const CLOSE_WAIT_TIMEOUT: Duration = Duration::from_secs(5);
+
 const RUNES_ALPHA: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
 
 /// math_rand_alpha generates a mathematical random alphabet sequence of the requested length.
@@ -142,6 +155,10 @@ pub type OnDataChannelHdlrFn = Box<
         + Sync,
 >;
 
+/// DataChannelPrefixHandler is a label prefix with the handler of the data channels
+/// whose label starts with it.
+pub(crate) type DataChannelPrefixHandler = (String, Arc<Mutex<OnDataChannelHdlrFn>>);
+
 pub type OnTrackHdlrFn = Box<
     dyn (FnMut(
             Arc<TrackRemote>,
@@ -228,6 +245,8 @@ impl RTCPeerConnection {
         RTCPeerConnection::init_configuration(&mut configuration)?;
 
         let (interceptor, stats_interceptor): (Arc<dyn Interceptor + Send + Sync>, _) = {
+            // the interceptors spawn their tasks when built, on the configured runtime if any
+            let _runtime = api.setting_engine.runtime.as_ref().map(|rt| rt.enter());
             let mut chain = api.interceptor_registry.build_chain("")?;
             let stats_interceptor = stats::make_stats_interceptor("");
             chain.add(stats_interceptor.clone());
@@ -235,28 +254,37 @@ impl RTCPeerConnection {
             (Arc::new(chain), stats_interceptor)
         };
 
+        let stats_id = format!(
+            "PeerConnection-{}",
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        );
+        let span = PeerConnectionSpan::new(&stats_id);
+
         let weak_interceptor = Arc::downgrade(&interceptor);
-        let (internal, configuration) = PeerConnectionInternal::new(
-            api,
-            weak_interceptor,
-            Arc::downgrade(&stats_interceptor),
-            configuration,
-        )
-        .await?;
+        let (internal, configuration) = span
+            .instrument(PeerConnectionInternal::new(
+                api,
+                weak_interceptor,
+                Arc::downgrade(&stats_interceptor),
+                configuration,
+                span.clone(),
+            ))
+            .await?;
         let internal_rtcp_writer = Arc::clone(&internal) as Arc<dyn RTCPWriter + Send + Sync>;
-        let interceptor_rtcp_writer = interceptor.bind_rtcp_writer(internal_rtcp_writer).await;
+        let interceptor_rtcp_writer = crate::in_runtime(
+            api.setting_engine.runtime.as_ref(),
+            interceptor.bind_rtcp_writer(internal_rtcp_writer),
+        )
+        .await;
 
         // <https://w3c.github.io/webrtc-pc/#constructor> (Step #2)
         // Some variables defined explicitly despite their implicit zero values to
         // allow better readability to understand what is happening.
         Ok(RTCPeerConnection {
-            stats_id: format!(
-                "PeerConnection-{}",
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_nanos()
-            ),
+            stats_id,
             interceptor,
             interceptor_rtcp_writer,
             internal,
@@ -304,7 +332,7 @@ impl RTCPeerConnection {
     }
 
     async fn do_signaling_state_change(&self, new_state: RTCSignalingState) {
-        log::info!("signaling state changed to {}", new_state);
+        self.internal.span.signaling_state_changed(new_state);
         if let Some(handler) = &*self.internal.on_signaling_state_change_handler.load() {
             let mut f = handler.lock().await;
             f(new_state).await;
@@ -319,6 +347,21 @@ impl RTCPeerConnection {
             .store(Some(Arc::new(Mutex::new(f))));
     }
 
+    /// on_data_channel_with_prefix sets an event handler which is invoked instead of the
+    /// one set by on_data_channel when the remote peer opens a data channel whose label
+    /// starts with prefix, e.g. "ctrl/" or "file/". The match is case-sensitive and when
+    /// several prefixes match the label, the handler of the longest one is invoked.
+    /// Setting a handler for a prefix again replaces the previous one.
+    pub fn on_data_channel_with_prefix(&self, prefix: &str, f: OnDataChannelHdlrFn) {
+        let handler = Arc::new(Mutex::new(f));
+        let mut handlers = self.internal.on_data_channel_prefix_handlers.lock();
+        if let Some((_, h)) = handlers.iter_mut().find(|(p, _)| p == prefix) {
+            *h = handler;
+        } else {
+            handlers.push((prefix.to_owned(), handler));
+        }
+    }
+
     /// on_negotiation_needed sets an event handler which is invoked when
     /// a change has occurred which requires session negotiation
     pub fn on_negotiation_needed(&self, f: OnNegotiationNeededHdlrFn) {
@@ -594,14 +637,21 @@ impl RTCPeerConnection {
     }
 
     fn do_track(
+        runtime: Option<&Handle>,
         on_track_handler: Arc<ArcSwapOption<Mutex<OnTrackHdlrFn>>>,
+        draining: &AtomicBool,
         track: Arc<TrackRemote>,
         receiver: Arc<RTCRtpReceiver>,
         transceiver: Arc<RTCRtpTransceiver>,
     ) {
+        if draining.load(Ordering::SeqCst) {
+            log::debug!("ignoring new track while draining: {:?}", track);
+            return;
+        }
+
         log::debug!("got new track: {:?}", track);
 
-        tokio::spawn(async move {
+        crate::spawn(runtime, async move {
             if let Some(handler) = &*on_track_handler.load() {
                 let mut f = handler.lock().await;
                 f(track, receiver, transceiver).await;
@@ -622,11 +672,12 @@ impl RTCPeerConnection {
     async fn do_ice_connection_state_change(
         handler: &Arc<ArcSwapOption<Mutex<OnICEConnectionStateChangeHdlrFn>>>,
         ice_connection_state: &Arc<AtomicU8>,
+        span: &PeerConnectionSpan,
         cs: RTCIceConnectionState,
     ) {
         ice_connection_state.store(cs as u8, Ordering::SeqCst);
 
-        log::info!("ICE connection state changed: {}", cs);
+        span.ice_connection_state_changed(cs);
         if let Some(handler) = &*handler.load() {
             let mut f = handler.lock().await;
             f(cs).await;
@@ -653,6 +704,7 @@ impl RTCPeerConnection {
 
     /// restart_ice restart ICE and triggers negotiation needed
     /// <https://w3c.github.io/webrtc-pc/#dom-rtcpeerconnection-restartice>
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn restart_ice(&self) -> Result<()> {
         self.internal.ice_transport.restart().await?;
         self.internal.trigger_negotiation_needed().await;
@@ -660,6 +712,7 @@ impl RTCPeerConnection {
     }
 
     // set_configuration updates the configuration of this PeerConnection object.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn set_configuration(&self, configuration: RTCConfiguration) -> Result<()> {
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-setconfiguration (step #2)
         let mut config_lock = self.configuration.lock().await;
@@ -739,6 +792,7 @@ impl RTCPeerConnection {
 
     /// create_offer starts the PeerConnection and generates the localDescription
     /// <https://w3c.github.io/webrtc-pc/#dom-rtcpeerconnection-createoffer>
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn create_offer(
         &self,
         options: Option<RTCOfferOptions>,
@@ -848,6 +902,9 @@ impl RTCPeerConnection {
                 let mut sdp_origin = self.internal.sdp_origin.lock().await;
                 update_sdp_origin(&mut sdp_origin, &mut d);
             }
+            if let Some(hook) = &self.internal.setting_engine.outgoing_sdp_hook {
+                hook(RTCSdpType::Offer, &mut d);
+            }
             let sdp = d.marshal();
 
             offer = RTCSessionDescription {
@@ -867,6 +924,16 @@ impl RTCPeerConnection {
             }
         }
 
+        // Adding the first data channel doesn't renegotiate the media: the media
+        // sections already negotiated are kept verbatim, so only the application
+        // section and the bundle group are new to the remote
+        if let Some(current) = self.current_local_description().await {
+            if adds_only_application_section(&current.sdp, &offer.sdp) {
+                offer.sdp = keep_unchanged_media_sections(&current.sdp, &offer.sdp);
+                offer.parsed = Some(offer.unmarshal()?);
+            }
+        }
+
         {
             let mut last_offer = self.internal.last_offer.lock().await;
             last_offer.clone_from(&offer.sdp);
@@ -874,6 +941,33 @@ impl RTCPeerConnection {
         Ok(offer)
     }
 
+    /// create_offer_for_changes generates an offer like create_offer, except that the
+    /// media sections which didn't change since the current local description are
+    /// copied from it verbatim. The offer then only differs from the negotiated one in
+    /// the media sections touched by the renegotiation, e.g. a transceiver direction or
+    /// a bandwidth set by an outgoing SDP hook, which keeps the changes easy to
+    /// validate on both ends with [`SessionDescriptionDiff`](::sdp::description::diff::SessionDescriptionDiff).
+    ///
+    /// Candidates aren't considered a change, since they were already exchanged. Without
+    /// a current local description this is the same as create_offer.
+    pub async fn create_offer_for_changes(
+        &self,
+        options: Option<RTCOfferOptions>,
+    ) -> Result<RTCSessionDescription> {
+        let mut offer = self.create_offer(options).await?;
+
+        let current_local_description = self.current_local_description().await;
+        if let Some(current) = current_local_description {
+            offer.sdp = keep_unchanged_media_sections(&current.sdp, &offer.sdp);
+            offer.parsed = Some(offer.unmarshal()?);
+
+            let mut last_offer = self.internal.last_offer.lock().await;
+            last_offer.clone_from(&offer.sdp);
+        }
+
+        Ok(offer)
+    }
+
     /// Update the PeerConnectionState given the state of relevant transports
     /// <https://www.w3.org/TR/webrtc/#rtcpeerconnectionstate-enum>
     async fn update_connection_state(
@@ -882,6 +976,7 @@ impl RTCPeerConnection {
         >,
         is_closed: &Arc<AtomicBool>,
         peer_connection_state: &Arc<AtomicU8>,
+        span: &PeerConnectionSpan,
         ice_connection_state: RTCIceConnectionState,
         dtls_transport_state: RTCDtlsTransportState,
     ) {
@@ -919,7 +1014,7 @@ impl RTCPeerConnection {
             return;
         }
 
-        log::info!("peer connection state changed: {}", connection_state);
+        span.connection_state_changed(connection_state);
         peer_connection_state.store(connection_state as u8, Ordering::SeqCst);
 
         RTCPeerConnection::do_peer_connection_state_change(
@@ -930,6 +1025,7 @@ impl RTCPeerConnection {
     }
 
     /// create_answer starts the PeerConnection and generates the localDescription
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn create_answer(
         &self,
         _options: Option<RTCAnswerOptions>,
@@ -959,8 +1055,15 @@ impl RTCPeerConnection {
             .to_connection_role();
         if connection_role == ConnectionRole::Unspecified {
             connection_role = DEFAULT_DTLS_ROLE_ANSWER.to_connection_role();
-            if let Some(parsed) = remote_description.parsed {
-                if Self::is_lite_set(&parsed) && !self.internal.setting_engine.candidates.ice_lite {
+            // The controlling agent is the DTLS server when the offer leaves the role to us
+            let ice_role = self.internal.setting_engine.ice_role;
+            if ice_role == RTCIceRole::Controlling {
+                connection_role = DTLSRole::Server.to_connection_role();
+            } else if let Some(parsed) = remote_description.parsed {
+                if ice_role == RTCIceRole::Unspecified
+                    && Self::is_lite_set(&parsed)
+                    && !self.internal.setting_engine.candidates.ice_lite
+                {
                     connection_role = DTLSRole::Server.to_connection_role();
                 }
             }
@@ -981,6 +1084,9 @@ impl RTCPeerConnection {
             let mut sdp_origin = self.internal.sdp_origin.lock().await;
             update_sdp_origin(&mut sdp_origin, &mut d);
         }
+        if let Some(hook) = &self.internal.setting_engine.outgoing_sdp_hook {
+            hook(RTCSdpType::Answer, &mut d);
+        }
         let sdp = d.marshal();
 
         let answer = RTCSessionDescription {
@@ -996,6 +1102,48 @@ impl RTCPeerConnection {
         Ok(answer)
     }
 
+    /// create_recvonly_answer generates an answer for a remote offer in which every
+    /// audio/video media section is only received, as is typical for ingest servers.
+    /// Transceivers matched to the offer are switched to recvonly and restricted to the
+    /// first codec negotiated with the MediaEngine (plus its RTX codec, if any).
+    pub async fn create_recvonly_answer(
+        &self,
+        options: Option<RTCAnswerOptions>,
+    ) -> Result<RTCSessionDescription> {
+        if self.remote_description().await.is_none() {
+            return Err(Error::ErrNoRemoteDescription);
+        } else if self.internal.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed);
+        } else if self.signaling_state() != RTCSignalingState::HaveRemoteOffer
+            && self.signaling_state() != RTCSignalingState::HaveLocalPranswer
+        {
+            return Err(Error::ErrIncorrectSignalingState);
+        }
+
+        for t in self.get_transceivers().await {
+            // transceivers without a mid were not matched to the offer and won't be answered
+            if t.mid().is_none() || t.stopped.load(Ordering::SeqCst) {
+                continue;
+            }
+
+            t.set_direction_internal(RTCRtpTransceiverDirection::Recvonly);
+
+            let codecs = t.get_codecs().await;
+            let primary = codecs
+                .iter()
+                .find(|c| !matches!(c.capability.mime_type.split_once('/'), Some((_, "rtx"))));
+            if let Some(primary) = primary {
+                let mut preferred = vec![primary.clone()];
+                if let Some(rtx) = codec_rtx_search(primary, &codecs) {
+                    preferred.push(rtx);
+                }
+                t.set_codec_preferences(preferred).await?;
+            }
+        }
+
+        self.create_answer(options).await
+    }
+
     // 4.4.1.6 Set the SessionDescription
     pub(crate) async fn set_description(
         &self,
@@ -1227,11 +1375,16 @@ impl RTCPeerConnection {
     }
 
     /// set_local_description sets the SessionDescription of the local peer
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn set_local_description(&self, mut desc: RTCSessionDescription) -> Result<()> {
         if self.internal.is_closed.load(Ordering::SeqCst) {
             return Err(Error::ErrConnectionClosed);
         }
 
+        if desc.sdp_type == RTCSdpType::Rollback {
+            return self.rollback(&desc, StateChangeOp::SetLocal).await;
+        }
+
         let have_local_description = {
             let current_local_description = self.internal.current_local_description.lock().await;
             current_local_description.is_some()
@@ -1256,6 +1409,11 @@ impl RTCPeerConnection {
         self.set_description(&desc, StateChangeOp::SetLocal).await?;
 
         let we_answer = desc.sdp_type == RTCSdpType::Answer;
+        if we_answer {
+            // the remote offer is answered, it can't be rolled back anymore
+            self.internal.remote_offer_transceivers.lock().await.clear();
+            self.internal.remote_offer_restore.lock().await.clear();
+        }
         let remote_description = self.remote_description().await;
         let mut local_transceivers = self.get_transceivers().await;
         if we_answer {
@@ -1323,6 +1481,47 @@ impl RTCPeerConnection {
         }
     }
 
+    /// rollback discards the pending offer set by op and returns the signaling state to
+    /// stable. The transceivers the offer created go away with it and the mids it assigned
+    /// are released, see <https://www.w3.org/TR/webrtc/#dfn-rollback>.
+    async fn rollback(&self, desc: &RTCSessionDescription, op: StateChangeOp) -> Result<()> {
+        self.set_description(desc, op).await?;
+
+        let created = std::mem::take(&mut *self.internal.remote_offer_transceivers.lock().await);
+        let restore = std::mem::take(&mut *self.internal.remote_offer_restore.lock().await);
+        if op == StateChangeOp::SetRemote {
+            for r in restore {
+                r.transceiver.set_direction_internal(r.direction);
+                r.transceiver.set_codec_preferences(r.codecs).await?;
+            }
+            if !created.is_empty() {
+                {
+                    let mut rtp_transceivers = self.internal.rtp_transceivers.lock().await;
+                    rtp_transceivers.retain(|t| !created.iter().any(|c| Arc::ptr_eq(t, c)));
+                }
+                for t in created {
+                    if let Err(err) = t.stop().await {
+                        log::warn!("Failed to stop transceiver on rollback: {}", err);
+                    }
+                }
+            }
+        }
+
+        let current_local_description = self.current_local_description().await;
+        for t in self.get_transceivers().await {
+            let negotiated = match (t.mid(), &current_local_description) {
+                (Some(mid), Some(desc)) => get_by_mid(&mid, desc).is_some(),
+                (Some(_), None) => false,
+                (None, _) => true,
+            };
+            if !negotiated {
+                t.clear_mid();
+            }
+        }
+
+        Ok(())
+    }
+
     /// local_description returns PendingLocalDescription if it is not null and
     /// otherwise it returns CurrentLocalDescription. This property is used to
     /// determine if set_local_description has already been called.
@@ -1344,17 +1543,30 @@ impl RTCPeerConnection {
     }
 
     /// set_remote_description sets the SessionDescription of the remote peer
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn set_remote_description(&self, mut desc: RTCSessionDescription) -> Result<()> {
         if self.internal.is_closed.load(Ordering::SeqCst) {
             return Err(Error::ErrConnectionClosed);
         }
 
+        if desc.sdp_type == RTCSdpType::Rollback {
+            return self.rollback(&desc, StateChangeOp::SetRemote).await;
+        }
+
         let is_renegotiation = {
             let current_remote_description = self.internal.current_remote_description.lock().await;
             current_remote_description.is_some()
         };
 
         desc.parsed = Some(desc.unmarshal()?);
+        if let Some(parsed) = &desc.parsed {
+            check_rtcp_mux(parsed)?;
+
+            // Codecs described by malformed attributes can't be negotiated
+            for err in parsed.codec_attribute_errors() {
+                log::warn!("remote description: {err}");
+            }
+        }
         self.set_description(&desc, StateChangeOp::SetRemote)
             .await?;
 
@@ -1367,6 +1579,18 @@ impl RTCPeerConnection {
             let mut local_transceivers = self.get_transceivers().await;
             let remote_description = self.remote_description().await;
             let we_offer = desc.sdp_type == RTCSdpType::Answer;
+            if desc.sdp_type == RTCSdpType::Offer {
+                self.internal.remote_offer_transceivers.lock().await.clear();
+                let mut restore = vec![];
+                for t in &local_transceivers {
+                    restore.push(TransceiverRestore {
+                        transceiver: Arc::clone(t),
+                        direction: t.direction(),
+                        codecs: t.codec_preferences().await,
+                    });
+                }
+                *self.internal.remote_offer_restore.lock().await = restore;
+            }
 
             if !we_offer {
                 if let Some(parsed) = remote_description.as_ref().and_then(|r| r.parsed.as_ref()) {
@@ -1450,6 +1674,13 @@ impl RTCPeerConnection {
                             .await;
 
                             self.internal.add_rtp_transceiver(Arc::clone(&t)).await;
+                            if desc.sdp_type == RTCSdpType::Offer {
+                                self.internal
+                                    .remote_offer_transceivers
+                                    .lock()
+                                    .await
+                                    .push(Arc::clone(&t));
+                            }
 
                             if t.mid().is_none() {
                                 t.set_mid(SmolStr::from(mid_value))?;
@@ -1512,6 +1743,28 @@ impl RTCPeerConnection {
                 }
             }
 
+            if let Some(remote_description) = &remote_description {
+                for t in self.get_transceivers().await {
+                    if let Some(mid) = t.mid() {
+                        let media = get_by_mid(&mid, remote_description);
+                        t.set_remote_content(media.and_then(get_content));
+                        t.set_remote_receive_constraints(media.and_then(get_receive_constraints));
+                        let header_extensions = media
+                            .filter(|media| media.media_name.port.value != 0)
+                            .and_then(|media| rtp_extensions_from_media_description(media).ok())
+                            .map(|extensions| {
+                                self.internal.media_engine.get_negotiated_header_extensions(
+                                    &extensions,
+                                    t.kind(),
+                                    t.direction(),
+                                )
+                            })
+                            .unwrap_or_default();
+                        t.set_negotiated_header_extensions(header_extensions);
+                    }
+                }
+            }
+
             let (remote_ufrag, remote_pwd, candidates) = extract_ice_details(parsed).await?;
 
             if is_renegotiation
@@ -1539,6 +1792,18 @@ impl RTCPeerConnection {
                     .await?;
             }
 
+            if parsed.end_of_candidates() {
+                self.internal
+                    .ice_transport
+                    .add_remote_candidate(None)
+                    .await?;
+            }
+
+            // the description is applied, the RTCP written from now on follows it
+            self.internal
+                .rtcp_reduced_size
+                .store(have_rtcp_reduced_size(parsed), Ordering::SeqCst);
+
             if is_renegotiation {
                 if we_offer {
                     self.start_rtp_senders().await?;
@@ -1567,10 +1832,12 @@ impl RTCPeerConnection {
 
             let (fingerprint, fingerprint_hash) = extract_fingerprint(parsed)?;
 
-            // If one of the agents is lite and the other one is not, the lite agent must be the controlling agent.
+            // If one of the agents is lite and the other one is not, the full agent must be the controlling agent.
             // If both or neither agents are lite the offering agent is controlling.
             // RFC 8445 S6.1.1
-            let ice_role = if (we_offer
+            let ice_role = if self.internal.setting_engine.ice_role != RTCIceRole::Unspecified {
+                self.internal.setting_engine.ice_role
+            } else if (we_offer
                 && remote_is_lite == self.internal.setting_engine.candidates.ice_lite)
                 || (remote_is_lite && !self.internal.setting_engine.candidates.ice_lite)
             {
@@ -1649,6 +1916,7 @@ impl RTCPeerConnection {
 
     /// add_ice_candidate accepts an ICE candidate string and adds it
     /// to the existing set of candidates.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn add_ice_candidate(&self, candidate: RTCIceCandidateInit) -> Result<()> {
         if self.remote_description().await.is_none() {
             return Err(Error::ErrNoRemoteDescription);
@@ -1660,10 +1928,7 @@ impl RTCPeerConnection {
         };
 
         let ice_candidate = if !candidate_value.is_empty() {
-            let candidate: Arc<dyn Candidate + Send + Sync> =
-                Arc::new(unmarshal_candidate(candidate_value)?);
-
-            Some(RTCIceCandidate::from(&candidate))
+            Some(RTCIceCandidate::from_sdp_string(candidate_value)?)
         } else {
             None
         };
@@ -1711,6 +1976,7 @@ impl RTCPeerConnection {
     }
 
     /// add_track adds a Track to the PeerConnection
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn add_track(
         &self,
         track: Arc<dyn TrackLocal + Send + Sync>,
@@ -1719,6 +1985,10 @@ impl RTCPeerConnection {
             return Err(Error::ErrConnectionClosed);
         }
 
+        if self.internal.draining.load(Ordering::SeqCst) {
+            return Err(Error::ErrDraining);
+        }
+
         {
             let rtp_transceivers = self.internal.rtp_transceivers.lock().await;
             for t in &*rtp_transceivers {
@@ -1760,6 +2030,7 @@ impl RTCPeerConnection {
     }
 
     /// remove_track removes a Track from the PeerConnection
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn remove_track(&self, sender: &Arc<RTCRtpSender>) -> Result<()> {
         if self.internal.is_closed.load(Ordering::SeqCst) {
             return Err(Error::ErrConnectionClosed);
@@ -1800,15 +2071,21 @@ impl RTCPeerConnection {
     }
 
     /// add_transceiver_from_kind Create a new RtpTransceiver and adds it to the set of transceivers.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn add_transceiver_from_kind(
         &self,
         kind: RTPCodecType,
         init: Option<RTCRtpTransceiverInit>,
     ) -> Result<Arc<RTCRtpTransceiver>> {
+        if self.internal.draining.load(Ordering::SeqCst) {
+            return Err(Error::ErrDraining);
+        }
+
         self.internal.add_transceiver_from_kind(kind, init).await
     }
 
     /// add_transceiver_from_track Create a new RtpTransceiver(SendRecv or SendOnly) and add it to the set of transceivers.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn add_transceiver_from_track(
         &self,
         track: Arc<dyn TrackLocal + Send + Sync>,
@@ -1818,6 +2095,10 @@ impl RTCPeerConnection {
             return Err(Error::ErrConnectionClosed);
         }
 
+        if self.internal.draining.load(Ordering::SeqCst) {
+            return Err(Error::ErrDraining);
+        }
+
         let direction = init
             .map(|init| init.direction)
             .unwrap_or(RTCRtpTransceiverDirection::Sendrecv);
@@ -1835,6 +2116,7 @@ impl RTCPeerConnection {
     /// create_data_channel creates a new DataChannel object with the given label
     /// and optional DataChannelInit used to configure properties of the
     /// underlying channel such as data reliability.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn create_data_channel(
         &self,
         label: &str,
@@ -1845,6 +2127,10 @@ impl RTCPeerConnection {
             return Err(Error::ErrConnectionClosed);
         }
 
+        if self.internal.draining.load(Ordering::SeqCst) {
+            return Err(Error::ErrDraining);
+        }
+
         let mut params = DataChannelParameters {
             label: label.to_owned(),
             ordered: true,
@@ -1878,6 +2164,11 @@ impl RTCPeerConnection {
 
             // https://w3c.github.io/webrtc-pc/#peer-to-peer-data-api (Step #12)
             params.negotiated = options.negotiated;
+
+            // https://w3c.github.io/webrtc-priority/#rtcdatachannel-extensions
+            if let Some(priority) = options.priority {
+                params.priority = priority;
+            }
         }
 
         let d = Arc::new(RTCDataChannel::new(
@@ -1909,6 +2200,28 @@ impl RTCPeerConnection {
         Ok(d)
     }
 
+    /// create_datagram_channel creates a DatagramChannel that sends unreliable,
+    /// unordered datagrams over the DTLS transport of the PeerConnection. It
+    /// doesn't need SCTP, but the session still has to negotiate a media or an
+    /// application section to establish ICE and DTLS. Both peers have to enable
+    /// datagram channels with SettingEngine::enable_datagram_channels, and all
+    /// channels of a PeerConnection share the same datagrams.
+    pub fn create_datagram_channel(&self) -> Result<Arc<RTCDatagramChannel>> {
+        if self.internal.is_closed.load(Ordering::SeqCst) {
+            return Err(Error::ErrConnectionClosed);
+        }
+
+        let demux = match &self.internal.dtls_transport.datagram_demux {
+            Some(demux) => Arc::clone(demux),
+            None => return Err(Error::ErrDatagramChannelsNotEnabled),
+        };
+
+        Ok(Arc::new(RTCDatagramChannel {
+            dtls_transport: Arc::clone(&self.internal.dtls_transport),
+            demux,
+        }))
+    }
+
     /// set_identity_provider is used to configure an identity provider to generate identity assertions
     pub fn set_identity_provider(&self, _provider: &str) -> Result<()> {
         Err(Error::ErrPeerConnSetIdentityProviderNotImplemented)
@@ -1916,6 +2229,9 @@ impl RTCPeerConnection {
 
     /// write_rtcp sends a user provided RTCP packet to the connected peer. If no peer is connected the
     /// packet is discarded. It also runs any configured interceptors.
+    ///
+    /// The packets are sent as a single compound RTCP packet in one datagram, so several packets
+    /// can be batched into one call to save on SRTCP and socket overhead.
     pub async fn write_rtcp(
         &self,
         pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
@@ -1925,17 +2241,43 @@ impl RTCPeerConnection {
     }
 
     /// close ends the PeerConnection
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
     pub async fn close(&self) -> Result<()> {
+        RTCPeerConnection::close_internal(&self.internal, &self.interceptor, false).await
+    }
+
+    /// close_and_wait ends the PeerConnection like close, then waits for the tasks of the
+    /// ICE agent, the mux and the media acceptors to exit. Once it returns the sockets
+    /// gathered by the PeerConnection are released, sockets of a shared UDP mux excepted.
+    ///
+    /// It has to be the call that closes the PeerConnection, it doesn't wait if the
+    /// PeerConnection was already closed. The event handlers run outside of the waited
+    /// tasks, so it can be called from them, e.g. from on_peer_connection_state_change.
+    /// The wait is bounded: if the tasks, such as a candidate gathering stuck on an
+    /// unresponsive STUN or TURN server, are still running after 5 seconds an error is
+    /// returned, and the sockets are released once they exit.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", parent = self.internal.span.span(), skip_all))]
+    pub async fn close_and_wait(&self) -> Result<()> {
+        RTCPeerConnection::close_internal(&self.internal, &self.interceptor, true).await
+    }
+
+    /// close_internal closes the PeerConnection of internal, it is shared by
+    /// close, close_and_wait and RTCPeerConnectionHandle::close
+    pub(crate) async fn close_internal(
+        internal: &PeerConnectionInternal,
+        interceptor: &Arc<dyn Interceptor + Send + Sync>,
+        wait: bool,
+    ) -> Result<()> {
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #1)
-        if self.internal.is_closed.load(Ordering::SeqCst) {
+        if internal.is_closed.load(Ordering::SeqCst) {
             return Ok(());
         }
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #2)
-        self.internal.is_closed.store(true, Ordering::SeqCst);
+        internal.is_closed.store(true, Ordering::SeqCst);
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #3)
-        self.internal
+        internal
             .signaling_state
             .store(RTCSignalingState::Closed as u8, Ordering::SeqCst);
 
@@ -1947,13 +2289,13 @@ impl RTCPeerConnection {
         //    continue the chain the Mux has to be closed.
         let mut close_errs = vec![];
 
-        if let Err(err) = self.interceptor.close().await {
+        if let Err(err) = interceptor.close().await {
             close_errs.push(Error::new(format!("interceptor: {err}")));
         }
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #4)
         {
-            let mut rtp_transceivers = self.internal.rtp_transceivers.lock().await;
+            let mut rtp_transceivers = internal.rtp_transceivers.lock().await;
             for t in &*rtp_transceivers {
                 if let Err(err) = t.stop().await {
                     close_errs.push(Error::new(format!("rtp_transceivers: {err}")));
@@ -1964,7 +2306,7 @@ impl RTCPeerConnection {
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #5)
         {
-            let mut data_channels = self.internal.sctp_transport.data_channels.lock().await;
+            let mut data_channels = internal.sctp_transport.data_channels.lock().await;
             for d in &*data_channels {
                 if let Err(err) = d.close().await {
                     close_errs.push(Error::new(format!("data_channels: {err}")));
@@ -1974,34 +2316,46 @@ impl RTCPeerConnection {
         }
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #6)
-        if let Err(err) = self.internal.sctp_transport.stop().await {
+        if let Err(err) = internal.sctp_transport.stop().await {
             close_errs.push(Error::new(format!("sctp_transport: {err}")));
         }
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #7)
-        if let Err(err) = self.internal.dtls_transport.stop().await {
+        if let Err(err) = internal.dtls_transport.stop().await {
             close_errs.push(Error::new(format!("dtls_transport: {err}")));
         }
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #8, #9, #10)
-        if let Err(err) = self.internal.ice_transport.stop().await {
+        let result = if wait {
+            internal.ice_transport.stop_and_wait().await
+        } else {
+            internal.ice_transport.stop().await
+        };
+        if let Err(err) = result {
             close_errs.push(Error::new(format!("ice_transport: {err}")));
         }
 
         // https://www.w3.org/TR/webrtc/#dom-rtcpeerconnection-close (step #11)
         RTCPeerConnection::update_connection_state(
-            &self.internal.on_peer_connection_state_change_handler,
-            &self.internal.is_closed,
-            &self.internal.peer_connection_state,
-            self.ice_connection_state(),
-            self.internal.dtls_transport.state(),
+            &internal.on_peer_connection_state_change_handler,
+            &internal.is_closed,
+            &internal.peer_connection_state,
+            &internal.span,
+            internal.ice_connection_state.load(Ordering::SeqCst).into(),
+            internal.dtls_transport.state(),
         )
         .await;
 
-        if let Err(err) = self.internal.ops.close().await {
+        if let Err(err) = internal.ops.close().await {
             close_errs.push(Error::new(format!("ops: {err}")));
         }
 
+        if wait {
+            if let Err(err) = internal.wait_tasks().await {
+                close_errs.push(err);
+            }
+        }
+
         flatten_errs(close_errs)
     }
 
@@ -2075,6 +2429,17 @@ impl RTCPeerConnection {
             .into()
     }
 
+    /// connection_timing returns how long each phase of the connection
+    /// establishment took, as far as it has completed.
+    pub fn connection_timing(&self) -> RTCConnectionTiming {
+        RTCConnectionTiming {
+            ice_gathering: self.internal.ice_gatherer.gathering_timer.elapsed(),
+            ice_connecting: self.internal.ice_transport.connecting_timer.elapsed(),
+            dtls_handshake: self.internal.dtls_transport.handshake_timer.elapsed(),
+            sctp_open: self.internal.sctp_transport.open_timer.elapsed(),
+        }
+    }
+
     pub async fn get_stats(&self) -> StatsReport {
         self.internal
             .get_stats(self.get_stats_id().to_owned())
@@ -2082,6 +2447,12 @@ impl RTCPeerConnection {
             .into()
     }
 
+    /// connection_quality estimates the quality of the connection from the round trip time,
+    /// jitter and loss the remote last reported, see [`QualityScore`] for the formula.
+    pub async fn connection_quality(&self) -> QualityScore {
+        QualityScore::from_stats_report(&self.get_stats().await)
+    }
+
     /// sctp returns the SCTPTransport for this PeerConnection
     ///
     /// The SCTP transport over which SCTP data is sent and received. If SCTP has not been negotiated, the value is nil.
@@ -2121,6 +2492,18 @@ impl RTCPeerConnection {
         gathering_complete_rx
     }
 
+    /// selected_local_addr returns the address of the local socket the connection uses, see
+    /// [`RTCIceTransport::selected_local_addr`]. None until a candidate pair is selected.
+    pub async fn selected_local_addr(&self) -> Option<SocketAddr> {
+        self.internal.ice_transport.selected_local_addr().await
+    }
+
+    /// selected_remote_addr returns the address of the remote socket the connection uses,
+    /// e.g. to log the IP of the remote. None until a candidate pair is selected.
+    pub async fn selected_remote_addr(&self) -> Option<SocketAddr> {
+        self.internal.ice_transport.selected_remote_addr().await
+    }
+
     /// Returns the internal [`RTCDtlsTransport`].
     pub fn dtls_transport(&self) -> Arc<RTCDtlsTransport> {
         Arc::clone(&self.internal.dtls_transport)
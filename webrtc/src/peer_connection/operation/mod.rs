@@ -8,6 +8,7 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use portable_atomic::AtomicUsize;
+use tokio::runtime::Handle;
 use tokio::sync::mpsc;
 use waitgroup::WaitGroup;
 
@@ -46,16 +47,20 @@ pub(crate) struct Operations {
 }
 
 impl Operations {
-    pub(crate) fn new() -> Self {
+    pub(crate) fn new(runtime: Option<&Handle>) -> Self {
         let length = Arc::new(AtomicUsize::new(0));
         let (ops_tx, ops_rx) = mpsc::unbounded_channel();
         let (close_tx, close_rx) = mpsc::channel(1);
         let l = Arc::clone(&length);
         let ops_tx = Arc::new(ops_tx);
         let ops_tx2 = Arc::clone(&ops_tx);
-        tokio::spawn(async move {
+        let run = async move {
             Operations::start(l, ops_tx, ops_rx, close_rx).await;
-        });
+        };
+        // the operations run in the span of the PeerConnection that created them
+        #[cfg(feature = "tracing")]
+        let run = tracing::Instrument::in_current_span(run);
+        crate::spawn(runtime, run);
 
         Operations {
             length,
@@ -5,7 +5,7 @@ use crate::error::Result;
 
 #[tokio::test]
 async fn test_operations_enqueue() -> Result<()> {
-    let ops = Operations::new();
+    let ops = Operations::new(None);
     for _ in 0..100 {
         let results = Arc::new(Mutex::new(vec![0; 16]));
         for k in 0..16 {
@@ -40,7 +40,7 @@ async fn test_operations_enqueue() -> Result<()> {
 
 #[tokio::test]
 async fn test_operations_done() -> Result<()> {
-    let ops = Operations::new();
+    let ops = Operations::new(None);
     ops.done().await;
 
     Ok(())
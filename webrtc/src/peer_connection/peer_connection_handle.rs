@@ -0,0 +1,98 @@
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Weak};
+
+use interceptor::Interceptor;
+
+use super::peer_connection_internal::PeerConnectionInternal;
+use super::peer_connection_state::RTCPeerConnectionState;
+use super::RTCPeerConnection;
+use crate::data_channel::data_channel_state::RTCDataChannelState;
+use crate::error::Result;
+
+/// RTCPeerConnectionHandle refers to a PeerConnection created by an API
+/// without keeping it alive. It is returned by [`API::peer_connections`] so
+/// a server can inspect and close the PeerConnections it didn't keep track of.
+///
+/// [`API::peer_connections`]: crate::api::API::peer_connections
+#[derive(Clone)]
+pub struct RTCPeerConnectionHandle {
+    stats_id: String,
+    internal: Weak<PeerConnectionInternal>,
+    interceptor: Weak<dyn Interceptor + Send + Sync>,
+}
+
+impl std::fmt::Debug for RTCPeerConnectionHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RTCPeerConnectionHandle")
+            .field("stats_id", &self.stats_id)
+            .field("connection_state", &self.connection_state())
+            .finish()
+    }
+}
+
+impl RTCPeerConnectionHandle {
+    pub(crate) fn new(pc: &RTCPeerConnection) -> Self {
+        RTCPeerConnectionHandle {
+            stats_id: pc.get_stats_id().to_owned(),
+            internal: Arc::downgrade(&pc.internal),
+            interceptor: Arc::downgrade(&pc.interceptor),
+        }
+    }
+
+    /// stats_id returns the stats id of the PeerConnection
+    pub fn stats_id(&self) -> &str {
+        &self.stats_id
+    }
+
+    /// is_closed returns true once the PeerConnection has been closed or dropped
+    pub fn is_closed(&self) -> bool {
+        self.connection_state() == RTCPeerConnectionState::Closed
+    }
+
+    /// connection_state returns the state of the PeerConnection, Closed once
+    /// it has been dropped
+    pub fn connection_state(&self) -> RTCPeerConnectionState {
+        match self.internal.upgrade() {
+            Some(internal) if !internal.is_closed.load(Ordering::SeqCst) => {
+                internal.peer_connection_state.load(Ordering::SeqCst).into()
+            }
+            _ => RTCPeerConnectionState::Closed,
+        }
+    }
+
+    /// is_idle returns true if the PeerConnection has no open data channel and
+    /// no transceiver currently sending or receiving media, so closing it
+    /// doesn't interrupt anything.
+    pub async fn is_idle(&self) -> bool {
+        let internal = match self.internal.upgrade() {
+            Some(internal) => internal,
+            None => return true,
+        };
+
+        {
+            let data_channels = internal.sctp_transport.data_channels.lock().await;
+            if data_channels
+                .iter()
+                .any(|d| d.ready_state() == RTCDataChannelState::Open)
+            {
+                return false;
+            }
+        }
+
+        let rtp_transceivers = internal.rtp_transceivers.lock().await;
+        !rtp_transceivers.iter().any(|t| {
+            let direction = t.current_direction();
+            direction.has_send() || direction.has_recv()
+        })
+    }
+
+    /// close closes the PeerConnection, as RTCPeerConnection::close does
+    pub async fn close(&self) -> Result<()> {
+        match (self.internal.upgrade(), self.interceptor.upgrade()) {
+            (Some(internal), Some(interceptor)) => {
+                RTCPeerConnection::close_internal(&internal, &interceptor, false).await
+            }
+            _ => Ok(()),
+        }
+    }
+}
@@ -3,6 +3,7 @@ use std::sync::Weak;
 
 use super::*;
 use crate::rtp_transceiver::create_stream_info;
+use crate::rtp_transceiver::rtp_codec::RTCRtpCodecParameters;
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::{
     InboundRTPStats, OutboundRTPStats, RTCStatsType, RemoteInboundRTPStats, RemoteOutboundRTPStats,
@@ -13,9 +14,30 @@ use crate::track::TrackStream;
 use crate::SDP_ATTRIBUTE_RID;
 use arc_swap::ArcSwapOption;
 use portable_atomic::AtomicIsize;
+use rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use rtcp::payload_feedbacks::receiver_estimated_maximum_bitrate::ReceiverEstimatedMaximumBitrate;
+use rtcp::payload_feedbacks::slice_loss_indication::SliceLossIndication;
+use rtcp::transport_feedbacks::rapid_resynchronization_request::RapidResynchronizationRequest;
+use rtcp::transport_feedbacks::transport_layer_cc::TransportLayerCc;
+use rtcp::transport_feedbacks::transport_layer_nack::TransportLayerNack;
 use smol_str::SmolStr;
-use tokio::time::Instant;
+use tokio::runtime::Handle;
+use tokio::time::{Duration, Instant};
+use util::sync::Mutex as SyncMutex;
 use util::Unmarshal;
+use waitgroup::{WaitGroup, Worker};
+
+/// the number of times the traffic is checked per idle timeout
+const IDLE_CHECKS_PER_TIMEOUT: u32 = 4;
+
+/// TransceiverRestore is what answering a remote offer may change of a transceiver, for
+/// example with create_recvonly_answer.
+pub(super) struct TransceiverRestore {
+    pub(super) transceiver: Arc<RTCRtpTransceiver>,
+    pub(super) direction: RTCRtpTransceiverDirection,
+    pub(super) codecs: Vec<RTCRtpCodecParameters>,
+}
 
 pub(crate) struct PeerConnectionInternal {
     /// a value containing the last known greater mid value
@@ -44,6 +66,9 @@ pub(crate) struct PeerConnectionInternal {
         Arc<ArcSwapOption<Mutex<OnPeerConnectionStateChangeHdlrFn>>>,
     pub(super) peer_connection_state: Arc<AtomicU8>,
     pub(super) ice_connection_state: Arc<AtomicU8>,
+    /// rtcp_reduced_size is set when the remote description allows RTCP
+    /// packets that don't start with a sender or receiver report (RFC 5506)
+    pub(super) rtcp_reduced_size: AtomicBool,
 
     pub(super) sctp_transport: Arc<RTCSctpTransport>,
     pub(super) rtp_transceivers: Arc<Mutex<Vec<Arc<RTCRtpTransceiver>>>>,
@@ -54,6 +79,8 @@ pub(crate) struct PeerConnectionInternal {
     pub(super) on_ice_connection_state_change_handler:
         Arc<ArcSwapOption<Mutex<OnICEConnectionStateChangeHdlrFn>>>,
     pub(super) on_data_channel_handler: Arc<ArcSwapOption<Mutex<OnDataChannelHdlrFn>>>,
+    /// handlers of the data channels whose label starts with their prefix
+    pub(super) on_data_channel_prefix_handlers: Arc<SyncMutex<Vec<DataChannelPrefixHandler>>>,
 
     pub(super) ice_gatherer: Arc<RTCIceGatherer>,
 
@@ -61,12 +88,22 @@ pub(crate) struct PeerConnectionInternal {
     pub(super) current_remote_description: Arc<Mutex<Option<RTCSessionDescription>>>,
     pub(super) pending_local_description: Arc<Mutex<Option<RTCSessionDescription>>>,
     pub(super) pending_remote_description: Arc<Mutex<Option<RTCSessionDescription>>>,
+    /// transceivers created by applying the last remote offer, removed if it is rolled back
+    pub(super) remote_offer_transceivers: Mutex<Vec<Arc<RTCRtpTransceiver>>>,
+    /// the transceivers as they were before the last remote offer, restored if it is rolled back
+    pub(super) remote_offer_restore: Mutex<Vec<TransceiverRestore>>,
 
     // A reference to the associated API state used by this connection
     pub(super) setting_engine: Arc<SettingEngine>,
     pub(crate) media_engine: Arc<MediaEngine>,
     pub(super) interceptor: Weak<dyn Interceptor + Send + Sync>,
     stats_interceptor: Weak<stats::StatsInterceptor>,
+    // Set while the API is draining its PeerConnections
+    pub(super) draining: Arc<AtomicBool>,
+    // The media acceptors and the routines they spawn, waited on by close_and_wait
+    wg: SyncMutex<Option<WaitGroup>>,
+
+    pub(super) span: PeerConnectionSpan,
 }
 
 impl PeerConnectionInternal {
@@ -75,6 +112,7 @@ impl PeerConnectionInternal {
         interceptor: Weak<dyn Interceptor + Send + Sync>,
         stats_interceptor: Weak<stats::StatsInterceptor>,
         mut configuration: RTCConfiguration,
+        span: PeerConnectionSpan,
     ) -> Result<(Arc<Self>, RTCConfiguration)> {
         // Create the ice gatherer
         let ice_gatherer = Arc::new(api.new_ice_gatherer(RTCIceGatherOptions {
@@ -100,7 +138,7 @@ impl PeerConnectionInternal {
             last_answer: Mutex::new("".to_owned()),
 
             on_negotiation_needed_handler: Arc::new(ArcSwapOption::empty()),
-            ops: Arc::new(Operations::new()),
+            ops: Arc::new(Operations::new(api.setting_engine.runtime.as_ref())),
             is_closed: Arc::new(AtomicBool::new(false)),
             is_negotiation_needed: Arc::new(AtomicBool::new(false)),
             negotiation_needed_state: Arc::new(AtomicU8::new(NegotiationNeededState::Empty as u8)),
@@ -108,12 +146,14 @@ impl PeerConnectionInternal {
             ice_transport,
             dtls_transport,
             ice_connection_state: Arc::new(AtomicU8::new(RTCIceConnectionState::New as u8)),
+            rtcp_reduced_size: AtomicBool::new(false),
             sctp_transport,
             rtp_transceivers: Arc::new(Default::default()),
             on_track_handler: Arc::new(ArcSwapOption::empty()),
             on_signaling_state_change_handler: ArcSwapOption::empty(),
             on_ice_connection_state_change_handler: Arc::new(ArcSwapOption::empty()),
             on_data_channel_handler: Arc::new(Default::default()),
+            on_data_channel_prefix_handlers: Arc::new(SyncMutex::new(vec![])),
             ice_gatherer,
             current_local_description: Arc::new(Default::default()),
             current_remote_description: Arc::new(Default::default()),
@@ -130,6 +170,11 @@ impl PeerConnectionInternal {
             stats_interceptor,
             on_peer_connection_state_change_handler: Arc::new(ArcSwapOption::empty()),
             pending_remote_description: Arc::new(Default::default()),
+            remote_offer_transceivers: Mutex::new(vec![]),
+            remote_offer_restore: Mutex::new(vec![]),
+            draining: Arc::clone(&api.peer_connections.draining),
+            wg: SyncMutex::new(Some(WaitGroup::new())),
+            span,
         });
 
         // Wire up the ice transport connection state change handler
@@ -141,6 +186,7 @@ impl PeerConnectionInternal {
             Arc::clone(&pc.on_ice_connection_state_change_handler);
         let on_peer_connection_state_change_handler =
             Arc::clone(&pc.on_peer_connection_state_change_handler);
+        let span = pc.span.clone();
 
         pc.ice_transport.on_connection_state_change(Box::new(
             move |state: RTCIceTransportState| {
@@ -166,10 +212,12 @@ impl PeerConnectionInternal {
                     Arc::clone(&on_peer_connection_state_change_handler);
                 let is_closed = Arc::clone(&is_closed);
                 let peer_connection_state = Arc::clone(&peer_connection_state);
-                Box::pin(async move {
+                let span = span.clone();
+                Box::pin(span.clone().instrument(async move {
                     RTCPeerConnection::do_ice_connection_state_change(
                         &on_ice_connection_state_change_handler,
                         &ice_connection_state,
+                        &span,
                         cs,
                     )
                     .await;
@@ -179,6 +227,7 @@ impl PeerConnectionInternal {
                             &on_peer_connection_state_change_handler,
                             &is_closed,
                             &peer_connection_state,
+                            &span,
                             cs,
                             dtls_transport.state(),
                         )
@@ -186,17 +235,28 @@ impl PeerConnectionInternal {
                     } else {
                         log::warn!("on_ice_connection_state_change: dtls_transport unavailable");
                     }
-                })
+                }))
             },
         ));
 
         // Wire up the on datachannel handler
         let on_data_channel_handler = Arc::clone(&pc.on_data_channel_handler);
+        let on_data_channel_prefix_handlers = Arc::clone(&pc.on_data_channel_prefix_handlers);
         pc.sctp_transport
             .on_data_channel(Box::new(move |d: Arc<RTCDataChannel>| {
                 let on_data_channel_handler = Arc::clone(&on_data_channel_handler);
+                // The longest prefix matching the label wins
+                let prefix_handler = on_data_channel_prefix_handlers
+                    .lock()
+                    .iter()
+                    .filter(|(prefix, _)| d.label().starts_with(prefix.as_str()))
+                    .max_by_key(|(prefix, _)| prefix.len())
+                    .map(|(_, handler)| Arc::clone(handler));
                 Box::pin(async move {
-                    if let Some(handler) = &*on_data_channel_handler.load() {
+                    if let Some(handler) = prefix_handler {
+                        let mut f = handler.lock().await;
+                        f(d).await;
+                    } else if let Some(handler) = &*on_data_channel_handler.load() {
                         let mut f = handler.lock().await;
                         f(d).await;
                     }
@@ -284,13 +344,84 @@ impl PeerConnectionInternal {
             .await?;
         if let Some(parsed) = &remote_desc.parsed {
             if have_application_media_section(parsed) {
-                self.start_sctp().await;
+                self.start_sctp(SCTPTransportCapabilities {
+                    max_message_size: get_max_message_size(parsed),
+                })
+                .await;
             }
         }
 
         Ok(())
     }
 
+    /// worker returns a worker of the wait group of the PeerConnection's tasks,
+    /// None once wait_tasks was called.
+    fn worker(&self) -> Option<Worker> {
+        self.wg.lock().as_ref().map(|wg| wg.worker())
+    }
+
+    /// wait_tasks waits for the tasks spawned by the PeerConnection to exit, for at
+    /// most CLOSE_WAIT_TIMEOUT.
+    pub(super) async fn wait_tasks(&self) -> Result<()> {
+        let wg = self.wg.lock().take();
+        if let Some(wg) = wg {
+            if tokio::time::timeout(CLOSE_WAIT_TIMEOUT, wg.wait())
+                .await
+                .is_err()
+            {
+                return Err(Error::new(
+                    "timed out waiting for the media tasks to exit".to_owned(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// idle_monitor closes the PeerConnection once no packet was received or sent
+    /// through the ICE transport for idle_timeout. It holds no reference to the
+    /// PeerConnection and exits once it is closed or dropped.
+    fn idle_monitor(self: &Arc<Self>, idle_timeout: Duration) {
+        let pci = Arc::downgrade(self);
+        crate::spawn(
+            self.setting_engine.runtime.as_ref(),
+            self.span.instrument(async move {
+                let mut ticker = tokio::time::interval(idle_timeout / IDLE_CHECKS_PER_TIMEOUT);
+                let mut packets = None;
+                let mut last_activity = Instant::now();
+                loop {
+                    ticker.tick().await;
+
+                    let pci = match pci.upgrade() {
+                        Some(pci) if !pci.is_closed.load(Ordering::SeqCst) => pci,
+                        _ => return,
+                    };
+                    let current_packets = pci.ice_transport.packets().await;
+                    if current_packets != packets {
+                        packets = current_packets;
+                        last_activity = Instant::now();
+                        continue;
+                    }
+                    if last_activity.elapsed() < idle_timeout {
+                        continue;
+                    }
+
+                    log::info!(
+                        "Closing PeerConnection idle for {:?}",
+                        last_activity.elapsed()
+                    );
+                    if let Some(interceptor) = pci.interceptor.upgrade() {
+                        if let Err(err) =
+                            RTCPeerConnection::close_internal(&pci, &interceptor, false).await
+                        {
+                            log::warn!("Failed to close idle PeerConnection: {}", err);
+                        }
+                    }
+                    return;
+                }
+            }),
+        );
+    }
+
     /// undeclared_media_processor handles RTP/RTCP packets that don't match any a:ssrc lines
     fn undeclared_media_processor(self: &Arc<Self>) {
         let dtls_transport = Arc::clone(&self.dtls_transport);
@@ -298,91 +429,128 @@ impl PeerConnectionInternal {
         let pci = Arc::clone(self);
 
         // SRTP acceptor
-        tokio::spawn(async move {
-            let simulcast_routine_count = Arc::new(AtomicU64::new(0));
-            loop {
-                let srtp_session = match dtls_transport.get_srtp_session().await {
-                    Some(s) => s,
-                    None => {
-                        log::warn!("undeclared_media_processor failed to open SrtpSession");
-                        return;
-                    }
-                };
+        let w = self.worker();
+        crate::spawn(
+            self.setting_engine.runtime.as_ref(),
+            self.span.instrument(async move {
+                let _d = w;
+
+                let simulcast_routine_count = Arc::new(AtomicU64::new(0));
+                let max_probe_routines = pci.setting_engine.get_simulcast_max_probe_routines();
+                loop {
+                    let srtp_session = match dtls_transport.get_srtp_session().await {
+                        Some(s) => s,
+                        None => {
+                            log::warn!("undeclared_media_processor failed to open SrtpSession");
+                            return;
+                        }
+                    };
 
-                let stream = match srtp_session.accept().await {
-                    Ok(stream) => stream,
-                    Err(err) => {
-                        log::warn!("Failed to accept RTP {}", err);
-                        return;
+                    let stream = match srtp_session.accept().await {
+                        Ok(stream) => stream,
+                        Err(err) => {
+                            log::warn!("Failed to accept RTP {}", err);
+                            return;
+                        }
+                    };
+
+                    if is_closed.load(Ordering::SeqCst) {
+                        if let Err(err) = stream.close().await {
+                            log::warn!("Failed to close RTP stream {}", err);
+                        }
+                        continue;
                     }
-                };
 
-                if is_closed.load(Ordering::SeqCst) {
-                    if let Err(err) = stream.close().await {
-                        log::warn!("Failed to close RTP stream {}", err);
+                    if !dtls_transport
+                        .admit_inbound_stream(&srtp_session, &stream)
+                        .await
+                    {
+                        continue;
                     }
-                    continue;
-                }
 
-                if simulcast_routine_count.fetch_add(1, Ordering::SeqCst) + 1
-                    >= SIMULCAST_MAX_PROBE_ROUTINES
-                {
-                    simulcast_routine_count.fetch_sub(1, Ordering::SeqCst);
-                    log::warn!("{:?}", Error::ErrSimulcastProbeOverflow);
-                    continue;
-                }
+                    if !start_simulcast_probe(&simulcast_routine_count, max_probe_routines) {
+                        log::warn!(
+                            "{} (limit {}), on_track will not be fired for ssrc({})",
+                            Error::ErrSimulcastProbeOverflow,
+                            max_probe_routines,
+                            stream.get_ssrc()
+                        );
+                        continue;
+                    }
 
-                {
-                    let dtls_transport = Arc::clone(&dtls_transport);
-                    let simulcast_routine_count = Arc::clone(&simulcast_routine_count);
-                    let pci = Arc::clone(&pci);
-                    tokio::spawn(async move {
-                        let ssrc = stream.get_ssrc();
-
-                        dtls_transport
-                            .store_simulcast_stream(ssrc, Arc::clone(&stream))
-                            .await;
-
-                        if let Err(err) = pci.handle_incoming_ssrc(stream, ssrc).await {
-                            log::warn!(
+                    {
+                        let dtls_transport = Arc::clone(&dtls_transport);
+                        let simulcast_routine_count = Arc::clone(&simulcast_routine_count);
+                        let pci = Arc::clone(&pci);
+                        let w = pci.worker();
+                        let runtime = pci.setting_engine.runtime.clone();
+                        crate::spawn(
+                            runtime.as_ref(),
+                            pci.span.clone().instrument(async move {
+                                let _d = w;
+
+                                let ssrc = stream.get_ssrc();
+
+                                dtls_transport
+                                    .store_simulcast_stream(ssrc, Arc::clone(&stream))
+                                    .await;
+
+                                if let Err(err) = pci.handle_incoming_ssrc(stream, ssrc).await {
+                                    log::warn!(
                                 "Incoming unhandled RTP ssrc({}), on_track will not be fired. {}",
                                 ssrc,
                                 err
                             );
-                        }
+                                }
 
-                        simulcast_routine_count.fetch_sub(1, Ordering::SeqCst);
-                    });
+                                simulcast_routine_count.fetch_sub(1, Ordering::SeqCst);
+                            }),
+                        );
+                    }
                 }
-            }
-        });
+            }),
+        );
 
         // SRTCP acceptor
         {
             let dtls_transport = Arc::clone(&self.dtls_transport);
-            tokio::spawn(async move {
-                loop {
-                    let srtcp_session = match dtls_transport.get_srtcp_session().await {
-                        Some(s) => s,
-                        None => {
-                            log::warn!("undeclared_media_processor failed to open SrtcpSession");
-                            return;
-                        }
-                    };
-
-                    let stream = match srtcp_session.accept().await {
-                        Ok(stream) => stream,
-                        Err(err) => {
-                            log::warn!("Failed to accept RTCP {}", err);
-                            return;
+            let w = self.worker();
+            crate::spawn(
+                self.setting_engine.runtime.as_ref(),
+                self.span.instrument(async move {
+                    let _d = w;
+
+                    loop {
+                        let srtcp_session = match dtls_transport.get_srtcp_session().await {
+                            Some(s) => s,
+                            None => {
+                                log::warn!(
+                                    "undeclared_media_processor failed to open SrtcpSession"
+                                );
+                                return;
+                            }
+                        };
+
+                        let stream = match srtcp_session.accept().await {
+                            Ok(stream) => stream,
+                            Err(err) => {
+                                log::warn!("Failed to accept RTCP {}", err);
+                                return;
+                            }
+                        };
+                        if !dtls_transport
+                            .admit_inbound_stream(&srtcp_session, &stream)
+                            .await
+                        {
+                            continue;
                         }
-                    };
-                    log::warn!(
-                        "Incoming unhandled RTCP ssrc({}), on_track will not be fired",
-                        stream.get_ssrc()
-                    );
-                }
-            });
+                        log::warn!(
+                            "Incoming unhandled RTCP ssrc({}), on_track will not be fired",
+                            stream.get_ssrc()
+                        );
+                    }
+                }),
+            );
         }
     }
 
@@ -429,10 +597,12 @@ impl PeerConnectionInternal {
                 }
                 PeerConnectionInternal::start_receiver(
                     self.setting_engine.get_receive_mtu(),
+                    self.setting_engine.runtime.clone(),
                     incoming_track,
                     receiver,
                     Arc::clone(t),
                     Arc::clone(&self.on_track_handler),
+                    Arc::clone(&self.draining),
                 )
                 .await;
                 track_handled = true;
@@ -447,15 +617,9 @@ impl PeerConnectionInternal {
     }
 
     /// Start SCTP subsystem
-    async fn start_sctp(&self) {
+    async fn start_sctp(&self, remote_caps: SCTPTransportCapabilities) {
         // Start sctp
-        if let Err(err) = self
-            .sctp_transport
-            .start(SCTPTransportCapabilities {
-                max_message_size: 0,
-            })
-            .await
-        {
+        if let Err(err) = self.sctp_transport.start(remote_caps).await {
             log::warn!("Failed to start SCTP: {}", err);
             if let Err(err) = self.sctp_transport.stop().await {
                 log::warn!("Failed to stop SCTPTransport: {}", err);
@@ -694,6 +858,9 @@ impl PeerConnectionInternal {
             log::warn!("Failed to start manager ice: {}", err);
             return;
         }
+        if let Some(idle_timeout) = self.setting_engine.timeout.idle_timeout {
+            self.idle_monitor(idle_timeout);
+        }
 
         // Start the dtls_transport transport
         let result = self
@@ -710,6 +877,7 @@ impl PeerConnectionInternal {
             &self.on_peer_connection_state_change_handler,
             &self.is_closed,
             &self.peer_connection_state,
+            &self.span,
             self.ice_connection_state.load(Ordering::SeqCst).into(),
             self.dtls_transport.state(),
         )
@@ -764,11 +932,10 @@ impl PeerConnectionInternal {
             });
         }
 
-        let dtls_fingerprints = if let Some(cert) = self.dtls_transport.certificates.first() {
-            cert.get_fingerprints()
-        } else {
+        let dtls_fingerprints = self.dtls_transport.get_local_parameters()?.fingerprints;
+        if dtls_fingerprints.is_empty() {
             return Err(Error::ErrNonCertificate);
-        };
+        }
 
         let params = PopulateSdpParams {
             media_description_fingerprint: self.setting_engine.sdp_media_level_fingerprints,
@@ -777,6 +944,7 @@ impl PeerConnectionInternal {
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: self.ice_gathering_state(),
             match_bundle_group: None,
+            codec_filter: self.setting_engine.codec_filter.clone(),
         };
         populate_sdp(
             d,
@@ -897,11 +1065,10 @@ impl PeerConnectionInternal {
                 .or(Some(String::new()))
         };
 
-        let dtls_fingerprints = if let Some(cert) = self.dtls_transport.certificates.first() {
-            cert.get_fingerprints()
-        } else {
+        let dtls_fingerprints = self.dtls_transport.get_local_parameters()?.fingerprints;
+        if dtls_fingerprints.is_empty() {
             return Err(Error::ErrNonCertificate);
-        };
+        }
 
         let params = PopulateSdpParams {
             media_description_fingerprint: self.setting_engine.sdp_media_level_fingerprints,
@@ -910,6 +1077,7 @@ impl PeerConnectionInternal {
             connection_role,
             ice_gathering_state: self.ice_gathering_state(),
             match_bundle_group,
+            codec_filter: self.setting_engine.codec_filter.clone(),
         };
         populate_sdp(
             d,
@@ -993,10 +1161,12 @@ impl PeerConnectionInternal {
         let receiver = t.receiver().await;
         PeerConnectionInternal::start_receiver(
             self.setting_engine.get_receive_mtu(),
+            self.setting_engine.runtime.clone(),
             &incoming,
             receiver,
             t,
             Arc::clone(&self.on_track_handler),
+            Arc::clone(&self.draining),
         )
         .await;
         Ok(true)
@@ -1090,18 +1260,23 @@ impl PeerConnectionInternal {
             .await?;
 
         let a = Attributes::new();
-        for _ in 0..=SIMULCAST_PROBE_COUNT {
-            if mid.is_empty() || (rid.is_empty() && rsid.is_empty()) {
+        for _ in 0..=self.setting_engine.get_simulcast_probe_count() {
+            // Without simulcast in its media section, the MID alone identifies the stream
+            let routable = !mid.is_empty()
+                && (!rid.is_empty()
+                    || !rsid.is_empty()
+                    || !is_simulcast_media_section(&parsed, &mid));
+            if !routable {
                 let (pkt, _) = rtp_interceptor.read(&mut buf, &a).await?;
-                let (m, r, rs, _) = handle_unknown_rtp_packet(
-                    &buf[..n],
-                    mid_extension_id as u8,
-                    sid_extension_id as u8,
-                    rsid_extension_id as u8,
-                )?;
-                mid = m;
-                rid = r;
-                rsid = rs;
+                let extension = |id: isize| -> Result<String> {
+                    Ok(match pkt.header.get_extension(id as u8) {
+                        Some(payload) => String::from_utf8(payload.to_vec())?,
+                        None => String::new(),
+                    })
+                };
+                mid = extension(mid_extension_id)?;
+                rid = extension(sid_extension_id)?;
+                rsid = extension(rsid_extension_id)?;
 
                 buffered_packets.push_back((pkt, a.clone()));
                 continue;
@@ -1115,7 +1290,25 @@ impl PeerConnectionInternal {
 
                 let receiver = t.receiver().await;
 
-                if !rsid.is_empty() {
+                let track = if rid.is_empty() && rsid.is_empty() {
+                    let Some(incoming) = self.mid_only_track_details(t, &receiver, &parsed).await
+                    else {
+                        break;
+                    };
+                    receiver
+                        .receive_for_ssrc(
+                            &incoming,
+                            params,
+                            TrackStream {
+                                stream_info: Some(stream_info.clone()),
+                                rtp_read_stream: Some(rtp_read_stream),
+                                rtp_interceptor: Some(rtp_interceptor),
+                                rtcp_read_stream: Some(rtcp_read_stream),
+                                rtcp_interceptor: Some(rtcp_interceptor),
+                            },
+                        )
+                        .await?
+                } else if !rsid.is_empty() {
                     return receiver
                         .receive_for_rtx(
                             0,
@@ -1129,31 +1322,34 @@ impl PeerConnectionInternal {
                             },
                         )
                         .await;
-                }
-
-                let track = receiver
-                    .receive_for_rid(
-                        SmolStr::from(rid),
-                        params,
-                        TrackStream {
-                            stream_info: Some(stream_info.clone()),
-                            rtp_read_stream: Some(rtp_read_stream),
-                            rtp_interceptor: Some(rtp_interceptor),
-                            rtcp_read_stream: Some(rtcp_read_stream),
-                            rtcp_interceptor: Some(rtcp_interceptor),
-                        },
-                    )
-                    .await?;
+                } else {
+                    receiver
+                        .receive_for_rid(
+                            SmolStr::from(rid),
+                            params,
+                            TrackStream {
+                                stream_info: Some(stream_info.clone()),
+                                rtp_read_stream: Some(rtp_read_stream),
+                                rtp_interceptor: Some(rtp_interceptor),
+                                rtcp_read_stream: Some(rtcp_read_stream),
+                                rtcp_interceptor: Some(rtcp_interceptor),
+                            },
+                        )
+                        .await?
+                };
                 track.prepopulate_peeked_data(buffered_packets).await;
 
                 RTCPeerConnection::do_track(
+                    self.setting_engine.runtime.as_ref(),
                     Arc::clone(&self.on_track_handler),
+                    &self.draining,
                     track,
                     receiver,
                     Arc::clone(t),
                 );
                 return Ok(());
             }
+            break;
         }
 
         let _ = rtp_read_stream.close().await;
@@ -1164,12 +1360,52 @@ impl PeerConnectionInternal {
         Err(Error::ErrPeerConnSimulcastIncomingSSRCFailed)
     }
 
+    /// mid_only_track_details describes the track of a transceiver whose media section declares
+    /// neither SSRCs nor RIDs, its stream is then only identified by the MID header extension.
+    /// Returns None if the receiver can't take an undeclared stream.
+    async fn mid_only_track_details(
+        &self,
+        t: &Arc<RTCRtpTransceiver>,
+        receiver: &Arc<RTCRtpReceiver>,
+        remote_description: &SessionDescription,
+    ) -> Option<TrackDetails> {
+        if receiver.have_received().await
+            || (t.direction() != RTCRtpTransceiverDirection::Recvonly
+                && t.direction() != RTCRtpTransceiverDirection::Sendrecv)
+        {
+            return None;
+        }
+
+        let mut incoming = TrackDetails {
+            mid: t.mid().unwrap_or_default(),
+            kind: t.kind(),
+            ..Default::default()
+        };
+        if let Some(media) = remote_description
+            .media_descriptions
+            .iter()
+            .find(|m| get_mid_value(m).map(|mid| mid.as_str()) == Some(incoming.mid.as_str()))
+        {
+            if let Some(msid) = media.attribute(ATTR_KEY_MSID).flatten() {
+                let split: Vec<&str> = msid.split(' ').collect();
+                if split.len() == 2 {
+                    incoming.stream_id = split[0].to_owned();
+                    incoming.id = split[1].to_owned();
+                }
+            }
+        }
+
+        Some(incoming)
+    }
+
     async fn start_receiver(
         receive_mtu: usize,
+        runtime: Option<Handle>,
         incoming: &TrackDetails,
         receiver: Arc<RTCRtpReceiver>,
         transceiver: Arc<RTCRtpTransceiver>,
         on_track_handler: Arc<ArcSwapOption<Mutex<OnTrackHdlrFn>>>,
+        draining: Arc<AtomicBool>,
     ) {
         receiver.start(incoming).await;
         for track in receiver.tracks().await {
@@ -1180,7 +1416,9 @@ impl PeerConnectionInternal {
             let receiver = Arc::clone(&receiver);
             let transceiver = Arc::clone(&transceiver);
             let on_track_handler = Arc::clone(&on_track_handler);
-            tokio::spawn(async move {
+            let draining = Arc::clone(&draining);
+            let track_runtime = runtime.clone();
+            crate::spawn(runtime.as_ref(), async move {
                 let mut b = vec![0u8; receive_mtu];
                 let pkt = match track.peek(&mut b).await {
                     Ok((pkt, _)) => pkt,
@@ -1203,7 +1441,14 @@ impl PeerConnectionInternal {
                     return;
                 }
 
-                RTCPeerConnection::do_track(on_track_handler, track, receiver, transceiver);
+                RTCPeerConnection::do_track(
+                    track_runtime.as_ref(),
+                    on_track_handler,
+                    &draining,
+                    track,
+                    receiver,
+                    transceiver,
+                );
             });
         }
     }
@@ -1379,6 +1624,7 @@ impl PeerConnectionInternal {
             mid: SmolStr,
             rid: Option<SmolStr>,
             kind: &'static str,
+            clock_rate: u32,
         }
         let mut track_infos = vec![];
         for transceiver in transceivers {
@@ -1403,6 +1649,7 @@ impl PeerConnectionInternal {
                     mid: mid.to_owned(),
                     rid: encoding.track.rid().map(Into::into),
                     kind,
+                    clock_rate: encoding.stream_info.clock_rate,
                 });
 
                 if let Some(rtx) = &encoding.rtx {
@@ -1412,6 +1659,7 @@ impl PeerConnectionInternal {
                         mid: mid.to_owned(),
                         rid: encoding.track.rid().map(Into::into),
                         kind,
+                        clock_rate: encoding.stream_info.clock_rate,
                     });
                 }
             }
@@ -1444,6 +1692,7 @@ impl PeerConnectionInternal {
                     remote_total_rtt_ms,
                     remote_rtt_measurements,
                     remote_fraction_lost,
+                    remote_jitter,
                 ) = (
                     stats.packets_sent(),
                     stats.payload_bytes_sent(),
@@ -1455,6 +1704,7 @@ impl PeerConnectionInternal {
                     stats.remote_total_round_trip_time(),
                     stats.remote_round_trip_time_measurements(),
                     stats.remote_fraction_lost(),
+                    stats.remote_jitter(),
                 );
 
                 let TrackInfo {
@@ -1462,6 +1712,7 @@ impl PeerConnectionInternal {
                     ssrc,
                     rid,
                     kind,
+                    clock_rate,
                     track_id: track_identifier,
                 } = info;
 
@@ -1504,6 +1755,11 @@ impl PeerConnectionInternal {
 
                         packets_received: remote_inbound_packets_received,
                         packets_lost: remote_inbound_packets_lost as i64,
+                        jitter: if clock_rate != 0 {
+                            remote_jitter as f64 / clock_rate as f64
+                        } else {
+                            0.0
+                        },
 
                         local_id,
 
@@ -1527,7 +1783,65 @@ impl RTCPWriter for PeerConnectionInternal {
         pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
         _a: &Attributes,
     ) -> IResult<usize> {
-        Ok(self.dtls_transport.write_rtcp(pkts).await?)
+        if self.rtcp_reduced_size.load(Ordering::SeqCst) || starts_with_report(pkts) {
+            return Ok(self.dtls_transport.write_rtcp(pkts).await?);
+        }
+
+        // Without reduced-size RTCP, every compound packet has to start with a
+        // sender or receiver report (RFC 3550 Section 6.1), so feedback is
+        // preceded by an empty receiver report.
+        let mut compound: Vec<Box<dyn rtcp::packet::Packet + Send + Sync>> =
+            Vec::with_capacity(pkts.len() + 1);
+        compound.push(Box::new(rtcp::receiver_report::ReceiverReport {
+            ssrc: sender_ssrc(pkts[0].as_ref()),
+            ..Default::default()
+        }));
+        compound.extend(pkts.iter().cloned());
+        Ok(self.dtls_transport.write_rtcp(&compound).await?)
+    }
+}
+
+/// start_simulcast_probe counts a new probe routine in count and returns true, or
+/// returns false without counting it if it would make max_probe_routines of them.
+pub(crate) fn start_simulcast_probe(count: &AtomicU64, max_probe_routines: u64) -> bool {
+    if count.fetch_add(1, Ordering::SeqCst) + 1 >= max_probe_routines {
+        count.fetch_sub(1, Ordering::SeqCst);
+        false
+    } else {
+        true
+    }
+}
+
+fn starts_with_report(pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>]) -> bool {
+    match pkts.first() {
+        Some(p) => matches!(
+            p.header().packet_type,
+            rtcp::header::PacketType::SenderReport | rtcp::header::PacketType::ReceiverReport
+        ),
+        None => true,
+    }
+}
+
+/// sender_ssrc returns the SSRC of the sender of a feedback packet, or 0 for the
+/// packets that don't carry one.
+fn sender_ssrc(pkt: &(dyn rtcp::packet::Packet + Send + Sync)) -> u32 {
+    let pkt = pkt.as_any();
+    if let Some(pli) = pkt.downcast_ref::<PictureLossIndication>() {
+        pli.sender_ssrc
+    } else if let Some(fir) = pkt.downcast_ref::<FullIntraRequest>() {
+        fir.sender_ssrc
+    } else if let Some(sli) = pkt.downcast_ref::<SliceLossIndication>() {
+        sli.sender_ssrc
+    } else if let Some(remb) = pkt.downcast_ref::<ReceiverEstimatedMaximumBitrate>() {
+        remb.sender_ssrc
+    } else if let Some(nack) = pkt.downcast_ref::<TransportLayerNack>() {
+        nack.sender_ssrc
+    } else if let Some(rrr) = pkt.downcast_ref::<RapidResynchronizationRequest>() {
+        rrr.sender_ssrc
+    } else if let Some(cc) = pkt.downcast_ref::<TransportLayerCc>() {
+        cc.sender_ssrc
+    } else {
+        0
     }
 }
 
@@ -0,0 +1,93 @@
+use std::future::Future;
+
+#[cfg(feature = "tracing")]
+use tracing::Instrument;
+
+use crate::ice_transport::ice_connection_state::RTCIceConnectionState;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::signaling_state::RTCSignalingState;
+
+/// PeerConnectionSpan is the root of the spans of a PeerConnection when the
+/// tracing feature is enabled. It carries the stats_id of the connection and
+/// its current signaling_state, ice_state and connection_state. The public
+/// methods of RTCPeerConnection open child spans of it, and the operations,
+/// transports and media processing of the connection run inside it, so the
+/// log lines of ICE, DTLS and SCTP bridged with tracing-log can be filtered
+/// by connection. Without the feature it does nothing and the state changes
+/// are reported through log.
+#[derive(Clone)]
+pub(crate) struct PeerConnectionSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl PeerConnectionSpan {
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new(stats_id: &str) -> Self {
+        PeerConnectionSpan {
+            span: tracing::info_span!(
+                "peer_connection",
+                stats_id,
+                signaling_state = %RTCSignalingState::Stable,
+                ice_state = %RTCIceConnectionState::New,
+                connection_state = %RTCPeerConnectionState::New,
+            ),
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn new(_stats_id: &str) -> Self {
+        PeerConnectionSpan {}
+    }
+
+    /// span returns the tracing span of the PeerConnection
+    #[cfg(feature = "tracing")]
+    pub(crate) fn span(&self) -> &tracing::Span {
+        &self.span
+    }
+
+    /// instrument makes future run inside the span of the PeerConnection
+    pub(crate) fn instrument<F: Future>(&self, future: F) -> impl Future<Output = F::Output> {
+        #[cfg(feature = "tracing")]
+        return future.instrument(self.span.clone());
+
+        #[cfg(not(feature = "tracing"))]
+        future
+    }
+
+    pub(crate) fn signaling_state_changed(&self, state: RTCSignalingState) {
+        #[cfg(feature = "tracing")]
+        {
+            self.span
+                .record("signaling_state", tracing::field::display(state));
+            tracing::info!(parent: &self.span, signaling_state = %state, "signaling state changed");
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        log::info!("signaling state changed to {}", state);
+    }
+
+    pub(crate) fn ice_connection_state_changed(&self, state: RTCIceConnectionState) {
+        #[cfg(feature = "tracing")]
+        {
+            self.span
+                .record("ice_state", tracing::field::display(state));
+            tracing::info!(parent: &self.span, ice_state = %state, "ICE connection state changed");
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        log::info!("ICE connection state changed: {}", state);
+    }
+
+    pub(crate) fn connection_state_changed(&self, state: RTCPeerConnectionState) {
+        #[cfg(feature = "tracing")]
+        {
+            self.span
+                .record("connection_state", tracing::field::display(state));
+            tracing::info!(parent: &self.span, connection_state = %state, "peer connection state changed");
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        log::info!("peer connection state changed: {}", state);
+    }
+}
@@ -10,16 +10,18 @@ use util::vnet::router::{Router, RouterConfig};
 use waitgroup::WaitGroup;
 
 use super::*;
-use crate::api::interceptor_registry::register_default_interceptors;
+use crate::api::interceptor_registry::{configure_rtcp_reports, register_default_interceptors};
 use crate::api::media_engine::{MediaEngine, MIME_TYPE_VP8};
 use crate::api::APIBuilder;
 use crate::ice_transport::ice_candidate_pair::RTCIceCandidatePair;
 use crate::ice_transport::ice_server::RTCIceServer;
 use crate::peer_connection::configuration::RTCConfiguration;
 use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
+use crate::rtp_transceiver::RTCRtpVideoConstraints;
 use crate::stats::StatsReportType;
 use crate::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use crate::track::track_local::TrackLocalWriter;
 use crate::Error;
 
 pub(crate) async fn create_vnet_pair(
@@ -383,6 +385,37 @@ async fn test_get_stats() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_connection_timing() -> Result<()> {
+    let api = APIBuilder::new().build();
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    assert_eq!(pc_offer.connection_timing(), RTCConnectionTiming::default());
+
+    let (data_channel_tx, mut data_channel_rx) = mpsc::channel::<()>(1);
+    pc_answer.on_data_channel(Box::new(move |_| {
+        let data_channel_tx = data_channel_tx.clone();
+        Box::pin(async move {
+            let _ = data_channel_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    let _ = data_channel_rx.recv().await;
+
+    for pc in [&pc_offer, &pc_answer] {
+        let timing = pc.connection_timing();
+        assert!(timing.ice_gathering.is_some(), "{timing:?}");
+        assert!(timing.ice_connecting.is_some(), "{timing:?}");
+        assert!(timing.dtls_handshake.is_some(), "{timing:?}");
+        assert!(timing.sctp_open.is_some(), "{timing:?}");
+    }
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_peer_connection_close_is_send() -> Result<()> {
     let handle = tokio::spawn(async move { peer().await });
@@ -636,6 +669,275 @@ async fn test_peer_connection_simulcast_no_data_channel() -> Result<()> {
     Ok(())
 }
 
+// A stream whose SSRC isn't declared in a media section with other media sections around
+// identifies itself only with the MID header extension, it is routed to the transceiver of that mid.
+// Once the receiver reports the stream the sender stops sending the MID, without reading RTCP.
+#[tokio::test]
+async fn test_peer_connection_undeclared_ssrc_routed_by_mid() -> Result<()> {
+    let mut m = MediaEngine::default();
+    for ext in [
+        ::sdp::extmap::SDES_MID_URI,
+        ::sdp::extmap::SDES_RTP_STREAM_ID_URI,
+    ] {
+        m.register_header_extension(
+            RTCRtpHeaderExtensionCapability {
+                uri: ext.to_owned(),
+            },
+            RTPCodecType::Video,
+            None,
+        )?;
+    }
+    m.register_default_codecs()?;
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_interceptor_registry(configure_rtcp_reports(Registry::new()))
+        .build();
+
+    let (pc_send, pc_recv) = new_pair(&api).await?;
+    let (send_notifier, mut send_connected) = on_connected();
+    let (recv_notifier, mut recv_connected) = on_connected();
+    pc_send.on_peer_connection_state_change(send_notifier);
+    pc_recv.on_peer_connection_state_change(recv_notifier);
+    let (track_tx, mut track_rx) = mpsc::unbounded_channel();
+    pc_recv.on_track(Box::new(move |t, _, transceiver| {
+        let _ = track_tx.send((t.id(), transceiver.mid()));
+        Box::pin(async move {})
+    }));
+
+    let mut tracks = vec![];
+    for id in ["video_a", "video_b"] {
+        let track = Arc::new(TrackLocalStaticRTP::new(
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_VP8.to_owned(),
+                ..Default::default()
+            },
+            id.to_owned(),
+            "webrtc-rs".to_owned(),
+        ));
+        pc_send.add_track(Arc::clone(&track) as _).await?;
+        tracks.push(track);
+    }
+
+    // signaling, without the ssrcs of the offer
+    let offer = pc_send.create_offer(None).await?;
+    let mut gathering_complete = pc_send.gathering_complete_promise().await;
+    pc_send.set_local_description(offer).await?;
+    let _ = gathering_complete.recv().await;
+    let offer = pc_send.local_description().await.unwrap();
+    let sdp: String = offer
+        .sdp
+        .split_inclusive('\n')
+        .filter(|line| !line.starts_with("a=ssrc"))
+        .collect();
+    pc_recv
+        .set_remote_description(RTCSessionDescription::offer(sdp)?)
+        .await?;
+
+    let answer = pc_recv.create_answer(None).await?;
+    let mut gathering_complete = pc_recv.gathering_complete_promise().await;
+    pc_recv.set_local_description(answer).await?;
+    let _ = gathering_complete.recv().await;
+    pc_send
+        .set_remote_description(pc_recv.local_description().await.unwrap())
+        .await?;
+    let _ = send_connected.recv().await;
+    let _ = recv_connected.recv().await;
+
+    for sequence_number in 0..100 {
+        let pkt = rtp::packet::Packet {
+            header: rtp::header::Header {
+                version: 2,
+                sequence_number,
+                payload_type: 96,
+                ..Default::default()
+            },
+            payload: Bytes::from_static(&[0; 2]),
+        };
+        tracks[1].write_rtp_with_extensions(&pkt, &[]).await?;
+    }
+
+    let (id, mid) = track_rx.recv().await.unwrap();
+    assert_eq!(id, "video_b");
+    assert_eq!(mid.as_deref(), Some("1"));
+
+    let sender = pc_send.get_senders().await.remove(1);
+    let mid_acked = sender.track_encodings.lock().await[0]
+        .context
+        .mid_acked
+        .clone();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !mid_acked.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await
+    .expect("the receiver report acknowledges the mid");
+
+    close_pair_now(&pc_send, &pc_recv).await;
+
+    Ok(())
+}
+
+// The internal tasks run on the runtime given to the SettingEngine rather than the ambient one
+#[tokio::test]
+async fn test_peer_connection_runtime_handle() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("webrtc-runtime")
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut s = SettingEngine::default();
+    s.set_runtime_handle(runtime.handle().clone());
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+    let (thread_tx, mut thread_rx) = mpsc::unbounded_channel();
+    pc_answer.on_data_channel(Box::new(move |_| {
+        let _ = thread_tx.send(std::thread::current().name().map(str::to_owned));
+        Box::pin(async move {})
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+
+    let thread = thread_rx.recv().await.unwrap();
+    assert_eq!(thread.as_deref(), Some("webrtc-runtime"));
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+    runtime.shutdown_background();
+
+    Ok(())
+}
+
+// RuntimeInterceptor spawns a task when built and bound, reporting the thread each task runs on
+#[derive(Clone)]
+struct RuntimeInterceptor(mpsc::UnboundedSender<(&'static str, Option<String>)>);
+
+impl RuntimeInterceptor {
+    fn spawn_report(&self, event: &'static str) {
+        let events = self.0.clone();
+        tokio::spawn(async move {
+            let _ = events.send((event, std::thread::current().name().map(str::to_owned)));
+        });
+    }
+}
+
+#[async_trait]
+impl Interceptor for RuntimeInterceptor {
+    async fn bind_rtcp_reader(
+        &self,
+        reader: Arc<dyn interceptor::RTCPReader + Send + Sync>,
+    ) -> Arc<dyn interceptor::RTCPReader + Send + Sync> {
+        reader
+    }
+
+    async fn bind_rtcp_writer(
+        &self,
+        writer: Arc<dyn RTCPWriter + Send + Sync>,
+    ) -> Arc<dyn RTCPWriter + Send + Sync> {
+        self.spawn_report("bind_rtcp_writer");
+        writer
+    }
+
+    async fn bind_local_stream(
+        &self,
+        _info: &interceptor::stream_info::StreamInfo,
+        writer: Arc<dyn interceptor::RTPWriter + Send + Sync>,
+    ) -> Arc<dyn interceptor::RTPWriter + Send + Sync> {
+        self.spawn_report("bind_local_stream");
+        writer
+    }
+
+    async fn unbind_local_stream(&self, _info: &interceptor::stream_info::StreamInfo) {}
+
+    async fn bind_remote_stream(
+        &self,
+        _info: &interceptor::stream_info::StreamInfo,
+        reader: Arc<dyn interceptor::RTPReader + Send + Sync>,
+    ) -> Arc<dyn interceptor::RTPReader + Send + Sync> {
+        self.spawn_report("bind_remote_stream");
+        reader
+    }
+
+    async fn unbind_remote_stream(&self, _info: &interceptor::stream_info::StreamInfo) {}
+
+    async fn close(&self) -> std::result::Result<(), interceptor::Error> {
+        Ok(())
+    }
+}
+
+impl interceptor::InterceptorBuilder for RuntimeInterceptor {
+    fn build(
+        &self,
+        _id: &str,
+    ) -> std::result::Result<Arc<dyn Interceptor + Send + Sync>, interceptor::Error> {
+        self.spawn_report("build");
+        Ok(Arc::new(self.clone()))
+    }
+}
+
+// The tasks the interceptors spawn when they are built and bound run on the runtime given to
+// the SettingEngine as well
+#[tokio::test]
+async fn test_peer_connection_runtime_handle_interceptors() -> Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("webrtc-runtime")
+        .enable_all()
+        .build()
+        .unwrap();
+    let mut s = SettingEngine::default();
+    s.set_runtime_handle(runtime.handle().clone());
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let (events_tx, mut events_rx) = mpsc::unbounded_channel();
+    let mut registry = Registry::new();
+    registry.add(Box::new(RuntimeInterceptor(events_tx)));
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .with_interceptor_registry(registry)
+        .build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pc_offer.add_track(track).await?;
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+
+    let mut pending = vec![
+        "build",
+        "bind_rtcp_writer",
+        "bind_local_stream",
+        "bind_remote_stream",
+    ];
+    while !pending.is_empty() {
+        let (event, thread) = tokio::time::timeout(Duration::from_secs(10), events_rx.recv())
+            .await
+            .expect("interceptor was not bound")
+            .unwrap();
+        assert_eq!(thread.as_deref(), Some("webrtc-runtime"), "{event}");
+        pending.retain(|pending| *pending != event);
+    }
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+    runtime.shutdown_background();
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_peer_connection_state() -> Result<()> {
     let mut m = MediaEngine::default();
@@ -649,6 +951,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Checking,
         RTCDtlsTransportState::New,
     )
@@ -659,6 +962,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Connected,
         RTCDtlsTransportState::New,
     )
@@ -669,6 +973,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Connected,
         RTCDtlsTransportState::Connecting,
     )
@@ -679,6 +984,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Connected,
         RTCDtlsTransportState::Connected,
     )
@@ -689,6 +995,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Completed,
         RTCDtlsTransportState::Connected,
     )
@@ -699,6 +1006,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Connected,
         RTCDtlsTransportState::Closed,
     )
@@ -709,6 +1017,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Disconnected,
         RTCDtlsTransportState::Connected,
     )
@@ -719,6 +1028,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Failed,
         RTCDtlsTransportState::Connected,
     )
@@ -729,6 +1039,7 @@ async fn test_peer_connection_state() -> Result<()> {
         &pc.internal.on_peer_connection_state_change_handler,
         &pc.internal.is_closed,
         &pc.internal.peer_connection_state,
+        &pc.internal.span,
         RTCIceConnectionState::Connected,
         RTCDtlsTransportState::Failed,
     )
@@ -740,3 +1051,1456 @@ async fn test_peer_connection_state() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_peer_connection_create_recvonly_answer() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+
+    assert!(matches!(
+        pc_answer.create_recvonly_answer(None).await,
+        Err(Error::ErrNoRemoteDescription)
+    ));
+
+    pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+    pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Audio, None)
+        .await?;
+
+    let offer = pc_offer.create_offer(None).await?;
+    pc_offer.set_local_description(offer.clone()).await?;
+    pc_answer.set_remote_description(offer).await?;
+
+    let answer = pc_answer.create_recvonly_answer(None).await?;
+    let parsed = answer.unmarshal()?;
+    assert_eq!(parsed.media_descriptions.len(), 2);
+    for media in &parsed.media_descriptions {
+        assert!(media.attribute(ATTR_KEY_RECV_ONLY).is_some());
+        assert_eq!(media.media_name.formats.len(), 1);
+    }
+
+    for t in pc_answer.get_transceivers().await {
+        assert_eq!(t.direction(), RTCRtpTransceiverDirection::Recvonly);
+    }
+
+    pc_answer.set_local_description(answer).await?;
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_rollback() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+
+    // nothing to roll back while stable
+    assert!(matches!(
+        pc_answer
+            .set_remote_description(RTCSessionDescription::rollback())
+            .await,
+        Err(Error::ErrSignalingStateCannotRollback)
+    ));
+
+    pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+    let offer = pc_offer.create_offer(None).await?;
+    pc_offer.set_local_description(offer.clone()).await?;
+
+    // the remote offer creates a transceiver, which goes away with the rollback
+    pc_answer.set_remote_description(offer.clone()).await?;
+    assert_eq!(
+        pc_answer.signaling_state(),
+        RTCSignalingState::HaveRemoteOffer
+    );
+    assert_eq!(pc_answer.get_transceivers().await.len(), 1);
+
+    pc_answer
+        .set_remote_description(RTCSessionDescription::rollback())
+        .await?;
+    assert_eq!(pc_answer.signaling_state(), RTCSignalingState::Stable);
+    assert!(pc_answer.pending_remote_description().await.is_none());
+    assert!(pc_answer.remote_description().await.is_none());
+    assert!(pc_answer.get_transceivers().await.is_empty());
+
+    // glare: the answering side made an offer of its own, it rolls it back to take the remote one
+    let audio = pc_answer
+        .add_transceiver_from_kind(RTPCodecType::Audio, None)
+        .await?;
+    let glare_offer = pc_answer.create_offer(None).await?;
+    pc_answer.set_local_description(glare_offer).await?;
+    assert_eq!(audio.mid(), Some("0".into()));
+
+    pc_answer
+        .set_local_description(RTCSessionDescription::rollback())
+        .await?;
+    assert_eq!(pc_answer.signaling_state(), RTCSignalingState::Stable);
+    assert!(pc_answer.pending_local_description().await.is_none());
+    assert!(pc_answer.local_description().await.is_none());
+    // the transceiver added by the application stays, without the mid of the rolled back offer
+    assert_eq!(pc_answer.get_transceivers().await.len(), 1);
+    assert_eq!(audio.mid(), None);
+
+    pc_answer.set_remote_description(offer).await?;
+    let transceivers = pc_answer.get_transceivers().await;
+    assert_eq!(transceivers.len(), 2);
+    assert_eq!(transceivers[1].kind(), RTPCodecType::Video);
+    assert_eq!(transceivers[1].mid(), Some("0".into()));
+
+    let answer = pc_answer.create_answer(None).await?;
+    pc_answer.set_local_description(answer.clone()).await?;
+    pc_offer.set_remote_description(answer).await?;
+    assert_eq!(pc_offer.signaling_state(), RTCSignalingState::Stable);
+    assert_eq!(pc_answer.signaling_state(), RTCSignalingState::Stable);
+
+    // answering a renegotiation changes the transceivers, the rollback restores them
+    let video = Arc::clone(&transceivers[1]);
+    video
+        .set_direction(RTCRtpTransceiverDirection::Sendrecv)
+        .await;
+    let offer = pc_offer.create_offer(None).await?;
+    pc_offer.set_local_description(offer.clone()).await?;
+    pc_answer.set_remote_description(offer).await?;
+    pc_answer.create_recvonly_answer(None).await?;
+    assert_eq!(video.direction(), RTCRtpTransceiverDirection::Recvonly);
+    assert!(!video.codec_preferences().await.is_empty());
+
+    pc_answer
+        .set_remote_description(RTCSessionDescription::rollback())
+        .await?;
+    assert_eq!(video.direction(), RTCRtpTransceiverDirection::Sendrecv);
+    assert!(video.codec_preferences().await.is_empty());
+    assert_eq!(video.mid(), Some("0".into()));
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_transceiver_content() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+
+    let t = pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+    t.set_content(Some("slides".to_owned())).await;
+    assert_eq!(t.content(), Some("slides".to_owned()));
+
+    for _ in 0..2 {
+        let offer = pc_offer.create_offer(None).await?;
+        let parsed = offer.unmarshal()?;
+        assert_eq!(
+            parsed.media_descriptions[0].attribute(ATTR_KEY_CONTENT),
+            Some(Some("slides"))
+        );
+
+        pc_offer.set_local_description(offer.clone()).await?;
+        pc_answer.set_remote_description(offer).await?;
+
+        let transceivers = pc_answer.get_transceivers().await;
+        assert_eq!(transceivers[0].remote_content(), Some("slides".to_owned()));
+        assert_eq!(transceivers[0].content(), None);
+
+        let answer = pc_answer.create_answer(None).await?;
+        assert!(answer.unmarshal()?.media_descriptions[0]
+            .attribute(ATTR_KEY_CONTENT)
+            .is_none());
+        pc_answer.set_local_description(answer.clone()).await?;
+        pc_offer.set_remote_description(answer).await?;
+        assert_eq!(t.remote_content(), None);
+    }
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_negotiated_header_extensions() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    for (uri, typ) in [
+        (::sdp::extmap::SDES_MID_URI, RTPCodecType::Video),
+        (::sdp::extmap::ABS_SEND_TIME_URI, RTPCodecType::Video),
+        (::sdp::extmap::AUDIO_LEVEL_URI, RTPCodecType::Audio),
+    ] {
+        m.register_header_extension(
+            RTCRtpHeaderExtensionCapability {
+                uri: uri.to_owned(),
+            },
+            typ,
+            None,
+        )?;
+    }
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+
+    let t = pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+    assert!(t.negotiated_header_extensions().is_empty());
+
+    let offer = pc_offer.create_offer(None).await?;
+    let offered = rtp_extensions_from_media_description(&offer.unmarshal()?.media_descriptions[0])?;
+    pc_offer.set_local_description(offer.clone()).await?;
+    pc_answer.set_remote_description(offer).await?;
+
+    let expected = {
+        let mut expected = vec![
+            (
+                offered[::sdp::extmap::SDES_MID_URI] as u8,
+                ::sdp::extmap::SDES_MID_URI.to_owned(),
+            ),
+            (
+                offered[::sdp::extmap::ABS_SEND_TIME_URI] as u8,
+                ::sdp::extmap::ABS_SEND_TIME_URI.to_owned(),
+            ),
+        ];
+        expected.sort();
+        expected
+    };
+    let transceivers = pc_answer.get_transceivers().await;
+    assert_eq!(transceivers[0].negotiated_header_extensions(), expected);
+
+    let answer = pc_answer.create_answer(None).await?;
+    pc_answer.set_local_description(answer.clone()).await?;
+    pc_offer.set_remote_description(answer).await?;
+    assert_eq!(t.negotiated_header_extensions(), expected);
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_receive_constraints() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+
+    let constraints = RTCRtpVideoConstraints {
+        max_resolution: Some((1280, 720)),
+        max_framerate: Some(30.0),
+    };
+    let t = pc_offer
+        .add_transceiver_from_kind(
+            RTPCodecType::Video,
+            Some(RTCRtpTransceiverInit {
+                direction: RTCRtpTransceiverDirection::Recvonly,
+                send_encodings: vec![],
+            }),
+        )
+        .await?;
+    t.set_receive_constraints(Some(constraints)).await;
+    assert_eq!(t.receive_constraints(), Some(constraints));
+
+    // Constraints are only signaled by media sections that receive
+    let sendonly = pc_offer
+        .add_transceiver_from_kind(
+            RTPCodecType::Video,
+            Some(RTCRtpTransceiverInit {
+                direction: RTCRtpTransceiverDirection::Sendonly,
+                send_encodings: vec![],
+            }),
+        )
+        .await?;
+    sendonly.set_receive_constraints(Some(constraints)).await;
+
+    let offer = pc_offer.create_offer(None).await?;
+    let parsed = offer.unmarshal()?;
+    assert_eq!(
+        parsed.media_descriptions[0].attribute(ATTR_KEY_IMAGEATTR),
+        Some(Some("* recv [x=[1:1280],y=[1:720]]"))
+    );
+    assert_eq!(
+        parsed.media_descriptions[0].attribute(ATTR_KEY_FRAMERATE),
+        Some(Some("30"))
+    );
+    assert!(!parsed.media_descriptions[1].has_attribute(ATTR_KEY_IMAGEATTR));
+    assert!(!parsed.media_descriptions[1].has_attribute(ATTR_KEY_FRAMERATE));
+
+    pc_offer.set_local_description(offer.clone()).await?;
+    pc_answer.set_remote_description(offer).await?;
+
+    let transceivers = pc_answer.get_transceivers().await;
+    assert_eq!(
+        transceivers[0].remote_receive_constraints(),
+        Some(constraints)
+    );
+    assert_eq!(
+        transceivers[0]
+            .sender()
+            .await
+            .get_parameters()
+            .await
+            .remote_receive_constraints,
+        Some(constraints)
+    );
+    assert_eq!(transceivers[1].remote_receive_constraints(), None);
+
+    let answer = pc_answer.create_answer(None).await?;
+    pc_answer.set_local_description(answer.clone()).await?;
+    pc_offer.set_remote_description(answer).await?;
+    assert_eq!(t.remote_receive_constraints(), None);
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_peer_connections() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+
+    let handles = api.peer_connections();
+    assert_eq!(handles.len(), 2);
+    assert_eq!(handles[0].stats_id(), pc_offer.get_stats_id());
+    assert_eq!(handles[1].stats_id(), pc_answer.get_stats_id());
+    assert!(handles[0].is_idle().await);
+
+    pc_offer.close().await?;
+    assert!(handles[0].is_closed());
+    assert_eq!(api.peer_connections().len(), 1);
+
+    drop(pc_answer);
+    assert!(handles[1].is_closed());
+    assert!(api.peer_connections().is_empty());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_drain_idle() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+
+    // Both PeerConnections are idle, so drain doesn't wait for the grace period
+    tokio::time::timeout(Duration::from_secs(5), api.drain(Duration::from_secs(60)))
+        .await
+        .expect("drain timed out")?;
+
+    assert!(api.is_draining());
+    assert!(api.peer_connections().is_empty());
+    assert_eq!(pc_offer.connection_state(), RTCPeerConnectionState::Closed);
+    assert_eq!(pc_answer.connection_state(), RTCPeerConnectionState::Closed);
+
+    let result = api.new_peer_connection(RTCConfiguration::default()).await;
+    assert!(matches!(result, Err(Error::ErrDraining)));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_drain_grace_period() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    // Keep the remote data channels so the answerer is busy too, and wait
+    // until both of them are open on the answerer
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(2);
+    pc_answer.on_data_channel(Box::new(move |d: Arc<RTCDataChannel>| {
+        let open_tx = open_tx.clone();
+        Box::pin(async move {
+            d.on_open(Box::new(move || {
+                Box::pin(async move {
+                    let _ = open_tx.send(()).await;
+                })
+            }));
+        })
+    }));
+    pc_offer.create_data_channel("busy", None).await?;
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    open_rx.recv().await;
+    open_rx.recv().await;
+
+    let grace_period = Duration::from_millis(500);
+    let start = tokio::time::Instant::now();
+    let (result, _) = tokio::join!(api.drain(grace_period), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        // The open data channel keeps the PeerConnection busy
+        assert!(!api.peer_connections()[0].is_idle().await);
+        assert!(matches!(
+            pc_offer.create_data_channel("refused", None).await,
+            Err(Error::ErrDraining)
+        ));
+        assert!(matches!(
+            pc_answer
+                .add_transceiver_from_kind(RTPCodecType::Video, None)
+                .await,
+            Err(Error::ErrDraining)
+        ));
+    });
+    result?;
+
+    assert!(start.elapsed() >= grace_period);
+    assert!(api.peer_connections().is_empty());
+    assert_eq!(pc_offer.connection_state(), RTCPeerConnectionState::Closed);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_api_max_concurrent_dtls_handshakes() -> Result<()> {
+    let new_api = |max_concurrent: Option<usize>| -> Result<API> {
+        let mut m = MediaEngine::default();
+        m.register_default_codecs()?;
+        let mut builder = APIBuilder::new().with_media_engine(m);
+        if let Some(max_concurrent) = max_concurrent {
+            builder = builder.with_max_concurrent_dtls_handshakes(max_concurrent);
+        }
+        Ok(builder.build())
+    };
+    // Only the answering side acts as a server that limits handshakes
+    let client_api = new_api(None)?;
+    let server_api = new_api(Some(1))?;
+    assert_eq!(server_api.dtls_handshakes_in_flight(), 0);
+    assert_eq!(server_api.dtls_handshakes_queued(), 0);
+
+    // Take the only permit so the handshake of the server has to wait
+    let permit = server_api.dtls_handshakes.acquire().await;
+    assert_eq!(server_api.dtls_handshakes_in_flight(), 1);
+
+    let mut pc_offer = client_api
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+    let mut pc_answer = server_api
+        .new_peer_connection(RTCConfiguration::default())
+        .await?;
+    let (hdlr_fn, mut connected_rx) = on_connected();
+    pc_answer.on_peer_connection_state_change(hdlr_fn);
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while server_api.dtls_handshakes_queued() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("handshake wasn't queued");
+    assert_eq!(server_api.dtls_handshakes_in_flight(), 1);
+    assert_eq!(client_api.dtls_handshakes_queued(), 0);
+    assert_eq!(
+        pc_answer.connection_state(),
+        RTCPeerConnectionState::Connecting
+    );
+
+    drop(permit);
+    tokio::time::timeout(Duration::from_secs(10), connected_rx.recv())
+        .await
+        .expect("PeerConnection didn't connect");
+    assert_eq!(server_api.dtls_handshakes_queued(), 0);
+    assert_eq!(server_api.dtls_handshakes_in_flight(), 0);
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_remote_description_rtcp_mux_required() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+    pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Audio, None)
+        .await?;
+
+    // A legacy endpoint sending RTCP on its own port instead of rtcp-mux
+    let offer = pc_offer.create_offer(None).await?;
+    let sdp = offer
+        .sdp
+        .replace("a=rtcp-mux\r\n", "a=rtcp:10 IN IP4 0.0.0.0\r\n");
+    let result = pc_answer
+        .set_remote_description(RTCSessionDescription::offer(sdp)?)
+        .await;
+    assert!(
+        matches!(result, Err(Error::ErrRtcpMuxRequired(ref mid)) if mid == "0"),
+        "{result:?}"
+    );
+    assert_eq!(pc_answer.signaling_state(), RTCSignalingState::Stable);
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_session_level_transport_attributes() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let pc_offer = api.new_peer_connection(RTCConfiguration::default()).await?;
+
+    // The answerer is passive, the offerer has to read it to become the DTLS client
+    let mut s = SettingEngine::default();
+    s.set_answering_dtls_role(DTLSRole::Server)?;
+    let api = APIBuilder::new()
+        .with_media_engine({
+            let mut m = MediaEngine::default();
+            m.register_default_codecs()?;
+            m
+        })
+        .with_setting_engine(s)
+        .build();
+    let pc_answer = api.new_peer_connection(RTCConfiguration::default()).await?;
+
+    let (offer_notifier, mut offer_connected) = on_connected();
+    pc_offer.on_peer_connection_state_change(offer_notifier);
+    let (answer_notifier, mut answer_connected) = on_connected();
+    pc_answer.on_peer_connection_state_change(answer_notifier);
+
+    pc_offer.create_data_channel("data", None).await?;
+    let offer = pc_offer.create_offer(None).await?;
+    let mut offer_gathering_complete = pc_offer.gathering_complete_promise().await;
+    pc_offer.set_local_description(offer).await?;
+    let _ = offer_gathering_complete.recv().await;
+    pc_answer
+        .set_remote_description(pc_offer.local_description().await.unwrap())
+        .await?;
+
+    let answer = pc_answer.create_answer(None).await?;
+    let mut answer_gathering_complete = pc_answer.gathering_complete_promise().await;
+    pc_answer.set_local_description(answer).await?;
+    let _ = answer_gathering_complete.recv().await;
+
+    // An endpoint declaring the transport attributes at the session level only
+    let answer = pc_answer.local_description().await.unwrap();
+    let mut parsed = answer.unmarshal()?;
+    for key in ["fingerprint", "setup", "ice-ufrag", "ice-pwd"] {
+        let mut value = None;
+        for media in &mut parsed.media_descriptions {
+            if let Some(i) = media.attributes.iter().position(|a| a.key == key) {
+                value = media.attributes.remove(i).value;
+            }
+        }
+        parsed
+            .attributes
+            .push(::sdp::description::common::Attribute {
+                key: key.to_owned(),
+                value,
+            });
+    }
+    assert!(parsed
+        .attributes
+        .contains(&::sdp::description::common::Attribute {
+            key: "setup".to_owned(),
+            value: Some("passive".to_owned()),
+        }));
+    pc_offer
+        .set_remote_description(RTCSessionDescription::answer(parsed.marshal())?)
+        .await?;
+
+    tokio::time::timeout(Duration::from_secs(10), offer_connected.recv())
+        .await
+        .expect("offerer should connect");
+    tokio::time::timeout(Duration::from_secs(10), answer_connected.recv())
+        .await
+        .expect("answerer should connect");
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_outgoing_sdp_hook() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let mut s = SettingEngine::default();
+    s.set_outgoing_sdp_hook(|sdp_type, desc| {
+        desc.attributes
+            .push(::sdp::description::common::Attribute::new(
+                "x-app-id".to_owned(),
+                Some(sdp_type.to_string()),
+            ));
+    });
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+    pc_offer.create_data_channel("data", None).await?;
+
+    let offer = pc_offer.create_offer(None).await?;
+    assert!(offer.sdp.contains("a=x-app-id:offer\r\n"), "{}", offer.sdp);
+    pc_offer.set_local_description(offer.clone()).await?;
+    pc_answer.set_remote_description(offer).await?;
+
+    let answer = pc_answer.create_answer(None).await?;
+    assert!(
+        answer.sdp.contains("a=x-app-id:answer\r\n"),
+        "{}",
+        answer.sdp
+    );
+    pc_answer.set_local_description(answer.clone()).await?;
+    pc_offer.set_remote_description(answer).await?;
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+// The codec filter is consulted on every negotiation and removes the RTX
+// codec along with the codec it protects
+#[tokio::test]
+async fn test_codec_filter() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    m.register_rtx_codec(97, 96, RTPCodecType::Video)?;
+    m.register_rtx_codec(99, 98, RTPCodecType::Video)?;
+    let vp8_available = Arc::new(AtomicBool::new(false));
+    let mut s = SettingEngine::default();
+    let available = Arc::clone(&vp8_available);
+    s.set_codec_filter(move |kind, codec| {
+        assert_eq!(kind, RTPCodecType::Video);
+        codec.capability.mime_type != MIME_TYPE_VP8 || available.load(Ordering::SeqCst)
+    });
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+
+    let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+    pc.add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+
+    let offer = pc.create_offer(None).await?;
+    assert!(!offer.sdp.contains("VP8/90000"), "{}", offer.sdp);
+    assert!(!offer.sdp.contains("apt=96"), "{}", offer.sdp);
+    assert!(offer.sdp.contains("VP9/90000"), "{}", offer.sdp);
+    assert!(offer.sdp.contains("apt=98"), "{}", offer.sdp);
+
+    vp8_available.store(true, Ordering::SeqCst);
+    let offer = pc.create_offer(None).await?;
+    assert!(offer.sdp.contains("VP8/90000"), "{}", offer.sdp);
+    assert!(offer.sdp.contains("apt=96"), "{}", offer.sdp);
+
+    pc.close().await?;
+
+    Ok(())
+}
+
+// An offer for changes only differs from the current local description in the
+// media sections that changed
+#[tokio::test]
+async fn test_create_offer_for_changes() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+    pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Audio, None)
+        .await?;
+    let video = pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+
+    video
+        .set_direction(RTCRtpTransceiverDirection::Recvonly)
+        .await;
+    let offer = pc_offer.create_offer_for_changes(None).await?;
+
+    let current = pc_offer.current_local_description().await.unwrap();
+    let sections =
+        |sdp: &str| -> Vec<String> { sdp.split("\r\nm=").skip(1).map(|s| s.to_owned()).collect() };
+    let (current_sections, offer_sections) = (sections(&current.sdp), sections(&offer.sdp));
+    assert_eq!(offer_sections.len(), 3);
+    assert_eq!(offer_sections[0], current_sections[0]);
+    assert_ne!(offer_sections[1], current_sections[1]);
+    assert_eq!(offer_sections[2], current_sections[2]);
+
+    let diff = ::sdp::description::diff::SessionDescriptionDiff::new(
+        current.parsed.as_ref().unwrap(),
+        offer.parsed.as_ref().unwrap(),
+    );
+    assert_eq!(diff.media_descriptions.len(), 1, "{diff:?}");
+    match &diff.media_descriptions[0] {
+        ::sdp::description::diff::MediaDescriptionDiff::Modified(changes) => {
+            assert_eq!(changes.index, 1);
+            assert!(changes.direction.is_some());
+        }
+        d => panic!("unexpected change {d:?}"),
+    }
+
+    pc_offer.set_local_description(offer.clone()).await?;
+    pc_answer.set_remote_description(offer).await?;
+    let answer = pc_answer.create_answer(None).await?;
+    pc_answer.set_local_description(answer.clone()).await?;
+    pc_offer.set_remote_description(answer).await?;
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ice_gathering_timeout() -> Result<()> {
+    // A STUN server that never answers
+    let stun_server = tokio::net::UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let stun_server_port = stun_server.local_addr().unwrap().port();
+
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let mut s = SettingEngine::default();
+    s.set_ice_gathering_timeout(Duration::from_millis(300));
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+
+    let pc = api
+        .new_peer_connection(RTCConfiguration {
+            ice_servers: vec![RTCIceServer {
+                urls: vec![format!("stun:127.0.0.1:{stun_server_port}")],
+                ..Default::default()
+            }],
+            ..Default::default()
+        })
+        .await?;
+    pc.create_data_channel("data", None).await?;
+
+    let offer = pc.create_offer(None).await?;
+    let mut gathering_complete = pc.gathering_complete_promise().await;
+    pc.set_local_description(offer).await?;
+
+    // The STUN transaction takes 5 seconds to time out, the gathering completes before
+    tokio::time::timeout(Duration::from_secs(2), gathering_complete.recv())
+        .await
+        .expect("gathering didn't complete on its timeout");
+    assert_eq!(pc.ice_gathering_state(), RTCIceGatheringState::Complete);
+
+    pc.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_header_extension_direction() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    for (uri, direction) in [
+        (
+            ::sdp::extmap::ABS_SEND_TIME_URI,
+            Some(RTCRtpTransceiverDirection::Recvonly),
+        ),
+        (
+            ::sdp::extmap::TRANSPORT_CC_URI,
+            Some(RTCRtpTransceiverDirection::Sendonly),
+        ),
+        (::sdp::extmap::SDES_MID_URI, None),
+    ] {
+        m.register_header_extension(
+            RTCRtpHeaderExtensionCapability {
+                uri: uri.to_owned(),
+            },
+            RTPCodecType::Video,
+            direction,
+        )?;
+    }
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let pc = api.new_peer_connection(RTCConfiguration::default()).await?;
+
+    let transceiver = pc
+        .add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+    let offer = pc.create_offer(None).await?;
+    let extmap = |uri: &str| {
+        offer
+            .sdp
+            .lines()
+            .find(|l| l.starts_with("a=extmap:") && l.ends_with(uri))
+            .and_then(|l| l["a=extmap:".len()..].split(' ').next())
+            .map(|v| v.split_once('/').map(|(_, d)| d.to_owned()))
+    };
+    assert_eq!(
+        extmap(::sdp::extmap::ABS_SEND_TIME_URI),
+        Some(Some("recvonly".to_owned()))
+    );
+    assert_eq!(
+        extmap(::sdp::extmap::TRANSPORT_CC_URI),
+        Some(Some("sendonly".to_owned()))
+    );
+    assert_eq!(extmap(::sdp::extmap::SDES_MID_URI), Some(None));
+
+    // Only the extensions allowed for sending are stamped on outgoing packets
+    let params = transceiver.sender().await.get_parameters().await;
+    let mut uris: Vec<&str> = params
+        .rtp_parameters
+        .header_extensions
+        .iter()
+        .map(|e| e.uri.as_str())
+        .collect();
+    uris.sort();
+    assert_eq!(
+        uris,
+        vec![::sdp::extmap::TRANSPORT_CC_URI, ::sdp::extmap::SDES_MID_URI]
+    );
+
+    pc.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_close_and_wait() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+    let (offer_notifier, mut offer_connected) = on_connected();
+    let (answer_notifier, mut answer_connected) = on_connected();
+    pc_offer.on_peer_connection_state_change(offer_notifier);
+    pc_answer.on_peer_connection_state_change(answer_notifier);
+    pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    let _ = offer_connected.recv().await;
+    let _ = answer_connected.recv().await;
+
+    let agent = pc_offer
+        .internal
+        .ice_gatherer
+        .get_agent()
+        .await
+        .expect("agent exists while connected");
+    let sockets: Vec<std::sync::Weak<dyn util::Conn + Send + Sync>> = agent
+        .get_local_candidates()
+        .await?
+        .iter()
+        .filter_map(|c| c.get_conn().map(Arc::downgrade))
+        .collect();
+    drop(agent);
+    assert!(!sockets.is_empty(), "expected gathered sockets");
+
+    pc_offer.close_and_wait().await?;
+    assert!(
+        sockets.iter().all(|s| s.upgrade().is_none()),
+        "sockets should be released once close_and_wait returns"
+    );
+    assert_eq!(pc_offer.connection_state(), RTCPeerConnectionState::Closed);
+
+    pc_answer.close_and_wait().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_connection_close_and_wait_from_handler() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+    let (answer_notifier, mut answer_connected) = on_connected();
+    pc_answer.on_peer_connection_state_change(answer_notifier);
+
+    // the handler closes the PeerConnection it is called by once connected
+    let (pc_tx, pc_rx) = tokio::sync::oneshot::channel::<Arc<RTCPeerConnection>>();
+    let pc_rx = Arc::new(Mutex::new(Some(pc_rx)));
+    let (closed_tx, mut closed_rx) = mpsc::channel::<Result<()>>(1);
+    pc_offer.on_peer_connection_state_change(Box::new(move |s: RTCPeerConnectionState| {
+        let pc_rx = Arc::clone(&pc_rx);
+        let closed_tx = closed_tx.clone();
+        Box::pin(async move {
+            if s != RTCPeerConnectionState::Connected {
+                return;
+            }
+            let Some(pc_rx) = pc_rx.lock().await.take() else {
+                return;
+            };
+            if let Ok(pc) = pc_rx.await {
+                let _ = closed_tx.send(pc.close_and_wait().await).await;
+            }
+        })
+    }));
+    pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Video, None)
+        .await?;
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    let pc_offer = Arc::new(pc_offer);
+    let _ = pc_tx.send(Arc::clone(&pc_offer));
+
+    let result = tokio::time::timeout(Duration::from_secs(10), closed_rx.recv())
+        .await
+        .expect("close_and_wait should not deadlock when called from a handler");
+    assert_eq!(result, Some(Ok(())));
+    assert_eq!(pc_offer.connection_state(), RTCPeerConnectionState::Closed);
+
+    let _ = answer_connected.recv().await;
+    pc_answer.close_and_wait().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_add_data_channel_keeps_media_sections() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+    pc_offer
+        .add_transceiver_from_kind(RTPCodecType::Audio, None)
+        .await?;
+
+    let offer = pc_offer.create_offer(None).await?;
+    let mut gather_complete = pc_offer.gathering_complete_promise().await;
+    pc_offer.set_local_description(offer).await?;
+    let _ = gather_complete.recv().await;
+    let offer = pc_offer.local_description().await.unwrap();
+    pc_answer.set_remote_description(offer).await?;
+    let answer = pc_answer.create_answer(None).await?;
+    let mut gather_complete = pc_answer.gathering_complete_promise().await;
+    pc_answer.set_local_description(answer).await?;
+    let _ = gather_complete.recv().await;
+    let answer = pc_answer.local_description().await.unwrap();
+    pc_offer.set_remote_description(answer).await?;
+
+    pc_offer.create_data_channel("data", None).await?;
+    let offer = pc_offer.create_offer(None).await?;
+
+    let current = pc_offer.current_local_description().await.unwrap();
+    let sections =
+        |sdp: &str| -> Vec<String> { sdp.split("\r\nm=").skip(1).map(|s| s.to_owned()).collect() };
+    let (current_sections, offer_sections) = (sections(&current.sdp), sections(&offer.sdp));
+    assert_eq!(offer_sections.len(), 2);
+    assert_eq!(offer_sections[0], current_sections[0].trim_end());
+    assert!(offer_sections[1].starts_with("application"));
+
+    // The session is only bumped to the next version
+    let (current_origin, offer_origin) = (
+        &current.parsed.as_ref().unwrap().origin,
+        &offer.parsed.as_ref().unwrap().origin,
+    );
+    assert_eq!(offer_origin.session_id, current_origin.session_id);
+    assert_eq!(
+        offer_origin.session_version,
+        current_origin.session_version + 1
+    );
+
+    pc_offer.set_local_description(offer.clone()).await?;
+    pc_answer.set_remote_description(offer).await?;
+    let answer = pc_answer.create_answer(None).await?;
+    pc_answer.set_local_description(answer.clone()).await?;
+    pc_offer.set_remote_description(answer).await?;
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_idle_timeout() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let mut s = SettingEngine::default();
+    s.set_idle_timeout(Duration::from_millis(500));
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+    let mut pc_offer = api.new_peer_connection(RTCConfiguration::default()).await?;
+
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+    let mut pc_answer = api.new_peer_connection(RTCConfiguration::default()).await?;
+
+    let (closed_tx, mut closed_rx) = mpsc::channel::<()>(1);
+    pc_offer.on_peer_connection_state_change(Box::new(move |state: RTCPeerConnectionState| {
+        let closed_tx = closed_tx.clone();
+        Box::pin(async move {
+            if state == RTCPeerConnectionState::Closed {
+                let _ = closed_tx.try_send(());
+            }
+        })
+    }));
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    let dc = pc_offer.create_data_channel("data", None).await?;
+    dc.on_open(Box::new(move || {
+        Box::pin(async move {
+            let _ = open_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    let _ = open_rx.recv().await;
+
+    // Traffic keeps the PeerConnection open past the idle timeout
+    for _ in 0..10 {
+        dc.send_text("ping").await?;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    assert_ne!(pc_offer.connection_state(), RTCPeerConnectionState::Closed);
+
+    tokio::time::timeout(Duration::from_secs(5), closed_rx.recv())
+        .await
+        .expect("idle PeerConnection wasn't closed");
+    assert_eq!(pc_offer.connection_state(), RTCPeerConnectionState::Closed);
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+// a=end-of-candidates in a remote description is handled like an empty
+// candidate added with add_ice_candidate
+#[tokio::test]
+async fn test_remote_end_of_candidates() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (pc_offer, pc_answer) = new_pair(&api).await?;
+    pc_offer.create_data_channel("data", None).await?;
+
+    // trickled offer, without a=end-of-candidates
+    let offer = pc_offer.create_offer(None).await?;
+    assert!(!offer.sdp.contains("a=end-of-candidates"), "{}", offer.sdp);
+    pc_offer.set_local_description(offer.clone()).await?;
+    pc_answer.set_remote_description(offer).await?;
+    let answer_dtls_transport = pc_answer.dtls_transport();
+    let answer_ice_transport = answer_dtls_transport.ice_transport();
+    assert!(!answer_ice_transport.remote_end_of_candidates());
+
+    // non-trickled answer, with a=end-of-candidates
+    let answer = pc_answer.create_answer(None).await?;
+    let mut answer_gathering_complete = pc_answer.gathering_complete_promise().await;
+    pc_answer.set_local_description(answer).await?;
+    let _ = answer_gathering_complete.recv().await;
+    let answer = pc_answer
+        .local_description()
+        .await
+        .ok_or(Error::new("non local description".to_owned()))?;
+    assert!(answer.sdp.contains("a=end-of-candidates"), "{}", answer.sdp);
+    pc_offer.set_remote_description(answer).await?;
+    assert!(pc_offer
+        .dtls_transport()
+        .ice_transport()
+        .remote_end_of_candidates());
+
+    pc_answer
+        .add_ice_candidate(RTCIceCandidateInit::default())
+        .await?;
+    assert!(answer_ice_transport.remote_end_of_candidates());
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_budget() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let mut s = SettingEngine::default();
+    s.set_memory_budget(Some(4096));
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    let dc = pc_offer.create_data_channel("data", None).await?;
+    dc.on_open(Box::new(move || {
+        Box::pin(async move {
+            let _ = open_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    let _ = open_rx.recv().await;
+
+    // A message larger than the budget can't be buffered
+    let err = dc
+        .send(&Bytes::from(vec![0u8; 8192]))
+        .await
+        .expect_err("message beyond the memory budget sent");
+    assert!(
+        matches!(
+            err,
+            Error::Data(data::Error::Sctp(sctp::Error::ErrMemoryBudgetExceeded))
+        ),
+        "{err}"
+    );
+    dc.send(&Bytes::from(vec![0u8; 1024])).await?;
+
+    let memory_budget_used = |stats: StatsReport| {
+        stats.reports.values().find_map(|report| match report {
+            StatsReportType::PeerConnection(stats) => Some(stats.memory_budget_used),
+            _ => None,
+        })
+    };
+    let used = memory_budget_used(pc_offer.get_stats().await);
+    assert!(matches!(used, Some(Some(n)) if n <= 4096), "{used:?}");
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_memory_budget_shared_by_sctp_and_nack() -> Result<()> {
+    const BUDGET: usize = 16 * 1024;
+
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let registry = register_default_interceptors(Registry::new(), &mut m)?;
+    let mut s = SettingEngine::default();
+    s.set_memory_budget(Some(BUDGET));
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_interceptor_registry(registry)
+        .with_setting_engine(s)
+        .build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pc_offer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    let dc = pc_offer.create_data_channel("data", None).await?;
+    dc.on_open(Box::new(move || {
+        Box::pin(async move {
+            let _ = open_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    let _ = open_rx.recv().await;
+
+    // The NACK send history of more media than the whole budget is kept to its share
+    let budget = pc_offer
+        .internal
+        .dtls_transport
+        .memory_budget
+        .clone()
+        .expect("memory budget set");
+    let packet = |sequence_number: u16| rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            sequence_number,
+            payload_type: 96,
+            ..Default::default()
+        },
+        payload: Bytes::from(vec![0u8; 1000]),
+    };
+    let mut sequence_number = 0u16;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while budget.used() < BUDGET / 2 - 1200 {
+            track
+                .write_rtp(&packet(sequence_number))
+                .await
+                .expect("write rtp");
+            sequence_number = sequence_number.wrapping_add(1);
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+    })
+    .await
+    .expect("the send history should be charged to the budget");
+    for _ in 0..64 {
+        track.write_rtp(&packet(sequence_number)).await?;
+        sequence_number = sequence_number.wrapping_add(1);
+    }
+    assert!(budget.used() <= BUDGET / 2, "{}", budget.used());
+
+    // and the data channels can still use the rest
+    dc.send(&Bytes::from(vec![0u8; BUDGET / 4])).await?;
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_max_inbound_ssrcs() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let mut s = SettingEngine::default();
+    s.set_max_inbound_ssrcs(Some(2));
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_setting_engine(s)
+        .build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let (open_tx, mut open_rx) = mpsc::channel::<()>(1);
+    let dc = pc_offer.create_data_channel("data", None).await?;
+    dc.on_open(Box::new(move || {
+        Box::pin(async move {
+            let _ = open_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+    let _ = open_rx.recv().await;
+
+    let inbound_ssrcs = |stats: StatsReport| {
+        stats.reports.values().find_map(|report| match report {
+            StatsReportType::PeerConnection(stats) => {
+                Some((stats.inbound_ssrcs, stats.inbound_ssrcs_dropped))
+            }
+            _ => None,
+        })
+    };
+    assert_eq!(inbound_ssrcs(pc_answer.get_stats().await), Some((0, 0)));
+
+    // Send RTP on more SSRCs than the answerer takes, none of them signaled
+    let srtp_session = pc_offer
+        .internal
+        .dtls_transport
+        .get_srtp_session()
+        .await
+        .unwrap();
+    let (ssrcs, dropped) = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            for ssrc in 1000..1005 {
+                srtp_session
+                    .write_rtp(&rtp::packet::Packet {
+                        header: rtp::header::Header {
+                            version: 2,
+                            payload_type: 96,
+                            ssrc,
+                            ..Default::default()
+                        },
+                        payload: Bytes::from_static(&[0xAA]),
+                    })
+                    .await?;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+
+            let (ssrcs, dropped) = inbound_ssrcs(pc_answer.get_stats().await).unwrap();
+            if dropped >= 3 {
+                return Result::Ok((ssrcs, dropped));
+            }
+        }
+    })
+    .await
+    .expect("inbound SSRCs weren't dropped")?;
+    assert!(ssrcs <= 2, "{ssrcs} inbound SSRCs");
+    assert!(dropped >= 3);
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_ice_restart_keeps_media_flowing() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pc_offer.add_track(Arc::clone(&track) as _).await?;
+
+    let (seq_tx, mut seq_rx) = mpsc::unbounded_channel();
+    pc_answer.on_track(Box::new(move |t, _, _| {
+        let seq_tx = seq_tx.clone();
+        tokio::spawn(async move {
+            while let Ok((pkt, _)) = t.read_rtp().await {
+                if seq_tx.send(pkt.header.sequence_number).is_err() {
+                    return;
+                }
+            }
+        });
+        Box::pin(async move {})
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let sender = {
+        let track = Arc::clone(&track);
+        tokio::spawn(async move {
+            for sequence_number in 0u16.. {
+                let pkt = rtp::packet::Packet {
+                    header: rtp::header::Header {
+                        version: 2,
+                        sequence_number,
+                        payload_type: 96,
+                        ..Default::default()
+                    },
+                    payload: Bytes::from_static(&[0; 2]),
+                };
+                let _ = track.write_rtp_with_extensions(&pkt, &[]).await;
+                tokio::select! {
+                    _ = tokio::time::sleep(Duration::from_millis(5)) => {}
+                    _ = done_rx.recv() => return,
+                }
+            }
+        })
+    };
+
+    async fn recv_seq(seq_rx: &mut mpsc::UnboundedReceiver<u16>) -> u16 {
+        tokio::time::timeout(Duration::from_secs(10), seq_rx.recv())
+            .await
+            .expect("RTP isn't received")
+            .unwrap()
+    }
+    let mut last_seq = recv_seq(&mut seq_rx).await;
+
+    let srtp_sessions = (
+        pc_offer.dtls_transport().get_srtp_session().await.unwrap(),
+        pc_answer.dtls_transport().get_srtp_session().await.unwrap(),
+    );
+    let (dtls_state_tx, mut dtls_state_rx) = mpsc::unbounded_channel();
+    for pc in [&pc_offer, &pc_answer] {
+        let dtls_state_tx = dtls_state_tx.clone();
+        pc.dtls_transport()
+            .on_state_change(Box::new(move |state: RTCDtlsTransportState| {
+                let _ = dtls_state_tx.send(state);
+                Box::pin(async move {})
+            }));
+    }
+    let (pair_tx, mut pair_rx) = mpsc::unbounded_channel();
+    pc_offer
+        .internal
+        .ice_transport
+        .on_selected_candidate_pair_change(Box::new(move |pair: RTCIceCandidatePair| {
+            let _ = pair_tx.send(pair);
+            Box::pin(async move {})
+        }));
+
+    // ICE restart initiated by the offerer
+    let ufrag = |desc: &RTCSessionDescription| {
+        desc.unmarshal()
+            .unwrap()
+            .media_descriptions
+            .iter()
+            .find_map(|m| m.attribute("ice-ufrag").flatten().map(str::to_owned))
+    };
+    let previous_ufrag = ufrag(&pc_offer.local_description().await.unwrap());
+    let offer = pc_offer
+        .create_offer(Some(RTCOfferOptions {
+            ice_restart: true,
+            ..Default::default()
+        }))
+        .await?;
+    let mut gathering_complete = pc_offer.gathering_complete_promise().await;
+    pc_offer.set_local_description(offer).await?;
+    let _ = gathering_complete.recv().await;
+    let offer = pc_offer.local_description().await.unwrap();
+    assert_ne!(ufrag(&offer), previous_ufrag);
+    pc_answer.set_remote_description(offer).await?;
+
+    let answer = pc_answer.create_answer(None).await?;
+    let mut gathering_complete = pc_answer.gathering_complete_promise().await;
+    pc_answer.set_local_description(answer).await?;
+    let _ = gathering_complete.recv().await;
+    pc_offer
+        .set_remote_description(pc_answer.local_description().await.unwrap())
+        .await?;
+
+    tokio::time::timeout(Duration::from_secs(10), pair_rx.recv())
+        .await
+        .expect("no candidate pair is selected after the ICE restart");
+
+    // RTP keeps flowing with its sequence numbers, over the same SRTP session
+    for _ in 0..20 {
+        let seq = recv_seq(&mut seq_rx).await;
+        assert!(seq > last_seq, "sequence number {seq} after {last_seq}");
+        last_seq = seq;
+    }
+    assert!(Arc::ptr_eq(
+        &srtp_sessions.0,
+        &pc_offer.dtls_transport().get_srtp_session().await.unwrap()
+    ));
+    assert!(Arc::ptr_eq(
+        &srtp_sessions.1,
+        &pc_answer.dtls_transport().get_srtp_session().await.unwrap()
+    ));
+    assert!(
+        dtls_state_rx.try_recv().is_err(),
+        "the DTLS transport changed state"
+    );
+    assert_eq!(
+        pc_offer.dtls_transport().state(),
+        RTCDtlsTransportState::Connected
+    );
+
+    let _ = done_tx.send(()).await;
+    let _ = sender.await;
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[test]
+fn test_start_simulcast_probe_limit() {
+    let count = AtomicU64::new(0);
+
+    // fewer than max_probe_routines probes run at the same time
+    assert!(start_simulcast_probe(&count, 3));
+    assert!(start_simulcast_probe(&count, 3));
+    assert!(!start_simulcast_probe(&count, 3));
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+
+    // a finished probe makes room for the next one
+    count.fetch_sub(1, Ordering::SeqCst);
+    assert!(start_simulcast_probe(&count, 3));
+    assert!(!start_simulcast_probe(&count, 3));
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
@@ -2,6 +2,7 @@
 mod sdp_test;
 
 use crate::api::media_engine::MediaEngine;
+use crate::api::setting_engine::CodecFilterFn;
 use crate::dtls_transport::dtls_fingerprint::RTCDtlsFingerprint;
 use crate::error::{Error, Result};
 use crate::ice_transport::ice_candidate::RTCIceCandidate;
@@ -12,7 +13,10 @@ use crate::rtp_transceiver::rtp_codec::{
     RTCRtpCodecCapability, RTCRtpCodecParameters, RTPCodecType,
 };
 use crate::rtp_transceiver::rtp_transceiver_direction::RTCRtpTransceiverDirection;
-use crate::rtp_transceiver::{PayloadType, RTCPFeedback, RTCRtpTransceiver, SSRC};
+use crate::rtp_transceiver::{
+    fmtp, PayloadType, RTCPFeedback, RTCRtpTransceiver, RTCRtpVideoConstraints, SSRC,
+};
+use crate::sctp_transport::SCTP_MAX_MESSAGE_SIZE;
 
 pub mod sdp_type;
 pub mod session_description;
@@ -27,7 +31,9 @@ use ice::candidate::Candidate;
 use sdp::description::common::{Address, ConnectionInformation};
 use sdp::description::media::{MediaDescription, MediaName, RangedPort};
 use sdp::description::session::*;
+use sdp::direction::Direction;
 use sdp::extmap::ExtMap;
+use sdp::imageattr::{ImageAttr, ImageAttrSet};
 use sdp::util::ConnectionRole;
 use smol_str::SmolStr;
 use url::Url;
@@ -307,13 +313,8 @@ pub(crate) async fn add_candidates_to_media_descriptions(
     if ice_gathering_state != RTCIceGatheringState::Complete {
         return Ok(m);
     }
-    for a in &m.attributes {
-        if &a.key == "end-of-candidates" {
-            return Ok(m);
-        }
-    }
 
-    Ok(m.with_property_attribute("end-of-candidates".to_owned()))
+    Ok(m.with_end_of_candidates())
 }
 
 pub(crate) struct AddDataMediaSectionParams {
@@ -361,6 +362,10 @@ pub(crate) async fn add_data_media_section(
     .with_value_attribute(ATTR_KEY_MID.to_owned(), params.mid_value)
     .with_property_attribute(RTCRtpTransceiverDirection::Sendrecv.to_string())
     .with_property_attribute("sctp-port:5000".to_owned())
+    .with_value_attribute(
+        ATTR_KEY_MAX_MESSAGE_SIZE.to_owned(),
+        SCTP_MAX_MESSAGE_SIZE.to_string(),
+    )
     .with_ice_credentials(
         params.ice_params.username_fragment,
         params.ice_params.password,
@@ -425,6 +430,24 @@ pub(crate) struct AddTransceiverSdpParams {
     dtls_role: ConnectionRole,
     ice_gathering_state: RTCIceGatheringState,
     offered_direction: Option<RTCRtpTransceiverDirection>,
+    codec_filter: Option<Arc<CodecFilterFn>>,
+}
+
+/// filter_codecs returns the codecs the filter keeps, with the RTX codecs of the kept ones
+fn filter_codecs(
+    codecs: Vec<RTCRtpCodecParameters>,
+    kind: RTPCodecType,
+    filter: &CodecFilterFn,
+) -> Vec<RTCRtpCodecParameters> {
+    let (kept, removed): (Vec<_>, Vec<_>) = codecs.into_iter().partition(|c| filter(kind, c));
+    kept.into_iter()
+        .filter(|c| {
+            let apt = fmtp::parse(&c.capability.mime_type, &c.capability.sdp_fmtp_line)
+                .parameter("apt")
+                .and_then(|apt| apt.parse::<PayloadType>().ok());
+            !apt.is_some_and(|apt| removed.iter().any(|r| r.payload_type == apt))
+        })
+        .collect()
 }
 
 pub(crate) async fn add_transceiver_sdp(
@@ -463,7 +486,29 @@ pub(crate) async fn add_transceiver_sdp(
         media = media.with_property_attribute(ATTR_KEY_EXTMAP_ALLOW_MIXED.to_owned());
     }
 
-    let codecs = t.get_codecs().await;
+    if let Some(content) = t.content() {
+        media = media.with_value_attribute(ATTR_KEY_CONTENT.to_owned(), content);
+    }
+
+    if t.kind == RTPCodecType::Video && t.direction().has_recv() {
+        if let Some(constraints) = t.receive_constraints() {
+            if let Some((max_width, max_height)) = constraints.max_resolution {
+                media = media.with_image_attr(ImageAttr {
+                    payload_type: None,
+                    send: None,
+                    recv: Some(vec![ImageAttrSet::new(max_width, max_height)]),
+                });
+            }
+            if let Some(max_framerate) = constraints.max_framerate {
+                media = media.with_framerate(max_framerate);
+            }
+        }
+    }
+
+    let mut codecs = t.get_codecs().await;
+    if let Some(filter) = &params.codec_filter {
+        codecs = filter_codecs(codecs, t.kind, filter.as_ref());
+    }
     for codec in &codecs {
         let name = codec
             .capability
@@ -535,9 +580,16 @@ pub(crate) async fn add_transceiver_sdp(
     let parameters = media_engine.get_rtp_parameters_by_kind(t.kind, t.direction());
     for rtp_extension in &parameters.header_extensions {
         let ext_url = Url::parse(rtp_extension.uri.as_str())?;
+        let direction = match media_engine.get_header_extension_direction(&rtp_extension.uri) {
+            Some(RTCRtpTransceiverDirection::Sendonly) => Direction::SendOnly,
+            Some(RTCRtpTransceiverDirection::Recvonly) => Direction::RecvOnly,
+            Some(RTCRtpTransceiverDirection::Inactive) => Direction::Inactive,
+            _ => Direction::Unspecified,
+        };
         media = media.with_extmap(sdp::extmap::ExtMap {
             value: rtp_extension.id,
             uri: Some(ext_url),
+            direction,
             ..Default::default()
         });
     }
@@ -800,6 +852,7 @@ pub(crate) struct PopulateSdpParams {
     pub(crate) connection_role: ConnectionRole,
     pub(crate) ice_gathering_state: RTCIceGatheringState,
     pub(crate) match_bundle_group: Option<String>,
+    pub(crate) codec_filter: Option<Arc<CodecFilterFn>>,
 }
 
 /// populate_sdp serializes a PeerConnections state into an SDP
@@ -851,6 +904,7 @@ pub(crate) async fn populate_sdp(
                 dtls_role: params.connection_role,
                 ice_gathering_state: params.ice_gathering_state,
                 offered_direction: m.offered_direction,
+                codec_filter: params.codec_filter.clone(),
             };
             let (d1, should_add_id) = add_transceiver_sdp(
                 d,
@@ -913,6 +967,46 @@ pub(crate) fn get_mid_value(media: &MediaDescription) -> Option<&String> {
     None
 }
 
+/// is_simulcast_media_section returns whether the media section with the given mid declares RIDs,
+/// its streams then need the rtp-stream-id header extension besides the MID to be identified.
+/// An unknown mid is reported as simulcast so that it is never routed on the MID alone.
+pub(crate) fn is_simulcast_media_section(desc: &SessionDescription, mid: &str) -> bool {
+    desc.media_descriptions
+        .iter()
+        .find(|m| get_mid_value(m).map(|v| v.as_str()) == Some(mid))
+        .is_none_or(|m| m.attributes.iter().any(|a| a.key == SDP_ATTRIBUTE_RID))
+}
+
+/// get_content returns the value of the `a=content` attribute (RFC 4796) of a
+/// media description.
+pub(crate) fn get_content(media: &MediaDescription) -> Option<String> {
+    media
+        .attribute(ATTR_KEY_CONTENT)
+        .flatten()
+        .map(|content| content.to_owned())
+}
+
+/// get_receive_constraints returns the caps on the video a media description receives, the
+/// largest resolution of its `a=imageattr` recv sets and its `a=framerate`.
+pub(crate) fn get_receive_constraints(media: &MediaDescription) -> Option<RTCRtpVideoConstraints> {
+    let max_resolution = media
+        .image_attrs()
+        .into_iter()
+        .filter_map(|image_attr| image_attr.recv)
+        .flatten()
+        .map(|set| (set.x.max(), set.y.max()))
+        .reduce(|(w1, h1), (w2, h2)| (w1.max(w2), h1.max(h2)));
+    let max_framerate = media.framerate();
+
+    if max_resolution.is_none() && max_framerate.is_none() {
+        return None;
+    }
+    Some(RTCRtpVideoConstraints {
+        max_resolution,
+        max_framerate,
+    })
+}
+
 pub(crate) fn get_peer_direction(media: &MediaDescription) -> RTCRtpTransceiverDirection {
     for a in &media.attributes {
         let direction = RTCRtpTransceiverDirection::from(a.key.as_str());
@@ -1029,6 +1123,80 @@ pub(crate) fn have_application_media_section(desc: &SessionDescription) -> bool
     false
 }
 
+/// get_max_message_size returns the a=max-message-size (RFC 8841) of the
+/// application media section, 0 when the remote didn't advertise one.
+pub(crate) fn get_max_message_size(desc: &SessionDescription) -> u32 {
+    desc.media_descriptions
+        .iter()
+        .filter(|m| m.media_name.media == MEDIA_SECTION_APPLICATION)
+        .find_map(|m| m.attribute(ATTR_KEY_MAX_MESSAGE_SIZE).flatten())
+        .and_then(|value| value.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// get_rtcp_port returns the port of the a=rtcp attribute (RFC 3605) of a
+/// media section, None when it has none.
+pub(crate) fn get_rtcp_port(media: &MediaDescription) -> Result<Option<u16>> {
+    let value = match media.attribute(ATTR_KEY_RTCP) {
+        Some(value) => value.unwrap_or_default(),
+        None => return Ok(None),
+    };
+
+    value
+        .split_whitespace()
+        .next()
+        .and_then(|port| port.parse().ok())
+        .map(Some)
+        .ok_or_else(|| Error::ErrSessionDescriptionInvalidRtcp(value.to_owned()))
+}
+
+/// check_rtcp_mux returns an error if an audio or video media section in use
+/// doesn't negotiate rtcp-mux (RFC 5761). RTCP is always received on the
+/// transport of RTP, so a peer expecting it on a separate port, given by
+/// a=rtcp or the RTP port + 1, can't be supported.
+pub(crate) fn check_rtcp_mux(desc: &SessionDescription) -> Result<()> {
+    for m in &desc.media_descriptions {
+        if m.media_name.media == MEDIA_SECTION_APPLICATION
+            || m.media_name.port.value == 0
+            || m.has_attribute(ATTR_KEY_RTCPMUX)
+        {
+            continue;
+        }
+
+        let mid = get_mid_value(m).cloned().unwrap_or_default();
+        match get_rtcp_port(m)? {
+            Some(port) => log::warn!(
+                "media section {} requires RTCP on port {} without rtcp-mux",
+                mid,
+                port
+            ),
+            None => log::warn!("media section {} requires RTCP without rtcp-mux", mid),
+        }
+        return Err(Error::ErrRtcpMuxRequired(mid));
+    }
+
+    Ok(())
+}
+
+/// have_rtcp_reduced_size returns true if every audio and video media section
+/// of the description allows reduced-size RTCP (RFC 5506). The media sections
+/// are bundled on one transport, so a single section without `a=rtcp-rsize`
+/// requires compound RTCP for all of them.
+pub(crate) fn have_rtcp_reduced_size(desc: &SessionDescription) -> bool {
+    let mut have_media = false;
+    for m in &desc.media_descriptions {
+        if m.media_name.media == MEDIA_SECTION_APPLICATION {
+            continue;
+        }
+        if m.attribute(ATTR_KEY_RTCPRSIZE).is_none() {
+            return false;
+        }
+        have_media = true;
+    }
+
+    have_media
+}
+
 pub(crate) fn get_by_mid<'a>(
     search_mid: &str,
     desc: &'a session_description::RTCSessionDescription,
@@ -1160,6 +1328,76 @@ pub(crate) fn update_sdp_origin(origin: &mut Origin, d: &mut SessionDescription)
 
         //d.Origin.SessionVersion = atomic.AddUint64(&origin.SessionVersion, 1)
         origin.session_version += 1;
-        d.origin.session_version += 1;
+        d.origin.session_version = origin.session_version;
+    }
+}
+
+/// keep_unchanged_media_sections replaces every media section of the offer that only
+/// differs by its candidates from the section with the same mid in the current
+/// description with that section, so the offer is byte for byte the same as the
+/// current description outside of the media sections that changed.
+pub(crate) fn keep_unchanged_media_sections(current: &str, offer: &str) -> String {
+    let (_, current_sections) = split_media_sections(current);
+    let (session, offer_sections) = split_media_sections(offer);
+
+    let mut sdp = session.to_owned();
+    for section in offer_sections {
+        let unchanged = section_mid(section).and_then(|mid| {
+            current_sections.iter().find(|current| {
+                section_mid(current) == Some(mid)
+                    && section_lines(current).eq(section_lines(section))
+            })
+        });
+        sdp += unchanged.unwrap_or(&section);
     }
+
+    sdp
+}
+
+/// adds_only_application_section returns true when the offer only appends an
+/// application media section to the current description, e.g. for the first data
+/// channel, and every other media section is unchanged but for its candidates
+pub(crate) fn adds_only_application_section(current: &str, offer: &str) -> bool {
+    let (_, current_sections) = split_media_sections(current);
+    let (_, offer_sections) = split_media_sections(offer);
+    let Some((added, kept)) = offer_sections.split_last() else {
+        return false;
+    };
+
+    added.starts_with("m=application")
+        && kept.len() == current_sections.len()
+        && kept
+            .iter()
+            .zip(current_sections.iter())
+            .all(|(offer, current)| section_lines(offer).eq(section_lines(current)))
+}
+
+/// split_media_sections splits a marshaled SessionDescription into its session
+/// section and its media sections, each of them starting with its m= line
+fn split_media_sections(sdp: &str) -> (&str, Vec<&str>) {
+    let mut starts: Vec<usize> = sdp.match_indices("\r\nm=").map(|(i, _)| i + 2).collect();
+    if sdp.starts_with("m=") {
+        starts.insert(0, 0);
+    }
+
+    let session = &sdp[..starts.first().copied().unwrap_or(sdp.len())];
+    let sections = starts
+        .iter()
+        .enumerate()
+        .map(|(i, start)| &sdp[*start..starts.get(i + 1).copied().unwrap_or(sdp.len())])
+        .collect();
+
+    (session, sections)
+}
+
+fn section_mid(section: &str) -> Option<&str> {
+    section.lines().find_map(|line| line.strip_prefix("a=mid:"))
+}
+
+/// section_lines returns the lines of a media section except the candidates, which
+/// don't need to be signaled again as long as ICE isn't restarted
+fn section_lines(section: &str) -> impl Iterator<Item = &str> {
+    section.lines().filter(|line| {
+        !line.starts_with("a=candidate:") && !line.starts_with("a=end-of-candidates")
+    })
 }
@@ -568,6 +568,54 @@ fn test_have_application_media_section() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_have_rtcp_reduced_size() {
+    let media = |kind: &str, rtcp_rsize: bool| MediaDescription {
+        media_name: MediaName {
+            media: kind.to_owned(),
+            ..Default::default()
+        },
+        attributes: if rtcp_rsize {
+            vec![Attribute::new(ATTR_KEY_RTCPRSIZE.to_owned(), None)]
+        } else {
+            vec![]
+        },
+        ..Default::default()
+    };
+
+    let tests = vec![
+        ("No media", vec![], false),
+        ("Audio", vec![media("audio", true)], true),
+        (
+            "Audio without rtcp-rsize",
+            vec![media("audio", false)],
+            false,
+        ),
+        (
+            "Application without rtcp-rsize",
+            vec![
+                media("audio", true),
+                media("video", true),
+                media(MEDIA_SECTION_APPLICATION, false),
+            ],
+            true,
+        ),
+        (
+            "One video without rtcp-rsize",
+            vec![media("audio", true), media("video", false)],
+            false,
+        ),
+    ];
+
+    for (name, media_descriptions, expected) in tests {
+        let s = SessionDescription {
+            media_descriptions,
+            ..Default::default()
+        };
+        assert_eq!(have_rtcp_reduced_size(&s), expected, "{name}");
+    }
+}
+
 async fn fingerprint_test(
     certificate: &RTCCertificate,
     engine: &Arc<MediaEngine>,
@@ -586,6 +634,7 @@ async fn fingerprint_test(
         connection_role: ConnectionRole::Active,
         ice_gathering_state: RTCIceGatheringState::New,
         match_bundle_group: None,
+        codec_filter: None,
     };
 
     let s = populate_sdp(
@@ -783,6 +832,7 @@ async fn test_populate_sdp() -> Result<()> {
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
             match_bundle_group: None,
+            codec_filter: None,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -889,6 +939,7 @@ async fn test_populate_sdp() -> Result<()> {
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
             match_bundle_group: None,
+            codec_filter: None,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -970,6 +1021,7 @@ async fn test_populate_sdp() -> Result<()> {
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
             match_bundle_group: None,
+            codec_filter: None,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -1068,6 +1120,7 @@ async fn test_populate_sdp() -> Result<()> {
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
             match_bundle_group: Some("audio".to_owned()),
+            codec_filter: None,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -1134,6 +1187,7 @@ async fn test_populate_sdp() -> Result<()> {
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
             match_bundle_group: Some("".to_owned()),
+            codec_filter: None,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -1244,6 +1298,7 @@ async fn test_populate_sdp() -> Result<()> {
             connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
             ice_gathering_state: RTCIceGatheringState::Complete,
             match_bundle_group: None,
+            codec_filter: None,
         };
         let offer_sdp = populate_sdp(
             d,
@@ -1399,6 +1454,7 @@ async fn test_populate_sdp_reject() -> Result<()> {
         connection_role: DEFAULT_DTLS_ROLE_OFFER.to_connection_role(),
         ice_gathering_state: RTCIceGatheringState::Complete,
         match_bundle_group: None,
+        codec_filter: None,
     };
     let offer_sdp = populate_sdp(
         d,
@@ -1586,3 +1642,113 @@ fn test_rtp_extensions_from_media_description() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_get_max_message_size() {
+    let parse = |raw: &str| {
+        let mut reader = std::io::Cursor::new(raw.as_bytes());
+        SessionDescription::unmarshal(&mut reader).unwrap()
+    };
+
+    let base = "v=0\r\no=- 0 0 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n";
+    let tests =
+        vec![
+        ("", 0),
+        ("m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n", 0),
+        (
+            "m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\na=max-message-size:1024\r\n",
+            1024,
+        ),
+        (
+            "m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\na=max-message-size:invalid\r\n",
+            0,
+        ),
+        ("m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=max-message-size:1024\r\n", 0),
+    ];
+
+    for (media, expected) in tests {
+        let desc = parse(&format!("{base}{media}"));
+        assert_eq!(get_max_message_size(&desc), expected, "{media}");
+    }
+}
+
+#[test]
+fn test_check_rtcp_mux() {
+    let parse = |raw: &str| {
+        let mut reader = std::io::Cursor::new(raw.as_bytes());
+        SessionDescription::unmarshal(&mut reader).unwrap()
+    };
+
+    let base = "v=0\r\no=- 0 0 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n";
+    let tests = vec![
+        ("", None, true),
+        (
+            "m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\na=mid:0\r\n",
+            None,
+            true,
+        ),
+        (
+            "m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\na=rtcp-mux\r\n",
+            None,
+            true,
+        ),
+        (
+            "m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\na=rtcp:9 IN IP4 0.0.0.0\r\na=rtcp-mux\r\n",
+            Some(9),
+            true,
+        ),
+        (
+            "m=audio 0 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\na=rtcp:1\r\n",
+            Some(1),
+            true,
+        ),
+        ("m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\n", None, false),
+        (
+            "m=video 50000 RTP/AVP 96\r\na=mid:0\r\na=rtcp:50002\r\n",
+            Some(50002),
+            false,
+        ),
+    ];
+
+    for (media, rtcp_port, mux) in tests {
+        let desc = parse(&format!("{base}{media}"));
+        if let Some(m) = desc.media_descriptions.first() {
+            assert_eq!(get_rtcp_port(m).unwrap(), rtcp_port, "{media}");
+        }
+        match check_rtcp_mux(&desc) {
+            Ok(()) => assert!(mux, "{media}"),
+            Err(Error::ErrRtcpMuxRequired(mid)) => {
+                assert!(!mux, "{media}");
+                assert_eq!(mid, "0");
+            }
+            Err(err) => panic!("unexpected error {err}"),
+        }
+    }
+
+    let desc = parse(&format!(
+        "{base}m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=rtcp:invalid\r\n"
+    ));
+    assert!(matches!(
+        check_rtcp_mux(&desc),
+        Err(Error::ErrSessionDescriptionInvalidRtcp(_))
+    ));
+}
+
+#[test]
+fn test_keep_unchanged_media_sections() {
+    let current = "v=0\r\no=- 1 1 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\na=sendrecv\r\na=candidate:1 1 udp 1 1.2.3.4 5000 typ host\r\na=end-of-candidates\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\na=mid:1\r\na=sendrecv\r\n";
+    let offer = "v=0\r\no=- 1 2 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\na=sendrecv\r\na=candidate:2 1 udp 1 1.2.3.4 5002 typ host\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\na=mid:1\r\na=recvonly\r\n\
+        m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\na=mid:2\r\n";
+
+    assert_eq!(
+        keep_unchanged_media_sections(current, offer),
+        "v=0\r\no=- 1 2 IN IP4 0.0.0.0\r\ns=-\r\nt=0 0\r\n\
+        m=audio 9 UDP/TLS/RTP/SAVPF 111\r\na=mid:0\r\na=sendrecv\r\na=candidate:1 1 udp 1 1.2.3.4 5000 typ host\r\na=end-of-candidates\r\n\
+        m=video 9 UDP/TLS/RTP/SAVPF 96\r\na=mid:1\r\na=recvonly\r\n\
+        m=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\na=mid:2\r\n"
+    );
+}
@@ -74,6 +74,17 @@ impl RTCSessionDescription {
         Ok(desc)
     }
 
+    /// Returns an RTCSessionDescription that discards the pending offer when given to
+    /// set_local_description or set_remote_description, returning the signaling state to
+    /// stable. The polite peer of perfect negotiation uses it on glare.
+    pub fn rollback() -> RTCSessionDescription {
+        RTCSessionDescription {
+            sdp: String::new(),
+            sdp_type: RTCSdpType::Rollback,
+            parsed: None,
+        }
+    }
+
     /// Unmarshal is a helper to deserialize the sdp
     pub fn unmarshal(&self) -> Result<SessionDescription> {
         let mut reader = Cursor::new(self.sdp.as_bytes());
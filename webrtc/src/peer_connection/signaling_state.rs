@@ -169,6 +169,12 @@ pub(crate) fn check_next_signaling_state(
                 && next == RTCSignalingState::HaveLocalOffer
             {
                 return Ok(next);
+            } else if op == StateChangeOp::SetLocal
+                && sdp_type == RTCSdpType::Rollback
+                && next == RTCSignalingState::Stable
+            {
+                // have-local-offer->SetLocal(rollback)->stable
+                return Ok(next);
             }
         }
         RTCSignalingState::HaveRemotePranswer => {
@@ -196,6 +202,9 @@ pub(crate) fn check_next_signaling_state(
                     }
                     _ => {}
                 }
+            } else if sdp_type == RTCSdpType::Rollback && next == RTCSignalingState::Stable {
+                // have-remote-offer->SetRemote(rollback)->stable
+                return Ok(next);
             }
         }
         RTCSignalingState::HaveLocalPranswer => {
@@ -353,6 +362,34 @@ mod test {
                 RTCSdpType::Rollback,
                 Some(Error::ErrSignalingStateCannotRollback),
             ),
+            (
+                "have-local-offer->SetLocal(rollback)->stable",
+                RTCSignalingState::HaveLocalOffer,
+                RTCSignalingState::Stable,
+                StateChangeOp::SetLocal,
+                RTCSdpType::Rollback,
+                None,
+            ),
+            (
+                "have-remote-offer->SetRemote(rollback)->stable",
+                RTCSignalingState::HaveRemoteOffer,
+                RTCSignalingState::Stable,
+                StateChangeOp::SetRemote,
+                RTCSdpType::Rollback,
+                None,
+            ),
+            (
+                "(invalid) have-local-offer->SetRemote(rollback)->stable",
+                RTCSignalingState::HaveLocalOffer,
+                RTCSignalingState::Stable,
+                StateChangeOp::SetRemote,
+                RTCSdpType::Rollback,
+                Some(Error::ErrSignalingStateProposedTransitionInvalid {
+                    from: RTCSignalingState::HaveLocalOffer,
+                    is_local: false,
+                    applying: RTCSdpType::Rollback,
+                }),
+            ),
         ];
 
         for (desc, cur, next, op, sdp_type, expected_err) in tests {
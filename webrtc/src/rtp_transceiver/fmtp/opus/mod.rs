@@ -0,0 +1,37 @@
+#[cfg(test)]
+mod opus_test;
+
+use super::*;
+
+/// OpusFmtp holds the parameters of RFC 7587. They describe what each side prefers to
+/// receive, such as usedtx or stereo, and are not negotiated, so any two Opus fmtp
+/// descriptions are compatible.
+#[derive(Debug, PartialEq)]
+pub(crate) struct OpusFmtp {
+    pub(crate) parameters: HashMap<String, String>,
+}
+
+impl Fmtp for OpusFmtp {
+    fn mime_type(&self) -> &str {
+        "audio/opus"
+    }
+
+    fn match_fmtp(&self, f: &dyn Fmtp) -> bool {
+        f.as_any().downcast_ref::<OpusFmtp>().is_some()
+    }
+
+    fn parameter(&self, key: &str) -> Option<&String> {
+        self.parameters.get(key)
+    }
+
+    fn equal(&self, other: &dyn Fmtp) -> bool {
+        other
+            .as_any()
+            .downcast_ref::<OpusFmtp>()
+            .is_some_and(|a| self == a)
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
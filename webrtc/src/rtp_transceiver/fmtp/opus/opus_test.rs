@@ -0,0 +1,49 @@
+use super::*;
+
+#[test]
+fn test_opus_fmtp_parse() {
+    let f = parse(
+        "audio/OPUS",
+        "minptime=10;useinbandfec=1;usedtx=1;stereo=1;sprop-stereo=1",
+    );
+    let opus = f.as_any().downcast_ref::<OpusFmtp>().expect("OpusFmtp");
+    assert_eq!(opus.mime_type(), "audio/opus");
+    assert_eq!(f.parameter("usedtx"), Some(&"1".to_owned()));
+    assert_eq!(f.parameter("stereo"), Some(&"1".to_owned()));
+    assert_eq!(f.parameter("sprop-stereo"), Some(&"1".to_owned()));
+    assert_eq!(f.parameter("cbr"), None);
+}
+
+#[test]
+fn test_opus_fmtp_compare() {
+    let tests = vec![
+        (
+            "Equal",
+            "minptime=10;useinbandfec=1",
+            "minptime=10;useinbandfec=1",
+        ),
+        ("Empty", "minptime=10;useinbandfec=1", ""),
+        (
+            "DifferentDtx",
+            "minptime=10;useinbandfec=1;usedtx=1",
+            "minptime=10;useinbandfec=1;usedtx=0",
+        ),
+        (
+            "DifferentStereo",
+            "stereo=1;sprop-stereo=1",
+            "stereo=0;sprop-stereo=0",
+        ),
+    ];
+
+    for (name, a, b) in tests {
+        let fa = parse("audio/opus", a);
+        let fb = parse("audio/opus", b);
+        assert!(fa.match_fmtp(&*fb), "{name}: {a} must match {b}");
+        assert!(fb.match_fmtp(&*fa), "{name}: {b} must match {a}");
+    }
+
+    let opus = parse("audio/opus", "minptime=10");
+    let g722 = parse("audio/G722", "minptime=10");
+    assert!(!opus.match_fmtp(&*g722));
+    assert!(!g722.match_fmtp(&*opus));
+}
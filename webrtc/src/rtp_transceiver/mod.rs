@@ -13,7 +13,8 @@ use log::trace;
 use portable_atomic::{AtomicBool, AtomicU8};
 use serde::{Deserialize, Serialize};
 use smol_str::SmolStr;
-use tokio::sync::{Mutex, OnceCell};
+use tokio::sync::Mutex;
+use util::sync::Mutex as SyncMutex;
 use util::Unmarshal;
 
 use crate::api::media_engine::MediaEngine;
@@ -121,6 +122,19 @@ pub struct RTCRtpReceiveParameters {
 pub struct RTCRtpSendParameters {
     pub rtp_parameters: RTCRtpParameters,
     pub encodings: Vec<RTCRtpEncodingParameters>,
+    /// remote_receive_constraints caps the resolution and frame rate of the media sent, as
+    /// negotiated by the remote peer. Encoders should stay within them.
+    pub remote_receive_constraints: Option<RTCRtpVideoConstraints>,
+}
+
+/// RTCRtpVideoConstraints caps the resolution and frame rate of a video stream. They are
+/// signaled with `a=imageattr` (RFC 6236) and `a=framerate` (RFC 4566).
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct RTCRtpVideoConstraints {
+    /// max_resolution is the largest width and height of the video
+    pub max_resolution: Option<(u32, u32)>,
+    /// max_framerate is the highest frame rate of the video
+    pub max_framerate: Option<f64>,
 }
 
 /// RTPTransceiverInit dictionary is used when calling the WebRTC function addTransceiver() to provide configuration options for the new transceiver.
@@ -167,6 +181,7 @@ pub(crate) fn create_stream_info(
         sdp_fmtp_line: codec.sdp_fmtp_line,
         rtcp_feedback: feedbacks,
         associated_stream,
+        memory_budget: None,
     }
 }
 
@@ -175,7 +190,7 @@ pub type TriggerNegotiationNeededFnOption =
 
 /// RTPTransceiver represents a combination of an RTPSender and an RTPReceiver that share a common mid.
 pub struct RTCRtpTransceiver {
-    mid: OnceCell<SmolStr>,               //atomic.Value
+    mid: SyncMutex<Option<SmolStr>>,      //atomic.Value
     sender: Mutex<Arc<RTCRtpSender>>,     //atomic.Value
     receiver: Mutex<Arc<RTCRtpReceiver>>, //atomic.Value
 
@@ -184,6 +199,14 @@ pub struct RTCRtpTransceiver {
 
     codecs: Arc<Mutex<Vec<RTCRtpCodecParameters>>>, // User provided codecs via set_codec_preferences
 
+    content: SyncMutex<Option<String>>, // a=content, RFC 4796
+    remote_content: SyncMutex<Option<String>>,
+
+    receive_constraints: SyncMutex<Option<RTCRtpVideoConstraints>>, // a=imageattr and a=framerate
+    remote_receive_constraints: SyncMutex<Option<RTCRtpVideoConstraints>>,
+
+    negotiated_header_extensions: SyncMutex<Vec<(u8, String)>>, // a=extmap
+
     pub(crate) stopped: AtomicBool,
     pub(crate) kind: RTPCodecType,
 
@@ -206,7 +229,7 @@ impl RTCRtpTransceiver {
         receiver.set_transceiver_codecs(Some(Arc::clone(&codecs)));
 
         let t = Arc::new(RTCRtpTransceiver {
-            mid: OnceCell::new(),
+            mid: SyncMutex::new(None),
             sender: Mutex::new(sender),
             receiver: Mutex::new(receiver),
 
@@ -214,6 +237,11 @@ impl RTCRtpTransceiver {
             current_direction: AtomicU8::new(RTCRtpTransceiverDirection::Unspecified as u8),
 
             codecs,
+            content: SyncMutex::new(None),
+            remote_content: SyncMutex::new(None),
+            receive_constraints: SyncMutex::new(None),
+            remote_receive_constraints: SyncMutex::new(None),
+            negotiated_header_extensions: SyncMutex::new(vec![]),
             stopped: AtomicBool::new(false),
             kind,
             media_engine,
@@ -244,6 +272,12 @@ impl RTCRtpTransceiver {
         Ok(())
     }
 
+    /// codec_preferences returns the codecs set with set_codec_preferences, empty if the
+    /// defaults of the MediaEngine are used.
+    pub(crate) async fn codec_preferences(&self) -> Vec<RTCRtpCodecParameters> {
+        self.codecs.lock().await.clone()
+    }
+
     /// Codecs returns list of supported codecs
     pub(crate) async fn get_codecs(&self) -> Vec<RTCRtpCodecParameters> {
         let mut codecs = self.codecs.lock().await;
@@ -297,14 +331,22 @@ impl RTCRtpTransceiver {
 
     /// set_mid sets the RTPTransceiver's mid. If it was already set, will return an error.
     pub(crate) fn set_mid(&self, mid: SmolStr) -> Result<()> {
-        self.mid
-            .set(mid)
-            .map_err(|_| Error::ErrRTPTransceiverCannotChangeMid)
+        let mut current = self.mid.lock();
+        if current.is_some() {
+            return Err(Error::ErrRTPTransceiverCannotChangeMid);
+        }
+        *current = Some(mid);
+        Ok(())
+    }
+
+    /// clear_mid releases the mid of an offer that was rolled back before it was negotiated.
+    pub(crate) fn clear_mid(&self) {
+        *self.mid.lock() = None;
     }
 
     /// mid gets the Transceiver's mid value. When not already set, this value will be set in CreateOffer or create_answer.
     pub fn mid(&self) -> Option<SmolStr> {
-        self.mid.get().cloned()
+        self.mid.lock().clone()
     }
 
     /// kind returns RTPTransceiver's kind.
@@ -346,6 +388,90 @@ impl RTCRtpTransceiver {
         changed
     }
 
+    /// content returns the `a=content` label (RFC 4796) this transceiver
+    /// signals for its media, e.g. `slides` for screen content.
+    pub fn content(&self) -> Option<String> {
+        self.content.lock().clone()
+    }
+
+    /// set_content sets the `a=content` label of the media, or removes it with
+    /// `None`. The label is kept across renegotiations. This might trigger a
+    /// renegotiation.
+    pub async fn set_content(&self, content: Option<String>) {
+        let changed = {
+            let mut current = self.content.lock();
+            let changed = *current != content;
+            *current = content;
+            changed
+        };
+
+        if changed {
+            let lock = self.trigger_negotiation_needed.lock().await;
+            if let Some(trigger) = &*lock {
+                (trigger)().await;
+            }
+        }
+    }
+
+    /// remote_content returns the `a=content` label of the media section in
+    /// the last applied remote description.
+    pub fn remote_content(&self) -> Option<String> {
+        self.remote_content.lock().clone()
+    }
+
+    pub(crate) fn set_remote_content(&self, content: Option<String>) {
+        *self.remote_content.lock() = content;
+    }
+
+    /// negotiated_header_extensions returns the id and URI of the RTP header extensions
+    /// negotiated for the media section of this transceiver in the last applied remote
+    /// description, sorted by id. It is empty until a remote description is applied.
+    pub fn negotiated_header_extensions(&self) -> Vec<(u8, String)> {
+        self.negotiated_header_extensions.lock().clone()
+    }
+
+    pub(crate) fn set_negotiated_header_extensions(&self, header_extensions: Vec<(u8, String)>) {
+        *self.negotiated_header_extensions.lock() = header_extensions;
+    }
+
+    /// receive_constraints returns the resolution and frame rate caps this transceiver
+    /// signals for the video it receives.
+    pub fn receive_constraints(&self) -> Option<RTCRtpVideoConstraints> {
+        *self.receive_constraints.lock()
+    }
+
+    /// set_receive_constraints sets the caps on the video the remote peer sends, or removes
+    /// them with `None`. They are signaled with `a=imageattr` and `a=framerate` on video
+    /// media sections that receive. This might trigger a renegotiation.
+    pub async fn set_receive_constraints(&self, constraints: Option<RTCRtpVideoConstraints>) {
+        let changed = {
+            let mut current = self.receive_constraints.lock();
+            let changed = *current != constraints;
+            *current = constraints;
+            changed
+        };
+
+        if changed {
+            let lock = self.trigger_negotiation_needed.lock().await;
+            if let Some(trigger) = &*lock {
+                (trigger)().await;
+            }
+        }
+    }
+
+    /// remote_receive_constraints returns the caps on the video sent, as signaled by the
+    /// media section in the last applied remote description.
+    pub fn remote_receive_constraints(&self) -> Option<RTCRtpVideoConstraints> {
+        *self.remote_receive_constraints.lock()
+    }
+
+    pub(crate) fn set_remote_receive_constraints(
+        &self,
+        constraints: Option<RTCRtpVideoConstraints>,
+    ) {
+        *self.remote_receive_constraints.lock() = constraints;
+    }
+
     /// current_direction returns the RTPTransceiver's current direction as negotiated.
     ///
     /// If this transceiver has never been negotiated or if it's stopped this returns [`RTCRtpTransceiverDirection::Unspecified`].
@@ -403,9 +529,9 @@ impl RTCRtpTransceiver {
             let pause_receiver = !current_direction.has_recv();
 
             if pause_receiver {
-                receiver.pause().await?;
+                receiver.mute().await?;
             } else {
-                receiver.resume().await?;
+                receiver.unmute().await?;
             }
         }
 
@@ -465,7 +591,7 @@ impl RTCRtpTransceiver {
 impl fmt::Debug for RTCRtpTransceiver {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RTCRtpTransceiver")
-            .field("mid", &self.mid)
+            .field("mid", &self.mid())
             .field("sender", &self.sender)
             .field("receiver", &self.receiver)
             .field("direction", &self.direction)
@@ -80,7 +80,11 @@ impl RTCRtpCodecCapability {
         } else if mime_type == MIME_TYPE_VP9.to_lowercase() {
             Ok(Box::<rtp::codecs::vp9::Vp9Payloader>::default())
         } else if mime_type == MIME_TYPE_OPUS.to_lowercase() {
-            Ok(Box::<rtp::codecs::opus::OpusPayloader>::default())
+            // usedtx is what the remote prefers to receive, the codec negotiated carries its fmtp
+            let enable_dtx = fmtp::parse(&self.mime_type, &self.sdp_fmtp_line)
+                .parameter("usedtx")
+                .is_some_and(|v| v == "1");
+            Ok(Box::new(rtp::codecs::opus::OpusPayloader::new(enable_dtx)))
         } else if mime_type == MIME_TYPE_G722.to_lowercase()
             || mime_type == MIME_TYPE_PCMU.to_lowercase()
             || mime_type == MIME_TYPE_PCMA.to_lowercase()
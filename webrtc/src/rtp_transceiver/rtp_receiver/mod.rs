@@ -1,15 +1,20 @@
 #[cfg(test)]
 mod rtp_receiver_test;
 
+pub mod rtp_source;
+
 use std::fmt;
 use std::sync::Arc;
+use std::time::SystemTime;
 
 use arc_swap::ArcSwapOption;
 use interceptor::stream_info::{AssociatedStreamInfo, RTPHeaderExtension};
-use interceptor::{Attributes, Interceptor};
+use interceptor::{Attributes, Interceptor, RTCPReader};
 use log::trace;
+use rtp_source::{RTCRtpContributingSource, RTCRtpSynchronizationSource, RtpSources};
 use smol_str::SmolStr;
 use tokio::sync::{watch, Mutex, RwLock};
+use util::sync::Mutex as SyncMutex;
 
 use crate::api::media_engine::MediaEngine;
 use crate::dtls_transport::RTCDtlsTransport;
@@ -151,10 +156,16 @@ pub struct RTPReceiverInternal {
     state_tx: watch::Sender<State>,
     state_rx: watch::Receiver<State>,
 
+    // Set by the application through RTCRtpReceiver::pause, independent of the direction
+    app_paused_tx: watch::Sender<bool>,
+
     tracks: RwLock<Vec<TrackStreams>>,
 
     transceiver_codecs: ArcSwapOption<Mutex<Vec<RTCRtpCodecParameters>>>,
 
+    // The sources of the packets the tracks delivered recently
+    sources: SyncMutex<RtpSources>,
+
     transport: Arc<RTCDtlsTransport>,
     media_engine: Arc<MediaEngine>,
     interceptor: Arc<dyn Interceptor + Send + Sync>,
@@ -171,25 +182,65 @@ impl RTPReceiverInternal {
         // isn't flowing.
         State::wait_for(&mut state_watch_rx, &[State::Started, State::Paused]).await?;
 
+        // Don't hold the tracks lock while blocked on RTCP, it would stall RTP reads and
+        // simulcast streams being added until the next RTCP packet arrives.
+        let rtcp_interceptor = {
+            let tracks = self.tracks.read().await;
+            match tracks.first() {
+                Some(t) => t.stream.rtcp_interceptor.clone(),
+                None => return Err(Error::ErrExistingTrack),
+            }
+        };
+
+        if let Some(rtcp_interceptor) = rtcp_interceptor {
+            let result =
+                Self::read_rtcp_interceptor(&rtcp_interceptor, b, &mut state_watch_rx).await;
+            if let Ok((pkts, _)) = &result {
+                self.process_rtcp(pkts).await;
+            }
+            result
+        } else {
+            Err(Error::ErrInterceptorNotBind)
+        }
+    }
+
+    /// process_rtcp keeps the Sender Reports and the BYEs read on the tracks they describe
+    async fn process_rtcp(&self, pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>]) {
         let tracks = self.tracks.read().await;
-        if let Some(t) = tracks.first() {
-            if let Some(rtcp_interceptor) = &t.stream.rtcp_interceptor {
-                let a = Attributes::new();
-                loop {
-                    tokio::select! {
-                        res = State::error_on_close(&mut state_watch_rx) => {
-                            res?
-                        }
-                        result = rtcp_interceptor.read(b, &a) => {
-                            return Ok(result?)
-                        }
-                    }
+        for p in pkts {
+            if let Some(sr) = p
+                .as_any()
+                .downcast_ref::<rtcp::sender_report::SenderReport>()
+            {
+                if let Some(t) = tracks.iter().find(|t| t.track.ssrc() == sr.ssrc) {
+                    t.track.set_last_sender_report(sr);
+                }
+            } else if let Some(bye) = p.as_any().downcast_ref::<rtcp::goodbye::Goodbye>() {
+                for t in tracks
+                    .iter()
+                    .filter(|t| bye.sources.contains(&t.track.ssrc()))
+                {
+                    t.track.set_goodbye_reason(&bye.reason);
+                }
+            }
+        }
+    }
+
+    async fn read_rtcp_interceptor(
+        rtcp_interceptor: &Arc<dyn RTCPReader + Send + Sync>,
+        b: &mut [u8],
+        state_watch_rx: &mut watch::Receiver<State>,
+    ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
+        let a = Attributes::new();
+        loop {
+            tokio::select! {
+                res = State::error_on_close(state_watch_rx) => {
+                    res?
+                }
+                result = rtcp_interceptor.read(b, &a) => {
+                    return Ok(result?)
                 }
-            } else {
-                Err(Error::ErrInterceptorNotBind)
             }
-        } else {
-            Err(Error::ErrExistingTrack)
         }
     }
 
@@ -205,28 +256,24 @@ impl RTPReceiverInternal {
         // isn't flowing.
         State::wait_for(&mut state_watch_rx, &[State::Started, State::Paused]).await?;
 
-        let tracks = self.tracks.read().await;
-        for t in &*tracks {
-            if t.track.rid() == rid {
-                if let Some(rtcp_interceptor) = &t.stream.rtcp_interceptor {
-                    let a = Attributes::new();
+        let rtcp_interceptor = {
+            let tracks = self.tracks.read().await;
+            match tracks.iter().find(|t| t.track.rid() == rid) {
+                Some(t) => t.stream.rtcp_interceptor.clone(),
+                None => return Err(Error::ErrRTPReceiverForRIDTrackStreamNotFound),
+            }
+        };
 
-                    loop {
-                        tokio::select! {
-                            res = State::error_on_close(&mut state_watch_rx) => {
-                                res?
-                            }
-                            result = rtcp_interceptor.read(b, &a) => {
-                                return Ok(result?);
-                            }
-                        }
-                    }
-                } else {
-                    return Err(Error::ErrInterceptorNotBind);
-                }
+        if let Some(rtcp_interceptor) = rtcp_interceptor {
+            let result =
+                Self::read_rtcp_interceptor(&rtcp_interceptor, b, &mut state_watch_rx).await;
+            if let Ok((pkts, _)) = &result {
+                self.process_rtcp(pkts).await;
             }
+            result
+        } else {
+            Err(Error::ErrInterceptorNotBind)
         }
-        Err(Error::ErrRTPReceiverForRIDTrackStreamNotFound)
     }
 
     /// read_rtcp is a convenience method that wraps Read and unmarshal for you.
@@ -265,12 +312,14 @@ impl RTPReceiverInternal {
 
         //log::debug!("read_rtp enter tracks tid {}", tid);
         let mut rtp_interceptor = None;
+        let mut rtp_read_stream = None;
         //let mut ssrc = 0;
         {
             let tracks = self.tracks.read().await;
             for t in &*tracks {
                 if t.track.tid() == tid {
                     rtp_interceptor.clone_from(&t.stream.rtp_interceptor);
+                    rtp_read_stream.clone_from(&t.stream.rtp_read_stream);
                     //ssrc = t.track.ssrc();
                     break;
                 }
@@ -288,8 +337,23 @@ impl RTPReceiverInternal {
             //    "read_rtp rtp_interceptor.read enter with tid {} ssrc {}",
             //    tid, ssrc
             //);
+            let mut app_paused_rx = self.app_paused_tx.subscribe();
             let mut current_state = *state_watch_rx.borrow();
             loop {
+                let app_paused = *app_paused_rx.borrow_and_update();
+                // While paused by the application, packets are drained straight from the SRTP
+                // stream so that neither the interceptors nor the track see them.
+                let read = async {
+                    match &rtp_read_stream {
+                        Some(rtp_read_stream) if app_paused => {
+                            let n = rtp_read_stream.read(b).await?;
+                            trace!("Dropping {} read bytes received while RTPReceiver was paused by the application", n);
+                            Ok(None)
+                        }
+                        _ => Ok(Some(rtp_interceptor.read(b, &a).await?)),
+                    }
+                };
+
                 tokio::select! {
                     _ = state_watch_rx.changed() => {
                         let new_state = *state_watch_rx.borrow();
@@ -299,14 +363,16 @@ impl RTPReceiverInternal {
                         }
                         current_state = new_state;
                     }
-                    result = rtp_interceptor.read(b, &a) => {
-                        let result = result?;
-
-                        if current_state == State::Paused {
-                            trace!("Dropping {} read bytes received while RTPReceiver was paused", result.0);
-                            continue;
+                    _ = app_paused_rx.changed() => {}
+                    result = read => {
+                        let result: Result<Option<_>> = result;
+                        match result? {
+                            Some(result) if current_state == State::Paused => {
+                                trace!("Dropping {} read bytes received while RTPReceiver was paused", result.0);
+                            }
+                            Some(result) => return Ok(result),
+                            None => {}
                         }
-                        return Ok(result);
                     }
                 }
             }
@@ -361,6 +427,12 @@ impl RTPReceiverInternal {
         *self.state_rx.borrow()
     }
 
+    /// record_sources accounts the sources of a packet a track delivered,
+    /// level is the audio level of its ssrc-audio-level header extension
+    pub(crate) fn record_sources(&self, header: &rtp::header::Header, level: Option<u8>) {
+        self.sources.lock().record(SystemTime::now(), header, level);
+    }
+
     pub(crate) fn start(&self) -> Result<()> {
         State::transition(State::Started, &self.state_tx)
     }
@@ -388,6 +460,14 @@ impl RTPReceiverInternal {
     pub(crate) fn close(&self) -> Result<()> {
         State::transition(State::Stopped, &self.state_tx)
     }
+
+    fn set_app_paused(&self, paused: bool) {
+        self.app_paused_tx.send_replace(paused);
+    }
+
+    fn is_app_paused(&self) -> bool {
+        *self.app_paused_tx.borrow()
+    }
 }
 
 /// RTPReceiver allows an application to inspect the receipt of a TrackRemote
@@ -422,6 +502,7 @@ impl RTCRtpReceiver {
         interceptor: Arc<dyn Interceptor + Send + Sync>,
     ) -> Self {
         let (state_tx, state_rx) = watch::channel(State::Unstarted);
+        let (app_paused_tx, _) = watch::channel(false);
 
         RTCRtpReceiver {
             receive_mtu,
@@ -437,7 +518,11 @@ impl RTCRtpReceiver {
                 state_tx,
                 state_rx,
 
+                app_paused_tx,
+
                 transceiver_codecs: ArcSwapOption::new(None),
+
+                sources: SyncMutex::new(RtpSources::default()),
             }),
         }
     }
@@ -493,6 +578,26 @@ impl RTCRtpReceiver {
         }
     }
 
+    /// get_contributing_sources returns the CSRCs of the packets the tracks of
+    /// the receiver delivered in the last 10 seconds, most recent first.
+    pub fn get_contributing_sources(&self) -> Vec<RTCRtpContributingSource> {
+        self.internal
+            .sources
+            .lock()
+            .contributing_sources(SystemTime::now())
+    }
+
+    /// get_synchronization_sources returns the SSRCs of the packets the tracks
+    /// of the receiver delivered in the last 10 seconds, most recent first,
+    /// with their audio level if the ssrc-audio-level header extension was
+    /// negotiated.
+    pub fn get_synchronization_sources(&self) -> Vec<RTCRtpSynchronizationSource> {
+        self.internal
+            .sources
+            .lock()
+            .synchronization_sources(SystemTime::now())
+    }
+
     /// tracks returns the RtpTransceiver traclockks
     /// A RTPReceiver to support Simulcast may now have multiple tracks
     pub async fn tracks(&self) -> Vec<Arc<TrackRemote>> {
@@ -701,6 +806,23 @@ impl RTCRtpReceiver {
         }
     }
 
+    /// pause stops delivering incoming RTP to the tracks and interceptors without renegotiating.
+    /// Packets received while paused are read and discarded, so resuming doesn't replay a
+    /// backlog of stale media. RTCP is still received.
+    pub fn pause(&self) {
+        self.internal.set_app_paused(true);
+    }
+
+    /// resume restarts delivering incoming RTP after [`RTCRtpReceiver::pause`].
+    pub fn resume(&self) {
+        self.internal.set_app_paused(false);
+    }
+
+    /// is_paused returns whether the receiver was paused with [`RTCRtpReceiver::pause`].
+    pub fn is_paused(&self) -> bool {
+        self.internal.is_app_paused()
+    }
+
     /// Stop irreversibly stops the RTPReceiver
     pub async fn stop(&self) -> Result<()> {
         let previous_state = self.internal.current_state();
@@ -789,6 +911,57 @@ impl RTCRtpReceiver {
         Err(Error::ErrRTPReceiverForRIDTrackStreamNotFound)
     }
 
+    /// receive_for_ssrc is the sibling of Receive for a stream whose SSRC was only learned from its
+    /// packets, it starts the receiver with a single track bound to the already opened stream.
+    pub(crate) async fn receive_for_ssrc(
+        &self,
+        incoming: &TrackDetails,
+        params: RTCRtpParameters,
+        stream: TrackStream,
+    ) -> Result<Arc<TrackRemote>> {
+        if self.internal.current_state().is_started() {
+            return Err(Error::ErrRTPReceiverReceiveAlreadyCalled);
+        }
+        self.internal.start()?;
+
+        let track = Arc::new(TrackRemote::new(
+            self.receive_mtu,
+            self.internal.kind,
+            stream.stream_info.as_ref().map_or(0, |s| s.ssrc),
+            SmolStr::default(),
+            Arc::downgrade(&self.internal),
+            Arc::clone(&self.internal.media_engine),
+            Arc::clone(&self.internal.interceptor),
+        ));
+        if let Some(codec) = params.codecs.first() {
+            track.set_codec(codec.clone());
+        }
+        track.set_params(params);
+        track.set_id(incoming.id.clone());
+        track.set_stream_id(incoming.stream_id.clone());
+
+        {
+            let mut tracks = self.internal.tracks.write().await;
+            tracks.push(TrackStreams {
+                track: Arc::clone(&track),
+                stream,
+                repair_stream: TrackStream {
+                    stream_info: None,
+                    rtp_read_stream: None,
+                    rtp_interceptor: None,
+                    rtcp_read_stream: None,
+                    rtcp_interceptor: None,
+                },
+            });
+        }
+
+        if self.current_state() == State::Started {
+            track.fire_onunmute().await;
+        }
+
+        Ok(track)
+    }
+
     /// receiveForRtx starts a routine that processes the repair stream
     /// These packets aren't exposed to the user yet, but we need to process them for
     /// TWCC
@@ -806,7 +979,8 @@ impl RTCRtpReceiver {
 
                 let receive_mtu = self.receive_mtu;
                 let track = t.clone();
-                tokio::spawn(async move {
+                let runtime = self.internal.transport.setting_engine.runtime.clone();
+                crate::spawn(runtime.as_ref(), async move {
                     let a = Attributes::new();
                     let mut b = vec![0u8; receive_mtu];
                     while let Some(repair_rtp_interceptor) = &track.repair_stream.rtp_interceptor {
@@ -831,7 +1005,7 @@ impl RTCRtpReceiver {
         self.internal.current_state()
     }
 
-    pub(crate) async fn pause(&self) -> Result<()> {
+    pub(crate) async fn mute(&self) -> Result<()> {
         self.internal.pause()?;
 
         if !self.internal.current_state().is_started() {
@@ -849,7 +1023,7 @@ impl RTCRtpReceiver {
         Ok(())
     }
 
-    pub(crate) async fn resume(&self) -> Result<()> {
+    pub(crate) async fn unmute(&self) -> Result<()> {
         self.internal.resume()?;
 
         if !self.internal.current_state().is_started() {
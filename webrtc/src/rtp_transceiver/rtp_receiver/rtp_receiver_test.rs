@@ -1,20 +1,27 @@
+use std::sync::atomic::{AtomicU8, Ordering};
+
 use bytes::Bytes;
 use media::Sample;
+use rtcp::sender_report::SenderReport;
 use tokio::sync::mpsc;
 use tokio::time::Duration;
 use waitgroup::WaitGroup;
 
 use super::*;
 use crate::api::media_engine::{MIME_TYPE_OPUS, MIME_TYPE_VP8};
+use crate::api::APIBuilder;
 use crate::error::Result;
 use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
 use crate::peer_connection::peer_connection_test::{
-    close_pair_now, create_vnet_pair, signal_pair, until_connection_state,
+    close_pair_now, create_vnet_pair, new_pair, send_video_until_done, signal_pair,
+    until_connection_state,
 };
 use crate::rtp_transceiver::rtp_codec::RTCRtpHeaderExtensionParameters;
 use crate::rtp_transceiver::{RTCPFeedback, RTCRtpCodecCapability};
+use crate::track::track_local::track_local_static_rtp::TrackLocalStaticRTP;
 use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
-use crate::track::track_local::TrackLocal;
+use crate::track::track_local::{TrackLocal, TrackLocalWriter};
+use crate::track::track_remote::SenderReportInfo;
 
 lazy_static! {
     static ref P: RTCRtpParameters = RTCRtpParameters {
@@ -231,3 +238,360 @@ async fn test_rtp_receiver_set_read_deadline() -> Result<()> {
 
     Ok(())
 }
+
+// This test uses VNet since we must have zero loss
+#[tokio::test]
+async fn test_rtp_receiver_pause_resume() -> Result<()> {
+    let (mut sender, mut receiver, wan) = create_vnet_pair().await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    // Number of samples written so far, the last payload byte carries the sample index
+    let sent = Arc::new(AtomicU8::new(0));
+    let (done_tx, mut done_rx) = mpsc::channel::<()>(1);
+    let sent2 = Arc::clone(&sent);
+    receiver.on_track(Box::new(move |track, receiver, _| {
+        let done_tx = done_tx.clone();
+        let sent = Arc::clone(&sent2);
+        Box::pin(async move {
+            assert!(track.read_rtp().await.is_ok());
+
+            receiver.pause();
+            assert!(receiver.is_paused());
+            let result = tokio::time::timeout(Duration::from_millis(500), track.read_rtp()).await;
+            assert!(result.is_err(), "no RTP must be delivered while paused");
+
+            let resumed_at = Arc::new(AtomicU8::new(0));
+            let resumed_at2 = Arc::clone(&resumed_at);
+            let receiver2 = Arc::clone(&receiver);
+            tokio::spawn(async move {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                resumed_at2.store(sent.load(Ordering::SeqCst), Ordering::SeqCst);
+                receiver2.resume();
+            });
+
+            let (pkt, _) = tokio::time::timeout(Duration::from_secs(2), track.read_rtp())
+                .await
+                .expect("RTP must be delivered after resume")
+                .unwrap();
+            assert!(!receiver.is_paused());
+
+            // Packets that arrived while paused were discarded rather than replayed
+            let index = *pkt.payload.last().unwrap();
+            assert!(u16::from(index) + 5 >= u16::from(resumed_at.load(Ordering::SeqCst)));
+
+            let _ = done_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    let mut ticker = tokio::time::interval(Duration::from_millis(20));
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                let index = sent.fetch_add(1, Ordering::SeqCst);
+                track
+                    .write_sample(&Sample {
+                        data: Bytes::copy_from_slice(&[index]),
+                        duration: Duration::from_millis(20),
+                        ..Default::default()
+                    })
+                    .await?;
+            }
+            _ = done_rx.recv() => break,
+        }
+    }
+
+    {
+        let mut w = wan.lock().await;
+        w.stop().await?;
+    }
+    close_pair_now(&sender, &receiver).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_receiver_read_rtp_and_rtcp_concurrently() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    // No interceptors, so no RTCP arrives unless the test sends it
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (result_tx, mut result_rx) = mpsc::channel(1);
+    let (rtcp_tx, mut rtcp_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, receiver, _| {
+        let result_tx = result_tx.clone();
+        let rtcp_tx = rtcp_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            let rtcp_receiver = Arc::clone(&receiver);
+            tokio::spawn(async move {
+                let _ = rtcp_tx.send(rtcp_receiver.read_rtcp().await.is_ok()).await;
+            });
+            tokio::time::sleep(Duration::from_millis(50)).await;
+
+            // RTP keeps flowing while the RTCP reader is blocked
+            let mut rtp_ok = true;
+            for _ in 0..10 {
+                let result = tokio::time::timeout(Duration::from_secs(1), track.read_rtp()).await;
+                rtp_ok &= matches!(result, Ok(Ok(_)));
+            }
+
+            // Adding streams to the receiver must not wait on the pending RTCP read either
+            let tracks_ok =
+                tokio::time::timeout(Duration::from_secs(1), receiver.internal.tracks.write())
+                    .await
+                    .is_ok();
+
+            let _ = result_tx.send((track.ssrc(), rtp_ok, tracks_ok)).await;
+            let _ = seen_packet_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+
+    let (ssrc, rtp_ok, tracks_ok) = result_rx.recv().await.unwrap();
+    assert!(rtp_ok, "RTP read blocked by RTCP read");
+    assert!(tracks_ok, "tracks locked by RTCP read");
+
+    // RTCP is delivered while nobody reads RTP
+    sender
+        .write_rtcp(&[Box::new(SenderReport {
+            ssrc,
+            ..Default::default()
+        })])
+        .await?;
+    let rtcp = tokio::time::timeout(Duration::from_secs(5), rtcp_rx.recv()).await;
+    assert_eq!(rtcp, Ok(Some(true)), "RTCP read blocked");
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_track_remote_last_sender_report() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    // No interceptors, so no RTCP arrives unless the test sends it
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (track_tx, mut track_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, receiver, _| {
+        let track_tx = track_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            let _ = track_tx.send((track, receiver)).await;
+            let _ = seen_packet_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+
+    let (remote_track, rtp_receiver) = track_rx.recv().await.unwrap();
+    assert_eq!(remote_track.last_sender_report(), None);
+
+    let sr = SenderReport {
+        ssrc: remote_track.ssrc(),
+        ntp_time: 0xe8a1_1234_8000_0000,
+        rtp_time: 90000,
+        packet_count: 42,
+        octet_count: 4200,
+        ..Default::default()
+    };
+    sender.write_rtcp(&[Box::new(sr.clone())]).await?;
+
+    let (pkts, _) = tokio::time::timeout(Duration::from_secs(5), rtp_receiver.read_rtcp())
+        .await
+        .expect("RTCP not received")?;
+    assert_eq!(pkts.len(), 1);
+    assert_eq!(
+        remote_track.last_sender_report(),
+        Some(SenderReportInfo {
+            ntp_time: sr.ntp_time,
+            rtp_time: sr.rtp_time,
+            packet_count: sr.packet_count,
+            octet_count: sr.octet_count,
+        })
+    );
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[test]
+fn test_rtp_sources() {
+    let mut sources = rtp_source::RtpSources::default();
+    let start = SystemTime::now();
+
+    let header = rtp::header::Header {
+        ssrc: 1,
+        csrc: vec![2, 3],
+        timestamp: 960,
+        ..Default::default()
+    };
+    sources.record(start, &header, Some(20));
+
+    let ssrcs = sources.synchronization_sources(start);
+    assert_eq!(ssrcs.len(), 1);
+    assert_eq!(ssrcs[0].source, 1);
+    assert_eq!(ssrcs[0].rtp_timestamp, 960);
+    assert!((ssrcs[0].audio_level.unwrap() - 0.1).abs() < 1e-9);
+
+    let mut csrcs: Vec<_> = sources
+        .contributing_sources(start)
+        .iter()
+        .map(|s| (s.source, s.audio_level))
+        .collect();
+    csrcs.sort_by_key(|(source, _)| *source);
+    assert_eq!(csrcs, vec![(2, None), (3, None)]);
+
+    // the most recent source comes first, silence is reported as 0
+    let later = start + Duration::from_secs(5);
+    let header = rtp::header::Header {
+        ssrc: 4,
+        ..Default::default()
+    };
+    sources.record(later, &header, Some(127));
+    let ssrcs = sources.synchronization_sources(later);
+    assert_eq!(
+        ssrcs.iter().map(|s| s.source).collect::<Vec<_>>(),
+        vec![4, 1]
+    );
+    assert_eq!(ssrcs[0].audio_level, Some(0.0));
+
+    // sources are forgotten 10 seconds after their last packet
+    let expired = start + Duration::from_secs(11);
+    let ssrcs = sources.synchronization_sources(expired);
+    assert_eq!(ssrcs.iter().map(|s| s.source).collect::<Vec<_>>(), vec![4]);
+    assert!(sources.contributing_sources(expired).is_empty());
+}
+
+#[tokio::test]
+async fn test_rtp_receiver_sources() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (seen_tx, mut seen_rx) = mpsc::channel::<Vec<u32>>(1);
+    receiver.on_track(Box::new(move |track, receiver, _| {
+        let seen_tx = seen_tx.clone();
+        Box::pin(async move {
+            assert!(receiver.get_synchronization_sources().is_empty());
+            let (pkt, _) = track.read_rtp().await.unwrap();
+
+            let ssrcs = receiver.get_synchronization_sources();
+            assert_eq!(ssrcs.len(), 1);
+            assert_eq!(ssrcs[0].source, pkt.header.ssrc);
+            assert_eq!(ssrcs[0].rtp_timestamp, pkt.header.timestamp);
+            assert_eq!(ssrcs[0].audio_level, None);
+
+            let mut csrcs: Vec<_> = receiver
+                .get_contributing_sources()
+                .iter()
+                .map(|s| s.source)
+                .collect();
+            csrcs.sort_unstable();
+            let _ = seen_tx.send(csrcs).await;
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    let csrcs = loop {
+        let pkt = rtp::packet::Packet {
+            header: rtp::header::Header {
+                version: 2,
+                csrc: vec![7, 8],
+                timestamp: 90000,
+                ..Default::default()
+            },
+            payload: Bytes::from_static(&[0xAA]),
+        };
+        track.write_rtp(&pkt).await?;
+
+        tokio::select! {
+            csrcs = seen_rx.recv() => break csrcs.unwrap(),
+            _ = tokio::time::sleep(Duration::from_millis(20)) => {}
+        }
+    };
+    assert_eq!(csrcs, vec![7, 8]);
+
+    close_pair_now(&sender, &receiver).await;
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+/// How long a source is reported after the last packet it contributed to
+const RTP_SOURCE_LIFETIME: Duration = Duration::from_secs(10);
+
+/// RTCRtpContributingSource describes a source that contributed to the
+/// packets an RTCRtpReceiver delivered recently.
+///
+/// ## Specifications
+///
+/// * [W3C]
+///
+/// [W3C]: https://w3c.github.io/webrtc-pc/#dom-rtcrtpcontributingsource
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RTCRtpContributingSource {
+    /// timestamp is the time the most recent packet from the source was delivered
+    pub timestamp: SystemTime,
+    /// source is the CSRC or SSRC of the source
+    pub source: u32,
+    /// audio_level is the level of the most recent packet from the source,
+    /// between 0.0 (silence) and 1.0 (0 dBov), if the remote reported it
+    pub audio_level: Option<f64>,
+    /// rtp_timestamp is the RTP timestamp of the most recent packet from the source
+    pub rtp_timestamp: u32,
+}
+
+/// RTCRtpSynchronizationSource describes a synchronization source of the
+/// packets an RTCRtpReceiver delivered recently. The audio level of an SSRC
+/// is the one of the ssrc-audio-level header extension (RFC 6464).
+///
+/// ## Specifications
+///
+/// * [W3C]
+///
+/// [W3C]: https://w3c.github.io/webrtc-pc/#dom-rtcrtpsynchronizationsource
+pub type RTCRtpSynchronizationSource = RTCRtpContributingSource;
+
+/// RtpSources keeps the sources of the recently delivered packets
+#[derive(Debug, Default)]
+pub(crate) struct RtpSources {
+    synchronization_sources: HashMap<u32, RTCRtpSynchronizationSource>,
+    contributing_sources: HashMap<u32, RTCRtpContributingSource>,
+}
+
+impl RtpSources {
+    /// record updates the sources of a packet delivered at now, level is
+    /// the -dBov audio level of its ssrc-audio-level header extension.
+    pub(crate) fn record(
+        &mut self,
+        now: SystemTime,
+        header: &rtp::header::Header,
+        level: Option<u8>,
+    ) {
+        self.expire(now);
+
+        let source = RTCRtpContributingSource {
+            timestamp: now,
+            source: header.ssrc,
+            audio_level: level.map(audio_level_from_dbov),
+            rtp_timestamp: header.timestamp,
+        };
+        self.synchronization_sources.insert(header.ssrc, source);
+        for &csrc in &header.csrc {
+            self.contributing_sources.insert(
+                csrc,
+                RTCRtpContributingSource {
+                    source: csrc,
+                    audio_level: None,
+                    ..source
+                },
+            );
+        }
+    }
+
+    pub(crate) fn synchronization_sources(
+        &self,
+        now: SystemTime,
+    ) -> Vec<RTCRtpSynchronizationSource> {
+        recent(&self.synchronization_sources, now)
+    }
+
+    pub(crate) fn contributing_sources(&self, now: SystemTime) -> Vec<RTCRtpContributingSource> {
+        recent(&self.contributing_sources, now)
+    }
+
+    fn expire(&mut self, now: SystemTime) {
+        let live = |source: &RTCRtpContributingSource| is_recent(source, now);
+        self.synchronization_sources
+            .retain(|_, source| live(source));
+        self.contributing_sources.retain(|_, source| live(source));
+    }
+}
+
+fn is_recent(source: &RTCRtpContributingSource, now: SystemTime) -> bool {
+    now.duration_since(source.timestamp)
+        .map_or(true, |age| age <= RTP_SOURCE_LIFETIME)
+}
+
+/// recent returns the sources of the last 10 seconds, most recent first
+fn recent(
+    sources: &HashMap<u32, RTCRtpContributingSource>,
+    now: SystemTime,
+) -> Vec<RTCRtpContributingSource> {
+    let mut recent: Vec<_> = sources
+        .values()
+        .filter(|source| is_recent(source, now))
+        .copied()
+        .collect();
+    recent.sort_by_key(|source| std::cmp::Reverse(source.timestamp));
+    recent
+}
+
+/// audio_level_from_dbov converts the 0-127 -dBov level of RFC 6464 to the
+/// linear 0.0-1.0 scale of W3C, 127 being silence.
+fn audio_level_from_dbov(level: u8) -> f64 {
+    if level >= 127 {
+        0.0
+    } else {
+        10f64.powf(-(level as f64) / 20.0)
+    }
+}
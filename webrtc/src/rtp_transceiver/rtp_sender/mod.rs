@@ -1,15 +1,25 @@
 #[cfg(test)]
 mod rtp_sender_test;
 
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Weak};
 
+use arc_swap::ArcSwapOption;
+use bytes::Bytes;
 use ice::rand::generate_crypto_random_string;
 use interceptor::stream_info::{AssociatedStreamInfo, StreamInfo};
 use interceptor::{Attributes, Interceptor, RTCPReader, RTPWriter};
 use portable_atomic::AtomicBool;
+use rtcp::goodbye::Goodbye;
+use rtcp::payload_feedbacks::full_intra_request::FullIntraRequest;
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
+use rtcp::receiver_report::ReceiverReport;
+use rtcp::sender_report::SenderReport;
+use rtp::extension::playout_delay_extension::{PlayoutDelayExtension, PLAYOUT_DELAY_MAX_VALUE};
 use tokio::select;
-use tokio::sync::{watch, Mutex, Notify};
+use tokio::sync::{mpsc, watch, Mutex, Notify};
 use util::sync::Mutex as SyncMutex;
 
 use super::srtp_writer_future::SequenceTransformer;
@@ -27,6 +37,27 @@ use crate::rtp_transceiver::{
 };
 use crate::track::track_local::{InterceptorToTrackLocalWriter, TrackLocal, TrackLocalContext};
 
+/// KeyframeRequest is the kind of RTCP feedback a remote used to ask for a new keyframe.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum KeyframeRequest {
+    /// Picture Loss Indication, see RFC 4585 section 6.3.1.
+    PictureLoss,
+    /// Full Intra Request, see RFC 5104 section 3.5.1.
+    FullIntra,
+}
+
+pub type OnKeyframeRequestHdlrFn = Box<
+    dyn (FnMut(KeyframeRequest, SSRC) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
+        + Send
+        + Sync,
+>;
+
+/// RTCP_READ_QUEUE_SIZE is how many incoming RTCP reads of an encoding are kept until the
+/// application reads them, the oldest ones are dropped while the queue is full.
+const RTCP_READ_QUEUE_SIZE: usize = 64;
+
+type RTCPReadResult = Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)>;
+
 pub(crate) struct RTPSenderInternal {
     pub(crate) stop_called_rx: Arc<Notify>,
     pub(crate) stop_called_signal: Arc<AtomicBool>,
@@ -39,6 +70,10 @@ pub(crate) struct TrackEncoding {
     pub(crate) stream_info: StreamInfo,
     pub(crate) context: TrackLocalContext,
 
+    /// the incoming RTCP, read from rtcp_interceptor once the encoding is sent
+    rtcp_rx: Arc<Mutex<mpsc::Receiver<RTCPReadResult>>>,
+    rtcp_tx: Option<mpsc::Sender<RTCPReadResult>>,
+
     pub(crate) ssrc: SSRC,
 
     pub(crate) rtx: Option<RtxEncoding>,
@@ -62,7 +97,7 @@ pub(crate) struct RtxEncoding {
 /// [MDN]: https://developer.mozilla.org/en-US/docs/Web/API/RTCRtpSender
 /// [W3C]: https://w3c.github.io/webrtc-pc/#rtcrtpsender-interface
 pub struct RTCRtpSender {
-    pub(crate) track_encodings: Mutex<Vec<TrackEncoding>>,
+    pub(crate) track_encodings: Arc<Mutex<Vec<TrackEncoding>>>,
 
     seq_trans: Arc<SequenceTransformer>,
     rtx_seq_trans: Arc<SequenceTransformer>,
@@ -96,6 +131,10 @@ pub struct RTCRtpSender {
     stop_called_signal: Arc<AtomicBool>,
 
     pub(crate) paused: Arc<AtomicBool>,
+    direction_paused: AtomicBool,
+    app_paused: AtomicBool,
+    playout_delay: Arc<SyncMutex<Option<PlayoutDelayExtension>>>,
+    on_keyframe_request_handler: Arc<ArcSwapOption<Mutex<OnKeyframeRequestHdlrFn>>>,
 
     internal: Arc<RTPSenderInternal>,
 }
@@ -140,7 +179,7 @@ impl RTCRtpSender {
             .map(|track| vec![track.stream_id().to_string()])
             .unwrap_or_default();
         let ret = Self {
-            track_encodings: Mutex::new(vec![]),
+            track_encodings: Arc::new(Mutex::new(vec![])),
 
             seq_trans,
             rtx_seq_trans,
@@ -168,6 +207,10 @@ impl RTCRtpSender {
             stop_called_signal,
 
             paused: Arc::new(AtomicBool::new(start_paused)),
+            direction_paused: AtomicBool::new(start_paused),
+            app_paused: AtomicBool::new(false),
+            playout_delay: Arc::new(SyncMutex::new(None)),
+            on_keyframe_request_handler: Arc::new(ArcSwapOption::empty()),
 
             internal,
         };
@@ -224,7 +267,7 @@ impl RTCRtpSender {
         track_encodings: &mut Vec<TrackEncoding>,
         track: Arc<dyn TrackLocal + Send + Sync>,
     ) -> Result<()> {
-        let ssrc = rand::random::<u32>();
+        let ssrc = track.ssrc().unwrap_or_else(rand::random::<u32>);
         let srtp_stream = Arc::new(SrtpWriterFuture {
             closed: AtomicBool::new(false),
             ssrc,
@@ -236,7 +279,11 @@ impl RTCRtpSender {
         });
 
         let srtp_rtcp_reader = Arc::clone(&srtp_stream) as Arc<dyn RTCPReader + Send + Sync>;
-        let rtcp_interceptor = self.interceptor.bind_rtcp_reader(srtp_rtcp_reader).await;
+        let rtcp_interceptor = crate::in_runtime(
+            self.transport.setting_engine.runtime.as_ref(),
+            self.interceptor.bind_rtcp_reader(srtp_rtcp_reader),
+        )
+        .await;
 
         let create_rtx_stream = self.enable_rtx
             && self
@@ -261,7 +308,11 @@ impl RTCRtpSender {
             });
 
             let srtp_rtcp_reader = Arc::clone(&srtp_stream) as Arc<dyn RTCPReader + Send + Sync>;
-            let rtcp_interceptor = self.interceptor.bind_rtcp_reader(srtp_rtcp_reader).await;
+            let rtcp_interceptor = crate::in_runtime(
+                self.transport.setting_engine.runtime.as_ref(),
+                self.interceptor.bind_rtcp_reader(srtp_rtcp_reader),
+            )
+            .await;
 
             Some(RtxEncoding {
                 srtp_stream,
@@ -273,7 +324,10 @@ impl RTCRtpSender {
             None
         };
 
-        let write_stream = Arc::new(InterceptorToTrackLocalWriter::new(self.paused.clone()));
+        let write_stream = Arc::new(InterceptorToTrackLocalWriter::new(
+            self.paused.clone(),
+            Arc::clone(&self.playout_delay),
+        ));
         let context = TrackLocalContext {
             id: self.id.clone(),
             params: super::RTCRtpParameters::default(),
@@ -281,13 +335,17 @@ impl RTCRtpSender {
             write_stream,
             paused: self.paused.clone(),
             mid: None,
+            mid_acked: Arc::new(AtomicBool::new(false)),
         };
+        let (rtcp_tx, rtcp_rx) = mpsc::channel(RTCP_READ_QUEUE_SIZE);
         let encoding = TrackEncoding {
             track,
             srtp_stream,
             rtcp_interceptor,
             stream_info: StreamInfo::default(),
             context,
+            rtcp_rx: Arc::new(Mutex::new(rtcp_rx)),
+            rtcp_tx: Some(rtcp_tx),
             ssrc,
             rtx,
         };
@@ -314,6 +372,32 @@ impl RTCRtpSender {
     }
 
     pub(crate) fn set_paused(&self, paused: bool) {
+        self.direction_paused.store(paused, Ordering::SeqCst);
+        self.update_paused();
+    }
+
+    /// pause stops transmitting RTP without renegotiating. Packets written to the track while
+    /// paused are dropped. Unlike setting the transceiver direction to inactive, the remote is
+    /// not told about the pause.
+    pub fn pause(&self) {
+        self.app_paused.store(true, Ordering::SeqCst);
+        self.update_paused();
+    }
+
+    /// resume restarts transmitting RTP after [`RTCRtpSender::pause`].
+    pub fn resume(&self) {
+        self.app_paused.store(false, Ordering::SeqCst);
+        self.update_paused();
+    }
+
+    /// is_paused returns whether the sender was paused with [`RTCRtpSender::pause`].
+    pub fn is_paused(&self) -> bool {
+        self.app_paused.load(Ordering::SeqCst)
+    }
+
+    fn update_paused(&self) {
+        let paused =
+            self.direction_paused.load(Ordering::SeqCst) || self.app_paused.load(Ordering::SeqCst);
         self.paused.store(paused, Ordering::SeqCst);
     }
 
@@ -346,22 +430,21 @@ impl RTCRtpSender {
         let mut rtp_parameters = self
             .media_engine
             .get_rtp_parameters_by_kind(self.kind, RTCRtpTransceiverDirection::Sendonly);
-        rtp_parameters.codecs = {
-            let tr = self
-                .rtp_transceiver
-                .lock()
-                .clone()
-                .and_then(|t| t.upgrade());
-            if let Some(t) = &tr {
-                t.get_codecs().await
-            } else {
-                self.media_engine.get_codecs_by_kind(self.kind)
-            }
+        let tr = self
+            .rtp_transceiver
+            .lock()
+            .clone()
+            .and_then(|t| t.upgrade());
+        rtp_parameters.codecs = if let Some(t) = &tr {
+            t.get_codecs().await
+        } else {
+            self.media_engine.get_codecs_by_kind(self.kind)
         };
 
         RTCRtpSendParameters {
             rtp_parameters,
             encodings,
+            remote_receive_constraints: tr.and_then(|t| t.remote_receive_constraints()),
         }
     }
 
@@ -420,6 +503,7 @@ impl RTCRtpSender {
                 write_stream: encoding.context.write_stream.clone(),
                 paused: self.paused.clone(),
                 mid,
+                mid_acked: encoding.context.mid_acked.clone(),
             };
 
             match t.bind(&new_context).await {
@@ -467,14 +551,33 @@ impl RTCRtpSender {
             .and_then(|t| t.mid());
 
         for (idx, encoding) in track_encodings.iter_mut().enumerate() {
-            let write_stream = Arc::new(InterceptorToTrackLocalWriter::new(self.paused.clone()));
+            let write_stream = Arc::new(InterceptorToTrackLocalWriter::new(
+                self.paused.clone(),
+                Arc::clone(&self.playout_delay),
+            ));
             encoding.context.params = self.media_engine.get_rtp_parameters_by_kind(
                 encoding.track.kind(),
                 RTCRtpTransceiverDirection::Sendonly,
             );
             encoding.context.ssrc = parameters.encodings[idx].ssrc;
+            if let Some(dscp_marker) = &self.transport.ice_transport.dscp_marker {
+                let kind = encoding.track.kind();
+                dscp_marker.set_ssrc_kind(parameters.encodings[idx].ssrc, kind);
+                if encoding.rtx.is_some() {
+                    dscp_marker.set_ssrc_kind(parameters.encodings[idx].rtx.ssrc, kind);
+                }
+            }
             encoding.context.write_stream = Arc::clone(&write_stream) as _;
             encoding.context.mid = mid.to_owned();
+            if let Some(ext) = encoding
+                .context
+                .params
+                .header_extensions
+                .iter()
+                .find(|ext| ext.uri == ::sdp::extmap::PLAYOUT_DELAY_URI)
+            {
+                write_stream.set_playout_delay_id(ext.id as u8);
+            }
 
             let codec = encoding.track.bind(&encoding.context).await?;
             encoding.stream_info = create_stream_info(
@@ -485,16 +588,29 @@ impl RTCRtpSender {
                 &parameters.rtp_parameters.header_extensions,
                 None,
             );
+            encoding.stream_info.memory_budget = self.transport.media_memory_budget.clone();
             encoding.context.params.codecs = vec![codec.clone()];
 
             let srtp_writer = Arc::clone(&encoding.srtp_stream) as Arc<dyn RTPWriter + Send + Sync>;
-            let rtp_writer = self
-                .interceptor
-                .bind_local_stream(&encoding.stream_info, srtp_writer)
-                .await;
+            let rtp_writer = crate::in_runtime(
+                self.transport.setting_engine.runtime.as_ref(),
+                self.interceptor
+                    .bind_local_stream(&encoding.stream_info, srtp_writer),
+            )
+            .await;
 
             *write_stream.interceptor_rtp_writer.lock().await = Some(rtp_writer);
 
+            if let Some(rtcp_tx) = encoding.rtcp_tx.take() {
+                self.receive_rtcp(
+                    encoding.rtcp_interceptor.clone(),
+                    encoding.ssrc,
+                    encoding.context.mid_acked.clone(),
+                    rtcp_tx,
+                    encoding.rtcp_rx.clone(),
+                );
+            }
+
             if let (Some(rtx), Some(rtx_codec)) = (
                 &encoding.rtx,
                 codec_rtx_search(&codec, &parameters.rtp_parameters.codecs),
@@ -516,9 +632,12 @@ impl RTCRtpSender {
                 let rtx_srtp_writer =
                     Arc::clone(&rtx.srtp_stream) as Arc<dyn RTPWriter + Send + Sync>;
                 // ignore the rtp writer, only interceptors can write to the stream
-                self.interceptor
-                    .bind_local_stream(&rtx_stream_info, rtx_srtp_writer)
-                    .await;
+                crate::in_runtime(
+                    self.transport.setting_engine.runtime.as_ref(),
+                    self.interceptor
+                        .bind_local_stream(&rtx_stream_info, rtx_srtp_writer),
+                )
+                .await;
 
                 *rtx.stream_info.lock().await = rtx_stream_info;
 
@@ -530,6 +649,56 @@ impl RTCRtpSender {
         Ok(())
     }
 
+    /// starts a routine that reads the rtcp stream of an encoding as it arrives, so interceptors,
+    /// the acknowledgement of the mid and the keyframe requests don't wait for the application
+    /// to read it.
+    /// The packets are queued for read and read_simulcast, the oldest are dropped when the
+    /// queue is full.
+    fn receive_rtcp(
+        &self,
+        rtcp_reader: Arc<dyn RTCPReader + Send + Sync>,
+        ssrc: SSRC,
+        mid_acked: Arc<AtomicBool>,
+        rtcp_tx: mpsc::Sender<RTCPReadResult>,
+        rtcp_rx: Arc<Mutex<mpsc::Receiver<RTCPReadResult>>>,
+    ) {
+        let receive_mtu = self.receive_mtu;
+        let stop_called_signal = self.internal.stop_called_signal.clone();
+        let stop_called_rx = self.internal.stop_called_rx.clone();
+        let track_encodings = Arc::clone(&self.track_encodings);
+        let on_keyframe_request_handler = Arc::clone(&self.on_keyframe_request_handler);
+
+        crate::spawn(self.transport.setting_engine.runtime.as_ref(), async move {
+            let attrs = Attributes::new();
+            let mut b = vec![0u8; receive_mtu];
+            while !stop_called_signal.load(Ordering::SeqCst) {
+                let result = select! {
+                    r = rtcp_reader.read(&mut b, &attrs) => r.map_err(Error::from),
+                    _ = stop_called_rx.notified() => break,
+                };
+                if let Ok((pkts, _)) = &result {
+                    ack_mid(pkts, ssrc, &mid_acked);
+                    fire_keyframe_requests(
+                        pkts,
+                        ssrc,
+                        &on_keyframe_request_handler,
+                        &track_encodings,
+                    )
+                    .await;
+                }
+                let failed = result.is_err();
+                if let Err(mpsc::error::TrySendError::Full(result)) = rtcp_tx.try_send(result) {
+                    log::trace!("dropping the oldest RTCP for ssrc {ssrc}, it isn't read");
+                    let _ = rtcp_rx.lock().await.try_recv();
+                    let _ = rtcp_tx.try_send(result);
+                }
+                if failed {
+                    break;
+                }
+            }
+        });
+    }
+
     /// starts a routine that reads the rtx rtcp stream
     /// These packets aren't exposed to the user, but we need to process them
     /// for TWCC
@@ -538,7 +707,7 @@ impl RTCRtpSender {
         let stop_called_signal = self.internal.stop_called_signal.clone();
         let stop_called_rx = self.internal.stop_called_rx.clone();
 
-        tokio::spawn(async move {
+        crate::spawn(self.transport.setting_engine.runtime.as_ref(), async move {
             let attrs = Attributes::new();
             let mut b = vec![0u8; receive_mtu];
             while !stop_called_signal.load(Ordering::SeqCst) {
@@ -554,8 +723,16 @@ impl RTCRtpSender {
         });
     }
 
-    /// stop irreversibly stops the RTPSender
+    /// stop irreversibly stops the RTPSender. If it was sending, the remote
+    /// receives an RTCP BYE for its SSRCs.
     pub async fn stop(&self) -> Result<()> {
+        self.stop_with_reason("").await
+    }
+
+    /// stop_with_reason irreversibly stops the RTPSender like stop, and gives the
+    /// remote the reason in the RTCP BYE for the SSRCs of the sender. The reason
+    /// is cut to the 255 bytes a BYE can carry.
+    pub async fn stop_with_reason(&self, reason: &str) -> Result<()> {
         if self.stop_called_signal.load(Ordering::SeqCst) {
             return Ok(());
         }
@@ -566,6 +743,8 @@ impl RTCRtpSender {
             return Ok(());
         }
 
+        self.send_goodbye(&self.track_encodings.lock().await, reason)
+            .await;
         self.replace_track(None).await?;
 
         let track_encodings = self.track_encodings.lock().await;
@@ -587,68 +766,127 @@ impl RTCRtpSender {
         Ok(())
     }
 
-    /// read reads incoming RTCP for this RTPReceiver
+    /// send_goodbye tells the remote that the encodings stop, with an RTCP BYE
+    /// (RFC 3550 section 6.6) for their SSRCs, so it can clean up without
+    /// waiting for a timeout.
+    async fn send_goodbye(&self, track_encodings: &[TrackEncoding], reason: &str) {
+        let Some(first) = track_encodings.first() else {
+            return;
+        };
+        let mut sources = vec![];
+        for encoding in track_encodings {
+            sources.push(encoding.ssrc);
+            if let Some(rtx) = &encoding.rtx {
+                sources.push(rtx.ssrc);
+            }
+        }
+
+        // A compound RTCP packet has to start with a report (RFC 3550 section 6.1)
+        let pkts: [Box<dyn rtcp::packet::Packet + Send + Sync>; 2] = [
+            Box::new(ReceiverReport {
+                ssrc: first.ssrc,
+                ..Default::default()
+            }),
+            Box::new(Goodbye {
+                sources,
+                reason: Bytes::copy_from_slice(&reason.as_bytes()[..reason.len().min(255)]),
+            }),
+        ];
+        if let Err(err) = self.transport.write_rtcp(&pkts).await {
+            log::warn!("failed to send RTCP BYE: {err}");
+        }
+    }
+
+    /// read reads incoming RTCP for this RTPSender, like read_rtcp. b isn't written to.
+    #[deprecated(note = "the RTCP isn't read into b, use read_rtcp")]
     pub async fn read(
         &self,
-        b: &mut [u8],
+        _b: &mut [u8],
+    ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
+        self.read_rtcp().await
+    }
+
+    /// read_rtcp reads incoming RTCP for this RTPSender. The RTCP is already read and processed
+    /// by the interceptors as it arrives.
+    pub async fn read_rtcp(
+        &self,
     ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
         tokio::select! {
             _ = self.wait_for_send() => {
-                let rtcp_interceptor = {
+                let rtcp_rx = {
                     let track_encodings = self.track_encodings.lock().await;
-                    track_encodings.first().map(|e|e.rtcp_interceptor.clone())
+                    track_encodings.first().map(|e| e.rtcp_rx.clone())
                 }.ok_or(Error::ErrInterceptorNotBind)?;
-                let a = Attributes::new();
+                let mut rtcp_rx = rtcp_rx.lock().await;
                 tokio::select! {
                     _ = self.internal.stop_called_rx.notified() => Err(Error::ErrClosedPipe),
-                    result = rtcp_interceptor.read(b, &a) => Ok(result?),
+                    result = rtcp_rx.recv() => result.ok_or(Error::ErrClosedPipe)?,
                 }
             }
             _ = self.internal.stop_called_rx.notified() => Err(Error::ErrClosedPipe),
         }
     }
 
-    /// read_rtcp is a convenience method that wraps Read and unmarshals for you.
-    pub async fn read_rtcp(
+    /// read_simulcast reads incoming RTCP for this RTPSender for given rid, like
+    /// read_rtcp_simulcast. b isn't written to.
+    #[deprecated(note = "the RTCP isn't read into b, use read_rtcp_simulcast")]
+    pub async fn read_simulcast(
         &self,
+        _b: &mut [u8],
+        rid: &str,
     ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
-        let mut b = vec![0u8; self.receive_mtu];
-        let (pkts, attributes) = self.read(&mut b).await?;
-
-        Ok((pkts, attributes))
+        self.read_rtcp_simulcast(rid).await
     }
 
-    /// ReadSimulcast reads incoming RTCP for this RTPSender for given rid
-    pub async fn read_simulcast(
+    /// read_rtcp_simulcast reads incoming RTCP for this RTPSender for given rid, like read_rtcp.
+    pub async fn read_rtcp_simulcast(
         &self,
-        b: &mut [u8],
         rid: &str,
     ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
         tokio::select! {
             _ = self.wait_for_send() => {
-                let rtcp_interceptor = {
+                let rtcp_rx = {
                     let track_encodings = self.track_encodings.lock().await;
-                    track_encodings.iter().find(|e| e.track.rid() == Some(rid)).map(|e| e.rtcp_interceptor.clone())
+                    track_encodings.iter().find(|e| e.track.rid() == Some(rid)).map(|e| e.rtcp_rx.clone())
                 }.ok_or(Error::ErrRTPSenderNoTrackForRID)?;
-                let a = Attributes::new();
+                let mut rtcp_rx = rtcp_rx.lock().await;
                 tokio::select! {
                     _ = self.internal.stop_called_rx.notified() => Err(Error::ErrClosedPipe),
-                    result = rtcp_interceptor.read(b, &a) => Ok(result?),
+                    result = rtcp_rx.recv() => result.ok_or(Error::ErrClosedPipe)?,
                 }
             }
             _ = self.internal.stop_called_rx.notified() => Err(Error::ErrClosedPipe),
         }
     }
 
-    /// ReadSimulcastRTCP is a convenience method that wraps ReadSimulcast and unmarshal for you
-    pub async fn read_rtcp_simulcast(
-        &self,
-        rid: &str,
-    ) -> Result<(Vec<Box<dyn rtcp::packet::Packet + Send + Sync>>, Attributes)> {
-        let mut b = vec![0u8; self.receive_mtu];
-        let (pkts, attributes) = self.read_simulcast(&mut b, rid).await?;
+    /// on_keyframe_request sets an event handler which is invoked when the remote asks for a new
+    /// keyframe with a Picture Loss Indication or a Full Intra Request. It is invoked as the RTCP
+    /// arrives, whether the application reads it or not. The track the keyframe is asked for is
+    /// told as well, see [`TrackLocal::key_frame_requested`].
+    pub fn on_keyframe_request(&self, f: OnKeyframeRequestHdlrFn) {
+        self.on_keyframe_request_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
 
-        Ok((pkts, attributes))
+    /// set_playout_delay asks the remote to keep its playout delay within the given bounds by
+    /// attaching the playout-delay header extension to every outgoing packet. The extension is
+    /// only written if it was negotiated, see
+    /// [`configure_playout_delay`](crate::api::interceptor_registry::configure_playout_delay).
+    /// Passing `None` stops sending the extension.
+    pub fn set_playout_delay(&self, playout_delay: Option<PlayoutDelayExtension>) -> Result<()> {
+        if let Some(d) = &playout_delay {
+            if d.min_delay > PLAYOUT_DELAY_MAX_VALUE || d.max_delay > PLAYOUT_DELAY_MAX_VALUE {
+                return Err(Error::Rtp(rtp::Error::PlayoutDelayOverflow));
+            }
+        }
+
+        *self.playout_delay.lock() = playout_delay;
+        Ok(())
+    }
+
+    /// playout_delay returns the playout delay set with [`RTCRtpSender::set_playout_delay`].
+    pub fn playout_delay(&self) -> Option<PlayoutDelayExtension> {
+        *self.playout_delay.lock()
     }
 
     /// Enables overriding outgoing `RTP` packets' `sequence number`s.
@@ -720,3 +958,71 @@ impl RTCRtpSender {
         lock.clone()
     }
 }
+
+/// ack_mid records that the remote reported receiving the ssrc, it then knows which transceiver
+/// the stream belongs to and the mid header extension doesn't need to be sent anymore.
+fn ack_mid(
+    pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+    ssrc: SSRC,
+    mid_acked: &AtomicBool,
+) {
+    let acked = pkts.iter().any(|pkt| {
+        let reports = if let Some(rr) = pkt.as_any().downcast_ref::<ReceiverReport>() {
+            &rr.reports
+        } else if let Some(sr) = pkt.as_any().downcast_ref::<SenderReport>() {
+            &sr.reports
+        } else {
+            return false;
+        };
+        reports.iter().any(|report| report.ssrc == ssrc)
+    });
+    if acked {
+        mid_acked.store(true, Ordering::SeqCst);
+    }
+}
+
+/// fire_keyframe_requests tells the keyframe request handler and the track sent with the ssrc
+/// about the Picture Loss Indications and Full Intra Requests for it.
+async fn fire_keyframe_requests(
+    pkts: &[Box<dyn rtcp::packet::Packet + Send + Sync>],
+    ssrc: SSRC,
+    handler: &ArcSwapOption<Mutex<OnKeyframeRequestHdlrFn>>,
+    track_encodings: &Mutex<Vec<TrackEncoding>>,
+) {
+    let requests: Vec<KeyframeRequest> = pkts
+        .iter()
+        .filter_map(|pkt| {
+            if let Some(pli) = pkt.as_any().downcast_ref::<PictureLossIndication>() {
+                (pli.media_ssrc == ssrc).then_some(KeyframeRequest::PictureLoss)
+            } else if let Some(fir) = pkt.as_any().downcast_ref::<FullIntraRequest>() {
+                fir.fir
+                    .iter()
+                    .any(|entry| entry.ssrc == ssrc)
+                    .then_some(KeyframeRequest::FullIntra)
+            } else {
+                None
+            }
+        })
+        .collect();
+    if requests.is_empty() {
+        return;
+    }
+
+    let handler = handler.load_full();
+    let track = {
+        let track_encodings = track_encodings.lock().await;
+        track_encodings
+            .iter()
+            .find(|e| e.ssrc == ssrc)
+            .map(|e| Arc::clone(&e.track))
+    };
+    for request in requests {
+        if let Some(handler) = &handler {
+            let mut f = handler.lock().await;
+            f(request, ssrc).await;
+        }
+        if let Some(track) = &track {
+            track.key_frame_requested(request, ssrc).await;
+        }
+    }
+}
@@ -2,7 +2,11 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use interceptor::registry::Registry;
 use interceptor::InterceptorBuilder;
+use media::Sample;
 use portable_atomic::AtomicU64;
+use rtcp::payload_feedbacks::full_intra_request::FirEntry;
+use rtcp::payload_feedbacks::receiver_estimated_maximum_bitrate::ReceiverEstimatedMaximumBitrate;
+use rtcp::transport_feedbacks::transport_layer_nack::{NackPair, TransportLayerNack};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -10,11 +14,13 @@ use tokio::time::Duration;
 use waitgroup::WaitGroup;
 
 use super::*;
+use crate::api::interceptor_registry::{configure_audio_level, configure_playout_delay};
 use crate::api::media_engine::{MIME_TYPE_H264, MIME_TYPE_OPUS, MIME_TYPE_VP8, MIME_TYPE_VP9};
 use crate::api::setting_engine::SettingEngine;
 use crate::api::APIBuilder;
 use crate::error::Result;
 use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::sdp::session_description::RTCSessionDescription;
 use crate::peer_connection::peer_connection_test::{
     close_pair_now, create_vnet_pair, new_pair, send_video_until_done, signal_pair,
     until_connection_state,
@@ -22,6 +28,7 @@ use crate::peer_connection::peer_connection_test::{
 use crate::rtp_transceiver::rtp_codec::RTCRtpCodecCapability;
 use crate::rtp_transceiver::RTCRtpCodecParameters;
 use crate::track::track_local::track_local_static_sample::TrackLocalStaticSample;
+use crate::track::track_remote::{ATTR_KEY_PLAYOUT_DELAY_MAX, ATTR_KEY_PLAYOUT_DELAY_MIN};
 
 #[tokio::test]
 async fn test_rtp_sender_replace_track() -> Result<()> {
@@ -410,6 +417,33 @@ async fn test_rtp_sender_get_parameters_replaced() -> Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_rtp_sender_track_ssrc() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (sender, receiver) = new_pair(&api).await?;
+    let track = Arc::new(
+        TrackLocalStaticSample::new(
+            RTCRtpCodecCapability {
+                mime_type: MIME_TYPE_VP8.to_owned(),
+                ..Default::default()
+            },
+            "video".to_owned(),
+            "webrtc-rs".to_owned(),
+        )
+        .with_ssrc(0x1234_5678),
+    );
+    let rtp_sender = sender.add_track(track).await?;
+    let param = rtp_sender.get_parameters().await;
+    assert_eq!(1, param.encodings.len());
+    assert_eq!(0x1234_5678, param.encodings[0].ssrc);
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_rtp_sender_send() -> Result<()> {
     let mut m = MediaEngine::default();
@@ -796,3 +830,652 @@ async fn test_rtp_sender_rtx() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_rtp_sender_playout_delay() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let registry = configure_playout_delay(Registry::new(), &mut m)?;
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_interceptor_registry(registry)
+        .build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let rtp_sender = sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    assert!(rtp_sender
+        .set_playout_delay(Some(PlayoutDelayExtension::new(0, 4096)))
+        .is_err());
+    rtp_sender.set_playout_delay(Some(PlayoutDelayExtension::new(10, 50)))?;
+    assert_eq!(
+        rtp_sender.playout_delay(),
+        Some(PlayoutDelayExtension::new(10, 50))
+    );
+
+    let (attributes_tx, mut attributes_rx) = mpsc::channel::<Attributes>(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, _, _| {
+        let attributes_tx = attributes_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            if let Ok((_, attributes)) = track.read_rtp().await {
+                let _ = attributes_tx.send(attributes).await;
+                let _ = seen_packet_tx.send(()).await;
+            }
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+
+    let attributes = attributes_rx.recv().await.unwrap();
+    assert_eq!(attributes.get(&ATTR_KEY_PLAYOUT_DELAY_MIN), Some(&100));
+    assert_eq!(attributes.get(&ATTR_KEY_PLAYOUT_DELAY_MAX), Some(&500));
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_audio_level() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let registry = configure_audio_level(Registry::new(), &mut m)?;
+    let api = APIBuilder::new()
+        .with_media_engine(m)
+        .with_interceptor_registry(registry)
+        .build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_OPUS.to_owned(),
+            ..Default::default()
+        },
+        "audio".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (seen_tx, mut seen_rx) = mpsc::channel(1);
+    receiver.on_track(Box::new(move |track, receiver, _| {
+        let seen_tx = seen_tx.clone();
+        Box::pin(async move {
+            let (pkt, _) = track.read_rtp().await.unwrap();
+            let id = track
+                .params()
+                .header_extensions
+                .iter()
+                .find(|ext| ext.uri == sdp::extmap::AUDIO_LEVEL_URI)
+                .map(|ext| ext.id as u8)
+                .unwrap();
+            let extension = pkt.header.get_extension(id);
+            let sources = receiver.get_synchronization_sources();
+            let _ = seen_tx.send((extension, sources)).await;
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    // -6 dBov of voice
+    let pcm = [16384i16, -16384].repeat(480);
+    let (extension, sources) = loop {
+        track
+            .sample_writer()
+            .with_audio_level_from_pcm(&pcm)
+            .write_sample(&Sample {
+                data: Bytes::from_static(&[0xAA]),
+                duration: Duration::from_millis(20),
+                ..Default::default()
+            })
+            .await?;
+
+        tokio::select! {
+            seen = seen_rx.recv() => break seen.unwrap(),
+            _ = tokio::time::sleep(Duration::from_millis(20)) => {}
+        }
+    };
+
+    assert_eq!(extension, Some(Bytes::from_static(&[0x80 | 6])));
+    assert_eq!(sources.len(), 1);
+    let audio_level = sources[0].audio_level.unwrap();
+    assert!((audio_level - 10f64.powf(-6.0 / 20.0)).abs() < 1e-9);
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_pause_resume() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let rtp_sender = sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (track_tx, mut track_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, _, _| {
+        let track_tx = track_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            if track.read_rtp().await.is_ok() {
+                let _ = track_tx.send(track).await;
+                let _ = seen_packet_tx.send(()).await;
+            }
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![Arc::clone(&track)],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+    let remote_track = track_rx.recv().await.unwrap();
+
+    // Drain whatever was sent before pausing
+    while tokio::time::timeout(Duration::from_millis(100), remote_track.read_rtp())
+        .await
+        .is_ok()
+    {}
+
+    rtp_sender.pause();
+    assert!(rtp_sender.is_paused());
+    for _ in 0..5 {
+        track
+            .write_sample(&Sample {
+                data: Bytes::from_static(&[0xAA]),
+                duration: Duration::from_millis(20),
+                ..Default::default()
+            })
+            .await?;
+    }
+    let result = tokio::time::timeout(Duration::from_millis(300), remote_track.read_rtp()).await;
+    assert!(result.is_err(), "no RTP must be sent while paused");
+
+    rtp_sender.resume();
+    assert!(!rtp_sender.is_paused());
+    track
+        .write_sample(&Sample {
+            data: Bytes::from_static(&[0xAA]),
+            duration: Duration::from_millis(20),
+            ..Default::default()
+        })
+        .await?;
+    let result = tokio::time::timeout(Duration::from_secs(1), remote_track.read_rtp()).await;
+    assert!(result.is_ok(), "RTP must be sent after resume");
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_on_keyframe_request() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let rtp_sender = sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (request_tx, mut request_rx) = mpsc::channel(2);
+    rtp_sender.on_keyframe_request(Box::new(move |request, ssrc| {
+        let request_tx = request_tx.clone();
+        Box::pin(async move {
+            let _ = request_tx.send((request, ssrc)).await;
+        })
+    }));
+
+    let (ssrc_tx, mut ssrc_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, _, _| {
+        let ssrc_tx = ssrc_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            if track.read_rtp().await.is_ok() {
+                let _ = ssrc_tx.send(track.ssrc()).await;
+                let _ = seen_packet_tx.send(()).await;
+            }
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+    let ssrc = ssrc_rx.recv().await.unwrap();
+
+    receiver
+        .write_rtcp(&[
+            Box::new(FullIntraRequest {
+                sender_ssrc: 0,
+                media_ssrc: ssrc,
+                fir: vec![FirEntry {
+                    ssrc,
+                    sequence_number: 1,
+                }],
+            }),
+            Box::new(PictureLossIndication {
+                sender_ssrc: 0,
+                media_ssrc: ssrc,
+            }),
+        ])
+        .await?;
+
+    let timeout = Duration::from_secs(5);
+    let first = tokio::time::timeout(timeout, request_rx.recv())
+        .await
+        .unwrap();
+    let second = tokio::time::timeout(timeout, request_rx.recv())
+        .await
+        .unwrap();
+    assert_eq!(first, Some((KeyframeRequest::FullIntra, ssrc)));
+    assert_eq!(second, Some((KeyframeRequest::PictureLoss, ssrc)));
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_read_rtcp_compound() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let rtp_sender = sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (ssrc_tx, mut ssrc_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, _, _| {
+        let ssrc_tx = ssrc_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            if track.read_rtp().await.is_ok() {
+                let _ = ssrc_tx.send(track.ssrc()).await;
+                let _ = seen_packet_tx.send(()).await;
+            }
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+    let ssrc = ssrc_rx.recv().await.unwrap();
+
+    // The three packets must be sent as one compound packet, so they are read back together
+    receiver
+        .write_rtcp(&[
+            Box::new(PictureLossIndication {
+                sender_ssrc: 0,
+                media_ssrc: ssrc,
+            }),
+            Box::new(ReceiverEstimatedMaximumBitrate {
+                sender_ssrc: 0,
+                bitrate: 1_000_000.0,
+                ssrcs: vec![ssrc],
+            }),
+            Box::new(TransportLayerNack {
+                sender_ssrc: 0,
+                media_ssrc: ssrc,
+                nacks: vec![NackPair {
+                    packet_id: 1,
+                    lost_packets: 0,
+                }],
+            }),
+        ])
+        .await?;
+
+    let pkts = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let (pkts, _) = rtp_sender.read_rtcp().await?;
+            if pkts
+                .iter()
+                .any(|p| p.as_any().is::<PictureLossIndication>())
+            {
+                return Result::Ok(pkts);
+            }
+        }
+    })
+    .await
+    .expect("compound packet should be received")?;
+
+    assert_eq!(pkts.len(), 3);
+    assert!(pkts[0].as_any().is::<PictureLossIndication>());
+    assert!(pkts[1].as_any().is::<ReceiverEstimatedMaximumBitrate>());
+    assert!(pkts[2].as_any().is::<TransportLayerNack>());
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_read_rtcp_drops_oldest() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let rtp_sender = sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (ssrc_tx, mut ssrc_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, _, _| {
+        let ssrc_tx = ssrc_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            if track.read_rtp().await.is_ok() {
+                let _ = ssrc_tx.send(track.ssrc()).await;
+                let _ = seen_packet_tx.send(()).await;
+            }
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+    let ssrc = ssrc_rx.recv().await.unwrap();
+
+    // more NACKs than the read queue holds arrive while nothing is read
+    let count = RTCP_READ_QUEUE_SIZE as u16 + 16;
+    for packet_id in 0..count {
+        receiver
+            .write_rtcp(&[Box::new(TransportLayerNack {
+                sender_ssrc: 0,
+                media_ssrc: ssrc,
+                nacks: vec![NackPair {
+                    packet_id,
+                    lost_packets: 0,
+                }],
+            })])
+            .await?;
+    }
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // the oldest ones are dropped and the newest one is still read
+    let packet_ids = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut packet_ids = vec![];
+        while packet_ids.last() != Some(&(count - 1)) {
+            let (pkts, _) = rtp_sender.read_rtcp().await?;
+            packet_ids.extend(pkts.iter().filter_map(|p| {
+                p.as_any()
+                    .downcast_ref::<TransportLayerNack>()
+                    .map(|nack| nack.nacks[0].packet_id)
+            }));
+        }
+        Result::Ok(packet_ids)
+    })
+    .await
+    .expect("the newest NACK should be read")?;
+
+    assert!(packet_ids.len() <= RTCP_READ_QUEUE_SIZE);
+    assert!(packet_ids[0] >= count - RTCP_READ_QUEUE_SIZE as u16);
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+// Without reduced-size RTCP negotiated, feedback is written as a compound packet
+// led by an empty receiver report from the sender of the feedback
+#[tokio::test]
+async fn test_rtp_sender_read_rtcp_without_reduced_size() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (sender, receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let rtp_sender = sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (ssrc_tx, mut ssrc_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, _, _| {
+        let ssrc_tx = ssrc_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            if track.read_rtp().await.is_ok() {
+                let _ = ssrc_tx.send(track.ssrc()).await;
+                let _ = seen_packet_tx.send(()).await;
+            }
+        })
+    }));
+
+    // the receiver is offered compound RTCP only
+    let offer = sender.create_offer(None).await?;
+    let mut offer_gathering_complete = sender.gathering_complete_promise().await;
+    sender.set_local_description(offer).await?;
+    let _ = offer_gathering_complete.recv().await;
+    let offer = sender.local_description().await.unwrap();
+    assert!(offer.sdp.contains("a=rtcp-rsize\r\n"));
+    receiver
+        .set_remote_description(RTCSessionDescription::offer(
+            offer.sdp.replace("a=rtcp-rsize\r\n", ""),
+        )?)
+        .await?;
+
+    let answer = receiver.create_answer(None).await?;
+    let mut answer_gathering_complete = receiver.gathering_complete_promise().await;
+    receiver.set_local_description(answer).await?;
+    let _ = answer_gathering_complete.recv().await;
+    sender
+        .set_remote_description(receiver.local_description().await.unwrap())
+        .await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+    let ssrc = ssrc_rx.recv().await.unwrap();
+
+    receiver
+        .write_rtcp(&[Box::new(PictureLossIndication {
+            sender_ssrc: 5000,
+            media_ssrc: ssrc,
+        })])
+        .await?;
+
+    let pkts = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let (pkts, _) = rtp_sender.read_rtcp().await?;
+            if pkts
+                .iter()
+                .any(|p| p.as_any().is::<PictureLossIndication>())
+            {
+                return Result::Ok(pkts);
+            }
+        }
+    })
+    .await
+    .expect("compound packet should be received")?;
+
+    assert_eq!(pkts.len(), 2);
+    assert_eq!(
+        pkts[0]
+            .as_any()
+            .downcast_ref::<rtcp::receiver_report::ReceiverReport>(),
+        Some(&rtcp::receiver_report::ReceiverReport {
+            ssrc: 5000,
+            ..Default::default()
+        })
+    );
+    assert!(pkts[1].as_any().is::<PictureLossIndication>());
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_rtp_sender_stop_sends_goodbye() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut sender, mut receiver) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let rtp_sender = sender
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (track_tx, mut track_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    receiver.on_track(Box::new(move |track, receiver, _| {
+        let track_tx = track_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            let _ = track_tx.send((track, receiver)).await;
+            let _ = seen_packet_tx.send(()).await;
+        })
+    }));
+
+    signal_pair(&mut sender, &mut receiver).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![track],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+
+    let (remote_track, rtp_receiver) = track_rx.recv().await.unwrap();
+    assert_eq!(remote_track.goodbye_reason(), None);
+
+    rtp_sender.stop_with_reason("leaving").await?;
+
+    let bye = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            let (pkts, _) = rtp_receiver.read_rtcp().await?;
+            if let Some(bye) = pkts
+                .iter()
+                .find_map(|p| p.as_any().downcast_ref::<Goodbye>())
+            {
+                return Result::Ok(bye.clone());
+            }
+        }
+    })
+    .await
+    .expect("BYE should be received")?;
+
+    assert_eq!(bye.sources, vec![remote_track.ssrc()]);
+    assert_eq!(bye.reason, Bytes::from_static(b"leaving"));
+    assert_eq!(remote_track.goodbye_reason(), Some("leaving".to_owned()));
+
+    close_pair_now(&sender, &receiver).await;
+    Ok(())
+}
@@ -13,11 +13,10 @@ use std::sync::Arc;
 use arc_swap::ArcSwapOption;
 use data::data_channel::DataChannel;
 use data::message::message_channel_open::ChannelType;
-use portable_atomic::{AtomicBool, AtomicU32, AtomicU8};
+use portable_atomic::{AtomicBool, AtomicU32, AtomicU8, AtomicUsize};
 use sctp::association::Association;
 use sctp_transport_state::RTCSctpTransportState;
 use tokio::sync::{Mutex, Notify};
-use util::Conn;
 
 use crate::api::setting_engine::SettingEngine;
 use crate::data_channel::data_channel_parameters::DataChannelParameters;
@@ -26,6 +25,7 @@ use crate::data_channel::RTCDataChannel;
 use crate::dtls_transport::dtls_role::DTLSRole;
 use crate::dtls_transport::*;
 use crate::error::*;
+use crate::peer_connection::connection_timing::PhaseTimer;
 use crate::sctp_transport::sctp_transport_capabilities::SCTPTransportCapabilities;
 use crate::stats::stats_collector::StatsCollector;
 use crate::stats::StatsReportType::{PeerConnection, SCTPTransport};
@@ -33,6 +33,11 @@ use crate::stats::{ICETransportStats, PeerConnectionStats};
 
 const SCTP_MAX_CHANNELS: u16 = u16::MAX;
 
+/// SCTP_MAX_MESSAGE_SIZE is the size of the largest message the SCTPTransport
+/// sends and accepts. It is advertised as the a=max-message-size (RFC 8841) of
+/// the application media section.
+pub(crate) const SCTP_MAX_MESSAGE_SIZE: u32 = 65536;
+
 pub type OnDataChannelHdlrFn = Box<
     dyn (FnMut(Arc<RTCDataChannel>) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>>)
         + Send
@@ -45,6 +50,19 @@ pub type OnDataChannelOpenedHdlrFn = Box<
         + Sync,
 >;
 
+/// DataChannelInfo describes a RTCDataChannel of the SCTPTransport at the time
+/// [`RTCSctpTransport::data_channel_snapshot`] was called.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataChannelInfo {
+    pub label: String,
+    pub id: u16,
+    pub state: RTCDataChannelState,
+    pub buffered_amount: usize,
+    pub ordered: bool,
+    pub max_retransmits: Option<u16>,
+    pub protocol: String,
+}
+
 struct AcceptDataChannelParams {
     notify_rx: Arc<Notify>,
     sctp_association: Arc<Association>,
@@ -55,6 +73,7 @@ struct AcceptDataChannelParams {
     data_channels_opened: Arc<AtomicU32>,
     data_channels_accepted: Arc<AtomicU32>,
     setting_engine: Arc<SettingEngine>,
+    draining: Arc<AtomicBool>,
 }
 
 /// SCTPTransport provides details about the SCTP transport.
@@ -71,7 +90,7 @@ pub struct RTCSctpTransport {
 
     // max_message_size represents the maximum size of data that can be passed to
     // DataChannel's send() method.
-    max_message_size: usize,
+    max_message_size: AtomicUsize,
 
     // max_channels represents the maximum amount of DataChannel's that can
     // be used simultaneously.
@@ -92,18 +111,29 @@ pub struct RTCSctpTransport {
     notify_tx: Arc<Notify>,
 
     setting_engine: Arc<SettingEngine>,
+
+    // Set while the API that created the transport is draining, the data
+    // channels opened by the remote are then closed right away
+    draining: Arc<AtomicBool>,
+
+    /// open_timer measures the time until the association is established
+    pub(crate) open_timer: PhaseTimer,
 }
 
 impl RTCSctpTransport {
     pub(crate) fn new(
         dtls_transport: Arc<RTCDtlsTransport>,
         setting_engine: Arc<SettingEngine>,
+        draining: Arc<AtomicBool>,
     ) -> Self {
         RTCSctpTransport {
             dtls_transport,
             state: AtomicU8::new(RTCSctpTransportState::Connecting as u8),
             is_started: AtomicBool::new(false),
-            max_message_size: RTCSctpTransport::calc_message_size(65536, 65536),
+            max_message_size: AtomicUsize::new(RTCSctpTransport::calc_message_size(
+                SCTP_MAX_MESSAGE_SIZE as usize,
+                SCTP_MAX_MESSAGE_SIZE as usize,
+            )),
             max_channels: SCTP_MAX_CHANNELS,
             sctp_association: Mutex::new(None),
             on_error_handler: Arc::new(ArcSwapOption::empty()),
@@ -118,6 +148,9 @@ impl RTCSctpTransport {
             notify_tx: Arc::new(Notify::new()),
 
             setting_engine,
+            draining,
+
+            open_timer: PhaseTimer::default(),
         }
     }
 
@@ -129,22 +162,38 @@ impl RTCSctpTransport {
     /// get_capabilities returns the SCTPCapabilities of the SCTPTransport.
     pub fn get_capabilities(&self) -> SCTPTransportCapabilities {
         SCTPTransportCapabilities {
-            max_message_size: 0,
+            max_message_size: SCTP_MAX_MESSAGE_SIZE,
         }
     }
 
     /// Start the SCTPTransport. Since both local and remote parties must mutually
     /// create an SCTPTransport, SCTP SO (Simultaneous Open) is used to establish
-    /// a connection over SCTP.
-    pub async fn start(&self, _remote_caps: SCTPTransportCapabilities) -> Result<()> {
+    /// a connection over SCTP. The max_message_size of the remote capabilities
+    /// limits the messages that are sent, 0 leaves it to the local limit.
+    pub async fn start(&self, remote_caps: SCTPTransportCapabilities) -> Result<()> {
         if self.is_started.load(Ordering::SeqCst) {
             return Ok(());
         }
         self.is_started.store(true, Ordering::SeqCst);
+        self.open_timer.start();
+
+        let max_message_size = RTCSctpTransport::calc_message_size(
+            remote_caps.max_message_size as usize,
+            SCTP_MAX_MESSAGE_SIZE as usize,
+        );
+        self.max_message_size
+            .store(max_message_size, Ordering::SeqCst);
 
         let dtls_transport = self.transport();
-        if let Some(net_conn) = &dtls_transport.conn().await {
+        if let Some(net_conn) = &dtls_transport.sctp_conn().await {
             let sctp_association = loop {
+                let mut config = sctp::association::Config::new(Arc::clone(net_conn));
+                config.max_message_size = max_message_size as u32;
+                config.no_delay = self.setting_engine.sctp_no_delay;
+                config.heartbeat_interval = self.setting_engine.sctp_heartbeat_interval;
+                config.handshake_timeout = self.setting_engine.sctp_handshake_timeout;
+                config.memory_budget = dtls_transport.memory_budget.clone();
+
                 tokio::select! {
                     _ = self.notify_tx.notified() => {
                         // It seems like notify_tx is only notified on Stop so perhaps this check
@@ -154,12 +203,7 @@ impl RTCSctpTransport {
                             return Err(Error::ErrSCTPTransportDTLS);
                         }
                     },
-                    association = sctp::association::Association::client(sctp::association::Config {
-                        net_conn: Arc::clone(net_conn) as Arc<dyn Conn + Send + Sync>,
-                        max_receive_buffer_size: 0,
-                        max_message_size: 0,
-                        name: String::new(),
-                    }) => {
+                    association = sctp::association::Association::client(config) => {
                         break Arc::new(association?);
                     }
                 };
@@ -169,6 +213,7 @@ impl RTCSctpTransport {
                 let mut sa = self.sctp_association.lock().await;
                 *sa = Some(Arc::clone(&sctp_association));
             }
+            self.open_timer.complete();
             self.state
                 .store(RTCSctpTransportState::Connected as u8, Ordering::SeqCst);
 
@@ -182,8 +227,9 @@ impl RTCSctpTransport {
                 data_channels_opened: Arc::clone(&self.data_channels_opened),
                 data_channels_accepted: Arc::clone(&self.data_channels_accepted),
                 setting_engine: Arc::clone(&self.setting_engine),
+                draining: Arc::clone(&self.draining),
             };
-            tokio::spawn(async move {
+            crate::spawn(self.setting_engine.runtime.as_ref(), async move {
                 RTCSctpTransport::accept_data_channels(param).await;
             });
 
@@ -231,7 +277,9 @@ impl RTCSctpTransport {
                     match result {
                         Ok(dc) => dc,
                         Err(err) => {
-                            if data::Error::ErrStreamClosed == err {
+                            if data::Error::ErrStreamClosed == err
+                                || matches!(err, data::Error::Sctp(sctp::Error::ErrAbortReceived(_)))
+                            {
                                 log::error!("Failed to accept data channel: {}", err);
                                 if let Some(handler) = &*param.on_error_handler.load() {
                                     let mut f = handler.lock().await;
@@ -244,6 +292,17 @@ impl RTCSctpTransport {
                 }
             };
 
+            if param.draining.load(Ordering::SeqCst) {
+                log::debug!(
+                    "Closing data channel {} opened while draining",
+                    dc.config.label
+                );
+                if let Err(err) = dc.close().await {
+                    log::warn!("Failed to close data channel: {}", err);
+                }
+                continue;
+            }
+
             let mut max_retransmits = None;
             let mut max_packet_life_time = None;
             let val = dc.config.reliability_parameter as u16;
@@ -287,6 +346,7 @@ impl RTCSctpTransport {
                     ordered,
                     max_packet_life_time,
                     max_retransmits,
+                    priority: dc.config.priority.into(),
                 },
                 Arc::clone(&param.setting_engine),
             ));
@@ -343,6 +403,13 @@ impl RTCSctpTransport {
         }
     }
 
+    /// max_message_size is the size of the largest message that can be sent on
+    /// the RTCDataChannels, the minimum of the local limit and the one the
+    /// remote advertised.
+    pub fn max_message_size(&self) -> usize {
+        self.max_message_size.load(Ordering::SeqCst)
+    }
+
     /// max_channels is the maximum number of RTCDataChannels that can be open simultaneously.
     pub fn max_channels(&self) -> u16 {
         if self.max_channels == 0 {
@@ -357,6 +424,27 @@ impl RTCSctpTransport {
         self.state.load(Ordering::SeqCst).into()
     }
 
+    /// data_channel_snapshot returns the RTCDataChannels of the SCTPTransport
+    /// ordered by id. The data channels can't be added nor removed while the
+    /// snapshot is taken.
+    pub async fn data_channel_snapshot(&self) -> Vec<DataChannelInfo> {
+        let data_channels = self.data_channels.lock().await;
+        let mut snapshot = Vec::with_capacity(data_channels.len());
+        for dc in &*data_channels {
+            snapshot.push(DataChannelInfo {
+                label: dc.label().to_owned(),
+                id: dc.id(),
+                state: dc.ready_state(),
+                buffered_amount: dc.buffered_amount().await,
+                ordered: dc.ordered(),
+                max_retransmits: dc.max_retransmits(),
+                protocol: dc.protocol().to_owned(),
+            });
+        }
+        snapshot.sort_by_key(|info| info.id);
+        snapshot
+    }
+
     pub(crate) async fn collect_stats(
         &self,
         collector: &StatsCollector,
@@ -380,8 +468,9 @@ impl RTCSctpTransport {
         }
 
         let mut reports = HashMap::new();
-        let peer_connection_stats =
+        let mut peer_connection_stats =
             PeerConnectionStats::new(self, peer_connection_id.clone(), data_channels_closed);
+        peer_connection_stats.inbound_ssrcs = dtls_transport.inbound_ssrc_count().await;
         reports.insert(peer_connection_id, PeerConnection(peer_connection_stats));
 
         // conn
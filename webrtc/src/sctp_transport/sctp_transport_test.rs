@@ -41,3 +41,42 @@ async fn test_generate_data_channel_id() -> Result<()> {
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_data_channel_snapshot() {
+    let data_channels = [(5, "c"), (1, "a"), (3, "b")]
+        .into_iter()
+        .map(|(id, label)| {
+            Arc::new(RTCDataChannel {
+                id: AtomicU16::new(id),
+                label: label.to_owned(),
+                ordered: true,
+                max_retransmits: Some(id),
+                ready_state: Arc::new(AtomicU8::new(RTCDataChannelState::Open as u8)),
+                ..Default::default()
+            })
+        })
+        .collect();
+    let s = RTCSctpTransport {
+        data_channels: Arc::new(Mutex::new(data_channels)),
+        ..Default::default()
+    };
+
+    let snapshot = s.data_channel_snapshot().await;
+    assert_eq!(
+        snapshot.iter().map(|info| info.id).collect::<Vec<_>>(),
+        vec![1, 3, 5]
+    );
+    assert_eq!(
+        snapshot[1],
+        DataChannelInfo {
+            label: "b".to_owned(),
+            id: 3,
+            state: RTCDataChannelState::Open,
+            buffered_amount: 0,
+            ordered: true,
+            max_retransmits: Some(3),
+            protocol: String::new(),
+        }
+    );
+}
@@ -0,0 +1,194 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use super::{StatsReport, StatsReportType};
+
+/// the delay, in milliseconds, added to the network delay for the codec and the jitter buffer
+const CODEC_DELAY_MS: f64 = 10.0;
+/// the transmission rating of a path without any impairment
+const BASE_RATING: f64 = 93.2;
+/// the lowest score of a connection that is good
+const GOOD_SCORE: f64 = 4.0;
+/// the lowest score of a connection that is fair
+const FAIR_SCORE: f64 = 3.0;
+
+/// QualityLevel is a coarse rating of a [`QualityScore`], for a good/fair/poor indicator.
+#[derive(Default, Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum QualityLevel {
+    /// no report from the remote was received yet
+    #[default]
+    Unknown,
+    Poor,
+    Fair,
+    Good,
+}
+
+impl fmt::Display for QualityLevel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match *self {
+            QualityLevel::Unknown => "unknown",
+            QualityLevel::Poor => "poor",
+            QualityLevel::Fair => "fair",
+            QualityLevel::Good => "good",
+        };
+        write!(f, "{s}")
+    }
+}
+
+/// QualityScore estimates the quality of a connection from the round trip time, jitter
+/// and loss the remote reports about the streams it receives, as found in the
+/// [`RemoteInboundRTPStats`](super::RemoteInboundRTPStats) of a [`StatsReport`].
+///
+/// The score is the MOS (Mean Opinion Score) of the simplified E-model (ITU-T G.107) and
+/// ranges from 1 (bad) to 4.5 (excellent):
+///
+/// ```text
+/// effective latency = rtt / 2 + 2 * jitter + 10ms
+/// R = 93.2 - effective latency / 40             when effective latency < 160ms
+///     93.2 - (effective latency - 120) / 10     otherwise
+/// R = R - 2.5 * loss percentage
+/// MOS = 1 + 0.035 * R + 0.000007 * R * (R - 60) * (100 - R)
+/// ```
+///
+/// The inputs are the worst of all the streams sent, taken from the latest receiver
+/// reports, so the score follows the recent state of the connection. A connection only
+/// receiving media gets no receiver reports and stays [`QualityLevel::Unknown`].
+#[derive(Default, Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct QualityScore {
+    /// MOS from 1 to 4.5, 0 when the level is unknown
+    pub score: f64,
+    pub level: QualityLevel,
+    /// round trip time in milliseconds
+    pub round_trip_time: Option<f64>,
+    /// jitter in seconds
+    pub jitter: f64,
+    /// fraction of packets lost, from 0 to 1
+    pub fraction_lost: f64,
+}
+
+impl QualityScore {
+    /// from_stats_report computes the quality score of the connection a report was collected on.
+    pub fn from_stats_report(report: &StatsReport) -> Self {
+        let mut reported = false;
+        let mut round_trip_time: Option<f64> = None;
+        let mut jitter: f64 = 0.0;
+        let mut fraction_lost: f64 = 0.0;
+        for stats in report.reports.values() {
+            let StatsReportType::RemoteInboundRTP(stats) = stats else {
+                continue;
+            };
+            if stats.packets_received == 0 && stats.round_trip_time.is_none() {
+                continue;
+            }
+
+            reported = true;
+            if let Some(rtt) = stats.round_trip_time {
+                round_trip_time = Some(round_trip_time.map_or(rtt, |r| r.max(rtt)));
+            }
+            jitter = jitter.max(stats.jitter);
+            fraction_lost = fraction_lost.max(stats.fraction_lost);
+        }
+
+        if !reported {
+            return QualityScore::default();
+        }
+
+        let score = mean_opinion_score(round_trip_time.unwrap_or_default(), jitter, fraction_lost);
+        let level = if score >= GOOD_SCORE {
+            QualityLevel::Good
+        } else if score >= FAIR_SCORE {
+            QualityLevel::Fair
+        } else {
+            QualityLevel::Poor
+        };
+
+        QualityScore {
+            score,
+            level,
+            round_trip_time,
+            jitter,
+            fraction_lost,
+        }
+    }
+}
+
+/// mean_opinion_score returns the MOS of the simplified E-model, rtt in milliseconds
+/// and jitter in seconds
+fn mean_opinion_score(round_trip_time: f64, jitter: f64, fraction_lost: f64) -> f64 {
+    let effective_latency = round_trip_time / 2.0 + 2.0 * jitter * 1000.0 + CODEC_DELAY_MS;
+    let mut rating = if effective_latency < 160.0 {
+        BASE_RATING - effective_latency / 40.0
+    } else {
+        BASE_RATING - (effective_latency - 120.0) / 10.0
+    };
+    rating -= 2.5 * fraction_lost * 100.0;
+    let rating = rating.clamp(0.0, 100.0);
+
+    1.0 + 0.035 * rating + 0.000007 * rating * (rating - 60.0) * (100.0 - rating)
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use tokio::time::Instant;
+
+    use super::*;
+    use crate::stats::{RTCStatsType, RemoteInboundRTPStats};
+
+    fn report(streams: &[(Option<f64>, f64, f64)]) -> StatsReport {
+        let reports: HashMap<String, StatsReportType> = streams
+            .iter()
+            .enumerate()
+            .map(|(i, (round_trip_time, jitter, fraction_lost))| {
+                let id = format!("RTCRemoteInboundRTPAudioStream_{i}");
+                let stats = RemoteInboundRTPStats {
+                    timestamp: Instant::now(),
+                    stats_type: RTCStatsType::RemoteInboundRTP,
+                    id: id.clone(),
+                    ssrc: i as u32,
+                    kind: "audio".to_owned(),
+                    packets_received: 100,
+                    packets_lost: 0,
+                    jitter: *jitter,
+                    local_id: String::new(),
+                    round_trip_time: *round_trip_time,
+                    total_round_trip_time: round_trip_time.unwrap_or_default(),
+                    fraction_lost: *fraction_lost,
+                    round_trip_time_measurements: 1,
+                };
+                (id, StatsReportType::RemoteInboundRTP(stats))
+            })
+            .collect();
+
+        StatsReport { reports }
+    }
+
+    #[test]
+    fn test_quality_score() {
+        let quality = QualityScore::from_stats_report(&report(&[]));
+        assert_eq!(quality, QualityScore::default());
+        assert_eq!(quality.level, QualityLevel::Unknown);
+
+        let quality = QualityScore::from_stats_report(&report(&[(Some(40.0), 0.005, 0.0)]));
+        assert_eq!(quality.level, QualityLevel::Good);
+        assert!(quality.score > 4.3 && quality.score <= 4.5, "{quality:?}");
+
+        let quality = QualityScore::from_stats_report(&report(&[(Some(300.0), 0.03, 0.03)]));
+        assert_eq!(quality.level, QualityLevel::Fair, "{quality:?}");
+
+        // The worst stream sets the score
+        let quality = QualityScore::from_stats_report(&report(&[
+            (Some(40.0), 0.005, 0.0),
+            (Some(600.0), 0.05, 0.15),
+        ]));
+        assert_eq!(quality.level, QualityLevel::Poor, "{quality:?}");
+        assert_eq!(quality.round_trip_time, Some(600.0));
+        assert_eq!(quality.jitter, 0.05);
+        assert_eq!(quality.fraction_lost, 0.15);
+        assert!(quality.score >= 1.0, "{quality:?}");
+    }
+}
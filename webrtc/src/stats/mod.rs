@@ -1,4 +1,5 @@
 use std::collections::HashMap;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 use std::time::SystemTime;
 
@@ -19,6 +20,7 @@ use crate::rtp_transceiver::rtp_codec::RTCRtpCodecParameters;
 use crate::rtp_transceiver::{PayloadType, SSRC};
 use crate::sctp_transport::RTCSctpTransport;
 
+pub mod connection_quality;
 mod serialize;
 pub mod stats_collector;
 
@@ -521,6 +523,12 @@ pub struct PeerConnectionStats {
     // Non-canon
     pub data_channels_accepted: u32,
     pub data_channels_requested: u32,
+    /// bytes buffered by the PeerConnection, if SettingEngine::set_memory_budget was set
+    pub memory_budget_used: Option<usize>,
+    /// distinct SSRCs the remote sends RTP on
+    pub inbound_ssrcs: usize,
+    /// streams of new SSRCs dropped because of SettingEngine::set_max_inbound_ssrcs
+    pub inbound_ssrcs_dropped: u64,
 }
 
 impl PeerConnectionStats {
@@ -530,6 +538,17 @@ impl PeerConnectionStats {
             data_channels_closed,
             data_channels_opened: transport.data_channels_opened(),
             data_channels_requested: transport.data_channels_requested(),
+            memory_budget_used: transport
+                .transport()
+                .memory_budget
+                .as_ref()
+                .map(|budget| budget.used()),
+            // Counting the SSRCs needs to lock the SRTP session, see RTCSctpTransport::collect_stats
+            inbound_ssrcs: 0,
+            inbound_ssrcs_dropped: transport
+                .transport()
+                .inbound_ssrcs_dropped
+                .load(Ordering::SeqCst),
             id: stats_id,
             stats_type: RTCStatsType::PeerConnection,
             timestamp: Instant::now(),
@@ -646,7 +665,8 @@ pub struct RemoteInboundRTPStats {
     // RTCReceivedRtpStreamStats
     pub packets_received: u64,
     pub packets_lost: i64,
-    // TODO: jitter(maybe, might be uattainable for the same reason as `framesDropped`)
+    /// jitter reported by the remote, in seconds
+    pub jitter: f64,
     // NB: `framesDropped` can't be produced since we aren't decoding, might be worth introducing a
     // way for consumers to control this in the future.
 
@@ -1,6 +1,7 @@
 #[cfg(test)]
 mod track_local_static_test;
 
+pub mod track_local_fanout;
 pub mod track_local_static_rtp;
 pub mod track_local_static_sample;
 
@@ -10,14 +11,18 @@ use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use async_trait::async_trait;
+use bytes::Bytes;
 use interceptor::{Attributes, RTPWriter};
-use portable_atomic::AtomicBool;
+use portable_atomic::{AtomicBool, AtomicU8};
+use rtp::extension::playout_delay_extension::PlayoutDelayExtension;
 use smol_str::SmolStr;
 use tokio::sync::Mutex;
-use util::Unmarshal;
+use util::sync::Mutex as SyncMutex;
+use util::{Marshal, Unmarshal};
 
 use crate::error::{Error, Result};
 use crate::rtp_transceiver::rtp_codec::*;
+use crate::rtp_transceiver::rtp_sender::KeyframeRequest;
 use crate::rtp_transceiver::*;
 
 /// TrackLocalWriter is the Writer for outbound RTP Packets
@@ -55,6 +60,10 @@ pub struct TrackLocalContext {
     pub(crate) write_stream: Arc<dyn TrackLocalWriter + Send + Sync>,
     pub(crate) paused: Arc<AtomicBool>,
     pub(crate) mid: Option<SmolStr>,
+    /// set once the remote reported receiving the stream, the mid header extension isn't needed
+    /// anymore to associate it to its transceiver. The reports are seen as the RTCP of the sender
+    /// arrives, whether the application reads it or not.
+    pub(crate) mid_acked: Arc<AtomicBool>,
 }
 
 impl TrackLocalContext {
@@ -125,6 +134,18 @@ pub trait TrackLocal {
     /// kind controls if this TrackLocal is audio or video
     fn kind(&self) -> RTPCodecType;
 
+    /// ssrc is the SSRC a RTPSender uses to send this track when it is added to one,
+    /// a random SSRC is picked if it returns None
+    fn ssrc(&self) -> Option<SSRC> {
+        None
+    }
+
+    /// key_frame_requested is called by the RTPSender the track is sent with when the remote
+    /// asks for a keyframe of the stream with the given SSRC, so the track can have its source
+    /// produce one. It is called as the RTCP of the sender arrives, whether the application reads
+    /// it or not.
+    async fn key_frame_requested(&self, _request: KeyframeRequest, _ssrc: SSRC) {}
+
     fn as_any(&self) -> &dyn Any;
 }
 
@@ -140,9 +161,63 @@ pub(crate) struct TrackBinding {
     write_stream: Arc<dyn TrackLocalWriter + Send + Sync>,
     sender_paused: Arc<AtomicBool>,
     hdr_ext_ids: Vec<rtp::header::Extension>,
+    mid_extension_id: Option<u8>,
+    mid_acked: Arc<AtomicBool>,
 }
 
 impl TrackBinding {
+    /// new creates the binding of a track with the given rid to the context,
+    /// the mid and rid header extensions are set on the packets if negotiated
+    pub(crate) fn new(t: &TrackLocalContext, payload_type: PayloadType, rid: Option<&str>) -> Self {
+        let mut hdr_ext_ids = vec![];
+        let mid_extension_id = t
+            .header_extensions()
+            .iter()
+            .find(|e| e.uri == ::sdp::extmap::SDES_MID_URI)
+            .map(|e| e.id as u8);
+        if let Some(id) = mid_extension_id {
+            if let Some(payload) = t
+                .mid
+                .as_ref()
+                .map(|mid| Bytes::copy_from_slice(mid.as_bytes()))
+            {
+                hdr_ext_ids.push(rtp::header::Extension { id, payload });
+            }
+        }
+
+        if let Some(id) = t
+            .header_extensions()
+            .iter()
+            .find(|e| e.uri == ::sdp::extmap::SDES_RTP_STREAM_ID_URI)
+            .map(|e| e.id as u8)
+        {
+            if let Some(payload) = rid.map(|rid| Bytes::copy_from_slice(rid.as_bytes())) {
+                hdr_ext_ids.push(rtp::header::Extension { id, payload });
+            }
+        }
+
+        TrackBinding {
+            id: t.id(),
+            ssrc: t.ssrc(),
+            payload_type,
+            params: t.params.clone(),
+            write_stream: t.write_stream(),
+            sender_paused: t.paused.clone(),
+            hdr_ext_ids,
+            mid_extension_id,
+            mid_acked: t.mid_acked.clone(),
+        }
+    }
+
+    /// header_extensions returns the mid and rid header extensions to set on a packet. The mid
+    /// is only sent until the remote acknowledged the stream, see [`TrackLocalContext::mid_acked`].
+    pub(crate) fn header_extensions(&self) -> impl Iterator<Item = &rtp::header::Extension> {
+        let mid_acked = self.mid_acked.load(Ordering::SeqCst);
+        self.hdr_ext_ids
+            .iter()
+            .filter(move |ext| !mid_acked || Some(ext.id) != self.mid_extension_id)
+    }
+
     pub fn is_sender_paused(&self) -> bool {
         self.sender_paused.load(Ordering::SeqCst)
     }
@@ -151,19 +226,31 @@ impl TrackBinding {
 pub(crate) struct InterceptorToTrackLocalWriter {
     pub(crate) interceptor_rtp_writer: Mutex<Option<Arc<dyn RTPWriter + Send + Sync>>>,
     sender_paused: Arc<AtomicBool>,
+    sender_playout_delay: Arc<SyncMutex<Option<PlayoutDelayExtension>>>,
+    /// negotiated id of the playout-delay header extension, 0 if not negotiated
+    playout_delay_id: AtomicU8,
 }
 
 impl InterceptorToTrackLocalWriter {
-    pub(crate) fn new(paused: Arc<AtomicBool>) -> Self {
+    pub(crate) fn new(
+        paused: Arc<AtomicBool>,
+        playout_delay: Arc<SyncMutex<Option<PlayoutDelayExtension>>>,
+    ) -> Self {
         InterceptorToTrackLocalWriter {
             interceptor_rtp_writer: Mutex::new(None),
             sender_paused: paused,
+            sender_playout_delay: playout_delay,
+            playout_delay_id: AtomicU8::new(0),
         }
     }
 
     fn is_sender_paused(&self) -> bool {
         self.sender_paused.load(Ordering::SeqCst)
     }
+
+    pub(crate) fn set_playout_delay_id(&self, id: u8) {
+        self.playout_delay_id.store(id, Ordering::SeqCst);
+    }
 }
 
 impl std::fmt::Debug for InterceptorToTrackLocalWriter {
@@ -185,7 +272,17 @@ impl TrackLocalWriter for InterceptorToTrackLocalWriter {
 
         let interceptor_rtp_writer = self.interceptor_rtp_writer.lock().await;
         if let Some(writer) = &*interceptor_rtp_writer {
-            Ok(writer.write(pkt, attr).await?)
+            let playout_delay_id = self.playout_delay_id.load(Ordering::SeqCst);
+            let playout_delay = *self.sender_playout_delay.lock();
+            match playout_delay {
+                Some(playout_delay) if playout_delay_id != 0 => {
+                    let mut pkt = pkt.clone();
+                    pkt.header
+                        .set_extension(playout_delay_id, playout_delay.marshal()?)?;
+                    Ok(writer.write(&pkt, attr).await?)
+                }
+                _ => Ok(writer.write(pkt, attr).await?),
+            }
         } else {
             Ok(0)
         }
@@ -0,0 +1,209 @@
+use portable_atomic::AtomicU16;
+use tokio::sync::Mutex;
+use util::Unmarshal;
+
+use super::*;
+use crate::error::flatten_errs;
+
+/// TrackLocalFanout is a TrackLocal that has a pre-set codec and writes every
+/// RTP packet it is given to all the senders it is bound to, typically one per
+/// egress PeerConnection of an SFU. The packet is packetized once, each binding
+/// rewrites the SSRC, payload type and sequence number of its own copy of the
+/// header while the payload Bytes are shared between all of them.
+///
+/// Each binding numbers its packets from a random sequence number of its own,
+/// and the packets dropped while its sender is paused don't leave a gap, so a
+/// sender joining or resuming mid-stream is contiguous for its receiver.
+#[derive(Debug)]
+pub struct TrackLocalFanout {
+    bindings: Mutex<Vec<Arc<FanoutBinding>>>,
+    codec: RTCRtpCodecCapability,
+    id: String,
+    rid: Option<String>,
+    stream_id: String,
+}
+
+/// FanoutBinding is a TrackBinding with the sequence number rewriting state
+#[derive(Debug)]
+struct FanoutBinding {
+    binding: TrackBinding,
+    /// offset from the sequence numbers of the source to the ones of the binding
+    sequence_offset: AtomicU16,
+}
+
+impl TrackLocalFanout {
+    /// returns a TrackLocalFanout without rid.
+    pub fn new(codec: RTCRtpCodecCapability, id: String, stream_id: String) -> Self {
+        TrackLocalFanout {
+            bindings: Mutex::new(vec![]),
+            codec,
+            id,
+            rid: None,
+            stream_id,
+        }
+    }
+
+    /// returns a TrackLocalFanout with rid.
+    pub fn new_with_rid(
+        codec: RTCRtpCodecCapability,
+        id: String,
+        rid: String,
+        stream_id: String,
+    ) -> Self {
+        TrackLocalFanout {
+            bindings: Mutex::new(vec![]),
+            codec,
+            id,
+            rid: Some(rid),
+            stream_id,
+        }
+    }
+
+    /// codec gets the Codec of the track
+    pub fn codec(&self) -> RTCRtpCodecCapability {
+        self.codec.clone()
+    }
+
+    /// binding_count returns the number of senders the track is bound to
+    pub async fn binding_count(&self) -> usize {
+        self.bindings.lock().await.len()
+    }
+}
+
+#[async_trait]
+impl TrackLocal for TrackLocalFanout {
+    /// bind is called by the PeerConnection after negotiation is complete
+    /// This asserts that the code requested is supported by the remote peer.
+    /// If so it setups all the state (SSRC, PayloadType and sequence number)
+    /// to have a call
+    async fn bind(&self, t: &TrackLocalContext) -> Result<RTCRtpCodecParameters> {
+        let parameters = RTCRtpCodecParameters {
+            capability: self.codec.clone(),
+            ..Default::default()
+        };
+
+        let (codec, match_type) = codec_parameters_fuzzy_search(&parameters, t.codec_parameters());
+        if match_type == CodecMatch::None {
+            return Err(Error::ErrUnsupportedCodec);
+        }
+
+        let mut bindings = self.bindings.lock().await;
+        bindings.push(Arc::new(FanoutBinding {
+            binding: TrackBinding::new(t, codec.payload_type, self.rid()),
+            sequence_offset: AtomicU16::new(rand::random()),
+        }));
+
+        Ok(codec)
+    }
+
+    /// unbind implements the teardown logic when the track is no longer needed. This happens
+    /// because a track has been stopped.
+    async fn unbind(&self, t: &TrackLocalContext) -> Result<()> {
+        let mut bindings = self.bindings.lock().await;
+        match bindings.iter().position(|b| b.binding.id == t.id()) {
+            Some(index) => {
+                bindings.remove(index);
+                Ok(())
+            }
+            None => Err(Error::ErrUnbindFailed),
+        }
+    }
+
+    /// id is the unique identifier for this Track. This should be unique for the
+    /// stream, but doesn't have to globally unique. A common example would be 'audio' or 'video'
+    /// and StreamID would be 'desktop' or 'webcam'
+    fn id(&self) -> &str {
+        self.id.as_str()
+    }
+
+    /// RID is the RTP Stream ID for this track.
+    fn rid(&self) -> Option<&str> {
+        self.rid.as_deref()
+    }
+
+    /// stream_id is the group this track belongs too. This must be unique
+    fn stream_id(&self) -> &str {
+        self.stream_id.as_str()
+    }
+
+    /// kind controls if this TrackLocal is audio or video
+    fn kind(&self) -> RTPCodecType {
+        if self.codec.mime_type.starts_with("audio/") {
+            RTPCodecType::Audio
+        } else if self.codec.mime_type.starts_with("video/") {
+            RTPCodecType::Video
+        } else {
+            RTPCodecType::Unspecified
+        }
+    }
+
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+}
+
+#[async_trait]
+impl TrackLocalWriter for TrackLocalFanout {
+    /// `write_rtp_with_attributes` writes a RTP Packet to every sender the
+    /// TrackLocalFanout is bound to. If one PeerConnection fails the packet
+    /// will still be sent to all PeerConnections. The error message will
+    /// contain the ID of the failed PeerConnections so you can remove them
+    async fn write_rtp_with_attributes(
+        &self,
+        pkt: &rtp::packet::Packet,
+        attr: &Attributes,
+    ) -> Result<usize> {
+        let bindings = {
+            let bindings = self.bindings.lock().await;
+            bindings.clone()
+        };
+
+        let mut n = 0;
+        let mut write_errs = vec![];
+        for b in bindings {
+            if b.binding.is_sender_paused() {
+                // Keep the sequence numbers of the binding contiguous when it resumes
+                b.sequence_offset.fetch_sub(1, Ordering::SeqCst);
+                continue;
+            }
+
+            let mut header = pkt.header.clone();
+            header.ssrc = b.binding.ssrc;
+            header.payload_type = b.binding.payload_type;
+            header.sequence_number = pkt
+                .header
+                .sequence_number
+                .wrapping_add(b.sequence_offset.load(Ordering::SeqCst));
+            for ext in b.binding.header_extensions() {
+                if let Err(err) = header.set_extension(ext.id, ext.payload.clone()) {
+                    write_errs.push(Error::Rtp(err));
+                }
+            }
+
+            let pkt = rtp::packet::Packet {
+                header,
+                payload: pkt.payload.clone(),
+            };
+            match b
+                .binding
+                .write_stream
+                .write_rtp_with_attributes(&pkt, attr)
+                .await
+            {
+                Ok(m) => n += m,
+                Err(err) => write_errs.push(err),
+            }
+        }
+
+        flatten_errs(write_errs)?;
+        Ok(n)
+    }
+
+    /// write writes a RTP Packet as a buffer to every sender the
+    /// TrackLocalFanout is bound to
+    async fn write(&self, mut b: &[u8]) -> Result<usize> {
+        let pkt = rtp::packet::Packet::unmarshal(&mut b)?;
+        self.write_rtp(&pkt).await?;
+        Ok(b.len())
+    }
+}
@@ -1,21 +1,47 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 
+use arc_swap::ArcSwapOption;
 use bytes::{Bytes, BytesMut};
 use tokio::sync::Mutex;
 use util::{Marshal, MarshalSize};
 
 use super::*;
 use crate::error::flatten_errs;
+use crate::rtp_transceiver::rtp_sender::OnKeyframeRequestHdlrFn;
 
 /// TrackLocalStaticRTP  is a TrackLocal that has a pre-set codec and accepts RTP Packets.
 /// If you wish to send a media.Sample use TrackLocalStaticSample
-#[derive(Debug)]
+///
+/// The packets are sent as they are written, which suits an external pacer: the sequence
+/// numbers and timestamps are kept, unless [`RTCRtpSender::enable_seq_transformer`] is
+/// called, and only the SSRC, the payload type and the header extensions negotiated for
+/// each binding are set. Nothing is buffered, a write returns once the packet went through
+/// the interceptors and SRTP, and packets written before the transport is connected or
+/// while the sender is paused are dropped.
+///
+/// [`RTCRtpSender::enable_seq_transformer`]: crate::rtp_transceiver::rtp_sender::RTCRtpSender::enable_seq_transformer
 pub struct TrackLocalStaticRTP {
     pub(crate) bindings: Mutex<Vec<Arc<TrackBinding>>>,
     codec: RTCRtpCodecCapability,
     id: String,
     rid: Option<String>,
     stream_id: String,
+    ssrc: Option<SSRC>,
+    on_key_frame_requested_handler: ArcSwapOption<Mutex<OnKeyframeRequestHdlrFn>>,
+}
+
+impl fmt::Debug for TrackLocalStaticRTP {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TrackLocalStaticRTP")
+            .field("bindings", &self.bindings)
+            .field("codec", &self.codec)
+            .field("id", &self.id)
+            .field("rid", &self.rid)
+            .field("stream_id", &self.stream_id)
+            .field("ssrc", &self.ssrc)
+            .finish()
+    }
 }
 
 impl TrackLocalStaticRTP {
@@ -27,6 +53,8 @@ impl TrackLocalStaticRTP {
             id,
             rid: None,
             stream_id,
+            ssrc: None,
+            on_key_frame_requested_handler: ArcSwapOption::empty(),
         }
     }
 
@@ -43,14 +71,34 @@ impl TrackLocalStaticRTP {
             id,
             rid: Some(rid),
             stream_id,
+            ssrc: None,
+            on_key_frame_requested_handler: ArcSwapOption::empty(),
         }
     }
 
+    /// with_ssrc makes the RTPSender the track is added to send it with the given SSRC
+    /// instead of a random one. The SSRC is kept when the track of that sender is
+    /// replaced, so a receiver sees the same stream across source swaps
+    pub fn with_ssrc(mut self, ssrc: SSRC) -> Self {
+        self.ssrc = Some(ssrc);
+        self
+    }
+
     /// codec gets the Codec of the track
     pub fn codec(&self) -> RTCRtpCodecCapability {
         self.codec.clone()
     }
 
+    /// on_key_frame_requested sets an event handler which is invoked when the remote of any
+    /// of the PeerConnections the track is sent to asks for a keyframe with a Picture Loss
+    /// Indication or a Full Intra Request, with the SSRC of the stream it was asked for. It
+    /// lets a forwarded track ask its own source for a keyframe. It is invoked as the RTCP of
+    /// the RTPSender arrives, whether the application reads it or not.
+    pub fn on_key_frame_requested(&self, f: OnKeyframeRequestHdlrFn) {
+        self.on_key_frame_requested_handler
+            .store(Some(Arc::new(Mutex::new(f))));
+    }
+
     pub async fn any_binding_paused(&self) -> bool {
         let bindings = self.bindings.lock().await;
         bindings
@@ -93,76 +141,102 @@ impl TrackLocalStaticRTP {
         extensions: &[rtp::extension::HeaderExtension],
         attr: &Attributes,
     ) -> Result<usize> {
-        let mut n = 0;
         let mut write_errs = vec![];
-        let mut pkt = p.clone();
 
-        let bindings = {
-            let bindings = self.bindings.lock().await;
-            bindings.clone()
-        };
-        // Prepare the extensions data
-        let extension_data: HashMap<_, _> = extensions
-            .iter()
-            .flat_map(|extension| {
-                let buf = {
-                    let mut buf = BytesMut::with_capacity(extension.marshal_size());
-                    buf.resize(extension.marshal_size(), 0);
-                    if let Err(err) = extension.marshal_to(&mut buf) {
-                        write_errs.push(Error::Util(err));
-                        return None;
-                    }
-
-                    buf.freeze()
-                };
-
-                Some((extension.uri(), buf))
-            })
-            .collect();
-
-        for b in bindings.into_iter() {
-            if b.is_sender_paused() {
-                // See caveat in function doc.
-                continue;
-            }
-            pkt.header.ssrc = b.ssrc;
-            pkt.header.payload_type = b.payload_type;
+        let bindings = self.bindings().await;
+        let extension_data = marshal_extensions(extensions, &mut write_errs);
+        let n = write_rtp_to_bindings(&bindings, p, &extension_data, attr, &mut write_errs).await;
 
-            for ext in b.hdr_ext_ids.iter() {
-                let payload = ext.payload.to_owned();
-                if let Err(err) = pkt.header.set_extension(ext.id, payload) {
-                    write_errs.push(Error::Rtp(err));
-                }
-            }
+        flatten_errs(write_errs)?;
+        Ok(n)
+    }
 
-            for (uri, data) in extension_data.iter() {
-                if let Some(id) = b
-                    .params
-                    .header_extensions
-                    .iter()
-                    .find(|ext| &ext.uri == uri)
-                    .map(|ext| ext.id)
-                {
-                    if let Err(err) = pkt.header.set_extension(id as u8, data.clone()) {
-                        write_errs.push(Error::Rtp(err));
-                        continue;
-                    }
+    /// bindings returns a snapshot of the bindings the track is written to
+    pub(crate) async fn bindings(&self) -> Vec<Arc<TrackBinding>> {
+        let bindings = self.bindings.lock().await;
+        bindings.clone()
+    }
+}
+
+/// marshal_extensions prepares the data of the extensions to set on the packets written,
+/// keyed by their uri. The extensions failing to marshal are reported in write_errs.
+pub(crate) fn marshal_extensions(
+    extensions: &[rtp::extension::HeaderExtension],
+    write_errs: &mut Vec<Error>,
+) -> HashMap<Cow<'static, str>, Bytes> {
+    extensions
+        .iter()
+        .flat_map(|extension| {
+            let buf = {
+                let mut buf = BytesMut::with_capacity(extension.marshal_size());
+                buf.resize(extension.marshal_size(), 0);
+                if let Err(err) = extension.marshal_to(&mut buf) {
+                    write_errs.push(Error::Util(err));
+                    return None;
                 }
+
+                buf.freeze()
+            };
+
+            Some((extension.uri(), buf))
+        })
+        .collect()
+}
+
+/// write_rtp_to_bindings writes a copy of the packet to each of the bindings that is not
+/// paused, with the ssrc, payload type and header extensions of the binding. It returns the
+/// number of bytes written, the errors are reported in write_errs.
+pub(crate) async fn write_rtp_to_bindings(
+    bindings: &[Arc<TrackBinding>],
+    p: &rtp::packet::Packet,
+    extension_data: &HashMap<Cow<'static, str>, Bytes>,
+    attr: &Attributes,
+    write_errs: &mut Vec<Error>,
+) -> usize {
+    let mut n = 0;
+    let mut pkt = p.clone();
+
+    for b in bindings {
+        if b.is_sender_paused() {
+            // See caveat in TrackLocalStaticRTP::write_rtp_with_extensions.
+            continue;
+        }
+        pkt.header.ssrc = b.ssrc;
+        pkt.header.payload_type = b.payload_type;
+
+        for ext in b.header_extensions() {
+            let payload = ext.payload.to_owned();
+            if let Err(err) = pkt.header.set_extension(ext.id, payload) {
+                write_errs.push(Error::Rtp(err));
             }
+        }
 
-            match b.write_stream.write_rtp_with_attributes(&pkt, attr).await {
-                Ok(m) => {
-                    n += m;
-                }
-                Err(err) => {
-                    write_errs.push(err);
+        for (uri, data) in extension_data.iter() {
+            if let Some(id) = b
+                .params
+                .header_extensions
+                .iter()
+                .find(|ext| &ext.uri == uri)
+                .map(|ext| ext.id)
+            {
+                if let Err(err) = pkt.header.set_extension(id as u8, data.clone()) {
+                    write_errs.push(Error::Rtp(err));
+                    continue;
                 }
             }
         }
 
-        flatten_errs(write_errs)?;
-        Ok(n)
+        match b.write_stream.write_rtp_with_attributes(&pkt, attr).await {
+            Ok(m) => {
+                n += m;
+            }
+            Err(err) => {
+                write_errs.push(err);
+            }
+        }
     }
+
+    n
 }
 
 #[async_trait]
@@ -175,46 +249,15 @@ impl TrackLocal for TrackLocalStaticRTP {
             capability: self.codec.clone(),
             ..Default::default()
         };
-        let mut hdr_ext_ids = vec![];
-        if let Some(id) = t
-            .header_extensions()
-            .iter()
-            .find(|e| e.uri == ::sdp::extmap::SDES_MID_URI)
-            .map(|e| e.id as u8)
-        {
-            if let Some(payload) = t
-                .mid
-                .as_ref()
-                .map(|mid| Bytes::copy_from_slice(mid.as_bytes()))
-            {
-                hdr_ext_ids.push(rtp::header::Extension { id, payload });
-            }
-        }
-
-        if let Some(id) = t
-            .header_extensions()
-            .iter()
-            .find(|e| e.uri == ::sdp::extmap::SDES_RTP_STREAM_ID_URI)
-            .map(|e| e.id as u8)
-        {
-            if let Some(payload) = self.rid().map(|rid| rid.to_owned().into()) {
-                hdr_ext_ids.push(rtp::header::Extension { id, payload });
-            }
-        }
-
         let (codec, match_type) = codec_parameters_fuzzy_search(&parameters, t.codec_parameters());
         if match_type != CodecMatch::None {
             {
                 let mut bindings = self.bindings.lock().await;
-                bindings.push(Arc::new(TrackBinding {
-                    id: t.id(),
-                    ssrc: t.ssrc(),
-                    payload_type: codec.payload_type,
-                    params: t.params.clone(),
-                    write_stream: t.write_stream(),
-                    sender_paused: t.paused.clone(),
-                    hdr_ext_ids,
-                }));
+                bindings.push(Arc::new(TrackBinding::new(
+                    t,
+                    codec.payload_type,
+                    self.rid(),
+                )));
             }
 
             Ok(codec)
@@ -270,6 +313,17 @@ impl TrackLocal for TrackLocalStaticRTP {
         }
     }
 
+    fn ssrc(&self) -> Option<SSRC> {
+        self.ssrc
+    }
+
+    async fn key_frame_requested(&self, request: KeyframeRequest, ssrc: SSRC) {
+        if let Some(handler) = &*self.on_key_frame_requested_handler.load() {
+            let mut f = handler.lock().await;
+            f(request, ssrc).await;
+        }
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
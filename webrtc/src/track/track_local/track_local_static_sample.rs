@@ -2,9 +2,12 @@ use log::warn;
 use media::Sample;
 use tokio::sync::Mutex;
 
-use super::track_local_static_rtp::TrackLocalStaticRTP;
+use super::track_local_static_rtp::{
+    marshal_extensions, write_rtp_to_bindings, TrackLocalStaticRTP,
+};
 use super::*;
 use crate::error::flatten_errs;
+use crate::rtp_transceiver::rtp_sender::OnKeyframeRequestHdlrFn;
 use crate::track::RTP_OUTBOUND_MTU;
 
 #[derive(Debug, Clone)]
@@ -12,6 +15,9 @@ struct TrackLocalStaticSampleInternal {
     packetizer: Option<Box<dyn rtp::packetizer::Packetizer + Send + Sync>>,
     sequencer: Option<Box<dyn rtp::sequence::Sequencer + Send + Sync>>,
     clock_rate: f64,
+    clock_rate_override: u32,
+    initial_sequence_number: Option<u16>,
+    initial_timestamp: Option<u32>,
     did_warn_about_wonky_pause: bool,
 }
 
@@ -34,6 +40,9 @@ impl TrackLocalStaticSample {
                 packetizer: None,
                 sequencer: None,
                 clock_rate: 0.0f64,
+                clock_rate_override: 0,
+                initial_sequence_number: None,
+                initial_timestamp: None,
                 did_warn_about_wonky_pause: false,
             }),
         }
@@ -54,22 +63,76 @@ impl TrackLocalStaticSample {
                 packetizer: None,
                 sequencer: None,
                 clock_rate: 0.0f64,
+                clock_rate_override: 0,
+                initial_sequence_number: None,
+                initial_timestamp: None,
                 did_warn_about_wonky_pause: false,
             }),
         }
     }
 
+    /// with_ssrc makes the RTPSender the track is added to send it with the given SSRC
+    /// instead of a random one, see [`TrackLocalStaticRTP::with_ssrc`]
+    pub fn with_ssrc(mut self, ssrc: SSRC) -> Self {
+        self.rtp_track = self.rtp_track.with_ssrc(ssrc);
+        self
+    }
+
+    /// with_initial_sequence_number makes the first packet of the track carry the given
+    /// RTP sequence number instead of a random one. Sequence numbers increase from it and
+    /// wrap around at u16::MAX, so a source swapped in can continue the previous one
+    pub fn with_initial_sequence_number(mut self, sequence_number: u16) -> Self {
+        self.internal.get_mut().initial_sequence_number = Some(sequence_number);
+        self
+    }
+
+    /// with_initial_timestamp makes the first sample of the track carry the given
+    /// RTP timestamp instead of a random one. Timestamps advance from it and wrap around
+    /// at u32::MAX
+    pub fn with_initial_timestamp(mut self, timestamp: u32) -> Self {
+        self.internal.get_mut().initial_timestamp = Some(timestamp);
+        self
+    }
+
+    /// on_key_frame_requested sets an event handler which is invoked when a remote asks for a
+    /// keyframe of the track, see [`TrackLocalStaticRTP::on_key_frame_requested`].
+    pub fn on_key_frame_requested(&self, f: OnKeyframeRequestHdlrFn) {
+        self.rtp_track.on_key_frame_requested(f);
+    }
+
     /// codec gets the Codec of the track
     pub fn codec(&self) -> RTCRtpCodecCapability {
         self.rtp_track.codec()
     }
 
+    /// set_clock_rate overrides the clock rate RTP timestamp increments are computed against,
+    /// for sources whose timestamps don't use the clock rate of the negotiated codec.
+    /// It takes effect with the next sample written. If it is set to 0 the clock rate of the
+    /// codec is used, which is the default
+    pub async fn set_clock_rate(&self, clock_rate: u32) {
+        let mut internal = self.internal.lock().await;
+        internal.clock_rate_override = clock_rate;
+    }
+
     /// write_sample writes a Sample to the TrackLocalStaticSample
     /// If one PeerConnection fails the packets will still be sent to
     /// all PeerConnections. The error message will contain the ID of the failed
     /// PeerConnections so you can remove them
     pub async fn write_sample(&self, sample: &Sample) -> Result<()> {
-        self.write_sample_with_extensions(sample, &[]).await
+        self.write_samples_with_extensions(std::slice::from_ref(sample), &[])
+            .await
+    }
+
+    /// write_samples writes a batch of Samples to the TrackLocalStaticSample, in order.
+    /// The packets and their timestamps are the same as writing the samples one at a time
+    /// with [`TrackLocalStaticSample::write_sample`], but the track state is locked and
+    /// the bindings of the track are looked up once for the whole batch.
+    ///
+    /// If one PeerConnection fails the remaining samples will still be sent to all
+    /// PeerConnections, the errors are returned once the batch is written. A sample failing
+    /// to packetize stops the batch, the samples before it have been written.
+    pub async fn write_samples(&self, samples: &[Sample]) -> Result<()> {
+        self.write_samples_with_extensions(samples, &[]).await
     }
 
     /// Write a sample with provided RTP extensions.
@@ -81,6 +144,15 @@ impl TrackLocalStaticSample {
         &self,
         sample: &Sample,
         extensions: &[rtp::extension::HeaderExtension],
+    ) -> Result<()> {
+        self.write_samples_with_extensions(std::slice::from_ref(sample), extensions)
+            .await
+    }
+
+    async fn write_samples_with_extensions(
+        &self,
+        samples: &[Sample],
+        extensions: &[rtp::extension::HeaderExtension],
     ) -> Result<()> {
         let mut internal = self.internal.lock().await;
 
@@ -88,9 +160,10 @@ impl TrackLocalStaticSample {
             return Ok(());
         }
 
+        let bindings = self.rtp_track.bindings().await;
         let (any_paused, all_paused) = (
-            self.rtp_track.any_binding_paused().await,
-            self.rtp_track.all_binding_paused().await,
+            bindings.iter().any(|b| b.is_sender_paused()),
+            bindings.iter().all(|b| b.is_sender_paused()),
         );
 
         if all_paused {
@@ -124,33 +197,43 @@ impl TrackLocalStaticSample {
             }
         }
 
-        // skip packets by the number of previously dropped packets
-        if let Some(sequencer) = &internal.sequencer {
-            for _ in 0..sample.prev_dropped_packets {
-                sequencer.next_sequence_number();
-            }
-        }
-
-        let clock_rate = internal.clock_rate;
-
-        let packets = if let Some(packetizer) = &mut internal.packetizer {
-            let samples = (sample.duration.as_secs_f64() * clock_rate) as u32;
-            if sample.prev_dropped_packets > 0 {
-                packetizer.skip_samples(samples * sample.prev_dropped_packets as u32);
+        let mut write_errs = vec![];
+        let extension_data = marshal_extensions(extensions, &mut write_errs);
+        let attr = Attributes::new();
+
+        for sample in samples {
+            // skip packets by the number of previously dropped packets
+            if let Some(sequencer) = &internal.sequencer {
+                for _ in 0..sample.prev_dropped_packets {
+                    sequencer.next_sequence_number();
+                }
             }
-            packetizer.packetize(&sample.data, samples)?
-        } else {
-            vec![]
-        };
 
-        let mut write_errs = vec![];
-        for p in packets {
-            if let Err(err) = self
-                .rtp_track
-                .write_rtp_with_extensions(&p, extensions)
-                .await
-            {
-                write_errs.push(err);
+            let clock_rate = if internal.clock_rate_override != 0 {
+                internal.clock_rate_override as f64
+            } else {
+                internal.clock_rate
+            };
+
+            let packets = if let Some(packetizer) = &mut internal.packetizer {
+                let samples = (sample.duration.as_secs_f64() * clock_rate) as u32;
+                if sample.prev_dropped_packets > 0 {
+                    packetizer.skip_samples(samples * sample.prev_dropped_packets as u32);
+                }
+                match packetizer.packetize(&sample.data, samples) {
+                    Ok(packets) => packets,
+                    Err(err) if write_errs.is_empty() => return Err(err.into()),
+                    Err(err) => {
+                        write_errs.push(err.into());
+                        break;
+                    }
+                }
+            } else {
+                vec![]
+            };
+
+            for p in &packets {
+                write_rtp_to_bindings(&bindings, p, &extension_data, &attr, &mut write_errs).await;
             }
         }
 
@@ -212,15 +295,22 @@ impl TrackLocal for TrackLocalStaticSample {
         }
 
         let payloader = codec.capability.payloader_for_codec()?;
-        let sequencer: Box<dyn rtp::sequence::Sequencer + Send + Sync> =
-            Box::new(rtp::sequence::new_random_sequencer());
-        internal.packetizer = Some(Box::new(rtp::packetizer::new_packetizer(
+        let sequencer: Box<dyn rtp::sequence::Sequencer + Send + Sync> = match internal
+            .initial_sequence_number
+        {
+            Some(sequence_number) => Box::new(rtp::sequence::new_fixed_sequencer(sequence_number)),
+            None => Box::new(rtp::sequence::new_random_sequencer()),
+        };
+        internal.packetizer = Some(Box::new(rtp::packetizer::new_packetizer_with_timestamp(
             RTP_OUTBOUND_MTU,
             0, // Value is handled when writing
             0, // Value is handled when writing
             payloader,
             sequencer.clone(),
             codec.capability.clock_rate,
+            internal
+                .initial_timestamp
+                .unwrap_or_else(rand::random::<u32>),
         )));
         internal.sequencer = Some(sequencer);
         internal.clock_rate = codec.capability.clock_rate as f64;
@@ -256,6 +346,14 @@ impl TrackLocal for TrackLocalStaticSample {
         self.rtp_track.kind()
     }
 
+    fn ssrc(&self) -> Option<SSRC> {
+        self.rtp_track.ssrc()
+    }
+
+    async fn key_frame_requested(&self, request: KeyframeRequest, ssrc: SSRC) {
+        self.rtp_track.key_frame_requested(request, ssrc).await
+    }
+
     fn as_any(&self) -> &dyn Any {
         self
     }
@@ -293,6 +391,15 @@ mod sample_writer {
             self.with_extension(HeaderExtension::AudioLevel(ext))
         }
 
+        /// Add a RTP audio level extension computed from the 16-bit PCM samples the
+        /// sample was encoded from to all packets written for the sample, see
+        /// [`AudioLevelExtension::from_pcm`].
+        ///
+        /// This overwrites any previously configured audio level extension.
+        pub fn with_audio_level_from_pcm(self, pcm: &[i16]) -> Self {
+            self.with_audio_level(AudioLevelExtension::from_pcm(pcm))
+        }
+
         /// Add a RTP video orientation extension to all packets written for the sample.
         ///
         /// This overwrites any previously configured video orientation extension.
@@ -1,15 +1,20 @@
 use std::sync::Arc;
 
 use bytes::Bytes;
+use rtcp::payload_feedbacks::full_intra_request::{FirEntry, FullIntraRequest};
+use rtcp::payload_feedbacks::picture_loss_indication::PictureLossIndication;
 use tokio::sync::{mpsc, Mutex};
+use tokio::time::Duration;
 
+use super::track_local_fanout::*;
 use super::track_local_static_rtp::*;
 use super::track_local_static_sample::*;
 use super::*;
-use crate::api::media_engine::{MediaEngine, MIME_TYPE_VP8};
+use crate::api::media_engine::{MediaEngine, MIME_TYPE_OPUS, MIME_TYPE_VP8};
 use crate::api::APIBuilder;
 use crate::peer_connection::configuration::RTCConfiguration;
 use crate::peer_connection::peer_connection_test::*;
+use crate::rtp_transceiver::rtp_sender::KeyframeRequest;
 
 // If a remote doesn't support a Codec used by a `TrackLocalStatic`
 // an error should be returned to the user
@@ -406,6 +411,357 @@ async fn test_track_local_static_binding_non_blocking() -> Result<()> {
     Ok(())
 }
 
+// Records the packets a track writes instead of sending them
+#[derive(Debug, Default)]
+struct RecordingWriter {
+    packets: Mutex<Vec<rtp::packet::Packet>>,
+}
+
+#[async_trait]
+impl TrackLocalWriter for RecordingWriter {
+    async fn write_rtp_with_attributes(
+        &self,
+        pkt: &rtp::packet::Packet,
+        _attr: &Attributes,
+    ) -> Result<usize> {
+        self.packets.lock().await.push(pkt.clone());
+        Ok(pkt.payload.len())
+    }
+
+    async fn write(&self, b: &[u8]) -> Result<usize> {
+        Ok(b.len())
+    }
+}
+
+// The mid header extension is sent until the remote acknowledged the stream
+#[tokio::test]
+async fn test_track_local_static_rtp_mid_until_acked() -> Result<()> {
+    let codec = RTCRtpCodecCapability {
+        mime_type: MIME_TYPE_VP8.to_owned(),
+        clock_rate: 90000,
+        ..Default::default()
+    };
+    let track = TrackLocalStaticRTP::new(codec.clone(), "video".to_owned(), "webrtc-rs".to_owned());
+
+    let writer = Arc::new(RecordingWriter::default());
+    let mid_acked = Arc::new(AtomicBool::new(false));
+    let ctx = TrackLocalContext {
+        id: "id".to_owned(),
+        params: RTCRtpParameters {
+            header_extensions: vec![RTCRtpHeaderExtensionParameters {
+                uri: ::sdp::extmap::SDES_MID_URI.to_owned(),
+                id: 4,
+            }],
+            codecs: vec![RTCRtpCodecParameters {
+                capability: codec,
+                payload_type: 96,
+                ..Default::default()
+            }],
+        },
+        ssrc: 1,
+        write_stream: Arc::clone(&writer) as Arc<dyn TrackLocalWriter + Send + Sync>,
+        paused: Arc::new(AtomicBool::new(false)),
+        mid: Some("0".into()),
+        mid_acked: Arc::clone(&mid_acked),
+    };
+    track.bind(&ctx).await?;
+
+    let pkt = rtp::packet::Packet {
+        header: rtp::header::Header {
+            version: 2,
+            ..Default::default()
+        },
+        payload: Bytes::from_static(&[0x00]),
+    };
+    track.write_rtp(&pkt).await?;
+    mid_acked.store(true, Ordering::SeqCst);
+    track.write_rtp(&pkt).await?;
+
+    let mids: Vec<Option<Bytes>> = writer
+        .packets
+        .lock()
+        .await
+        .iter()
+        .map(|p| p.header.get_extension(4))
+        .collect();
+    assert_eq!(mids, vec![Some(Bytes::from_static(b"0")), None]);
+
+    Ok(())
+}
+
+// Timestamps advance with the codec clock rate unless it is overridden
+#[tokio::test]
+async fn test_track_local_static_sample_clock_rate() -> Result<()> {
+    let codec = RTCRtpCodecCapability {
+        mime_type: MIME_TYPE_VP8.to_owned(),
+        clock_rate: 90000,
+        ..Default::default()
+    };
+    let track =
+        TrackLocalStaticSample::new(codec.clone(), "video".to_owned(), "webrtc-rs".to_owned());
+
+    let writer = Arc::new(RecordingWriter::default());
+    let ctx = TrackLocalContext {
+        id: "id".to_owned(),
+        params: RTCRtpParameters {
+            codecs: vec![RTCRtpCodecParameters {
+                capability: codec,
+                payload_type: 96,
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        ssrc: 1,
+        write_stream: Arc::clone(&writer) as Arc<dyn TrackLocalWriter + Send + Sync>,
+        paused: Arc::new(AtomicBool::new(false)),
+        mid: None,
+        mid_acked: Arc::new(AtomicBool::new(false)),
+    };
+    track.bind(&ctx).await?;
+
+    let sample = media::Sample {
+        data: Bytes::from_static(&[0x00]),
+        duration: Duration::from_millis(20),
+        ..Default::default()
+    };
+    track.write_sample(&sample).await?;
+    track.write_sample(&sample).await?;
+    track.set_clock_rate(48000).await;
+    track.write_sample(&sample).await?;
+    track.set_clock_rate(0).await;
+    track.write_sample(&sample).await?;
+
+    let timestamps: Vec<u32> = writer
+        .packets
+        .lock()
+        .await
+        .iter()
+        .map(|p| p.header.timestamp)
+        .collect();
+    assert_eq!(timestamps.len(), 4);
+    // A packet carries the timestamp of its sample, the increment is applied to the next one
+    let increments: Vec<u32> = timestamps
+        .windows(2)
+        .map(|w| w[1].wrapping_sub(w[0]))
+        .collect();
+    assert_eq!(increments, vec![1800, 1800, 960]);
+
+    Ok(())
+}
+
+// The first sample carries the initial sequence number and timestamp, which then
+// advance and wrap around
+#[tokio::test]
+async fn test_track_local_static_sample_initial_state() -> Result<()> {
+    let codec = RTCRtpCodecCapability {
+        mime_type: MIME_TYPE_VP8.to_owned(),
+        clock_rate: 90000,
+        ..Default::default()
+    };
+    let track =
+        TrackLocalStaticSample::new(codec.clone(), "video".to_owned(), "webrtc-rs".to_owned())
+            .with_initial_sequence_number(u16::MAX)
+            .with_initial_timestamp(u32::MAX - 899);
+
+    let writer = Arc::new(RecordingWriter::default());
+    let ctx = TrackLocalContext {
+        id: "id".to_owned(),
+        params: RTCRtpParameters {
+            codecs: vec![RTCRtpCodecParameters {
+                capability: codec,
+                payload_type: 96,
+                ..Default::default()
+            }],
+            ..Default::default()
+        },
+        ssrc: 1,
+        write_stream: Arc::clone(&writer) as Arc<dyn TrackLocalWriter + Send + Sync>,
+        paused: Arc::new(AtomicBool::new(false)),
+        mid: None,
+        mid_acked: Arc::new(AtomicBool::new(false)),
+    };
+    track.bind(&ctx).await?;
+
+    let sample = media::Sample {
+        data: Bytes::from_static(&[0x00]),
+        duration: Duration::from_millis(20),
+        ..Default::default()
+    };
+    track.write_sample(&sample).await?;
+    track.write_sample(&sample).await?;
+
+    let headers: Vec<(u16, u32)> = writer
+        .packets
+        .lock()
+        .await
+        .iter()
+        .map(|p| (p.header.sequence_number, p.header.timestamp))
+        .collect();
+    assert_eq!(headers, vec![(u16::MAX, u32::MAX - 899), (0, 900)]);
+
+    Ok(())
+}
+
+// A batch of samples is packetized and timestamped the same as the samples written
+// one at a time
+#[tokio::test]
+async fn test_track_local_static_sample_write_samples() -> Result<()> {
+    let codec = RTCRtpCodecCapability {
+        mime_type: MIME_TYPE_OPUS.to_owned(),
+        clock_rate: 48000,
+        channels: 2,
+        ..Default::default()
+    };
+
+    let samples: Vec<media::Sample> = (0..10u8)
+        .map(|i| media::Sample {
+            data: Bytes::from(vec![i; 3]),
+            duration: Duration::from_millis(if i < 5 { 20 } else { 10 }),
+            prev_dropped_packets: if i == 7 { 2 } else { 0 },
+            ..Default::default()
+        })
+        .collect();
+
+    let mut written = vec![];
+    for batch in [false, true] {
+        let track =
+            TrackLocalStaticSample::new(codec.clone(), "audio".to_owned(), "webrtc-rs".to_owned())
+                .with_initial_sequence_number(100)
+                .with_initial_timestamp(1000);
+
+        let writer = Arc::new(RecordingWriter::default());
+        let ctx = TrackLocalContext {
+            id: "id".to_owned(),
+            params: RTCRtpParameters {
+                codecs: vec![RTCRtpCodecParameters {
+                    capability: codec.clone(),
+                    payload_type: 111,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ssrc: 1,
+            write_stream: Arc::clone(&writer) as Arc<dyn TrackLocalWriter + Send + Sync>,
+            paused: Arc::new(AtomicBool::new(false)),
+            mid: None,
+            mid_acked: Arc::new(AtomicBool::new(false)),
+        };
+        track.bind(&ctx).await?;
+
+        if batch {
+            track.write_samples(&samples).await?;
+        } else {
+            for sample in &samples {
+                track.write_sample(sample).await?;
+            }
+        }
+
+        let packets: Vec<(u16, u32, Bytes)> = writer
+            .packets
+            .lock()
+            .await
+            .iter()
+            .map(|p| {
+                (
+                    p.header.sequence_number,
+                    p.header.timestamp,
+                    p.payload.clone(),
+                )
+            })
+            .collect();
+        written.push(packets);
+    }
+
+    assert_eq!(written[0].len(), 10);
+    assert_eq!(written[0], written[1]);
+    assert_eq!(written[1][0], (100, 1000, Bytes::from(vec![0; 3])));
+    // Dropped packets skip sequence numbers and timestamps
+    assert_eq!(written[1][7].0, 109);
+    assert_eq!(written[1][7].1, 1000 + 5 * 960 + 2 * 480 + 2 * 480);
+
+    Ok(())
+}
+
+// Every binding of a fanout track rewrites the header of its own copy of the
+// packet, sharing the payload, and stays contiguous across a pause
+#[tokio::test]
+async fn test_track_local_fanout() -> Result<()> {
+    let codec = RTCRtpCodecCapability {
+        mime_type: MIME_TYPE_VP8.to_owned(),
+        clock_rate: 90000,
+        ..Default::default()
+    };
+    let track = TrackLocalFanout::new(codec.clone(), "video".to_owned(), "webrtc-rs".to_owned());
+
+    let mut bindings = vec![];
+    for ssrc in 1..=3 {
+        let writer = Arc::new(RecordingWriter::default());
+        let ctx = TrackLocalContext {
+            id: format!("id{ssrc}"),
+            params: RTCRtpParameters {
+                codecs: vec![RTCRtpCodecParameters {
+                    capability: codec.clone(),
+                    payload_type: 95 + ssrc as u8,
+                    ..Default::default()
+                }],
+                ..Default::default()
+            },
+            ssrc,
+            write_stream: Arc::clone(&writer) as Arc<dyn TrackLocalWriter + Send + Sync>,
+            paused: Arc::new(AtomicBool::new(false)),
+            mid: None,
+            mid_acked: Arc::new(AtomicBool::new(false)),
+        };
+        track.bind(&ctx).await?;
+        bindings.push((ctx, writer));
+    }
+    assert_eq!(track.binding_count().await, 3);
+
+    let payload = Bytes::from_static(&[0xAA; 100]);
+    for sequence_number in 0..6u16 {
+        // the second binding misses the packets 2 and 3
+        bindings[1].0.paused.store(
+            sequence_number == 2 || sequence_number == 3,
+            Ordering::SeqCst,
+        );
+        let pkt = rtp::packet::Packet {
+            header: rtp::header::Header {
+                version: 2,
+                sequence_number: sequence_number.wrapping_sub(3),
+                ssrc: 0xDEAD,
+                payload_type: 111,
+                ..Default::default()
+            },
+            payload: payload.clone(),
+        };
+        track.write_rtp(&pkt).await?;
+    }
+
+    for (ssrc, (ctx, writer)) in (1..=3).zip(&bindings) {
+        let packets = writer.packets.lock().await;
+        let expected = if ssrc == 2 { 4 } else { 6 };
+        assert_eq!(packets.len(), expected, "ssrc {ssrc}");
+        for (i, p) in packets.iter().enumerate() {
+            assert_eq!(p.header.ssrc, ctx.ssrc);
+            assert_eq!(p.header.payload_type, 95 + ssrc as u8);
+            assert_eq!(p.payload.as_ptr(), payload.as_ptr());
+            assert_eq!(
+                p.header.sequence_number,
+                packets[0].header.sequence_number.wrapping_add(i as u16)
+            );
+        }
+    }
+
+    track.unbind(&bindings[0].0).await?;
+    assert_eq!(track.binding_count().await, 2);
+    assert_eq!(
+        track.unbind(&bindings[0].0).await,
+        Err(Error::ErrUnbindFailed)
+    );
+
+    Ok(())
+}
+
 /*
 //TODO: func BenchmarkTrackLocalWrite(b *testing.B) {
     offerPC, answerPC, err := newPair()
@@ -432,3 +788,164 @@ async fn test_track_local_static_binding_non_blocking() -> Result<()> {
     }
 }
 */
+
+// Assert that the packets written to a TrackLocalStaticRTP are sent with the
+// sequence numbers and timestamps they were written with
+#[tokio::test]
+async fn test_track_local_static_rtp_keeps_timing() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticRTP::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+    pc_offer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (received_tx, mut received_rx) = mpsc::channel::<(u16, u32)>(16);
+    pc_answer.on_track(Box::new(move |track, _, _| {
+        let received_tx = received_tx.clone();
+        Box::pin(async move {
+            while let Ok((pkt, _)) = track.read_rtp().await {
+                let header = (pkt.header.sequence_number, pkt.header.timestamp);
+                if received_tx.send(header).await.is_err() {
+                    break;
+                }
+            }
+        })
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+
+    // Uneven gaps between the timestamps, as a pacer delaying some packets would write
+    let timestamp = |sequence_number: u16| 5000 + u32::from(sequence_number) * 3001;
+    let mut received = vec![];
+    let mut sequence_number = 65000u16;
+    while received.len() < 5 {
+        track
+            .write_rtp(&rtp::packet::Packet {
+                header: rtp::header::Header {
+                    version: 2,
+                    sequence_number,
+                    timestamp: timestamp(sequence_number),
+                    ..Default::default()
+                },
+                payload: Bytes::from_static(&[0x00]),
+            })
+            .await?;
+        sequence_number = sequence_number.wrapping_add(1);
+
+        if let Ok(Some(header)) =
+            tokio::time::timeout(Duration::from_millis(20), received_rx.recv()).await
+        {
+            received.push(header);
+        }
+    }
+
+    for (sequence_number, ts) in received {
+        assert_eq!(ts, timestamp(sequence_number));
+    }
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_track_local_static_sample_on_key_frame_requested() -> Result<()> {
+    let mut m = MediaEngine::default();
+    m.register_default_codecs()?;
+    let api = APIBuilder::new().with_media_engine(m).build();
+
+    let (mut pc_offer, mut pc_answer) = new_pair(&api).await?;
+
+    let track = Arc::new(TrackLocalStaticSample::new(
+        RTCRtpCodecCapability {
+            mime_type: MIME_TYPE_VP8.to_owned(),
+            ..Default::default()
+        },
+        "video".to_owned(),
+        "webrtc-rs".to_owned(),
+    ));
+
+    let (request_tx, mut request_rx) = mpsc::channel(3);
+    track.on_key_frame_requested(Box::new(move |request, ssrc| {
+        let request_tx = request_tx.clone();
+        Box::pin(async move {
+            let _ = request_tx.send((request, ssrc)).await;
+        })
+    }));
+
+    pc_offer
+        .add_track(Arc::clone(&track) as Arc<dyn TrackLocal + Send + Sync>)
+        .await?;
+
+    let (ssrc_tx, mut ssrc_rx) = mpsc::channel(1);
+    let (seen_packet_tx, seen_packet_rx) = mpsc::channel::<()>(1);
+    pc_answer.on_track(Box::new(move |track, _, _| {
+        let ssrc_tx = ssrc_tx.clone();
+        let seen_packet_tx = seen_packet_tx.clone();
+        Box::pin(async move {
+            if track.read_rtp().await.is_ok() {
+                let _ = ssrc_tx.send(track.ssrc()).await;
+                let _ = seen_packet_tx.send(()).await;
+            }
+        })
+    }));
+
+    signal_pair(&mut pc_offer, &mut pc_answer).await?;
+
+    send_video_until_done(
+        seen_packet_rx,
+        vec![Arc::clone(&track)],
+        Bytes::from_static(&[0xAA]),
+        None,
+    )
+    .await;
+    let ssrc = ssrc_rx.recv().await.unwrap();
+
+    // Only the requests for the SSRC of the track reach it
+    pc_answer
+        .write_rtcp(&[
+            Box::new(PictureLossIndication {
+                sender_ssrc: 0,
+                media_ssrc: ssrc.wrapping_add(1),
+            }),
+            Box::new(PictureLossIndication {
+                sender_ssrc: 0,
+                media_ssrc: ssrc,
+            }),
+            Box::new(FullIntraRequest {
+                sender_ssrc: 0,
+                media_ssrc: ssrc,
+                fir: vec![FirEntry {
+                    ssrc,
+                    sequence_number: 1,
+                }],
+            }),
+        ])
+        .await?;
+
+    let timeout = Duration::from_secs(5);
+    let first = tokio::time::timeout(timeout, request_rx.recv())
+        .await
+        .expect("the track should be asked for a keyframe");
+    let second = tokio::time::timeout(timeout, request_rx.recv())
+        .await
+        .expect("the track should be asked for a keyframe");
+    assert_eq!(first, Some((KeyframeRequest::PictureLoss, ssrc)));
+    assert_eq!(second, Some((KeyframeRequest::FullIntra, ssrc)));
+    assert!(request_rx.try_recv().is_err());
+
+    close_pair_now(&pc_offer, &pc_answer).await;
+    Ok(())
+}
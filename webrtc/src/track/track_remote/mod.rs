@@ -1,15 +1,26 @@
+#[cfg(test)]
+mod track_remote_test;
+
+pub mod rate_estimator;
+
 use std::collections::VecDeque;
 use std::future::Future;
 use std::pin::Pin;
 use std::sync::atomic::Ordering;
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 
 use arc_swap::ArcSwapOption;
 use interceptor::{Attributes, Interceptor};
 use portable_atomic::{AtomicU32, AtomicU8, AtomicUsize};
+use rate_estimator::RateEstimator;
+use rtp::extension::audio_level_extension::AudioLevelExtension;
+use rtp::extension::playout_delay_extension::PlayoutDelayExtension;
 use smol_str::SmolStr;
 use tokio::sync::Mutex;
+use tokio::time::Instant;
 use util::sync::Mutex as SyncMutex;
+use util::{MarshalSize, Unmarshal};
 
 use crate::api::media_engine::MediaEngine;
 use crate::error::{Error, Result};
@@ -17,6 +28,16 @@ use crate::rtp_transceiver::rtp_codec::{RTCRtpCodecParameters, RTCRtpParameters,
 use crate::rtp_transceiver::rtp_receiver::RTPReceiverInternal;
 use crate::rtp_transceiver::{PayloadType, SSRC};
 
+/// Attributes key under which [`TrackRemote::read`] reports the minimum playout delay, in
+/// milliseconds, requested by the remote with the playout-delay header extension.
+pub const ATTR_KEY_PLAYOUT_DELAY_MIN: usize = 0x5044_4d4e;
+/// Attributes key under which [`TrackRemote::read`] reports the maximum playout delay, in
+/// milliseconds, requested by the remote with the playout-delay header extension.
+pub const ATTR_KEY_PLAYOUT_DELAY_MAX: usize = 0x5044_4d58;
+
+/// Playout delays are signaled in units of 10 milliseconds.
+const PLAYOUT_DELAY_GRANULARITY_MS: usize = 10;
+
 lazy_static! {
     static ref TRACK_REMOTE_UNIQUE_ID: AtomicUsize = AtomicUsize::new(0);
 }
@@ -30,6 +51,22 @@ struct Handlers {
     on_unmute: ArcSwapOption<Mutex<OnMuteHdlrFn>>,
 }
 
+/// SenderReportInfo is the mapping between the NTP wallclock and the RTP timestamps of a
+/// track that the remote sent in its latest RTCP Sender Report (RFC 3550 section 6.4.1).
+/// Mapping the RTP timestamps of several tracks of the remote to its wallclock synchronizes
+/// them, e.g. audio and video.
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct SenderReportInfo {
+    /// wallclock time of the report, in the 64 bits NTP format
+    pub ntp_time: u64,
+    /// RTP timestamp corresponding to ntp_time
+    pub rtp_time: u32,
+    /// number of RTP packets sent by the remote since the start of the stream
+    pub packet_count: u32,
+    /// number of payload bytes sent by the remote since the start of the stream
+    pub octet_count: u32,
+}
+
 #[derive(Default)]
 struct TrackRemoteInternal {
     peeked: VecDeque<(rtp::packet::Packet, Attributes)>,
@@ -57,6 +94,9 @@ pub struct TrackRemote {
 
     receiver: Option<Weak<RTPReceiverInternal>>,
     internal: Mutex<TrackRemoteInternal>,
+    rate_estimator: SyncMutex<RateEstimator>,
+    last_sender_report: SyncMutex<Option<SenderReportInfo>>,
+    goodbye_reason: SyncMutex<Option<String>>,
 }
 
 impl std::fmt::Debug for TrackRemote {
@@ -101,6 +141,9 @@ impl TrackRemote {
             handlers: Default::default(),
 
             internal: Default::default(),
+            rate_estimator: Default::default(),
+            last_sender_report: Default::default(),
+            goodbye_reason: Default::default(),
         }
     }
 
@@ -210,16 +253,93 @@ impl TrackRemote {
             .store(Some(Arc::new(Mutex::new(Box::new(handler)))));
     }
 
+    /// last_sender_report returns the latest RTCP Sender Report received for the track.
+    /// The RTCP packets are processed as the application reads them from the RTPReceiver
+    /// of the track, like for the interceptors, so it is None until a Sender Report was read.
+    pub fn last_sender_report(&self) -> Option<SenderReportInfo> {
+        *self.last_sender_report.lock()
+    }
+
+    pub(crate) fn set_last_sender_report(&self, sr: &rtcp::sender_report::SenderReport) {
+        *self.last_sender_report.lock() = Some(SenderReportInfo {
+            ntp_time: sr.ntp_time,
+            rtp_time: sr.rtp_time,
+            packet_count: sr.packet_count,
+            octet_count: sr.octet_count,
+        });
+    }
+
+    /// goodbye_reason returns the reason of the RTCP BYE (RFC 3550 section 6.6) the remote
+    /// sent for the track, which is empty if it gave none. It is None until a BYE was read
+    /// from the RTPReceiver of the track. The remote sends a BYE when it stops sending the
+    /// track, so the application can release it without waiting for a timeout.
+    pub fn goodbye_reason(&self) -> Option<String> {
+        self.goodbye_reason.lock().clone()
+    }
+
+    pub(crate) fn set_goodbye_reason(&self, reason: &[u8]) {
+        *self.goodbye_reason.lock() = Some(String::from_utf8_lossy(reason).into_owned());
+    }
+
+    /// current_bitrate returns the bitrate of the track in bits per second,
+    /// estimated from the size of the RTP packets read over the rate
+    /// estimation window.
+    pub fn current_bitrate(&self) -> u64 {
+        self.rate_estimator.lock().bitrate(Instant::now())
+    }
+
+    /// current_framerate returns the frames per second of the track, estimated
+    /// from the frames read over the rate estimation window. A frame ends with
+    /// a packet that has the marker bit set, so the estimate is only meaningful
+    /// for video.
+    pub fn current_framerate(&self) -> f64 {
+        let clock_rate = self.codec().capability.clock_rate;
+        self.rate_estimator
+            .lock()
+            .framerate(Instant::now(), clock_rate)
+    }
+
+    /// rate_estimation_window returns the sliding window over which
+    /// current_bitrate and current_framerate are estimated.
+    pub fn rate_estimation_window(&self) -> Duration {
+        self.rate_estimator.lock().window()
+    }
+
+    /// set_rate_estimation_window sets the sliding window over which
+    /// current_bitrate and current_framerate are estimated, a longer window
+    /// gives smoother but slower reacting estimates. It defaults to
+    /// [`rate_estimator::DEFAULT_RATE_ESTIMATION_WINDOW`].
+    pub fn set_rate_estimation_window(&self, window: Duration) {
+        self.rate_estimator.lock().set_window(window);
+    }
+
     /// Reads data from the track.
     ///
     /// **Cancel Safety:** This method is not cancel safe. Dropping the resulting [`Future`] before
     /// it returns [`std::task::Poll::Ready`] will cause data loss.
     pub async fn read(&self, b: &mut [u8]) -> Result<(rtp::packet::Packet, Attributes)> {
+        let (pkt, attributes) = self.read_packet(b).await?;
+        self.rate_estimator.lock().record(
+            Instant::now(),
+            pkt.marshal_size(),
+            pkt.header.marker,
+            pkt.header.timestamp,
+        );
+        if let Some(receiver) = self.receiver.as_ref().and_then(|r| r.upgrade()) {
+            receiver.record_sources(&pkt.header, self.audio_level(&pkt));
+        }
+        Ok((pkt, attributes))
+    }
+
+    /// read_packet reads the next packet, either a peeked one or one from the
+    /// receiver, without accounting it in the rate estimation.
+    async fn read_packet(&self, b: &mut [u8]) -> Result<(rtp::packet::Packet, Attributes)> {
         {
             // Internal lock scope
             let mut internal = self.internal.lock().await;
-            if let Some((pkt, attributes)) = internal.peeked.pop_front() {
+            if let Some((pkt, mut attributes)) = internal.peeked.pop_front() {
                 self.check_and_update_track(&pkt).await?;
+                self.add_playout_delay_attributes(&pkt, &mut attributes);
 
                 return Ok((pkt, attributes));
             }
@@ -230,11 +350,53 @@ impl TrackRemote {
             None => return Err(Error::ErrRTPReceiverNil),
         };
 
-        let (pkt, attributes) = receiver.read_rtp(b, self.tid).await?;
+        let (pkt, mut attributes) = receiver.read_rtp(b, self.tid).await?;
         self.check_and_update_track(&pkt).await?;
+        self.add_playout_delay_attributes(&pkt, &mut attributes);
         Ok((pkt, attributes))
     }
 
+    /// add_playout_delay_attributes reports the playout delay carried by the packet, if the
+    /// playout-delay header extension was negotiated and is present.
+    fn add_playout_delay_attributes(&self, pkt: &rtp::packet::Packet, attributes: &mut Attributes) {
+        let id = self.header_extension_id(::sdp::extmap::PLAYOUT_DELAY_URI);
+        let mut payload = match id.and_then(|id| pkt.header.get_extension(id)) {
+            Some(payload) => payload,
+            None => return,
+        };
+
+        if let Ok(playout_delay) = PlayoutDelayExtension::unmarshal(&mut payload) {
+            attributes.insert(
+                ATTR_KEY_PLAYOUT_DELAY_MIN,
+                playout_delay.min_delay as usize * PLAYOUT_DELAY_GRANULARITY_MS,
+            );
+            attributes.insert(
+                ATTR_KEY_PLAYOUT_DELAY_MAX,
+                playout_delay.max_delay as usize * PLAYOUT_DELAY_GRANULARITY_MS,
+            );
+        }
+    }
+
+    /// audio_level returns the -dBov level of the ssrc-audio-level header
+    /// extension of the packet, if it was negotiated and is present.
+    fn audio_level(&self, pkt: &rtp::packet::Packet) -> Option<u8> {
+        let id = self.header_extension_id(::sdp::extmap::AUDIO_LEVEL_URI)?;
+        let mut payload = pkt.header.get_extension(id)?;
+        AudioLevelExtension::unmarshal(&mut payload)
+            .ok()
+            .map(|ext| ext.level)
+    }
+
+    /// header_extension_id returns the negotiated id of the header extension
+    fn header_extension_id(&self, uri: &str) -> Option<u8> {
+        let params = self.params.lock();
+        params
+            .header_extensions
+            .iter()
+            .find(|ext| ext.uri == uri)
+            .map(|ext| ext.id as u8)
+    }
+
     /// check_and_update_track checks payloadType for every incoming packet
     /// once a different payloadType is detected the track will be updated
     pub(crate) async fn check_and_update_track(&self, pkt: &rtp::packet::Packet) -> Result<()> {
@@ -278,7 +440,7 @@ impl TrackRemote {
 
     /// peek is like Read, but it doesn't discard the packet read
     pub(crate) async fn peek(&self, b: &mut [u8]) -> Result<(rtp::packet::Packet, Attributes)> {
-        let (pkt, a) = self.read(b).await?;
+        let (pkt, a) = self.read_packet(b).await?;
 
         // this might overwrite data if somebody peeked between the Read
         // and us getting the lock.  Oh well, we'll just drop a packet in
@@ -0,0 +1,112 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use tokio::time::Instant;
+
+/// The window used to estimate the rates of a TrackRemote by default
+pub const DEFAULT_RATE_ESTIMATION_WINDOW: Duration = Duration::from_secs(1);
+
+/// RateEstimator estimates the bitrate and framerate of an RTP stream from the
+/// packets observed over a sliding window.
+#[derive(Debug)]
+pub(crate) struct RateEstimator {
+    window: Duration,
+    first_arrival: Option<Instant>,
+    /// arrival time and size of every packet in the window
+    packets: VecDeque<(Instant, usize)>,
+    packets_size: usize,
+    /// arrival time and RTP timestamp of every frame completed in the window
+    frames: VecDeque<(Instant, u32)>,
+}
+
+impl Default for RateEstimator {
+    fn default() -> Self {
+        RateEstimator {
+            window: DEFAULT_RATE_ESTIMATION_WINDOW,
+            first_arrival: None,
+            packets: VecDeque::new(),
+            packets_size: 0,
+            frames: VecDeque::new(),
+        }
+    }
+}
+
+impl RateEstimator {
+    pub(crate) fn window(&self) -> Duration {
+        self.window
+    }
+
+    pub(crate) fn set_window(&mut self, window: Duration) {
+        self.window = window;
+    }
+
+    /// record observes a packet of size bytes, a packet with the marker bit set
+    /// completes the frame with the given RTP timestamp.
+    pub(crate) fn record(&mut self, now: Instant, size: usize, marker: bool, timestamp: u32) {
+        self.first_arrival.get_or_insert(now);
+        self.packets.push_back((now, size));
+        self.packets_size += size;
+        if marker {
+            self.frames.push_back((now, timestamp));
+        }
+        self.expire(now);
+    }
+
+    /// bitrate returns the estimated bitrate in bits per second
+    pub(crate) fn bitrate(&mut self, now: Instant) -> u64 {
+        self.expire(now);
+
+        // Until a whole window has passed only the time since the first packet counts
+        let elapsed = match self.first_arrival {
+            Some(first_arrival) => now.duration_since(first_arrival).min(self.window),
+            None => return 0,
+        };
+        if elapsed.is_zero() {
+            return 0;
+        }
+
+        (self.packets_size as f64 * 8.0 / elapsed.as_secs_f64()) as u64
+    }
+
+    /// framerate returns the estimated frames per second. The frame interval is
+    /// taken from the RTP timestamps when clock_rate is known, which is immune
+    /// to the jitter of the arrival times.
+    pub(crate) fn framerate(&mut self, now: Instant, clock_rate: u32) -> f64 {
+        self.expire(now);
+
+        let (first, last) = match (self.frames.front(), self.frames.back()) {
+            (Some(first), Some(last)) if self.frames.len() >= 2 => (first, last),
+            _ => return 0.0,
+        };
+
+        let intervals = (self.frames.len() - 1) as f64;
+        let ticks = last.1.wrapping_sub(first.1);
+        if clock_rate != 0 && ticks != 0 {
+            return intervals * clock_rate as f64 / ticks as f64;
+        }
+
+        let elapsed = last.0.duration_since(first.0);
+        if elapsed.is_zero() {
+            0.0
+        } else {
+            intervals / elapsed.as_secs_f64()
+        }
+    }
+
+    /// expire drops what arrived before the window
+    fn expire(&mut self, now: Instant) {
+        while let Some(&(arrival, size)) = self.packets.front() {
+            if now.duration_since(arrival) < self.window {
+                break;
+            }
+            self.packets.pop_front();
+            self.packets_size -= size;
+        }
+        while let Some(&(arrival, _)) = self.frames.front() {
+            if now.duration_since(arrival) < self.window {
+                break;
+            }
+            self.frames.pop_front();
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use super::rate_estimator::*;
+use super::*;
+
+#[test]
+fn test_rate_estimator_bitrate() {
+    let start = Instant::now();
+    let mut e = RateEstimator::default();
+    assert_eq!(e.bitrate(start), 0);
+
+    // 1000 bytes every 10ms is 800kbps
+    for i in 0..200 {
+        e.record(start + Duration::from_millis(i * 10), 1000, false, 0);
+    }
+    let now = start + Duration::from_millis(1990);
+    assert_eq!(e.bitrate(now), 800_000);
+
+    // the first half second only counts the time since the first packet
+    let mut e = RateEstimator::default();
+    for i in 0..50 {
+        e.record(start + Duration::from_millis(i * 10), 1000, false, 0);
+    }
+    assert_eq!(e.bitrate(start + Duration::from_millis(500)), 800_000);
+
+    // packets expire once the stream stops
+    assert_eq!(e.bitrate(start + Duration::from_secs(5)), 0);
+}
+
+#[test]
+fn test_rate_estimator_window() {
+    let start = Instant::now();
+    let mut e = RateEstimator::default();
+    assert_eq!(e.window(), DEFAULT_RATE_ESTIMATION_WINDOW);
+
+    e.set_window(Duration::from_millis(100));
+    for i in 0..100 {
+        // the bitrate doubles for the last 100ms
+        let size = if i < 90 { 1000 } else { 2000 };
+        e.record(start + Duration::from_millis(i * 10), size, false, 0);
+    }
+    let now = start + Duration::from_millis(995);
+    assert_eq!(e.bitrate(now), 1_600_000);
+}
+
+#[test]
+fn test_rate_estimator_framerate() {
+    let start = Instant::now();
+    let mut e = RateEstimator::default();
+    assert_eq!(e.framerate(start, 90000), 0.0);
+
+    // 30fps video with 3 packets per frame, arriving with jitter
+    for frame in 0..30u64 {
+        let timestamp = 1000 + frame as u32 * 3000;
+        let arrival = start + Duration::from_millis(frame * 33 + frame % 3 * 5);
+        for packet in 0..3 {
+            e.record(arrival, 1200, packet == 2, timestamp);
+        }
+    }
+    let now = start + Duration::from_millis(990);
+    assert_eq!(e.framerate(now, 90000), 30.0);
+
+    // without a clock rate the arrival times are used
+    let framerate = e.framerate(now, 0);
+    assert!((29.0..=31.0).contains(&framerate), "{framerate}");
+}
+
+#[test]
+fn test_rate_estimator_framerate_timestamp_wraparound() {
+    let start = Instant::now();
+    let mut e = RateEstimator::default();
+    for frame in 0..10u64 {
+        let timestamp = (u32::MAX - 6000).wrapping_add(frame as u32 * 3000);
+        e.record(
+            start + Duration::from_millis(frame * 33),
+            1200,
+            true,
+            timestamp,
+        );
+    }
+    assert_eq!(e.framerate(start + Duration::from_millis(300), 90000), 30.0);
+}
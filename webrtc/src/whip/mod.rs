@@ -0,0 +1,247 @@
+#[cfg(test)]
+mod whip_test;
+
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::header::{AUTHORIZATION, CONTENT_TYPE, ETAG, IF_MATCH, LOCATION};
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
+use hyper_util::client::legacy::connect::HttpConnector;
+use hyper_util::client::legacy::Client;
+use hyper_util::rt::TokioExecutor;
+use sdp::description::session::{ATTR_KEY_END_OF_CANDIDATES, ATTR_KEY_MID};
+use tokio::sync::Mutex;
+use url::Url;
+
+use crate::error::{Error, Result};
+use crate::ice_transport::ice_candidate::RTCIceCandidate;
+use crate::peer_connection::sdp::session_description::RTCSessionDescription;
+use crate::peer_connection::sdp::{extract_ice_details, get_mid_value};
+use crate::peer_connection::RTCPeerConnection;
+
+const MIME_TYPE_SDP: &str = "application/sdp";
+const MIME_TYPE_TRICKLE_ICE_SDPFRAG: &str = "application/trickle-ice-sdpfrag";
+
+/// WhipClient performs the HTTP signaling of WHIP ([RFC 9725]) and WHEP for a
+/// PeerConnection: the local offer is POSTed to the endpoint, which answers and
+/// creates a session that later candidates are PATCHed to and that is DELETEd
+/// on close. WHIP and WHEP only differ in the direction of the media, so the
+/// same client ingests with sendonly and egresses with recvonly transceivers.
+///
+/// [RFC 9725]: https://www.rfc-editor.org/rfc/rfc9725
+pub struct WhipClient {
+    http: Client<HttpsConnector<HttpConnector>, Full<Bytes>>,
+    endpoint: Url,
+    bearer_token: Option<String>,
+    trickle: bool,
+    session: Mutex<Option<WhipSession>>,
+    pending_candidates: Mutex<Vec<Option<RTCIceCandidate>>>,
+}
+
+/// WhipSession is the session the endpoint created for the PeerConnection
+struct WhipSession {
+    url: Url,
+    etag: Option<String>,
+    /// the ice credentials and the bundled media section the candidates of
+    /// the PeerConnection belong to
+    ice_ufrag: String,
+    ice_pwd: String,
+    media: String,
+    mid: String,
+}
+
+impl WhipClient {
+    /// new creates a client for the WHIP or WHEP endpoint at the given URL
+    pub fn new(endpoint: &str) -> Result<Self> {
+        let connector = HttpsConnectorBuilder::new()
+            .with_provider_and_webpki_roots(rustls::crypto::ring::default_provider())
+            .map_err(|err| Error::Http(err.to_string()))?
+            .https_or_http()
+            .enable_http1()
+            .build();
+
+        Ok(WhipClient {
+            http: Client::builder(TokioExecutor::new()).build(connector),
+            endpoint: Url::parse(endpoint)?,
+            bearer_token: None,
+            trickle: false,
+            session: Mutex::new(None),
+            pending_candidates: Mutex::new(vec![]),
+        })
+    }
+
+    /// with_bearer_token authenticates every request with the given token
+    pub fn with_bearer_token(mut self, token: impl Into<String>) -> Self {
+        self.bearer_token = Some(token.into());
+        self
+    }
+
+    /// with_trickle makes connect send the offer without waiting for ICE
+    /// gathering to complete, the candidates then have to be passed to
+    /// trickle as they are gathered. Without trickle the offer carries all
+    /// the candidates, which every endpoint supports.
+    pub fn with_trickle(mut self, trickle: bool) -> Self {
+        self.trickle = trickle;
+        self
+    }
+
+    /// connect offers the PeerConnection to the endpoint and applies its answer
+    pub async fn connect(&self, pc: &RTCPeerConnection) -> Result<()> {
+        let offer = pc.create_offer(None).await?;
+        let mut gathering_complete = pc.gathering_complete_promise().await;
+        pc.set_local_description(offer).await?;
+        if !self.trickle {
+            let _ = gathering_complete.recv().await;
+        }
+
+        let offer = match pc.local_description().await {
+            Some(offer) => offer,
+            None => return Err(Error::new("non local description".to_owned())),
+        };
+
+        let mut request = self.request(Method::POST, self.endpoint.as_str());
+        request = request.header(CONTENT_TYPE, MIME_TYPE_SDP);
+        let response = self
+            .http
+            .request(build(request, offer.sdp.clone())?)
+            .await?;
+        if response.status() != StatusCode::CREATED {
+            return Err(Error::ErrWhipUnexpectedStatus(response.status().as_u16()));
+        }
+
+        let url = match response
+            .headers()
+            .get(LOCATION)
+            .and_then(|location| location.to_str().ok())
+        {
+            Some(location) => self.endpoint.join(location)?,
+            None => return Err(Error::ErrWhipNoSession),
+        };
+        let etag = response
+            .headers()
+            .get(ETAG)
+            .and_then(|etag| etag.to_str().ok())
+            .map(|etag| etag.to_owned());
+
+        let answer = response.into_body().collect().await?.to_bytes();
+        let answer = RTCSessionDescription::answer(String::from_utf8(answer.to_vec())?)?;
+        pc.set_remote_description(answer).await?;
+
+        let session = WhipSession::new(url, etag, &offer).await?;
+        *self.session.lock().await = Some(session);
+
+        let pending_candidates: Vec<_> = self.pending_candidates.lock().await.drain(..).collect();
+        for candidate in pending_candidates {
+            self.trickle(candidate).await?;
+        }
+
+        Ok(())
+    }
+
+    /// trickle sends a local candidate to the endpoint, None signals the end
+    /// of candidates. It fits RTCPeerConnection::on_ice_candidate, candidates
+    /// gathered before the session is established are sent once it is.
+    pub async fn trickle(&self, candidate: Option<RTCIceCandidate>) -> Result<()> {
+        let fragment = {
+            let session = self.session.lock().await;
+            match &*session {
+                Some(session) => session.fragment(candidate.as_ref())?,
+                None => {
+                    self.pending_candidates.lock().await.push(candidate);
+                    return Ok(());
+                }
+            }
+        };
+
+        let mut request = self.session_request(Method::PATCH).await?;
+        request = request.header(CONTENT_TYPE, MIME_TYPE_TRICKLE_ICE_SDPFRAG);
+        let response = self.http.request(build(request, fragment)?).await?;
+        expect_success(response)
+    }
+
+    /// close ends the session at the endpoint, the PeerConnection has to be
+    /// closed separately.
+    pub async fn close(&self) -> Result<()> {
+        let request = self.session_request(Method::DELETE).await?;
+        let response = self.http.request(build(request, Bytes::new())?).await?;
+        *self.session.lock().await = None;
+        expect_success(response)
+    }
+
+    fn request(&self, method: Method, url: &str) -> hyper::http::request::Builder {
+        let mut request = Request::builder().method(method).uri(url);
+        if let Some(token) = &self.bearer_token {
+            request = request.header(AUTHORIZATION, format!("Bearer {token}"));
+        }
+        request
+    }
+
+    async fn session_request(&self, method: Method) -> Result<hyper::http::request::Builder> {
+        let session = self.session.lock().await;
+        let session = session.as_ref().ok_or(Error::ErrWhipNotConnected)?;
+        let mut request = self.request(method, session.url.as_str());
+        if let Some(etag) = &session.etag {
+            request = request.header(IF_MATCH, etag);
+        }
+        Ok(request)
+    }
+}
+
+impl WhipSession {
+    async fn new(url: Url, etag: Option<String>, offer: &RTCSessionDescription) -> Result<Self> {
+        let parsed = offer.unmarshal()?;
+        let (ice_ufrag, ice_pwd, _) = extract_ice_details(&parsed).await?;
+        let media = match parsed.media_descriptions.first() {
+            Some(media) => media,
+            None => return Err(Error::new("offer without media section".to_owned())),
+        };
+
+        Ok(WhipSession {
+            url,
+            etag,
+            ice_ufrag,
+            ice_pwd,
+            media: format!(
+                "{} 9 {} {}",
+                media.media_name.media,
+                media.media_name.protos.join("/"),
+                media.media_name.formats.join(" ")
+            ),
+            mid: get_mid_value(media).cloned().unwrap_or_default(),
+        })
+    }
+
+    /// fragment returns the trickle-ice-sdpfrag (RFC 8840) carrying candidate
+    fn fragment(&self, candidate: Option<&RTCIceCandidate>) -> Result<String> {
+        let mut fragment = format!(
+            "a=ice-ufrag:{}\r\na=ice-pwd:{}\r\nm={}\r\na={}:{}\r\n",
+            self.ice_ufrag, self.ice_pwd, self.media, ATTR_KEY_MID, self.mid
+        );
+        match candidate {
+            Some(candidate) => {
+                fragment += &format!("a={}\r\n", candidate.to_json()?.candidate);
+            }
+            None => fragment += &format!("a={ATTR_KEY_END_OF_CANDIDATES}\r\n"),
+        }
+        Ok(fragment)
+    }
+}
+
+fn build(
+    request: hyper::http::request::Builder,
+    body: impl Into<Full<Bytes>>,
+) -> Result<Request<Full<Bytes>>> {
+    request
+        .body(body.into())
+        .map_err(|err| Error::Http(err.to_string()))
+}
+
+/// expect_success fails unless the endpoint accepted the request
+fn expect_success(response: Response<Incoming>) -> Result<()> {
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        Err(Error::ErrWhipUnexpectedStatus(response.status().as_u16()))
+    }
+}
@@ -0,0 +1,222 @@
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::mpsc;
+use waitgroup::WaitGroup;
+
+use super::*;
+use crate::api::APIBuilder;
+use crate::peer_connection::configuration::RTCConfiguration;
+use crate::peer_connection::peer_connection_state::RTCPeerConnectionState;
+use crate::peer_connection::peer_connection_test::until_connection_state;
+
+const SESSION_PATH: &str = "/whip/session/1";
+
+/// EndpointRequest is an HTTP/1.1 request received by the endpoint
+struct EndpointRequest {
+    method: Method,
+    path: String,
+    authorization: Option<String>,
+    body: String,
+}
+
+/// EndpointResponse is the HTTP/1.1 response the endpoint returns
+struct EndpointResponse {
+    status: StatusCode,
+    headers: Vec<(&'static str, String)>,
+    body: String,
+}
+
+/// serve runs a WHIP endpoint that answers with pc and reports every PATCH and
+/// DELETE of the session to requests.
+async fn serve(
+    pc: Arc<RTCPeerConnection>,
+    requests: mpsc::UnboundedSender<(Method, String)>,
+) -> SocketAddr {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        while let Ok((conn, _)) = listener.accept().await {
+            tokio::spawn(serve_conn(conn, Arc::clone(&pc), requests.clone()));
+        }
+    });
+    addr
+}
+
+/// serve_conn handles the requests of a keep-alive connection until the client closes it
+async fn serve_conn(
+    conn: TcpStream,
+    pc: Arc<RTCPeerConnection>,
+    requests: mpsc::UnboundedSender<(Method, String)>,
+) {
+    let mut conn = BufReader::new(conn);
+    while let Some(request) = read_request(&mut conn).await {
+        let response = handle(Arc::clone(&pc), requests.clone(), request).await;
+
+        let mut head = format!(
+            "HTTP/1.1 {} {}\r\n",
+            response.status.as_u16(),
+            response.status.canonical_reason().unwrap_or_default()
+        );
+        for (name, value) in &response.headers {
+            head += &format!("{name}: {value}\r\n");
+        }
+        head += &format!("content-length: {}\r\n\r\n", response.body.len());
+        let conn = conn.get_mut();
+        if conn.write_all(head.as_bytes()).await.is_err()
+            || conn.write_all(response.body.as_bytes()).await.is_err()
+        {
+            return;
+        }
+    }
+}
+
+async fn read_request(conn: &mut BufReader<TcpStream>) -> Option<EndpointRequest> {
+    let mut line = String::new();
+    conn.read_line(&mut line).await.ok().filter(|n| *n > 0)?;
+    let mut request_line = line.split_whitespace();
+    let method = Method::from_bytes(request_line.next()?.as_bytes()).ok()?;
+    let path = request_line.next()?.to_owned();
+
+    let mut authorization = None;
+    let mut content_length = 0;
+    loop {
+        line.clear();
+        conn.read_line(&mut line).await.ok().filter(|n| *n > 0)?;
+        let Some((name, value)) = line.trim_end().split_once(':') else {
+            break;
+        };
+        let value = value.trim();
+        if name.eq_ignore_ascii_case(AUTHORIZATION.as_str()) {
+            authorization = Some(value.to_owned());
+        } else if name.eq_ignore_ascii_case("content-length") {
+            content_length = value.parse().ok()?;
+        }
+    }
+
+    let mut body = vec![0; content_length];
+    conn.read_exact(&mut body).await.ok()?;
+
+    Some(EndpointRequest {
+        method,
+        path,
+        authorization,
+        body: String::from_utf8(body).ok()?,
+    })
+}
+
+async fn handle(
+    pc: Arc<RTCPeerConnection>,
+    requests: mpsc::UnboundedSender<(Method, String)>,
+    request: EndpointRequest,
+) -> EndpointResponse {
+    if request.authorization.as_deref() != Some("Bearer token") {
+        return status(StatusCode::UNAUTHORIZED);
+    }
+
+    match (&request.method, request.path.as_str()) {
+        (&Method::POST, "/whip") => {
+            let offer = RTCSessionDescription::offer(request.body).unwrap();
+            pc.set_remote_description(offer).await.unwrap();
+            let answer = pc.create_answer(None).await.unwrap();
+            let mut gathering_complete = pc.gathering_complete_promise().await;
+            pc.set_local_description(answer).await.unwrap();
+            let _ = gathering_complete.recv().await;
+
+            let answer = pc.local_description().await.unwrap();
+            EndpointResponse {
+                status: StatusCode::CREATED,
+                headers: vec![
+                    (CONTENT_TYPE.as_str(), MIME_TYPE_SDP.to_owned()),
+                    (LOCATION.as_str(), SESSION_PATH.to_owned()),
+                    (ETAG.as_str(), "\"1\"".to_owned()),
+                ],
+                body: answer.sdp,
+            }
+        }
+        (&Method::PATCH, SESSION_PATH) | (&Method::DELETE, SESSION_PATH) => {
+            let _ = requests.send((request.method, request.body));
+            status(StatusCode::NO_CONTENT)
+        }
+        _ => status(StatusCode::NOT_FOUND),
+    }
+}
+
+fn status(status: StatusCode) -> EndpointResponse {
+    EndpointResponse {
+        status,
+        headers: vec![],
+        body: String::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_whip_client() -> Result<()> {
+    let api = APIBuilder::new().build();
+    let mut pc_client = api.new_peer_connection(RTCConfiguration::default()).await?;
+    let mut pc_endpoint = api.new_peer_connection(RTCConfiguration::default()).await?;
+
+    let wg = WaitGroup::new();
+    until_connection_state(&mut pc_client, &wg, RTCPeerConnectionState::Connected).await;
+    until_connection_state(&mut pc_endpoint, &wg, RTCPeerConnectionState::Connected).await;
+
+    let pc_endpoint = Arc::new(pc_endpoint);
+    let (requests_tx, mut requests_rx) = mpsc::unbounded_channel();
+    let addr = serve(Arc::clone(&pc_endpoint), requests_tx).await;
+
+    pc_client.create_data_channel("whip", None).await?;
+    let client = WhipClient::new(&format!("http://{addr}/whip"))?.with_bearer_token("token");
+
+    client.trickle(None).await?;
+    client.connect(&pc_client).await?;
+    wg.wait().await;
+
+    // the candidate queued before the session existed is sent after connecting
+    let (method, fragment) = requests_rx.recv().await.unwrap();
+    assert_eq!(method, Method::PATCH);
+    assert!(fragment.starts_with("a=ice-ufrag:"), "{fragment}");
+    assert!(fragment.contains("\r\nm=application 9 UDP/DTLS/SCTP webrtc-datachannel\r\n"));
+    assert!(fragment.contains("\r\na=mid:0\r\n"), "{fragment}");
+    assert!(
+        fragment.ends_with("\r\na=end-of-candidates\r\n"),
+        "{fragment}"
+    );
+
+    client.close().await?;
+    let (method, _) = requests_rx.recv().await.unwrap();
+    assert_eq!(method, Method::DELETE);
+
+    assert!(matches!(
+        client.close().await,
+        Err(Error::ErrWhipNotConnected)
+    ));
+
+    pc_client.close().await?;
+    pc_endpoint.close().await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_whip_client_rejected() -> Result<()> {
+    let api = APIBuilder::new().build();
+    let pc_client = api.new_peer_connection(RTCConfiguration::default()).await?;
+    let pc_endpoint = Arc::new(api.new_peer_connection(RTCConfiguration::default()).await?);
+
+    let (requests_tx, _requests_rx) = mpsc::unbounded_channel();
+    let addr = serve(Arc::clone(&pc_endpoint), requests_tx).await;
+
+    pc_client.create_data_channel("whip", None).await?;
+    let client = WhipClient::new(&format!("http://{addr}/whip"))?;
+    assert!(matches!(
+        client.connect(&pc_client).await,
+        Err(Error::ErrWhipUnexpectedStatus(401))
+    ));
+
+    pc_client.close().await?;
+    pc_endpoint.close().await?;
+
+    Ok(())
+}